@@ -0,0 +1,4 @@
+// Demo source file with no spec/math.md, shown by `spec-check demo`
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
@@ -0,0 +1,152 @@
+//! Run spec-check from a build script or an xtask, so drift between code and spec shows up
+//! in `cargo build` itself.
+//!
+//! ```no_run
+//! // In build.rs's `main`
+//! spec_check_build::SpecCheck::from_cargo_toml().unwrap().deny_drift(true).enforce();
+//! ```
+
+use anyhow::{Context, Result};
+use spec_check::comparator::CompareOptions;
+use spec_check::config::{Config, FileFilter, MappingRule, SourceOfTruth, Visibility};
+use spec_check::reporter::Finding;
+use std::path::{Path, PathBuf};
+
+/// A check of one source tree against its spec tree
+pub struct SpecCheck {
+    src: PathBuf,
+    spec: PathBuf,
+    mappings: Vec<MappingRule>,
+    filter: FileFilter,
+    visibility: Visibility,
+    ignored_attributes: Vec<String>,
+    compare: CompareOptions,
+    version: Option<String>,
+    source_of_truth: Option<SourceOfTruth>,
+    deny_drift: bool,
+}
+
+impl SpecCheck {
+    /// Check `src` against `spec` with the CLI's defaults
+    pub fn new(src: impl Into<PathBuf>, spec: impl Into<PathBuf>) -> Self {
+        Self::with_config(&Config::default()).src(src).spec(spec)
+    }
+
+    /// Use `[package.metadata.spec-check]` from the Cargo.toml in the current directory,
+    /// which is the package's own manifest when run from build.rs
+    pub fn from_cargo_toml() -> Result<Self> {
+        let config = Config::load_from_cargo_toml()
+            .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+        Ok(Self::with_config(&config))
+    }
+
+    fn with_config(config: &Config) -> Self {
+        Self {
+            src: PathBuf::from(config.src_dir.as_deref().unwrap_or("src")),
+            spec: PathBuf::from(config.spec_dir.as_deref().unwrap_or("spec")),
+            mappings: config.mapping.clone().unwrap_or_default(),
+            filter: config.file_filter(),
+            visibility: config.visibility(),
+            ignored_attributes: config.get_ignored_attributes(),
+            compare: config.compare_options(),
+            // Cargo sets it for build scripts, in the package's own version
+            version: std::env::var("CARGO_PKG_VERSION").ok(),
+            source_of_truth: config.source_of_truth,
+            deny_drift: false,
+        }
+    }
+
+    pub fn src(self, src: impl Into<PathBuf>) -> Self {
+        Self { src: src.into(), ..self }
+    }
+
+    pub fn spec(self, spec: impl Into<PathBuf>) -> Self {
+        Self { spec: spec.into(), ..self }
+    }
+
+    pub fn check_private(self, check_private: bool) -> Self {
+        self.visibility(Visibility::from_check_private(check_private))
+    }
+
+    pub fn visibility(self, visibility: Visibility) -> Self {
+        Self { visibility, ..self }
+    }
+
+    pub fn ignore_attr(mut self, attribute: impl Into<String>) -> Self {
+        self.ignored_attributes.push(attribute.into());
+        self
+    }
+
+    /// Fail the build on findings instead of only warning about them
+    pub fn deny_drift(self, deny_drift: bool) -> Self {
+        Self { deny_drift, ..self }
+    }
+
+    /// Run the check and return its findings
+    pub fn run(&self) -> Result<Vec<Finding>> {
+        let paths = spec_check::CheckPaths { src: self.src.clone(), spec: self.spec.clone(), mappings: self.mappings.clone(), filter: self.filter.clone() };
+        let options = spec_check::CheckOptions {
+            visibility: self.visibility,
+            ignored_attributes: self.ignored_attributes.clone(),
+            compare: self.compare.clone(),
+            version: self.version.clone(),
+            spec_version: None,
+            source_of_truth: self.source_of_truth,
+        };
+        Ok(spec_check::check(&paths, &options)?.findings)
+    }
+
+    /// Run the check from build.rs: rerun when either tree changes, report each finding as a
+    /// cargo warning, and panic (failing the build) if drift is denied and anything was found
+    pub fn enforce(&self) {
+        println!("cargo:rerun-if-changed={}", self.src.display());
+        println!("cargo:rerun-if-changed={}", self.spec.display());
+
+        let findings = match self.run() {
+            Ok(findings) => findings,
+            Err(err) => panic!("spec-check failed: {:#}", err),
+        };
+        for finding in &findings {
+            println!("cargo:warning={}", warning(finding));
+        }
+        if self.deny_drift && !findings.is_empty() {
+            panic!("spec-check found {} problem(s) between {} and {}", findings.len(), self.src.display(), self.spec.display());
+        }
+    }
+}
+
+/// One line per finding, since cargo shows each `cargo:warning` line on its own
+fn warning(finding: &Finding) -> String {
+    let location = match finding.line {
+        Some(line) => format!("{}:{}", display(&finding.file), line),
+        None => display(&finding.file),
+    };
+    format!("{}: {} {}", location, finding.rule, finding.message.replace('\n', " "))
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_run_reports_drift_as_warnings() {
+        let dir = std::env::temp_dir().join(format!("spec-check-build-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("spec")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub struct Mesh;\npub fn build() {}\n").unwrap();
+        fs::write(dir.join("spec/lib.md"), "```rust\npub struct Mesh;\n```\n").unwrap();
+
+        let check = SpecCheck::new(dir.join("src"), dir.join("spec"));
+        let findings = check.run().unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].item.as_deref(), Some("build"));
+        assert!(warning(&findings[0]).contains("lib.rs:2: SC001"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
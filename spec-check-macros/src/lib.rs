@@ -0,0 +1,103 @@
+//! `#[spec_checked(file = "spec/math.md", section = "Geometry")]` names the spec file, and
+//! optionally the section of it, that describes an item. The attribute leaves the item as it is;
+//! spec-check reads it from the source (with `spec-annotations = true`) and reports annotated
+//! items that the named spec doesn't describe.
+//!
+//! `#[ignore]` marks an item that spec-check skips entirely, e.g. one that is generated.
+//!
+//! `#[requirement("REQ-ANIM-012")]` says which of the spec's requirement IDs an item implements,
+//! for `spec-check requirements`.
+
+use proc_macro::TokenStream;
+
+#[proc_macro_attribute]
+pub fn spec_checked(args: TokenStream, item: TokenStream) -> TokenStream {
+    match check_args(args.into()) {
+        Ok(()) => item,
+        Err(err) => {
+            let mut output: TokenStream = err.to_compile_error().into();
+            output.extend(item);
+            output
+        }
+    }
+}
+
+/// Leaves the item as it is; spec-check doesn't parse items that carry it
+#[proc_macro_attribute]
+pub fn ignore(args: TokenStream, item: TokenStream) -> TokenStream {
+    if args.is_empty() {
+        return item;
+    }
+    let mut output: TokenStream = syn::Error::new_spanned(proc_macro2::TokenStream::from(args), "#[ignore] takes no arguments")
+        .to_compile_error()
+        .into();
+    output.extend(item);
+    output
+}
+
+/// Leaves the item as it is; spec-check reads the requirement IDs from the source
+#[proc_macro_attribute]
+pub fn requirement(args: TokenStream, item: TokenStream) -> TokenStream {
+    match check_requirements(args.into()) {
+        Ok(()) => item,
+        Err(err) => {
+            let mut output: TokenStream = err.to_compile_error().into();
+            output.extend(item);
+            output
+        }
+    }
+}
+
+/// Accept one or more string literals
+fn check_requirements(args: proc_macro2::TokenStream) -> syn::Result<()> {
+    let ids = syn::parse::Parser::parse2(syn::punctuated::Punctuated::<syn::LitStr, syn::Token![,]>::parse_terminated, args.clone())?;
+    if ids.is_empty() {
+        return Err(syn::Error::new_spanned(args, "#[requirement] needs at least one ID, e.g. `\"REQ-ANIM-012\"`"));
+    }
+    Ok(())
+}
+
+/// Accept `file = "..."` (required) and `section = "..."`, so typos fail the build
+/// instead of silently checking against the wrong spec
+fn check_args(args: proc_macro2::TokenStream) -> syn::Result<()> {
+    let mut file = false;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("file") {
+            file = true;
+            let _: syn::LitStr = meta.value()?.parse()?;
+        } else if meta.path.is_ident("section") {
+            let _: syn::LitStr = meta.value()?.parse()?;
+        } else {
+            return Err(meta.error("expected `file = \"...\"` or `section = \"...\"`"));
+        }
+        Ok(())
+    });
+    syn::parse::Parser::parse2(parser, args.clone())?;
+    if !file {
+        return Err(syn::Error::new_spanned(args, "#[spec_checked] needs `file = \"path/to/spec.md\"`"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_need_a_file_and_known_keys() {
+        let args = |text: &str| check_args(text.parse().unwrap());
+        assert!(args(r#"file = "spec/math.md", section = "Geometry""#).is_ok());
+        assert!(args(r#"file = "spec/math.md""#).is_ok());
+        assert!(args(r#"section = "Geometry""#).is_err());
+        assert!(args(r#"file = "spec/math.md", sectoin = "Geometry""#).is_err());
+        assert!(args("file = 3").is_err());
+    }
+
+    #[test]
+    fn test_requirements_are_string_literals() {
+        let args = |text: &str| check_requirements(text.parse().unwrap());
+        assert!(args(r#""REQ-ANIM-012", "REQ-ANIM-013""#).is_ok());
+        assert!(args("").is_err());
+        assert!(args("REQ_ANIM_012").is_err());
+    }
+}
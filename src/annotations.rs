@@ -0,0 +1,199 @@
+//! `#[spec_checked(file = "spec/math.md", section = "Geometry")]` annotations, which check an
+//! item against a spec other than its source file's own (see the `spec-check-macros` crate).
+//!
+//! Annotated items are compared with the items of the named spec file, limited to the named
+//! section and its subsections. The items they claim are left out of the spec's own source file,
+//! which would otherwise report them as missing in the code.
+
+use crate::comparator::{self, ComparisonResult};
+use crate::config::Visibility;
+use crate::spec_format;
+use crate::rust_parser::{ItemKind, RustItem};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use syn::parse::Parser;
+
+/// Name of the annotation attribute, which is never compared as an attribute itself
+pub const ATTRIBUTE: &str = "spec_checked";
+
+/// Where an annotated item's spec lives
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpecAnnotation {
+    /// Relative to the project root
+    pub file: PathBuf,
+    /// Heading text of the section describing the item
+    pub section: Option<String>,
+}
+
+/// The item's annotation, if it has a well-formed one. Malformed annotations are left to the
+/// macro to reject at compile time.
+pub fn annotation(item: &RustItem) -> Option<SpecAnnotation> {
+    item.attributes.iter().find_map(|attr| parse_attribute(attr))
+}
+
+fn parse_attribute(attr: &str) -> Option<SpecAnnotation> {
+    let attrs = syn::Attribute::parse_outer.parse_str(attr).ok()?;
+    let attr = attrs.first()?;
+    if attr.path().segments.last()?.ident != ATTRIBUTE {
+        return None;
+    }
+    let mut file = None;
+    let mut section = None;
+    attr.parse_nested_meta(|meta| {
+        let value: syn::LitStr = meta.value()?.parse()?;
+        if meta.path.is_ident("file") {
+            file = Some(normalize(Path::new(&value.value())));
+        } else if meta.path.is_ident("section") {
+            section = Some(value.value());
+        }
+        Ok(())
+    }).ok()?;
+    Some(SpecAnnotation { file: file?, section })
+}
+
+/// `./spec/../spec/math.md` and `spec/math.md` name the same spec
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// The spec items of the section with `heading` (and its subsections), or of the whole file
+pub fn section_items(spec: &str, spec_file: &Path, heading: Option<&str>, visibility: Visibility) -> Vec<RustItem> {
+    let items = crate::parse_spec_file(spec, spec_file, visibility).0;
+    let Some(heading) = heading else { return items };
+    let sections: Vec<_> = spec_format::of(spec_file).sections(spec).into_iter()
+        .filter(|section| section.heading.trim() == heading.trim())
+        .collect();
+    items.into_iter()
+        .filter(|item| sections.iter().any(|section| section.lines.contains(&item.line_number)))
+        .collect()
+}
+
+/// Separate annotated items from the rest, which are checked against their file's own spec
+pub fn split_annotated(items: Vec<RustItem>) -> (Vec<(RustItem, SpecAnnotation)>, Vec<RustItem>) {
+    let mut annotated = Vec::new();
+    let mut plain = Vec::new();
+    for item in items {
+        match annotation(&item) {
+            Some(annotation) => annotated.push((item, annotation)),
+            None => plain.push(item),
+        }
+    }
+    (annotated, plain)
+}
+
+/// Every annotation in the project, with the spec items each one can resolve to
+#[derive(Default)]
+pub struct Annotations {
+    targets: HashMap<SpecAnnotation, Vec<RustItem>>,
+    claimed: HashMap<PathBuf, HashSet<(String, ItemKind)>>,
+    fingerprint: u64,
+}
+
+impl Annotations {
+    /// Index the annotated items of every source file. `read_spec` returns a spec file's
+    /// markdown, or none if it doesn't exist (every item pointing at it is then unresolved).
+    pub fn new<'a>(
+        annotated: impl IntoIterator<Item = (&'a RustItem, SpecAnnotation)>,
+        visibility: Visibility,
+        mut read_spec: impl FnMut(&Path) -> Option<String>,
+    ) -> Self {
+        let mut index = Self::default();
+        let mut markdown: BTreeMap<PathBuf, Option<String>> = BTreeMap::new();
+        let mut seen = BTreeSet::new();
+        for (item, annotation) in annotated {
+            seen.insert(format!("{}\0{:?}\0{}\0{:?}", annotation.file.display(), annotation.section, item.name, item.kind));
+            let content = markdown.entry(annotation.file.clone()).or_insert_with(|| read_spec(&annotation.file));
+            let items = index.targets.entry(annotation.clone()).or_insert_with(|| {
+                content.as_deref().map_or_else(Vec::new, |spec| section_items(spec, &annotation.file, annotation.section.as_deref(), visibility))
+            });
+            if items.iter().any(|spec| spec.name == item.name && spec.kind == item.kind) {
+                index.claimed.entry(annotation.file).or_default().insert((item.name.clone(), item.kind.clone()));
+            }
+        }
+
+        let mut parts: Vec<&[u8]> = seen.iter().map(|entry| entry.as_bytes()).collect();
+        parts.extend(markdown.values().map(|content| content.as_deref().unwrap_or("").as_bytes()));
+        index.fingerprint = crate::cache::hash(&parts);
+        index
+    }
+
+    /// Identifies the annotations and the specs they name, since every file's outcome depends on them
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Drop the spec items of `spec_file` that annotated items elsewhere have claimed
+    pub fn remove_claimed(&self, spec_file: &Path, spec_items: &mut Vec<RustItem>) {
+        if let Some(claimed) = self.claimed.get(&normalize(spec_file)) {
+            spec_items.retain(|item| !claimed.contains(&(item.name.clone(), item.kind.clone())));
+        }
+    }
+
+    /// Compare annotated items with the spec items they name, adding to `result`
+    pub fn check(&self, annotated: Vec<(RustItem, SpecAnnotation)>, ignored_attributes: &[String], result: &mut ComparisonResult) {
+        for (item, annotation) in annotated {
+            let matching: Vec<RustItem> = self.targets.get(&annotation).into_iter().flatten()
+                .filter(|spec| spec.name == item.name && spec.kind == item.kind)
+                .cloned()
+                .collect();
+            if matching.is_empty() {
+                result.unresolved_annotations.push(item);
+                continue;
+            }
+            let compared = comparator::compare_items(vec![item], matching, ignored_attributes);
+            result.signature_mismatches.extend(compared.signature_mismatches);
+            result.attribute_mismatches.extend(compared.attribute_mismatches);
+            result.discriminant_mismatches.extend(compared.discriminant_mismatches);
+            result.value_mismatches.extend(compared.value_mismatches);
+            result.abi_mismatches.extend(compared.abi_mismatches);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotated_items_resolve_within_their_section() {
+        let code = r#"
+            #[spec_check_macros::spec_checked(file = "./spec/math.md", section = "Geometry")]
+            pub struct Curve;
+            #[spec_checked(file = "spec/math.md", section = "Color")]
+            pub struct Surface;
+            pub struct Plain;
+        "#;
+        let items = crate::parse_rust_items(code, Visibility::Pub).unwrap();
+        let annotated: Vec<_> = items.iter().filter_map(|item| Some((item.clone(), annotation(item)?))).collect();
+        assert_eq!(annotated.len(), 2);
+        assert_eq!(annotated[0].1, SpecAnnotation { file: PathBuf::from("spec/math.md"), section: Some("Geometry".to_string()) });
+
+        let markdown = "## Geometry\n\n```rust\npub struct Curve;\npub struct Surface;\n```\n\n## Color\n";
+        let index = Annotations::new(annotated.iter().map(|(item, a)| (item, a.clone())), Visibility::Pub, |path| {
+            (path == Path::new("spec/math.md")).then(|| markdown.to_string())
+        });
+
+        let mut spec_items = crate::extract_spec_items(markdown, Visibility::Pub);
+        index.remove_claimed(Path::new("spec/math.md"), &mut spec_items);
+        assert_eq!(spec_items.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), vec!["Surface"]);
+
+        let mut result = comparator::compare_items(Vec::new(), Vec::new(), &[]);
+        index.check(annotated, &[ATTRIBUTE.to_string()], &mut result);
+        assert!(result.signature_mismatches.is_empty() && result.attribute_mismatches.is_empty());
+        assert_eq!(result.unresolved_annotations.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), vec!["Surface"]);
+    }
+}
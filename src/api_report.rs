@@ -0,0 +1,165 @@
+//! The crate's whole parsed public surface as one document for API review, grouped by module
+//! and kind, whatever the specs say about it
+
+use crate::json;
+use crate::rust_parser::{self, RustItem};
+use crate::rustdoc_json::Module;
+use std::collections::BTreeMap;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// The order kinds are listed in within a module
+const KIND_ORDER: [&str; 14] = [
+    "re-export", "macro", "trait", "trait method", "trait type", "trait const", "struct", "enum", "union",
+    "method", "trait impl", "function", "const", "static",
+];
+
+/// An item and the file it's declared in
+struct Entry<'a> {
+    file: &'a Path,
+    item: &'a RustItem,
+}
+
+/// The items of `modules` by module path (`my_crate::geometry`, inline modules included), then
+/// by kind in [`KIND_ORDER`], in source order
+fn grouped<'a>(crate_name: &str, modules: &'a [Module]) -> BTreeMap<String, Vec<(&'static str, Vec<Entry<'a>>)>> {
+    let mut grouped: BTreeMap<String, Vec<(&'static str, Vec<Entry>)>> = BTreeMap::new();
+    for module in modules {
+        for item in &module.items {
+            let path: Vec<&str> = std::iter::once(crate_name).chain(module.path.iter().chain(&item.module).map(String::as_str)).collect();
+            let kinds = grouped.entry(path.join("::")).or_default();
+            let kind = item.kind.label();
+            match kinds.iter_mut().find(|(label, _)| *label == kind) {
+                Some((_, entries)) => entries.push(Entry { file: &module.file, item }),
+                None => kinds.push((kind, vec![Entry { file: &module.file, item }])),
+            }
+        }
+    }
+    for kinds in grouped.values_mut() {
+        kinds.sort_by_key(|(kind, _)| KIND_ORDER.iter().position(|known| known == kind));
+    }
+    grouped
+}
+
+/// The first line of an item's doc comment
+fn summary(item: &RustItem) -> Option<String> {
+    let docs = rust_parser::doc_text(&item.attributes);
+    docs.lines().map(str::trim).find(|line| !line.is_empty()).map(str::to_string)
+}
+
+/// `name`, or `Owner::name` for members of a trait or type
+fn member_path(item: &RustItem) -> String {
+    match item.kind.owner() {
+        Some(owner) => format!("{}::{}", owner, item.name),
+        None => item.name.clone(),
+    }
+}
+
+fn location(entry: &Entry) -> String {
+    format!("{}:{}", entry.file.to_string_lossy().replace('\\', "/"), entry.item.line_number)
+}
+
+fn item_count(modules: &[Module]) -> usize {
+    modules.iter().map(|module| module.items.len()).sum()
+}
+
+/// The report as markdown: a section per module, a list per kind
+pub fn to_markdown(crate_name: &str, crate_version: Option<&str>, modules: &[Module]) -> String {
+    let grouped = grouped(crate_name, modules);
+    let mut out = match crate_version {
+        Some(version) => format!("# API of `{}` {}\n\n", crate_name, version),
+        None => format!("# API of `{}`\n\n", crate_name),
+    };
+    out.push_str(&format!("{} item(s) in {} module(s)\n", item_count(modules), grouped.len()));
+    for (path, kinds) in &grouped {
+        out.push_str(&format!("\n## `{}`\n", path));
+        for (kind, entries) in kinds {
+            let mut heading = format!("{}s", kind);
+            heading[..1].make_ascii_uppercase();
+            out.push_str(&format!("\n### {}\n\n", heading));
+            for entry in entries {
+                out.push_str(&format!("- `{}` ({})", member_path(entry.item), location(entry)));
+                if let Some(summary) = summary(entry.item) {
+                    out.push_str(&format!(": {}", summary));
+                }
+                out.push_str(&format!("\n\n  `{}`\n", entry.item.signature));
+            }
+        }
+    }
+    out
+}
+
+/// The report as JSON: `crate`, `version` and `modules`, each with its `path` and its `kinds` of items
+pub fn to_json(crate_name: &str, crate_version: Option<&str>, modules: &[Module]) -> String {
+    let grouped = grouped(crate_name, modules);
+    let module_values = grouped.into_iter().map(|(path, kinds)| {
+        let kinds: Vec<Value> = kinds.into_iter().map(|(kind, entries)| {
+            let items = entries.iter().map(|entry| {
+                let mut item = Table::new();
+                item.insert("name".to_string(), entry.item.name.clone().into());
+                item.insert("path".to_string(), format!("{}::{}", path, member_path(entry.item)).into());
+                item.insert("signature".to_string(), entry.item.signature.clone().into());
+                item.insert("file".to_string(), entry.file.to_string_lossy().replace('\\', "/").into());
+                item.insert("line".to_string(), Value::Integer(entry.item.line_number as i64));
+                if let Some(summary) = summary(entry.item) {
+                    item.insert("summary".to_string(), summary.into());
+                }
+                Value::Table(item)
+            });
+            let mut kind_table = Table::new();
+            kind_table.insert("kind".to_string(), kind.into());
+            kind_table.insert("items".to_string(), Value::Array(items.collect()));
+            Value::Table(kind_table)
+        }).collect();
+        let mut module = Table::new();
+        module.insert("path".to_string(), path.into());
+        module.insert("kinds".to_string(), Value::Array(kinds));
+        Value::Table(module)
+    });
+
+    let mut root = Table::new();
+    root.insert("crate".to_string(), crate_name.into());
+    if let Some(version) = crate_version {
+        root.insert("version".to_string(), version.into());
+    }
+    root.insert("item_count".to_string(), Value::Integer(item_count(modules) as i64));
+    root.insert("modules".to_string(), Value::Array(module_values.collect()));
+    json::to_string_pretty(&Value::Table(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_items_are_grouped_by_module_and_kind() {
+        let parse = |source: &str| crate::parse_rust_items(source, Default::default()).unwrap();
+        let modules = [
+            Module {
+                file: PathBuf::from("src/lib.rs"),
+                path: Vec::new(),
+                items: parse("/// Loads a mesh\n///\n/// More.\npub fn load() {}\npub struct Mesh;\nimpl Mesh { pub fn new() -> Self { Mesh } }\npub mod io { pub fn read() {} }\n"),
+            },
+            Module { file: PathBuf::from("src/geometry.rs"), path: vec!["geometry".to_string()], items: parse("pub const EPSILON: f32 = 0.1;\n") },
+        ];
+        let markdown = to_markdown("mesh", Some("0.3.0"), &modules);
+        let headings: Vec<&str> = markdown.lines().filter(|line| line.starts_with('#')).collect();
+        assert_eq!(headings, [
+            "# API of `mesh` 0.3.0", "## `mesh`", "### Structs", "### Methods", "### Functions",
+            "## `mesh::geometry`", "### Consts", "## `mesh::io`", "### Functions",
+        ]);
+        assert!(markdown.contains("\n5 item(s) in 3 module(s)\n"));
+        assert!(markdown.contains("- `load` (src/lib.rs:4): Loads a mesh\n\n  `pub fn load () { }`\n"));
+        assert!(markdown.contains("- `Mesh::new` (src/lib.rs:6)\n"));
+
+        let json = json::parse(&to_json("mesh", None, &modules)).unwrap();
+        let module_paths: Vec<&str> = json["modules"].as_array().unwrap().iter().map(|module| module["path"].as_str().unwrap()).collect();
+        assert_eq!(module_paths, ["mesh", "mesh::geometry", "mesh::io"]);
+        let constant = &json["modules"][1]["kinds"][0];
+        assert_eq!(constant["kind"].as_str(), Some("const"));
+        assert_eq!(constant["items"][0]["file"].as_str(), Some("src/geometry.rs"));
+        assert_eq!(json["modules"][2]["kinds"][0]["items"][0]["path"].as_str(), Some("mesh::io::read"));
+        assert!(json.get("version").is_none());
+    }
+}
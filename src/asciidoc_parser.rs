@@ -0,0 +1,223 @@
+//! Specs written in AsciiDoc. A Rust block is a `[source,rust]` listing, delimited by `----` or
+//! `....` or else the one paragraph after the attribute line, with the tags markdown fences
+//! take after `rust` (`[source,rust,planned]`). Directives are `// spec-check: ...` comments.
+
+use crate::markdown_parser::{self, Line, RustBlock, Section};
+use crate::rust_parser::Stage;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Whether a line opens or closes a block whose lines aren't AsciiDoc: a listing, literal,
+/// passthrough or comment block, or a table
+fn is_verbatim_delimiter(line: &str) -> bool {
+    let line = line.trim_end();
+    let repeated = line.len() >= 4 && ['-', '.', '+', '/'].iter().any(|&c| line.chars().all(|ch| ch == c));
+    repeated || line == "|==="
+}
+
+/// The level and title of a section heading, `2` for `== Geometry`. Backticks are dropped, as
+/// markdown drops them from `` ## `Curve` ``.
+fn heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '=').count();
+    let title = line[level..].strip_prefix(' ')?.trim().trim_end_matches('=').trim();
+    (level > 0 && !title.is_empty()).then(|| (level, title.replace('`', "")))
+}
+
+/// The tags after `rust` of a `[source,rust,...]` attribute line, leaving out the ones that only
+/// change how the listing renders, such as `linenums` or `indent=0`
+fn source_tags(line: &str) -> Option<Vec<&str>> {
+    let attributes = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let mut positional = attributes.split(',').map(str::trim);
+    let style = positional.next()?.split(['#', '.', '%']).next().unwrap_or_default();
+    if !matches!(style, "source" | "") || positional.next()?.trim_matches('"') != "rust" {
+        return None;
+    }
+    Some(positional
+        .filter(|tag| !tag.is_empty() && *tag != "linenums" && !tag.starts_with('%'))
+        .filter(|tag| tag.split_once('=').is_none_or(|(name, _)| matches!(name.trim(), "removal" | "feature" | "since" | "until")))
+        .collect())
+}
+
+/// The body of a `// spec-check: ...` comment
+fn directive(line: &str) -> Option<&str> {
+    let body = line.strip_prefix("//")?.trim();
+    Some(body.strip_prefix("spec-check:")?.trim())
+}
+
+/// The normative Rust blocks of an AsciiDoc spec
+pub fn rust_blocks(adoc: &str) -> Vec<RustBlock<'_>> {
+    blocks(adoc, false)
+}
+
+/// The `[source,rust,example]` blocks instead of the normative ones
+pub fn example_blocks(adoc: &str) -> Vec<RustBlock<'_>> {
+    blocks(adoc, true)
+}
+
+/// A Rust block being read: its tags, the line that ends it (empty for a paragraph) and the
+/// start of its contents
+struct OpenBlock<'a> {
+    tags: Vec<&'a str>,
+    delimiter: &'a str,
+    start: usize,
+    line: usize,
+    prose: Option<&'a str>,
+}
+
+fn blocks(adoc: &str, examples: bool) -> Vec<RustBlock<'_>> {
+    let mut blocks = Vec::new();
+    // The last paragraph, if only attribute lines, titles and comments came after it
+    let mut prose: Option<Range<usize>> = None;
+    let mut in_paragraph = false;
+    let mut source: Option<Vec<&str>> = None;
+    let mut verbatim: Option<&str> = None;
+    let mut open: Option<OpenBlock> = None;
+    let mut directive_stage: Option<Stage> = None;
+    let (mut skip_next, mut off) = (false, false);
+
+    // A blank line at the end closes a listing without delimiters
+    let last = Line { text: "", start: adoc.len(), end: adoc.len(), number: 0 };
+    for line in markdown_parser::body_lines(adoc).chain([last]) {
+        if let Some(block) = &open {
+            let ends = if block.delimiter.is_empty() { line.text.trim().is_empty() } else { line.text.trim_end() == block.delimiter };
+            if !ends {
+                continue;
+            }
+            let block = open.take().expect("block is open");
+            let Some(stage) = markdown_parser::block_stage(&block.tags, examples, &mut directive_stage) else { continue };
+            if off || std::mem::take(&mut skip_next) {
+                continue;
+            }
+            let features = block.tags.iter().filter_map(|tag| tag.strip_prefix("feature=")).map(|feature| feature.trim_matches('"').to_string()).collect();
+            blocks.push(RustBlock {
+                code: Cow::Borrowed(&adoc[block.start..line.start]),
+                range: block.start..line.start,
+                line: block.line,
+                stage,
+                features,
+                prose: block.prose,
+                tags: block.tags.iter().map(|tag| tag.to_string()).collect(),
+                include: None,
+                unknown_tag: None,
+            });
+            continue;
+        }
+        if let Some(delimiter) = verbatim {
+            if line.text.trim_end() == delimiter {
+                verbatim = None;
+            }
+            continue;
+        }
+
+        let text = line.text.trim_end();
+        if text.trim().is_empty() {
+            in_paragraph = false;
+        } else if is_verbatim_delimiter(text) {
+            match source.take() {
+                Some(tags) if text.starts_with("----") || text.starts_with("....") => {
+                    let prose = prose.take().map(|range| adoc[range].trim_end());
+                    open = Some(OpenBlock { tags, delimiter: text, start: line.end, line: line.number + 1, prose });
+                }
+                _ => {
+                    prose = None;
+                    verbatim = Some(text);
+                }
+            }
+            in_paragraph = false;
+        } else if let Some(words) = directive(text) {
+            match words {
+                "ignore-next-block" => skip_next = true,
+                "off" => off = true,
+                "on" => off = false,
+                words => {
+                    if let Some(stage) = markdown_parser::stage_tags(words.split_whitespace(), "").flatten() {
+                        directive_stage = Some(stage);
+                    }
+                }
+            }
+        } else if text.starts_with("//") || (text.starts_with('.') && !text.starts_with(". ") && !text.starts_with("..")) {
+            // Comments and block titles
+        } else if text.starts_with('[') && text.ends_with(']') {
+            if let Some(tags) = source_tags(text) {
+                source = Some(tags);
+            } else if !text.starts_with("[[") && !text.starts_with("[#") {
+                source = None;
+            }
+        } else if let Some(tags) = source.take() {
+            // A listing without delimiters, up to the next blank line
+            let prose = prose.take().map(|range| adoc[range].trim_end());
+            open = Some(OpenBlock { tags, delimiter: "", start: line.start, line: line.number, prose });
+        } else if heading(text).is_some() || text.starts_with(['*', '-']) || text.starts_with(". ") {
+            // Headings and lists aren't about what comes after them
+            prose = None;
+            in_paragraph = false;
+        } else {
+            let end = line.start + line.text.len();
+            match prose.as_mut().filter(|_| in_paragraph) {
+                Some(range) => range.end = end,
+                None => prose = Some(line.start..end),
+            }
+            in_paragraph = true;
+        }
+    }
+    blocks
+}
+
+/// The sections of an AsciiDoc spec, as [`markdown_parser::sections`] has them for markdown
+pub fn sections(adoc: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut verbatim: Option<&str> = None;
+    for line in markdown_parser::body_lines(adoc) {
+        let text = line.text.trim_end();
+        if let Some(delimiter) = verbatim {
+            if text == delimiter {
+                verbatim = None;
+            }
+            continue;
+        }
+        if is_verbatim_delimiter(text) {
+            verbatim = Some(text);
+        } else if let Some((level, heading)) = heading(text) {
+            for section in sections.iter_mut().filter(|s| s.lines.end == usize::MAX && s.level >= level) {
+                section.lines.end = line.number;
+            }
+            sections.push(Section { heading, level, lines: line.number..usize::MAX });
+        }
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_listings_are_rust_blocks() {
+        let adoc = concat!(
+            "= Animation\n\n== Easing\n\nEases `t` (REQ-ANIM-012).\n\n.Easing\n[source,rust,linenums]\n----\npub fn ease(t: f32) -> f32;\n----\n\n",
+            "[source,python]\n----\ndef ease(): pass\n----\n\n",
+            "// spec-check: deprecated removal=0.5.0\n[source,rust]\n....\npub fn draw();\n....\n\n",
+            "[source,rust,planned]\npub fn blend();\npub fn mix();\n\n",
+            "[source,rust,example]\n----\nassert_eq!(ease(0.0), 0.0);\n----\n\n",
+            "// spec-check: ignore-next-block\n[source,rust]\n----\nfn sketch() {}\n----\n\n",
+            "== Blending\n\n----\n== Not a heading\n----\n",
+        );
+        let blocks = rust_blocks(adoc);
+        let summary: Vec<(&str, usize, Stage)> = blocks.iter().map(|block| (block.code.as_ref(), block.line, block.stage.clone())).collect();
+        assert_eq!(summary, [
+            ("pub fn ease(t: f32) -> f32;\n", 10, Stage::Stable),
+            ("pub fn draw();\n", 21, Stage::Deprecated { removal: Some("0.5.0".to_string()) }),
+            ("pub fn blend();\npub fn mix();\n", 25, Stage::Planned),
+        ]);
+        assert_eq!(blocks[0].prose, Some("Eases `t` (REQ-ANIM-012)."));
+        assert_eq!(&adoc[blocks[1].range.clone()], blocks[1].code);
+        assert_eq!(example_blocks(adoc).iter().map(|block| block.line).collect::<Vec<_>>(), [30]);
+
+        let sections: Vec<(String, usize, Range<usize>)> = sections(adoc).into_iter().map(|s| (s.heading, s.level, s.lines)).collect();
+        assert_eq!(sections, [
+            ("Animation".to_string(), 1, 1..usize::MAX),
+            ("Easing".to_string(), 2, 3..39),
+            ("Blending".to_string(), 2, 39..usize::MAX),
+        ]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Findings of an earlier run that are accepted for now, so a crate adopting spec-check only
+//! fails on new drift. Findings are matched by rule, file and item rather than by line or
+//! message, so they stay matched while the code around them moves.
+
+use crate::comparator::ComparisonResult;
+use crate::reporter::{item_path, Finding};
+use crate::rust_parser::RustItem;
+use crate::{json, plugin, rules};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Bumped when entries are matched differently
+const VERSION: i64 = 1;
+
+/// Rule code, file, and item path when the finding has one
+type Key = (String, String, Option<String>);
+
+#[derive(Debug, Default)]
+pub struct Baseline {
+    /// How many findings of each key are still to be suppressed
+    remaining: HashMap<Key, usize>,
+    suppressed: usize,
+}
+
+fn key(rule: &str, file: &Path, item: Option<&str>) -> Key {
+    (rule.to_string(), file.to_string_lossy().replace('\\', "/"), item.map(String::from))
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        Self::from_json(&content).with_context(|| format!("Failed to parse baseline {}", path.display()))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        let root = json::parse(content)?;
+        let version = root.get("version").and_then(Value::as_integer);
+        if version != Some(VERSION) {
+            anyhow::bail!("Unsupported baseline version {:?}; write it again with --write-baseline", version);
+        }
+        let mut baseline = Baseline::default();
+        for entry in root.get("findings").and_then(Value::as_array).into_iter().flatten() {
+            let field = |name: &str| entry.get(name).and_then(Value::as_str);
+            let (Some(rule), Some(file)) = (field("rule"), field("file")) else {
+                anyhow::bail!("Baseline entry without a rule and file: {}", entry);
+            };
+            *baseline.remaining.entry(key(rule, Path::new(file), field("item"))).or_default() += 1;
+        }
+        Ok(baseline)
+    }
+
+    /// The baseline accepting every one of `findings`, as JSON
+    pub fn to_json(findings: &[Finding]) -> String {
+        let entries = findings.iter().map(|finding| {
+            let (rule, file, item) = key(finding.rule, &finding.file, finding.item.as_deref());
+            let mut entry = Table::new();
+            entry.insert("rule".to_string(), rule.into());
+            if let Some(rule) = rules::find(finding.rule) {
+                entry.insert("name".to_string(), rule.name.into());
+            }
+            entry.insert("file".to_string(), file.into());
+            if let Some(item) = item {
+                entry.insert("item".to_string(), item.into());
+            }
+            // For people reading the file; entries aren't matched by it
+            entry.insert("message".to_string(), finding.message.clone().into());
+            Value::Table(entry)
+        });
+        let mut root = Table::new();
+        root.insert("version".to_string(), Value::Integer(VERSION));
+        root.insert("findings".to_string(), Value::Array(entries.collect()));
+        json::to_string_pretty(&Value::Table(root))
+    }
+
+    /// Whether the baseline accepts a finding, using up its entry if so
+    pub fn suppresses(&mut self, rule: &str, file: &Path, item: Option<&str>) -> bool {
+        match self.remaining.get_mut(&key(rule, file, item)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                self.suppressed += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drop the parts of a file's result the baseline accepts
+    pub fn suppress(&mut self, file: &Path, result: &mut ComparisonResult) {
+        let mut keep = |rule: &str, item: &RustItem| !self.suppresses(rule, file, Some(&item_path(item)));
+        result.missing_in_spec.retain(|item| keep(rules::MISSING_IN_SPEC, item));
+        result.missing_in_code.retain(|item| keep(rules::MISSING_IN_CODE, item));
+        result.signature_mismatches.retain(|mismatch| keep(rules::SIGNATURE_MISMATCH, &mismatch.code_item));
+        result.attribute_mismatches.retain(|mismatch| keep(rules::ATTRIBUTE_MISMATCH, &mismatch.code_item));
+        result.discriminant_mismatches.retain(|mismatch| keep(rules::DISCRIMINANT_MISMATCH, &mismatch.code_item));
+        result.unresolved_annotations.retain(|item| keep(rules::UNRESOLVED_ANNOTATION, item));
+        result.forbidden_present.retain(|item| keep(rules::FORBIDDEN_PRESENT, item));
+        result.probable_renames.retain(|rename| keep(rules::PROBABLE_RENAME, &rename.code_item));
+        result.duplicates.retain(|duplicate| keep(rules::DUPLICATE_DEFINITION, &duplicate.duplicate));
+        result.value_mismatches.retain(|mismatch| keep(rules::VALUE_MISMATCH, &mismatch.code_item));
+        result.abi_mismatches.retain(|mismatch| keep(rules::ABI_MISMATCH, &mismatch.code_item));
+        result.doc_mismatches.retain(|mismatch| keep(rules::DOC_MISMATCH, &mismatch.code_item));
+        result.ordering_violations.retain(|violation| keep(rules::ORDERING_VIOLATION, &violation.code_item));
+        result.deprecated_in_code.retain(|deprecated| keep(rules::DEPRECATED_IN_CODE, &deprecated.code_item));
+        result.naming_violations.retain(|violation| keep(rules::NAMING_CONVENTION, &violation.code_item));
+        result.spec_parse_errors.retain(|_| !self.suppresses(rules::SPEC_PARSE_FAILURE, file, None));
+        result.custom_violations.retain(|violation| {
+            let rule = plugin::find(&violation.rule).map_or("custom", |rule| rule.code());
+            !self.suppresses(rule, file, violation.item.as_deref())
+        });
+    }
+
+    /// How many findings were suppressed so far
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    /// How many accepted findings haven't occurred, i.e. were fixed since the baseline was written
+    pub fn unused(&self) -> usize {
+        self.remaining.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparator::compare_items;
+    use crate::rust_parser::ItemKind;
+    use quote::quote;
+
+    fn item(name: &str) -> RustItem {
+        RustItem::new(name.to_string(), ItemKind::Struct, format!("pub struct {};", name), quote!(pub struct Foo;), vec![], 1)
+    }
+
+    #[test]
+    fn test_baseline_suppresses_only_known_findings() {
+        let file = Path::new("src/lib.rs");
+        let result = || compare_items(vec![item("Legacy"), item("Fresh")], vec![item("Planned")], &[]);
+        let findings: Vec<Finding> = crate::reporter::findings_for(file, None, &compare_items(vec![item("Legacy")], vec![item("Planned")], &[]), None);
+        let mut baseline = Baseline::from_json(&Baseline::to_json(&findings)).unwrap();
+
+        let mut current = result();
+        baseline.suppress(file, &mut current);
+        assert_eq!(current.missing_in_spec.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), ["Fresh"]);
+        assert!(current.missing_in_code.is_empty());
+        assert_eq!((baseline.suppressed(), baseline.unused()), (2, 0));
+
+        // Each entry accepts one finding
+        let mut again = result();
+        baseline.suppress(file, &mut again);
+        assert_eq!(again.finding_count(), 3);
+        assert!(Baseline::from_json("{\"version\": 0, \"findings\": []}").is_err());
+    }
+}
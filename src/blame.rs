@@ -0,0 +1,126 @@
+//! Who last changed the lines findings are about, from `git blame`, so drift can be routed to
+//! the people who own it
+
+use crate::git;
+use crate::reporter::Finding;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The author and commit of the last change to a line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Blame {
+    pub author: String,
+    /// Without the angle brackets
+    pub email: String,
+    pub commit: String,
+}
+
+impl Blame {
+    /// Whether the line was changed in the working tree, where git has no author for it yet
+    pub fn is_uncommitted(&self) -> bool {
+        self.commit.chars().all(|c| c == '0')
+    }
+
+    /// `Name <email>`, as findings are grouped by author
+    pub fn author_with_email(&self) -> String {
+        if self.is_uncommitted() {
+            return "not committed yet".to_string();
+        }
+        match self.email.as_str() {
+            "" => self.author.clone(),
+            email => format!("{} <{}>", self.author, email),
+        }
+    }
+}
+
+impl fmt::Display for Blame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_uncommitted() {
+            return f.write_str("not committed yet");
+        }
+        write!(f, "{} in {}", self.author, &self.commit[..self.commit.len().min(8)])
+    }
+}
+
+/// Each line's blame, from `git blame --line-porcelain` output
+fn parse(porcelain: &str) -> Vec<Blame> {
+    let mut lines = Vec::new();
+    let mut current = Blame { author: String::new(), email: String::new(), commit: String::new() };
+    for line in porcelain.lines() {
+        if line.starts_with('\t') {
+            lines.push(current.clone());
+        } else if let Some(author) = line.strip_prefix("author ") {
+            current.author = author.to_string();
+        } else if let Some(email) = line.strip_prefix("author-mail ") {
+            current.email = email.trim_start_matches('<').trim_end_matches('>').to_string();
+        } else if let Some((commit, _)) = line.split_once(' ').filter(|(commit, _)| commit.len() >= 40 && commit.chars().all(|c| c.is_ascii_hexdigit())) {
+            current.commit = commit.to_string();
+        }
+    }
+    lines
+}
+
+/// Blames files on first use, once each. Files git can't blame, such as untracked ones or any
+/// outside a repository, have no blame.
+#[derive(Default)]
+pub struct Blamer {
+    files: Mutex<HashMap<PathBuf, Vec<Blame>>>,
+}
+
+impl Blamer {
+    /// The blame of a 1-based line of `file`
+    pub fn line(&self, file: &Path, line: usize) -> Option<Blame> {
+        let mut files = self.files.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let lines = files.entry(file.to_path_buf())
+            .or_insert_with(|| git::blame(file).map(|porcelain| parse(&porcelain)).unwrap_or_default());
+        lines.get(line.checked_sub(1)?).cloned()
+    }
+
+    /// Fill in the blame of a finding's code line and spec line
+    pub fn attribute(&self, finding: &mut Finding) {
+        finding.blame = finding.line.and_then(|line| self.line(&finding.file, line));
+        finding.spec_blame = finding.spec_path.as_deref().zip(finding.spec_line).and_then(|(spec, line)| self.line(spec, line));
+    }
+}
+
+/// Who owns a finding: whoever last changed the side it asks to change, or else the other side
+pub fn owner(finding: &Finding) -> Option<&Blame> {
+    let (first, second) = match finding.spec_file {
+        Some(_) => (&finding.spec_blame, &finding.blame),
+        None => (&finding.blame, &finding.spec_blame),
+    };
+    first.as_ref().or(second.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_porcelain_lines_are_blamed_and_owned() {
+        let porcelain = concat!(
+            "712230761054211fd13add359c033eede16b9977 1 1 2\nauthor Ada Lovelace\nauthor-mail <ada@example.com>\nauthor-time 1791953239\n",
+            "summary Add curves\nfilename src/curve.rs\n\tpub struct Curve;\n",
+            "712230761054211fd13add359c033eede16b9977 2 2\nauthor Ada Lovelace\nauthor-mail <ada@example.com>\nfilename src/curve.rs\n\t\n",
+            "0000000000000000000000000000000000000000 3 3 1\nauthor Not Committed Yet\nauthor-mail <not.committed.yet>\nfilename src/curve.rs\n\tpub fn sample() {}\n",
+        );
+        let lines = parse(porcelain);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].to_string(), "Ada Lovelace in 71223076");
+        assert_eq!(lines[1].author_with_email(), "Ada Lovelace <ada@example.com>");
+        assert!(lines[2].is_uncommitted());
+        assert_eq!(lines[2].to_string(), "not committed yet");
+
+        let mut finding = Finding::new(crate::rules::MISSING_IN_SPEC, Path::new("src/curve.rs"), Some(1), "struct Curve is in the code but not in the spec".to_string());
+        finding.blame = Some(lines[0].clone());
+        finding.spec_blame = Some(lines[2].clone());
+        assert_eq!(owner(&finding), Some(&lines[0]));
+        finding.spec_file = Some(PathBuf::from("spec/curve.md"));
+        assert_eq!(owner(&finding), Some(&lines[2]));
+        finding.spec_blame = None;
+        assert_eq!(owner(&finding), Some(&lines[0]));
+    }
+}
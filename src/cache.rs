@@ -0,0 +1,147 @@
+//! Cache of parse and comparison results on disk (and optionally in memory), keyed by content hashes
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Bumped whenever cached data would be interpreted differently
+const FORMAT: &str = concat!("v42-", env!("CARGO_PKG_VERSION"));
+
+/// TOML needs a table at the top level, so every entry is wrapped
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    value: T,
+}
+
+type MemoryKey = (String, u64);
+
+/// Values kept in memory, and the keys used since the last `retain_used`
+#[derive(Default)]
+struct Memory {
+    values: HashMap<MemoryKey, Arc<dyn Any + Send + Sync>>,
+    used: HashSet<MemoryKey>,
+}
+
+pub struct Cache {
+    dir: Option<PathBuf>,
+    memory: Option<Mutex<Memory>>,
+    writable: bool,
+}
+
+impl Cache {
+    /// A cache under `dir`, or one that never stores anything
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir, memory: None, writable: true }
+    }
+
+    /// Like `new`, but also keeps values in memory for long-running processes
+    pub fn in_memory(dir: Option<PathBuf>) -> Self {
+        Self { dir, memory: Some(Mutex::new(Memory::default())), writable: true }
+    }
+
+    /// Read stored values but never write new ones
+    pub fn frozen(self) -> Self {
+        Self { writable: false, ..self }
+    }
+
+    /// Return the cached value for `key`, or compute and store it.
+    /// Errors from `compute` are returned and not cached; cache I/O problems just mean a miss.
+    pub fn get_or_insert_with<T, F>(&self, kind: &str, key: u64, compute: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+        F: FnOnce() -> Result<T>,
+    {
+        let memory_key = (kind.to_string(), key);
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            memory.used.insert(memory_key.clone());
+            if let Some(value) = memory.values.get(&memory_key).and_then(|v| v.downcast_ref::<T>()) {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = self.get_or_insert_on_disk(kind, key, compute)?;
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            memory.values.insert(memory_key, Arc::new(value.clone()));
+        }
+        Ok(value)
+    }
+
+    fn get_or_insert_on_disk<T, F>(&self, kind: &str, key: u64, compute: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T>,
+    {
+        let Some(dir) = &self.dir else { return compute() };
+        let path = dir.join(kind).join(format!("{:016x}.toml", key));
+
+        if let Some(entry) = fs::read_to_string(&path).ok().and_then(|s| toml::from_str::<Entry<T>>(&s).ok()) {
+            return Ok(entry.value);
+        }
+
+        let value = compute()?;
+        if !self.writable {
+            return Ok(value);
+        }
+        let entry = Entry { value };
+        if let Ok(serialized) = toml::to_string(&entry) {
+            // Write then rename, so parallel workers never read a half-written entry
+            let temp = path.with_extension(format!("tmp{:?}", std::thread::current().id()).replace(['(', ')'], ""));
+            let written = fs::create_dir_all(path.parent().unwrap_or(dir))
+                .and_then(|_| fs::write(&temp, serialized))
+                .and_then(|_| fs::rename(&temp, &path));
+            if written.is_err() {
+                fs::remove_file(&temp).ok();
+            }
+        }
+        Ok(entry.value)
+    }
+
+    /// Drop in-memory values not used since the last call, so memory tracks the current tree
+    pub fn retain_used(&self) {
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let used = std::mem::take(&mut memory.used);
+            memory.values.retain(|key, _| used.contains(key));
+        }
+    }
+}
+
+/// FNV-1a over each part, with a separator so ("ab", "c") and ("a", "bc") differ
+pub fn hash(parts: &[&[u8]]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    for part in [FORMAT.as_bytes()].iter().chain(parts) {
+        for &byte in part.iter().chain(&[0xff]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_insert_with_reuses_stored_value() {
+        let dir = std::env::temp_dir().join(format!("spec-check-cache-test-{}", std::process::id()));
+        let cache = Cache::new(Some(dir.clone()));
+        let key = hash(&[b"content"]);
+
+        let first: Vec<String> = cache.get_or_insert_with("test", key, || Ok(vec!["computed".to_string()])).unwrap();
+        let second: Vec<String> = cache.get_or_insert_with("test", key, || anyhow::bail!("should not recompute")).unwrap();
+        assert_eq!(first, second);
+        assert_ne!(hash(&[b"ab", b"c"]), hash(&[b"a", b"bc"]));
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
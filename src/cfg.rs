@@ -0,0 +1,158 @@
+//! `#[cfg(...)]` attributes as predicates, so items behind features compare by what they
+//! require rather than how it's spelled, and runs for a feature set can leave out the items
+//! it doesn't build
+
+use crate::rust_parser::RustItem;
+use std::collections::BTreeSet;
+use std::fmt;
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprLit, Lit, Meta};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cfg {
+    /// `feature = "vr"`
+    Feature(String),
+    /// A name, e.g. `unix` or `test`
+    Flag(String),
+    /// Any other `key = "value"`, e.g. `target_os = "linux"`
+    Value(String, String),
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    fn from_meta(meta: &Meta) -> Option<Cfg> {
+        let name = meta.path().get_ident()?.to_string();
+        match meta {
+            Meta::Path(_) => Some(Cfg::Flag(name)),
+            Meta::NameValue(pair) => {
+                let Expr::Lit(ExprLit { lit: Lit::Str(value), .. }) = &pair.value else { return None };
+                Some(if name == "feature" { Cfg::Feature(value.value()) } else { Cfg::Value(name, value.value()) })
+            }
+            Meta::List(list) => {
+                let nested = list.parse_args_with(Punctuated::<Meta, syn::Token![,]>::parse_terminated).ok()?;
+                let nested = nested.iter().map(Cfg::from_meta).collect::<Option<Vec<_>>>()?;
+                match name.as_str() {
+                    "all" => Some(Cfg::All(nested)),
+                    "any" => Some(Cfg::Any(nested)),
+                    "not" if nested.len() == 1 => Some(Cfg::Not(Box::new(nested.into_iter().next()?))),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// The same predicate with `all` and `any` flattened, sorted and deduplicated, so
+    /// `all(unix, feature = "vr")` and `all(feature = "vr", unix)` are equal
+    pub fn normalized(self) -> Cfg {
+        fn flatten(nested: Vec<Cfg>, all: bool) -> Vec<Cfg> {
+            let mut flat: Vec<Cfg> = nested.into_iter().map(Cfg::normalized).flat_map(|cfg| match cfg {
+                Cfg::All(inner) if all => inner,
+                Cfg::Any(inner) if !all => inner,
+                cfg => vec![cfg],
+            }).collect();
+            flat.sort();
+            flat.dedup();
+            flat
+        }
+        match self {
+            Cfg::All(nested) => match flatten(nested, true) {
+                mut one if one.len() == 1 => one.remove(0),
+                nested => Cfg::All(nested),
+            },
+            Cfg::Any(nested) => match flatten(nested, false) {
+                mut one if one.len() == 1 => one.remove(0),
+                nested => Cfg::Any(nested),
+            },
+            Cfg::Not(inner) => Cfg::Not(Box::new(inner.normalized())),
+            cfg => cfg,
+        }
+    }
+
+    /// Whether the predicate holds with exactly `features` enabled; `None` if that depends on
+    /// something else, such as the target
+    pub fn enabled(&self, features: &BTreeSet<String>) -> Option<bool> {
+        match self {
+            Cfg::Feature(feature) => Some(features.contains(feature)),
+            Cfg::Flag(_) | Cfg::Value(..) => None,
+            Cfg::All(nested) => nested.iter().try_fold(Some(true), |all, cfg| match cfg.enabled(features) {
+                Some(false) => Err(()),
+                Some(true) => Ok(all),
+                None => Ok(None),
+            }).unwrap_or(Some(false)),
+            Cfg::Any(nested) => nested.iter().try_fold(Some(false), |any, cfg| match cfg.enabled(features) {
+                Some(true) => Err(()),
+                Some(false) => Ok(any),
+                None => Ok(None),
+            }).unwrap_or(Some(true)),
+            Cfg::Not(inner) => inner.enabled(features).map(|enabled| !enabled),
+        }
+    }
+}
+
+impl fmt::Display for Cfg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, name: &str, nested: &[Cfg]| {
+            let nested: Vec<String> = nested.iter().map(Cfg::to_string).collect();
+            write!(f, "{}({})", name, nested.join(", "))
+        };
+        match self {
+            Cfg::Feature(feature) => write!(f, "feature = {:?}", feature),
+            Cfg::Flag(name) => write!(f, "{}", name),
+            Cfg::Value(name, value) => write!(f, "{} = {:?}", name, value),
+            Cfg::All(nested) => list(f, "all", nested),
+            Cfg::Any(nested) => list(f, "any", nested),
+            Cfg::Not(inner) => write!(f, "not({})", inner),
+        }
+    }
+}
+
+/// The predicate of a `#[cfg(...)]` attribute; `None` for other attributes, and for a `cfg`
+/// that doesn't parse
+pub fn parse(attribute: &str) -> Option<Cfg> {
+    let attrs = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attribute).ok()?;
+    let attr = attrs.first().filter(|attr| attr.path().is_ident("cfg"))?;
+    Cfg::from_meta(&attr.parse_args::<Meta>().ok()?)
+}
+
+/// What an item's `#[cfg]` attributes require together, if it has any
+pub fn item_cfg(attributes: &[String]) -> Option<Cfg> {
+    let all: Vec<Cfg> = attributes.iter().filter_map(|attribute| parse(attribute)).collect();
+    (!all.is_empty()).then(|| Cfg::All(all).normalized())
+}
+
+/// The attribute a spec block's `feature=` tags stand for on each of its items
+pub fn feature_attribute(features: &[String]) -> Option<String> {
+    let all: Vec<Cfg> = features.iter().cloned().map(Cfg::Feature).collect();
+    (!all.is_empty()).then(|| format!("#[cfg({})]", Cfg::All(all).normalized()))
+}
+
+/// Whether an item is built with exactly `features` enabled. Items whose `cfg` depends on the
+/// target as well count as built.
+pub fn is_enabled(item: &RustItem, features: &BTreeSet<String>) -> bool {
+    item_cfg(&item.attributes).and_then(|cfg| cfg.enabled(features)) != Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cfg_predicates() {
+        let cfg = parse(r#"# [cfg (all (feature = "vr" , any (unix , not (feature = "headless"))))]"#).unwrap();
+        assert_eq!(cfg.to_string(), r#"all(feature = "vr", any(unix, not(feature = "headless")))"#);
+        assert_eq!(parse("#[derive(Debug)]"), None);
+
+        let features = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(cfg.enabled(&features(&["vr"])), Some(true));
+        assert_eq!(cfg.enabled(&features(&[])), Some(false));
+        // Without `headless`'s say, only the target decides
+        assert_eq!(cfg.enabled(&features(&["vr", "headless"])), None);
+
+        let split = item_cfg(&[r#"#[cfg(unix)]"#.to_string(), r#"#[cfg(feature = "vr")]"#.to_string()]);
+        let joined = item_cfg(&[r#"#[cfg(all(feature = "vr", unix, feature = "vr"))]"#.to_string()]);
+        assert_eq!(split, joined);
+        assert_eq!(feature_attribute(&["vr".to_string()]).as_deref(), Some(r#"#[cfg(feature = "vr")]"#));
+    }
+}
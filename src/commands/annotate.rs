@@ -0,0 +1,106 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::comparator;
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::reporter::format_item;
+use spec_check::rules;
+use spec_check::rust_parser::{self, RustItem};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct AnnotateArgs {
+    /// Source file to annotate
+    pub file: PathBuf,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: AnnotateArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let source = fs::read_to_string(&args.file)
+        .with_context(|| format!("Failed to read {}", args.file.display()))?;
+    let code_items = rust_parser::parse_rust_file(&source, options.visibility)
+        .with_context(|| format!("Failed to parse {}", args.file.display()))?;
+
+    let spec_file = file_walker::mirrored_spec_path(&args.file, &options.src, &options.spec, &options.filter)
+        .with_context(|| format!("{} is not under the source directory {}", args.file.display(), options.src.display()))?;
+    let spec_items = if spec_file.exists() {
+        check::load_spec_items(&spec_file, options.visibility)?
+    } else {
+        Vec::new()
+    };
+
+    let result = comparator::compare_items(code_items.clone(), spec_items, &options.ignored_attributes);
+
+    // Collect one status per item, grouped by the line the item starts on
+    let mut gutter: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for item in &code_items {
+        let statuses = gutter.entry(item.line_number).or_default();
+        let status = item_status(item, &result);
+        if !statuses.contains(&status) {
+            statuses.push(status);
+        }
+    }
+
+    let width = gutter.values().map(|s| s.join(",").len()).max().unwrap_or(2).max(2);
+    println!("{} (spec: {})", args.file.display(), if spec_file.exists() { spec_file.display().to_string() } else { "missing".to_string() });
+    println!("Legend: OK = matches spec, {} = missing in spec, {} = signature mismatch, {} = attribute mismatch",
+        rules::MISSING_IN_SPEC, rules::SIGNATURE_MISMATCH, rules::ATTRIBUTE_MISMATCH);
+    println!();
+    for (index, line) in source.lines().enumerate() {
+        let status = gutter.get(&(index + 1)).map(|s| s.join(",")).unwrap_or_default();
+        println!("{:>width$} {:>4} | {}", status, index + 1, line, width = width);
+    }
+
+    if !result.missing_in_code.is_empty() {
+        println!();
+        println!("{} In spec but not in this file:", rules::MISSING_IN_CODE);
+        for item in &result.missing_in_code {
+            println!("  - {} (spec line {})", format_item(item), item.line_number);
+        }
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// Status of a code item: OK, or the rule code of its finding
+pub fn item_status(item: &RustItem, result: &comparator::ComparisonResult) -> &'static str {
+    if result.missing_in_spec.contains(item) {
+        rules::MISSING_IN_SPEC
+    } else if result.signature_mismatches.iter().any(|m| m.code_item == *item) {
+        rules::SIGNATURE_MISMATCH
+    } else if result.discriminant_mismatches.iter().any(|m| m.code_item == *item) {
+        rules::DISCRIMINANT_MISMATCH
+    } else if result.value_mismatches.iter().any(|m| m.code_item == *item) {
+        rules::VALUE_MISMATCH
+    } else if result.abi_mismatches.iter().any(|m| m.code_item == *item) {
+        rules::ABI_MISMATCH
+    } else if result.attribute_mismatches.iter().any(|m| m.code_item == *item) {
+        rules::ATTRIBUTE_MISMATCH
+    } else if result.doc_mismatches.iter().any(|m| m.code_item == *item) {
+        rules::DOC_MISMATCH
+    } else if result.ordering_violations.iter().any(|v| v.code_item == *item) {
+        rules::ORDERING_VIOLATION
+    } else if result.deprecated_in_code.iter().any(|d| d.code_item == *item) {
+        rules::DEPRECATED_IN_CODE
+    } else if result.naming_violations.iter().any(|v| v.code_item == *item) {
+        rules::NAMING_CONVENTION
+    } else if result.forbidden_present.contains(item) {
+        rules::FORBIDDEN_PRESENT
+    } else if result.probable_renames.iter().any(|r| r.code_item == *item) {
+        rules::PROBABLE_RENAME
+    } else if result.duplicates.iter().any(|d| !d.in_spec && d.duplicate == *item && d.duplicate.line_number == item.line_number) {
+        rules::DUPLICATE_DEFINITION
+    } else {
+        "OK"
+    }
+}
@@ -0,0 +1,49 @@
+use crate::commands::check::CheckArgs;
+use crate::commands::export::{self, Side};
+use spec_check::api_report;
+use spec_check::config::{self, Config};
+use crate::exit_code;
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ApiReportArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    pub format: Format,
+
+    /// Write the report to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Markdown,
+    Json,
+}
+
+pub fn run(args: ApiReportArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    let package = config::load_package_info()?;
+
+    let modules = export::collect_modules(&options, Side::Code)?;
+    let crate_name = export::crate_name(package.as_ref());
+    let version = package.as_ref().map(|p| p.version.as_str());
+    let report = match args.format {
+        Format::Markdown => api_report::to_markdown(&crate_name, version, &modules),
+        Format::Json => api_report::to_json(&crate_name, version, &modules),
+    };
+    match &args.output {
+        Some(path) => fs::write(path, report).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", report),
+    }
+    Ok(exit_code::SUCCESS)
+}
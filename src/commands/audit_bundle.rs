@@ -0,0 +1,183 @@
+use crate::commands::annotate::item_status;
+use crate::commands::check::{self, CheckArgs, CheckOptions};
+use crate::commands::export::{self, Side};
+use spec_check::config::{self, Config, Visibility};
+use crate::exit_code;
+use spec_check::{comparator, file_walker, git, json, rules, rust_parser, rustdoc_json};
+use spec_check::reporter::{self, format_item};
+use spec_check::rust_parser::RustItem;
+use spec_check::zip::ZipWriter;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use toml::{Table, Value};
+use walkdir::WalkDir;
+
+#[derive(Args)]
+pub struct AuditBundleArgs {
+    /// Archive to write
+    pub output: PathBuf,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: AuditBundleArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    let package = config::load_package_info()?;
+
+    let run = check::run_with(&options)?;
+    let mut zip = ZipWriter::default();
+    let mut files = Vec::new();
+    let mut add = |zip: &mut ZipWriter, name: String, contents: &[u8]| {
+        zip.add(&name, contents);
+        files.push(Value::String(name));
+    };
+
+    add(&mut zip, "config.toml".to_string(), toml::to_string(&resolved_config(&options))?.as_bytes());
+
+    for entry in WalkDir::new(&options.spec).sort_by_file_name().into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        let relative = entry.path().strip_prefix(&options.spec).unwrap_or(entry.path());
+        let contents = fs::read(entry.path()).with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        add(&mut zip, format!("spec/{}", relative.to_string_lossy()), &contents);
+    }
+
+    let modules = export::collect_modules(&options, Side::Code)?;
+    let items = rustdoc_json::to_string(
+        &export::crate_name(package.as_ref()),
+        package.as_ref().map(|p| p.version.as_str()),
+        &modules,
+        options.visibility != Visibility::Pub,
+    );
+    add(&mut zip, "items.json".to_string(), items.as_bytes());
+
+    let mut findings = Table::new();
+    findings.insert("exit_code".to_string(), Value::Integer(run.exit_code.into()));
+    findings.insert("findings".to_string(), Value::Array(run.findings.iter().map(reporter::finding_json).collect()));
+    add(&mut zip, "findings.json".to_string(), json::to_string_pretty(&Value::Table(findings)).as_bytes());
+
+    add(&mut zip, "traceability.csv".to_string(), traceability(&options)?.as_bytes());
+
+    // The manifest lists everything else, so it goes last
+    let mut manifest = Table::new();
+    manifest.insert("tool".to_string(), "spec-check".into());
+    manifest.insert("tool-version".to_string(), env!("CARGO_PKG_VERSION").into());
+    if let Some(package) = &package {
+        manifest.insert("crate".to_string(), package.name.clone().into());
+        manifest.insert("crate-version".to_string(), package.version.clone().into());
+    }
+    if let Ok(commit) = git::head_commit() {
+        manifest.insert("commit".to_string(), commit.into());
+    }
+    let generated = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    manifest.insert("generated-at".to_string(), Value::Integer(generated as i64));
+    manifest.insert("exit-code".to_string(), Value::Integer(run.exit_code.into()));
+    manifest.insert("findings".to_string(), Value::Integer(run.findings.len() as i64));
+    manifest.insert("files".to_string(), Value::Array(files));
+    zip.add("manifest.toml", toml::to_string(&manifest)?.as_bytes());
+
+    fs::write(&args.output, zip.finish()).with_context(|| format!("Failed to write {}", args.output.display()))?;
+    println!("Wrote audit bundle {} ({} finding(s))", args.output.display(), run.findings.len());
+
+    // The bundle records the outcome; producing it succeeded either way
+    Ok(exit_code::SUCCESS)
+}
+
+/// The settings the check ran with, after merging the CLI over Cargo.toml
+fn resolved_config(options: &CheckOptions) -> Table {
+    let path = |path: &Path| Value::String(path.to_string_lossy().replace('\\', "/"));
+    let mut table = Table::new();
+    table.insert("src-dir".to_string(), path(&options.src));
+    table.insert("spec-dir".to_string(), path(&options.spec));
+    table.insert("spec-format".to_string(), Value::String(options.filter.spec_format.name().to_string()));
+    table.insert("visibility".to_string(), Value::String(options.visibility.name().to_string()));
+    table.insert("check-orphaned-specs".to_string(), Value::Boolean(options.check_orphaned_specs));
+    table.insert("ignored-attributes".to_string(), Value::Array(options.ignored_attributes.iter().cloned().map(Value::String).collect()));
+    table.insert("compare-const-values".to_string(), Value::Boolean(options.compare.const_values));
+    table.insert("compare-bodies".to_string(), Value::Boolean(options.compare.bodies));
+    table.insert("compare-docs".to_string(), Value::Boolean(options.compare.docs));
+    table.insert("compare-order".to_string(), Value::Boolean(options.compare.order));
+    table.insert("allow-unspecced-deprecated".to_string(), Value::Boolean(options.compare.unspecced_deprecated));
+    table.insert("ignore-param-names".to_string(), Value::Boolean(!options.compare.param_names));
+    table.insert("normalize-generics".to_string(), Value::Boolean(!options.compare.bound_placement));
+    let superset: Table = options.compare.superset_attributes.iter().map(|name| (name.clone(), Value::from("code-superset-ok"))).collect();
+    table.insert("attribute-policy".to_string(), Value::Table(superset));
+    let mut abi = Table::new();
+    abi.insert("attributes".to_string(), Value::Array(options.compare.abi_attributes.iter().cloned().map(Value::String).collect()));
+    table.insert("attribute-classes".to_string(), Value::Table(Table::from_iter([(config::ABI_CLASS.to_string(), Value::Table(abi))])));
+    let aliases: Table = options.compare.type_aliases.iter().map(|(path, short)| (path.clone(), Value::from(short.as_str()))).collect();
+    table.insert("type-aliases".to_string(), Value::Table(aliases));
+    let naming = options.compare.naming.iter().map(|rule| {
+        let fields = [("kind", rule.kind.clone()), ("attribute", rule.attribute.clone()), ("case", rule.case.map(|case| case.to_string())), ("pattern", rule.pattern.clone())];
+        Value::Table(fields.into_iter().filter_map(|(key, value)| Some((key.to_string(), Value::String(value?)))).collect())
+    });
+    table.insert("naming".to_string(), Value::Array(naming.collect()));
+    if let Some(baseline) = options.baseline.as_deref().filter(|baseline| baseline.exists()) {
+        table.insert("baseline".to_string(), path(baseline));
+    }
+    table.insert("spec-annotations".to_string(), Value::Boolean(options.spec_annotations));
+    table.insert("staged".to_string(), Value::Boolean(options.staged));
+    table.insert("wasm-rules".to_string(), Value::Array(options.wasm_rules.iter().map(|rule| rule.code.clone().into()).collect()));
+    table
+}
+
+/// One row per item: where it is defined in the code and in the spec, and its status
+fn traceability(options: &CheckOptions) -> Result<String> {
+    let mut csv = String::from("item,kind,source,source_line,spec,spec_line,status\n");
+    for mapping in file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        let content = fs::read_to_string(&mapping.rust_file)
+            .with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
+        let source = mapping.rust_file.to_string_lossy().replace('\\', "/");
+        let Ok(code_items) = rust_parser::parse_rust_file(&content, options.visibility) else {
+            csv.push_str(&row(&["", "", &source, "", "", "", rules::PARSE_FAILURE]));
+            continue;
+        };
+        let Some(spec_file) = &mapping.spec_file else {
+            for item in &code_items {
+                csv.push_str(&code_row(item, &source, None, rules::MISSING_SPEC_FILE));
+            }
+            continue;
+        };
+        let spec = spec_file.to_string_lossy().replace('\\', "/");
+        let spec_items = check::load_spec_items(spec_file, options.visibility)?;
+        let result = comparator::compare_items(code_items.clone(), spec_items.clone(), &options.ignored_attributes);
+
+        for item in &code_items {
+            let spec_item = spec_items.iter().find(|spec_item| *spec_item == item);
+            csv.push_str(&code_row(item, &source, spec_item.map(|s| (spec.as_str(), s.line_number)), item_status(item, &result)));
+        }
+        for item in &result.missing_in_code {
+            let line = item.line_number.to_string();
+            csv.push_str(&row(&[&format_item(item), item.kind.label(), "", "", &spec, &line, rules::MISSING_IN_CODE]));
+        }
+    }
+    Ok(csv)
+}
+
+fn code_row(item: &RustItem, source: &str, spec: Option<(&str, usize)>, status: &str) -> String {
+    let source_line = item.line_number.to_string();
+    let spec_line = spec.map(|(_, line)| line.to_string()).unwrap_or_default();
+    row(&[&format_item(item), item.kind.label(), source, &source_line, spec.map_or("", |(file, _)| file), &spec_line, status])
+}
+
+fn row(fields: &[&str]) -> String {
+    let quoted: Vec<String> = fields.iter()
+        .map(|field| if field.contains([',', '"', '\n']) { format!("\"{}\"", field.replace('"', "\"\"")) } else { field.to_string() })
+        .collect();
+    format!("{}\n", quoted.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_quote_fields_that_need_it() {
+        assert_eq!(row(&["fn a", "function", "src/lib.rs", "3", "", "", "OK"]), "fn a,function,src/lib.rs,3,,,OK\n");
+        assert_eq!(row(&["say \"hi\"", "a,b"]), "\"say \"\"hi\"\"\",\"a,b\"\n");
+    }
+}
@@ -0,0 +1,260 @@
+use spec_check::comparator::{self, CompareOptions};
+use spec_check::config::{Config, Visibility};
+use crate::exit_code;
+use spec_check::git;
+use spec_check::reporter::format_item;
+use spec_check::rust_parser::{self, RustItem};
+use spec_check::spec_format;
+use crate::commands::export::Side;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ChangelogArgs {
+    /// Base revision (tag, branch, or commit)
+    #[arg(long)]
+    pub from: String,
+
+    /// Target revision
+    #[arg(long, default_value = "HEAD")]
+    pub to: String,
+
+    /// Source directory
+    #[arg(short, long)]
+    pub src: Option<PathBuf>,
+
+    /// Spec directory
+    #[arg(short = 'p', long)]
+    pub spec: Option<PathBuf>,
+
+    /// Diff the items of the source files, or of the spec files
+    #[arg(long, value_enum, default_value_t = Side::Code)]
+    pub of: Side,
+
+    /// Write the changelog to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// A single API change between two revisions
+pub struct ApiChange {
+    pub file: PathBuf,
+    pub old: Option<RustItem>,
+    pub new: Option<RustItem>,
+    pub breaking: bool,
+}
+
+pub fn run(args: ChangelogArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let dir = match args.of {
+        Side::Code => args.src.or_else(|| config.src_dir.as_ref().map(PathBuf::from)).unwrap_or_else(|| PathBuf::from("src")),
+        Side::Spec => args.spec.or_else(|| config.spec_dir.as_ref().map(PathBuf::from)).unwrap_or_else(|| PathBuf::from("spec")),
+    };
+    let visibility = config.visibility();
+
+    let old_items = items_at_revision(&args.from, &dir, args.of, visibility)?;
+    let new_items = items_at_revision(&args.to, &dir, args.of, visibility)?;
+    let changes = diff_revisions(old_items, new_items, &config.get_ignored_attributes(), None);
+
+    let markdown = render_markdown(&args.from, &args.to, &changes);
+    match &args.output {
+        Some(path) => fs::write(path, markdown)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", markdown),
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// Parse every Rust file, or with `Side::Spec` every spec file, under `dir` as it was at `rev`
+pub fn items_at_revision(rev: &str, dir: &Path, side: Side, visibility: Visibility) -> Result<BTreeMap<PathBuf, Vec<RustItem>>> {
+    let mut items = BTreeMap::new();
+    for file in git::list_files(rev, dir)? {
+        let wanted = match side {
+            Side::Code => file.extension().is_some_and(|ext| ext == "rs"),
+            Side::Spec => spec_format::is_spec_file(&file),
+        };
+        if !wanted {
+            continue;
+        }
+        let content = git::show_file(rev, &file)?;
+        if side == Side::Spec {
+            let (spec_items, _) = spec_check::parse_spec_as(&content, spec_format::of(&file), visibility);
+            items.insert(file, spec_items);
+            continue;
+        }
+        match rust_parser::parse_rust_file(&content, visibility) {
+            Ok(file_items) => {
+                items.insert(file, file_items);
+            }
+            Err(err) => eprintln!("Warning: skipping {} at {}: {}", file.display(), rev, err),
+        }
+    }
+
+    Ok(items)
+}
+
+/// Diff two per-file item sets, treating the old revision as the "spec" side. With `compare`,
+/// the differences it leaves out, such as function bodies, aren't changes.
+pub fn diff_revisions(
+    mut old: BTreeMap<PathBuf, Vec<RustItem>>,
+    mut new: BTreeMap<PathBuf, Vec<RustItem>>,
+    ignored_attributes: &[String],
+    compare: Option<&CompareOptions>,
+) -> Vec<ApiChange> {
+    let files: BTreeSet<PathBuf> = old.keys().chain(new.keys()).cloned().collect();
+    let mut changes = Vec::new();
+
+    for file in files {
+        let old_items = old.remove(&file).unwrap_or_default();
+        let new_items = new.remove(&file).unwrap_or_default();
+        let mut result = comparator::compare_items(new_items, old_items, ignored_attributes);
+        if let Some(compare) = compare {
+            comparator::relax(&mut result, compare, ignored_attributes);
+        }
+
+        for item in result.missing_in_spec {
+            changes.push(ApiChange { file: file.clone(), old: None, new: Some(item), breaking: false });
+        }
+        for item in result.missing_in_code {
+            changes.push(ApiChange { file: file.clone(), old: Some(item), new: None, breaking: true });
+        }
+        // A rename removes the old name, whatever replaces it
+        for rename in result.probable_renames {
+            changes.push(ApiChange { file: file.clone(), old: None, new: Some(rename.code_item), breaking: false });
+            changes.push(ApiChange { file: file.clone(), old: Some(rename.spec_item), new: None, breaking: true });
+        }
+        for mismatch in result.signature_mismatches {
+            changes.push(ApiChange {
+                file: file.clone(),
+                old: Some(mismatch.spec_item),
+                new: Some(mismatch.code_item),
+                breaking: true,
+            });
+        }
+        for mismatch in result.discriminant_mismatches {
+            changes.push(ApiChange {
+                file: file.clone(),
+                old: Some(mismatch.spec_item),
+                new: Some(mismatch.code_item),
+                breaking: true,
+            });
+        }
+        for mismatch in result.value_mismatches {
+            changes.push(ApiChange {
+                file: file.clone(),
+                old: Some(mismatch.spec_item),
+                new: Some(mismatch.code_item),
+                breaking: true,
+            });
+        }
+        for mismatch in result.abi_mismatches {
+            changes.push(ApiChange {
+                file: file.clone(),
+                old: Some(mismatch.spec_item),
+                new: Some(mismatch.code_item),
+                breaking: true,
+            });
+        }
+        for mismatch in result.attribute_mismatches {
+            // Gaining whole attributes is additive; losing or altering any is breaking
+            let old_attrs = comparator::normalize_attributes(&mismatch.spec_item.attributes, ignored_attributes);
+            let new_attrs = comparator::normalize_attributes(&mismatch.code_item.attributes, ignored_attributes);
+            let breaking = !old_attrs.iter().all(|attr| new_attrs.contains(attr));
+            changes.push(ApiChange {
+                file: file.clone(),
+                old: Some(mismatch.spec_item),
+                new: Some(mismatch.code_item),
+                breaking,
+            });
+        }
+    }
+
+    changes
+}
+
+pub fn render_markdown(from: &str, to: &str, changes: &[ApiChange]) -> String {
+    let mut out = format!("## API changes from {} to {}\n", from, to);
+
+    if changes.is_empty() {
+        out.push_str("\nNo API changes.\n");
+        return out;
+    }
+
+    for (heading, breaking) in [("Breaking changes", true), ("Additive changes", false)] {
+        let section: Vec<&ApiChange> = changes.iter().filter(|c| c.breaking == breaking).collect();
+        if section.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n### {}\n", heading));
+
+        for (label, old, new) in [("Added", false, true), ("Removed", true, false), ("Changed", true, true)] {
+            let group: Vec<&&ApiChange> = section.iter()
+                .filter(|c| c.old.is_some() == old && c.new.is_some() == new)
+                .collect();
+            if group.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n#### {}\n\n", label));
+            for change in group {
+                out.push_str(&render_change(change));
+            }
+        }
+    }
+
+    out
+}
+
+fn render_change(change: &ApiChange) -> String {
+    let item = change.new.as_ref().or(change.old.as_ref()).expect("change has at least one side");
+    let mut out = format!("- `{}` ({})\n", format_item(item), change.file.display());
+
+    if let (Some(old), Some(new)) = (&change.old, &change.new) {
+        if old.signature != new.signature {
+            out.push_str(&format!("  - before: `{}`\n", old.signature));
+            out.push_str(&format!("  - after: `{}`\n", new.signature));
+        }
+        if old.attributes != new.attributes {
+            out.push_str(&format!("  - attributes before: `{}`\n", old.attributes.join(" ")));
+            out.push_str(&format!("  - attributes after: `{}`\n", new.attributes.join(" ")));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(code: &str) -> BTreeMap<PathBuf, Vec<RustItem>> {
+        BTreeMap::from([(PathBuf::from("src/lib.rs"), rust_parser::parse_rust_file(code, Visibility::Pub).unwrap())])
+    }
+
+    #[test]
+    fn test_diff_revisions_classifies_changes() {
+        let old = items("pub struct Kept; pub fn removed() {} pub fn changed(x: u32) {}");
+        let new = items("pub struct Kept; pub fn added() {} pub fn changed(x: u64) {} #[must_use] pub struct Marked;");
+
+        let changes = diff_revisions(old, new, &[], None);
+        let find = |name: &str| changes.iter()
+            .find(|c| c.new.as_ref().or(c.old.as_ref()).unwrap().name == name)
+            .unwrap();
+
+        assert_eq!(changes.len(), 4);
+        assert!(!find("added").breaking);
+        assert!(!find("Marked").breaking);
+        assert!(find("removed").breaking && find("removed").new.is_none());
+        assert!(find("changed").breaking && find("changed").old.is_some());
+
+        // A probable rename is still a removal and an addition
+        let changes = diff_revisions(items("pub fn blend_frames(a: &[f32], b: &[f32], t: f32) -> Vec<f32> { todo!() }"),
+            items("pub fn mix_frames(a: &[f32], b: &[f32], t: f32) -> Vec<f32> { todo!() }"), &[], None);
+        let sides: Vec<(bool, bool)> = changes.iter().map(|c| (c.old.is_some(), c.new.is_some())).collect();
+        assert_eq!(sides, [(false, true), (true, false)]);
+    }
+}
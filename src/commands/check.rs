@@ -0,0 +1,1415 @@
+use spec_check::{annotations, blame, comparator, config, file_walker, fix, git, html, junit, line_diff, parallel, plugin, prometheus, reporter, rules, rust_parser, rustdoc_json, sarif, spec_format, terminal, wasm_rules, webhook};
+use spec_check::annotations::{Annotations, SpecAnnotation};
+use spec_check::baseline::Baseline;
+use spec_check::cache::{self, Cache};
+use spec_check::comparator::CompareOptions;
+use spec_check::coverage::Coverage;
+use spec_check::semver::{Bump, Impact};
+use spec_check::config::{Scope, Severity, SourceOfTruth, SpecSettings, Visibility};
+use spec_check::expand::Expanded;
+use spec_check::file_walker::FileMapping;
+use spec_check::journal::{Inputs, Journal, Stamp};
+use spec_check::reporter::{Finding, Report};
+use crate::exit_code;
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use spec_check::rust_parser::RustItem;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::ControlFlow;
+use std::fs;
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+use std::path::{Path, PathBuf};
+
+#[derive(Args, Clone, Default)]
+pub struct CheckArgs {
+    /// Source directory
+    #[arg(short, long)]
+    pub src: Option<PathBuf>,
+    
+    /// Spec directory
+    #[arg(short = 'p', long)]
+    pub spec: Option<PathBuf>,
+
+    /// Apply the settings of this `[profile.NAME]` over the rest of the config
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Check private items in addition to public items
+    #[arg(long)]
+    pub check_private: Option<bool>,
+
+    /// The least visible items to check, in place of `--check-private`
+    #[arg(long, value_enum)]
+    pub visibility: Option<Visibility>,
+
+    /// Report spec files that no source file maps to
+    #[arg(long)]
+    pub check_orphaned_specs: Option<bool>,
+
+    /// Match items per file pair, or across the whole project regardless of file
+    #[arg(long, value_enum)]
+    pub scope: Option<Scope>,
+
+    /// Output log file
+    #[arg(short, long)]
+    pub log: Option<PathBuf>,
+
+    /// Add this run to the log, under a timestamped header, instead of replacing it
+    #[arg(long)]
+    pub log_append: bool,
+
+    /// Only check source and spec files matching this glob, e.g. `src/core/**` (can be specified multiple times)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Leave out source and spec files matching this glob, e.g. `**/generated/**` (can be specified multiple times)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Also check this source directory, e.g. `tests`, against the spec subdirectory of its name
+    /// (can be specified multiple times)
+    #[arg(long, value_name = "DIR")]
+    pub extra_root: Vec<PathBuf>,
+
+    /// Walk files that `.gitignore` and `.ignore` files ignore, too
+    #[arg(long)]
+    pub no_ignore: bool,
+
+    /// Only expect the items built with exactly these features enabled, e.g. `vr,audio`
+    #[arg(long, value_name = "FEATURES", value_delimiter = ',')]
+    pub features: Vec<String>,
+
+    /// Check the code against the spec's blocks for this version of the API, e.g. `2.0` (defaults to the package's version)
+    #[arg(long, value_name = "VERSION")]
+    pub spec_version: Option<String>,
+
+    /// Also expect the items derive and attribute macros generate, from `cargo expand`
+    #[arg(long)]
+    pub expand: bool,
+
+    /// Attributes to ignore (can be specified multiple times)
+    #[arg(short = 'i', long)]
+    pub ignore_attr: Vec<String>,
+
+    /// Stop at the first file with errors
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Stop reporting after N findings
+    #[arg(long, value_name = "N")]
+    pub max_errors: Option<usize>,
+
+    /// Exit with code 0 when at most N findings fail the run, counting neither parse failures nor missing specs
+    #[arg(long, value_name = "N")]
+    pub error_budget: Option<usize>,
+
+    /// Report all findings but always exit with code 0
+    #[arg(long, visible_alias = "warn-only")]
+    pub no_fail: bool,
+
+    /// Fail when less than this percentage of the checked items is described by a spec, e.g. `80`
+    #[arg(long, value_name = "PERCENT")]
+    pub min_coverage: Option<f64>,
+
+    /// Fail when the drift needs a bigger version bump than this, reading the spec as the released API
+    #[arg(long, value_enum, value_name = "BUMP")]
+    pub max_bump: Option<Bump>,
+
+    /// Only findings of this severity or higher affect the exit code
+    #[arg(long, value_enum, value_name = "LEVEL")]
+    pub fail_on: Option<Severity>,
+
+    /// Turn a rule off, by code or name (can be specified multiple times)
+    #[arg(long, value_name = "RULE")]
+    pub allow: Vec<String>,
+
+    /// Report a rule's findings as warnings (can be specified multiple times)
+    #[arg(long, value_name = "RULE")]
+    pub warn: Vec<String>,
+
+    /// Report a rule's findings as errors (can be specified multiple times)
+    #[arg(long, value_name = "RULE")]
+    pub deny: Vec<String>,
+
+    /// Suppress the findings accepted in this baseline file (defaults to spec-check-baseline.json, if it exists)
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+
+    /// Report every finding, even those the baseline accepts
+    #[arg(long, conflicts_with = "baseline")]
+    pub no_baseline: bool,
+
+    /// Accept every current finding: write them to this baseline file and exit with code 0
+    #[arg(long, value_name = "PATH")]
+    pub write_baseline: Option<PathBuf>,
+
+    /// Check the content staged in the git index instead of the working tree, for the source
+    /// files with staged changes or whose spec has them
+    #[arg(long)]
+    pub staged: bool,
+
+    /// Only check source files that changed, or whose spec changed, since they forked from this git ref
+    #[arg(long, value_name = "REF", conflicts_with = "staged")]
+    pub since: Option<String>,
+
+    /// Only check source files with uncommitted changes, or whose spec has them (`--since HEAD`)
+    #[arg(long, conflicts_with_all = ["staged", "since"])]
+    pub changed: bool,
+
+    /// Rewrite mismatched spec items in place as the code declares them (or the code's as the
+    /// spec does, with `source-of-truth = "spec"`), then check
+    #[arg(long, visible_alias = "update-spec", conflicts_with = "staged")]
+    pub fix: bool,
+
+    /// Print what `--fix`, `init` and `scaffold` would write, as a diff, instead of writing it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Number of files to check in parallel (defaults to the number of CPUs)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Re-parse and re-compare every file instead of reusing cached results
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Reuse cached results but never write the cache or journal (e.g. for a cache restored in CI)
+    #[arg(long, conflicts_with = "no_cache")]
+    pub frozen_cache: bool,
+
+    /// POST the JSON results to this URL at the end of the run
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<String>,
+
+    /// Extra webhook header, e.g. `Authorization: Bearer ${TOKEN}` (can be specified multiple times)
+    #[arg(long, value_name = "HEADER")]
+    pub webhook_header: Vec<String>,
+}
+
+/// Where a plain check reports its results; not shared with subcommands that run a check for
+/// their own output
+#[derive(Args, Clone, Default)]
+pub struct OutputArgs {
+    /// Where results go: terminal, log, json, sarif, junit, html or prometheus, optionally as FORMAT=PATH
+    /// (can be specified multiple times; defaults to terminal and log)
+    #[arg(long, value_name = "FORMAT", value_parser = parse_output)]
+    pub format: Vec<Output>,
+
+    /// Write report formats given without a path here (otherwise to --log, unless the log is
+    /// one of the formats, or else stdout)
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Write the run's metrics here in Prometheus' textfile format, as `--format prometheus=PATH` does
+    #[arg(long, value_name = "PATH")]
+    pub metrics_out: Option<PathBuf>,
+
+    /// Print without colors (also when `NO_COLOR` is set or stdout isn't a terminal)
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Echo each file's outcome to stderr as it's checked, and with `-vv` its findings
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Print only the summary, with no findings or progress
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Show who last changed the code and spec lines of each finding, from `git blame`
+    #[arg(long)]
+    pub blame: bool,
+
+    /// Count findings by the author of the lines they're about, after the summary (implies --blame)
+    #[arg(long, value_enum, value_name = "BY")]
+    pub group_by: Option<GroupBy>,
+}
+
+/// How `--group-by` summarizes findings
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Author,
+}
+
+/// A format `check` reports results in
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Findings grouped by file, with the code they're about, on stdout
+    #[value(alias = "human")]
+    Terminal,
+    /// The plain-text log
+    Log,
+    /// The findings as JSON
+    Json,
+    /// A SARIF 2.1.0 report
+    Sarif,
+    /// A JUnit XML report, with a test case per checked file
+    Junit,
+    /// A standalone HTML page with an index of files and a diff for each mismatch
+    Html,
+    /// Files checked, findings by rule, coverage and run duration, in Prometheus' text format
+    Prometheus,
+}
+
+/// A format and where it's written; `None` is stdout, or the log file for `Log`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Output {
+    pub format: OutputFormat,
+    pub path: Option<PathBuf>,
+}
+
+fn parse_output(value: &str) -> Result<Output, String> {
+    let (name, path) = match value.split_once('=') {
+        Some((name, path)) => (name, Some(PathBuf::from(path))),
+        None => (value, None),
+    };
+    let format = <OutputFormat as clap::ValueEnum>::from_str(name, true)?;
+    if format == OutputFormat::Terminal && path.is_some() {
+        return Err("terminal output always goes to stdout".to_string());
+    }
+    Ok(Output { format, path })
+}
+
+/// Check settings after merging CLI arguments over Cargo.toml metadata
+pub struct CheckOptions {
+    pub src: PathBuf,
+    pub spec: PathBuf,
+    /// `[[mapping]]` rules, tried before the mirrored path
+    pub mappings: Vec<config::MappingRule>,
+    /// Which source and spec files are checked, from `include` and `exclude`
+    pub filter: config::FileFilter,
+    pub log: PathBuf,
+    /// Appending to and rotating the log
+    pub log_options: reporter::LogOptions,
+    pub visibility: Visibility,
+    /// Whether spec files no source file maps to are reported
+    pub check_orphaned_specs: bool,
+    pub scope: Scope,
+    pub ignored_attributes: Vec<String>,
+    pub compare: CompareOptions,
+    pub fail_fast: bool,
+    pub max_errors: Option<usize>,
+    /// How many failing findings the run tolerates
+    pub error_budget: usize,
+    pub no_fail: bool,
+    /// The least share of items, in percent, that must be specced for the run to pass
+    pub min_coverage: Option<f64>,
+    /// The largest version bump the drift may need for the run to pass
+    pub max_bump: Option<Bump>,
+    /// Severities by rule code, from `severity`; unlisted rules are errors
+    pub severities: BTreeMap<String, Severity>,
+    /// What `.spec-check.toml` files override for their subtrees
+    pub directories: config::DirectoryOverrides,
+    pub fail_on: Severity,
+    /// Accepted findings to suppress, if the file exists; `None` with `--no-baseline`
+    pub baseline: Option<PathBuf>,
+    pub write_baseline: Option<PathBuf>,
+    pub staged: bool,
+    /// Git ref whose changes limit the files checked
+    pub since: Option<String>,
+    /// Rewrite mismatched items before checking
+    pub fix: bool,
+    /// Print the diffs of rewrites instead of writing them
+    pub dry_run: bool,
+    /// Which side findings ask to change, and `--fix` rewrites
+    pub source_of_truth: Option<SourceOfTruth>,
+    pub jobs: usize,
+    pub cache: bool,
+    pub frozen_cache: bool,
+    pub wasm_rules: Vec<config::WasmRuleConfig>,
+    pub spec_annotations: bool,
+    /// The package's version, for spec items deprecated until a removal version
+    pub version: Option<String>,
+    /// The version of the API the code is checked against, from `--spec-version`
+    pub spec_version: Option<String>,
+    pub webhook: Option<String>,
+    pub webhook_headers: Vec<String>,
+    /// Where results go without `--format`, from `format`
+    pub formats: Vec<Output>,
+    /// The macro-expanded crate, with `--expand`
+    pub expanded: Option<Expanded>,
+}
+
+impl CheckArgs {
+    /// Determine final values (CLI overrides Cargo.toml metadata, and the `--profile` the rest of it)
+    pub fn resolve(self, config: &config::Config) -> Result<CheckOptions> {
+        let config = &config.with_profile(self.profile.as_deref())?;
+        let src = self.src
+            .or_else(|| config.src_dir.as_ref().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("src"));
+
+        let spec = self.spec
+            .or_else(|| config.spec_dir.as_ref().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("spec"));
+
+        let log = self.log
+            .or_else(|| config.log_file.as_ref().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(config::DEFAULT_LOG_FILE));
+
+        let visibility = self.visibility
+            .or(self.check_private.map(Visibility::from_check_private))
+            .unwrap_or_else(|| config.visibility());
+
+        let mut ignored_attributes = config.get_ignored_attributes();
+        ignored_attributes.extend(self.ignore_attr);
+
+        let mut filter = config.file_filter();
+        filter.include.extend(self.include);
+        filter.exclude.extend(self.exclude);
+        filter.no_ignore = self.no_ignore;
+        filter.roots.extend(self.extra_root);
+
+        let mut compare = config.compare_options();
+        if !self.features.is_empty() {
+            compare.features = Some(self.features.into_iter().collect());
+        }
+
+        let mut webhook_headers = config.webhook_headers.clone().unwrap_or_default();
+        webhook_headers.extend(self.webhook_header);
+
+        let expanded = match self.expand || config.expand.unwrap_or(false) {
+            true => {
+                let cache = Cache::new((!self.no_cache).then(|| Path::new(config::CACHE_DIR).join("check")));
+                let cache = if self.frozen_cache { cache.frozen() } else { cache };
+                Some(Expanded::load(compare.features.as_ref(), visibility, &cache)?)
+            }
+            false => None,
+        };
+
+        let directories = config::DirectoryOverrides::load(&src, &spec)?;
+        let formats = config.format.iter().flatten()
+            .map(|format| parse_output(format).map_err(|err| anyhow::anyhow!("Invalid format `{}` in config: {}", format, err)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(CheckOptions {
+            src,
+            spec,
+            mappings: config.mapping.clone().unwrap_or_default(),
+            filter,
+            log,
+            log_options: reporter::LogOptions {
+                append: self.log_append || config.log_append.unwrap_or(false),
+                max_size: config.log_max_size,
+                keep: config.log_keep.unwrap_or(config::DEFAULT_LOG_KEEP),
+            },
+            visibility,
+            check_orphaned_specs: self.check_orphaned_specs.or(config.check_orphaned_specs).unwrap_or(false),
+            scope: self.scope.or(config.scope).unwrap_or_default(),
+            ignored_attributes,
+            compare,
+            fail_fast: self.fail_fast,
+            max_errors: self.max_errors,
+            error_budget: self.error_budget.unwrap_or(0),
+            no_fail: self.no_fail,
+            min_coverage: self.min_coverage.or(config.min_coverage),
+            max_bump: self.max_bump.or(config.max_bump),
+            severities: rule_levels(config.severities(), &self.allow, &self.warn, &self.deny),
+            directories,
+            fail_on: self.fail_on.unwrap_or_default(),
+            baseline: (!self.no_baseline).then(|| self.baseline
+                .or_else(|| config.baseline.as_ref().map(PathBuf::from))
+                .unwrap_or_else(|| PathBuf::from(config::DEFAULT_BASELINE_FILE))),
+            write_baseline: self.write_baseline,
+            staged: self.staged,
+            since: self.since.or_else(|| self.changed.then(|| "HEAD".to_string())),
+            fix: self.fix,
+            dry_run: self.dry_run,
+            source_of_truth: config.source_of_truth,
+            jobs: self.jobs.unwrap_or_else(parallel::default_jobs),
+            cache: !self.no_cache,
+            frozen_cache: self.frozen_cache,
+            wasm_rules: config.wasm_rules.clone().unwrap_or_default(),
+            spec_annotations: config.spec_annotations.unwrap_or(false),
+            version: config::load_package_info().ok().flatten().map(|package| package.version),
+            spec_version: self.spec_version,
+            webhook: self.webhook.or_else(|| config.webhook.clone()),
+            webhook_headers,
+            formats,
+            expanded,
+        })
+    }
+}
+
+impl OutputArgs {
+    /// The outputs asked for, then the `configured` ones, and terminal and log by default.
+    /// Report formats without a path go to `--output`, or to `--log` (`log`) when the log itself
+    /// isn't one of them.
+    pub fn resolve(&self, log: Option<&Path>, configured: &[Output]) -> Vec<Output> {
+        let format = if self.format.is_empty() { configured } else { &self.format };
+        let mut outputs = if format.is_empty() {
+            vec![
+                Output { format: OutputFormat::Terminal, path: None },
+                Output { format: OutputFormat::Log, path: None },
+            ]
+        } else {
+            let has_log = format.iter().any(|output| output.format == OutputFormat::Log);
+            let fallback = self.output.as_deref().or(log.filter(|_| !has_log));
+            let mut outputs = format.to_vec();
+            for output in &mut outputs {
+                if output.path.is_none() && !matches!(output.format, OutputFormat::Terminal | OutputFormat::Log) {
+                    output.path = fallback.map(Path::to_path_buf);
+                }
+            }
+            outputs
+        };
+        outputs.extend(self.metrics_out.clone().map(|path| Output { format: OutputFormat::Prometheus, path: Some(path) }));
+        outputs
+    }
+}
+
+/// The configured severities with `--allow`, `--warn` and `--deny` on top; of a rule given to
+/// several, the strictest wins
+fn rule_levels(mut severities: BTreeMap<String, Severity>, allow: &[String], warn: &[String], deny: &[String]) -> BTreeMap<String, Severity> {
+    for (rules, severity) in [(allow, Severity::Off), (warn, Severity::Warning), (deny, Severity::Error)] {
+        severities.extend(rules.iter().map(|rule| (config::rule_code(rule), severity)));
+    }
+    severities
+}
+
+impl CheckOptions {
+    /// Whether findings of `rule` in `file` count towards the exit code under `--fail-on`
+    pub fn fails(&self, rule: &str, file: &Path) -> bool {
+        self.directories.severity(rule, file, &self.severities) >= self.fail_on
+    }
+
+    /// The version of the API the spec's blocks are checked for: `--spec-version`, or else the
+    /// package's
+    pub fn checked_version(&self) -> Option<&str> {
+        self.spec_version.as_deref().or(self.version.as_deref())
+    }
+
+    /// Whether `rule` is turned off for `file`, so its findings aren't reported at all
+    pub fn disabled(&self, rule: &str, file: &Path) -> bool {
+        self.directories.severity(rule, file, &self.severities) == Severity::Off
+    }
+}
+
+/// Outcome of a check run
+pub struct CheckRun {
+    pub exit_code: u8,
+    pub findings: Vec<Finding>,
+}
+
+pub fn run(args: CheckArgs, output: OutputArgs) -> Result<u8> {
+    let config = config::Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let log = args.log.clone();
+    let mut options = args.resolve(&config)?;
+    let outputs = output.resolve(log.as_deref(), &options.formats);
+    if options.fix {
+        fix_specs(&options)?;
+    }
+    if let Some(path) = options.write_baseline.take() {
+        // Everything found is accepted, including what the old baseline accepted
+        options.baseline = None;
+        let run = run_with(&options)?;
+        fs::write(&path, Baseline::to_json(&run.findings)).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Wrote {} accepted finding(s) to {}", run.findings.len(), path.display());
+        return Ok(exit_code::SUCCESS);
+    }
+
+    let blamer = (output.blame || output.group_by.is_some()).then(|| Arc::new(blame::Blamer::default()));
+    let reports = open_reports(&options, &outputs, &output, blamer.clone())?;
+    let bar = !output.quiet && std::io::stderr().is_terminal();
+    let mut reports = terminal::Progress::new(std::io::stderr(), reports, output.verbose, bar, options.source_of_truth);
+    // Only the webhook needs every finding at the end
+    let mut findings = Vec::new();
+    let exit_code = run_streaming(&options, &default_cache(&options), &mut reports, |mut finding| {
+        if options.webhook.is_some() {
+            if let Some(blamer) = &blamer {
+                blamer.attribute(&mut finding);
+            }
+            findings.push(finding);
+        }
+    })?;
+    post_webhook(&options, &CheckRun { exit_code, findings });
+    Ok(exit_code)
+}
+
+/// Rewrite the mismatched items of every spec file as its source file declares them, or of
+/// every source file as its spec does when the spec is the source of truth, printing what was
+/// updated. What can't be fixed this way is left to the check that follows.
+fn fix_specs(options: &CheckOptions) -> Result<()> {
+    let (mut items, mut files) = (0, 0);
+    let mut by_heading = BTreeSet::new();
+    for mapping in file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        // Which code item a spec item scoped by heading is for depends on every module
+        if mapping.headings {
+            by_heading.extend(mapping.mapped_specs.into_iter().flatten());
+            continue;
+        }
+        // Under a `[[mapping]]` rule, against each of the rule's spec files in turn
+        let spec_files = mapping.mapped_specs.clone().unwrap_or_else(|| mapping.spec_file.iter().cloned().collect());
+        for spec_file in &spec_files {
+            let source = fs::read_to_string(&mapping.rust_file).with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
+            let markdown = fs::read_to_string(spec_file).with_context(|| format!("Failed to read {}", spec_file.display()))?;
+            let Ok(result) = compare_contents(options, &mapping.rust_file, spec_file, &source, &markdown) else { continue };
+            let settings = SpecSettings::from_markdown(&markdown)?;
+            let (visibility, (ignored, _)) = (settings.visibility(options.visibility), file_comparison(options, &mapping.rust_file, &settings));
+            let (fixed, file, old) = match options.source_of_truth {
+                Some(SourceOfTruth::Spec) => (fix::fix_code(&source, &markdown, spec_format::of(spec_file), &result, visibility, &ignored)?, &mapping.rust_file, &source),
+                _ => (fix::fix(&markdown, spec_format::of(spec_file), &source, &result, visibility, &ignored)?, spec_file, &markdown),
+            };
+            if !fixed.updated.is_empty() {
+                let names: Vec<String> = fixed.updated.iter().map(reporter::format_item).collect();
+                if options.dry_run {
+                    print!("{}", line_diff::unified(&file.display().to_string(), Some(old), &fixed.text));
+                    println!("Would update {}: {}", file.display(), names.join(", "));
+                } else {
+                    fs::write(file, &fixed.text).with_context(|| format!("Failed to write {}", file.display()))?;
+                    println!("Updated {}: {}", file.display(), names.join(", "));
+                }
+                items += fixed.updated.len();
+                files += 1;
+            }
+            if !fixed.left.is_empty() {
+                let names: Vec<String> = fixed.left.iter().map(reporter::format_item).collect();
+                println!("Left {} for a manual update: {}", file.display(), names.join(", "));
+            }
+        }
+    }
+    for spec_file in by_heading {
+        println!("Left {} for a manual update: its items are placed by heading", spec_file.display());
+    }
+    println!("{} {} item(s) in {} file(s)", if options.dry_run { "Would update" } else { "Updated" }, items, files);
+    println!();
+    Ok(())
+}
+
+/// A report for each output, blaming findings' lines with `blamer` if given
+fn open_reports(options: &CheckOptions, outputs: &[Output], args: &OutputArgs, blamer: Option<Arc<blame::Blamer>>) -> Result<Vec<Box<dyn Report>>> {
+    let on_stdout = outputs.iter().filter(|output| output.format != OutputFormat::Log && output.path.is_none()).count();
+    if on_stdout > 1 {
+        anyhow::bail!("Only one --format can print to stdout; write the others to files with FORMAT=PATH");
+    }
+    let mut paths: Vec<&Path> = outputs.iter().filter_map(|output| output.path.as_deref()).collect();
+    paths.sort();
+    if let Some(pair) = paths.windows(2).find(|pair| pair[0] == pair[1]) {
+        anyhow::bail!("Several --format outputs would write to {}; give each its own with FORMAT=PATH", pair[0].display());
+    }
+    let log = outputs.iter().find(|output| output.format == OutputFormat::Log)
+        .map(|output| output.path.clone().unwrap_or_else(|| options.log.clone()));
+    outputs.iter().map(|output| -> Result<Box<dyn Report>> {
+        Ok(match output.format {
+            OutputFormat::Terminal => Box::new(
+                terminal::Printer::new(std::io::stdout(), terminal::use_color(args.no_color), options.severities.clone()).with_log(log.clone())
+                    .with_source_of_truth(options.source_of_truth).with_directories(options.directories.clone()).with_quiet(args.quiet)
+                    .with_blame(blamer.clone()).with_author_summary(args.group_by == Some(GroupBy::Author)),
+            ),
+            OutputFormat::Log => {
+                let path = log.as_deref().expect("a log output has a path");
+                Box::new(reporter::LogReport::open(path, &options.log_options).context("Failed to create log file")?)
+            }
+            format => Box::new(WholeReport {
+                format,
+                path: output.path.clone(),
+                truth: options.source_of_truth,
+                files: Vec::new(),
+                findings: Vec::new(),
+                coverage: None,
+                semver: None,
+                started: Instant::now(),
+                blamer: blamer.clone(),
+            }),
+        })
+    }).collect()
+}
+
+/// A JSON, SARIF, JUnit, HTML or Prometheus report, written to a file or stdout once every file
+/// is checked
+struct WholeReport {
+    format: OutputFormat,
+    path: Option<PathBuf>,
+    truth: Option<SourceOfTruth>,
+    /// Each checked source file, and its spec file if it has one
+    files: Vec<(PathBuf, Option<PathBuf>)>,
+    findings: Vec<Finding>,
+    /// Each source file's spec coverage and the total, for JSON
+    coverage: Option<(Vec<(PathBuf, Coverage)>, Coverage)>,
+    /// The semver impact and the package's version, for JSON
+    semver: Option<(Impact, Option<String>)>,
+    /// When the run began, for Prometheus
+    started: Instant,
+    /// With `--blame`, who last changed the lines of each finding
+    blamer: Option<Arc<blame::Blamer>>,
+}
+
+impl WholeReport {
+    fn push(&mut self, mut finding: Finding) {
+        if let Some(blamer) = &self.blamer {
+            blamer.attribute(&mut finding);
+        }
+        self.findings.push(finding);
+    }
+}
+
+impl Report for WholeReport {
+    fn report_missing_spec(&mut self, file: &Path) -> Result<()> {
+        self.files.push((file.to_path_buf(), None));
+        self.push(reporter::missing_spec_finding(file));
+        Ok(())
+    }
+
+    fn report_parse_failure(&mut self, file: &Path, error: &anyhow::Error) -> Result<()> {
+        self.files.push((file.to_path_buf(), None));
+        self.push(reporter::parse_failure_finding(file, error));
+        Ok(())
+    }
+
+    fn report_orphaned_spec(&mut self, spec_file: &Path) -> Result<()> {
+        self.push(reporter::orphaned_spec_finding(spec_file));
+        Ok(())
+    }
+
+    fn report_results(&mut self, file: &Path, spec: Option<&Path>, result: &comparator::ComparisonResult) -> Result<()> {
+        self.files.push((file.to_path_buf(), spec.map(Path::to_path_buf)));
+        reporter::findings_for(file, spec, result, self.truth).into_iter().for_each(|finding| self.push(finding));
+        Ok(())
+    }
+
+    fn report_coverage(&mut self, files: &[(PathBuf, Coverage)], total: Coverage, _min: Option<f64>) -> Result<()> {
+        self.coverage = Some((files.to_vec(), total));
+        Ok(())
+    }
+
+    fn report_semver(&mut self, impact: Impact, version: Option<&str>, _max: Option<Bump>) -> Result<()> {
+        self.semver = Some((impact, version.map(str::to_string)));
+        Ok(())
+    }
+
+    fn write_summary(&mut self, total_files: usize, files_with_errors: usize) -> Result<()> {
+        let text = match self.format {
+            OutputFormat::Sarif => sarif::to_string(&self.findings),
+            OutputFormat::Junit => {
+                let files: Vec<PathBuf> = self.files.iter().map(|(file, _)| file.clone()).collect();
+                junit::to_string(&files, &self.findings)
+            }
+            OutputFormat::Prometheus => prometheus::to_string(&prometheus::Metrics {
+                files_checked: total_files,
+                files_with_errors,
+                findings: &self.findings,
+                coverage: self.coverage.as_ref().map(|(_, total)| *total),
+                duration: self.started.elapsed(),
+            }),
+            OutputFormat::Html => {
+                // Links are relative to where the report is opened from
+                let dir = self.path.as_deref().and_then(Path::parent).unwrap_or(Path::new(""));
+                html::to_string(dir, &self.files, &self.findings)
+            }
+            _ => {
+                let mut root = toml::Table::new();
+                root.insert("tool".to_string(), "spec-check".into());
+                root.insert("version".to_string(), env!("CARGO_PKG_VERSION").into());
+                root.insert("files_checked".to_string(), toml::Value::Integer(total_files as i64));
+                root.insert("files_with_errors".to_string(), toml::Value::Integer(files_with_errors as i64));
+                root.insert("findings".to_string(), toml::Value::Array(self.findings.iter().map(reporter::finding_json).collect()));
+                if let Some((files, total)) = &self.coverage {
+                    root.insert("coverage".to_string(), coverage_json(files, *total));
+                }
+                if let Some((impact, version)) = &self.semver {
+                    root.insert("semver".to_string(), semver_json(impact, version.as_deref()));
+                }
+                spec_check::json::to_string_pretty(&toml::Value::Table(root))
+            }
+        };
+        match &self.path {
+            Some(path) => {
+                if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+            }
+            None => {
+                print!("{}", text);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `items`, `specced` and `percent` of the whole run, and per source file
+fn coverage_json(files: &[(PathBuf, Coverage)], total: Coverage) -> toml::Value {
+    let table = |coverage: Coverage| {
+        let mut table = toml::Table::new();
+        table.insert("items".to_string(), toml::Value::Integer(coverage.items as i64));
+        table.insert("specced".to_string(), toml::Value::Integer(coverage.specced as i64));
+        table.insert("percent".to_string(), toml::Value::Float(coverage.percent()));
+        table
+    };
+    let mut root = table(total);
+    root.insert("files".to_string(), toml::Value::Array(files.iter().map(|(file, coverage)| {
+        let mut entry = table(*coverage);
+        entry.insert("file".to_string(), file.to_string_lossy().replace('\\', "/").into());
+        toml::Value::Table(entry)
+    }).collect()));
+    toml::Value::Table(root)
+}
+
+/// The least `bump` the drift needs, or none, how many changes need each bump, and the `next`
+/// version if the package's is known
+fn semver_json(impact: &Impact, version: Option<&str>) -> toml::Value {
+    let mut root = toml::Table::new();
+    root.insert("bump".to_string(), impact.bump().map_or("none".to_string(), |bump| bump.to_string()).into());
+    for (key, count) in [("major", impact.major), ("minor", impact.minor), ("patch", impact.patch)] {
+        root.insert(key.to_string(), toml::Value::Integer(count as i64));
+    }
+    if let Some(next) = impact.bump().zip(version).and_then(|(bump, version)| bump.next(version)) {
+        root.insert("next".to_string(), next.into());
+    }
+    toml::Value::Table(root)
+}
+
+/// Send the results to `--webhook`, if set. A failed delivery is only a warning,
+/// so an unreachable endpoint can't change the outcome of the check.
+pub fn post_webhook(options: &CheckOptions, run: &CheckRun) {
+    let Some(url) = &options.webhook else { return };
+    let mut payload = toml::Table::new();
+    payload.insert("tool".to_string(), "spec-check".into());
+    payload.insert("version".to_string(), env!("CARGO_PKG_VERSION").into());
+    payload.insert("exit_code".to_string(), toml::Value::Integer(run.exit_code.into()));
+    payload.insert("findings".to_string(), toml::Value::Array(run.findings.iter().map(reporter::finding_json).collect()));
+    let body = spec_check::json::to_string_pretty(&toml::Value::Table(payload));
+    if let Err(err) = webhook::post(url, &options.webhook_headers, &body) {
+        eprintln!("Warning: {:#}", err);
+    }
+}
+
+/// Check every source file against its spec, writing the log and collecting findings
+pub fn run_with(options: &CheckOptions) -> Result<CheckRun> {
+    run_with_cache(options, &default_cache(options))
+}
+
+/// `run_with`, reusing a cache that may outlive this run
+pub fn run_with_cache(options: &CheckOptions, cache: &Cache) -> Result<CheckRun> {
+    let mut findings = Vec::new();
+    let mut log = reporter::LogReport::open(&options.log, &options.log_options).context("Failed to create log file")?;
+    let exit_code = run_streaming(options, cache, &mut log, |finding| findings.push(finding))?;
+    Ok(CheckRun { exit_code, findings })
+}
+
+/// The on-disk cache, or none with `--no-cache`
+fn default_cache(options: &CheckOptions) -> Cache {
+    let cache = Cache::new(cache_dir(options));
+    if options.frozen_cache { cache.frozen() } else { cache }
+}
+
+pub fn cache_dir(options: &CheckOptions) -> Option<PathBuf> {
+    options.cache.then(|| Path::new(config::CACHE_DIR).join("check"))
+}
+
+/// Check every source file, passing each to `reporter` and its findings to `on_finding` as soon
+/// as it's done, so results for the whole tree are never held at once
+pub fn run_streaming(
+    options: &CheckOptions,
+    cache: &Cache,
+    reporter: &mut dyn Report,
+    mut on_finding: impl FnMut(Finding),
+) -> Result<u8> {
+    wasm_rules::register(&options.wasm_rules);
+
+    // Validate directories exist (staged files come from the index, which may differ)
+    if !options.staged && !options.src.exists() {
+        anyhow::bail!("Source directory does not exist: {}", options.src.display());
+    }
+    if !options.staged && !options.spec.exists() {
+        anyhow::bail!("Spec directory does not exist: {}", options.spec.display());
+    }
+
+    let mut baseline = match &options.baseline {
+        Some(path) if path.exists() => Some(Baseline::load(path)?),
+        _ => None,
+    };
+
+    // Find all file mappings
+    let mappings = if options.staged {
+        file_walker::find_staged_mappings(&options.src, &options.spec, &options.mappings, &options.filter)
+    } else {
+        file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)
+    }
+    .context("Failed to find file mappings")?;
+
+    let mut files_with_errors = 0;
+    let mut violations = 0;
+    let mut files_missing_spec = 0;
+    let mut parse_failures = 0;
+    let mut files_checked = 0;
+    let mut findings_reported = 0;
+    let mut stopped = false;
+    let mut coverage = Vec::new();
+    let mut impact = Impact::default();
+
+    let annotations = if options.spec_annotations {
+        index_annotations(&mappings, options, cache)?
+    } else {
+        Annotations::default()
+    };
+    // After indexing annotations, as unchanged files may still claim spec items of changed ones.
+    // The journal keeps the verdicts of the files left out.
+    let sources: Vec<PathBuf> = mappings.iter().map(|mapping| mapping.rust_file.clone()).collect();
+    let mappings = match &options.since {
+        Some(base) => {
+            if options.scope == Scope::Project {
+                anyhow::bail!("--since and --changed can't be used with --scope project, where every file's outcome depends on the whole tree");
+            }
+            let changed = git::changed_files(base).with_context(|| format!("Failed to list the files changed since {}", base))?;
+            only_changed(mappings, options, changed.into_iter().collect())
+        }
+        // A commit only brings drift into the pairs it touches
+        None if options.staged && options.scope == Scope::File => {
+            let changed = git::staged_changes().context("Failed to list the staged changes")?;
+            only_changed(mappings, options, changed.into_iter().collect())
+        }
+        None => mappings,
+    };
+
+    // Pairs whose files kept their size and modification time since the last run aren't read at all.
+    // With `--scope project` every outcome depends on the whole tree, so all are worked out up front,
+    // plus entries for spec files with items no source file defines.
+    // Source files of a `[[mapping]]` rule are pooled like that, within their rule.
+    let journal = open_journal(options, &annotations);
+    let (unchanged, spec_only) = match options.scope {
+        Scope::File => {
+            let mut unchanged = parallel::map(&mappings, options.jobs, |mapping| match mapping.mapped_specs {
+                Some(_) => None,
+                None => journal.unchanged(&mapping.rust_file, mapping.spec_file.as_deref()),
+            });
+            let mut spec_only = Vec::new();
+            for (specs, members) in file_walker::mapped_groups(&mappings) {
+                let group: Vec<&FileMapping> = members.iter().map(|&index| &mappings[index]).collect();
+                let (verdicts, unmatched) = pool(&group, specs, group[0].headings, &group[0].settings, options, cache)?;
+                for (index, verdict) in members.into_iter().zip(verdicts) {
+                    unchanged[index] = Some(verdict);
+                }
+                spec_only.extend(unmatched);
+            }
+            (unchanged, spec_only)
+        }
+        Scope::Project => {
+            let spec_paths: Vec<PathBuf> = if options.staged {
+                git::list_staged_files(&options.spec)?.into_iter().filter(|path| path.extension().is_some_and(|ext| ext == options.filter.spec_format.extension())).collect()
+            } else {
+                file_walker::find_spec_files(&options.spec, &options.filter)
+            };
+            let (verdicts, spec_only) = pool(&mappings.iter().collect::<Vec<_>>(), &spec_paths, false, &SpecSettings::default(), options, cache)?;
+            (verdicts.into_iter().map(Some).collect(), spec_only)
+        }
+    };
+    let work: Vec<_> = mappings.iter().zip(unchanged)
+        .chain(spec_only.iter().map(|(mapping, verdict)| (mapping, Some(verdict.clone()))))
+        .collect();
+    reporter.report_start(work.len())?;
+    let specs = SpecFiles::read(work.iter().filter(|(_, verdict)| verdict.is_none()).map(|(mapping, _)| *mapping), options)?;
+
+    // Parse and compare in parallel, but report in mapping order so the log is deterministic
+    let check = |(mapping, verdict): &(&FileMapping, Option<Verdict>)| match verdict {
+        Some(verdict) => Ok(file_outcome(verdict.clone())),
+        None => check_file(mapping, options, cache, &specs, &annotations, &journal),
+    };
+    parallel::for_each_ordered(&work, options.jobs, check, |(mapping, _), outcome| -> Result<ControlFlow<()>> {
+        files_checked += 1;
+
+        let outcome = outcome?;
+        let file_rule = match &outcome {
+            FileOutcome::ParseFailure(_) => Some(rules::PARSE_FAILURE),
+            FileOutcome::MissingSpec(_) => Some(rules::MISSING_SPEC_FILE),
+            FileOutcome::Compared(_) => None,
+        };
+        // Before the baseline, which accepts findings without describing their items
+        let file_coverage = match &outcome {
+            FileOutcome::ParseFailure(_) => None,
+            FileOutcome::MissingSpec(items) => Some(Coverage::unspecced(*items)),
+            FileOutcome::Compared(result) => Some(Coverage::of(result)),
+        };
+        if let Some(file_coverage) = file_coverage.filter(|file_coverage| file_coverage.items > 0) {
+            coverage.push((mapping.rust_file.clone(), file_coverage));
+        }
+        if file_rule.is_some_and(|rule| options.disabled(rule, &mapping.rust_file)) {
+            return Ok(ControlFlow::Continue(()));
+        }
+        if let (Some(rule), Some(baseline)) = (file_rule, baseline.as_mut()) && baseline.suppresses(rule, &mapping.rust_file, None) {
+            return Ok(ControlFlow::Continue(()));
+        }
+        let mut result = match outcome {
+            FileOutcome::ParseFailure(err) => {
+                reporter.report_parse_failure(&mapping.rust_file, &err)?;
+                on_finding(reporter::parse_failure_finding(&mapping.rust_file, &err));
+                files_with_errors += 1;
+                parse_failures += options.fails(rules::PARSE_FAILURE, &mapping.rust_file) as usize;
+                findings_reported += 1;
+                if let Some(reason) = stop_reason(options.fail_fast, options.max_errors, findings_reported) {
+                    reporter.report_stopped_early(&reason)?;
+                    stopped = true;
+                    return Ok(ControlFlow::Break(()));
+                }
+                return Ok(ControlFlow::Continue(()));
+            }
+            FileOutcome::MissingSpec(items) => {
+                impact += Impact::unspecced(items);
+                reporter.report_missing_spec(&mapping.rust_file)?;
+                on_finding(reporter::missing_spec_finding(&mapping.rust_file));
+                files_with_errors += 1;
+                files_missing_spec += options.fails(rules::MISSING_SPEC_FILE, &mapping.rust_file) as usize;
+                findings_reported += 1;
+                if let Some(reason) = stop_reason(options.fail_fast, options.max_errors, findings_reported) {
+                    reporter.report_stopped_early(&reason)?;
+                    stopped = true;
+                    return Ok(ControlFlow::Break(()));
+                }
+                return Ok(ControlFlow::Continue(()));
+            }
+            FileOutcome::Compared(result) => *result,
+        };
+        result.retain_rules(|rule| !options.disabled(rule, &mapping.rust_file));
+        if let Some(baseline) = baseline.as_mut() {
+            baseline.suppress(&mapping.rust_file, &mut result);
+        }
+        impact += Impact::of(&result, &options.ignored_attributes);
+
+        if !result.has_errors() {
+            reporter.report_results(&mapping.rust_file, mapping.spec_file.as_deref(), &result)?;
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        files_with_errors += 1;
+        let findings = reporter::findings_for(&mapping.rust_file, mapping.spec_file.as_deref(), &result, options.source_of_truth);
+        violations += findings.iter().filter(|finding| options.fails(finding.rule, &finding.file)).count();
+        if let Some(max) = options.max_errors {
+            result.truncate(max.saturating_sub(findings_reported));
+        }
+        findings_reported += result.finding_count();
+
+        reporter.report_results(&mapping.rust_file, mapping.spec_file.as_deref(), &result)?;
+        reporter::findings_for(&mapping.rust_file, mapping.spec_file.as_deref(), &result, options.source_of_truth).into_iter().for_each(&mut on_finding);
+
+        if let Some(reason) = stop_reason(options.fail_fast, options.max_errors, findings_reported) {
+            reporter.report_stopped_early(&reason)?;
+            stopped = true;
+            return Ok(ControlFlow::Break(()));
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    journal.save(&sources.iter().map(PathBuf::as_path).collect::<Vec<_>>());
+
+    // Only a run over the whole tree knows which spec files nothing maps to. Under `--scope
+    // project` every spec is pooled, so none is.
+    let whole_tree = options.since.is_none() && !options.staged && options.scope == Scope::File;
+    if options.check_orphaned_specs && whole_tree && !stopped {
+        let orphans = file_walker::find_orphaned_specs(&options.src, &options.spec, &options.mappings, &options.filter)
+            .context("Failed to find orphaned spec files")?;
+        for spec_file in orphans {
+            if options.disabled(rules::ORPHANED_SPEC, &spec_file) {
+                continue;
+            }
+            if baseline.as_mut().is_some_and(|baseline| baseline.suppresses(rules::ORPHANED_SPEC, &spec_file, None)) {
+                continue;
+            }
+            reporter.report_orphaned_spec(&spec_file)?;
+            on_finding(reporter::orphaned_spec_finding(&spec_file));
+            files_checked += 1;
+            files_with_errors += 1;
+            violations += options.fails(rules::ORPHANED_SPEC, &spec_file) as usize;
+            findings_reported += 1;
+            if let Some(reason) = stop_reason(options.fail_fast, options.max_errors, findings_reported) {
+                reporter.report_stopped_early(&reason)?;
+                break;
+            }
+        }
+    }
+
+    // Write summary
+    let mut total = Coverage::default();
+    coverage.iter().for_each(|(_, file_coverage)| total += *file_coverage);
+    let below_coverage = options.min_coverage.is_some_and(|min| total.percent() < min);
+    reporter.report_coverage(&coverage, total, options.min_coverage)?;
+    let over_bump = options.max_bump.is_some_and(|max| impact.bump().is_some_and(|bump| bump > max));
+    reporter.report_semver(impact, options.version.as_deref(), options.max_bump)?;
+    reporter.write_summary(files_checked, files_with_errors)?;
+    if let Some(baseline) = &baseline {
+        reporter.report_baseline(baseline.suppressed(), baseline.unused())?;
+    }
+
+    // Exit with the code of the most severe failure class
+    let code = if options.no_fail {
+        exit_code::SUCCESS
+    } else if parse_failures > 0 {
+        exit_code::PARSE_FAILURE
+    } else if violations > options.error_budget || below_coverage || over_bump {
+        exit_code::VIOLATIONS
+    } else if files_missing_spec > 0 {
+        exit_code::MISSING_SPEC
+    } else {
+        exit_code::SUCCESS
+    };
+
+    Ok(code)
+}
+
+/// What checking one source file against its spec produced
+enum FileOutcome {
+    ParseFailure(anyhow::Error),
+    /// With how many items the source file has
+    MissingSpec(usize),
+    Compared(Box<comparator::ComparisonResult>),
+}
+
+/// `FileOutcome` in a form that can be cached between runs
+#[derive(Clone, Serialize, Deserialize)]
+enum CachedOutcome {
+    ParseFailure(String),
+    /// Without a spec, with how many items the source file has
+    MissingSpec(usize),
+    Compared(Box<comparator::ComparisonResult>),
+}
+
+/// What the journal remembers per source file
+type Verdict = CachedOutcome;
+
+fn file_outcome(verdict: Verdict) -> FileOutcome {
+    match verdict {
+        CachedOutcome::ParseFailure(message) => FileOutcome::ParseFailure(anyhow::anyhow!(message)),
+        CachedOutcome::MissingSpec(items) => FileOutcome::MissingSpec(items),
+        CachedOutcome::Compared(result) => FileOutcome::Compared(result),
+    }
+}
+
+/// Settings that change outcomes, so cached outcomes are only reused under the same ones
+fn comparison_settings(options: &CheckOptions, annotations: &Annotations) -> String {
+    format!("{:?}\0{}\0{:?}\0{:?}\0{}\0{:016x}\0{}\0{:?}", options.visibility, options.ignored_attributes.join("\0"), options.compare,
+        options.directories, plugin::fingerprint(), annotations.fingerprint(), options.checked_version().unwrap_or_default(),
+        options.expanded.as_ref().map(|expanded| expanded.hash))
+}
+
+/// The journal of last verdicts; disabled with `--no-cache`, for `--staged` where
+/// working tree timestamps say nothing about the index, and for `--scope project`
+/// where a file's verdict depends on more than its own pair
+fn open_journal(options: &CheckOptions, annotations: &Annotations) -> Journal<Verdict> {
+    let path = (options.cache && !options.staged && options.scope == Scope::File).then(|| Path::new(config::CACHE_DIR).join("journal.toml"));
+    Journal::load(path, cache::hash(&[comparison_settings(options, annotations).as_bytes()]), !options.frozen_cache)
+}
+
+/// Find every `#[spec_checked]` item in the source tree, since any of them may claim items
+/// from the spec of the file being checked. Files that don't parse are reported by the check itself.
+fn index_annotations(mappings: &[FileMapping], options: &CheckOptions, cache: &Cache) -> Result<Annotations> {
+    let annotated = parallel::map(mappings, options.jobs, |mapping| -> Result<Vec<(RustItem, SpecAnnotation)>> {
+        let content = read_input(&mapping.rust_file, options.staged)?;
+        if !content.contains(annotations::ATTRIBUTE) {
+            return Ok(Vec::new());
+        }
+        let items = parse_code_items(&content, options.visibility, cache).unwrap_or_default();
+        Ok(annotations::split_annotated(items).0)
+    });
+    let annotated = annotated.into_iter().collect::<Result<Vec<_>>>()?;
+    Ok(Annotations::new(
+        annotated.iter().flatten().map(|(item, annotation)| (item, annotation.clone())),
+        options.visibility,
+        |path| read_input(path, options.staged).ok().map(|content| content.to_string()),
+    ))
+}
+
+/// Spec files with items no source file defines, as entries checked like a source file
+type SpecOnly = Vec<(FileMapping, Verdict)>;
+
+/// The verdict of each source file with the items of `spec_paths` pooled, as for `--scope project`
+/// or a `[[mapping]]` rule, and an entry per spec file whose items none of them defines, reported
+/// as missing in code against the spec file itself. With `headings`, the level-2 headings of the
+/// specs scope their items to modules. `settings` are from the front matter of the first spec.
+fn pool(mappings: &[&FileMapping], spec_paths: &[PathBuf], headings: bool, settings: &SpecSettings, options: &CheckOptions, cache: &Cache) -> Result<(Vec<Verdict>, SpecOnly)> {
+    let visibility = settings.visibility(options.visibility);
+    let parsed = parallel::map(mappings, options.jobs, |mapping| -> Result<Result<Vec<RustItem>>> {
+        let content = read_input(&mapping.rust_file, options.staged)?;
+        Ok(parse_code_items(&content, visibility, cache).map(|items| with_generated(options, &mapping.rust_file, items)))
+    });
+    let mut verdicts = Vec::new();
+    let mut code = Vec::new();
+    for (mapping, items) in mappings.iter().zip(parsed) {
+        match items? {
+            Ok(items) => {
+                code.push((mapping.rust_file.clone(), items));
+                verdicts.push(None);
+            }
+            Err(err) => verdicts.push(Some(CachedOutcome::ParseFailure(format!("{:#}", err)))),
+        }
+    }
+
+    let pool = spec_check::Pool {
+        src: &options.src,
+        spec: &options.spec,
+        spec_files: spec_paths,
+        headings,
+        settings,
+        directories: &options.directories,
+        visibility: options.visibility,
+        ignored_attributes: &options.ignored_attributes,
+        compare: &options.compare,
+        version: options.checked_version(),
+        jobs: options.jobs,
+    };
+    let (results, spec_only) = spec_check::compare_pooled(&pool, code, cache, &|path| read_input(path, options.staged))?;
+    let mut compared = results.into_iter().map(|result| CachedOutcome::Compared(Box::new(result)));
+    let verdicts = verdicts.into_iter().map(|verdict| verdict.unwrap_or_else(|| compared.next().expect("a verdict per parsed file"))).collect();
+    let spec_only = spec_only.into_iter()
+        .map(|(spec_file, result)| (FileMapping { rust_file: spec_file.clone(), spec_file: Some(spec_file), ..Default::default() }, CachedOutcome::Compared(Box::new(result))))
+        .collect();
+    Ok((verdicts, spec_only))
+}
+
+/// A spec file's stamp and content hash, with that of the files it includes, and its items once
+/// some mapping needed them. The content is dropped as soon as it has been parsed.
+struct SpecFile {
+    path: PathBuf,
+    /// None if the spec or a file it includes can't be stamped
+    stamp: Option<Stamp>,
+    includes: BTreeMap<PathBuf, Stamp>,
+    hash: u64,
+    content: Mutex<Option<String>>,
+    items: OnceLock<Result<ParsedSpec, String>>,
+}
+
+/// Every spec file of a run, read once and parsed at most once however many source files map to it
+struct SpecFiles {
+    files: HashMap<PathBuf, SpecFile>,
+}
+
+impl SpecFiles {
+    fn read<'a>(mappings: impl Iterator<Item = &'a FileMapping>, options: &CheckOptions) -> Result<Self> {
+        let mut paths: Vec<&PathBuf> = mappings.filter_map(|m| m.spec_file.as_ref()).collect();
+        paths.sort();
+        paths.dedup();
+
+        // Stamp before reading, so a change during the run is seen next time
+        let contents = parallel::map(&paths, options.jobs, |path| {
+            let stamp = Stamp::of(path);
+            read_input(path, options.staged).map(|content| (stamp, content))
+        });
+        let mut files = HashMap::new();
+        for (path, content) in paths.into_iter().zip(contents) {
+            let (mut stamp, content) = content?;
+            let mut includes = BTreeMap::new();
+            for include in spec_check::spec_includes(&content, path) {
+                if let Some(include_stamp) = Stamp::of(&include) {
+                    includes.insert(include, include_stamp);
+                } else {
+                    stamp = None;
+                }
+            }
+            let hash = spec_check::spec_hash(&content, path);
+            files.insert(path.clone(), SpecFile { path: path.clone(), stamp, includes, hash, content: Mutex::new(Some(content)), items: OnceLock::new() });
+        }
+        Ok(Self { files })
+    }
+
+    fn get(&self, path: &Path) -> Result<&SpecFile> {
+        self.files.get(path).with_context(|| format!("Spec file {} was not read", path.display()))
+    }
+}
+
+impl SpecFile {
+    fn is_parsed(&self) -> bool {
+        self.items.get().is_some()
+    }
+
+    fn items(&self, visibility: Visibility, cache: &Cache) -> Result<ParsedSpec> {
+        let items = self.items.get_or_init(|| {
+            let content = self.content.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take().unwrap_or_default();
+            spec_check::parse_spec_cached(&content, &self.path, self.hash, visibility, cache).map_err(|err| format!("{:#}", err))
+        });
+        items.clone().map_err(|message| anyhow::anyhow!(message))
+    }
+}
+
+/// A spec's items, either at hand or still being parsed alongside the source file
+enum SpecItems<'scope> {
+    Ready(Result<ParsedSpec>),
+    Parsing(thread::ScopedJoinHandle<'scope, Result<ParsedSpec>>),
+}
+
+impl SpecItems<'_> {
+    fn join(self) -> Result<ParsedSpec> {
+        match self {
+            SpecItems::Ready(items) => items,
+            SpecItems::Parsing(handle) => handle.join().expect("spec parsing thread panicked"),
+        }
+    }
+}
+
+fn check_file(
+    mapping: &FileMapping,
+    options: &CheckOptions,
+    cache: &Cache,
+    specs: &SpecFiles,
+    annotations: &Annotations,
+    journal: &Journal<Verdict>,
+) -> Result<FileOutcome> {
+    let stamp = Stamp::of(&mapping.rust_file);
+    let rust_content = read_input(&mapping.rust_file, options.staged)?;
+    let spec = match &mapping.spec_file {
+        Some(spec_file) => Some((spec_file, specs.get(spec_file)?)),
+        None => None,
+    };
+
+    // Touched but unchanged files keep their verdict
+    let inputs = Inputs {
+        source: stamp,
+        source_hash: format!("{:016x}", cache::hash(&[rust_content.as_bytes()])),
+        spec: spec.and_then(|(_, spec)| spec.stamp),
+        spec_hash: spec.map(|(_, spec)| format!("{:016x}", spec.hash)),
+        includes: spec.map(|(_, spec)| spec.includes.clone()).unwrap_or_default(),
+    };
+    if let Some(verdict) = journal.same_content(&mapping.rust_file, &inputs) {
+        return Ok(file_outcome(verdict));
+    }
+
+    // Everything that affects the outcome: the source, the spec (by hash), and the comparison settings
+    let key = cache::hash(&[
+        rust_content.as_bytes(),
+        &spec.map_or([0; 8], |(_, spec)| spec.hash.to_le_bytes()),
+        comparison_settings(options, annotations).as_bytes(),
+    ]);
+    // Front matter settings are part of the spec's content, so the key covers them
+    let visibility = mapping.settings.visibility(options.visibility);
+    let (ignored, compare) = file_comparison(options, &mapping.rust_file, &mapping.settings);
+
+    let outcome = cache.get_or_insert_with("outcomes", key, || {
+        // The code and spec are independent, so parse the spec on a second thread meanwhile
+        let (code_items, spec_items) = thread::scope(|scope| {
+            let spec_items = spec.map(|(_, spec)| {
+                if spec.is_parsed() {
+                    // Another source file mapped to this spec already parsed it
+                    SpecItems::Ready(spec.items(visibility, cache))
+                } else {
+                    SpecItems::Parsing(scope.spawn(move || spec.items(visibility, cache)))
+                }
+            });
+            let code_items = parse_code_items(&rust_content, visibility, cache).map(|items| with_generated(options, &mapping.rust_file, items));
+            (code_items, spec_items.map(SpecItems::join))
+        });
+
+        let code_items = match code_items {
+            Ok(items) => items,
+            Err(err) => return Ok(CachedOutcome::ParseFailure(format!("{:#}", err))),
+        };
+        let Some(spec_items) = spec_items else {
+            return Ok(CachedOutcome::MissingSpec(code_items.len()));
+        };
+        let (spec_items, spec_parse_errors) = spec_items?;
+        let comparison = spec_check::FileComparison {
+            rust_file: &mapping.rust_file,
+            spec_file: mapping.spec_file.as_deref(),
+            ignored_attributes: &ignored,
+            compare: &compare,
+            version: options.checked_version(),
+            annotations: (!annotations.is_empty()).then_some(annotations),
+        };
+        let result = comparator::ComparisonResult { spec_parse_errors, ..spec_check::compare_file(&comparison, code_items, spec_items) };
+        Ok(CachedOutcome::Compared(Box::new(result)))
+    })?;
+
+    journal.record(&mapping.rust_file, inputs, outcome.clone());
+    Ok(file_outcome(outcome))
+}
+
+/// Compare a source file's content with its spec's, as `check_file` does, for content that may
+/// not be saved yet. Annotated items are left out, as they are checked against other specs.
+pub fn compare_contents(
+    options: &CheckOptions,
+    rust_file: &Path,
+    spec_file: &Path,
+    rust_content: &str,
+    spec_content: &str,
+) -> Result<comparator::ComparisonResult> {
+    let settings = SpecSettings::from_markdown(spec_content).with_context(|| format!("Invalid front matter in {}", spec_file.display()))?;
+    let visibility = settings.visibility(options.visibility);
+    let (ignored, compare) = file_comparison(options, rust_file, &settings);
+    let code_items = rust_parser::parse_rust_file(rust_content, visibility)?;
+    let (spec_items, spec_parse_errors) = spec_check::parse_spec_file(spec_content, spec_file, visibility);
+    let code_items = if options.spec_annotations { annotations::split_annotated(code_items).1 } else { code_items };
+    let comparison = spec_check::FileComparison {
+        rust_file,
+        spec_file: Some(spec_file),
+        ignored_attributes: &ignored,
+        compare: &compare,
+        version: options.checked_version(),
+        annotations: None,
+    };
+    Ok(comparator::ComparisonResult { spec_parse_errors, ..spec_check::compare_file(&comparison, code_items, spec_items) })
+}
+
+/// The attributes left out of comparing a source file with its spec, and how the rest are
+/// compared: as configured, overridden by the `.spec-check.toml` files of the source file's
+/// subtree, with the spec's front matter on top
+fn file_comparison(options: &CheckOptions, rust_file: &Path, settings: &SpecSettings) -> (Vec<String>, CompareOptions) {
+    spec_check::file_settings(rust_file, settings, &options.directories, &options.ignored_attributes, &options.compare)
+}
+
+/// A source file's items, and with `--expand` the ones the macros of the library crate's
+/// modules generate in it
+fn with_generated(options: &CheckOptions, rust_file: &Path, items: Vec<RustItem>) -> Vec<RustItem> {
+    let Some(expanded) = &options.expanded else { return items };
+    let Ok(relative) = rust_file.strip_prefix(&options.src) else { return items };
+    // Binaries are crates of their own
+    if relative == Path::new("main.rs") || relative.starts_with("bin") {
+        return items;
+    }
+    expanded.with_generated(&rustdoc_json::module_path(relative), items)
+}
+
+fn parse_code_items(content: &str, visibility: Visibility, cache: &Cache) -> Result<Vec<RustItem>> {
+    let key = cache::hash(&[b"code", content.as_bytes(), &[visibility as u8]]);
+    cache.get_or_insert_with("code-items", key, || rust_parser::parse_rust_file(content, visibility))
+}
+
+/// Read a file from the working tree, or from the git index with `--staged`
+fn read_input(path: &Path, staged: bool) -> Result<String> {
+    if staged {
+        git::show_staged_file(path).with_context(|| format!("Failed to read staged {}", path.display()))
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+/// Parse all Rust blocks from a spec file into items
+pub fn load_spec_items(spec_file: &Path, visibility: Visibility) -> Result<Vec<RustItem>> {
+    let spec_content = fs::read_to_string(spec_file)
+        .with_context(|| format!("Failed to read {}", spec_file.display()))?;
+    Ok(spec_check::parse_spec_file(&spec_content, spec_file, visibility).0)
+}
+
+/// A spec's items and the blocks of it that don't parse
+type ParsedSpec = (Vec<RustItem>, Vec<comparator::SpecParseError>);
+
+/// The mappings whose source file, or spec file at the conventional path or by its `[[mapping]]`
+/// rule, is among the `changed` files, with the rest of the rule's source files
+fn only_changed(mappings: Vec<FileMapping>, options: &CheckOptions, changed: HashSet<PathBuf>) -> Vec<FileMapping> {
+    let touched = |mapping: &FileMapping| {
+        // A deleted spec leaves no spec file to compare, but the source file is affected all the same
+        let spec = file_walker::mirrored_spec_path(&mapping.rust_file, &options.src, &options.spec, &options.filter).ok();
+        changed.contains(&mapping.rust_file) || spec.is_some_and(|spec| changed.contains(&spec))
+            || mapping.mapped_specs.iter().flatten().any(|spec| changed.contains(spec))
+    };
+    // A `[[mapping]]` rule's source files are checked together, so all of them or none
+    let touched_groups: Vec<&[PathBuf]> = file_walker::mapped_groups(&mappings).into_iter()
+        .filter(|(_, members)| members.iter().any(|&index| touched(&mappings[index])))
+        .map(|(specs, _)| specs)
+        .collect();
+    let keep: Vec<bool> = mappings.iter()
+        .map(|mapping| touched(mapping) || mapping.mapped_specs.as_deref().is_some_and(|specs| touched_groups.contains(&specs)))
+        .collect();
+    mappings.into_iter().zip(keep).filter_map(|(mapping, keep)| keep.then_some(mapping)).collect()
+}
+
+/// Decide whether to stop after a file with errors, given --fail-fast and --max-errors
+fn stop_reason(fail_fast: bool, max_errors: Option<usize>, findings_reported: usize) -> Option<String> {
+    if fail_fast {
+        return Some("first file with errors reached (--fail-fast)".to_string());
+    }
+    match max_errors {
+        Some(max) if findings_reported >= max => {
+            Some(format!("{} findings reported (--max-errors {})", findings_reported, max))
+        }
+        _ => None,
+    }
+}
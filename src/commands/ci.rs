@@ -0,0 +1,119 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::{self, Config};
+use spec_check::reporter::Finding;
+use spec_check::sarif;
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct CiArgs {
+    /// Annotation format (detected from the environment by default)
+    #[arg(long, value_enum)]
+    pub provider: Option<Provider>,
+
+    /// Where to write the SARIF report
+    #[arg(long, value_name = "PATH")]
+    pub sarif: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// CI systems whose log annotations we know how to emit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Provider {
+    /// GitHub Actions workflow commands (`::error file=...::`)
+    Github,
+    /// Azure Pipelines logging commands (`##vso[task.logissue ...]`)
+    Azure,
+    /// Compiler-style `file:line: error[SC001]: ...` lines (GitLab, Jenkins, local runs)
+    Plain,
+}
+
+impl Provider {
+    /// Detect the CI system from the variables it sets for every job
+    pub fn detect() -> Self {
+        let set = |name: &str| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+        if set("GITHUB_ACTIONS") {
+            Provider::Github
+        } else if set("TF_BUILD") {
+            Provider::Azure
+        } else {
+            Provider::Plain
+        }
+    }
+}
+
+pub fn run(args: CiArgs) -> Result<u8> {
+    let provider = args.provider.unwrap_or_else(Provider::detect);
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let sarif_path = args.sarif.unwrap_or_else(|| Path::new(config::CACHE_DIR).join("spec-check.sarif"));
+    if let Some(parent) = sarif_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut run = check::run_with(&options)?;
+    check::post_webhook(&options, &run);
+    run.findings.sort_by(|a, b| (&a.file, a.line, a.rule, &a.message).cmp(&(&b.file, b.line, b.rule, &b.message)));
+
+    for finding in &run.findings {
+        println!("{}", annotation(provider, finding));
+    }
+    sarif::write(&sarif_path, &run.findings)?;
+    println!("spec-check: {} finding(s); SARIF report written to {}", run.findings.len(), sarif_path.display());
+
+    Ok(run.exit_code)
+}
+
+fn annotation(provider: Provider, finding: &Finding) -> String {
+    let file = finding.file.display().to_string();
+    let level = sarif::level(finding.rule);
+    match provider {
+        Provider::Github => {
+            let line = finding.line.map(|l| format!(",line={}", l)).unwrap_or_default()
+                + &finding.column.map(|c| format!(",col={}", c)).unwrap_or_default();
+            format!("::{} file={}{},title={}::{}", level, escape_github(&file, true), line, finding.rule,
+                escape_github(&finding.message, false))
+        }
+        Provider::Azure => {
+            let line = finding.line.map(|l| format!(";linenumber={}", l)).unwrap_or_default()
+                + &finding.column.map(|c| format!(";columnnumber={}", c)).unwrap_or_default();
+            format!("##vso[task.logissue type={};sourcepath={}{};code={}]{}", level, escape_azure(&file), line,
+                finding.rule, escape_azure(&finding.message))
+        }
+        Provider::Plain => {
+            let line = finding.line.map(|l| format!(":{}", l)).unwrap_or_default()
+                + &finding.column.map(|c| format!(":{}", c)).unwrap_or_default();
+            format!("{}{}: {}[{}]: {}", file, line, level, finding.rule, finding.message)
+        }
+    }
+}
+
+/// Escape per GitHub's workflow command rules; property values also escape `:` and `,`
+fn escape_github(value: &str, property: bool) -> String {
+    let escaped = value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    if property { escaped.replace(':', "%3A").replace(',', "%2C") } else { escaped }
+}
+
+fn escape_azure(value: &str) -> String {
+    value.replace('%', "%AZP25").replace(';', "%3B").replace(']', "%5D").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec_check::rules;
+
+    #[test]
+    fn test_annotation_formats() {
+        let finding = Finding::new(rules::SIGNATURE_MISMATCH, Path::new("src/lib.rs"), Some(3), "fn a(x: u8) differs".to_string());
+        assert_eq!(annotation(Provider::Github, &finding), "::error file=src/lib.rs,line=3,title=SC003::fn a(x: u8) differs");
+        assert_eq!(annotation(Provider::Azure, &finding), "##vso[task.logissue type=error;sourcepath=src/lib.rs;linenumber=3;code=SC003]fn a(x: u8) differs");
+        assert_eq!(annotation(Provider::Plain, &finding), "src/lib.rs:3: error[SC003]: fn a(x: u8) differs");
+    }
+}
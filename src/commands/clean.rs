@@ -0,0 +1,55 @@
+use spec_check::config::{self, Config};
+use spec_check::reporter;
+use crate::exit_code;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CleanTarget {
+    /// The cache directory (target/spec-check)
+    Cache,
+    /// The log file and its rotated copies
+    Logs,
+    /// Everything spec-check has written
+    All,
+}
+
+pub fn run(what: CleanTarget, log: Option<PathBuf>) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+
+    if matches!(what, CleanTarget::Cache | CleanTarget::All) {
+        remove(Path::new(config::CACHE_DIR))?;
+    }
+
+    if matches!(what, CleanTarget::Logs | CleanTarget::All) {
+        let log = log
+            .or_else(|| config.log_file.as_ref().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(config::DEFAULT_LOG_FILE));
+        remove(&log)?;
+        // The rotated logs are numbered without gaps
+        for index in 1.. {
+            let rotated = reporter::rotated(&log, index);
+            if !rotated.exists() {
+                break;
+            }
+            remove(&rotated)?;
+        }
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+fn remove(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    } else if path.exists() {
+        fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    } else {
+        return Ok(());
+    }
+    println!("Removed {}", path.display());
+    Ok(())
+}
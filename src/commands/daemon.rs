@@ -0,0 +1,161 @@
+use spec_check::cache::Cache;
+use crate::commands::check::{self, CheckArgs, CheckOptions};
+use spec_check::reporter;
+use spec_check::config::{self, Config};
+use crate::exit_code;
+use spec_check::json;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml::Value;
+
+/// How long a client may take to send its request or read the response
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request line accepted
+const MAX_REQUEST: u64 = 4 * 1024;
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    /// Socket to listen on (defaults to target/spec-check/daemon.sock)
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: DaemonArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    let socket = args.socket.unwrap_or_else(|| Path::new(config::CACHE_DIR).join("daemon.sock"));
+
+    // Parsed items and outcomes stay in memory between requests, backed by the disk cache
+    let cache = Cache::in_memory(check::cache_dir(&options));
+    let cache = if options.frozen_cache { cache.frozen() } else { cache };
+    serve(&socket, &options, &cache)
+}
+
+/// Answer one request: `check` runs a check and returns JSON results
+fn respond(request: &str, options: &CheckOptions, cache: &Cache) -> (String, bool) {
+    match request.trim() {
+        "check" => {
+            let body = match check::run_with_cache(options, cache) {
+                Ok(run) => {
+                    cache.retain_used();
+                    let mut table = toml::Table::new();
+                    table.insert("exit_code".to_string(), Value::Integer(run.exit_code.into()));
+                    table.insert("findings".to_string(), Value::Array(run.findings.iter().map(reporter::finding_json).collect()));
+                    json::to_string_pretty(&Value::Table(table))
+                }
+                Err(err) => {
+                    let mut table = toml::Table::new();
+                    table.insert("error".to_string(), Value::String(format!("{:#}", err)));
+                    json::to_string_pretty(&Value::Table(table))
+                }
+            };
+            (body, false)
+        }
+        "ping" => ("pong\n".to_string(), false),
+        "shutdown" => ("bye\n".to_string(), true),
+        other => (format!("error: unknown request `{}` (expected check, ping or shutdown)\n", other), false),
+    }
+}
+
+#[cfg(unix)]
+fn serve(socket: &Path, options: &CheckOptions, cache: &Cache) -> Result<u8> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            anyhow::bail!("A spec-check daemon is already listening on {}", socket.display());
+        }
+        // Left behind by a daemon that did not shut down cleanly
+        std::fs::remove_file(socket).with_context(|| format!("Failed to remove stale socket {}", socket.display()))?;
+    }
+    if let Some(parent) = socket.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let listener = UnixListener::bind(socket).with_context(|| format!("Failed to listen on {}", socket.display()))?;
+    println!("spec-check daemon listening on {} (send `check`, `ping` or `shutdown`)", socket.display());
+
+    // Requests are answered one at a time; each check already runs files in parallel, and a
+    // client that stalls is dropped after IO_TIMEOUT rather than holding up the others
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        if handle(&stream, options, cache).unwrap_or(false) {
+            break;
+        }
+    }
+
+    std::fs::remove_file(socket).ok();
+    Ok(exit_code::SUCCESS)
+}
+
+/// Read one request line from a client and answer it; true once the daemon should shut down
+#[cfg(unix)]
+fn handle(mut stream: &std::os::unix::net::UnixStream, options: &CheckOptions, cache: &Cache) -> std::io::Result<bool> {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(stream.take(MAX_REQUEST)).read_line(&mut request)?;
+    let (response, shutdown) = respond(&request, options, cache);
+    stream.write_all(response.as_bytes()).ok();
+    Ok(shutdown)
+}
+
+#[cfg(not(unix))]
+fn serve(_socket: &Path, _options: &CheckOptions, _cache: &Cache) -> Result<u8> {
+    anyhow::bail!("`spec-check daemon` needs Unix domain sockets, which this platform does not provide")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    /// Send `request` over a socket pair and return the daemon's answer
+    fn round_trip(request: &str, options: &CheckOptions, cache: &Cache) -> (String, bool) {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        let shutdown = handle(&server, options, cache).unwrap();
+        drop(server);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        (response, shutdown)
+    }
+
+    #[test]
+    fn test_requests_round_trip() {
+        let dir = std::env::temp_dir().join(format!("spec-check-daemon-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("spec")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "pub fn lerp(a: f32, b: f32, t: f32) -> f32 { a }\n").unwrap();
+        std::fs::write(dir.join("spec/lib.md"), "# Lib\n\n```rust\npub fn lerp(a: f32, b: f32, t: f64) -> f32 {}\n```\n").unwrap();
+        let options = CheckArgs {
+            src: Some(dir.join("src")),
+            spec: Some(dir.join("spec")),
+            log: Some(dir.join("spec-check.log")),
+            no_baseline: true,
+            ..Default::default()
+        }.resolve(&Config::default()).unwrap();
+        let cache = Cache::in_memory(None);
+
+        assert_eq!(round_trip("ping\n", &options, &cache), ("pong\n".to_string(), false));
+        let (response, shutdown) = round_trip("check\n", &options, &cache);
+        assert!(!shutdown);
+        let response = json::parse(&response).unwrap();
+        assert_eq!(response["exit_code"].as_integer(), Some(exit_code::VIOLATIONS.into()));
+        assert_eq!(response["findings"].as_array().map(Vec::len), Some(1));
+        assert!(round_trip("status\n", &options, &cache).0.starts_with("error: unknown request `status`"));
+        assert_eq!(round_trip("shutdown\n", &options, &cache), ("bye\n".to_string(), true));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
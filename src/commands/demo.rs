@@ -0,0 +1,100 @@
+use crate::commands::check::{self, CheckArgs, Output, OutputArgs, OutputFormat};
+use crate::exit_code;
+use spec_check::rules;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The shipped example crate plus a small spec tree that drifts from it on purpose
+const DEMO_FILES: &[(&str, &str)] = &[
+    ("src/lib.rs", include_str!("../../example/src/lib.rs")),
+    ("src/math.rs", include_str!("../../example/demo/src/math.rs")),
+    ("spec/lib.md", include_str!("../../example/demo/spec/lib.md")),
+];
+
+/// Log lines that introduce each kind of finding, and the rule they belong to
+const FINDING_MARKERS: &[(&str, &str)] = &[
+    ("Items in code but not in spec:", rules::MISSING_IN_SPEC),
+    ("Items in spec but not in code:", rules::MISSING_IN_CODE),
+    ("Signature mismatches:", rules::SIGNATURE_MISMATCH),
+    ("Attribute mismatches:", rules::ATTRIBUTE_MISMATCH),
+    ("Doc comment mismatches:", rules::DOC_MISMATCH),
+    ("Ordering violations:", rules::ORDERING_VIOLATION),
+    ("Deprecated in code:", rules::DEPRECATED_IN_CODE),
+    ("Naming convention violations:", rules::NAMING_CONVENTION),
+    ("Discriminant mismatches:", rules::DISCRIMINANT_MISMATCH),
+    ("Value mismatches:", rules::VALUE_MISMATCH),
+    ("ABI mismatches:", rules::ABI_MISMATCH),
+    ("Probable renames:", rules::PROBABLE_RENAME),
+    ("Duplicate definitions:", rules::DUPLICATE_DEFINITION),
+    ("Unparseable spec blocks:", rules::SPEC_PARSE_FAILURE),
+    ("WARNING: No spec file found", rules::MISSING_SPEC_FILE),
+    ("ERROR: Failed to parse", rules::PARSE_FAILURE),
+];
+
+pub fn run() -> Result<u8> {
+    let demo_dir = std::env::temp_dir().join(format!("spec-check-demo-{}", std::process::id()));
+    write_demo_tree(&demo_dir)?;
+
+    let log = run_in_dir(&demo_dir);
+    fs::remove_dir_all(&demo_dir).ok();
+    let log = log?;
+
+    println!("spec-check demo");
+    println!("===============");
+    println!();
+    println!("Checked the bundled example crate (src/lib.rs, src/math.rs) against a demo");
+    println!("spec tree (spec/lib.md) that drifts from it on purpose. This is the log that");
+    println!("a normal run writes, with each finding type explained inline:");
+    println!();
+
+    for line in log.lines() {
+        println!("{}", line);
+        if let Some((_, code)) = FINDING_MARKERS.iter().find(|(marker, _)| line.trim_start().starts_with(marker)) {
+            let rule = rules::find(code).expect("demo markers reference known rules");
+            let indent = " ".repeat(line.len() - line.trim_start().len());
+            println!("{}  >> {} {}: {}", indent, rule.code, rule.name, rule.summary);
+        }
+    }
+
+    println!();
+    println!("Run `spec-check explain <code>` for the rationale behind any finding, and");
+    println!("`spec-check --src <dir> --spec <dir>` to check your own project.");
+
+    // The demo "fails" by design; that is not an error for the demo itself
+    Ok(exit_code::SUCCESS)
+}
+
+fn write_demo_tree(demo_dir: &Path) -> Result<()> {
+    for (relative, content) in DEMO_FILES {
+        let path = demo_dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Run a check inside the demo tree so paths in the log are short and the
+/// user's own Cargo.toml configuration does not leak into the demo
+fn run_in_dir(demo_dir: &Path) -> Result<String> {
+    let original_dir = std::env::current_dir()?;
+    std::env::set_current_dir(demo_dir)?;
+
+    let log = PathBuf::from("spec-check.log");
+    let args = CheckArgs {
+        src: Some(PathBuf::from("src")),
+        spec: Some(PathBuf::from("spec")),
+        log: Some(log.clone()),
+        ..Default::default()
+    };
+    // The demo walks through the log itself, so nothing is printed
+    let output = OutputArgs { format: vec![Output { format: OutputFormat::Log, path: None }], ..Default::default() };
+    let result = check::run(args, output).and_then(|_| Ok(fs::read_to_string(&log)?));
+
+    std::env::set_current_dir(original_dir)?;
+    result
+}
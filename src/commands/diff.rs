@@ -0,0 +1,40 @@
+use crate::commands::changelog;
+use crate::commands::check::CheckArgs;
+use crate::commands::snapshot::current_items;
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::snapshot;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Snapshot written by `spec-check snapshot` to compare the code with
+    #[arg(long, value_name = "PATH")]
+    pub against: PathBuf,
+
+    /// Write the changes to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: DiffArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let old_items = snapshot::load(&args.against)?;
+    let new_items = current_items(args.check)?;
+    // Differences the check leaves out, like function bodies, aren't API changes either
+    let changes = changelog::diff_revisions(old_items, new_items, &config.get_ignored_attributes(), Some(&config.compare_options()));
+
+    let markdown = changelog::render_markdown(&args.against.display().to_string(), "the working tree", &changes);
+    match &args.output {
+        Some(path) => fs::write(path, markdown).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", markdown),
+    }
+    Ok(exit_code::SUCCESS)
+}
@@ -0,0 +1,212 @@
+use crate::commands::check::{CheckArgs, CheckOptions};
+use spec_check::config::{self, Config, Visibility};
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::rust_parser;
+use spec_check::spec_format;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// A setup problem and how to fix it
+struct Diagnosis {
+    problem: String,
+    fix: String,
+}
+
+/// Attribute names that ignore patterns commonly truncate or collide with
+const COMMON_ATTRIBUTES: &[&str] = &["derive", "repr", "cfg", "cfg_attr", "non_exhaustive", "must_use", "deprecated", "serde"];
+
+pub fn run(args: CheckArgs) -> Result<u8> {
+    let cli_overrides = cli_overrides(&args);
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.resolve(&config)?;
+
+    let mut diagnoses = Vec::new();
+    diagnose_config(&config, &cli_overrides, &mut diagnoses)?;
+    diagnose_ignored_attributes(&options, &mut diagnoses);
+
+    let dirs_exist = diagnose_directories(&options, &mut diagnoses);
+    if dirs_exist {
+        diagnose_mappings(&options, &mut diagnoses)?;
+        diagnose_spec_files(&options, &mut diagnoses)?;
+    }
+
+    println!("spec-check doctor");
+    println!("  src:  {}", options.src.display());
+    println!("  spec: {}", options.spec.display());
+    println!();
+
+    if diagnoses.is_empty() {
+        println!("No problems found.");
+        return Ok(exit_code::SUCCESS);
+    }
+
+    for diagnosis in &diagnoses {
+        println!("problem: {}", diagnosis.problem);
+        println!("    fix: {}", diagnosis.fix);
+    }
+    println!();
+    println!("{} problem(s) found.", diagnoses.len());
+
+    Ok(exit_code::VIOLATIONS)
+}
+
+/// A config value given on the command line, which shadows Cargo.toml
+struct CliOverride {
+    flag: &'static str,
+    key: &'static str,
+    value: toml::Value,
+}
+
+fn cli_overrides(args: &CheckArgs) -> Vec<CliOverride> {
+    let path = |p: &Option<std::path::PathBuf>| p.as_ref().map(|p| toml::Value::String(p.display().to_string()));
+    [
+        ("--src", "src-dir", path(&args.src)),
+        ("--spec", "spec-dir", path(&args.spec)),
+        ("--log", "log-file", path(&args.log)),
+        ("--check-private", "check-private", args.check_private.map(toml::Value::Boolean)),
+        ("--check-orphaned-specs", "check-orphaned-specs", args.check_orphaned_specs.map(toml::Value::Boolean)),
+        ("--visibility", "visibility", args.visibility.map(|visibility| toml::Value::String(visibility.name().to_string()))),
+    ]
+    .into_iter()
+    .filter_map(|(flag, key, value)| Some(CliOverride { flag, key, value: value? }))
+    .collect()
+}
+
+fn diagnose_config(config: &Config, cli_overrides: &[CliOverride], diagnoses: &mut Vec<Diagnosis>) -> Result<()> {
+    let Some(metadata) = config::load_raw_metadata()? else {
+        return Ok(());
+    };
+
+    for key in metadata.keys() {
+        if !config::KNOWN_KEYS.contains(&key.as_str()) {
+            let suggestion = config::KNOWN_KEYS.iter()
+                .find(|known| known.contains(key.as_str()) || key.contains(*known))
+                .map(|known| format!("did you mean `{}`? ", known))
+                .unwrap_or_default();
+            diagnoses.push(Diagnosis {
+                problem: format!("Unknown key `{}` in [package.metadata.spec-check] is ignored", key),
+                fix: format!("{}known keys are: {}", suggestion, config::KNOWN_KEYS.join(", ")),
+            });
+        }
+    }
+
+    for over in cli_overrides {
+        if let Some(configured) = metadata.get(over.key)
+            && *configured != over.value
+        {
+            diagnoses.push(Diagnosis {
+                problem: format!("{} {} conflicts with `{} = {}` in Cargo.toml", over.flag, over.value, over.key, configured),
+                fix: format!("drop {} or update `{}` so local and CI runs agree", over.flag, over.key),
+            });
+        }
+    }
+
+    if config.ignored_attributes.as_ref().is_some_and(|attrs| !attrs.iter().any(|a| a == "doc")) {
+        diagnoses.push(Diagnosis {
+            problem: "`ignored-attributes` does not include \"doc\", so doc comments must match exactly".to_string(),
+            fix: "add \"doc\" to `ignored-attributes` unless doc comments are part of the contract".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn diagnose_ignored_attributes(options: &CheckOptions, diagnoses: &mut Vec<Diagnosis>) {
+    // Ignore patterns match any attribute that contains them, so short or partial names go wide
+    for pattern in &options.ignored_attributes {
+        let swallowed: Vec<&str> = COMMON_ATTRIBUTES.iter()
+            .copied()
+            .filter(|attr| *attr != pattern && attr.contains(pattern.as_str()))
+            .collect();
+
+        if pattern.contains('#') || pattern.contains('[') || pattern.contains(' ') {
+            diagnoses.push(Diagnosis {
+                problem: format!("Ignored attribute pattern `{}` contains attribute syntax", pattern),
+                fix: "list bare attribute names, e.g. \"serde\" instead of \"#[serde(...)]\"".to_string(),
+            });
+        } else if !swallowed.is_empty() {
+            diagnoses.push(Diagnosis {
+                problem: format!("Ignored attribute pattern `{}` also matches {}", pattern, swallowed.join(", ")),
+                fix: "use the full attribute name so unrelated attributes are still compared".to_string(),
+            });
+        }
+    }
+}
+
+fn diagnose_directories(options: &CheckOptions, diagnoses: &mut Vec<Diagnosis>) -> bool {
+    let mut ok = true;
+    for (dir, key, flag) in [(&options.src, "src-dir", "--src"), (&options.spec, "spec-dir", "--spec")] {
+        if !dir.is_dir() {
+            ok = false;
+            diagnoses.push(Diagnosis {
+                problem: format!("Directory {} does not exist", dir.display()),
+                fix: format!("create it, or point `{}` in Cargo.toml (or {}) at the right directory", key, flag),
+            });
+        }
+    }
+    ok
+}
+
+fn diagnose_mappings(options: &CheckOptions, diagnoses: &mut Vec<Diagnosis>) -> Result<()> {
+    for mapping in file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        if mapping.spec_file.is_some() {
+            continue;
+        }
+        let fix = match mapping.mapped_specs {
+            Some(_) => "create a spec file for it that its `[[mapping]]` rule's `spec` glob matches".to_string(),
+            None => format!("create {} describing its public items", file_walker::mirrored_spec_path(&mapping.rust_file, &options.src, &options.spec, &options.filter)?.display()),
+        };
+        diagnoses.push(Diagnosis { problem: format!("{} has no spec file", mapping.rust_file.display()), fix });
+    }
+    Ok(())
+}
+
+fn diagnose_spec_files(options: &CheckOptions, diagnoses: &mut Vec<Diagnosis>) -> Result<()> {
+    for spec_file in file_walker::find_orphaned_specs(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        diagnoses.push(Diagnosis {
+            problem: format!("{} has no matching source file under {}", spec_file.display(), options.src.display()),
+            fix: "move or rename the spec to mirror the source tree (see `spec-check migrate`), or delete it".to_string(),
+        });
+    }
+
+    for entry in WalkDir::new(&options.spec)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| spec_format::is_spec_file(e.path()))
+    {
+        diagnose_spec_blocks(entry.path(), options.visibility, diagnoses)?;
+    }
+    Ok(())
+}
+
+fn diagnose_spec_blocks(spec_file: &Path, visibility: Visibility, diagnoses: &mut Vec<Diagnosis>) -> Result<()> {
+    let content = fs::read_to_string(spec_file)
+        .with_context(|| format!("Failed to read {}", spec_file.display()))?;
+    let blocks: Vec<_> = spec_format::of(spec_file).rust_blocks(&content).collect();
+
+    if blocks.is_empty() {
+        diagnoses.push(Diagnosis {
+            problem: format!("{} contains no ```rust code blocks, so nothing in it is checked", spec_file.display()),
+            fix: "fence item definitions with ```rust (other languages and indented blocks are skipped)".to_string(),
+        });
+    }
+
+    for (index, block) in blocks.iter().enumerate() {
+        if let Some(tag) = &block.unknown_tag {
+            diagnoses.push(Diagnosis {
+                problem: format!("Rust block {} in {} has the unknown tag `{}`, so its items are unchecked", index + 1, spec_file.display(), tag),
+                fix: "fix the tag's spelling (the stages are planned, stable, deprecated and forbidden), or tag the block `ignore`".to_string(),
+            });
+        } else if let Err(err) = rust_parser::parse_spec_block(&block.code, 1, visibility) {
+            diagnoses.push(Diagnosis {
+                problem: format!("Rust block {} in {} does not parse ({}), so its items are unchecked", index + 1, spec_file.display(), err),
+                fix: "make the block valid Rust item syntax, or fence it as something other than rust".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::exit_code;
+use spec_check::plugin;
+use spec_check::rules::{self, Rule};
+use anyhow::Result;
+
+pub fn run(code: Option<&str>) -> Result<u8> {
+    let Some(code) = code else {
+        for rule in rules::RULES {
+            println!("{}  {:<20} {}", rule.code, rule.name, rule.summary);
+        }
+        for rule in plugin::registered() {
+            println!("{}  {:<20} {} (custom)", rule.code(), rule.name(), rule.summary());
+        }
+        return Ok(exit_code::SUCCESS);
+    };
+
+    if let Some(rule) = plugin::find(code) {
+        println!("{} {}\n{}\n\nCustom rule compiled into this build of spec-check.", rule.code(), rule.name(), rule.summary());
+        return Ok(exit_code::SUCCESS);
+    }
+    let Some(rule) = rules::find(code) else {
+        anyhow::bail!("Unknown rule: {} (run `spec-check explain` to list all rules)", code);
+    };
+
+    print!("{}", format_rule(rule));
+    Ok(exit_code::SUCCESS)
+}
+
+fn format_rule(rule: &Rule) -> String {
+    let mut out = format!("{} {}\n{}\n\n", rule.code, rule.name, rule.summary);
+
+    out.push_str("Rationale:\n");
+    out.push_str(&format!("  {}\n\n", rule.rationale));
+
+    out.push_str("Violating:\n");
+    out.push_str(&format_example("code", rule.violating.code));
+    out.push_str(&format_example("spec", rule.violating.spec));
+    out.push('\n');
+
+    out.push_str("Conforming:\n");
+    out.push_str(&format_example("code", rule.conforming.code));
+    out.push_str(&format_example("spec", rule.conforming.spec));
+    out.push('\n');
+
+    out.push_str("Fixing:\n");
+    out.push_str(&format_remedies(rule));
+    out.push('\n');
+
+    out.push_str("Config keys:\n");
+    if rule.config_keys.is_empty() {
+        out.push_str("  none\n");
+    }
+    for key in rule.config_keys {
+        out.push_str(&format!("  {}\n", key));
+    }
+    out
+}
+
+/// The ways out of a finding of `rule`: agreeing, accepting it, or checking it less strictly
+fn format_remedies(rule: &Rule) -> String {
+    let mut out = String::from("  Change the code or the spec so they agree.\n");
+    if matches!(rule.code, rules::SIGNATURE_MISMATCH | rules::ATTRIBUTE_MISMATCH | rules::DISCRIMINANT_MISMATCH) {
+        out.push_str("  `spec-check --fix` rewrites the spec item as the code declares it, or the code item with source-of-truth = \"spec\".\n");
+    }
+    out.push_str("  Accept the current findings with `spec-check --write-baseline spec-check-baseline.json`; only new ones fail.\n");
+    out.push_str(&format!("  Report it as a warning with severity = {{ {} = \"warning\" }}, or turn it off with \"off\" or `--allow {}`.\n", rule.name, rule.code));
+    if !rule.config_keys.is_empty() {
+        out.push_str("  Or change how it's checked with the config keys below.\n");
+    }
+    out
+}
+
+fn format_example(label: &str, text: &str) -> String {
+    let mut out = format!("  {}:\n", label);
+    for line in text.lines() {
+        out.push_str(&format!("    {}\n", line));
+    }
+    out
+}
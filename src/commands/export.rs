@@ -0,0 +1,106 @@
+use crate::commands::check::{self, CheckArgs, CheckOptions};
+use spec_check::config::{self, Config, Visibility};
+use crate::exit_code;
+use spec_check::rust_parser;
+use spec_check::spec_format;
+use spec_check::rustdoc_json::{self, Module};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use std::fs;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::RustdocJson)]
+    pub format: Format,
+
+    /// Export the items of the source tree or of the spec tree
+    #[arg(long, value_enum, default_value_t = Side::Code)]
+    pub from: Side,
+
+    /// Write the export to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// rustdoc's JSON layout (`root`, `index`, `paths`) with string signatures
+    RustdocJson,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Side {
+    Code,
+    Spec,
+}
+
+pub fn run(args: ExportArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    let package = config::load_package_info()?;
+
+    let modules = collect_modules(&options, args.from)?;
+
+    let export = match args.format {
+        Format::RustdocJson => rustdoc_json::to_string(
+            &crate_name(package.as_ref()),
+            package.as_ref().map(|p| p.version.as_str()),
+            &modules,
+            options.visibility != Visibility::Pub,
+        ),
+    };
+    match &args.output {
+        Some(path) => fs::write(path, export).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", export),
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// The items of every file in the source or spec tree, by module
+pub fn collect_modules(options: &CheckOptions, side: Side) -> Result<Vec<Module>> {
+    let dir = match side {
+        Side::Code => &options.src,
+        Side::Spec => &options.spec,
+    };
+    let mut modules = Vec::new();
+    for entry in WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| match side {
+            Side::Code => e.path().extension().is_some_and(|ext| ext == "rs"),
+            Side::Spec => spec_format::is_spec_file(e.path()),
+        })
+    {
+        let file = entry.path().to_path_buf();
+        let items = match side {
+            Side::Code => {
+                let content = fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+                match rust_parser::parse_rust_file(&content, options.visibility) {
+                    Ok(items) => items,
+                    Err(err) => {
+                        eprintln!("Warning: skipping {}: {}", file.display(), err);
+                        continue;
+                    }
+                }
+            }
+            Side::Spec => check::load_spec_items(&file, options.visibility)?,
+        };
+        let path = rustdoc_json::module_path(file.strip_prefix(dir).unwrap_or(&file));
+        modules.push(Module { file, path, items });
+    }
+    Ok(modules)
+}
+
+/// The crate's name as Rust code spells it, like rustdoc does
+pub fn crate_name(package: Option<&config::PackageInfo>) -> String {
+    package.map_or("crate".to_string(), |p| p.name.replace('-', "_"))
+}
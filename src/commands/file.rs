@@ -0,0 +1,102 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::{Config, Visibility};
+use crate::exit_code;
+use spec_check::json;
+use spec_check::reporter::{self, Finding};
+use spec_check::{rules, terminal};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// What findings name a side read from stdin
+const STDIN: &str = "<stdin>";
+
+#[derive(Args)]
+pub struct FileArgs {
+    /// Source file to check, or `-` to read it from stdin
+    #[arg(long, value_name = "PATH")]
+    pub rust: PathBuf,
+
+    /// Spec file to check it against, or `-` to read it from stdin
+    #[arg(long, value_name = "PATH")]
+    pub spec: PathBuf,
+
+    /// Print the exit code and findings as JSON instead
+    #[arg(long)]
+    pub json: bool,
+
+    /// Check private items in addition to public items
+    #[arg(long)]
+    pub check_private: Option<bool>,
+
+    /// The least visible items to check, in place of `--check-private`
+    #[arg(long, value_enum)]
+    pub visibility: Option<Visibility>,
+
+    /// Attributes to ignore (can be specified multiple times)
+    #[arg(short = 'i', long)]
+    pub ignore_attr: Vec<String>,
+
+    /// Print without colors (also when `NO_COLOR` is set or stdout isn't a terminal)
+    #[arg(long)]
+    pub no_color: bool,
+}
+
+pub fn run(args: FileArgs) -> Result<u8> {
+    if is_stdin(&args.rust) && is_stdin(&args.spec) {
+        anyhow::bail!("Only one of --rust and --spec can be read from stdin");
+    }
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = CheckArgs { check_private: args.check_private, visibility: args.visibility, ignore_attr: args.ignore_attr, ..Default::default() }.resolve(&config)?;
+
+    let (rust_file, rust_content) = read(&args.rust)?;
+    let (spec_file, spec_content) = read(&args.spec)?;
+    let mut findings = match check::compare_contents(&options, &rust_file, &spec_file, &rust_content, &spec_content) {
+        Ok(result) => reporter::findings_for(&rust_file, Some(&spec_file), &result, options.source_of_truth),
+        Err(err) => vec![reporter::parse_failure_finding(&rust_file, &err)],
+    };
+    findings.retain(|finding| !options.disabled(finding.rule, &finding.file));
+
+    let failing: Vec<&Finding> = findings.iter().filter(|finding| options.fails(finding.rule, &finding.file)).collect();
+    let code = match failing.first() {
+        None => exit_code::SUCCESS,
+        Some(finding) if finding.rule == rules::PARSE_FAILURE => exit_code::PARSE_FAILURE,
+        Some(_) => exit_code::VIOLATIONS,
+    };
+
+    if args.json {
+        let mut table = toml::Table::new();
+        table.insert("exit_code".to_string(), Value::Integer(code.into()));
+        table.insert("findings".to_string(), Value::Array(findings.iter().map(reporter::finding_json).collect()));
+        print!("{}", json::to_string_pretty(&Value::Table(table)));
+    } else {
+        let mut printer = terminal::Printer::new(io::stdout().lock(), terminal::use_color(args.no_color), options.severities.clone())
+            .with_source(&rust_file, rust_content)
+            .with_source_of_truth(options.source_of_truth)
+            .with_directories(options.directories.clone());
+        for finding in &findings {
+            printer.finding(finding)?;
+        }
+        printer.finish(1)?;
+    }
+    Ok(code)
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// The name findings use for a side and its content, from stdin for `-`
+fn read(path: &Path) -> Result<(PathBuf, String)> {
+    if !is_stdin(path) {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        return Ok((path.to_path_buf(), content));
+    }
+    let mut content = String::new();
+    io::stdin().read_to_string(&mut content).context("Failed to read stdin")?;
+    Ok((PathBuf::from(STDIN), content))
+}
@@ -0,0 +1,87 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::{self, Config};
+use crate::exit_code;
+use spec_check::file_walker::{self, FileMapping};
+use spec_check::reporter::Finding;
+use spec_check::rules;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct FragmentsArgs {
+    /// Directory for the fragments (commit it if docs.rs should show them)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: FragmentsArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    let output = args.output.unwrap_or_else(|| Path::new(config::CACHE_DIR).join("fragments"));
+
+    let run = check::run_with(&options)?;
+    let mappings = file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)?;
+    for mapping in &mappings {
+        let findings: Vec<&Finding> = run.findings.iter().filter(|f| f.file == mapping.rust_file).collect();
+        let relative = mapping.rust_file.strip_prefix(&options.src).unwrap_or(&mapping.rust_file);
+        let path = output.join(relative).with_extension("md");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, fragment(mapping, &findings))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    println!("Wrote {} spec status fragment(s) to {}", mappings.len(), output.display());
+
+    // The fragments describe the drift; writing them succeeded either way
+    Ok(exit_code::SUCCESS)
+}
+
+/// The status paragraph for one module, in markdown that reads well at the top of its rustdoc page
+fn fragment(mapping: &FileMapping, findings: &[&Finding]) -> String {
+    let version = env!("CARGO_PKG_VERSION");
+    let Some(spec_file) = &mapping.spec_file else {
+        return "**Spec status: no spec.** No spec file describes this module.\n".to_string();
+    };
+    let spec = spec_file.to_string_lossy().replace('\\', "/");
+    if findings.iter().any(|f| f.rule == rules::PARSE_FAILURE) {
+        return format!("**Spec status: not checked.** The module could not be parsed, so it wasn't compared with `{}`.\n", spec);
+    }
+    if findings.is_empty() {
+        return format!("**Spec status: verified.** The module matches `{}` (checked by spec-check {}).\n", spec, version);
+    }
+
+    let mut text = format!(
+        "**Spec status: {} finding(s).** The module doesn't match `{}` (checked by spec-check {}):\n\n",
+        findings.len(), spec, version,
+    );
+    for finding in findings {
+        text.push_str(&format!("- `{}` {}\n", finding.rule, finding.message.replace('\n', " ")));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_per_status() {
+        let mapping = FileMapping { rust_file: PathBuf::from("src/math.rs"), spec_file: Some(PathBuf::from("spec/math.md")), ..Default::default() };
+        assert!(fragment(&mapping, &[]).starts_with("**Spec status: verified.** The module matches `spec/math.md`"));
+
+        let finding = Finding::new(rules::MISSING_IN_SPEC, Path::new("src/math.rs"), Some(3), "struct Color is in the code but not in the spec".to_string());
+        let text = fragment(&mapping, &[&finding]);
+        assert!(text.starts_with("**Spec status: 1 finding(s).**"));
+        assert!(text.ends_with("\n\n- `SC001` struct Color is in the code but not in the spec\n"));
+
+        let unspecced = FileMapping { spec_file: None, ..mapping };
+        assert_eq!(fragment(&unspecced, &[]), "**Spec status: no spec.** No spec file describes this module.\n");
+    }
+}
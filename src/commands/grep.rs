@@ -0,0 +1,180 @@
+use crate::commands::annotate::item_status;
+use crate::commands::check::{self, CheckArgs};
+use spec_check::comparator::{self, ComparisonResult};
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::glob;
+use spec_check::reporter::{format_item, item_path};
+use spec_check::rules;
+use spec_check::rust_parser::{self, RustItem};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::PathBuf;
+
+/// Longest signature excerpt printed per match
+const SIGNATURE_WIDTH: usize = 80;
+
+#[derive(Args)]
+pub struct GrepArgs {
+    /// Name glob (`*Geometry`, `CurveGeometry::*`) or part of a signature (`-> Vec<Vec3>`)
+    #[arg(allow_hyphen_values = true)]
+    pub pattern: String,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// A matching item and where it was found
+struct Match {
+    file: PathBuf,
+    item: RustItem,
+    status: &'static str,
+}
+
+pub fn run(args: GrepArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let mappings = file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)?;
+    // Each source file with its spec, and the source files of a `[[mapping]]` rule with all of its specs
+    let mut units: Vec<(Vec<usize>, Vec<PathBuf>)> = mappings.iter().enumerate()
+        .filter(|(_, mapping)| mapping.mapped_specs.as_ref().is_none_or(Vec::is_empty))
+        .map(|(index, mapping)| (vec![index], mapping.spec_file.iter().cloned().collect()))
+        .collect();
+    units.extend(file_walker::mapped_groups(&mappings).into_iter().map(|(specs, members)| (members, specs.to_vec())));
+    units.sort_by_key(|(members, _)| members[0]);
+
+    let mut matches = Vec::new();
+    for (members, spec_files) in units {
+        let mut code = Vec::new();
+        for index in members {
+            let rust_file = &mappings[index].rust_file;
+            let content = fs::read_to_string(rust_file)
+                .with_context(|| format!("Failed to read {}", rust_file.display()))?;
+            let Ok(items) = rust_parser::parse_rust_file(&content, options.visibility) else { continue };
+            code.push((rust_file.clone(), items));
+        }
+        if code.is_empty() {
+            continue;
+        }
+        let mut spec = Vec::new();
+        for spec_file in spec_files {
+            let items = check::load_spec_items(&spec_file, options.visibility)?;
+            spec.push((spec_file, items));
+        }
+        let all = |files: &[(PathBuf, Vec<RustItem>)]| files.iter().flat_map(|(_, items)| items.iter().cloned()).collect();
+        let result = comparator::compare_items(all(&code), all(&spec), &options.ignored_attributes);
+
+        for (file, items) in code {
+            for item in items.into_iter().filter(|item| item_matches(&args.pattern, item)) {
+                let status = item_status(&item, &result);
+                matches.push(Match { file: file.clone(), item, status });
+            }
+        }
+        for (file, items) in spec {
+            for item in items.into_iter().filter(|item| item_matches(&args.pattern, item)) {
+                let status = spec_item_status(&item, &result);
+                matches.push(Match { file: file.clone(), item, status });
+            }
+        }
+    }
+
+    // Specs without a source file describe items that are missing in code
+    for spec_file in file_walker::find_orphaned_specs(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        for item in check::load_spec_items(&spec_file, options.visibility)? {
+            if item_matches(&args.pattern, &item) {
+                matches.push(Match { file: spec_file.clone(), item, status: rules::MISSING_IN_CODE });
+            }
+        }
+    }
+
+    for found in &matches {
+        println!(
+            "{}:{}: {:<5} {}  {}",
+            found.file.display(),
+            found.item.line_number,
+            found.status,
+            format_item(&found.item),
+            excerpt(&found.item.signature),
+        );
+    }
+
+    // Like grep: exit 1 when nothing matched
+    Ok(if matches.is_empty() { exit_code::VIOLATIONS } else { exit_code::SUCCESS })
+}
+
+/// Match by name glob when the pattern looks like a name, otherwise by signature substring
+fn item_matches(pattern: &str, item: &RustItem) -> bool {
+    let path = item_path(item);
+    if glob::is_pattern(pattern) {
+        return glob::matches(pattern, &path) || glob::matches(pattern, &item.name);
+    }
+    if path == pattern || item.name == pattern {
+        return true;
+    }
+
+    // Signatures are rendered token streams, so compare with whitespace removed
+    let squash = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    squash(&item.signature).contains(&squash(pattern))
+}
+
+fn spec_item_status(item: &RustItem, result: &ComparisonResult) -> &'static str {
+    if result.missing_in_code.contains(item) {
+        rules::MISSING_IN_CODE
+    } else if result.signature_mismatches.iter().any(|m| m.spec_item == *item) {
+        rules::SIGNATURE_MISMATCH
+    } else if result.discriminant_mismatches.iter().any(|m| m.spec_item == *item) {
+        rules::DISCRIMINANT_MISMATCH
+    } else if result.value_mismatches.iter().any(|m| m.spec_item == *item) {
+        rules::VALUE_MISMATCH
+    } else if result.abi_mismatches.iter().any(|m| m.spec_item == *item) {
+        rules::ABI_MISMATCH
+    } else if result.attribute_mismatches.iter().any(|m| m.spec_item == *item) {
+        rules::ATTRIBUTE_MISMATCH
+    } else if result.doc_mismatches.iter().any(|m| m.spec_item == *item) {
+        rules::DOC_MISMATCH
+    } else if result.ordering_violations.iter().any(|v| v.spec_item == *item) {
+        rules::ORDERING_VIOLATION
+    } else if result.deprecated_in_code.iter().any(|d| d.spec_item == *item) {
+        rules::DEPRECATED_IN_CODE
+    } else if result.probable_renames.iter().any(|r| r.spec_item == *item) {
+        rules::PROBABLE_RENAME
+    } else if result.duplicates.iter().any(|d| d.in_spec && d.duplicate == *item && d.duplicate.line_number == item.line_number) {
+        rules::DUPLICATE_DEFINITION
+    } else {
+        "OK"
+    }
+}
+
+fn excerpt(signature: &str) -> String {
+    if signature.chars().count() <= SIGNATURE_WIDTH {
+        return signature.to_string();
+    }
+    let cut: String = signature.chars().take(SIGNATURE_WIDTH - 3).collect();
+    format!("{}...", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec_check::rust_parser::ItemKind;
+
+    fn item(name: &str, kind: ItemKind, signature: &str) -> RustItem {
+        RustItem::new(name.to_string(), kind, signature.to_string(), Default::default(), vec![], 1)
+    }
+
+    #[test]
+    fn test_item_matches_name_glob_or_signature() {
+        let method = item("sample", ItemKind::TraitMethod { trait_name: "CurveGeometry".to_string() },
+            "fn sample (& self , resolution : u32) -> Vec < Vec3 > ;");
+
+        assert!(item_matches("CurveGeometry::*", &method));
+        assert!(item_matches("sample", &method));
+        assert!(item_matches("-> Vec<Vec3>", &method));
+        assert!(!item_matches("Surface*", &method));
+        assert!(!item_matches("-> Vec<f32>", &method));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::{file_walker, line_diff, skeleton, spec_format};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Overwrite spec files that already exist (default is to leave them alone)
+    #[arg(long)]
+    pub force: bool,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: InitArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    if !options.src.exists() {
+        anyhow::bail!("Source directory does not exist: {}", options.src.display());
+    }
+    if options.filter.spec_format != spec_format::Format::Markdown {
+        anyhow::bail!("`init` writes markdown specs, not spec-format = \"{}\"", options.filter.spec_format.name());
+    }
+
+    let (mut written, mut skipped, mut mismatched) = (0, 0, 0);
+    for mapping in file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        // A `[[mapping]]` rule's spec files are for the rule's source files together, so not written per file
+        if mapping.mapped_specs.is_some() {
+            skipped += 1;
+            continue;
+        }
+        let spec_file = file_walker::mirrored_spec_path(&mapping.rust_file, &options.src, &options.spec, &options.filter)?;
+        if mapping.spec_file.is_some() && !args.force {
+            skipped += 1;
+            continue;
+        }
+        let source = fs::read_to_string(&mapping.rust_file)
+            .with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
+        let title = mapping.rust_file.display().to_string();
+        let markdown = match skeleton::to_markdown(&source, &title, options.visibility, &options.ignored_attributes) {
+            Ok(markdown) => markdown,
+            Err(err) => {
+                eprintln!("Skipped {}: failed to parse: {}", mapping.rust_file.display(), err);
+                continue;
+            }
+        };
+
+        // What `check` would make of it, e.g. for items the source marks to be ignored
+        let result = check::compare_contents(&options, &mapping.rust_file, &spec_file, &source, &markdown)?;
+        let items = spec_check::extract_spec_items(&markdown, options.visibility).len();
+
+        let plural = if items == 1 { "" } else { "s" };
+        if options.dry_run {
+            let old = spec_file.exists().then(|| fs::read_to_string(&spec_file)).transpose()
+                .with_context(|| format!("Failed to read {}", spec_file.display()))?;
+            print!("{}", line_diff::unified(&spec_file.display().to_string(), old.as_deref(), &markdown));
+            println!("Would write {} ({} item{})", spec_file.display(), items, plural);
+        } else {
+            if let Some(dir) = spec_file.parent() {
+                fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+            }
+            fs::write(&spec_file, &markdown).with_context(|| format!("Failed to write {}", spec_file.display()))?;
+            println!("Wrote {} ({} item{})", spec_file.display(), items, plural);
+        }
+        written += 1;
+        if result.has_errors() {
+            eprintln!("warning: {} doesn't match {} yet; run `spec-check check` for details", spec_file.display(), mapping.rust_file.display());
+            mismatched += 1;
+        }
+    }
+
+    println!();
+    println!("{} spec file{} {}, {} left as they were{}", written, if written == 1 { "" } else { "s" },
+        if options.dry_run { "would be written" } else { "written" }, skipped,
+        if skipped > 0 && !args.force { " (--force overwrites them)" } else { "" });
+    Ok(if mismatched > 0 { exit_code::VIOLATIONS } else { exit_code::SUCCESS })
+}
@@ -0,0 +1,52 @@
+use crate::exit_code;
+use spec_check::git;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+
+/// Marks a hook as written by `install-hook`, so it can be replaced without `--force`
+const MARKER: &str = "# Installed by spec-check install-hook";
+
+#[derive(Args)]
+pub struct InstallHookArgs {
+    /// Replace a pre-commit hook spec-check didn't write
+    #[arg(long)]
+    pub force: bool,
+
+    /// Options for the hook's `spec-check --staged`, e.g. `--fail-fast`
+    #[arg(last = true)]
+    pub check_args: Vec<String>,
+}
+
+pub fn run(args: InstallHookArgs) -> Result<u8> {
+    let dir = git::hooks_dir().context("Failed to find the git hooks directory; is this a git repository?")?;
+    let path = dir.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&path) && !existing.contains(MARKER) && !args.force {
+        anyhow::bail!("{} already exists; pass --force to replace it", path.display());
+    }
+
+    // Hooks run at the top of the working tree, and the crate may be below it
+    let prefix = git::prefix()?;
+    let cd = if prefix.is_empty() { String::new() } else { format!("cd {} || exit 1\n", shell_quote(prefix.trim_end_matches('/'))) };
+    let check_args: String = args.check_args.iter().map(|arg| format!(" {}", shell_quote(arg))).collect();
+    let hook = format!("#!/bin/sh\n{}\n{}exec spec-check --staged{}\n", MARKER, cd, check_args);
+
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    fs::write(&path, hook).with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).with_context(|| format!("Failed to make {} executable", path.display()))?;
+    }
+    println!("Installed {}", path.display());
+    Ok(exit_code::SUCCESS)
+}
+
+/// `arg` as one word for sh
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
@@ -0,0 +1,431 @@
+use crate::commands::check::{self, CheckArgs, CheckOptions};
+use spec_check::baseline::Baseline;
+use spec_check::config::{Config, Severity};
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::glob;
+use spec_check::json;
+use spec_check::reporter::{self, Finding};
+use spec_check::rules;
+use spec_check::spec_format;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// JSON-RPC error codes the server answers with
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+#[derive(Args)]
+pub struct LspArgs {
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: LspArgs) -> Result<u8> {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut server = Server { args: Some(args.check), project: None, documents: HashMap::new(), shutdown: false };
+    // Stdout carries the protocol, so nothing else may be printed there
+    while let Some(body) = read_message(&mut input)? {
+        let Ok(message) = json::parse(&body) else { continue };
+        if message.get("method").and_then(Value::as_str) == Some("exit") {
+            return Ok(if server.shutdown { exit_code::SUCCESS } else { exit_code::USAGE });
+        }
+        for reply in server.handle(&message) {
+            write!(output, "Content-Length: {}\r\n\r\n{}", reply.len(), reply)?;
+            output.flush()?;
+        }
+    }
+    // The client went away without `exit`
+    Ok(exit_code::USAGE)
+}
+
+/// The next message's body, or `None` at the end of the input
+fn read_message(input: &mut impl BufRead) -> Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') && name.eq_ignore_ascii_case("content-length") {
+            length = Some(value.trim().parse::<usize>().context("Invalid Content-Length header")?);
+        }
+    }
+    let length = length.context("LSP message without a Content-Length header")?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8(body).context("LSP message is not UTF-8")?))
+}
+
+/// The settings and directories checks run with, known once the client has initialized
+struct Project {
+    root: PathBuf,
+    src: PathBuf,
+    spec: PathBuf,
+    options: CheckOptions,
+}
+
+struct Document {
+    uri: String,
+    text: String,
+}
+
+struct Server {
+    /// Resolved into `project` at `initialize`, once the root is known
+    args: Option<CheckArgs>,
+    project: Option<Project>,
+    /// Open documents by path relative to the root, with their unsaved text
+    documents: HashMap<PathBuf, Document>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Answer a message: responses to requests, and diagnostics to publish
+    fn handle(&mut self, message: &Value) -> Vec<String> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // A response to a request of ours; the server sends none
+            return Vec::new();
+        };
+        let id = message.get("id");
+        let params = message.get("params");
+        let text_document = params.and_then(|params| params.get("textDocument"));
+        let uri = text_document.and_then(|document| document.get("uri")).and_then(Value::as_str);
+
+        if method == "initialize" {
+            let reply = match self.initialize(params) {
+                Ok(result) => response(id, Some(result)),
+                Err(err) => error(id, SERVER_NOT_INITIALIZED, &format!("{:#}", err)),
+            };
+            return vec![reply];
+        }
+        if self.project.is_none() {
+            return id.map(|_| error(id, SERVER_NOT_INITIALIZED, "The server has not been initialized")).into_iter().collect();
+        }
+
+        match method {
+            "shutdown" => {
+                self.shutdown = true;
+                vec![response(id, None)]
+            }
+            "textDocument/didOpen" => {
+                let text = text_document.and_then(|document| document.get("text")).and_then(Value::as_str);
+                let (Some(uri), Some(text)) = (uri, text) else { return Vec::new() };
+                self.update(uri, text.to_string())
+            }
+            "textDocument/didChange" => {
+                // Changes are asked for as whole documents, so the last one is the text
+                let changes = params.and_then(|params| params.get("contentChanges")).and_then(Value::as_array);
+                let text = changes.and_then(|changes| changes.last()).and_then(|change| change.get("text")).and_then(Value::as_str);
+                let (Some(uri), Some(text)) = (uri, text) else { return Vec::new() };
+                self.update(uri, text.to_string())
+            }
+            "textDocument/didSave" => {
+                let Some(path) = uri.and_then(|uri| self.path_of(uri)) else { return Vec::new() };
+                self.publish_around(&path)
+            }
+            "textDocument/didClose" => {
+                let Some(uri) = uri else { return Vec::new() };
+                let Some(path) = self.path_of(uri) else { return Vec::new() };
+                self.documents.remove(&path);
+                // Its counterpart is checked against the saved file from now on
+                let mut replies = vec![publish(uri, Vec::new())];
+                replies.extend(self.publish_around(&path));
+                replies
+            }
+            _ => id.map(|_| error(id, METHOD_NOT_FOUND, &format!("Unsupported method {}", method))).into_iter().collect(),
+        }
+    }
+
+    fn initialize(&mut self, params: Option<&Value>) -> Result<Value> {
+        let root = params.and_then(|params| params.get("rootUri")).and_then(Value::as_str).and_then(uri_to_path)
+            .or_else(|| params.and_then(|params| params.get("rootPath")).and_then(Value::as_str).map(PathBuf::from));
+        if let Some(root) = &root {
+            // Config and the source and spec directories are relative to the project root
+            std::env::set_current_dir(root).with_context(|| format!("Failed to change to the project root {}", root.display()))?;
+        }
+        let config = Config::load_from_cargo_toml()
+            .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+        let options = self.args.take().unwrap_or_default().resolve(&config)?;
+        let root = std::env::current_dir().context("Failed to read the current directory")?;
+        self.project = Some(Project::new(root, options));
+
+        let mut sync = Table::new();
+        sync.insert("openClose".to_string(), Value::Boolean(true));
+        // Full documents on every change
+        sync.insert("change".to_string(), Value::Integer(1));
+        sync.insert("save".to_string(), Value::Boolean(true));
+        let mut capabilities = Table::new();
+        capabilities.insert("textDocumentSync".to_string(), Value::Table(sync));
+        let mut info = Table::new();
+        info.insert("name".to_string(), "spec-check".into());
+        info.insert("version".to_string(), env!("CARGO_PKG_VERSION").into());
+        let mut result = Table::new();
+        result.insert("capabilities".to_string(), Value::Table(capabilities));
+        result.insert("serverInfo".to_string(), Value::Table(info));
+        Ok(Value::Table(result))
+    }
+
+    fn path_of(&self, uri: &str) -> Option<PathBuf> {
+        let project = self.project.as_ref()?;
+        uri_to_path(uri).map(|path| project.relative(&path))
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> Vec<String> {
+        let Some(path) = self.path_of(uri) else { return Vec::new() };
+        self.documents.insert(path.clone(), Document { uri: uri.to_string(), text });
+        self.publish_around(&path)
+    }
+
+    /// Diagnostics for `path` and for its source or spec counterpart, where they are open
+    fn publish_around(&self, path: &Path) -> Vec<String> {
+        let Some(project) = &self.project else { return Vec::new() };
+        let mut replies = Vec::new();
+        for path in [Some(path.to_path_buf()), project.counterpart(path)].into_iter().flatten() {
+            let Some(document) = self.documents.get(&path) else { continue };
+            if let Some(diagnostics) = self.diagnostics(project, &path, &document.text) {
+                replies.push(publish(&document.uri, diagnostics));
+            }
+        }
+        replies
+    }
+
+    /// The text of an open document, or else of the file on disk
+    fn content(&self, path: &Path) -> Option<String> {
+        match self.documents.get(path) {
+            Some(document) => Some(document.text.clone()),
+            None => fs::read_to_string(path).ok(),
+        }
+    }
+
+    /// The diagnostics of a source or spec file, or `None` to leave earlier ones in place, as
+    /// while a source file being edited doesn't parse
+    fn diagnostics(&self, project: &Project, path: &Path, text: &str) -> Option<Vec<Value>> {
+        if path.starts_with(&project.src) && path.extension().is_some_and(|ext| ext == "rs") {
+            // Items a spec scopes by heading are only paired by `check`, which sees every module
+            if project.options.mappings.iter().find(|rule| rule.matches_rust(path)).is_some_and(|rule| rule.headings) {
+                return None;
+            }
+            let (spec_file, spec_text) = match project.mapped_specs(path) {
+                // Spec items that other source files of the rule define are only missing here, which isn't shown
+                Some(specs) => {
+                    let texts: Vec<String> = specs.iter().filter_map(|spec| self.content(spec)).collect();
+                    (specs.into_iter().next(), (!texts.is_empty()).then(|| texts.join("\n")))
+                }
+                None => {
+                    let spec_file = file_walker::mirrored_spec_path(path, &project.src, &project.spec, &project.options.filter).ok()?;
+                    let spec_text = self.content(&spec_file);
+                    (Some(spec_file), spec_text)
+                }
+            };
+            let (Some(spec_file), Some(spec_text)) = (spec_file, spec_text) else {
+                let finding = reporter::missing_spec_finding(path);
+                let suppressed = project.options.disabled(finding.rule, path) || project.baseline().is_some_and(|mut baseline| baseline.suppresses(finding.rule, path, None));
+                return Some(if suppressed { Vec::new() } else { vec![diagnostic(&project.options, text, 1, &finding)] });
+            };
+            let findings = project.findings(path, &spec_file, text, &spec_text)?;
+            Some(findings.iter()
+                .filter(|finding| finding.rule != rules::MISSING_IN_CODE)
+                .map(|finding| diagnostic(&project.options, text, finding.line.unwrap_or(1), finding))
+                .collect())
+        } else if path.starts_with(&project.spec) && spec_format::is_spec_file(path) {
+            // What a `[[mapping]]` rule's spec misses depends on all of its source files, so it's left to `check`
+            if project.options.mappings.iter().any(|rule| rule.matches_spec(path)) {
+                return None;
+            }
+            let rust_file = project.counterpart(path)?;
+            // Without a source file, every item of the spec is missing from the code
+            let rust_text = self.content(&rust_file).unwrap_or_default();
+            let findings = project.findings(&rust_file, path, &rust_text, text)?;
+            Some(findings.iter()
+                .filter(|finding| finding.rule == rules::MISSING_IN_CODE)
+                .map(|finding| diagnostic(&project.options, text, finding.spec_line.unwrap_or(1), finding))
+                .collect())
+        } else {
+            None
+        }
+    }
+}
+
+impl Project {
+    fn new(root: PathBuf, options: CheckOptions) -> Self {
+        let mut project = Project { src: PathBuf::new(), spec: PathBuf::new(), root, options };
+        project.src = project.relative(&project.root.join(&project.options.src));
+        project.spec = project.relative(&project.root.join(&project.options.spec));
+        project
+    }
+
+    /// `path` relative to the root, as check reports and baselines name files, if it's under it
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).map_or_else(|_| path.to_path_buf(), Path::to_path_buf)
+    }
+
+    /// The spec file of a source file, or the source file of a spec file
+    fn counterpart(&self, path: &Path) -> Option<PathBuf> {
+        if let Ok(relative) = path.strip_prefix(&self.spec) {
+            return Some(self.src.join(relative).with_extension("rs"));
+        }
+        file_walker::mirrored_spec_path(path, &self.src, &self.spec, &self.options.filter).ok()
+    }
+
+    /// The spec files of the `[[mapping]]` rule a source file falls under, if any
+    fn mapped_specs(&self, rust_file: &Path) -> Option<Vec<PathBuf>> {
+        let rule = self.options.mappings.iter().find(|rule| rule.matches_rust(rust_file))?;
+        if !glob::is_pattern(&rule.spec) {
+            return Some(Path::new(&rule.spec).is_file().then(|| PathBuf::from(&rule.spec)).into_iter().collect());
+        }
+        Some(file_walker::find_spec_files(&self.spec, &self.options.filter).into_iter().filter(|spec| rule.matches_spec(spec)).collect())
+    }
+
+    fn baseline(&self) -> Option<Baseline> {
+        self.options.baseline.as_deref().filter(|path| path.exists()).and_then(|path| Baseline::load(path).ok())
+    }
+
+    /// The findings of comparing the contents, less those the baseline accepts, or `None` if
+    /// the source doesn't parse
+    fn findings(&self, rust_file: &Path, spec_file: &Path, rust_text: &str, spec_text: &str) -> Option<Vec<Finding>> {
+        let mut result = check::compare_contents(&self.options, rust_file, spec_file, rust_text, spec_text).ok()?;
+        result.retain_rules(|rule| !self.options.disabled(rule, rust_file));
+        if let Some(mut baseline) = self.baseline() {
+            baseline.suppress(rust_file, &mut result);
+        }
+        Some(reporter::findings_for(rust_file, Some(spec_file), &result, self.options.source_of_truth))
+    }
+}
+
+/// A diagnostic spanning the text of `line` (1-based) from the finding's column, or without its
+/// indentation where the column isn't known
+fn diagnostic(options: &CheckOptions, text: &str, line: usize, finding: &Finding) -> Value {
+    let content = text.lines().nth(line.saturating_sub(1)).unwrap_or("");
+    // Columns count characters
+    let indent = match finding.column.filter(|_| finding.line == Some(line)) {
+        Some(column) => content.char_indices().nth(column - 1).map_or(content.len(), |(index, _)| index),
+        None => content.len() - content.trim_start().len(),
+    };
+    let position = |character: usize| {
+        let mut position = Table::new();
+        position.insert("line".to_string(), Value::Integer(line.saturating_sub(1) as i64));
+        // Positions count UTF-16 code units
+        position.insert("character".to_string(), Value::Integer(content[..character].encode_utf16().count() as i64));
+        Value::Table(position)
+    };
+    let mut range = Table::new();
+    range.insert("start".to_string(), position(indent));
+    range.insert("end".to_string(), position(content.trim_end().len().max(indent)));
+    let severity = match options.directories.severity(finding.rule, &finding.file, &options.severities) {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info | Severity::Off => 3,
+    };
+    let mut diagnostic = Table::new();
+    diagnostic.insert("range".to_string(), Value::Table(range));
+    diagnostic.insert("severity".to_string(), Value::Integer(severity));
+    diagnostic.insert("code".to_string(), finding.rule.into());
+    diagnostic.insert("source".to_string(), "spec-check".into());
+    diagnostic.insert("message".to_string(), finding.message.clone().into());
+    Value::Table(diagnostic)
+}
+
+fn publish(uri: &str, diagnostics: Vec<Value>) -> String {
+    let mut params = Table::new();
+    params.insert("uri".to_string(), uri.into());
+    params.insert("diagnostics".to_string(), Value::Array(diagnostics));
+    let mut message = Table::new();
+    message.insert("jsonrpc".to_string(), "2.0".into());
+    message.insert("method".to_string(), "textDocument/publishDiagnostics".into());
+    message.insert("params".to_string(), Value::Table(params));
+    json::to_string_pretty(&Value::Table(message))
+}
+
+/// The response to request `id`. A `None` result is written as `null`, which `toml::Value`
+/// can't hold.
+fn response(id: Option<&Value>, result: Option<Value>) -> String {
+    let result = result.map_or_else(|| "null".to_string(), |result| json::to_string_pretty(&result).trim_end().to_string());
+    format!("{{\"jsonrpc\": \"2.0\", \"id\": {}, \"result\": {}}}", json_id(id), result)
+}
+
+fn error(id: Option<&Value>, code: i64, message: &str) -> String {
+    let mut error = Table::new();
+    error.insert("code".to_string(), Value::Integer(code));
+    error.insert("message".to_string(), message.into());
+    let error = json::to_string_pretty(&Value::Table(error));
+    format!("{{\"jsonrpc\": \"2.0\", \"id\": {}, \"error\": {}}}", json_id(id), error.trim_end())
+}
+
+fn json_id(id: Option<&Value>) -> String {
+    id.map_or_else(|| "null".to_string(), |id| json::to_string_pretty(id).trim_end().to_string())
+}
+
+/// The path of a `file:` URI, percent-decoded
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(rest.len());
+    let mut raw = rest.bytes();
+    while let Some(byte) = raw.next() {
+        if byte == b'%' {
+            let hex = [raw.next()?, raw.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `file:///C:/project` on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(uri: &str, text: &str) -> Value {
+        json::parse(&format!("{{\"jsonrpc\": \"2.0\", \"method\": \"textDocument/didOpen\", \"params\": {{\"textDocument\": {{\"uri\": {:?}, \"text\": {:?}}}}}}}", uri, text)).unwrap()
+    }
+
+    #[test]
+    fn test_diagnostics_for_open_source_and_spec() {
+        let options = CheckArgs { no_baseline: true, ..Default::default() }.resolve(&Config::default()).unwrap();
+        let mut server = Server {
+            args: None,
+            project: Some(Project::new(PathBuf::from("/project"), options)),
+            documents: HashMap::new(),
+            shutdown: false,
+        };
+        let spec = "# Lib\n\n```rust\npub fn lerp(a: f32, b: f32, t: f64) -> f32 {}\n\npub fn gone() {}\n```\n";
+        server.handle(&open("file:///project/spec/lib.md", spec));
+        let replies = server.handle(&open("file:///project/src/lib.rs", "/// Ünïcode\n    pub fn lerp(a: f32, b: f32, t: f32) -> f32 { a }\n"));
+
+        // The source file, then the spec file it is compared with
+        assert_eq!(replies.len(), 2);
+        let code = json::parse(&replies[0]).unwrap();
+        assert_eq!(code["params"]["uri"].as_str(), Some("file:///project/src/lib.rs"));
+        let diagnostics = code["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["code"].as_str(), Some(rules::SIGNATURE_MISMATCH));
+        assert_eq!(diagnostics[0]["range"]["start"]["line"].as_integer(), Some(1));
+        // From the item's name
+        assert_eq!(diagnostics[0]["range"]["start"]["character"].as_integer(), Some(11));
+
+        let spec = json::parse(&replies[1]).unwrap();
+        let diagnostics = spec["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["code"].as_str(), Some(rules::MISSING_IN_CODE));
+        assert_eq!(diagnostics[0]["range"]["start"]["line"].as_integer(), Some(5));
+
+        assert_eq!(uri_to_path("file:///my%20project/src/lib.rs"), Some(PathBuf::from("/my project/src/lib.rs")));
+        assert_eq!(uri_to_path("file:///C:/project"), Some(PathBuf::from("C:/project")));
+    }
+}
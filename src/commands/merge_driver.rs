@@ -0,0 +1,271 @@
+use crate::scratch_dir::ScratchDir;
+use spec_check::config::Visibility;
+use spec_check::markdown_parser;
+use spec_check::rust_parser;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Args)]
+pub struct MergeDriverArgs {
+    /// Common ancestor version (`%O`)
+    pub base: PathBuf,
+
+    /// Current branch version (`%A`); the merge result is written here
+    pub ours: PathBuf,
+
+    /// Other branch version (`%B`)
+    pub theirs: PathBuf,
+}
+
+/// Stands in for a code block's contents while the surrounding prose is merged
+const BLOCK_PLACEHOLDER: &str = "spec-check-merge-driver-block";
+
+/// Exit status git reads as "merged with conflicts"
+const CONFLICTED: u8 = 1;
+
+pub fn run(args: MergeDriverArgs) -> Result<u8> {
+    let read = |path: &Path| fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()));
+    let base = read(&args.base)?;
+    let ours = read(&args.ours)?;
+    let theirs = read(&args.theirs)?;
+
+    // Plain line merge first: it already handles edits to different parts of the file
+    let (line_merged, clean) = merge_lines(&ours, &base, &theirs)?;
+    if clean {
+        return write_result(&args.ours, &line_merged, true);
+    }
+
+    match merge_structurally(&base, &ours, &theirs)? {
+        Some((merged, clean)) => write_result(&args.ours, &merged, clean),
+        None => write_result(&args.ours, &line_merged, false),
+    }
+}
+
+fn write_result(path: &Path, content: &str, clean: bool) -> Result<u8> {
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(if clean { 0 } else { CONFLICTED })
+}
+
+/// Three-way line merge via `git merge-file`, returning the result and whether it is conflict-free
+fn merge_lines(ours: &str, base: &str, theirs: &str) -> Result<(String, bool)> {
+    let dir = ScratchDir::new("spec-check-merge")?;
+    let paths = [dir.path().join("ours"), dir.path().join("base"), dir.path().join("theirs")];
+    for (path, content) in paths.iter().zip([ours, base, theirs]) {
+        fs::write(path, content)?;
+    }
+
+    let output = Command::new("git")
+        .args(["merge-file", "-p", "-L", "ours", "-L", "base", "-L", "theirs"])
+        .args(&paths)
+        .output();
+    drop(dir);
+    let output = output.context("Failed to run git merge-file")?;
+
+    // Exit status is the number of conflicts, or negative (>127 here) on error
+    match output.status.code() {
+        Some(conflicts @ 0..=127) => Ok((String::from_utf8_lossy(&output.stdout).into_owned(), conflicts == 0)),
+        _ => anyhow::bail!("git merge-file failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+    }
+}
+
+/// Merge prose line by line and Rust blocks item by item.
+/// Returns None when the versions' blocks cannot be lined up.
+fn merge_structurally(base: &str, ours: &str, theirs: &str) -> Result<Option<(String, bool)>> {
+    let (base_skeleton, base_blocks) = split_blocks(base);
+    let (our_skeleton, our_blocks) = split_blocks(ours);
+    let (their_skeleton, their_blocks) = split_blocks(theirs);
+    if base_blocks.len() != our_blocks.len() || base_blocks.len() != their_blocks.len() {
+        return Ok(None);
+    }
+
+    let (skeleton, prose_clean) = merge_lines(&our_skeleton, &base_skeleton, &their_skeleton)?;
+    if !prose_clean {
+        return Ok(None);
+    }
+
+    let mut clean = true;
+    let mut merged = String::new();
+    let mut blocks = base_blocks.iter().zip(&our_blocks).zip(&their_blocks);
+    for line in skeleton.split_inclusive('\n') {
+        if line.trim_end() != BLOCK_PLACEHOLDER {
+            merged.push_str(line);
+            continue;
+        }
+        let ((base_block, our_block), their_block) = blocks.next().context("Merged prose has extra code blocks")?;
+        let (block, block_clean) = merge_block(base_block, our_block, their_block);
+        clean &= block_clean;
+        merged.push_str(&block);
+    }
+
+    Ok(Some((merged, clean)))
+}
+
+/// Replace each Rust block's contents with a placeholder line, returning the skeleton and the blocks
+fn split_blocks(markdown: &str) -> (String, Vec<String>) {
+    let mut skeleton = String::new();
+    let mut blocks = Vec::new();
+    let mut last = 0;
+    for block in markdown_parser::rust_blocks(markdown).filter(|block| block.unknown_tag.is_none()) {
+        skeleton.push_str(&markdown[last..block.range.start]);
+        skeleton.push_str(BLOCK_PLACEHOLDER);
+        skeleton.push('\n');
+        blocks.push(markdown[block.range.clone()].to_string());
+        last = block.range.end;
+    }
+    skeleton.push_str(&markdown[last..]);
+    (skeleton, blocks)
+}
+
+/// One top-level item of a code block, with the doc comments, attributes and blank lines before it
+#[derive(Debug, Clone)]
+struct Chunk {
+    key: String,
+    text: String,
+}
+
+impl Chunk {
+    fn same_content(&self, other: &Chunk) -> bool {
+        self.text.trim() == other.text.trim()
+    }
+}
+
+/// Split a block into chunks that each end with a complete top-level item
+fn split_items(code: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut text = String::new();
+    let mut depth = 0i32;
+
+    for line in code.split_inclusive('\n') {
+        text.push_str(line);
+        let code_part = line.split("//").next().unwrap_or("");
+        depth += code_part.matches(['{', '(', '[']).count() as i32;
+        depth -= code_part.matches(['}', ')', ']']).count() as i32;
+
+        let trimmed = code_part.trim();
+        let ends_item = trimmed.ends_with(';') || trimmed.ends_with('}');
+        if depth <= 0 && ends_item && !trimmed.starts_with("#[") {
+            chunks.push(Chunk { key: item_key(&text), text: std::mem::take(&mut text) });
+            depth = 0;
+        }
+    }
+
+    // Trailing comments or blank lines after the last item
+    if !text.trim().is_empty() {
+        chunks.push(Chunk { key: item_key(&text), text });
+    }
+    chunks
+}
+
+/// Identify a chunk by the items it defines, or by its text when it does not parse
+fn item_key(text: &str) -> String {
+    let items = rust_parser::parse_spec_block(text, 1, Visibility::All).unwrap_or_default();
+    if items.is_empty() {
+        return text.trim().to_string();
+    }
+    items.iter().map(|item| format!("{}:{:?}", item.name, item.kind)).collect::<Vec<_>>().join(",")
+}
+
+/// Three-way merge of a code block by item, keeping our order and slotting in items only they added
+fn merge_block(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+    let base_chunks = split_items(base);
+    let our_chunks = split_items(ours);
+    let their_chunks = split_items(theirs);
+    let find = |chunks: &[Chunk], key: &str| chunks.iter().find(|c| c.key == key).cloned();
+
+    let mut clean = true;
+    let mut merged: Vec<Chunk> = Vec::new();
+    for ours in &our_chunks {
+        let base = find(&base_chunks, &ours.key);
+        let theirs = find(&their_chunks, &ours.key);
+        match (base, theirs) {
+            // Unchanged on our side: take theirs, including their deletion
+            (Some(base), theirs) if base.same_content(ours) => merged.extend(theirs),
+            (Some(base), Some(theirs)) if base.same_content(&theirs) => merged.push(ours.clone()),
+            (_, Some(theirs)) if theirs.same_content(ours) => merged.push(ours.clone()),
+            // Only we added it
+            (None, None) => merged.push(ours.clone()),
+            (_, theirs) => {
+                clean = false;
+                merged.push(conflict(ours, theirs.as_ref()));
+            }
+        }
+    }
+
+    // Items only they added go after the item that precedes them on their side
+    let mut insert_at = 0;
+    for theirs in &their_chunks {
+        if let Some(pos) = merged.iter().position(|c| c.key == theirs.key) {
+            insert_at = pos + 1;
+            // Keep our own additions at this spot ahead of theirs
+            while insert_at < merged.len() && find(&base_chunks, &merged[insert_at].key).is_none()
+                && find(&their_chunks, &merged[insert_at].key).is_none()
+            {
+                insert_at += 1;
+            }
+            continue;
+        }
+        let in_base = find(&base_chunks, &theirs.key);
+        let in_ours = our_chunks.iter().any(|c| c.key == theirs.key);
+        match in_base {
+            None if !in_ours => {
+                merged.insert(insert_at, theirs.clone());
+                insert_at += 1;
+            }
+            // We deleted it; fine if they left it alone
+            Some(base) if !in_ours && !base.same_content(theirs) => {
+                clean = false;
+                merged.insert(insert_at, conflict(&Chunk { key: theirs.key.clone(), text: String::new() }, Some(theirs)));
+                insert_at += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let mut text: String = merged.iter().map(|c| c.text.as_str()).collect();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    (text, clean)
+}
+
+fn conflict(ours: &Chunk, theirs: Option<&Chunk>) -> Chunk {
+    let side = |chunk: Option<&Chunk>| {
+        let text = chunk.map(|c| c.text.trim_matches('\n')).unwrap_or_default();
+        if text.is_empty() { String::new() } else { format!("{}\n", text) }
+    };
+    Chunk {
+        key: ours.key.clone(),
+        text: format!("<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n", side(Some(ours)), side(theirs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_block_keeps_items_added_on_both_sides() {
+        let base = "pub struct Base;\n";
+        let ours = "pub struct Base;\n\npub struct Ours;\n";
+        let theirs = "pub struct Base;\n\npub fn theirs() -> u32 { 0 }\n";
+
+        let (merged, clean) = merge_block(base, ours, theirs);
+        assert!(clean);
+        assert_eq!(merged, "pub struct Base;\n\npub struct Ours;\n\npub fn theirs() -> u32 { 0 }\n");
+    }
+
+    #[test]
+    fn test_merge_block_conflicts_on_divergent_edits() {
+        let base = "pub struct Point { pub x: i32 }\npub struct Other;\n";
+        let ours = "pub struct Point { pub x: i64 }\npub struct Other;\n";
+        let theirs = "pub struct Point { pub x: u8 }\n";
+
+        let (merged, clean) = merge_block(base, ours, theirs);
+        assert!(!clean);
+        assert!(merged.contains("<<<<<<< ours\npub struct Point { pub x: i64 }\n=======\npub struct Point { pub x: u8 }\n>>>>>>> theirs\n"));
+        assert!(!merged.contains("Other"));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::{Config, FileFilter};
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::rust_parser::{self, ItemKind};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Minimum share of items two files must have in common to be considered the same module
+const MIN_SIMILARITY: f64 = 0.5;
+
+#[derive(Args)]
+pub struct MigrateArgs {
+    /// Perform the moves (default is to only print them)
+    #[arg(long)]
+    pub apply: bool,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// A spec file that should follow its source file to a new location
+struct SpecMove {
+    from: PathBuf,
+    to: PathBuf,
+    source: PathBuf,
+    similarity: f64,
+}
+
+type ItemSet = HashSet<(String, ItemKind)>;
+
+pub fn run(args: MigrateArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    // Candidates: spec files whose source disappeared, and source files without a spec
+    let mut orphans = Vec::new();
+    for spec_file in file_walker::find_orphaned_specs(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        let items = check::load_spec_items(&spec_file, options.visibility)?;
+        orphans.push((spec_file, item_set(items)));
+    }
+
+    let mut unmapped = Vec::new();
+    for mapping in file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        // A `[[mapping]]` rule says where its specs are, not the mirrored path, and specs are
+        // only moved within the source directory's mirror
+        if mapping.spec_file.is_some() || mapping.mapped_specs.is_some() || !mapping.rust_file.starts_with(&options.src) {
+            continue;
+        }
+        let content = fs::read_to_string(&mapping.rust_file)
+            .with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
+        let Ok(items) = rust_parser::parse_rust_file(&content, options.visibility) else { continue };
+        unmapped.push((mapping.rust_file, item_set(items)));
+    }
+
+    let moves = plan_moves(&orphans, &unmapped, &options.src, &options.spec, &options.filter)?;
+
+    if moves.is_empty() {
+        println!("No moved or renamed source files detected.");
+    }
+    for plan in &moves {
+        println!(
+            "{} {} -> {} (follows {}, {:.0}% of items match)",
+            if args.apply { "Moving" } else { "Would move" },
+            plan.from.display(),
+            plan.to.display(),
+            plan.source.display(),
+            plan.similarity * 100.0,
+        );
+        if args.apply {
+            move_file(&plan.from, &plan.to)?;
+        }
+    }
+
+    let moved: HashSet<&PathBuf> = moves.iter().map(|m| &m.from).collect();
+    for (spec_file, _) in orphans.iter().filter(|(f, _)| !moved.contains(f)) {
+        println!("No match for {}; its items may have been split or removed, move them by hand", spec_file.display());
+    }
+
+    if !args.apply && !moves.is_empty() {
+        println!();
+        println!("Run with --apply to perform these moves.");
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+fn item_set(items: Vec<rust_parser::RustItem>) -> ItemSet {
+    items.into_iter().map(|item| (item.name, item.kind)).collect()
+}
+
+/// Jaccard similarity of two item sets
+fn similarity(a: &ItemSet, b: &ItemSet) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Pair orphaned specs with unmapped source files, best matches first
+fn plan_moves(
+    orphans: &[(PathBuf, ItemSet)],
+    unmapped: &[(PathBuf, ItemSet)],
+    src: &Path,
+    spec: &Path,
+    filter: &FileFilter,
+) -> Result<Vec<SpecMove>> {
+    let mut candidates = Vec::new();
+    for (orphan_index, (_, orphan_items)) in orphans.iter().enumerate() {
+        for (source_index, (_, source_items)) in unmapped.iter().enumerate() {
+            let score = similarity(orphan_items, source_items);
+            if score >= MIN_SIMILARITY {
+                candidates.push((score, orphan_index, source_index));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut used_orphans = HashSet::new();
+    let mut used_sources = HashSet::new();
+    let mut moves = Vec::new();
+    for (score, orphan_index, source_index) in candidates {
+        if used_orphans.contains(&orphan_index) || used_sources.contains(&source_index) {
+            continue;
+        }
+        used_orphans.insert(orphan_index);
+        used_sources.insert(source_index);
+
+        let source = &unmapped[source_index].0;
+        moves.push(SpecMove {
+            from: orphans[orphan_index].0.clone(),
+            to: file_walker::mirrored_spec_path(source, src, spec, filter)?,
+            source: source.clone(),
+            similarity: score,
+        });
+    }
+
+    Ok(moves)
+}
+
+/// Move a file with `git mv` so history follows it, falling back to a plain rename
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let tracked = Command::new("git")
+        .arg("mv")
+        .arg(from)
+        .arg(to)
+        .output()
+        .is_ok_and(|output| output.status.success());
+
+    if !tracked {
+        fs::rename(from, to)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(names: &[&str]) -> ItemSet {
+        names.iter().map(|n| (n.to_string(), ItemKind::Struct)).collect()
+    }
+
+    #[test]
+    fn test_plan_moves_pairs_best_match() {
+        let orphans = vec![
+            (PathBuf::from("spec/old.md"), set(&["A", "B", "C"])),
+            (PathBuf::from("spec/gone.md"), set(&["X"])),
+        ];
+        let unmapped = vec![
+            (PathBuf::from("src/other.rs"), set(&["A", "Z"])),
+            (PathBuf::from("src/new/place.rs"), set(&["A", "B", "C", "D"])),
+        ];
+
+        let moves = plan_moves(&orphans, &unmapped, Path::new("src"), Path::new("spec"), &FileFilter::default()).unwrap();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from, PathBuf::from("spec/old.md"));
+        assert_eq!(moves[0].to, PathBuf::from("spec/new/place.md"));
+    }
+}
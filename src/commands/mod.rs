@@ -0,0 +1,34 @@
+pub mod annotate;
+pub mod api_report;
+pub mod audit_bundle;
+pub mod changelog;
+pub mod check;
+pub mod ci;
+pub mod clean;
+pub mod daemon;
+pub mod demo;
+pub mod diff;
+pub mod doctor;
+pub mod explain;
+pub mod file;
+pub mod export;
+pub mod fragments;
+pub mod grep;
+pub mod init;
+pub mod install_hook;
+pub mod lsp;
+pub mod merge_driver;
+pub mod migrate;
+pub mod open;
+pub mod package;
+pub mod public_api;
+pub mod release_notes;
+pub mod requirements;
+pub mod scaffold;
+pub mod serve;
+pub mod snapshot;
+pub mod test;
+pub mod translations;
+pub mod tui;
+pub mod verify_published;
+pub mod workspace;
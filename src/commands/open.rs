@@ -0,0 +1,112 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::{Config, Visibility};
+use crate::exit_code;
+use spec_check::reporter::{format_item, item_path};
+use spec_check::rust_parser::RustItem;
+use spec_check::spec_format;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+#[derive(Args)]
+pub struct OpenArgs {
+    /// Item name, e.g. `Geometry` or `CurveGeometry::sample`
+    pub item: String,
+
+    /// Print `file:line` instead of opening an editor
+    #[arg(long)]
+    pub print: bool,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// Where a spec item is defined
+pub struct SpecLocation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub item: RustItem,
+}
+
+pub fn run(args: OpenArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let locations = find_in_specs(&options.spec, &args.item, options.visibility)?;
+    let Some(first) = locations.first() else {
+        anyhow::bail!("No spec entry for `{}` under {}", args.item, options.spec.display());
+    };
+
+    // Several matches (e.g. a method name shared by two traits): list them all
+    if args.print || locations.len() > 1 {
+        for location in &locations {
+            println!("{}:{}: {}", location.file.display(), location.line, format_item(&location.item));
+        }
+        return Ok(exit_code::SUCCESS);
+    }
+
+    let Some(editor) = editor() else {
+        println!("{}:{}", first.file.display(), first.line);
+        return Ok(exit_code::SUCCESS);
+    };
+
+    let status = editor_command(&editor, &first.file, first.line)
+        .status()
+        .with_context(|| format!("Failed to launch editor `{}`", editor))?;
+    if !status.success() {
+        anyhow::bail!("Editor `{}` exited with {}", editor, status);
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// The user's editor from `$VISUAL` or `$EDITOR`
+pub fn editor() -> Option<String> {
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).ok();
+    editor.filter(|e| !e.trim().is_empty())
+}
+
+pub fn find_in_specs(spec_dir: &Path, query: &str, visibility: Visibility) -> Result<Vec<SpecLocation>> {
+    let mut locations = Vec::new();
+
+    for entry in WalkDir::new(spec_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| spec_format::is_spec_file(e.path()))
+    {
+        let items = check::load_spec_items(entry.path(), visibility)?;
+        for item in items.into_iter().filter(|item| matches_query(item, query)) {
+            locations.push(SpecLocation { file: entry.path().to_path_buf(), line: item.line_number, item });
+        }
+    }
+
+    Ok(locations)
+}
+
+/// `Name` matches any item called `Name`; `Owner::name` or `module::Name` only the items whose path ends that way
+fn matches_query(item: &RustItem, query: &str) -> bool {
+    let path = item_path(item);
+    path == query || path.ends_with(&format!("::{}", query))
+}
+
+/// Build the editor invocation that jumps to a line; most editors accept `+LINE FILE`
+pub fn editor_command(editor: &str, file: &Path, line: usize) -> Command {
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or(editor);
+    let mut command = Command::new(program);
+    command.args(parts);
+
+    let name = Path::new(program).file_stem().and_then(|s| s.to_str()).unwrap_or(program);
+    if matches!(name, "code" | "code-insiders" | "codium" | "cursor") {
+        command.arg("--goto").arg(format!("{}:{}", file.display(), line));
+    } else if matches!(name, "subl" | "zed" | "hx") {
+        command.arg(format!("{}:{}", file.display(), line));
+    } else {
+        command.arg(format!("+{}", line)).arg(file);
+    }
+    command
+}
@@ -0,0 +1,136 @@
+use crate::commands::check::CheckArgs;
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::glob;
+use spec_check::spec_format;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::Command;
+use walkdir::WalkDir;
+
+#[derive(Args)]
+pub struct PackageArgs {
+    /// Output of `cargo package --list` to check (`-` for stdin) instead of running it
+    #[arg(long, value_name = "FILE")]
+    pub list: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: PackageArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let listing = match &args.list {
+        Some(path) if path.as_os_str() == "-" => {
+            let mut listing = String::new();
+            std::io::stdin().read_to_string(&mut listing).context("Failed to read stdin")?;
+            listing
+        }
+        Some(path) => fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?,
+        None => cargo_package_list()?,
+    };
+
+    let spec_files: Vec<String> = WalkDir::new(&options.spec)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| spec_format::is_spec_file(e.path()))
+        .map(|e| normalize(&e.path().to_string_lossy()))
+        .collect();
+    if spec_files.is_empty() {
+        eprintln!("No spec files found in {}", options.spec.display());
+        return Ok(exit_code::MISSING_SPEC);
+    }
+
+    let (include, exclude) = package_filters()?;
+    let missing = unshipped(&spec_files, &listing);
+    for file in &missing {
+        println!("{} is not in the package{}", file, reason(file, &include, &exclude));
+    }
+    if missing.is_empty() {
+        println!("The package ships all {} spec file(s)", spec_files.len());
+        Ok(exit_code::SUCCESS)
+    } else {
+        println!("\n{} of {} spec file(s) would not be published", missing.len(), spec_files.len());
+        Ok(exit_code::VIOLATIONS)
+    }
+}
+
+/// The files `cargo package` would publish, one per line
+fn cargo_package_list() -> Result<String> {
+    let output = Command::new("cargo")
+        .args(["package", "--list", "--allow-dirty", "--quiet"])
+        .output()
+        .context("Failed to run cargo package --list")?;
+    if !output.status.success() {
+        anyhow::bail!("cargo package --list failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The `include` and `exclude` patterns of `[package]`, to explain why a spec is left out
+fn package_filters() -> Result<(Vec<String>, Vec<String>)> {
+    let content = fs::read_to_string("Cargo.toml").context("Failed to read Cargo.toml")?;
+    let cargo: toml::Table = toml::from_str(&content)?;
+    let patterns = |key: &str| -> Vec<String> {
+        cargo.get("package")
+            .and_then(|p| p.get(key))
+            .and_then(|v| v.as_array())
+            .map(|v| v.iter().filter_map(|p| p.as_str()).map(normalize).collect())
+            .unwrap_or_default()
+    };
+    Ok((patterns("include"), patterns("exclude")))
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./").trim_start_matches('/').to_string()
+}
+
+/// Spec files missing from the package listing
+fn unshipped(spec_files: &[String], listing: &str) -> Vec<String> {
+    let listed: HashSet<String> = listing.lines().map(|line| normalize(line.trim())).collect();
+    spec_files.iter().filter(|file| !listed.contains(*file)).cloned().collect()
+}
+
+/// Why `file` is left out, as far as `include` and `exclude` tell
+fn reason(file: &str, include: &[String], exclude: &[String]) -> String {
+    // Cargo's patterns are gitignore-style; `*` here also crosses `/`, which is close enough to point at the culprit
+    let covers = |pattern: &str| {
+        let pattern = pattern.trim_end_matches('/');
+        glob::matches(pattern, file) || glob::matches(&format!("{}/*", pattern), file)
+    };
+    if !include.is_empty() {
+        if !include.iter().any(|p| covers(p)) {
+            return " (no `include` pattern in Cargo.toml matches it)".to_string();
+        }
+    } else if let Some(pattern) = exclude.iter().find(|p| covers(p)) {
+        return format!(" (excluded by `{}` in Cargo.toml)", pattern);
+    }
+    String::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unshipped_specs_and_reasons() {
+        let specs = vec!["spec/lib.md".to_string(), "spec/math/mod.md".to_string()];
+        let listing = "Cargo.toml\nsrc/lib.rs\n./spec/lib.md\n";
+        assert_eq!(unshipped(&specs, listing), vec!["spec/math/mod.md"]);
+
+        let include = vec!["src/**".to_string(), "Cargo.toml".to_string()];
+        assert_eq!(reason("spec/lib.md", &include, &[]), " (no `include` pattern in Cargo.toml matches it)");
+        assert_eq!(reason("spec/lib.md", &["spec/".to_string()], &[]), "");
+        assert_eq!(reason("spec/math/mod.md", &[], &["spec/math".to_string()]), " (excluded by `spec/math` in Cargo.toml)");
+        assert_eq!(reason("spec/lib.md", &[], &[]), "");
+    }
+}
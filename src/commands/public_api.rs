@@ -0,0 +1,317 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::reporter::format_item;
+use spec_check::rust_parser::{ItemKind, RustItem, Stage};
+use spec_check::spec_format;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+#[derive(Args)]
+pub struct PublicApiArgs {
+    /// Output of `cargo public-api` or `cargo public-api diff` (`-` for stdin)
+    pub input: PathBuf,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// How an item appears in the cargo-public-api output
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// Listed in a plain `cargo public-api` listing
+    Listed,
+    Added,
+    Removed,
+    Changed { old: String },
+}
+
+/// An item line of the output, e.g. `pub fn my_crate::math::lerp(a: f32, b: f32, t: f32) -> f32`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ApiItem {
+    kind: ItemKind,
+    name: String,
+    line: String,
+    change: Change,
+}
+
+/// A spec item and the file describing it
+struct SpecEntry {
+    file: PathBuf,
+    item: RustItem,
+}
+
+pub fn run(args: PublicApiArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let mut input = String::new();
+    if args.input.as_os_str() == "-" {
+        std::io::stdin().read_to_string(&mut input).context("Failed to read stdin")?;
+    } else {
+        input = fs::read_to_string(&args.input)
+            .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    }
+
+    let mut specs = Vec::new();
+    for entry in WalkDir::new(&options.spec)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| spec_format::is_spec_file(e.path()))
+    {
+        for item in check::load_spec_items(entry.path(), options.visibility)? {
+            specs.push(SpecEntry { file: entry.path().to_path_buf(), item });
+        }
+    }
+
+    let api = parse_output(&input, &specs);
+    let problems = cross_check(&api, &specs);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("The spec acknowledges every public API item ({} checked)", api.len());
+        Ok(exit_code::SUCCESS)
+    } else {
+        Ok(exit_code::VIOLATIONS)
+    }
+}
+
+/// Read the items of a listing or a diff, skipping items spec-check doesn't track
+/// (fields, variants, inherent methods, impls, modules, ...)
+fn parse_output(output: &str, specs: &[SpecEntry]) -> Vec<ApiItem> {
+    let lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+    let is_diff = lines.iter().any(|line| line.starts_with("+pub ") || line.starts_with("-pub "));
+
+    // A `Type::method` line is a trait method only if `Type` is a trait somewhere
+    let traits: HashSet<&str> = lines.iter()
+        .filter_map(|line| parse_line(line.trim_start_matches(['+', '-'])))
+        .filter(|(kind, _)| *kind == "trait")
+        .filter_map(|(_, path)| path.rsplit("::").next())
+        .chain(specs.iter().filter(|s| s.item.kind == ItemKind::Trait).map(|s| s.item.name.as_str()))
+        .collect();
+
+    let mut items = Vec::new();
+    let mut section = "";
+    let mut removed_line: Option<&str> = None;
+    for line in lines {
+        if line.ends_with("the public API") {
+            section = if line.starts_with("Removed") { "removed" } else if line.starts_with("Changed") { "changed" } else { "added" };
+            continue;
+        }
+        let (change, text) = match (is_diff, line.split_at_checked(1)) {
+            (false, _) => (Change::Listed, line),
+            (true, Some(("-", text))) if section == "changed" => {
+                removed_line = Some(text);
+                continue;
+            }
+            (true, Some(("+", text))) if section == "changed" => {
+                (Change::Changed { old: removed_line.take().unwrap_or_default().to_string() }, text)
+            }
+            (true, Some(("-", text))) => (Change::Removed, text),
+            (true, Some(("+", text))) => (Change::Added, text),
+            _ => continue,
+        };
+        let Some((keyword, path)) = parse_line(text) else { continue };
+
+        let mut segments: Vec<&str> = path.split("::").collect();
+        let name = segments.pop().unwrap_or_default().to_string();
+        let parent = segments.last().copied().filter(|_| segments.len() > 1);
+        let kind = match (keyword, parent) {
+            ("struct", _) => ItemKind::Struct,
+            ("enum", _) => ItemKind::Enum,
+            ("union", _) => ItemKind::Union,
+            ("trait", _) => ItemKind::Trait,
+            ("fn", Some(parent)) if traits.contains(parent) => ItemKind::TraitMethod { trait_name: parent.to_string() },
+            ("fn", Some(parent)) if parent.starts_with(char::is_uppercase) => ItemKind::Method { type_name: parent.to_string() },
+            ("fn", _) => ItemKind::Function,
+            _ => continue,
+        };
+        items.push(ApiItem { kind, name, line: text.to_string(), change });
+    }
+    items
+}
+
+/// The item keyword and path of an item line, e.g. `("fn", "my_crate::math::lerp")`
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let mut rest = line.trim().strip_prefix("pub ")?;
+    // Qualifiers before the keyword: `pub const unsafe fn`, `pub unsafe trait`, ...
+    while let Some((word, tail)) = rest.split_once(' ') {
+        match word {
+            "const" | "async" | "unsafe" | "extern" | "\"C\"" | "auto" => rest = tail,
+            "fn" | "struct" | "enum" | "union" | "trait" => {
+                let end = tail.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).unwrap_or(tail.len());
+                let path = tail[..end].trim_end_matches(':');
+                return (!path.is_empty()).then_some((word, path));
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// A signature with paths, visibility, bodies and whitespace removed, so
+/// `pub fn a::b(x: alloc::vec::Vec<u8>)` and `pub fn b(x: Vec<u8>) {}` compare equal
+fn signature_shape(signature: &str) -> String {
+    let signature = signature.trim();
+    let signature = signature.split_once('{').map_or(signature, |(head, _)| head);
+    let signature = signature.trim_end().trim_end_matches(';');
+    let signature = signature.strip_prefix("pub ").unwrap_or(signature);
+
+    // Drop every `ident::` qualifier, then the whitespace
+    let mut shape = String::new();
+    let mut ident = String::new();
+    let mut chars = signature.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            ident.clear();
+        } else {
+            shape.push_str(&ident);
+            ident.clear();
+            if !c.is_whitespace() {
+                shape.push(c);
+            }
+        }
+    }
+    shape.push_str(&ident);
+    shape
+}
+
+fn cross_check(api: &[ApiItem], specs: &[SpecEntry]) -> Vec<String> {
+    let described = |item: &ApiItem| specs.iter().find(|s| s.item.name == item.name && s.item.kind == item.kind);
+    let has_signature = |kind: &ItemKind| matches!(kind, ItemKind::Function | ItemKind::TraitMethod { .. } | ItemKind::Method { .. });
+
+    let mut problems = Vec::new();
+    for item in api {
+        let (label, spec) = (label(item), described(item));
+        if let Some(spec) = spec.filter(|spec| spec.item.stage == Stage::Forbidden) {
+            if item.change != Change::Removed {
+                problems.push(format!("{}:{}: {}: public but forbidden by the spec", spec.file.display(), spec.item.line_number, label));
+            }
+            continue;
+        }
+        match (&item.change, spec) {
+            (Change::Listed, None) => problems.push(format!("{}: public but not in the spec", label)),
+            (Change::Added, None) => problems.push(format!("{}: added to the public API but not in the spec", label)),
+            (Change::Removed, Some(spec)) => problems.push(format!(
+                "{}:{}: {}: removed from the public API but still in the spec", spec.file.display(), spec.item.line_number, label)),
+            (Change::Changed { .. }, None) => problems.push(format!("{}: changed in the public API but not in the spec", label)),
+            (Change::Changed { old }, Some(spec)) if has_signature(&item.kind) => {
+                let spec_shape = signature_shape(&spec.item.signature);
+                if spec_shape == signature_shape(&item.line) {
+                    continue;
+                }
+                let detail = if spec_shape == signature_shape(old) { "the spec still has the old signature" } else { "the spec doesn't match the new signature" };
+                problems.push(format!(
+                    "{}:{}: {}: changed in the public API, but {}: {}", spec.file.display(), spec.item.line_number, label, detail, item.line));
+            }
+            _ => {}
+        }
+    }
+
+    // A full listing also shows which spec items aren't public at all
+    if api.iter().all(|item| item.change == Change::Listed) && !api.is_empty() {
+        for spec in specs.iter().filter(|spec| spec.item.stage != Stage::Forbidden) {
+            if !api.iter().any(|item| item.name == spec.item.name && item.kind == spec.item.kind) {
+                problems.push(format!("{}:{}: {}: in the spec but not in the public API",
+                    spec.file.display(), spec.item.line_number, format_item(&spec.item)));
+            }
+        }
+    }
+    problems
+}
+
+fn label(item: &ApiItem) -> String {
+    format_item(&RustItem {
+        name: item.name.clone(),
+        kind: item.kind.clone(),
+        signature: String::new(),
+        tokens: String::new(),
+        attributes: Vec::new(),
+        line_number: 0,
+        column: 0,
+        module: Vec::new(),
+        stage: Default::default(),
+        requirements: Vec::new(),
+        prose: None,
+        versions: Default::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec_check::config::Visibility;
+
+    fn spec(markdown: &str) -> Vec<SpecEntry> {
+        spec_check::extract_spec_items(markdown, Visibility::Pub).into_iter()
+            .map(|item| SpecEntry { file: PathBuf::from("spec/lib.md"), item })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_changes_the_spec_has_not_caught_up_with() {
+        let specs = spec("```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\npub fn old() {}\npub trait Shape {\n    fn area(&self) -> f32;\n}\n```\n");
+        let diff = "\
+Removed items from the public API
+=================================
+-pub fn my_crate::old()
+
+Changed items in the public API
+===============================
+-pub fn my_crate::lerp(a: f32, b: f32) -> f32
++pub fn my_crate::lerp(a: f64, b: f64) -> f64
+-pub fn my_crate::Shape::area(&self) -> f32
++pub fn my_crate::Shape::area(&self) -> f32
+
+Added items to the public API
+=============================
++pub struct my_crate::Mesh
++pub fn my_crate::Mesh::new() -> Self
++pub fn <my_crate::Mesh as core::clone::Clone>::clone(&self) -> my_crate::Mesh
+";
+        let api = parse_output(diff, &specs);
+        assert_eq!(api.len(), 5);
+        assert_eq!(api[2].kind, ItemKind::TraitMethod { trait_name: "Shape".to_string() });
+        assert_eq!(api[4].kind, ItemKind::Method { type_name: "Mesh".to_string() });
+
+        let problems = cross_check(&api, &specs);
+        assert_eq!(problems, vec![
+            "spec/lib.md:3: fn old: removed from the public API but still in the spec".to_string(),
+            "spec/lib.md:2: fn lerp: changed in the public API, but the spec still has the old signature: pub fn my_crate::lerp(a: f64, b: f64) -> f64".to_string(),
+            "struct Mesh: added to the public API but not in the spec".to_string(),
+            "fn Mesh::new: added to the public API but not in the spec".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_listing_is_compared_both_ways() {
+        let specs = spec("```rust\npub struct Mesh;\npub fn gone() {}\n```\n");
+        let api = parse_output("pub mod my_crate\npub struct my_crate::Mesh\npub my_crate::Mesh::vertices: alloc::vec::Vec<f32>\npub unsafe fn my_crate::raw::peek<T>(ptr: *const T) -> T\n", &specs);
+        assert_eq!(cross_check(&api, &specs), vec![
+            "fn peek: public but not in the spec".to_string(),
+            "spec/lib.md:3: fn gone: in the spec but not in the public API".to_string(),
+        ]);
+
+        let forbidding = spec("```rust
+pub struct Mesh;
+```
+
+```rust,forbidden
+pub unsafe fn peek<T>(ptr: *const T) -> T {}
+pub fn legacy() {}
+```
+");
+        assert_eq!(cross_check(&api, &forbidding), vec!["spec/lib.md:6: fn peek: public but forbidden by the spec".to_string()]);
+    }
+}
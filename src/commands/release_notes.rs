@@ -0,0 +1,128 @@
+use crate::commands::changelog::{self, ApiChange, ChangelogArgs};
+use crate::commands::export::Side;
+use spec_check::config::{Config, Visibility};
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::git;
+use spec_check::markdown_parser;
+use spec_check::reporter::format_item;
+use spec_check::rust_parser::{self, ItemKind, RustItem};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ReleaseNotesArgs {
+    #[command(flatten)]
+    pub changelog: ChangelogArgs,
+}
+
+/// Spec section heading and prose describing one item, keyed by (name, kind)
+type ProseIndex = HashMap<(String, ItemKind), (Option<String>, String)>;
+
+pub fn run(args: ReleaseNotesArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let ChangelogArgs { from, to, src, spec, of, output } = args.changelog;
+    let src = src
+        .or_else(|| config.src_dir.as_ref().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("src"));
+    let spec = spec
+        .or_else(|| config.spec_dir.as_ref().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("spec"));
+    let visibility = config.visibility();
+
+    let dir = if of == Side::Spec { &spec } else { &src };
+    let old_items = changelog::items_at_revision(&from, dir, of, visibility)?;
+    let new_items = changelog::items_at_revision(&to, dir, of, visibility)?;
+    let changes = changelog::diff_revisions(old_items, new_items, &config.get_ignored_attributes(), None);
+
+    let mut old_prose = HashMap::new();
+    let mut new_prose = HashMap::new();
+    let mut notes = format!("## Release notes: {} to {}\n", from, to);
+
+    if changes.is_empty() {
+        notes.push_str("\nNo API changes.\n");
+    }
+
+    for (heading, breaking) in [("Breaking changes", true), ("New and improved", false)] {
+        let section: Vec<&ApiChange> = changes.iter().filter(|c| c.breaking == breaking).collect();
+        if section.is_empty() {
+            continue;
+        }
+        notes.push_str(&format!("\n### {}\n", heading));
+
+        for change in section {
+            // Removed items are described by the old spec, everything else by the new one
+            let (rev, cache) = if change.new.is_some() { (&to, &mut new_prose) } else { (&from, &mut old_prose) };
+            let prose = cache
+                .entry(change.file.clone())
+                .or_insert_with(|| {
+                    let spec_file = match of {
+                        Side::Code => file_walker::mirrored_spec_path(&change.file, &src, &spec, &config.file_filter()).ok(),
+                        Side::Spec => Some(change.file.clone()),
+                    };
+                    spec_file.map(|spec_file| prose_at_revision(rev, &spec_file, visibility)).unwrap_or_default()
+                });
+            notes.push_str(&render_note(change, prose));
+        }
+    }
+
+    match &output {
+        Some(path) => fs::write(path, notes)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", notes),
+    }
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// Index the spec prose for every item in `spec_file` at `rev`.
+/// An item's own doc comment in the spec wins over the paragraph introducing its block.
+fn prose_at_revision(rev: &str, spec_file: &Path, visibility: Visibility) -> ProseIndex {
+    let mut index = ProseIndex::new();
+
+    let Ok(markdown) = git::show_file(rev, spec_file) else { return index };
+    let Ok(blocks) = markdown_parser::extract_documented_blocks(&markdown) else { return index };
+
+    for block in blocks {
+        let Ok(items) = rust_parser::parse_spec_block(&block.code, 1, visibility) else { continue };
+        for item in items {
+            let docs = rust_parser::doc_text(&item.attributes);
+            let prose = if docs.is_empty() { block.prose.clone() } else { docs };
+            index.insert((item.name, item.kind), (block.heading.clone(), prose));
+        }
+    }
+
+    index
+}
+
+fn render_note(change: &ApiChange, prose: &ProseIndex) -> String {
+    let item: &RustItem = change.new.as_ref().or(change.old.as_ref()).expect("change has at least one side");
+    let action = match (&change.old, &change.new) {
+        (None, Some(_)) => "Added",
+        (Some(_), None) => "Removed",
+        _ => "Changed",
+    };
+
+    let mut out = format!("\n- **{}** `{}` ({})\n", action, format_item(item), change.file.display());
+
+    if let Some((heading, text)) = prose.get(&(item.name.clone(), item.kind.clone())) {
+        if let Some(heading) = heading {
+            out.push_str(&format!("\n  _Spec section: {}_\n", heading));
+        }
+        for paragraph in text.split("\n\n").filter(|p| !p.is_empty()) {
+            out.push_str(&format!("\n  {}\n", paragraph.replace('\n', " ")));
+        }
+    }
+
+    if let (Some(old), Some(new)) = (&change.old, &change.new)
+        && old.signature != new.signature
+    {
+        out.push_str(&format!("\n  Was: `{}`\n\n  Now: `{}`\n", old.signature, new.signature));
+    }
+
+    out
+}
@@ -0,0 +1,113 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::json;
+use spec_check::requirements::{Matrix, Site};
+use spec_check::rust_parser::{self, RustItem};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+#[derive(Args)]
+pub struct RequirementsArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Write the matrix to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+pub fn run(args: RequirementsArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+
+    let mut matrix = Matrix::default();
+    let mut specs: HashMap<PathBuf, Vec<RustItem>> = HashMap::new();
+    for spec_file in file_walker::find_spec_files(&options.spec, &options.filter) {
+        let items = check::load_spec_items(&spec_file, options.visibility)?;
+        matrix.add_spec(&spec_file, &items);
+        specs.insert(spec_file, items);
+    }
+    for mapping in file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)? {
+        let content = fs::read_to_string(&mapping.rust_file)
+            .with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
+        let items = match rust_parser::parse_rust_file(&content, mapping.settings.visibility(options.visibility)) {
+            Ok(items) => items,
+            Err(err) => {
+                eprintln!("Warning: skipping {}: {}", mapping.rust_file.display(), err);
+                continue;
+            }
+        };
+        let spec_files = mapping.mapped_specs.clone().unwrap_or_else(|| mapping.spec_file.iter().cloned().collect());
+        let spec_items: Vec<RustItem> = spec_files.iter().filter_map(|path| specs.get(path)).flatten().cloned().collect();
+        matrix.add_code(&mapping.rust_file, &items, &spec_items);
+    }
+
+    let report = match args.format {
+        Format::Text => text(&matrix),
+        Format::Json => json::to_string_pretty(&Value::Table(json_report(&matrix))),
+    };
+    match &args.output {
+        Some(path) => fs::write(path, report).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", report),
+    }
+
+    Ok(if matrix.orphaned().next().is_some() || matrix.unknown().next().is_some() { exit_code::VIOLATIONS } else { exit_code::SUCCESS })
+}
+
+fn text(matrix: &Matrix) -> String {
+    let mut out = String::new();
+    let site = |side: &str, site: &Site| format!("  {}: {}:{} {}\n", side, site.file.display(), site.line, site.item);
+    for (id, trace) in &matrix.requirements {
+        let status = match (trace.spec.is_empty(), trace.code.is_empty()) {
+            (false, true) => " (orphaned: nothing in the code implements it)",
+            (true, _) => " (unknown: no spec describes it)",
+            _ => "",
+        };
+        out.push_str(&format!("{}{}\n", id, status));
+        out.extend(trace.spec.iter().map(|spec| site("spec", spec)));
+        out.extend(trace.code.iter().map(|code| site("code", code)));
+    }
+    out.push_str(&format!("\n{} requirement(s): {} orphaned, {} unknown\n",
+        matrix.requirements.len(), matrix.orphaned().count(), matrix.unknown().count()));
+    out
+}
+
+fn json_report(matrix: &Matrix) -> Table {
+    let sites = |sites: &BTreeSet<Site>| Value::Array(sites.iter().map(|site| {
+        let mut table = Table::new();
+        table.insert("file".to_string(), Value::String(site.file.to_string_lossy().replace('\\', "/")));
+        table.insert("line".to_string(), Value::Integer(site.line as i64));
+        table.insert("item".to_string(), Value::String(site.item.clone()));
+        Value::Table(table)
+    }).collect());
+
+    let mut requirements = Table::new();
+    for (id, trace) in &matrix.requirements {
+        let mut table = Table::new();
+        table.insert("spec".to_string(), sites(&trace.spec));
+        table.insert("code".to_string(), sites(&trace.code));
+        requirements.insert(id.clone(), Value::Table(table));
+    }
+    let mut report = Table::new();
+    report.insert("requirements".to_string(), Value::Table(requirements));
+    report.insert("orphaned".to_string(), Value::Array(matrix.orphaned().map(|(id, _)| Value::String(id.clone())).collect()));
+    report.insert("unknown".to_string(), Value::Array(matrix.unknown().map(|(id, _)| Value::String(id.clone())).collect()));
+    report
+}
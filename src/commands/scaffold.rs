@@ -0,0 +1,108 @@
+use crate::commands::check::CheckArgs;
+use spec_check::config::Config;
+use crate::exit_code;
+use spec_check::reporter::format_item;
+use spec_check::{file_walker, rust_parser, scaffold};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ScaffoldArgs {
+    /// Write the stubs as a patch to this file (`-` for stdout) instead of to the source files
+    #[arg(long, value_name = "PATH")]
+    pub patch: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: ScaffoldArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    if !options.spec.exists() {
+        anyhow::bail!("Spec directory does not exist: {}", options.spec.display());
+    }
+    // A dry run prints the patch too, and writes nothing
+    let to_stdout = options.dry_run || args.patch.as_ref().is_some_and(|path| path.as_os_str() == "-");
+    let verb = if options.dry_run { "Would stub" } else { "Stubbed" };
+    // With the patch on stdout, the summary goes to stderr
+    let note = |line: String| if to_stdout { eprintln!("{}", line) } else { println!("{}", line) };
+
+    let mappings = file_walker::find_file_mappings(&options.src, &options.spec, &options.mappings, &options.filter)?;
+    let (mut patch, mut added, mut files) = (String::new(), 0, 0);
+    for spec_file in file_walker::find_spec_files(&options.spec, &options.filter) {
+        // A spec of a `[[mapping]]` rule is against all of the rule's source files, and its stubs go in the first
+        let rule_sources: Vec<&PathBuf> = mappings.iter()
+            .filter(|mapping| mapping.mapped_specs.as_ref().is_some_and(|specs| specs.contains(&spec_file)))
+            .map(|mapping| &mapping.rust_file)
+            .collect();
+        if options.mappings.iter().any(|rule| rule.headings && rule.matches_spec(&spec_file)) {
+            note(format!("Skipped {}: its [[mapping]] rule places items by heading, which stubs don't follow", spec_file.display()));
+            continue;
+        }
+        let rust_file = match rule_sources.first() {
+            Some(first) => (*first).clone(),
+            None if options.mappings.iter().any(|rule| rule.matches_spec(&spec_file)) => {
+                note(format!("Skipped {}: its [[mapping]] rule matches no source file to add stubs to", spec_file.display()));
+                continue;
+            }
+            None => file_walker::rust_path_for(&spec_file, &options.src, &options.spec, options.filter.spec_layout)?,
+        };
+        let old = if rust_file.exists() {
+            Some(fs::read_to_string(&rust_file).with_context(|| format!("Failed to read {}", rust_file.display()))?)
+        } else {
+            None
+        };
+        let mut code_items = match old.as_deref().map(|source| rust_parser::parse_rust_file(source, options.visibility)) {
+            Some(Ok(items)) => items,
+            Some(Err(err)) => {
+                note(format!("Skipped {}: failed to parse: {}", rust_file.display(), err));
+                continue;
+            }
+            None => Vec::new(),
+        };
+        for other in rule_sources.iter().skip(1) {
+            let source = fs::read_to_string(other).with_context(|| format!("Failed to read {}", other.display()))?;
+            code_items.extend(rust_parser::parse_rust_file(&source, options.visibility).unwrap_or_default());
+        }
+        let markdown = fs::read_to_string(&spec_file).with_context(|| format!("Failed to read {}", spec_file.display()))?;
+        let stubs = scaffold::stubs(&markdown, &code_items, options.visibility);
+
+        if !stubs.code.is_empty() {
+            if args.patch.is_some() || to_stdout {
+                patch.push_str(&scaffold::append_patch(&rust_file.display().to_string(), old.as_deref(), &stubs.code));
+            } else {
+                if let Some(dir) = rust_file.parent() {
+                    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+                }
+                let content = match old.as_deref() {
+                    None | Some("") => stubs.code.clone(),
+                    Some(old) => format!("{}{}{}", old, if old.ends_with("\n\n") { "" } else if old.ends_with('\n') { "\n" } else { "\n\n" }, stubs.code),
+                };
+                fs::write(&rust_file, content).with_context(|| format!("Failed to write {}", rust_file.display()))?;
+            }
+            let names: Vec<String> = stubs.added.iter().map(format_item).collect();
+            note(format!("{} {} in {}{}", verb, names.join(", "), rust_file.display(),
+                if old.is_none() { " (a new file; declare it with `mod` in its parent)" } else { "" }));
+            added += stubs.added.len();
+            files += 1;
+        }
+        if !stubs.left.is_empty() {
+            let names: Vec<String> = stubs.left.iter().map(format_item).collect();
+            note(format!("Left {} for {} to add by hand: {}", spec_file.display(), rust_file.display(), names.join(", ")));
+        }
+    }
+
+    match &args.patch {
+        _ if to_stdout => print!("{}", patch),
+        Some(path) => fs::write(path, &patch).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => {}
+    }
+    note(String::new());
+    note(format!("{} {} item(s) in {} source file(s){}", verb, added, files,
+        match &args.patch { Some(path) if !to_stdout => format!(", as a patch in {}", path.display()), _ => String::new() }));
+    Ok(exit_code::SUCCESS)
+}
@@ -0,0 +1,236 @@
+use crate::commands::check::{self, CheckArgs, CheckOptions};
+use spec_check::config::Config;
+use spec_check::json;
+use spec_check::reporter::{self, Finding};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use toml::Value;
+use walkdir::WalkDir;
+
+const DASHBOARD: &str = include_str!("serve_dashboard.html");
+
+/// How often the source and spec trees are polled for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a client may take to send its request or read the response
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request line and headers accepted
+const MAX_HEAD: u64 = 16 * 1024;
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 7878)]
+    pub port: u16,
+
+    /// Address to bind; keep the default unless other machines should see results
+    #[arg(long, default_value = "127.0.0.1")]
+    pub bind: String,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// The most recent check results
+struct Snapshot {
+    generation: u64,
+    checked_at: u64,
+    exit_code: u8,
+    findings: Vec<Finding>,
+    error: Option<String>,
+}
+
+type Shared = Arc<Mutex<Snapshot>>;
+
+pub fn run(args: ServeArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = Arc::new(args.check.resolve(&config)?);
+
+    let snapshot = Arc::new(Mutex::new(Snapshot { generation: 0, checked_at: 0, exit_code: 0, findings: Vec::new(), error: None }));
+    recheck(&options, &snapshot);
+
+    let listener = TcpListener::bind((args.bind.as_str(), args.port))
+        .with_context(|| format!("Failed to listen on {}:{}", args.bind, args.port))?;
+    println!("Serving spec-check dashboard on http://{}:{}/ (Ctrl-C to stop)", args.bind, args.port);
+    let hosts = Arc::new(allowed_hosts(&args.bind, args.port));
+
+    {
+        let options = Arc::clone(&options);
+        let snapshot = Arc::clone(&snapshot);
+        thread::spawn(move || watch(&options, &snapshot));
+    }
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let options = Arc::clone(&options);
+        let snapshot = Arc::clone(&snapshot);
+        let hosts = Arc::clone(&hosts);
+        thread::spawn(move || {
+            if let Err(err) = handle(stream, &options, &snapshot, &hosts) {
+                eprintln!("spec-check serve: {:#}", err);
+            }
+        });
+    }
+
+    Ok(crate::exit_code::SUCCESS)
+}
+
+/// Re-run the check whenever a file under the source or spec directory changes
+fn watch(options: &CheckOptions, snapshot: &Shared) {
+    let mut last = fingerprint(options);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = fingerprint(options);
+        if current != last {
+            last = current;
+            recheck(options, snapshot);
+        }
+    }
+}
+
+/// Hash of every path, size and modification time under the watched directories
+fn fingerprint(options: &CheckOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for dir in [&options.src, &options.spec] {
+        for entry in WalkDir::new(dir).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+            entry.path().hash(&mut hasher);
+            if let Ok(metadata) = entry.metadata() {
+                metadata.len().hash(&mut hasher);
+                metadata.modified().ok().hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Run the check while holding the lock, so concurrent re-checks don't interleave log writes
+fn recheck(options: &CheckOptions, snapshot: &Shared) {
+    let mut snapshot = snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let result = check::run_with(options);
+    let checked_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+    snapshot.generation += 1;
+    snapshot.checked_at = checked_at;
+    match result {
+        Ok(run) => {
+            snapshot.exit_code = run.exit_code;
+            snapshot.findings = run.findings;
+            snapshot.error = None;
+        }
+        Err(err) => snapshot.error = Some(format!("{:#}", err)),
+    }
+}
+
+/// The `host:port` values a request's `Host` may have: loopback names and the bound address.
+/// Anything else is a page reaching the server by DNS rebinding.
+fn allowed_hosts(bind: &str, port: u16) -> Vec<String> {
+    let bind = if bind.contains(':') && !bind.starts_with('[') { format!("[{}]", bind) } else { bind.to_string() };
+    let mut hosts: Vec<String> = ["localhost", "127.0.0.1", "[::1]", bind.as_str()].iter().map(|host| format!("{}:{}", host, port)).collect();
+    hosts.dedup();
+    hosts
+}
+
+/// Whether a request with these headers comes from the dashboard or a local tool: it names
+/// an allowed host, and if it comes from a web page, one served from that host
+fn is_allowed(host: Option<&str>, origin: Option<&str>, hosts: &[String]) -> bool {
+    let allowed = |host: &str| hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host));
+    host.is_some_and(allowed) && origin.is_none_or(|origin| origin.strip_prefix("http://").is_some_and(allowed))
+}
+
+fn handle(stream: TcpStream, options: &CheckOptions, snapshot: &Shared, hosts: &[String]) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_HEAD));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Keep the headers that say where a request comes from; requests carry no body we care about
+    let (mut host, mut origin) = (None, None);
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        if !header.ends_with('\n') {
+            return respond(&stream, "431 Request Header Fields Too Large", "text/plain", "request head too large\n");
+        }
+        let Some((name, value)) = header.split_once(':') else { break };
+        let value = Some(value.trim().to_string());
+        if name.eq_ignore_ascii_case("host") {
+            host = value;
+        } else if name.eq_ignore_ascii_case("origin") {
+            origin = value;
+        }
+    }
+    if !is_allowed(host.as_deref(), origin.as_deref(), hosts) {
+        return respond(&stream, "403 Forbidden", "text/plain", "forbidden: the Host or Origin isn't this server\n");
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("/").split('?').next().unwrap_or("/");
+
+    match (method, path) {
+        ("GET", "/") => respond(&stream, "200 OK", "text/html; charset=utf-8", DASHBOARD),
+        ("GET", "/api/results") => {
+            let body = results_json(&snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+            respond(&stream, "200 OK", "application/json", &body)
+        }
+        ("POST", "/api/check") => {
+            recheck(options, snapshot);
+            let body = results_json(&snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+            respond(&stream, "200 OK", "application/json", &body)
+        }
+        ("GET" | "POST", _) => respond(&stream, "404 Not Found", "text/plain", "not found\n"),
+        _ => respond(&stream, "405 Method Not Allowed", "text/plain", "method not allowed\n"),
+    }
+}
+
+fn respond(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn results_json(snapshot: &Snapshot) -> String {
+    let mut table = toml::Table::new();
+    table.insert("generation".to_string(), Value::Integer(snapshot.generation as i64));
+    table.insert("checked_at".to_string(), Value::Integer(snapshot.checked_at as i64));
+    table.insert("exit_code".to_string(), Value::Integer(snapshot.exit_code.into()));
+    if let Some(error) = &snapshot.error {
+        table.insert("error".to_string(), Value::String(error.clone()));
+    }
+    table.insert("findings".to_string(), Value::Array(snapshot.findings.iter().map(reporter::finding_json).collect()));
+    json::to_string_pretty(&Value::Table(table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_local_hosts_and_origins_are_allowed() {
+        let hosts = allowed_hosts("127.0.0.1", 7878);
+        assert!(is_allowed(Some("localhost:7878"), None, &hosts));
+        assert!(is_allowed(Some("127.0.0.1:7878"), Some("http://127.0.0.1:7878"), &hosts));
+        assert!(!is_allowed(None, None, &hosts));
+        // DNS rebinding, another port, or a cross-origin POST
+        assert!(!is_allowed(Some("attacker.example:7878"), None, &hosts));
+        assert!(!is_allowed(Some("localhost:8080"), None, &hosts));
+        assert!(!is_allowed(Some("localhost:7878"), Some("https://attacker.example"), &hosts));
+        assert!(!is_allowed(Some("localhost:7878"), Some("null"), &hosts));
+        assert!(is_allowed(Some("[::1]:9000"), None, &allowed_hosts("::1", 9000)));
+        assert!(is_allowed(Some("10.0.0.5:7878"), None, &allowed_hosts("10.0.0.5", 7878)));
+    }
+}
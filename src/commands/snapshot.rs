@@ -0,0 +1,37 @@
+use crate::commands::check::CheckArgs;
+use crate::commands::export::{self, Side};
+use spec_check::config::{self, Config};
+use crate::exit_code;
+use spec_check::snapshot::{self, Items};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct SnapshotArgs {
+    /// Write the snapshot to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: SnapshotArgs) -> Result<u8> {
+    let items = current_items(args.check)?;
+    let json = snapshot::to_json(&items, config::load_package_info()?.as_ref())?;
+    match &args.output {
+        Some(path) => fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?,
+        None => print!("{}", json),
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+/// The items of every file in the source tree, as the check options select them
+pub fn current_items(check: CheckArgs) -> Result<Items> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = check.resolve(&config)?;
+    Ok(export::collect_modules(&options, Side::Code)?.into_iter().map(|module| (module.file, module.items)).collect())
+}
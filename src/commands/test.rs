@@ -0,0 +1,67 @@
+use crate::commands::check::CheckArgs;
+use spec_check::config::{self, Config};
+use crate::exit_code;
+use spec_check::file_walker;
+use spec_check::spec_tests;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Args)]
+pub struct TestArgs {
+    /// Write the test crate without running `cargo test`
+    #[arg(long)]
+    pub no_run: bool,
+
+    /// Compile the normative spec blocks against the crate as well
+    #[arg(long)]
+    pub compile_specs: bool,
+
+    /// Arguments for `cargo test`, e.g. a test name filter
+    #[arg(last = true)]
+    pub cargo_args: Vec<String>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(args: TestArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.check.resolve(&config)?;
+    let package = config::load_package_info()?.context("No [package] in Cargo.toml to test the spec against")?;
+
+    let mut specs = Vec::new();
+    for spec_file in file_walker::find_spec_files(&options.spec, &options.filter) {
+        let markdown = fs::read_to_string(&spec_file)
+            .with_context(|| format!("Failed to read {}", spec_file.display()))?;
+        let relative = spec_file.strip_prefix(&options.spec).map(Path::to_path_buf).unwrap_or_else(|_| spec_file.clone());
+        specs.push((relative, markdown));
+    }
+    let specs: Vec<(&Path, &str)> = specs.iter().map(|(path, markdown)| (path.as_path(), markdown.as_str())).collect();
+
+    let package_dir = std::env::current_dir()?;
+    let dir = Path::new(config::CACHE_DIR).join("spec-tests");
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    fs::write(dir.join("Cargo.toml"), spec_tests::manifest(&package.name, &package_dir))?;
+    let (source, left_out) = spec_tests::source(&package.name.replace('-', "_"), &specs, args.compile_specs);
+    for note in left_out {
+        eprintln!("Warning: {}", note);
+    }
+    fs::write(dir.join("spec.rs"), source)?;
+    if args.no_run {
+        println!("Wrote the spec test crate to {}", dir.display());
+        return Ok(exit_code::SUCCESS);
+    }
+
+    let mut cargo = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cargo.arg("test").arg("--manifest-path").arg(dir.join("Cargo.toml")).args(&args.cargo_args);
+    // Share the package's build of itself and its dependencies
+    if std::env::var_os("CARGO_TARGET_DIR").is_none() {
+        cargo.env("CARGO_TARGET_DIR", package_dir.join("target"));
+    }
+    let status = cargo.status().context("Failed to run cargo test")?;
+    Ok(if status.success() { exit_code::SUCCESS } else { exit_code::VIOLATIONS })
+}
@@ -0,0 +1,177 @@
+use crate::commands::check::{self, CheckArgs};
+use spec_check::comparator::{self, ComparisonResult};
+use spec_check::config::{Config, Visibility};
+use crate::exit_code;
+use spec_check::reporter::format_item;
+use spec_check::rust_parser::{RustItem, Stage};
+use spec_check::spec_format;
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Args)]
+pub struct TranslationsArgs {
+    /// Spec trees to compare, reference language first (defaults to `translations` in Cargo.toml)
+    pub trees: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// The items of every spec file in a tree, by path relative to the tree
+type Tree = BTreeMap<PathBuf, Vec<RustItem>>;
+
+/// How a translated spec file falls behind the reference
+#[derive(Debug)]
+enum Lag {
+    MissingFile,
+    Compared(Box<ComparisonResult>),
+}
+
+pub fn run(args: TranslationsArgs) -> Result<u8> {
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let trees = if args.trees.is_empty() {
+        config.translations.iter().flatten().map(PathBuf::from).collect()
+    } else {
+        args.trees
+    };
+    let options = args.check.resolve(&config)?;
+    if trees.len() < 2 {
+        anyhow::bail!("Give at least two spec trees to compare, e.g. `spec-check translations spec/en spec/ja`");
+    }
+
+    let loaded = trees.iter().map(|dir| load_tree(dir, options.visibility)).collect::<Result<Vec<_>>>()?;
+    let (reference, translations) = loaded.split_first().expect("at least two trees");
+    let mut lagging = 0;
+    for (dir, tree) in trees[1..].iter().zip(translations) {
+        let lags = compare_trees(reference, tree, &options.ignored_attributes);
+        let behind = lags.len();
+        for (file, lag) in lags {
+            print_lag(&dir.join(&file), &trees[0].join(&file), &lag);
+        }
+        if behind > 0 {
+            println!("{}: {} file(s) behind {}\n", dir.display(), behind, trees[0].display());
+            lagging += 1;
+        }
+    }
+
+    if lagging == 0 {
+        println!("All {} spec trees describe the same items as {}", trees.len(), trees[0].display());
+        Ok(exit_code::SUCCESS)
+    } else {
+        println!("{} of {} translation(s) lag behind {}", lagging, translations.len(), trees[0].display());
+        Ok(exit_code::VIOLATIONS)
+    }
+}
+
+fn load_tree(dir: &Path, visibility: Visibility) -> Result<Tree> {
+    if !dir.exists() {
+        anyhow::bail!("Spec directory does not exist: {}", dir.display());
+    }
+    let mut tree = Tree::new();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()).filter(|e| spec_format::is_spec_file(e.path())) {
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path()).to_path_buf();
+        tree.insert(relative, check::load_spec_items(entry.path(), visibility)?);
+    }
+    Ok(tree)
+}
+
+/// Files of `translation` that don't have the reference's code blocks. Prose and doc comments
+/// may differ, so only items, signatures, and attributes other than the ignored ones count.
+fn compare_trees(reference: &Tree, translation: &Tree, ignored_attributes: &[String]) -> Vec<(PathBuf, Lag)> {
+    let mut lags = Vec::new();
+    for (file, items) in reference {
+        let Some(translated) = translation.get(file) else {
+            lags.push((file.clone(), Lag::MissingFile));
+            continue;
+        };
+        // The translation plays the code, so items only it has are "missing in spec". Forbidden
+        // blocks are translated like any other, so their items are compared as stable ones.
+        let stable = |items: &[RustItem]| -> Vec<RustItem> {
+            items.iter().cloned().map(|item| match item.stage {
+                Stage::Forbidden => RustItem { stage: Stage::Stable, ..item },
+                _ => item,
+            }).collect()
+        };
+        let result = comparator::compare_items(stable(translated), stable(items), ignored_attributes);
+        if result.has_errors() {
+            lags.push((file.clone(), Lag::Compared(Box::new(result))));
+        }
+    }
+    lags
+}
+
+fn print_lag(file: &Path, reference: &Path, lag: &Lag) {
+    let Lag::Compared(result) = lag else {
+        println!("{}: missing; {} has no translation", file.display(), reference.display());
+        return;
+    };
+    println!("{}:", file.display());
+    for item in &result.missing_in_code {
+        println!("  - {} is missing (in {}:{})", format_item(item), reference.display(), item.line_number);
+    }
+    for mismatch in &result.signature_mismatches {
+        println!("  - {} differs (line {}): {}", format_item(&mismatch.code_item), mismatch.code_item.line_number, mismatch.code_item.signature);
+        println!("    {}:{}: {}", reference.display(), mismatch.spec_item.line_number, mismatch.spec_item.signature);
+    }
+    for mismatch in &result.discriminant_mismatches {
+        let changes: Vec<String> = mismatch.changes.iter().map(|(variant, reference, value)| format!("{} = {} ({})", variant, value, reference)).collect();
+        println!("  - {} discriminants differ (line {}): {}", format_item(&mismatch.code_item), mismatch.code_item.line_number, changes.join(", "));
+    }
+    for mismatch in &result.value_mismatches {
+        println!("  - {} value differs (line {}): {} ({})", format_item(&mismatch.code_item), mismatch.code_item.line_number, mismatch.code, mismatch.spec);
+    }
+    for mismatch in &result.abi_mismatches {
+        println!("  - {} ABI differs (line {}): {}", format_item(&mismatch.code_item), mismatch.code_item.line_number, mismatch.differences.join("; "));
+    }
+    for mismatch in &result.attribute_mismatches {
+        println!("  - {} attributes differ (line {}, {}:{})", format_item(&mismatch.code_item), mismatch.code_item.line_number,
+            reference.display(), mismatch.spec_item.line_number);
+    }
+    for item in &result.missing_in_spec {
+        println!("  - {} is not in the reference (line {})", format_item(item), item.line_number);
+    }
+    for rename in &result.probable_renames {
+        println!("  - {} looks like {} renamed (line {}, {}:{})", format_item(&rename.code_item), format_item(&rename.spec_item),
+            rename.code_item.line_number, reference.display(), rename.spec_item.line_number);
+    }
+    for duplicate in &result.duplicates {
+        let path = if duplicate.in_spec { reference } else { file };
+        println!("  - {} is defined twice, differently ({}:{} and {})", format_item(&duplicate.duplicate), path.display(),
+            duplicate.first.line_number, duplicate.duplicate.line_number);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spec_check::extract_spec_items;
+
+    fn tree(files: &[(&str, &str)]) -> Tree {
+        files.iter().map(|(path, md)| (PathBuf::from(path), extract_spec_items(md, Visibility::Pub))).collect()
+    }
+
+    #[test]
+    fn test_translations_differ_only_in_prose() {
+        let reference = tree(&[
+            ("lib.md", "# Color\n\nAn RGB color.\n\n```rust\n/// Red, green, blue\npub struct Color { pub r: u8 }\npub fn mix() {}\n```\n"),
+            ("math.md", "```rust\npub fn lerp(t: f32) -> f32;\n```\n"),
+        ]);
+        let current = tree(&[
+            ("lib.md", "# 色\n\nRGB の色。\n\n```rust\n/// 赤、緑、青\npub struct Color { pub r: u8 }\npub fn mix() {}\n```\n"),
+            ("math.md", "```rust\npub fn lerp(t: f32) -> f32;\n```\n"),
+        ]);
+        assert!(compare_trees(&reference, &current, &["doc".to_string()]).is_empty());
+
+        let lagging = tree(&[("lib.md", "```rust\npub struct Color { pub r: u16 }\n```\n")]);
+        let lags = compare_trees(&reference, &lagging, &["doc".to_string()]);
+        assert_eq!(lags.len(), 2);
+        let Lag::Compared(result) = &lags[0].1 else { panic!("lib.md should be compared") };
+        assert_eq!(result.signature_mismatches.len(), 1);
+        assert_eq!(result.missing_in_code[0].name, "mix");
+        assert!(matches!(&lags[1], (file, Lag::MissingFile) if file == Path::new("math.md")));
+    }
+}
@@ -0,0 +1,535 @@
+use crate::commands::check::{self, CheckArgs, CheckOptions};
+use crate::commands::open;
+use spec_check::config::Config;
+use spec_check::reporter::Finding;
+use spec_check::rules;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::process::Command;
+
+const HELP: &str = "j/k move  enter fold  / search  o open code  s open spec  q quit";
+
+pub fn run(args: CheckArgs) -> Result<u8> {
+    if !std::io::stdout().is_terminal() {
+        anyhow::bail!("`spec-check tui` needs an interactive terminal; use `spec-check ci --provider plain` for plain output");
+    }
+
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let options = args.resolve(&config)?;
+    let mut run = check::run_with(&options)?;
+    run.findings.sort_by(|a, b| (&a.file, a.rule, a.line).cmp(&(&b.file, b.rule, b.line)));
+
+    let mut browser = Browser::new(run.findings);
+    let mut terminal = RawTerminal::enter()?;
+    loop {
+        let (height, width) = terminal_size();
+        terminal.draw(&browser.render(height, width))?;
+
+        let mut keys = read_keys()?;
+        while keys.is_empty() {
+            keys = read_keys()?;
+        }
+        for key in keys {
+            match browser.handle(key, height) {
+                Action::None => {}
+                Action::Quit => return Ok(run.exit_code),
+                Action::Open { code } => {
+                    let message = open_selected(&browser, code, &options, &mut terminal)?;
+                    browser.status = message;
+                }
+            }
+        }
+    }
+}
+
+/// One line of the findings tree: a file, a rule within a file, or a finding
+#[derive(Debug, PartialEq)]
+struct Row {
+    depth: usize,
+    key: String,
+    label: String,
+    finding: Option<usize>,
+}
+
+/// Group findings by file, then by rule, skipping children of collapsed nodes
+fn build_rows(findings: &[Finding], query: &str, collapsed: &HashSet<String>) -> Vec<Row> {
+    let query = query.to_lowercase();
+    let matching: Vec<usize> = (0..findings.len())
+        .filter(|&index| {
+            let finding = &findings[index];
+            query.is_empty()
+                || [finding.rule, &finding.message, &finding.file.to_string_lossy(), finding.item.as_deref().unwrap_or("")]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&query))
+        })
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut index = 0;
+    while index < matching.len() {
+        let file = &findings[matching[index]].file;
+        let in_file: Vec<usize> = matching[index..].iter().copied().take_while(|&i| findings[i].file == *file).collect();
+        index += in_file.len();
+
+        let file_key = file.display().to_string();
+        rows.push(Row { depth: 0, key: file_key.clone(), label: format!("{} ({})", file_key, in_file.len()), finding: None });
+        if collapsed.contains(&file_key) {
+            continue;
+        }
+
+        let mut start = 0;
+        while start < in_file.len() {
+            let rule = findings[in_file[start]].rule;
+            let in_rule: Vec<usize> = in_file[start..].iter().copied().take_while(|&i| findings[i].rule == rule).collect();
+            start += in_rule.len();
+
+            let rule_key = format!("{}\0{}", file_key, rule);
+            let name = rules::find(rule).map(|r| r.name).unwrap_or_default();
+            rows.push(Row { depth: 1, key: rule_key.clone(), label: format!("{} {} ({})", rule, name, in_rule.len()), finding: None });
+            if collapsed.contains(&rule_key) {
+                continue;
+            }
+
+            for finding_index in in_rule {
+                let finding = &findings[finding_index];
+                let name = finding.item.clone().unwrap_or_else(|| finding.message.clone());
+                let label = match finding.line {
+                    Some(line) => format!("{} (line {})", name, line),
+                    None => name,
+                };
+                rows.push(Row { depth: 2, key: format!("{}\0{}", rule_key, finding_index), label, finding: Some(finding_index) });
+            }
+        }
+    }
+    rows
+}
+
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Enter,
+    Esc,
+    Backspace,
+    Char(char),
+}
+
+enum Action {
+    None,
+    Quit,
+    Open { code: bool },
+}
+
+struct Browser {
+    findings: Vec<Finding>,
+    collapsed: HashSet<String>,
+    query: String,
+    searching: bool,
+    selected: usize,
+    scroll: usize,
+    status: String,
+}
+
+impl Browser {
+    fn new(findings: Vec<Finding>) -> Self {
+        let status = format!("{} finding(s)", findings.len());
+        Self { findings, collapsed: HashSet::new(), query: String::new(), searching: false, selected: 0, scroll: 0, status }
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        build_rows(&self.findings, &self.query, &self.collapsed)
+    }
+
+    fn selected_finding(&self) -> Option<&Finding> {
+        self.rows().get(self.selected).and_then(|row| row.finding).map(|index| &self.findings[index])
+    }
+
+    fn handle(&mut self, key: Key, height: usize) -> Action {
+        if self.searching {
+            match key {
+                Key::Enter => self.searching = false,
+                Key::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                }
+                Key::Backspace => {
+                    self.query.pop();
+                }
+                Key::Char(c) => self.query.push(c),
+                _ => {}
+            }
+            self.selected = 0;
+            return Action::None;
+        }
+
+        let rows = self.rows();
+        let page = height.saturating_sub(2).max(1);
+        match key {
+            Key::Char('q') | Key::Char('\u{3}') => return Action::Quit,
+            Key::Esc if self.query.is_empty() => return Action::Quit,
+            Key::Esc => self.query.clear(),
+            Key::Char('/') => {
+                self.searching = true;
+                self.query.clear();
+            }
+            Key::Up | Key::Char('k') => self.selected = self.selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => self.selected += 1,
+            Key::PageUp => self.selected = self.selected.saturating_sub(page),
+            Key::PageDown => self.selected += page,
+            Key::Char('g') => self.selected = 0,
+            Key::Char('G') => self.selected = rows.len().saturating_sub(1),
+            Key::Enter | Key::Char(' ') => {
+                if let Some(row) = rows.get(self.selected).filter(|row| row.finding.is_none())
+                    && !self.collapsed.remove(&row.key)
+                {
+                    self.collapsed.insert(row.key.clone());
+                }
+            }
+            Key::Right | Key::Char('l') => {
+                if let Some(row) = rows.get(self.selected) {
+                    self.collapsed.remove(&row.key);
+                }
+            }
+            Key::Left | Key::Char('h') => {
+                let Some(row) = rows.get(self.selected) else { return Action::None };
+                if row.finding.is_none() && !self.collapsed.contains(&row.key) {
+                    self.collapsed.insert(row.key.clone());
+                } else if let Some(parent) = rows[..self.selected].iter().rposition(|r| r.depth < row.depth) {
+                    self.selected = parent;
+                }
+            }
+            Key::Char('o') => return Action::Open { code: true },
+            Key::Char('s') => return Action::Open { code: false },
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn render(&mut self, height: usize, width: usize) -> Vec<String> {
+        let rows = self.rows();
+        self.selected = self.selected.min(rows.len().saturating_sub(1));
+
+        let body = height.saturating_sub(2);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + body {
+            self.scroll = self.selected + 1 - body;
+        }
+
+        let left_width = (width * 2 / 5).clamp(20, 60).min(width);
+        let right_width = width.saturating_sub(left_width + 3);
+        let detail = self.detail(&rows, right_width);
+
+        let mut lines = vec![format!("\x1b[7m{}\x1b[0m", fit(&format!(" spec-check  {}", HELP), width))];
+        for offset in 0..body {
+            let row_index = self.scroll + offset;
+            let left = match rows.get(row_index) {
+                Some(row) => {
+                    let marker = match row.finding {
+                        Some(_) => " ",
+                        None if self.collapsed.contains(&row.key) => "+",
+                        None => "-",
+                    };
+                    let text = fit(&format!("{}{} {}", "  ".repeat(row.depth), marker, row.label), left_width);
+                    if row_index == self.selected { format!("\x1b[7m{}\x1b[0m", text) } else { text }
+                }
+                None => " ".repeat(left_width),
+            };
+            let right = detail.get(offset).map(String::as_str).unwrap_or("");
+            lines.push(format!("{} \u{2502} {}", left, right));
+        }
+
+        let footer = if self.searching {
+            format!("/{}", self.query)
+        } else if !self.query.is_empty() {
+            format!("filter: {}  (esc clears)  {}", self.query, self.status)
+        } else {
+            self.status.clone()
+        };
+        lines.push(fit(&footer, width));
+        lines
+    }
+
+    /// Detail pane for the selected row: rule, location, and code/spec side by side
+    fn detail(&self, rows: &[Row], width: usize) -> Vec<String> {
+        let Some(row) = rows.get(self.selected) else {
+            return vec!["No findings.".to_string()];
+        };
+        let Some(finding) = row.finding.map(|index| &self.findings[index]) else {
+            return vec![fit(&row.label, width), String::new(), fit("enter folds this node; j/k select a finding", width)];
+        };
+
+        let mut lines = Vec::new();
+        if let Some(rule) = rules::find(finding.rule) {
+            lines.push(format!("\x1b[1m{}\x1b[0m", fit(&format!("{} {}", rule.code, rule.name), width)));
+            lines.extend(wrap(rule.summary, width));
+        }
+        lines.push(String::new());
+        let location = match finding.line {
+            Some(line) => format!("{}:{}", finding.file.display(), line),
+            None => finding.file.display().to_string(),
+        };
+        lines.push(fit(&location, width));
+        lines.extend(wrap(&finding.message, width));
+        lines.push(String::new());
+
+        // Side-by-side definitions; lines that differ are bold
+        let column = width.saturating_sub(3) / 2;
+        let code = finding.code.as_deref().map(|c| wrap(&layout_signature(c), column)).unwrap_or_default();
+        let spec = finding.spec.as_deref().map(|s| wrap(&layout_signature(s), column)).unwrap_or_default();
+        lines.push(format!("{} \u{2502} {}", fit("Code", column), fit("Spec", column)));
+        lines.push(format!("{}\u{253c}{}", "\u{2500}".repeat(column + 1), "\u{2500}".repeat(column + 1)));
+        for index in 0..code.len().max(spec.len()) {
+            let left = code.get(index).map(String::as_str).unwrap_or("");
+            let right = spec.get(index).map(String::as_str).unwrap_or("");
+            let (on, off) = if left != right { ("\x1b[1m", "\x1b[0m") } else { ("", "") };
+            lines.push(format!("{}{}{} \u{2502} {}{}{}", on, fit(left, column), off, on, fit(right, column), off));
+        }
+        lines
+    }
+}
+
+/// Break a one-line token signature into one member per line for display
+fn layout_signature(signature: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut parens = 0usize;
+    for c in signature.chars() {
+        match c {
+            '(' | '<' | '[' => {
+                parens += 1;
+                out.push(c);
+            }
+            ')' | '>' | ']' => {
+                // `->` is not a closing bracket
+                if !(c == '>' && out.ends_with('-')) {
+                    parens = parens.saturating_sub(1);
+                }
+                out.push(c);
+            }
+            '{' => {
+                depth += 1;
+                out.push_str("{\n");
+                out.push_str(&"    ".repeat(depth));
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                let trimmed = out.trim_end().len();
+                out.truncate(trimmed);
+                out.push('\n');
+                out.push_str(&"    ".repeat(depth));
+                out.push('}');
+            }
+            ';' | ',' if depth > 0 && parens == 0 => {
+                out.push(c);
+                out.push('\n');
+                out.push_str(&"    ".repeat(depth));
+            }
+            ' ' if out.ends_with(' ') || out.ends_with('\n') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Pad or truncate to exactly `width` characters
+fn fit(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+    if count > width {
+        text.chars().take(width).collect()
+    } else {
+        format!("{}{}", text, " ".repeat(width - count))
+    }
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chars.chunks(width).map(|chunk| chunk.iter().collect::<String>()));
+    }
+    lines
+}
+
+/// Open the selected finding's code or spec location in the editor, returning a status line
+fn open_selected(browser: &Browser, code: bool, options: &CheckOptions, terminal: &mut RawTerminal) -> Result<String> {
+    let Some(finding) = browser.selected_finding() else {
+        return Ok("Select a finding to open it".to_string());
+    };
+
+    let (file, line) = if code {
+        (finding.file.clone(), finding.line.unwrap_or(1))
+    } else {
+        let Some(item) = &finding.item else {
+            return Ok("This finding has no spec item".to_string());
+        };
+        let locations = open::find_in_specs(&options.spec, item, options.visibility)?;
+        let Some(location) = locations.into_iter().next() else {
+            return Ok(format!("`{}` is not in the spec", item));
+        };
+        (location.file, location.line)
+    };
+
+    let Some(editor) = open::editor() else {
+        return Ok(format!("{}:{} (set $EDITOR to open it)", file.display(), line));
+    };
+
+    terminal.suspend()?;
+    let status = open::editor_command(&editor, &file, line).status();
+    terminal.resume()?;
+    match status {
+        Ok(status) if status.success() => Ok(format!("Opened {}:{}", file.display(), line)),
+        Ok(status) => Ok(format!("Editor `{}` exited with {}", editor, status)),
+        Err(err) => Ok(format!("Failed to launch editor `{}`: {}", editor, err)),
+    }
+}
+
+/// Puts the controlling terminal in raw mode on the alternate screen until dropped
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        let saved = stty(&["-g"]).context("Failed to read terminal settings")?;
+        let terminal = Self { saved: saved.trim().to_string() };
+        terminal.resume()?;
+        Ok(terminal)
+    }
+
+    fn suspend(&self) -> Result<()> {
+        print!("\x1b[?25h\x1b[?1049l");
+        std::io::stdout().flush()?;
+        stty(&[&self.saved])?;
+        Ok(())
+    }
+
+    fn resume(&self) -> Result<()> {
+        // `time 1` makes reads return after 100ms, so a lone Esc is distinguishable from a sequence
+        stty(&["raw", "-echo", "min", "0", "time", "1"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        std::io::stdout().flush()?;
+        Ok(())
+    }
+
+    fn draw(&self, lines: &[String]) -> Result<()> {
+        let mut frame = String::from("\x1b[H");
+        for (index, line) in lines.iter().enumerate() {
+            frame.push_str(&format!("\x1b[{};1H{}\x1b[K", index + 1, line));
+        }
+        let mut stdout = std::io::stdout();
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        self.suspend().ok();
+    }
+}
+
+fn stty(args: &[&str]) -> Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(File::open("/dev/tty").context("Failed to open /dev/tty")?)
+        .output()
+        .context("Failed to run stty")?;
+    if !output.status.success() {
+        anyhow::bail!("stty {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Terminal (rows, columns), defaulting to 24x80
+fn terminal_size() -> (usize, usize) {
+    let size = stty(&["size"]).ok().and_then(|size| {
+        let mut parts = size.split_whitespace().map(|part| part.parse::<usize>().ok());
+        Some((parts.next()??, parts.next()??))
+    });
+    match size {
+        Some((rows, cols)) if rows > 2 && cols > 20 => (rows, cols),
+        _ => (24, 80),
+    }
+}
+
+/// Keys typed since the last read; pasted or fast input can deliver several at once
+fn read_keys() -> Result<Vec<Key>> {
+    let mut buffer = [0u8; 64];
+    let read = std::io::stdin().read(&mut buffer)?;
+    let mut bytes = &buffer[..read];
+    let mut keys = Vec::new();
+
+    while !bytes.is_empty() {
+        let (key, len) = match bytes {
+            [0x1b, b'[' | b'O', b'A', ..] => (Some(Key::Up), 3),
+            [0x1b, b'[' | b'O', b'B', ..] => (Some(Key::Down), 3),
+            [0x1b, b'[' | b'O', b'C', ..] => (Some(Key::Right), 3),
+            [0x1b, b'[' | b'O', b'D', ..] => (Some(Key::Left), 3),
+            [0x1b, b'[', b'5', b'~', ..] => (Some(Key::PageUp), 4),
+            [0x1b, b'[', b'6', b'~', ..] => (Some(Key::PageDown), 4),
+            // Unknown escape sequence: skip to its final byte
+            [0x1b, b'[', rest @ ..] => (None, 2 + rest.iter().position(|b| b.is_ascii_alphabetic() || *b == b'~').map_or(rest.len(), |i| i + 1)),
+            [0x1b, ..] => (Some(Key::Esc), 1),
+            [b'\r' | b'\n', ..] => (Some(Key::Enter), 1),
+            [0x7f | 0x08, ..] => (Some(Key::Backspace), 1),
+            _ => {
+                let len = match bytes[0] {
+                    0xf0.. => 4,
+                    0xe0.. => 3,
+                    0xc0.. => 2,
+                    _ => 1,
+                }.min(bytes.len());
+                let c = std::str::from_utf8(&bytes[..len]).ok().and_then(|s| s.chars().next());
+                (c.map(Key::Char), len)
+            }
+        };
+        keys.extend(key);
+        bytes = &bytes[len..];
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn finding(rule: &'static str, file: &str, item: &str) -> Finding {
+        Finding { item: Some(item.to_string()), ..Finding::new(rule, Path::new(file), Some(1), format!("{} differs", item)) }
+    }
+
+    #[test]
+    fn test_build_rows_groups_filters_and_collapses() {
+        let findings = vec![
+            finding(rules::MISSING_IN_SPEC, "src/a.rs", "Color"),
+            finding(rules::MISSING_IN_SPEC, "src/a.rs", "Mesh"),
+            finding(rules::SIGNATURE_MISMATCH, "src/b.rs", "Geometry"),
+        ];
+
+        let rows = build_rows(&findings, "", &HashSet::new());
+        let labels: Vec<&str> = rows.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, [
+            "src/a.rs (2)", "SC001 missing-in-spec (2)", "Color (line 1)", "Mesh (line 1)",
+            "src/b.rs (1)", "SC003 signature-mismatch (1)", "Geometry (line 1)",
+        ]);
+
+        let collapsed = HashSet::from(["src/a.rs".to_string()]);
+        assert_eq!(build_rows(&findings, "", &collapsed).len(), 4);
+
+        let filtered = build_rows(&findings, "mesh", &HashSet::new());
+        assert_eq!(filtered.len(), 3);
+        assert_eq!(filtered[2].finding, Some(1));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::commands::check::{self, CheckArgs};
+use crate::scratch_dir::ScratchDir;
+use spec_check::config::{self, Config};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Args)]
+pub struct VerifyPublishedArgs {
+    /// Published version to verify (defaults to the package version in Cargo.toml)
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Crate name (defaults to the package name in Cargo.toml)
+    #[arg(long = "crate", value_name = "NAME")]
+    pub crate_name: Option<String>,
+
+    /// Use a local .crate archive instead of downloading from crates.io
+    #[arg(long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+
+    /// Check options; --src is relative to the root of the published package
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+pub fn run(mut args: VerifyPublishedArgs) -> Result<u8> {
+    let package = config::load_package_info()?;
+    let crate_name = match args.crate_name {
+        Some(name) => name,
+        None => package.as_ref()
+            .map(|package| package.name.clone())
+            .context("No --crate given and no [package] name in Cargo.toml")?,
+    };
+    let version = match args.version {
+        Some(version) => version,
+        None => package.as_ref()
+            .map(|package| package.version.clone())
+            .context("No --version given and no [package] version in Cargo.toml")?,
+    };
+
+    // Removed when dropped, after the check
+    let scratch = ScratchDir::new("spec-check-published")?;
+    let work_dir = scratch.path();
+
+    let archive = match &args.file {
+        Some(file) => file.clone(),
+        None => {
+            let archive = work_dir.join(format!("{}-{}.crate", crate_name, version));
+            download(&crate_name, &version, &archive)?;
+            archive
+        }
+    };
+    extract(&archive, work_dir)?;
+
+    // The published package unpacks into `<name>-<version>/`; resolve --src inside it
+    let config = Config::load_from_cargo_toml()
+        .context("Failed to load [package.metadata.spec-check] from Cargo.toml")?;
+    let package_src = args.check.src.take()
+        .or_else(|| config.src_dir.as_ref().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("src"));
+    let package_root = work_dir.join(format!("{}-{}", crate_name, version));
+    args.check.src = Some(package_root.join(package_src));
+
+    check::run(args.check, Default::default())
+}
+
+fn download(crate_name: &str, version: &str, destination: &Path) -> Result<()> {
+    let url = format!("https://crates.io/api/v1/crates/{}/{}/download", crate_name, version);
+    let status = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--user-agent", concat!("spec-check/", env!("CARGO_PKG_VERSION"))])
+        .arg("--output")
+        .arg(destination)
+        .arg(&url)
+        .status()
+        .context("Failed to run curl")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to download {} {} from {}", crate_name, version, url);
+    }
+    Ok(())
+}
+
+fn extract(archive: &Path, destination: &Path) -> Result<()> {
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(destination)
+        .status()
+        .context("Failed to run tar")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to extract {}", archive.display());
+    }
+    Ok(())
+}
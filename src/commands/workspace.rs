@@ -0,0 +1,258 @@
+use crate::commands::check::{self, CheckArgs};
+use crate::commands::export::{self, Side};
+use spec_check::config::{self, Config, WorkspaceConfig, WorkspaceMember};
+use spec_check::coverage::Coverage;
+use crate::exit_code;
+use spec_check::reporter::{self, Finding};
+use spec_check::{json, rules, sarif};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+#[derive(Args)]
+pub struct WorkspaceArgs {
+    /// Write every crate's results and the workspace rollup as JSON
+    #[arg(long, value_name = "PATH")]
+    pub json: Option<PathBuf>,
+
+    /// Write the findings of every crate as one SARIF report
+    #[arg(long, value_name = "PATH")]
+    pub sarif: Option<PathBuf>,
+
+    /// Check options for every member; paths are relative to the member's directory
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+/// The outcome of checking one workspace member
+struct CrateReport {
+    name: String,
+    dir: PathBuf,
+    /// Why the member wasn't checked
+    skipped: Option<String>,
+    exit_code: u8,
+    files: usize,
+    items: usize,
+    specced: usize,
+    findings: Vec<Finding>,
+    /// Every code item, by item path, with how findings spell it (`struct Frame`)
+    defined: HashMap<String, String>,
+}
+
+pub fn run(args: WorkspaceArgs) -> Result<u8> {
+    let members = config::workspace_members()?;
+    if members.is_empty() {
+        anyhow::bail!("Cargo.toml has neither a [package] nor [workspace] members");
+    }
+
+    let workspace = config::load_workspace_config().context("Failed to load [workspace.metadata.spec-check] from Cargo.toml")?;
+    let mut reports = Vec::new();
+    for member in members {
+        reports.push(check_member(member, &workspace, &args.check)?);
+    }
+    flag_wrong_crate(&mut reports);
+
+    for report in &reports {
+        let dir = if report.dir.as_os_str().is_empty() { ".".to_string() } else { report.dir.display().to_string() };
+        match &report.skipped {
+            Some(reason) => println!("{} ({}): skipped, {}", report.name, dir, reason),
+            None => println!("{} ({}): {} file(s), {} finding(s), {}", report.name, dir, report.files, report.findings.len(),
+                coverage(report.specced, report.items)),
+        }
+    }
+    let cross_crate: Vec<&Finding> = reports.iter().flat_map(|r| &r.findings).filter(|f| f.rule == rules::WRONG_CRATE).collect();
+    if !cross_crate.is_empty() {
+        println!("\nCross-crate findings:");
+        for finding in &cross_crate {
+            println!("  {} {}: {}", finding.rule, finding.file.display(), finding.message);
+        }
+    }
+
+    let checked: Vec<&CrateReport> = reports.iter().filter(|r| r.skipped.is_none()).collect();
+    let findings: Vec<Finding> = checked.iter().flat_map(|r| r.findings.iter().cloned()).collect();
+    let (items, specced) = checked.iter().fold((0, 0), |(items, specced), r| (items + r.items, specced + r.specced));
+    let code = workspace_exit_code(checked.iter().map(|r| r.exit_code));
+    println!(
+        "\nWorkspace: {} crate(s) checked, {} skipped; {} finding(s); {}",
+        checked.len(), reports.len() - checked.len(), findings.len(), coverage(specced, items),
+    );
+
+    if let Some(path) = &args.json {
+        fs::write(path, json_report(&reports, code)).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    if let Some(path) = &args.sarif {
+        sarif::write(path, &findings)?;
+    }
+    Ok(code)
+}
+
+fn check_member(member: WorkspaceMember, workspace: &WorkspaceConfig, check: &CheckArgs) -> Result<CrateReport> {
+    // The member's own settings win over the workspace's
+    let config = Config::load_from_manifest(&member.dir.join("Cargo.toml"))
+        .with_context(|| format!("Failed to load [package.metadata.spec-check] of {}", member.name))?
+        .or(workspace.for_crate(&member.name));
+    let mut options = check.clone().resolve(&config)?;
+    options.src = member.dir.join(&options.src);
+    options.spec = member.dir.join(&options.spec);
+    options.mappings = options.mappings.iter().map(|rule| rule.within(&member.dir)).collect();
+    options.filter = options.filter.within(&member.dir);
+    options.log = member.dir.join(&options.log);
+    options.baseline = options.baseline.map(|baseline| member.dir.join(baseline));
+    options.directories = config::DirectoryOverrides::load(&options.src, &options.spec)?;
+    options.version = config::load_package_info_at(&member.dir.join("Cargo.toml"))?.map(|package| package.version);
+
+    let mut report = CrateReport {
+        name: member.name,
+        dir: member.dir,
+        skipped: None,
+        exit_code: exit_code::SUCCESS,
+        files: 0,
+        items: 0,
+        specced: 0,
+        findings: Vec::new(),
+        defined: HashMap::new(),
+    };
+    for (dir, what) in [(&options.src, "source"), (&options.spec, "spec")] {
+        if report.skipped.is_none() && !dir.exists() {
+            report.skipped = Some(format!("no {} directory {}", what, dir.display()));
+        }
+    }
+    if report.skipped.is_some() {
+        return Ok(report);
+    }
+
+    let run = check::run_with(&options)?;
+    let modules = export::collect_modules(&options, Side::Code)?;
+    report.exit_code = run.exit_code;
+    report.files = modules.len();
+    for module in &modules {
+        let unspecced = run.findings.iter().filter(|f| f.file == module.file).map(|f| match f.rule {
+            rules::MISSING_SPEC_FILE => module.items.len(),
+            rules::MISSING_IN_SPEC => 1,
+            _ => 0,
+        }).sum::<usize>();
+        report.items += module.items.len();
+        report.specced += module.items.len().saturating_sub(unspecced);
+        for item in &module.items {
+            report.defined.insert(reporter::item_path(item), reporter::format_item(item));
+        }
+    }
+    report.findings = run.findings.into_iter()
+        .map(|finding| Finding { crate_name: Some(report.name.clone()), ..finding })
+        .collect();
+    Ok(report)
+}
+
+/// Turn "in the spec but not in the code" into a cross-crate finding when the item
+/// is missing from the whole crate but another crate of the workspace defines it
+fn flag_wrong_crate(reports: &mut [CrateReport]) {
+    let owners: Vec<(String, HashMap<String, String>)> = reports.iter().map(|r| (r.name.clone(), r.defined.clone())).collect();
+    for report in reports.iter_mut() {
+        for finding in &mut report.findings {
+            let Some(item) = finding.item.as_ref().filter(|_| finding.rule == rules::MISSING_IN_CODE) else { continue };
+            if report.defined.contains_key(item) {
+                continue;
+            }
+            let Some((owner, display)) = owners.iter().find_map(|(name, defined)| defined.get(item).map(|d| (name, d))) else { continue };
+            finding.rule = rules::WRONG_CRATE;
+            finding.message = format!("{} is specced in {} but defined in {}", display, report.name, owner);
+        }
+    }
+}
+
+/// The most severe exit code of the checked crates, ranked like a single run ranks its files
+fn workspace_exit_code(codes: impl Iterator<Item = u8>) -> u8 {
+    let rank = |code: u8| match code {
+        exit_code::PARSE_FAILURE => 3,
+        exit_code::VIOLATIONS => 2,
+        exit_code::MISSING_SPEC => 1,
+        _ => 0,
+    };
+    codes.max_by_key(|&code| rank(code)).unwrap_or(exit_code::SUCCESS)
+}
+
+fn coverage(specced: usize, items: usize) -> String {
+    Coverage { items, specced }.to_string()
+}
+
+fn json_report(reports: &[CrateReport], code: u8) -> String {
+    let count = |n: usize| Value::Integer(n as i64);
+    let mut crates = Vec::new();
+    for report in reports {
+        let mut table = Table::new();
+        table.insert("name".to_string(), report.name.clone().into());
+        table.insert("dir".to_string(), report.dir.to_string_lossy().replace('\\', "/").into());
+        if let Some(reason) = &report.skipped {
+            table.insert("skipped".to_string(), reason.clone().into());
+        } else {
+            table.insert("exit_code".to_string(), Value::Integer(report.exit_code.into()));
+            table.insert("files".to_string(), count(report.files));
+            table.insert("items".to_string(), count(report.items));
+            table.insert("specced".to_string(), count(report.specced));
+            table.insert("findings".to_string(), Value::Array(report.findings.iter().map(reporter::finding_json).collect()));
+        }
+        crates.push(Value::Table(table));
+    }
+
+    let checked = || reports.iter().filter(|r| r.skipped.is_none());
+    let mut summary = Table::new();
+    summary.insert("exit_code".to_string(), Value::Integer(code.into()));
+    summary.insert("crates".to_string(), count(checked().count()));
+    summary.insert("skipped".to_string(), count(reports.len() - checked().count()));
+    summary.insert("findings".to_string(), count(checked().map(|r| r.findings.len()).sum()));
+    summary.insert("cross_crate_findings".to_string(), count(checked().flat_map(|r| &r.findings).filter(|f| f.rule == rules::WRONG_CRATE).count()));
+    summary.insert("items".to_string(), count(checked().map(|r| r.items).sum()));
+    summary.insert("specced".to_string(), count(checked().map(|r| r.specced).sum()));
+
+    let mut root = Table::new();
+    root.insert("workspace".to_string(), Value::Table(summary));
+    root.insert("crates".to_string(), Value::Array(crates));
+    json::to_string_pretty(&Value::Table(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn report(name: &str, defined: &[(&str, &str)], findings: Vec<Finding>) -> CrateReport {
+        CrateReport {
+            name: name.to_string(),
+            dir: PathBuf::from(format!("crates/{}", name)),
+            skipped: None,
+            exit_code: exit_code::VIOLATIONS,
+            files: 1,
+            items: defined.len(),
+            specced: defined.len(),
+            findings,
+            defined: defined.iter().map(|(path, display)| (path.to_string(), display.to_string())).collect(),
+        }
+    }
+
+    fn missing_in_code(item: &str) -> Finding {
+        Finding {
+            item: Some(item.to_string()),
+            ..Finding::new(rules::MISSING_IN_CODE, Path::new("crates/geometry/src/lib.rs"), None, format!("{} is in the spec but not in the code", item))
+        }
+    }
+
+    #[test]
+    fn test_spec_items_of_sibling_crates_are_flagged() {
+        let mut reports = vec![
+            report("geometry", &[("Curve", "struct Curve")], vec![missing_in_code("Frame"), missing_in_code("Shader")]),
+            report("render", &[("Frame", "struct Frame")], Vec::new()),
+        ];
+        flag_wrong_crate(&mut reports);
+
+        let findings = &reports[0].findings;
+        assert_eq!(findings[0].rule, rules::WRONG_CRATE);
+        assert_eq!(findings[0].message, "struct Frame is specced in geometry but defined in render");
+        assert_eq!(findings[1].rule, rules::MISSING_IN_CODE);
+
+        assert_eq!(workspace_exit_code([0, 4, 1].into_iter()), exit_code::VIOLATIONS);
+        assert_eq!(coverage(5, 6), "5/6 item(s) specced (83%)");
+    }
+}
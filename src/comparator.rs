@@ -1,52 +1,346 @@
-use crate::rust_parser::RustItem;
-use std::collections::HashMap;
+use crate::cfg;
+use crate::naming::{NamingRule, NamingViolation};
+use crate::placeholder;
+use crate::plugin::{self, Violation};
+use crate::rules;
+use crate::rust_parser::{self, ItemKind, RustItem, Stage};
+use crate::signature_diff;
+use crate::type_paths;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use syn::punctuated::Punctuated;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ComparisonResult {
     pub missing_in_spec: Vec<RustItem>,
     pub missing_in_code: Vec<RustItem>,
     pub signature_mismatches: Vec<SignatureMismatch>,
     pub attribute_mismatches: Vec<AttributeMismatch>,
+    /// Enums whose discriminant values changed, reported instead of a signature mismatch
+    #[serde(default)]
+    pub discriminant_mismatches: Vec<DiscriminantMismatch>,
+    /// Consts and statics whose value changed, reported instead of a signature mismatch
+    #[serde(default)]
+    pub value_mismatches: Vec<ValueMismatch>,
+    /// Items whose ABI differs, by an `abi` class attribute or a function's `extern`, reported
+    /// instead of a signature or attribute mismatch
+    #[serde(default)]
+    pub abi_mismatches: Vec<AbiMismatch>,
+    /// Found by registered custom rules, after the built-in comparison
+    #[serde(default)]
+    pub custom_violations: Vec<Violation>,
+    /// Code items whose `#[spec_checked]` annotation names a spec without them
+    #[serde(default)]
+    pub unresolved_annotations: Vec<RustItem>,
+    /// Code items that a spec block tagged `forbidden` says must not exist
+    #[serde(default)]
+    pub forbidden_present: Vec<RustItem>,
+    /// Code items missing from the spec that look like a spec item missing from the code, renamed
+    #[serde(default)]
+    pub probable_renames: Vec<ProbableRename>,
+    /// Items defined more than once on one side, differently
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateDefinition>,
+    /// The spec's ```rust blocks that don't parse, whose items went unchecked
+    #[serde(default)]
+    pub spec_parse_errors: Vec<SpecParseError>,
+    /// Items whose doc comments say something other than the spec's, with `compare-docs`
+    #[serde(default)]
+    pub doc_mismatches: Vec<DocMismatch>,
+    /// Items the code puts in another order than the spec, with `compare-order`
+    #[serde(default)]
+    pub ordering_violations: Vec<OrderingViolation>,
+    /// Code items marked `#[deprecated]` whose spec still describes them as stable
+    #[serde(default)]
+    pub deprecated_in_code: Vec<AttributeMismatch>,
+    /// Code items whose names break a `[[naming]]` rule
+    #[serde(default)]
+    pub naming_violations: Vec<NamingViolation>,
+    /// How many code items were compared, for spec coverage
+    #[serde(default)]
+    pub code_items: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignatureMismatch {
     pub code_item: RustItem,
     pub spec_item: RustItem,
-    pub first_diff_pos: Option<usize>,
+    /// Which parts differ, e.g. "parameter 2 type: `f32` (spec: `f64`)"; empty if that can't be told
+    #[serde(default)]
+    pub differences: Vec<String>,
+    /// For structs and unions, how each drifted field differs
+    #[serde(default)]
+    pub field_diffs: Vec<FieldDiff>,
+    /// For enums, how each drifted variant differs
+    #[serde(default)]
+    pub variant_diffs: Vec<VariantDiff>,
+    /// Whether the function bodies weren't compared, so the signatures are shown without them
+    #[serde(default)]
+    pub bodies_ignored: bool,
 }
 
-#[derive(Debug)]
+/// How one field of a struct or union differs from the spec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    /// The field's name, or its position in a tuple struct
+    pub field: String,
+    pub change: FieldChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldChange {
+    /// In the spec but not in the code, with the spec's declaration
+    Missing { spec: String },
+    /// In the code but not in the spec, with the code's declaration
+    Extra { code: String },
+    Type { code: String, spec: String },
+    Visibility { code: String, spec: String },
+}
+
+impl std::fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.change {
+            FieldChange::Missing { spec } => write!(f, "missing field `{}` (spec: `{}`)", self.field, spec),
+            FieldChange::Extra { code } => write!(f, "extra field `{}` (`{}`)", self.field, code),
+            FieldChange::Type { code, spec } => write!(f, "field `{}` type: `{}` (spec: `{}`)", self.field, code, spec),
+            FieldChange::Visibility { code, spec } => write!(f, "field `{}` visibility: {} (spec: {})", self.field,
+                signature_diff::or_none(code), signature_diff::or_none(spec)),
+        }
+    }
+}
+
+/// How one variant of an enum differs from the spec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantDiff {
+    pub variant: String,
+    pub change: VariantChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VariantChange {
+    /// In the spec but not in the code, with the spec's declaration
+    Missing { spec: String },
+    /// In the code but not in the spec, with the code's declaration
+    Extra { code: String },
+    /// Unit, tuple or struct payload in the code vs the spec
+    Payload { code: String, spec: String },
+    /// The same kind of payload with different fields
+    Fields(Vec<FieldDiff>),
+    /// The explicit discriminants, empty when there is none
+    Discriminant { code: String, spec: String },
+}
+
+impl std::fmt::Display for VariantDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.change {
+            VariantChange::Missing { .. } => write!(f, "missing variant `{}`", self.variant),
+            VariantChange::Extra { .. } => write!(f, "extra variant `{}`", self.variant),
+            VariantChange::Payload { code, spec } => write!(f, "variant `{}` is a {} variant (spec: {})", self.variant, code, spec),
+            VariantChange::Fields(fields) => {
+                let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+                write!(f, "variant `{}`: {}", self.variant, fields.join(", "))
+            }
+            VariantChange::Discriminant { code, spec } => write!(f, "variant `{}` discriminant: {} (spec: {})", self.variant,
+                signature_diff::or_none(code), signature_diff::or_none(spec)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttributeMismatch {
     pub code_item: RustItem,
     pub spec_item: RustItem,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbableRename {
+    pub code_item: RustItem,
+    pub spec_item: RustItem,
+    /// How alike the two are with their names left out, from 0 to 1
+    pub similarity: f64,
+}
+
+/// A second definition of an item, with the same name, kind, module and `#[cfg]`s as an
+/// earlier one on its side but a different signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateDefinition {
+    pub first: RustItem,
+    pub duplicate: RustItem,
+    /// Whether both are spec items rather than code items
+    pub in_spec: bool,
+}
+
+/// An item whose doc comments differ from the spec's doc comments for it, or the prose right
+/// before its block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocMismatch {
+    pub code_item: RustItem,
+    pub spec_item: RustItem,
+    /// Both as plain text; the code's is empty if it has no doc comments
+    pub code: String,
+    pub spec: String,
+}
+
+/// An item the code has somewhere else among its neighbours than the spec does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderingViolation {
+    pub code_item: RustItem,
+    pub spec_item: RustItem,
+    /// The neighbour the spec puts right before it, of those both sides have; `None` when the
+    /// spec puts it first
+    pub after: Option<RustItem>,
+}
+
+/// A spec block that isn't valid Rust, or an item of one that can't be checked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecParseError {
+    /// 1-based, among the spec's checked ```rust blocks
+    pub block: usize,
+    /// In the markdown, of the error where it's known, else of the block's first line
+    pub line: usize,
+    pub message: String,
+    /// The item left out, as written, when the rest of the block was still read
+    #[serde(default)]
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscriminantMismatch {
+    pub code_item: RustItem,
+    pub spec_item: RustItem,
+    /// Variants in both whose value differs, as `(variant, spec value, code value)`
+    pub changes: Vec<(String, String, String)>,
+}
+
+/// A const or static that differs from the spec only in its value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueMismatch {
+    pub code_item: RustItem,
+    pub spec_item: RustItem,
+    /// Integers in decimal, other values as written
+    pub code: String,
+    pub spec: String,
+}
+
+/// An item whose layout or calling convention differs from the spec's
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbiMismatch {
+    pub code_item: RustItem,
+    pub spec_item: RustItem,
+    /// What differs, e.g. "ABI attributes: # [repr (C)] (spec: # [repr (u8)])"
+    pub differences: Vec<String>,
+}
+
 impl ComparisonResult {
     pub fn has_errors(&self) -> bool {
         !self.missing_in_spec.is_empty() 
             || !self.missing_in_code.is_empty() 
             || !self.signature_mismatches.is_empty()
             || !self.attribute_mismatches.is_empty()
+            || !self.discriminant_mismatches.is_empty()
+            || !self.value_mismatches.is_empty()
+            || !self.abi_mismatches.is_empty()
+            || !self.custom_violations.is_empty()
+            || !self.unresolved_annotations.is_empty()
+            || !self.forbidden_present.is_empty()
+            || !self.probable_renames.is_empty()
+            || !self.duplicates.is_empty()
+            || !self.spec_parse_errors.is_empty()
+            || !self.doc_mismatches.is_empty()
+            || !self.ordering_violations.is_empty()
+            || !self.deprecated_in_code.is_empty()
+            || !self.naming_violations.is_empty()
     }
-}
 
-fn find_first_diff(s1: &str, s2: &str) -> Option<usize> {
-    s1.chars()
-        .zip(s2.chars())
-        .position(|(c1, c2)| c1 != c2)
-        .or_else(|| {
-            // If one string is a prefix of the other
-            if s1.len() != s2.len() {
-                Some(s1.len().min(s2.len()))
-            } else {
-                None
-            }
-        })
+    /// Total number of individual findings across all categories
+    pub fn finding_count(&self) -> usize {
+        self.missing_in_spec.len()
+            + self.missing_in_code.len()
+            + self.signature_mismatches.len()
+            + self.attribute_mismatches.len()
+            + self.discriminant_mismatches.len()
+            + self.value_mismatches.len()
+            + self.abi_mismatches.len()
+            + self.custom_violations.len()
+            + self.unresolved_annotations.len()
+            + self.forbidden_present.len()
+            + self.probable_renames.len()
+            + self.duplicates.len()
+            + self.spec_parse_errors.len()
+            + self.doc_mismatches.len()
+            + self.ordering_violations.len()
+            + self.deprecated_in_code.len()
+            + self.naming_violations.len()
+    }
+
+    /// Keep at most `max` findings, dropping from the later categories first
+    pub fn truncate(&mut self, max: usize) {
+        let mut remaining = max;
+        let mut keep = |len: usize| {
+            let kept = len.min(remaining);
+            remaining -= kept;
+            kept
+        };
+        let n = keep(self.missing_in_spec.len());
+        self.missing_in_spec.truncate(n);
+        let n = keep(self.missing_in_code.len());
+        self.missing_in_code.truncate(n);
+        let n = keep(self.signature_mismatches.len());
+        self.signature_mismatches.truncate(n);
+        let n = keep(self.attribute_mismatches.len());
+        self.attribute_mismatches.truncate(n);
+        let n = keep(self.discriminant_mismatches.len());
+        self.discriminant_mismatches.truncate(n);
+        let n = keep(self.value_mismatches.len());
+        self.value_mismatches.truncate(n);
+        let n = keep(self.abi_mismatches.len());
+        self.abi_mismatches.truncate(n);
+        let n = keep(self.custom_violations.len());
+        self.custom_violations.truncate(n);
+        let n = keep(self.unresolved_annotations.len());
+        self.unresolved_annotations.truncate(n);
+        let n = keep(self.forbidden_present.len());
+        self.forbidden_present.truncate(n);
+        let n = keep(self.probable_renames.len());
+        self.probable_renames.truncate(n);
+        let n = keep(self.duplicates.len());
+        self.duplicates.truncate(n);
+        let n = keep(self.spec_parse_errors.len());
+        self.spec_parse_errors.truncate(n);
+        let n = keep(self.doc_mismatches.len());
+        self.doc_mismatches.truncate(n);
+        let n = keep(self.ordering_violations.len());
+        self.ordering_violations.truncate(n);
+        let n = keep(self.deprecated_in_code.len());
+        self.deprecated_in_code.truncate(n);
+        let n = keep(self.naming_violations.len());
+        self.naming_violations.truncate(n);
+    }
+
+    /// Drop the findings of the rules `enabled` turns down, by code
+    pub fn retain_rules(&mut self, enabled: impl Fn(&str) -> bool) {
+        self.missing_in_spec.retain(|_| enabled(rules::MISSING_IN_SPEC));
+        self.missing_in_code.retain(|_| enabled(rules::MISSING_IN_CODE));
+        self.signature_mismatches.retain(|_| enabled(rules::SIGNATURE_MISMATCH));
+        self.attribute_mismatches.retain(|_| enabled(rules::ATTRIBUTE_MISMATCH));
+        self.discriminant_mismatches.retain(|_| enabled(rules::DISCRIMINANT_MISMATCH));
+        self.value_mismatches.retain(|_| enabled(rules::VALUE_MISMATCH));
+        self.abi_mismatches.retain(|_| enabled(rules::ABI_MISMATCH));
+        self.custom_violations.retain(|violation| enabled(plugin::find(&violation.rule).map_or("custom", |rule| rule.code())));
+        self.unresolved_annotations.retain(|_| enabled(rules::UNRESOLVED_ANNOTATION));
+        self.forbidden_present.retain(|_| enabled(rules::FORBIDDEN_PRESENT));
+        self.probable_renames.retain(|_| enabled(rules::PROBABLE_RENAME));
+        self.duplicates.retain(|_| enabled(rules::DUPLICATE_DEFINITION));
+        self.spec_parse_errors.retain(|_| enabled(rules::SPEC_PARSE_FAILURE));
+        self.doc_mismatches.retain(|_| enabled(rules::DOC_MISMATCH));
+        self.ordering_violations.retain(|_| enabled(rules::ORDERING_VIOLATION));
+        self.deprecated_in_code.retain(|_| enabled(rules::DEPRECATED_IN_CODE));
+        self.naming_violations.retain(|_| enabled(rules::NAMING_CONVENTION));
+    }
 }
 
-fn normalize_attributes(attrs: &[String], ignored_attributes: &[String]) -> Vec<String> {
-    let mut normalized: Vec<String> = attrs.iter()
+pub fn normalize_attributes(attrs: &[String], ignored_attributes: &[String]) -> Vec<String> {
+    let (cfgs, attrs): (Vec<String>, Vec<String>) = attrs.iter()
         .filter(|a| {
             // Check if any ignored attribute name is a prefix of this attribute
             // e.g., if ignored is "doc", it matches "#[doc = ...]"
@@ -54,84 +348,718 @@ fn normalize_attributes(attrs: &[String], ignored_attributes: &[String]) -> Vec<
                 a.contains(ignored) || a.starts_with(&format!("#[{}(", ignored)) || a.starts_with(&format!("#[{}", ignored))
             })
         })
-        .map(|a| a.trim().to_string())
-        .collect();
+        .cloned()
+        .partition(|a| cfg::parse(a).is_some());
+    let mut normalized: Vec<String> = attrs.iter().flat_map(|a| canonical_attributes(a.trim())).collect();
+    // `cfg`s compare as the one predicate they make together
+    normalized.extend(cfg::item_cfg(&cfgs).map(|cfg| format!("#[cfg({})]", cfg)));
     normalized.sort();
     normalized
 }
 
+/// An attribute re-rendered from its parsed meta, so differently formatted spellings match.
+/// A `derive` list becomes one attribute per derive, making derives compare as a set.
+fn canonical_attributes(attr: &str) -> Vec<String> {
+    let Ok(parsed) = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attr) else {
+        return vec![attr.to_string()];
+    };
+    parsed.into_iter().flat_map(|attr| {
+        if attr.path().is_ident("derive")
+            && let Ok(paths) = attr.parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated) {
+            return paths.iter().map(|path| quote::quote!(#[derive(#path)]).to_string()).collect();
+        }
+        vec![quote::quote!(#attr).to_string()]
+    }).collect()
+}
+
+/// Items are matched by name, kind and inline module, borrowed from the item lists rather than rebuilt per lookup
+type ItemKey<'a> = (&'a str, &'a ItemKind, &'a [String]);
+
+fn item_key(item: &RustItem) -> ItemKey<'_> {
+    (&item.name, &item.kind, &item.module)
+}
+
+/// A code item found in the spec that differs from it
+struct Mismatch {
+    code: usize,
+    spec: usize,
+    signature: bool,
+    attributes: bool,
+}
+
 pub fn compare_items(
     code_items: Vec<RustItem>,
     spec_items: Vec<RustItem>,
     ignored_attributes: &[String],
 ) -> ComparisonResult {
-    // Create maps for efficient lookup by (name, kind)
-    let mut code_map: HashMap<(String, String), &RustItem> = HashMap::new();
-    let mut spec_map: HashMap<(String, String), &RustItem> = HashMap::new();
-    
-    for item in &code_items {
-        let key = (item.name.clone(), format!("{:?}", item.kind));
-        code_map.insert(key, item);
-    }
-    
-    for item in &spec_items {
-        let key = (item.name.clone(), format!("{:?}", item.kind));
-        spec_map.insert(key, item);
-    }
-    
-    let mut missing_in_spec = Vec::new();
-    let mut missing_in_code = Vec::new();
-    let mut signature_mismatches = Vec::new();
-    let mut attribute_mismatches = Vec::new();
-    
-    // Check items in code
-    for code_item in &code_items {
-        let key = (code_item.name.clone(), format!("{:?}", code_item.kind));
-        
-        if let Some(spec_item) = spec_map.get(&key) {
-            // Item exists in both - compare using token streams
-            let code_tokens = code_item.tokens.to_string();
-            let spec_tokens = spec_item.tokens.to_string();
-            
-            if code_tokens != spec_tokens {
-                let first_diff_pos = find_first_diff(&code_item.signature, &spec_item.signature);
-                signature_mismatches.push(SignatureMismatch {
-                    code_item: code_item.clone(),
-                    spec_item: (*spec_item).clone(),
-                    first_diff_pos,
-                });
+    compare_items_at(code_items, spec_items, ignored_attributes, None)
+}
+
+/// The spec items of the API at `version`, leaving out those of blocks about other versions
+pub fn in_version(spec_items: Vec<RustItem>, version: Option<&str>) -> Vec<RustItem> {
+    spec_items.into_iter().filter(|item| item.versions.contains(version)).collect()
+}
+
+/// `compare_items` for a package at `version`, which decides whether deprecated spec items
+/// past their removal version may be missing from the code. Planned items always may.
+pub fn compare_items_at(
+    code_items: Vec<RustItem>,
+    spec_items: Vec<RustItem>,
+    ignored_attributes: &[String],
+    version: Option<&str>,
+) -> ComparisonResult {
+    // Trait impls are checked against specs that declare some, so others needn't list them all
+    let code_items: Vec<RustItem> = if spec_items.iter().any(|item| item.kind == ItemKind::TraitImpl) {
+        code_items
+    } else {
+        code_items.into_iter().filter(|item| item.kind != ItemKind::TraitImpl).collect()
+    };
+    let compared = code_items.len();
+    let (forbidden, spec_items): (Vec<RustItem>, Vec<RustItem>) = spec_items.into_iter().partition(|item| item.stage == Stage::Forbidden);
+    let mut duplicates = duplicate_definitions(&code_items, false);
+    duplicates.extend(duplicate_definitions(&spec_items, true));
+    let forbidden: HashSet<ItemKey> = forbidden.iter().map(item_key).collect();
+    let mut forbidden_present = Vec::new();
+    let mut mismatches = Vec::new();
+    let mut deprecations = Vec::new();
+    let mut unmatched_code = Vec::new();
+    let mut unmatched_spec: Vec<usize>;
+    {
+        // When a key repeats, the last spec item with it is the one compared
+        let spec_map: HashMap<ItemKey, usize> = spec_items.iter().enumerate().map(|(i, item)| (item_key(item), i)).collect();
+        let code_keys: HashSet<ItemKey> = code_items.iter().map(item_key).collect();
+
+        for (code, code_item) in code_items.iter().enumerate() {
+            if forbidden.contains(&item_key(code_item)) {
+                forbidden_present.push(code);
+                continue;
             }
-            
-            // Check attributes
-            let code_attrs = normalize_attributes(&code_item.attributes, ignored_attributes);
-            let spec_attrs = normalize_attributes(&spec_item.attributes, ignored_attributes);
-            
-            if code_attrs != spec_attrs {
-                attribute_mismatches.push(AttributeMismatch {
-                    code_item: code_item.clone(),
-                    spec_item: (*spec_item).clone(),
-                });
+            let Some(&spec) = spec_map.get(&item_key(code_item)) else {
+                unmatched_code.push(code);
+                continue;
+            };
+            let spec_item = &spec_items[spec];
+
+            // Item exists in both - compare using token streams, then attributes. A deprecation
+            // the spec doesn't have is its own finding, unless the spec's stage expects it.
+            let signature = code_item.tokens != spec_item.tokens && !placeholder::matches(&code_item.tokens, &spec_item.tokens);
+            let deprecated = is_deprecated(code_item) && !is_deprecated(spec_item);
+            if deprecated && !matches!(spec_item.stage, Stage::Deprecated { .. }) {
+                deprecations.push((code, spec));
+            }
+            let code_attributes: Vec<String> = code_item.attributes.iter()
+                .filter(|attr| !(deprecated && is_deprecation(attr)))
+                .cloned()
+                .collect();
+            let attributes = normalize_attributes(&code_attributes, ignored_attributes)
+                != normalize_attributes(&spec_item.attributes, ignored_attributes);
+            if signature || attributes {
+                mismatches.push(Mismatch { code, spec, signature, attributes });
             }
-        } else {
-            // Item in code but not in spec
-            missing_in_spec.push(code_item.clone());
         }
+
+        unmatched_spec = (0..spec_items.len())
+            .filter(|&i| !code_keys.contains(&item_key(&spec_items[i])) && !spec_items[i].stage.allows_missing(version))
+            .collect();
     }
-    
-    // Check for items in spec but not in code
-    for spec_item in &spec_items {
-        let key = (spec_item.name.clone(), format!("{:?}", spec_item.kind));
-        
-        if !code_map.contains_key(&key) {
-            missing_in_code.push(spec_item.clone());
+    let renames = pair_renames(&code_items, &mut unmatched_code, &spec_items, &mut unmatched_spec);
+    let deprecated_in_code = deprecations.into_iter()
+        .map(|(code, spec)| AttributeMismatch { code_item: code_items[code].clone(), spec_item: spec_items[spec].clone() })
+        .collect();
+
+    // Move items into the result, cloning only those that appear in it more than once
+    let mut spec_uses = vec![0usize; spec_items.len()];
+    for mismatch in &mismatches {
+        spec_uses[mismatch.spec] += mismatch.signature as usize + mismatch.attributes as usize;
+    }
+    let mut code_items: Vec<Option<RustItem>> = code_items.into_iter().map(Some).collect();
+    let mut spec_items: Vec<Option<RustItem>> = spec_items.into_iter().map(Some).collect();
+    let mut take_spec = |index: usize| {
+        spec_uses[index] = spec_uses[index].saturating_sub(1);
+        if spec_uses[index] == 0 { spec_items[index].take() } else { spec_items[index].clone() }
+            .expect("spec item used after it was moved")
+    };
+
+    let mut signature_mismatches = Vec::new();
+    let mut attribute_mismatches = Vec::new();
+    let mut discriminant_mismatches = Vec::new();
+    let mut value_mismatches = Vec::new();
+    for mismatch in mismatches {
+        if mismatch.signature {
+            let code_slot = &mut code_items[mismatch.code];
+            let code_item = if mismatch.attributes { code_slot.clone() } else { code_slot.take() }.expect("code item moved");
+            let spec_item = take_spec(mismatch.spec);
+            let changes = discriminant_changes(&code_item, &spec_item);
+            if !changes.is_empty() {
+                discriminant_mismatches.push(DiscriminantMismatch { code_item, spec_item, changes });
+            } else if let Some((spec, code)) = values(&code_item, &spec_item) {
+                // The same value written another way, like `0x10` for `16`, is no difference
+                if spec != code {
+                    value_mismatches.push(ValueMismatch { code_item, spec_item, code, spec });
+                }
+            } else {
+                // A placeholder's markers would show up as fields or variants of their own. Of a
+                // partial struct, only the fields it lists can differ.
+                let (differences, field_diffs, variant_diffs) = if placeholder::is_partial(&spec_item.tokens) {
+                    let field_diffs = signature_diff::struct_field_diffs(&code_item.tokens, &spec_item.tokens).into_iter()
+                        .filter(|diff| !matches!(diff.change, FieldChange::Extra { .. }) && !placeholder::is_fields_marker(&diff.field))
+                        .collect();
+                    (Vec::new(), field_diffs, Vec::new())
+                } else if placeholder::has_placeholders(&spec_item.tokens) {
+                    Default::default()
+                } else {
+                    (signature_diff::differences(&code_item.tokens, &spec_item.tokens),
+                        signature_diff::struct_field_diffs(&code_item.tokens, &spec_item.tokens),
+                        signature_diff::enum_variant_diffs(&code_item.tokens, &spec_item.tokens))
+                };
+                signature_mismatches.push(SignatureMismatch { code_item, spec_item, differences, field_diffs, variant_diffs, bodies_ignored: false });
+            }
+        }
+        if mismatch.attributes {
+            let code_item = code_items[mismatch.code].take().expect("code item moved");
+            attribute_mismatches.push(AttributeMismatch { code_item, spec_item: take_spec(mismatch.spec) });
         }
     }
 
+    // Unmatched items appear nowhere else in the result
+    let missing_in_spec = unmatched_code.into_iter().filter_map(|i| code_items[i].take()).collect();
+    let missing_in_code = unmatched_spec.into_iter().filter_map(|i| spec_items[i].take()).collect();
+    let forbidden_present = forbidden_present.into_iter().filter_map(|i| code_items[i].take()).collect();
+    let probable_renames = renames.into_iter()
+        .filter_map(|(code, spec, similarity)| Some(ProbableRename { code_item: code_items[code].take()?, spec_item: spec_items[spec].take()?, similarity }))
+        .collect();
+
     ComparisonResult {
         missing_in_spec,
         missing_in_code,
         signature_mismatches,
         attribute_mismatches,
+        discriminant_mismatches,
+        value_mismatches,
+        abi_mismatches: Vec::new(),
+        custom_violations: Vec::new(),
+        unresolved_annotations: Vec::new(),
+        forbidden_present,
+        probable_renames,
+        duplicates,
+        spec_parse_errors: Vec::new(),
+        doc_mismatches: Vec::new(),
+        ordering_violations: Vec::new(),
+        deprecated_in_code,
+        naming_violations: Vec::new(),
+        code_items: compared,
+    }
+}
+
+/// The code items whose doc comments differ from what the spec says about them, for
+/// `compare-docs`. Only items the spec documents are compared.
+pub fn doc_mismatches(code_items: &[RustItem], spec_items: &[RustItem]) -> Vec<DocMismatch> {
+    // The spec item compared is the last with the item's key, as in `compare_items`
+    let spec_map: HashMap<ItemKey, &RustItem> = spec_items.iter()
+        .filter(|item| item.stage != Stage::Forbidden)
+        .map(|item| (item_key(item), item))
+        .collect();
+    code_items.iter()
+        .filter_map(|code_item| {
+            let spec_item = spec_map.get(&item_key(code_item))?;
+            let spec = docs(spec_item)?;
+            let code = docs(code_item).unwrap_or_default();
+            (code != spec).then(|| DocMismatch { code_item: code_item.clone(), spec_item: (*spec_item).clone(), code, spec })
+        })
+        .collect()
+}
+
+/// The code items out of the spec's order, for `compare-order`. Items are ordered among the
+/// others of their module and owner that both sides have (top-level items among top-level
+/// items, methods among the methods of their type), and the fewest items that put the rest in
+/// the spec's order are reported.
+pub fn ordering_violations(code_items: &[RustItem], spec_items: &[RustItem]) -> Vec<OrderingViolation> {
+    let mut spec_order: HashMap<ItemKey, usize> = HashMap::new();
+    for (index, item) in spec_items.iter().enumerate().filter(|(_, item)| item.stage != Stage::Forbidden) {
+        spec_order.entry(item_key(item)).or_insert(index);
+    }
+    // The spec positions of each group's items, in code order, each item once
+    let mut groups: BTreeMap<OrderGroup, Vec<(usize, usize)>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for (index, item) in code_items.iter().enumerate() {
+        let Some(&spec) = spec_order.get(&item_key(item)) else { continue };
+        if seen.insert(item_key(item)) {
+            groups.entry((&item.module, item.kind.owner())).or_default().push((index, spec));
+        }
+    }
+
+    let mut violations = Vec::new();
+    for group in groups.values() {
+        let spec_positions: Vec<usize> = group.iter().map(|&(_, spec)| spec).collect();
+        let in_order = longest_increasing(&spec_positions);
+        for (_, &(code, spec)) in group.iter().enumerate().filter(|(position, _)| !in_order.contains(position)) {
+            let after = group.iter().map(|&(_, other)| other).filter(|&other| other < spec).max();
+            violations.push(OrderingViolation {
+                code_item: code_items[code].clone(),
+                spec_item: spec_items[spec].clone(),
+                after: after.map(|other| spec_items[other].clone()),
+            });
+        }
+    }
+    violations.sort_by_key(|violation| violation.code_item.line_number);
+    violations
+}
+
+fn is_deprecation(attribute: &str) -> bool {
+    let attribute = attribute.replace(' ', "");
+    attribute == "#[deprecated]" || attribute.starts_with("#[deprecated(") || attribute.starts_with("#[deprecated=")
+}
+
+fn is_deprecated(item: &RustItem) -> bool {
+    item.attributes.iter().any(|attr| is_deprecation(attr))
+}
+
+/// Items ordered among each other: their module and owner
+type OrderGroup<'a> = (&'a [String], Option<&'a str>);
+
+/// The indices of a longest strictly increasing subsequence of `values`
+fn longest_increasing(values: &[usize]) -> HashSet<usize> {
+    // `tails[k]` ends the best subsequence of length `k + 1` found so far
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; values.len()];
+    for (index, &value) in values.iter().enumerate() {
+        let length = tails.partition_point(|&tail| values[tail] < value);
+        previous[index] = length.checked_sub(1).map(|before| tails[before]);
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+    std::iter::successors(tails.last().copied(), |&index| previous[index]).collect()
+}
+
+/// An item's doc comments as plain text, or for a spec item without any, the prose before its block
+fn docs(item: &RustItem) -> Option<String> {
+    let docs = rust_parser::doc_text(&item.attributes);
+    let docs = if docs.is_empty() { item.prose.as_deref()? } else { docs.as_str() };
+    Some(crate::markdown_parser::plain_text(docs))
+}
+
+/// The items defined again after an earlier item with the same key and `#[cfg]`s, differently.
+/// Definitions behind different `#[cfg]`s are alternatives, and repeating one exactly is harmless.
+fn duplicate_definitions(items: &[RustItem], in_spec: bool) -> Vec<DuplicateDefinition> {
+    let mut first: HashMap<(ItemKey, Option<cfg::Cfg>), &RustItem> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for item in items {
+        let earlier = *first.entry((item_key(item), cfg::item_cfg(&item.attributes))).or_insert(item);
+        if earlier.tokens != item.tokens {
+            duplicates.push(DuplicateDefinition { first: earlier.clone(), duplicate: item.clone(), in_spec });
+        }
+    }
+    duplicates
+}
+
+/// How alike two items must be, names aside, to be reported as a rename
+const RENAME_SIMILARITY: f64 = 0.85;
+/// Fewer tokens than this and every item of a kind looks alike (`pub struct _ ;`)
+const RENAME_MIN_TOKENS: usize = 8;
+
+/// Pair unmatched code and spec items that look like one item renamed, best matches first,
+/// taking the paired ones out of the unmatched lists
+fn pair_renames(code_items: &[RustItem], unmatched_code: &mut Vec<usize>, spec_items: &[RustItem], unmatched_spec: &mut Vec<usize>) -> Vec<(usize, usize, f64)> {
+    let mut candidates: Vec<(usize, usize, f64)> = Vec::new();
+    for &code in unmatched_code.iter() {
+        for &spec in unmatched_spec.iter() {
+            if let Some(similarity) = rename_similarity(&code_items[code], &spec_items[spec]) {
+                candidates.push((code, spec, similarity));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut renames: Vec<(usize, usize, f64)> = Vec::new();
+    for (code, spec, similarity) in candidates {
+        if !renames.iter().any(|&(c, s, _)| c == code || s == spec) {
+            renames.push((code, spec, similarity));
+        }
+    }
+    unmatched_code.retain(|code| !renames.iter().any(|&(c, _, _)| c == *code));
+    unmatched_spec.retain(|spec| !renames.iter().any(|&(_, s, _)| s == *spec));
+    renames.sort_by_key(|&(code, _, _)| code);
+    renames
+}
+
+/// The share of tokens two items of the same kind and module have in common, in order, with
+/// each one's name left out; `None` if they are too different or too short to tell
+fn rename_similarity(code: &RustItem, spec: &RustItem) -> Option<f64> {
+    if code.kind != spec.kind || code.module != spec.module || code.name == spec.name {
+        return None;
+    }
+    // Functions are compared by signature; a renamed one rarely keeps its body token for token
+    let tokens = |item: &RustItem| -> Vec<String> {
+        let source = rust_parser::without_body(&item.tokens).unwrap_or_else(|| item.tokens.clone());
+        source.split_whitespace().map(|token| if token == item.name { "_".to_string() } else { token.to_string() }).collect()
+    };
+    let (code_tokens, spec_tokens) = (tokens(code), tokens(spec));
+    if code_tokens.len().min(spec_tokens.len()) < RENAME_MIN_TOKENS {
+        return None;
+    }
+    // Longest common subsequence, one row at a time
+    let mut row = vec![0usize; spec_tokens.len() + 1];
+    for code_token in &code_tokens {
+        let mut diagonal = 0;
+        for (index, spec_token) in spec_tokens.iter().enumerate() {
+            let above = row[index + 1];
+            row[index + 1] = if code_token == spec_token { diagonal + 1 } else { above.max(row[index]) };
+            diagonal = above;
+        }
+    }
+    let similarity = 2.0 * row[spec_tokens.len()] as f64 / (code_tokens.len() + spec_tokens.len()) as f64;
+    (similarity >= RENAME_SIMILARITY).then_some(similarity)
+}
+
+/// Parts of items that only count as differences when asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompareOptions {
+    /// The values of consts and statics (`compare-const-values`, on by default)
+    pub const_values: bool,
+    /// The bodies of functions (`compare-bodies`, off by default)
+    pub bodies: bool,
+    /// Doc comments, against the spec's doc comments or prose (`compare-docs`, off by default)
+    pub docs: bool,
+    /// The order of items among their neighbours (`compare-order`, off by default)
+    pub order: bool,
+    /// Whether `#[deprecated]` code items may be missing from the spec
+    /// (`allow-unspecced-deprecated`, on by default)
+    pub unspecced_deprecated: bool,
+    /// The names of function parameters, not just their types (off with `ignore-param-names`)
+    pub param_names: bool,
+    /// Whether generic bounds are written inline or in a where clause (off with `normalize-generics`)
+    pub bound_placement: bool,
+    /// Names of attributes the code may have more of than the spec (`code-superset-ok`)
+    pub superset_attributes: Vec<String>,
+    /// Names of the attributes of the `abi` class compared exactly, whose differences are ABI
+    /// mismatches (`attribute-classes`)
+    pub abi_attributes: Vec<String>,
+    /// Paths and what they're written as instead (`type-aliases`), on top of the well-known ones
+    pub type_aliases: BTreeMap<String, String>,
+    /// The features a run is scoped to (`--features`); items `#[cfg]`-ed out without them are
+    /// expected on neither side. `None` expects every item.
+    pub features: Option<BTreeSet<String>>,
+    /// Naming conventions of code items (`[[naming]]`)
+    pub naming: Vec<NamingRule>,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self { const_values: true, bodies: false, docs: false, order: false, unspecced_deprecated: true, param_names: true, bound_placement: true, superset_attributes: Vec::new(), abi_attributes: ABI_ATTRIBUTES.map(str::to_string).to_vec(), type_aliases: BTreeMap::new(), features: None, naming: Vec::new() }
+    }
+}
+
+/// Drop the differences `options` leave out
+pub fn relax(result: &mut ComparisonResult, options: &CompareOptions, ignored_attributes: &[String]) {
+    // Before the other relaxations, which don't apply to ABI changes
+    separate_abi_mismatches(result, &options.abi_attributes, ignored_attributes);
+    normalize_type_paths(result, &options.type_aliases);
+    if !options.bound_placement {
+        normalize_generics(result);
+    }
+    if !options.superset_attributes.is_empty() {
+        allow_extra_attributes(result, &options.superset_attributes, ignored_attributes);
+    }
+    if !options.const_values {
+        ignore_const_values(result);
+    }
+    if !options.bodies {
+        ignore_function_bodies(result);
+    }
+    if !options.param_names {
+        ignore_param_names(result);
+    }
+    if options.unspecced_deprecated {
+        allow_unspecced_deprecated(result);
+    }
+    if let Some(features) = &options.features {
+        drop_disabled(result, features);
+    }
+}
+
+/// The attributes of the built-in `abi` class, which set an item's layout or linkage
+pub const ABI_ATTRIBUTES: [&str; 4] = ["repr", "no_mangle", "export_name", "link_section"];
+
+/// Report the signature and attribute mismatches that change an item's ABI as ABI mismatches:
+/// those where the `abi` attributes or a function's `extern` differ
+pub fn separate_abi_mismatches(result: &mut ComparisonResult, abi_attributes: &[String], ignored_attributes: &[String]) {
+    let mut found: Vec<AbiMismatch> = Vec::new();
+    let mut add = |code_item: &RustItem, spec_item: &RustItem, differences: Vec<String>| {
+        match found.iter_mut().find(|mismatch| mismatch.code_item == *code_item && mismatch.code_item.line_number == code_item.line_number) {
+            Some(mismatch) => mismatch.differences.extend(differences),
+            None => found.push(AbiMismatch { code_item: code_item.clone(), spec_item: spec_item.clone(), differences }),
+        }
+    };
+    result.signature_mismatches.retain(|mismatch| {
+        if function_abi(&mismatch.code_item) == function_abi(&mismatch.spec_item) {
+            return true;
+        }
+        add(&mismatch.code_item, &mismatch.spec_item, mismatch.differences.clone());
+        false
+    });
+    let abi_only = |item: &RustItem| -> Vec<String> {
+        normalize_attributes(&item.attributes, ignored_attributes).into_iter()
+            .filter(|attr| attribute_name(attr).is_some_and(|name| abi_attributes.contains(&name)))
+            .collect()
+    };
+    result.attribute_mismatches.retain(|mismatch| {
+        let (code, spec) = (abi_only(&mismatch.code_item), abi_only(&mismatch.spec_item));
+        if code == spec {
+            return true;
+        }
+        let list = |attrs: Vec<String>| if attrs.is_empty() { "none".to_string() } else { attrs.join(", ") };
+        add(&mismatch.code_item, &mismatch.spec_item, vec![format!("ABI attributes: {} (spec: {})", list(code), list(spec))]);
+        false
+    });
+    found.sort_by_key(|mismatch| mismatch.code_item.line_number);
+    result.abi_mismatches.extend(found);
+}
+
+/// The ABI a function's tokens declare, with a bare `extern` as `extern "C"`; `None` for
+/// Rust's own and for other items
+fn function_abi(item: &RustItem) -> Option<String> {
+    use proc_macro2::{TokenStream, TokenTree};
+    if !matches!(item.kind, ItemKind::Function | ItemKind::Method { .. } | ItemKind::TraitMethod { .. }) {
+        return None;
+    }
+    let tokens: Vec<TokenTree> = item.tokens.parse::<TokenStream>().ok()?.into_iter().collect();
+    let is_ident = |tree: &TokenTree, name: &str| matches!(tree, TokenTree::Ident(ident) if ident == name);
+    let fn_at = tokens.iter().position(|tree| is_ident(tree, "fn"))?;
+    let extern_at = tokens[..fn_at].iter().position(|tree| is_ident(tree, "extern"))?;
+    Some(match tokens.get(extern_at + 1) {
+        Some(TokenTree::Literal(abi)) => format!("extern {}", abi),
+        _ => "extern \"C\"".to_string(),
+    })
+}
+
+/// Drop the `#[deprecated]` code items missing from the spec, which are on their way out, and
+/// leave them out of coverage
+fn allow_unspecced_deprecated(result: &mut ComparisonResult) {
+    let missing = result.missing_in_spec.len();
+    result.missing_in_spec.retain(|item| !is_deprecated(item));
+    result.code_items = result.code_items.saturating_sub(missing - result.missing_in_spec.len());
+}
+
+/// Drop the findings of items that aren't built with exactly `features` enabled
+pub fn drop_disabled(result: &mut ComparisonResult, features: &BTreeSet<String>) {
+    let enabled = |item: &RustItem| cfg::is_enabled(item, features);
+    result.missing_in_spec.retain(enabled);
+    result.missing_in_code.retain(enabled);
+    result.signature_mismatches.retain(|mismatch| enabled(&mismatch.code_item) || enabled(&mismatch.spec_item));
+    result.attribute_mismatches.retain(|mismatch| enabled(&mismatch.code_item) || enabled(&mismatch.spec_item));
+    result.discriminant_mismatches.retain(|mismatch| enabled(&mismatch.code_item) || enabled(&mismatch.spec_item));
+    result.value_mismatches.retain(|mismatch| enabled(&mismatch.code_item) || enabled(&mismatch.spec_item));
+    result.abi_mismatches.retain(|mismatch| enabled(&mismatch.code_item) || enabled(&mismatch.spec_item));
+    result.forbidden_present.retain(enabled);
+    result.probable_renames.retain(|rename| enabled(&rename.code_item) || enabled(&rename.spec_item));
+    result.duplicates.retain(|duplicate| enabled(&duplicate.duplicate));
+    result.doc_mismatches.retain(|mismatch| enabled(&mismatch.code_item) || enabled(&mismatch.spec_item));
+    result.ordering_violations.retain(|violation| enabled(&violation.code_item) || enabled(&violation.spec_item));
+    result.deprecated_in_code.retain(|deprecated| enabled(&deprecated.code_item) || enabled(&deprecated.spec_item));
+    result.naming_violations.retain(|violation| enabled(&violation.code_item));
+}
+
+/// Drop the signature mismatches that go away once both sides spell paths the same way
+/// (`std::vec::Vec<Vec3>` as `Vec<Vec3>`), and compare the rest in that spelling
+pub fn normalize_type_paths(result: &mut ComparisonResult, aliases: &BTreeMap<String, String>) {
+    result.signature_mismatches.retain_mut(|mismatch| {
+        let code = type_paths::normalize(&mismatch.code_item.tokens, aliases);
+        let spec = type_paths::normalize(&mismatch.spec_item.tokens, aliases);
+        if code == mismatch.code_item.tokens && spec == mismatch.spec_item.tokens {
+            return true;
+        }
+        if placeholder::has_placeholders(&spec) {
+            let matched = placeholder::matches(&code, &spec);
+            mismatch.code_item.tokens = code;
+            mismatch.spec_item.tokens = spec;
+            return !matched;
+        }
+        if code == spec {
+            return false;
+        }
+        mismatch.differences = signature_diff::differences(&code, &spec);
+        mismatch.field_diffs = signature_diff::struct_field_diffs(&code, &spec);
+        mismatch.variant_diffs = signature_diff::enum_variant_diffs(&code, &spec);
+        // The rest of `relax` compares the normalized forms too
+        mismatch.code_item.tokens = code;
+        mismatch.spec_item.tokens = spec;
+        true
+    });
+}
+
+/// Drop the signature mismatches whose only difference is where generic bounds are written,
+/// and compare the rest with their bounds in a sorted where clause, for `normalize-generics = true`
+pub fn normalize_generics(result: &mut ComparisonResult) {
+    result.signature_mismatches.retain_mut(|mismatch| {
+        let (Some(code), Some(spec)) = (signature_diff::with_canonical_generics(&mismatch.code_item.tokens), signature_diff::with_canonical_generics(&mismatch.spec_item.tokens)) else {
+            return true;
+        };
+        if placeholder::has_placeholders(&spec) {
+            let matched = placeholder::matches(&code, &spec);
+            mismatch.code_item.tokens = code;
+            mismatch.spec_item.tokens = spec;
+            return !matched;
+        }
+        if code == spec {
+            return false;
+        }
+        mismatch.differences = signature_diff::differences(&code, &spec);
+        mismatch.code_item.tokens = code;
+        mismatch.spec_item.tokens = spec;
+        true
+    });
+}
+
+/// Drop the value mismatches, and the signature mismatches of consts and statics whose only
+/// difference is their value, for `compare-const-values = false`
+pub fn ignore_const_values(result: &mut ComparisonResult) {
+    result.value_mismatches.clear();
+    result.signature_mismatches.retain(|mismatch| {
+        match (rust_parser::without_value(&mismatch.code_item.tokens), rust_parser::without_value(&mismatch.spec_item.tokens)) {
+            (Some(code), Some(spec)) => code != spec,
+            _ => true,
+        }
+    });
+}
+
+/// Drop the attribute mismatches where the only difference is that the code has more of the
+/// `superset` attributes than the spec; all others are still compared exactly
+pub fn allow_extra_attributes(result: &mut ComparisonResult, superset: &[String], ignored_attributes: &[String]) {
+    let is_superset = |attr: &String| attribute_name(attr).is_some_and(|name| superset.contains(&name));
+    result.attribute_mismatches.retain(|mismatch| {
+        let mut code = normalize_attributes(&mismatch.code_item.attributes, ignored_attributes);
+        let spec = normalize_attributes(&mismatch.spec_item.attributes, ignored_attributes);
+        for attr in &spec {
+            match code.iter().position(|code_attr| code_attr == attr) {
+                Some(found) => { code.remove(found); }
+                None => return true,
+            }
+        }
+        // What's left is extra in the code
+        !code.iter().all(is_superset)
+    });
+}
+
+/// The path an attribute starts with, `derive` for `#[derive(Debug)]`
+fn attribute_name(attr: &str) -> Option<String> {
+    let attrs = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attr).ok()?;
+    let path = attrs.first()?.path();
+    Some(path.segments.iter().map(|segment| segment.ident.to_string()).collect::<Vec<_>>().join("::"))
+}
+
+/// Drop the signature mismatches of functions whose only difference is their body, and the
+/// body from the differences of the rest, for `compare-bodies = false`. Specs often give a
+/// function `{}` or `unimplemented!()` for a body, or none at all, placeholders or not.
+pub fn ignore_function_bodies(result: &mut ComparisonResult) {
+    result.signature_mismatches.retain_mut(|mismatch| {
+        match (rust_parser::without_body(&mismatch.code_item.tokens), rust_parser::without_body(&mismatch.spec_item.tokens)) {
+            (Some(code), Some(spec)) => {
+                mismatch.differences.retain(|difference| difference != signature_diff::BODY_DIFFERS);
+                mismatch.bodies_ignored = true;
+                if placeholder::has_placeholders(&spec) {
+                    return !placeholder::matches(&code, &spec);
+                }
+                code != spec
+            }
+            _ => true,
+        }
+    });
+}
+
+/// Drop the signature mismatches of functions whose only difference is what their parameters
+/// are called, and those names from the differences of the rest, for `ignore-param-names = true`
+pub fn ignore_param_names(result: &mut ComparisonResult) {
+    result.signature_mismatches.retain_mut(|mismatch| {
+        match (signature_diff::without_param_names(&mismatch.code_item.tokens), signature_diff::without_param_names(&mismatch.spec_item.tokens)) {
+            (Some(code), Some(spec)) => {
+                mismatch.differences.retain(|difference| !signature_diff::is_param_name(difference));
+                code != spec
+            }
+            _ => true,
+        }
+    });
+}
+
+/// Variants whose discriminant differs between two versions of an enum. Only enums with an
+/// explicit discriminant or a `#[repr]` on either side count, since their values are a wire or FFI contract.
+fn discriminant_changes(code_item: &RustItem, spec_item: &RustItem) -> Vec<(String, String, String)> {
+    if code_item.kind != ItemKind::Enum {
+        return Vec::new();
+    }
+    let (Ok(code), Ok(spec)) = (syn::parse_str::<syn::ItemEnum>(&code_item.tokens), syn::parse_str::<syn::ItemEnum>(&spec_item.tokens)) else {
+        return Vec::new();
+    };
+    let has_repr = |item: &RustItem| item.attributes.iter().any(|attr| attr.contains("repr"));
+    let has_explicit = |item: &syn::ItemEnum| item.variants.iter().any(|v| v.discriminant.is_some());
+    if !(has_repr(code_item) || has_repr(spec_item) || has_explicit(&code) || has_explicit(&spec)) {
+        return Vec::new();
+    }
+
+    let spec_values: HashMap<String, String> = discriminants(&spec).into_iter().collect();
+    discriminants(&code).into_iter()
+        .filter_map(|(variant, code_value)| {
+            let spec_value = spec_values.get(&variant)?;
+            (*spec_value != code_value).then(|| (variant, spec_value.clone(), code_value))
+        })
+        .collect()
+}
+
+/// The values of a const or static as `(spec, code)`, when nothing else about it differs.
+/// Integers are given in decimal, so `0x10` and `16` are the same value.
+fn values(code_item: &RustItem, spec_item: &RustItem) -> Option<(String, String)> {
+    if !matches!(code_item.kind, ItemKind::Const | ItemKind::Static)
+        || rust_parser::without_value(&code_item.tokens)? != rust_parser::without_value(&spec_item.tokens)? {
+        return None;
+    }
+    let value = |tokens: &str| {
+        let expr = match syn::parse_str::<syn::Item>(tokens).ok()? {
+            syn::Item::Const(item) => item.expr,
+            syn::Item::Static(item) => item.expr,
+            _ => return None,
+        };
+        Some(integer(&expr).map_or_else(|| quote::quote!(#expr).to_string(), |value| value.to_string()))
+    };
+    Some((value(&spec_item.tokens)?, value(&code_item.tokens)?))
+}
+
+/// Each variant's value: explicit integer literals in decimal, implicit ones counted on from the
+/// previous variant, and other expressions as written (with `+ n` when counted on from one)
+fn discriminants(item: &syn::ItemEnum) -> Vec<(String, String)> {
+    let mut values = Vec::new();
+    let mut previous: Option<(Option<i128>, String, i128)> = None;
+    for variant in &item.variants {
+        let (number, base, offset) = match &variant.discriminant {
+            Some((_, expr)) => match integer(expr) {
+                Some(value) => (Some(value), String::new(), 0),
+                None => (None, quote::quote!(#expr).to_string(), 0),
+            },
+            None => match &previous {
+                None => (Some(0), String::new(), 0),
+                Some((Some(value), _, _)) => (Some(value + 1), String::new(), 0),
+                Some((None, base, offset)) => (None, base.clone(), offset + 1),
+            },
+        };
+        let shown = match number {
+            Some(value) => value.to_string(),
+            None if offset == 0 => base.clone(),
+            None => format!("{} + {}", base, offset),
+        };
+        values.push((variant.ident.to_string(), shown));
+        previous = Some((number, base, offset));
+    }
+    values
+}
+
+/// The value of an integer literal, possibly negated
+fn integer(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(int), .. }) => int.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Neg(_), expr, .. }) => integer(expr).map(|value| -value),
+        syn::Expr::Paren(paren) => integer(&paren.expr),
+        _ => None,
     }
 }
 
@@ -194,4 +1122,411 @@ mod tests {
         assert_eq!(result.attribute_mismatches.len(), 1);
         assert!(result.has_errors());
     }
+
+    #[test]
+    fn test_derives_compare_as_sets() {
+        let item = |attributes: &[&str]| {
+            RustItem::new("Foo".to_string(), ItemKind::Struct, "struct Foo {}".to_string(), quote!(struct Foo {}), attributes.iter().map(|a| a.to_string()).collect(), 1)
+        };
+        let reordered = compare_items(vec![item(&["#[derive(Debug, Clone)]"])], vec![item(&["# [derive (Clone , Debug ,)]"])], &[]);
+        assert!(!reordered.has_errors());
+        let split = compare_items(vec![item(&["#[derive(Debug)]", "#[inline]"])], vec![item(&["#[inline]", "#[derive(Debug)]"])], &[]);
+        assert!(!split.has_errors());
+        let merged = compare_items(vec![item(&["#[derive(Debug)]", "#[derive(Clone)]"])], vec![item(&["#[derive(Clone, Debug)]"])], &[]);
+        assert!(!merged.has_errors());
+        let extra = compare_items(vec![item(&["#[derive(Debug, Clone)]"])], vec![item(&["#[derive(Debug)]"])], &[]);
+        assert_eq!(extra.attribute_mismatches.len(), 1);
+    }
+
+    #[test]
+    fn test_code_superset_attributes() {
+        let item = |attributes: &[&str]| {
+            RustItem::new("Foo".to_string(), ItemKind::Struct, "struct Foo {}".to_string(), quote!(struct Foo {}), attributes.iter().map(|a| a.to_string()).collect(), 1)
+        };
+        let superset = ["derive".to_string()];
+        let compare = |code: &[&str], spec: &[&str]| {
+            let mut result = compare_items(vec![item(code)], vec![item(spec)], &[]);
+            allow_extra_attributes(&mut result, &superset, &[]);
+            result.attribute_mismatches.len()
+        };
+        assert_eq!(compare(&["#[derive(Debug, Serialize)]"], &["#[derive(Debug)]"]), 0);
+        assert_eq!(compare(&["#[derive(Serialize)]"], &["#[derive(Debug)]"]), 1);
+        assert_eq!(compare(&["#[derive(Debug)]", "#[non_exhaustive]"], &["#[derive(Debug)]"]), 1);
+        assert_eq!(compare(&["#[derive(Debug)]"], &["#[derive(Debug)]", "#[repr(C)]"]), 1);
+    }
+
+    #[test]
+    fn test_repeated_item_reuses_spec_item() {
+        // e.g. a function defined twice under different `#[cfg]`s
+        let code_items = vec![
+            RustItem::new("foo".to_string(), ItemKind::Function, "fn foo(a: u8)".to_string(), quote!(fn foo(a: u8)), vec!["#[inline]".to_string()], 1),
+            RustItem::new("foo".to_string(), ItemKind::Function, "fn foo(a: u16)".to_string(), quote!(fn foo(a: u16)), vec![], 5),
+        ];
+        let spec_items = vec![
+            RustItem::new("foo".to_string(), ItemKind::Function, "fn foo(a: u32)".to_string(), quote!(fn foo(a: u32)), vec![], 1),
+        ];
+
+        let result = compare_items(code_items, spec_items, &[]);
+        assert_eq!(result.signature_mismatches.len(), 2);
+        assert_eq!(result.attribute_mismatches.len(), 1);
+        assert!(result.signature_mismatches.iter().all(|m| m.spec_item.signature == "fn foo(a: u32)"));
+        assert_eq!(result.attribute_mismatches[0].code_item.line_number, 1);
+        assert!(result.missing_in_code.is_empty() && result.missing_in_spec.is_empty());
+    }
+
+    #[test]
+    fn test_truncate_findings() {
+        let code_items = vec![
+            RustItem::new("Foo".to_string(), ItemKind::Struct, "struct Foo {}".to_string(), quote!(struct Foo {}), vec![], 1),
+            RustItem::new("Bar".to_string(), ItemKind::Struct, "struct Bar {}".to_string(), quote!(struct Bar {}), vec![], 2),
+        ];
+        let spec_items = vec![
+            RustItem::new("Baz".to_string(), ItemKind::Struct, "struct Baz {}".to_string(), quote!(struct Baz {}), vec![], 1),
+        ];
+
+        let mut result = compare_items(code_items, spec_items, &[]);
+        assert_eq!(result.finding_count(), 3);
+
+        result.truncate(2);
+        assert_eq!(result.finding_count(), 2);
+        assert_eq!(result.missing_in_spec.len(), 2);
+        assert!(result.missing_in_code.is_empty());
+    }
+
+    #[test]
+    fn test_retain_rules_drops_turned_off_rules() {
+        let code_items = vec![RustItem::new("Foo".to_string(), ItemKind::Struct, "struct Foo {}".to_string(), quote!(struct Foo {}), vec![], 1)];
+        let spec_items = vec![RustItem::new("Baz".to_string(), ItemKind::Struct, "struct Baz {}".to_string(), quote!(struct Baz {}), vec![], 1)];
+
+        let mut result = compare_items(code_items, spec_items, &[]);
+        result.retain_rules(|rule| rule != rules::MISSING_IN_SPEC);
+        assert!(result.missing_in_spec.is_empty());
+        assert_eq!(result.missing_in_code.len(), 1);
+    }
+
+    #[test]
+    fn test_discriminant_changes() {
+        let item = |tokens: proc_macro2::TokenStream, attributes: Vec<String>| RustItem::new("Status".to_string(), ItemKind::Enum, tokens.to_string(), tokens, attributes, 1);
+        let code = item(quote!(pub enum Status { Ok = 0, Err = 2, Retry, Other }), vec![]);
+        let spec = item(quote!(pub enum Status { Ok = 0, Err = 1, Retry }), vec![]);
+
+        let result = compare_items(vec![code], vec![spec], &[]);
+        assert!(result.signature_mismatches.is_empty());
+        assert_eq!(result.discriminant_mismatches[0].changes, vec![
+            ("Err".to_string(), "1".to_string(), "2".to_string()),
+            ("Retry".to_string(), "2".to_string(), "3".to_string()),
+        ]);
+
+        // Reordering implicit values only matters for a `#[repr]` enum
+        let plain = compare_items(vec![item(quote!(enum Status { B, A }), vec![])], vec![item(quote!(enum Status { A, B }), vec![])], &[]);
+        assert_eq!(plain.signature_mismatches.len(), 1);
+        let repr = vec!["# [repr (u8)]".to_string()];
+        let reordered = compare_items(vec![item(quote!(enum Status { B, A }), repr.clone())], vec![item(quote!(enum Status { A, B }), repr)], &[]);
+        assert_eq!(reordered.discriminant_mismatches[0].changes.len(), 2);
+    }
+
+    #[test]
+    fn test_value_mismatches() {
+        let code = "pub const MAX: usize = 0x80;\npub const MIN: i32 = -(4);\npub static NAME: &str = \"mesh\";\npub const SCALE: f32 = 2.0 * 0.5;\npub const LIMIT: u64 = 16;\npub enum Level { Low = 0x1, High = 2 }\n";
+        let spec = "```rust\npub const MAX: usize = 64;\npub const MIN: i32 = -4;\npub static NAME: &str = \"curve\";\npub const SCALE: f32 = 1.0;\npub const LIMIT: usize = 16;\npub enum Level { Low = 1, High = 3 }\n```\n";
+        let result = compare_items(crate::parse_rust_items(code, Default::default()).unwrap(), crate::extract_spec_items(spec, Default::default()), &[]);
+
+        let values: Vec<(&str, &str, &str)> = result.value_mismatches.iter().map(|mismatch| (mismatch.code_item.name.as_str(), mismatch.code.as_str(), mismatch.spec.as_str())).collect();
+        assert_eq!(values, [("MAX", "128", "64"), ("NAME", "\"mesh\"", "\"curve\""), ("SCALE", "2.0 * 0.5", "1.0")]);
+        assert_eq!(result.value_mismatches[0].spec_item.line_number, 2);
+        // A changed type is more than a changed value
+        assert_eq!(result.signature_mismatches.iter().map(|mismatch| mismatch.code_item.name.as_str()).collect::<Vec<_>>(), ["LIMIT"]);
+        // Discriminants compare by value too
+        assert_eq!(result.discriminant_mismatches[0].changes, vec![("High".to_string(), "3".to_string(), "2".to_string())]);
+    }
+
+    #[test]
+    fn test_cfg_aware_comparison() {
+        let code = "#[cfg(all(unix, feature = \"vr\"))]\npub struct Headset;\n#[cfg(feature = \"audio\")]\npub fn play() {}\npub struct Mesh;\n";
+        let spec = "```rust,feature=vr\n#[cfg(unix)]\npub struct Headset;\n```\n\n```rust\npub struct Mesh;\n```\n";
+        let compare = |features: Option<&[&str]>| {
+            let code = crate::parse_rust_items(code, Default::default()).unwrap();
+            let mut result = compare_items(code, crate::extract_spec_items(spec, Default::default()), &[]);
+            let features = features.map(|features| features.iter().map(|feature| feature.to_string()).collect());
+            relax(&mut result, &CompareOptions { features, ..CompareOptions::default() }, &[]);
+            result
+        };
+
+        // The block's feature and the item's own `cfg` make up the code's
+        let all = compare(None);
+        assert!(all.attribute_mismatches.is_empty());
+        assert_eq!(all.missing_in_spec.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["play"]);
+        assert!(!compare(Some(&["vr"])).has_errors());
+    }
+
+    #[test]
+    fn test_ignore_const_values() {
+        let item = |tokens: proc_macro2::TokenStream| RustItem::new("MAX".to_string(), ItemKind::Const, tokens.to_string(), tokens, vec![], 1);
+        let mut result = compare_items(
+            vec![item(quote!(pub const MAX: usize = 128;))],
+            vec![item(quote!(pub const MAX: usize = 64;))],
+            &[],
+        );
+        // A changed value is a value mismatch, not a signature mismatch
+        assert!(result.signature_mismatches.is_empty());
+        assert_eq!((result.value_mismatches[0].code.as_str(), result.value_mismatches[0].spec.as_str()), ("128", "64"));
+        ignore_const_values(&mut result);
+        assert!(!result.has_errors());
+
+        let hex = compare_items(vec![item(quote!(pub const MAX: usize = 0x40;))], vec![item(quote!(pub const MAX: usize = 64;))], &[]);
+        assert!(!hex.has_errors());
+
+        let mut retyped = compare_items(vec![item(quote!(pub const MAX: u64 = 64;))], vec![item(quote!(pub const MAX: usize = 64;))], &[]);
+        ignore_const_values(&mut retyped);
+        assert_eq!(retyped.signature_mismatches.len(), 1);
+    }
+
+    #[test]
+    fn test_ignore_function_bodies() {
+        let item = |tokens: proc_macro2::TokenStream| RustItem::new("lerp".to_string(), ItemKind::Function, tokens.to_string(), tokens, vec![], 1);
+        let code = || item(quote!(pub fn lerp(a: f32, t: f32) -> f32 { a * t }));
+        let mut result = compare_items(vec![code()], vec![item(quote!(pub fn lerp(a: f32, t: f32) -> f32 { unimplemented!() }))], &[]);
+        assert_eq!(result.signature_mismatches.len(), 1);
+        relax(&mut result, &CompareOptions::default(), &[]);
+        assert!(!result.has_errors());
+
+        let mut retyped = compare_items(vec![code()], vec![item(quote!(pub fn lerp(a: f32, t: f64) -> f32 {}))], &[]);
+        relax(&mut retyped, &CompareOptions::default(), &[]);
+        assert_eq!(retyped.signature_mismatches[0].differences, ["parameter 2 type: `f32` (spec: `f64`)"]);
+
+        let mut strict = compare_items(vec![code()], vec![item(quote!(pub fn lerp(a: f32, t: f32) -> f32 {}))], &[]);
+        relax(&mut strict, &CompareOptions { bodies: true, ..CompareOptions::default() }, &[]);
+        assert_eq!(strict.signature_mismatches[0].differences, ["body differs"]);
+
+        // Placeholder specs, with a body or without one, match code that has a body
+        let spec = |block: &str| crate::rust_parser::parse_spec_block(block, 1, crate::config::Visibility::Pub).unwrap();
+        let frame = || {
+            let tokens = quote!(pub fn frame(curve: &Curve, t: f32) -> (Vec3, Vec3) { (curve.tangent(t), curve.normal(t)) });
+            RustItem::new("frame".to_string(), ItemKind::Function, tokens.to_string(), tokens, vec![], 1)
+        };
+        for block in ["pub fn frame(curve: &Curve, t: f32) -> (..);", "pub fn frame(curve: &Curve, t: f32) -> (..) {}", "pub fn frame(/* ... */) -> (Vec3, Vec3);"] {
+            let mut placeholder = compare_items(vec![frame()], spec(block), &[]);
+            relax(&mut placeholder, &CompareOptions::default(), &[]);
+            assert!(!placeholder.has_errors(), "{}", block);
+        }
+        let mut retyped = compare_items(vec![frame()], spec("pub fn frame(curve: &Curve, t: f64) -> (..);"), &[]);
+        relax(&mut retyped, &CompareOptions::default(), &[]);
+        assert_eq!(retyped.signature_mismatches.len(), 1);
+    }
+
+    #[test]
+    fn test_ignore_param_names() {
+        let method = |tokens: proc_macro2::TokenStream| RustItem {
+            kind: ItemKind::Method { type_name: "Curve".to_string() },
+            ..RustItem::new("sample".to_string(), ItemKind::Function, tokens.to_string(), tokens, vec![], 1)
+        };
+        let code = || method(quote!(pub fn sample(&self, resolution: usize) -> Vec<f32>));
+        let mut renamed = compare_items(vec![code()], vec![method(quote!(pub fn sample(&self, n: usize) -> Vec<f32>))], &[]);
+        assert_eq!(renamed.signature_mismatches.len(), 1);
+        ignore_param_names(&mut renamed);
+        assert!(!renamed.has_errors());
+
+        let mut retyped = compare_items(vec![code()], vec![method(quote!(pub fn sample(&self, n: u32) -> Vec<f32>))], &[]);
+        ignore_param_names(&mut retyped);
+        assert_eq!(retyped.signature_mismatches[0].differences, ["parameter 2 type: `usize` (spec: `u32`)"]);
+    }
+
+    #[test]
+    fn test_normalize_type_paths() {
+        let item = |tokens: proc_macro2::TokenStream| RustItem::new("Mesh".to_string(), ItemKind::Struct, tokens.to_string(), tokens, vec![], 1);
+        let code = || item(quote!(pub struct Mesh { pub points: Vec<Vec3>, pub scale: f32 }));
+        let mut spelled_out = compare_items(vec![code()], vec![item(quote!(pub struct Mesh { pub points: std::vec::Vec<Vec3>, pub scale: Real }))], &[]);
+        normalize_type_paths(&mut spelled_out, &BTreeMap::new());
+        assert_eq!(spelled_out.signature_mismatches[0].field_diffs.len(), 1);
+        normalize_type_paths(&mut spelled_out, &BTreeMap::from([("Real".to_string(), "f32".to_string())]));
+        assert!(!spelled_out.has_errors());
+    }
+
+    #[test]
+    fn test_normalize_generics() {
+        let item = |tokens: proc_macro2::TokenStream| RustItem::new("f".to_string(), ItemKind::Function, tokens.to_string(), tokens, vec![], 1);
+        let mut moved = compare_items(vec![item(quote!(fn f<T: Clone>(x: T) {}))], vec![item(quote!(fn f<T>(x: T) where T: Clone {}))], &[]);
+        assert_eq!(moved.signature_mismatches.len(), 1);
+        normalize_generics(&mut moved);
+        assert!(!moved.has_errors());
+
+        let mut rebound = compare_items(vec![item(quote!(fn f<T: Clone>(x: T) {}))], vec![item(quote!(fn f<T>(x: T) where T: Copy {}))], &[]);
+        normalize_generics(&mut rebound);
+        assert_eq!(rebound.signature_mismatches[0].differences, ["where clause: `where T : Clone` (spec: `where T : Copy`)"]);
+    }
+
+    #[test]
+    fn test_stages_of_missing_spec_items() {
+        let item = |name: &str, stage: Stage| RustItem { stage, ..RustItem::new(name.to_string(), ItemKind::Function, format!("fn {}()", name), quote!(fn f()), vec![], 1) };
+        let spec = || vec![
+            item("planned", Stage::Planned),
+            item("stable", Stage::Stable),
+            item("deprecated", Stage::Deprecated { removal: Some("0.10".to_string()) }),
+        ];
+        let missing = |version| compare_items_at(vec![], spec(), &[], version).missing_in_code.into_iter().map(|i| i.name).collect::<Vec<_>>();
+
+        assert_eq!(missing(None), ["stable", "deprecated"]);
+        assert_eq!(missing(Some("0.9.3")), ["stable", "deprecated"]);
+        assert_eq!(missing(Some("0.10.0")), ["stable"]);
+    }
+
+    #[test]
+    fn test_deprecated_code_items() {
+        let code = crate::parse_rust_items(concat!(
+            "#[deprecated]\npub fn draw() {}\n#[deprecated(note = \"use blend\")]\n#[inline]\npub fn mix() {}\n",
+            "#[deprecated]\npub fn legacy() {}\n#[deprecated]\npub fn old() {}\npub fn blend() {}\n",
+        ), Default::default()).unwrap();
+        let spec = crate::extract_spec_items(concat!(
+            "```rust\npub fn draw() {}\npub fn mix() {}\npub fn blend() {}\n```\n\n",
+            "```rust,deprecated\npub fn legacy() {}\n```\n",
+        ), Default::default());
+        let mut result = compare_items(code, spec, &[]);
+        let names = |items: Vec<&RustItem>| items.into_iter().map(|item| item.name.clone()).collect::<Vec<_>>();
+
+        // The spec's `deprecated` stage expects the attribute, and other attributes still count
+        assert_eq!(names(result.deprecated_in_code.iter().map(|d| &d.code_item).collect()), ["draw", "mix"]);
+        assert_eq!(names(result.attribute_mismatches.iter().map(|m| &m.code_item).collect()), ["mix"]);
+        assert_eq!(names(result.missing_in_spec.iter().collect()), ["old"]);
+
+        relax(&mut result, &CompareOptions::default(), &[]);
+        assert!(result.missing_in_spec.is_empty());
+        assert_eq!(result.code_items, 4);
+    }
+
+    #[test]
+    fn test_abi_mismatches() {
+        let code = crate::parse_rust_items(concat!(
+            "#[repr(u16)]\n#[derive(Debug)]\npub enum Opcode { Nop }\n#[repr(C)]\n#[derive(Clone)]\npub struct Point { pub x: f32 }\n",
+            "pub extern \"system\" fn init() {}\npub extern \"C\" fn tick(n: u32) {}\npub fn run(n: u64) {}\n",
+        ), Default::default()).unwrap();
+        let spec = crate::extract_spec_items(concat!(
+            "```rust\n#[repr(u8)]\npub enum Opcode { Nop }\n#[repr(C)]\npub struct Point { pub x: f32 }\n",
+            "pub extern fn init() {}\npub extern fn tick(n: u64) {}\npub fn run(n: u32) {}\n```\n",
+        ), Default::default());
+        let mut result = compare_items(code, spec, &[]);
+        // A lax `derive` doesn't make the rest of `Opcode`'s attributes lax
+        let options = CompareOptions { superset_attributes: vec!["derive".to_string()], ..CompareOptions::default() };
+        relax(&mut result, &options, &[]);
+
+        let abi: Vec<(&str, &[String])> = result.abi_mismatches.iter().map(|m| (m.code_item.name.as_str(), &m.differences[..])).collect();
+        assert_eq!(abi.len(), 2);
+        assert_eq!(abi[0], ("Opcode", &["ABI attributes: # [repr (u16)] (spec: # [repr (u8)])".to_string()][..]));
+        assert_eq!(abi[1].0, "init");
+        // A bare `extern` is `extern "C"`, so `tick` only changed its parameter
+        let names: Vec<&str> = result.signature_mismatches.iter().map(|m| m.code_item.name.as_str()).collect();
+        assert_eq!(names, ["tick", "run"]);
+        assert!(result.attribute_mismatches.is_empty());
+    }
+
+    #[test]
+    fn test_probable_renames() {
+        let item = |name: &str, tokens: proc_macro2::TokenStream| RustItem::new(name.to_string(), ItemKind::Function, tokens.to_string(), tokens, vec![], 1);
+        let code = vec![
+            item("lerp_clamped", quote!(pub fn lerp_clamped(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t })),
+            item("draw", quote!(pub fn draw(frame: &mut Frame) {})),
+        ];
+        let spec = vec![
+            item("lerp", quote!(pub fn lerp(a: f32, b: f32, t: f32) -> f32 {})),
+            item("render", quote!(pub fn render(scene: &Scene, camera: Camera) -> Image {})),
+        ];
+        let result = compare_items(code, spec, &[]);
+        assert_eq!(result.probable_renames.len(), 1);
+        let rename = &result.probable_renames[0];
+        assert_eq!((rename.code_item.name.as_str(), rename.spec_item.name.as_str()), ("lerp_clamped", "lerp"));
+        assert_eq!(rename.similarity, 1.0);
+        assert_eq!(result.missing_in_spec[0].name, "draw");
+        assert_eq!(result.missing_in_code[0].name, "render");
+    }
+
+    #[test]
+    fn test_forbidden_items() {
+        let item = |name: &str, stage: Stage| RustItem { stage, ..RustItem::new(name.to_string(), ItemKind::Function, format!("fn {}()", name), quote!(fn f()), vec![], 1) };
+        let spec = vec![item("draw", Stage::Stable), item("unsafe_raw_access", Stage::Forbidden), item("legacy_draw", Stage::Forbidden)];
+        let result = compare_items(vec![item("draw", Stage::Stable), item("unsafe_raw_access", Stage::Stable)], spec, &[]);
+        assert_eq!(result.forbidden_present.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), ["unsafe_raw_access"]);
+        assert!(result.missing_in_spec.is_empty() && result.missing_in_code.is_empty());
+        assert_eq!(result.finding_count(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_definitions() {
+        let item = |tokens: proc_macro2::TokenStream, cfg: &str, line: usize| {
+            let attributes = if cfg.is_empty() { vec![] } else { vec![cfg.to_string()] };
+            RustItem::new("scale".to_string(), ItemKind::Function, tokens.to_string(), tokens, attributes, line)
+        };
+        // Defined once per platform, which is no duplicate
+        let code = vec![
+            item(quote!(pub fn scale(by: f32)), "#[cfg(unix)]", 1),
+            item(quote!(pub fn scale(by: f64)), "#[cfg(windows)]", 4),
+        ];
+        let spec = vec![
+            item(quote!(pub fn scale(by: f32)), "#[cfg(unix)]", 2),
+            item(quote!(pub fn scale(by: f32)), "#[cfg(unix)]", 8),
+            item(quote!(pub fn scale(by: f64)), "#[cfg(unix)]", 12),
+        ];
+        let result = compare_items(code, spec, &[]);
+        assert_eq!(result.duplicates.len(), 1);
+        let duplicate = &result.duplicates[0];
+        assert!(duplicate.in_spec);
+        assert_eq!((duplicate.first.line_number, duplicate.duplicate.line_number), (2, 12));
+    }
+
+    #[test]
+    fn test_doc_mismatches() {
+        let code = crate::parse_rust_items(concat!(
+            "/// Eases `t`\n/// in and out.\npub fn ease(t: f32) -> f32 { t }\n",
+            "/// Blends two frames.\npub fn blend() {}\n",
+            "pub fn fade() {}\n",
+        ), Default::default()).unwrap();
+        let spec = crate::extract_spec_items(concat!(
+            "Eases *t* in and out.\n\n```rust\npub fn ease(t: f32) -> f32 {}\n```\n\n",
+            "```rust\n/// Blends three frames.\npub fn blend() {}\n/// Fades out.\npub fn fade() {}\n```\n",
+        ), Default::default());
+        let mismatches = doc_mismatches(&code, &spec);
+        let mismatches: Vec<(&str, &str)> = mismatches.iter()
+            .map(|mismatch| (mismatch.code_item.name.as_str(), mismatch.code.as_str()))
+            .collect();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0], ("blend", "Blends two frames."));
+        // The code without docs is held to what the spec documents
+        assert_eq!(mismatches[1], ("fade", ""));
+    }
+
+    #[test]
+    fn test_trait_impls() {
+        let code = "pub struct Sphere;\nimpl Geometry for Sphere {}\nimpl fmt::Debug for Sphere {}\n";
+        let code = || crate::parse_rust_items(code, Default::default()).unwrap();
+
+        // A spec without trait impls doesn't check them
+        let spec = crate::extract_spec_items("```rust\npub struct Sphere;\n```\n", Default::default());
+        let result = compare_items(code(), spec, &[]);
+        assert!(!result.has_errors());
+        assert_eq!(result.code_items, 1);
+
+        let spec = crate::extract_spec_items("```rust\npub struct Sphere;\nimpl Geometry for Sphere {}\nimpl Bounded for Sphere {}\n```\n", Default::default());
+        let result = compare_items(code(), spec, &[]);
+        let names = |items: &[RustItem]| items.iter().map(|item| item.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&result.missing_in_code), ["Bounded for Sphere"]);
+        assert_eq!(names(&result.missing_in_spec), ["Debug for Sphere"]);
+        assert!(result.signature_mismatches.is_empty());
+    }
+
+    #[test]
+    fn test_ordering_violations() {
+        let code = crate::parse_rust_items(concat!(
+            "pub fn blend() {}\npub fn ease() {}\npub fn fade() {}\npub fn extra() {}\npub fn clamp() {}\n",
+            "pub struct Frame;\nimpl Frame { pub fn width(&self) {} pub fn new() -> Self { Frame } }\n",
+        ), Default::default()).unwrap();
+        let spec = crate::extract_spec_items(concat!(
+            "```rust\npub fn clamp() {}\npub fn ease() {}\npub fn fade() {}\npub fn blend() {}\n```\n\n",
+            "```rust\nimpl Frame { pub fn new() -> Self {} pub fn width(&self) {} }\npub struct Frame;\n```\n",
+        ), Default::default());
+        let violations = ordering_violations(&code, &spec);
+        let violations: Vec<(&str, Option<&str>)> = violations.iter()
+            .map(|violation| (violation.code_item.name.as_str(), violation.after.as_ref().map(|after| after.name.as_str())))
+            .collect();
+        // `ease` and `fade` are in order, and methods are only ordered among themselves
+        assert_eq!(violations, [("blend", Some("fade")), ("clamp", None), ("width", Some("new"))]);
+        assert!(ordering_violations(&spec, &spec).is_empty());
+    }
 }
@@ -1,47 +1,679 @@
 use serde::Deserialize;
-use std::path::Path;
+use crate::comparator::{self, CompareOptions};
+use crate::naming::NamingRule;
+use crate::spec_format;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::fs;
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-#[derive(Debug, Deserialize, Default)]
+/// Directory for spec-check's cached state, relative to the project root
+pub const CACHE_DIR: &str = "target/spec-check";
+
+/// Log file used when neither the CLI nor Cargo.toml names one
+pub const DEFAULT_LOG_FILE: &str = "spec-check.log";
+
+/// How many rotated logs are kept without `log-keep`
+pub const DEFAULT_LOG_KEEP: usize = 5;
+
+/// Where accepted findings are read from, unless `baseline` or `--baseline` says otherwise
+pub const DEFAULT_BASELINE_FILE: &str = "spec-check-baseline.json";
+
+/// Every key accepted in `[package.metadata.spec-check]`; keep in sync with `Config`
+pub const KNOWN_KEYS: &[&str] = &[
+    "ignored-attributes",
+    "check-private",
+    "check-orphaned-specs",
+    "visibility",
+    "src-dir",
+    "spec-dir",
+    "spec-format",
+    "spec-layout",
+    "log-file",
+    "log-append",
+    "log-max-size",
+    "log-keep",
+    "wasm-rules",
+    "spec-annotations",
+    "webhook",
+    "webhook-headers",
+    "scope",
+    "translations",
+    "compare-const-values",
+    "compare-bodies",
+    "compare-docs",
+    "compare-order",
+    "allow-unspecced-deprecated",
+    "ignore-param-names",
+    "attribute-policy",
+    "attribute-classes",
+    "type-aliases",
+    "normalize-generics",
+    "severity",
+    "baseline",
+    "source-of-truth",
+    "mapping",
+    "naming",
+    "include",
+    "exclude",
+    "extra-roots",
+    "features",
+    "expand",
+    "min-coverage",
+    "max-bump",
+    "format",
+    "profile",
+];
+
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct Config {
     #[serde(rename = "ignored-attributes")]
     pub ignored_attributes: Option<Vec<String>>,
     #[serde(rename = "check-private")]
     pub check_private: Option<bool>,
+    /// Report spec files that no source file maps to
+    #[serde(rename = "check-orphaned-specs")]
+    pub check_orphaned_specs: Option<bool>,
+    /// The least visible items checked, in place of `check-private`
+    pub visibility: Option<Visibility>,
     #[serde(rename = "src-dir")]
     pub src_dir: Option<String>,
     #[serde(rename = "spec-dir")]
     pub spec_dir: Option<String>,
+    /// The format of the spec files walked and mirrored, `markdown` unless set
+    #[serde(rename = "spec-format")]
+    pub spec_format: Option<spec_format::Format>,
+    /// Where a source file's spec is, `mirror` unless set
+    #[serde(rename = "spec-layout")]
+    pub spec_layout: Option<SpecLayout>,
     #[serde(rename = "log-file")]
     pub log_file: Option<String>,
+    /// Add each run to the log instead of replacing it
+    #[serde(rename = "log-append")]
+    pub log_append: Option<bool>,
+    /// Size in bytes at which the log is rotated to `<log-file>.1` before a run
+    #[serde(rename = "log-max-size")]
+    pub log_max_size: Option<u64>,
+    /// How many rotated logs are kept, 5 unless set
+    #[serde(rename = "log-keep")]
+    pub log_keep: Option<usize>,
+    #[serde(rename = "wasm-rules")]
+    pub wasm_rules: Option<Vec<WasmRuleConfig>>,
+    /// Honor `#[spec_checked]` annotations (off by default, since it means reading every source file)
+    #[serde(rename = "spec-annotations")]
+    pub spec_annotations: Option<bool>,
+    /// URL that receives the JSON results of `check` and `ci` runs
+    pub webhook: Option<String>,
+    /// `Name: value` headers for the webhook; `${VAR}` is read from the environment
+    #[serde(rename = "webhook-headers")]
+    pub webhook_headers: Option<Vec<String>>,
+    /// Whether items are matched per file pair or across the whole project
+    pub scope: Option<Scope>,
+    /// Localized spec trees for `translations`, reference language first
+    pub translations: Option<Vec<String>>,
+    /// Compare the values of consts and statics, not just their names and types (on by default)
+    #[serde(rename = "compare-const-values")]
+    pub compare_const_values: Option<bool>,
+    /// Compare the bodies of functions, not just their signatures (off by default)
+    #[serde(rename = "compare-bodies")]
+    pub compare_bodies: Option<bool>,
+    /// Compare doc comments with the spec's doc comments or prose (off by default)
+    #[serde(rename = "compare-docs")]
+    pub compare_docs: Option<bool>,
+    /// Require items in the order the spec has them (off by default)
+    #[serde(rename = "compare-order")]
+    pub compare_order: Option<bool>,
+    /// Let `#[deprecated]` code items be missing from the spec (on by default)
+    #[serde(rename = "allow-unspecced-deprecated")]
+    pub allow_unspecced_deprecated: Option<bool>,
+    /// Compare only the types of function parameters, not their names (off by default)
+    #[serde(rename = "ignore-param-names")]
+    pub ignore_param_names: Option<bool>,
+    /// How each attribute, by name, is compared (e.g. `derive = "code-superset-ok"`)
+    #[serde(rename = "attribute-policy")]
+    pub attribute_policy: Option<BTreeMap<String, AttributePolicy>>,
+    /// Named sets of attributes with one policy for all of them, over the built-in `abi` class
+    #[serde(rename = "attribute-classes")]
+    pub attribute_classes: Option<BTreeMap<String, AttributeClass>>,
+    /// Paths the spec and code may write differently, and the form they're compared in
+    /// (e.g. `"crate::math::Vec3" = "Vec3"`)
+    #[serde(rename = "type-aliases")]
+    pub type_aliases: Option<BTreeMap<String, String>>,
+    /// Compare generic bounds wherever they're written, inline or in a where clause (off by default)
+    #[serde(rename = "normalize-generics")]
+    pub normalize_generics: Option<bool>,
+    /// How severe each rule's findings are, by rule name or code (e.g. `missing-in-spec = "warning"`)
+    pub severity: Option<BTreeMap<String, Severity>>,
+    /// File of accepted findings, written by `--write-baseline`
+    pub baseline: Option<String>,
+    /// Which side is right when code and spec disagree, if either
+    #[serde(rename = "source-of-truth")]
+    pub source_of_truth: Option<SourceOfTruth>,
+    /// `[[mapping]]` rules pairing source files with spec files by glob, ahead of the mirrored paths
+    pub mapping: Option<Vec<MappingRule>>,
+    /// `[[naming]]` conventions for the names of code items
+    pub naming: Option<Vec<NamingRule>>,
+    /// Globs of the only source and spec files to check, relative to the project root
+    pub include: Option<Vec<String>>,
+    /// Globs of source and spec files to leave out, relative to the project root
+    pub exclude: Option<Vec<String>>,
+    /// Source directories checked besides `src-dir`, e.g. `tests` against `spec/tests`
+    #[serde(rename = "extra-roots")]
+    pub extra_roots: Option<Vec<String>>,
+    /// The features runs are scoped to, as with `--features`
+    pub features: Option<Vec<String>>,
+    /// Also expect the items macros generate, as with `--expand`
+    pub expand: Option<bool>,
+    /// The least spec coverage, in percent, a run passes with, as with `--min-coverage`
+    #[serde(rename = "min-coverage")]
+    pub min_coverage: Option<f64>,
+    /// The largest version bump the drift may need, as with `--max-bump`
+    #[serde(rename = "max-bump")]
+    pub max_bump: Option<crate::semver::Bump>,
+    /// Where `check` reports results when `--format` isn't given, as `--format` takes them
+    pub format: Option<Vec<String>>,
+    /// Named sets of settings over these ones, picked with `--profile`
+    pub profile: Option<BTreeMap<String, Config>>,
+}
+
+/// How the attributes of one name are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AttributePolicy {
+    /// The code has exactly the spec's, even if `ignored-attributes` lists the name
+    Exact,
+    /// The code has at least the spec's, and may have more (e.g. extra derives)
+    CodeSupersetOk,
+    /// Never compared, like a name in `ignored-attributes`
+    Ignore,
+}
+
+/// Attributes compared alike, e.g. `abi = { attributes = ["repr"], policy = "exact" }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttributeClass {
+    /// The attribute names in the class; for `abi`, in place of the built-in ones
+    pub attributes: Option<Vec<String>>,
+    /// How they are compared, `exact` unless set
+    pub policy: Option<AttributePolicy>,
+}
+
+/// The attribute class whose differences are reported as ABI mismatches
+pub const ABI_CLASS: &str = "abi";
+
+/// How code items find the spec items they are compared against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Each source file against the spec file at the mirrored path
+    #[default]
+    File,
+    /// Every source item against every spec item, by qualified name
+    Project,
+}
+
+/// How a source file's path becomes its spec's
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecLayout {
+    /// The same path in the spec directory: `src/anim/mod.rs` -> `spec/anim/mod.md`
+    #[default]
+    Mirror,
+    /// The module's path, so a `mod.rs` is specced like the file it could have been:
+    /// `src/anim/mod.rs` and `src/anim.rs` -> `spec/anim.md`
+    Module,
+}
+
+/// The side that is right when code and spec disagree: findings say what to change on the
+/// other, and `--fix` rewrites it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceOfTruth {
+    /// The spec documents the code, so it's the spec that's out of date
+    Code,
+    /// The code implements the spec, so it's the code that's out of date
+    Spec,
+}
+
+/// The least visible items spec-check checks: `pub(crate)` takes in crate-internal APIs
+/// without every private helper
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, clap::ValueEnum)]
+pub enum Visibility {
+    /// Public items only
+    #[default]
+    #[serde(rename = "pub")]
+    #[value(name = "pub")]
+    Pub,
+    /// Public and `pub(crate)` items
+    #[serde(rename = "pub(crate)")]
+    #[value(name = "pub(crate)")]
+    PubCrate,
+    /// Every item, private ones included
+    #[serde(rename = "all")]
+    #[value(name = "all")]
+    All,
+}
+
+impl Visibility {
+    /// What `check-private` stands for
+    pub fn from_check_private(check_private: bool) -> Self {
+        if check_private { Self::All } else { Self::Pub }
+    }
+
+    /// The value as written in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pub => "pub",
+            Self::PubCrate => "pub(crate)",
+            Self::All => "all",
+        }
+    }
+}
+
+/// How much a finding matters; `--fail-on` picks the lowest one that fails the run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Not checked at all
+    #[value(skip)]
+    Off,
+    Info,
+    Warning,
+    #[default]
+    Error,
+}
+
+/// A custom rule in a WASM module: `{ code = "ACME001", module = "checks/acme.wasm" }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WasmRuleConfig {
+    pub code: String,
+    pub name: Option<String>,
+    pub summary: Option<String>,
+    /// Path to the module, relative to the project root
+    pub module: String,
+    /// Command that runs a WASI module given its path (default `wasmtime run`)
+    pub runner: Option<Vec<String>>,
+}
+
+/// Source files checked together against a set of spec files, for specs that cover a whole
+/// directory or are split in several: `{ rust = "src/animation/**/*.rs", spec = "spec/animation.md" }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MappingRule {
+    /// Glob of the source files, relative to the project root; `**` matches any number of directories
+    pub rust: String,
+    /// Glob of their spec files, relative to the project root; a plain path may be outside the
+    /// spec directory, such as a `SPEC.md` for the whole crate
+    pub spec: String,
+    /// Level-2 headings of the spec files name modules (`## math`), and the items under each
+    /// are only matched with that module's code
+    #[serde(default)]
+    pub headings: bool,
+}
+
+impl MappingRule {
+    pub fn matches_rust(&self, path: &Path) -> bool {
+        crate::glob::matches_path(&self.rust, &slashed(path))
+    }
+
+    pub fn matches_spec(&self, path: &Path) -> bool {
+        crate::glob::matches_path(&self.spec, &slashed(path))
+    }
+
+    /// The rule with both globs relative to `dir` instead, e.g. a workspace member's directory
+    pub fn within(&self, dir: &Path) -> MappingRule {
+        let join = |pattern: &str| slashed(&dir.join(pattern));
+        MappingRule { rust: join(&self.rust), spec: join(&self.spec), headings: self.headings }
+    }
+}
+
+/// Which files under the source and spec directories are walked: those `include` matches, or
+/// all of them without it, except what `exclude` matches. Hidden files and directories are left
+/// out unless an `include` glob names one, and so is what `.gitignore` and `.ignore` files
+/// ignore unless `no_ignore` is set.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_ignore: bool,
+    /// Source directories walked besides the source directory, e.g. `tests`, each mirrored by
+    /// the subdirectory of the spec directory with its name
+    pub roots: Vec<PathBuf>,
+    /// The format of the spec files walked, and of the spec paths source files mirror to
+    pub spec_format: spec_format::Format,
+    /// How source paths become spec paths
+    pub spec_layout: SpecLayout,
+}
+
+impl FileFilter {
+    pub fn allows(&self, path: &Path) -> bool {
+        let path = slashed(path);
+        (self.include.is_empty() || self.include.iter().any(|pattern| crate::glob::matches_path(pattern, &path)))
+            && !self.exclude.iter().any(|pattern| crate::glob::matches_path(pattern, &path))
+    }
+
+    /// Whether some `include` glob names a hidden file or directory, so they have to be walked
+    pub fn walks_hidden(&self) -> bool {
+        self.include.iter().any(|pattern| pattern.split('/').any(|part| part.starts_with('.') && part != "." && part != ".."))
+    }
+
+    /// The filter with its globs and roots relative to `dir` instead, e.g. a workspace member's directory
+    pub fn within(&self, dir: &Path) -> FileFilter {
+        let join = |patterns: &[String]| patterns.iter().map(|pattern| slashed(&dir.join(pattern))).collect();
+        FileFilter {
+            include: join(&self.include),
+            exclude: join(&self.exclude),
+            no_ignore: self.no_ignore,
+            roots: self.roots.iter().map(|root| dir.join(root)).collect(),
+            spec_format: self.spec_format,
+            spec_layout: self.spec_layout,
+        }
+    }
+}
+
+/// A path as the globs of `[[mapping]]` rules see it, with `/` separators on every platform
+fn slashed(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// What a spec file's front matter sets for itself, over `[package.metadata.spec-check]`:
+///
+/// ```text
+/// ---
+/// check-private: true
+/// ignored-attributes: [serde]
+/// rust: animation/**/*.rs
+/// ---
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecSettings {
+    #[serde(rename = "check-private")]
+    pub check_private: Option<bool>,
+    pub visibility: Option<Visibility>,
+    /// Ignored in this file on top of the configured `ignored-attributes`
+    #[serde(rename = "ignored-attributes")]
+    pub ignored_attributes: Option<Vec<String>>,
+    /// Glob of the source files the spec is for, relative to the source directory, in place of
+    /// the mirrored path; they are checked together against it, as under a `[[mapping]]` rule
+    pub rust: Option<String>,
+}
+
+impl SpecSettings {
+    /// The settings of a spec's front matter, if it has any. YAML front matter is read as flat
+    /// `key: value` lines, with lists written `[a, b]`.
+    pub fn from_markdown(markdown: &str) -> Result<Self> {
+        let Some(front) = crate::markdown_parser::front_matter(markdown) else { return Ok(Self::default()) };
+        let text = if front.toml { front.text.to_string() } else { yaml_to_toml(front.text)? };
+        Ok(toml::from_str(&text)?)
+    }
+
+    pub fn visibility(&self, configured: Visibility) -> Visibility {
+        self.visibility.or(self.check_private.map(Visibility::from_check_private)).unwrap_or(configured)
+    }
+
+    pub fn ignored_attributes(&self, configured: &[String]) -> Vec<String> {
+        configured.iter().chain(self.ignored_attributes.iter().flatten()).cloned().collect()
+    }
+}
+
+/// Flat YAML as TOML: `key: value` lines, with bare strings quoted
+fn yaml_to_toml(yaml: &str) -> Result<String> {
+    let scalar = |value: &str| -> String {
+        let value = value.trim();
+        let literal = matches!(value, "true" | "false") || value.parse::<f64>().is_ok() || value.starts_with(['"', '\'']);
+        if literal { value.to_string() } else { format!("{:?}", value) }
+    };
+    let mut toml = String::new();
+    for line in yaml.lines().map(str::trim_end).filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#')) {
+        let Some((key, value)) = line.split_once(':').filter(|(key, value)| !key.starts_with(' ') && !value.trim().is_empty()) else {
+            anyhow::bail!("front matter line `{}` isn't a `key: value` pair; nested YAML isn't supported", line.trim());
+        };
+        let value = value.trim();
+        let value = match value.strip_prefix('[').and_then(|list| list.strip_suffix(']')) {
+            Some(list) => format!("[{}]", list.split(',').filter(|item| !item.trim().is_empty()).map(scalar).collect::<Vec<_>>().join(", ")),
+            None => scalar(value),
+        };
+        toml.push_str(&format!("{} = {}\n", key.trim(), value));
+    }
+    Ok(toml)
+}
+
+/// File that overrides settings for the subtree of the source or spec directory it's in
+pub const DIRECTORY_CONFIG_FILE: &str = ".spec-check.toml";
+
+/// The settings a `.spec-check.toml` overrides for its subtree, written as in
+/// `[package.metadata.spec-check]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DirectoryConfig {
+    /// In place of the configured list
+    pub ignored_attributes: Option<Vec<String>>,
+    /// Over the configured severities, by rule name or code
+    pub severity: Option<BTreeMap<String, Severity>>,
+    /// Over the configured policies, by attribute name
+    pub attribute_policy: Option<BTreeMap<String, AttributePolicy>>,
+}
+
+impl DirectoryConfig {
+    /// These settings with those of a deeper `.spec-check.toml` over them
+    fn then(mut self, inner: &DirectoryConfig) -> Self {
+        if inner.ignored_attributes.is_some() {
+            self.ignored_attributes = inner.ignored_attributes.clone();
+        }
+        if let Some(severity) = &inner.severity {
+            self.severity.get_or_insert_with(BTreeMap::new).extend(severity.clone());
+        }
+        if let Some(policy) = &inner.attribute_policy {
+            self.attribute_policy.get_or_insert_with(BTreeMap::new).extend(policy.clone());
+        }
+        self
+    }
+
+    /// The attributes left out of the comparison in this subtree, given the configured ones
+    pub fn ignored_attributes(&self, configured: &[String]) -> Vec<String> {
+        let mut ignored = match &self.ignored_attributes {
+            Some(names) => names.iter().cloned().chain([crate::annotations::ATTRIBUTE, crate::requirements::ATTRIBUTE].map(str::to_string)).collect(),
+            None => configured.to_vec(),
+        };
+        let policies = self.attribute_policy.iter().flatten();
+        ignored.retain(|name| !policies.clone().any(|(policy_name, policy)| policy_name == name && *policy != AttributePolicy::Ignore));
+        ignored.extend(policies.filter(|(_, policy)| **policy == AttributePolicy::Ignore).map(|(name, _)| name.clone()));
+        ignored
+    }
+
+    /// How items are compared in this subtree, given the configured options
+    pub fn compare_options(&self, configured: &CompareOptions) -> CompareOptions {
+        let mut options = configured.clone();
+        for (name, policy) in self.attribute_policy.iter().flatten() {
+            options.superset_attributes.retain(|superset| superset != name);
+            if *policy == AttributePolicy::CodeSupersetOk {
+                options.superset_attributes.push(name.clone());
+            }
+            if *policy != AttributePolicy::Exact {
+                options.abi_attributes.retain(|abi| abi != name);
+            }
+        }
+        options
+    }
+
+    /// The severity of a rule, by code, if this subtree overrides it
+    pub fn severity(&self, code: &str) -> Option<Severity> {
+        self.severity.iter().flatten().find(|(rule, _)| rule_code(rule) == code).map(|(_, severity)| *severity)
+    }
+}
+
+/// The `.spec-check.toml` files of the source and spec directories, by the subtree they cover:
+/// `src/protocol/.spec-check.toml` and `spec/protocol/.spec-check.toml` both cover `protocol/`
+/// in both directories, the spec directory's settings over the source directory's
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryOverrides {
+    roots: Vec<PathBuf>,
+    subtrees: BTreeMap<PathBuf, DirectoryConfig>,
+}
+
+impl DirectoryOverrides {
+    pub fn load(src: &Path, spec: &Path) -> Result<Self> {
+        let mut subtrees: BTreeMap<PathBuf, DirectoryConfig> = BTreeMap::new();
+        for root in [src, spec] {
+            let files = walkdir::WalkDir::new(root)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name() == DIRECTORY_CONFIG_FILE && e.file_type().is_file());
+            for file in files {
+                let path = file.path();
+                let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                let config: DirectoryConfig = toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+                let subtree = path.parent().and_then(|dir| dir.strip_prefix(root).ok()).unwrap_or(Path::new("")).to_path_buf();
+                let merged = subtrees.remove(&subtree).unwrap_or_default().then(&config);
+                subtrees.insert(subtree, merged);
+            }
+        }
+        Ok(Self { roots: vec![src.to_path_buf(), spec.to_path_buf()], subtrees })
+    }
+
+    /// The overrides for a file in either directory, those of deeper subtrees over shallower ones
+    pub fn for_file(&self, file: &Path) -> DirectoryConfig {
+        let Some(relative) = self.roots.iter().find_map(|root| file.strip_prefix(root).ok()) else {
+            return DirectoryConfig::default();
+        };
+        let mut dirs: Vec<&Path> = relative.ancestors().skip(1).collect();
+        dirs.reverse();
+        dirs.into_iter().filter_map(|dir| self.subtrees.get(dir)).fold(DirectoryConfig::default(), DirectoryConfig::then)
+    }
+
+    /// The severity of a rule's findings in `file`: as its subtree overrides it, or as configured
+    pub fn severity(&self, code: &str, file: &Path, configured: &BTreeMap<String, Severity>) -> Severity {
+        self.for_file(file).severity(code).or_else(|| configured.get(code).copied()).unwrap_or_default()
+    }
+}
+
+/// A rule's code, from its name or code
+/// The code of a rule given by code or name; custom rules' codes as they are
+pub fn rule_code(rule: &str) -> String {
+    crate::rules::find(rule).map_or_else(|| rule.to_string(), |rule| rule.code.to_string())
 }
 
 #[derive(Debug, Deserialize)]
 struct CargoToml {
     package: Option<Package>,
+    workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Workspace {
+    members: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    metadata: Option<WorkspaceMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceMetadata {
+    #[serde(rename = "spec-check")]
+    spec_check: Option<WorkspaceConfig>,
+}
+
+/// `[workspace.metadata.spec-check]`: settings for every member, and per member by package
+/// name under `crates`, read as if written in the member's own table
+#[derive(Debug, Deserialize, Default)]
+pub struct WorkspaceConfig {
+    #[serde(flatten)]
+    pub defaults: Config,
+    pub crates: Option<BTreeMap<String, Config>>,
+}
+
+impl WorkspaceConfig {
+    /// The workspace's settings for one member: its `crates` entry over the defaults
+    pub fn for_crate(&self, name: &str) -> Config {
+        match self.crates.as_ref().and_then(|crates| crates.get(name)) {
+            Some(config) => config.clone().or(self.defaults.clone()),
+            None => self.defaults.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct Package {
+    name: Option<String>,
+    // May be `{ workspace = true }`, so don't insist on a string here
+    version: Option<toml::Value>,
     metadata: Option<Metadata>,
 }
 
+/// The `[package]` identity from Cargo.toml
+#[derive(Debug, Clone)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: String,
+}
+
+/// A package of the workspace rooted at the current directory
+#[derive(Debug, Clone)]
+pub struct WorkspaceMember {
+    pub name: String,
+    /// Relative to the workspace root; empty for the root package
+    pub dir: PathBuf,
+}
+
 #[derive(Debug, Deserialize)]
 struct Metadata {
     #[serde(rename = "spec-check")]
     spec_check: Option<Config>,
 }
 
+fn load_cargo_toml() -> Result<Option<CargoToml>> {
+    load_cargo_toml_at(Path::new("Cargo.toml"))
+}
+
+fn load_cargo_toml_at(cargo_toml_path: &Path) -> Result<Option<CargoToml>> {
+    if !cargo_toml_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(cargo_toml_path)?;
+    Ok(Some(toml::from_str(&content)?))
+}
+
+/// The raw `[package.metadata.spec-check]` table, for diagnosing unknown keys
+pub fn load_raw_metadata() -> Result<Option<toml::Table>> {
+    let cargo_toml_path = Path::new("Cargo.toml");
+    if !cargo_toml_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(cargo_toml_path)?;
+    let cargo: toml::Table = toml::from_str(&content)?;
+    Ok(cargo.get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("spec-check"))
+        .and_then(|t| t.as_table())
+        .cloned())
+}
+
+/// Read the package name and version from Cargo.toml, if it has a `[package]`
+pub fn load_package_info() -> Result<Option<PackageInfo>> {
+    load_package_info_at(Path::new("Cargo.toml"))
+}
+
+/// Like `load_package_info`, for a manifest that isn't in the current directory
+pub fn load_package_info_at(manifest: &Path) -> Result<Option<PackageInfo>> {
+    let package = load_cargo_toml_at(manifest)?.and_then(|cargo| cargo.package);
+    Ok(package.and_then(|p| {
+        let version = p.version?.as_str()?.to_string();
+        Some(PackageInfo { name: p.name?, version })
+    }))
+}
+
 impl Config {
     pub fn load_from_cargo_toml() -> Result<Self> {
-        let cargo_toml_path = Path::new("Cargo.toml");
-        if !cargo_toml_path.exists() {
-            return Ok(Config::default());
-        }
+        Self::load_from_manifest(Path::new("Cargo.toml"))
+    }
 
-        let content = fs::read_to_string(cargo_toml_path)?;
-        let cargo: CargoToml = toml::from_str(&content)?;
+    /// Like `load_from_cargo_toml`, for a manifest that isn't in the current directory
+    pub fn load_from_manifest(manifest: &Path) -> Result<Self> {
+        let Some(cargo) = load_cargo_toml_at(manifest)? else {
+            return Ok(Config::default());
+        };
 
         Ok(cargo.package
             .and_then(|p| p.metadata)
@@ -49,7 +681,329 @@ impl Config {
             .unwrap_or_default())
     }
 
+    /// These settings, with those left unset taken from `fallback`
+    pub fn or(self, fallback: Config) -> Config {
+        Config {
+            ignored_attributes: self.ignored_attributes.or(fallback.ignored_attributes),
+            check_private: self.check_private.or(fallback.check_private),
+            check_orphaned_specs: self.check_orphaned_specs.or(fallback.check_orphaned_specs),
+            visibility: self.visibility.or(fallback.visibility),
+            src_dir: self.src_dir.or(fallback.src_dir),
+            spec_dir: self.spec_dir.or(fallback.spec_dir),
+            spec_format: self.spec_format.or(fallback.spec_format),
+            spec_layout: self.spec_layout.or(fallback.spec_layout),
+            log_file: self.log_file.or(fallback.log_file),
+            log_append: self.log_append.or(fallback.log_append),
+            log_max_size: self.log_max_size.or(fallback.log_max_size),
+            log_keep: self.log_keep.or(fallback.log_keep),
+            wasm_rules: self.wasm_rules.or(fallback.wasm_rules),
+            spec_annotations: self.spec_annotations.or(fallback.spec_annotations),
+            webhook: self.webhook.or(fallback.webhook),
+            webhook_headers: self.webhook_headers.or(fallback.webhook_headers),
+            scope: self.scope.or(fallback.scope),
+            translations: self.translations.or(fallback.translations),
+            compare_const_values: self.compare_const_values.or(fallback.compare_const_values),
+            compare_bodies: self.compare_bodies.or(fallback.compare_bodies),
+            compare_docs: self.compare_docs.or(fallback.compare_docs),
+            compare_order: self.compare_order.or(fallback.compare_order),
+            allow_unspecced_deprecated: self.allow_unspecced_deprecated.or(fallback.allow_unspecced_deprecated),
+            ignore_param_names: self.ignore_param_names.or(fallback.ignore_param_names),
+            attribute_policy: self.attribute_policy.or(fallback.attribute_policy),
+            attribute_classes: self.attribute_classes.or(fallback.attribute_classes),
+            type_aliases: self.type_aliases.or(fallback.type_aliases),
+            normalize_generics: self.normalize_generics.or(fallback.normalize_generics),
+            severity: self.severity.or(fallback.severity),
+            baseline: self.baseline.or(fallback.baseline),
+            source_of_truth: self.source_of_truth.or(fallback.source_of_truth),
+            mapping: self.mapping.or(fallback.mapping),
+            naming: self.naming.or(fallback.naming),
+            include: self.include.or(fallback.include),
+            exclude: self.exclude.or(fallback.exclude),
+            extra_roots: self.extra_roots.or(fallback.extra_roots),
+            features: self.features.or(fallback.features),
+            expand: self.expand.or(fallback.expand),
+            min_coverage: self.min_coverage.or(fallback.min_coverage),
+            max_bump: self.max_bump.or(fallback.max_bump),
+            format: self.format.or(fallback.format),
+            profile: self.profile.or(fallback.profile),
+        }
+    }
+
+    /// The settings of a `[profile.NAME]` over these ones, or these ones without a name
+    pub fn with_profile(&self, name: Option<&str>) -> Result<Config> {
+        let Some(name) = name else { return Ok(self.clone()) };
+        let profiles = self.profile.clone().unwrap_or_default();
+        let Some(profile) = profiles.get(name) else {
+            let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            anyhow::bail!("No profile `{}` in [package.metadata.spec-check.profile] (profiles are: {})",
+                name, if known.is_empty() { "none".to_string() } else { known.join(", ") });
+        };
+        // Profiles don't nest
+        Ok(Config { profile: None, ..profile.clone().or(self.clone()) })
+    }
+
+    /// The least visible items checked, from `visibility` or else `check-private`
+    pub fn visibility(&self) -> Visibility {
+        self.visibility.or(self.check_private.map(Visibility::from_check_private)).unwrap_or_default()
+    }
+
+    /// The attributes of each class, by class name, with the built-in `abi` class unless it's
+    /// configured otherwise
+    pub fn attribute_classes(&self) -> BTreeMap<String, (Vec<String>, AttributePolicy)> {
+        let mut classes = BTreeMap::from([(ABI_CLASS.to_string(), (comparator::ABI_ATTRIBUTES.map(str::to_string).to_vec(), AttributePolicy::Exact))]);
+        for (name, class) in self.attribute_classes.iter().flatten() {
+            let (attributes, policy) = classes.entry(name.clone()).or_insert_with(|| (Vec::new(), AttributePolicy::Exact));
+            if let Some(names) = &class.attributes {
+                attributes.clone_from(names);
+            }
+            *policy = class.policy.unwrap_or(AttributePolicy::Exact);
+        }
+        classes
+    }
+
+    /// How attributes are compared, by name: by their class's policy, unless `attribute-policy`
+    /// names them
+    pub fn attribute_policies(&self) -> BTreeMap<String, AttributePolicy> {
+        let mut policies: BTreeMap<String, AttributePolicy> = self.attribute_classes().into_values()
+            .flat_map(|(attributes, policy)| attributes.into_iter().map(move |name| (name, policy)))
+            .collect();
+        policies.extend(self.attribute_policy.clone().unwrap_or_default());
+        policies
+    }
+
     pub fn get_ignored_attributes(&self) -> Vec<String> {
-        self.ignored_attributes.clone().unwrap_or_else(|| vec!["doc".to_string()])
+        let mut ignored = self.ignored_attributes.clone().unwrap_or_else(|| vec!["doc".to_string()]);
+        let policies = self.attribute_policies();
+        let policies = policies.iter();
+        ignored.retain(|name| !policies.clone().any(|(policy_name, policy)| policy_name == name && *policy != AttributePolicy::Ignore));
+        ignored.extend(policies.filter(|(_, policy)| **policy == AttributePolicy::Ignore).map(|(name, _)| name.clone()));
+        // The annotation and requirement references are for the checker, so the spec never repeats them
+        ignored.push(crate::annotations::ATTRIBUTE.to_string());
+        ignored.push(crate::requirements::ATTRIBUTE.to_string());
+        ignored
+    }
+
+    /// The source and spec files `include` and `exclude` leave to check
+    pub fn file_filter(&self) -> FileFilter {
+        FileFilter {
+            include: self.include.clone().unwrap_or_default(),
+            exclude: self.exclude.clone().unwrap_or_default(),
+            no_ignore: false,
+            roots: self.extra_roots.iter().flatten().map(PathBuf::from).collect(),
+            spec_format: self.spec_format.unwrap_or_default(),
+            spec_layout: self.spec_layout.unwrap_or_default(),
+        }
+    }
+
+    /// The configured severity of each rule, by code; rules not listed are errors
+    pub fn severities(&self) -> BTreeMap<String, Severity> {
+        self.severity.iter().flatten()
+            .map(|(rule, severity)| (rule_code(rule), *severity))
+            .collect()
+    }
+
+    /// Which optional parts of items are compared, defaults filled in
+    pub fn compare_options(&self) -> CompareOptions {
+        let policies = self.attribute_policies();
+        let defaults = CompareOptions::default();
+        CompareOptions {
+            const_values: self.compare_const_values.unwrap_or(defaults.const_values),
+            bodies: self.compare_bodies.unwrap_or(defaults.bodies),
+            docs: self.compare_docs.unwrap_or(defaults.docs),
+            order: self.compare_order.unwrap_or(defaults.order),
+            unspecced_deprecated: self.allow_unspecced_deprecated.unwrap_or(defaults.unspecced_deprecated),
+            param_names: self.ignore_param_names.map_or(defaults.param_names, |ignore| !ignore),
+            bound_placement: self.normalize_generics.map_or(defaults.bound_placement, |normalize| !normalize),
+            superset_attributes: policies.iter()
+                .filter(|(_, policy)| **policy == AttributePolicy::CodeSupersetOk)
+                .map(|(name, _)| name.clone())
+                .collect(),
+            abi_attributes: self.attribute_classes().remove(ABI_CLASS).map(|(attributes, _)| attributes).unwrap_or_default()
+                .into_iter()
+                .filter(|name| policies.get(name) == Some(&AttributePolicy::Exact))
+                .collect(),
+            type_aliases: self.type_aliases.clone().unwrap_or_default(),
+            features: self.features.as_ref().map(|features| features.iter().cloned().collect()),
+            naming: self.naming.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// The root package, if Cargo.toml has one, then the `[workspace]` members in the order
+/// they are listed, with `*` patterns expanded and `exclude` left out
+pub fn workspace_members() -> Result<Vec<WorkspaceMember>> {
+    let Some(cargo) = load_cargo_toml()? else {
+        return Ok(Vec::new());
+    };
+    let name_of = |dir: &Path, package: Option<&Package>| {
+        package.and_then(|p| p.name.clone())
+            .unwrap_or_else(|| dir.file_name().map_or("root".to_string(), |name| name.to_string_lossy().into_owned()))
+    };
+
+    let mut members = Vec::new();
+    if let Some(package) = &cargo.package {
+        members.push(WorkspaceMember { name: name_of(Path::new(""), Some(package)), dir: PathBuf::new() });
+    }
+    let Some(workspace) = cargo.workspace else {
+        return Ok(members);
+    };
+    let exclude: Vec<PathBuf> = workspace.exclude.unwrap_or_default().iter().map(PathBuf::from).collect();
+    for pattern in workspace.members.unwrap_or_default() {
+        for dir in expand_member(&pattern) {
+            let manifest = dir.join("Cargo.toml");
+            if exclude.contains(&dir) || !manifest.exists() || members.iter().any(|m: &WorkspaceMember| m.dir == dir) {
+                continue;
+            }
+            let package = load_cargo_toml_at(&manifest)?.and_then(|cargo| cargo.package);
+            members.push(WorkspaceMember { name: name_of(&dir, package.as_ref()), dir });
+        }
+    }
+    Ok(members)
+}
+
+/// `[workspace.metadata.spec-check]` of the Cargo.toml in the current directory, empty if it has none
+pub fn load_workspace_config() -> Result<WorkspaceConfig> {
+    Ok(load_cargo_toml()?
+        .and_then(|cargo| cargo.workspace)
+        .and_then(|workspace| workspace.metadata)
+        .and_then(|metadata| metadata.spec_check)
+        .unwrap_or_default())
+}
+
+/// Directories matching a `members` entry, where any path component may hold wildcards
+fn expand_member(pattern: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::new()];
+    for component in pattern.trim_end_matches('/').split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !crate::glob::is_pattern(component) {
+            dirs.iter_mut().for_each(|dir| dir.push(component));
+            continue;
+        }
+        let mut expanded = Vec::new();
+        for dir in &dirs {
+            let base = if dir.as_os_str().is_empty() { Path::new(".") } else { dir.as_path() };
+            let Ok(entries) = fs::read_dir(base) else { continue };
+            let mut names: Vec<String> = entries.filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|name| crate::glob::matches(component, name))
+                .collect();
+            names.sort();
+            expanded.extend(names.into_iter().map(|name| dir.join(name)));
+        }
+        dirs = expanded;
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_settings_per_crate() {
+        let cargo: CargoToml = toml::from_str(r#"
+            [workspace]
+            members = ["crates/*"]
+
+            [workspace.metadata.spec-check]
+            check-private = true
+            spec-dir = "docs/spec"
+
+            [workspace.metadata.spec-check.crates.geometry]
+            spec-dir = "../../spec/geometry"
+        "#).unwrap();
+        let workspace = cargo.workspace.and_then(|w| w.metadata).and_then(|m| m.spec_check).unwrap();
+
+        let geometry = workspace.for_crate("geometry");
+        assert_eq!(geometry.spec_dir.as_deref(), Some("../../spec/geometry"));
+        assert_eq!(geometry.check_private, Some(true));
+        assert_eq!(workspace.for_crate("render").spec_dir.as_deref(), Some("docs/spec"));
+
+        // A member's own table wins over the workspace's
+        let own = Config { check_private: Some(false), ..Config::default() }.or(geometry);
+        assert_eq!((own.check_private, own.spec_dir.as_deref()), (Some(false), Some("../../spec/geometry")));
+    }
+
+    #[test]
+    fn test_profile_over_config() {
+        let config: Config = toml::from_str(r#"
+            ignored-attributes = ["doc"]
+            severity = { missing-in-spec = "info" }
+
+            [profile.ci]
+            severity = { missing-in-spec = "error" }
+            format = ["sarif=results.sarif"]
+        "#).unwrap();
+
+        let ci = config.with_profile(Some("ci")).unwrap();
+        assert_eq!(ci.severities().get("SC001"), Some(&Severity::Error));
+        assert_eq!(ci.ignored_attributes, Some(vec!["doc".to_string()]));
+        assert_eq!(ci.format, Some(vec!["sarif=results.sarif".to_string()]));
+        assert_eq!(config.with_profile(None).unwrap().severities().get("SC001"), Some(&Severity::Info));
+        assert!(config.with_profile(Some("dev")).is_err());
+    }
+
+    #[test]
+    fn test_attribute_classes() {
+        let config: Config = toml::from_str(r#"
+            ignored-attributes = ["doc", "repr"]
+            attribute-policy = { no_mangle = "ignore" }
+
+            [attribute-classes]
+            abi = { attributes = ["repr", "no_mangle", "target_feature"] }
+            derives = { attributes = ["derive"], policy = "code-superset-ok" }
+        "#).unwrap();
+
+        // `abi` is exact unless an attribute's own policy says otherwise
+        let options = config.compare_options();
+        assert_eq!(options.abi_attributes, ["repr", "target_feature"]);
+        assert_eq!(options.superset_attributes, ["derive"]);
+        assert_eq!(config.get_ignored_attributes()[..2], ["doc", "no_mangle"]);
+        assert_eq!(Config::default().compare_options().abi_attributes, crate::comparator::ABI_ATTRIBUTES);
+    }
+
+    #[test]
+    fn test_spec_settings_from_front_matter() {
+        let yaml = "---\ncheck-private: true\nignored-attributes: [serde, \"cfg_attr\"]\nrust: animation/**/*.rs\n---\n# Animation\n";
+        let settings = SpecSettings::from_markdown(yaml).unwrap();
+        assert_eq!(settings, SpecSettings {
+            check_private: Some(true),
+            visibility: None,
+            ignored_attributes: Some(vec!["serde".to_string(), "cfg_attr".to_string()]),
+            rust: Some("animation/**/*.rs".to_string()),
+        });
+        assert_eq!(settings.ignored_attributes(&["doc".to_string()]), ["doc", "serde", "cfg_attr"]);
+
+        let toml = "+++\ncheck-private = false\n+++\n";
+        assert_eq!(SpecSettings::from_markdown(toml).unwrap().check_private, Some(false));
+        assert_eq!(SpecSettings::from_markdown("# No front matter\n").unwrap(), SpecSettings::default());
+        assert!(SpecSettings::from_markdown("---\nchek-private: true\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_directory_overrides_by_subtree() {
+        let dir = std::env::temp_dir().join(format!("spec-check-directories-{}", std::process::id()));
+        let (src, spec) = (dir.join("src"), dir.join("spec"));
+        fs::create_dir_all(src.join("protocol/v2")).unwrap();
+        fs::create_dir_all(spec.join("protocol")).unwrap();
+        fs::write(src.join("protocol").join(DIRECTORY_CONFIG_FILE), "ignored-attributes = [\"doc\", \"serde\"]\n[severity]\nmissing-in-spec = \"warning\"\n").unwrap();
+        fs::write(spec.join("protocol").join(DIRECTORY_CONFIG_FILE), "[attribute-policy]\nderive = \"code-superset-ok\"\n").unwrap();
+        fs::write(src.join("protocol/v2").join(DIRECTORY_CONFIG_FILE), "[severity]\nSC001 = \"info\"\n").unwrap();
+
+        let overrides = DirectoryOverrides::load(&src, &spec).unwrap();
+        let configured = BTreeMap::new();
+        assert_eq!(overrides.severity("SC001", &src.join("protocol/frame.rs"), &configured), Severity::Warning);
+        assert_eq!(overrides.severity("SC001", &src.join("protocol/v2/frame.rs"), &configured), Severity::Info);
+        assert_eq!(overrides.severity("SC001", &src.join("core.rs"), &configured), Severity::Error);
+
+        // The spec directory's file covers the same subtree of the source directory
+        let frame = overrides.for_file(&src.join("protocol/v2/frame.rs"));
+        assert_eq!(frame.ignored_attributes(&["doc".to_string()]), ["doc", "serde", crate::annotations::ATTRIBUTE, crate::requirements::ATTRIBUTE]);
+        assert_eq!(frame.compare_options(&CompareOptions::default()).superset_attributes, ["derive"]);
+        assert_eq!(overrides.for_file(&spec.join("core.md")), DirectoryConfig::default());
+
+        fs::write(src.join(DIRECTORY_CONFIG_FILE), "src-dir = \"lib\"\n").unwrap();
+        assert!(DirectoryOverrides::load(&src, &spec).is_err());
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,65 @@
+//! Spec coverage: how many of the checked code items a spec describes
+
+use crate::comparator::ComparisonResult;
+use std::fmt;
+use std::ops::AddAssign;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub items: usize,
+    pub specced: usize,
+}
+
+impl Coverage {
+    /// Of a source file compared with its spec: its items, less those missing from the spec
+    pub fn of(result: &ComparisonResult) -> Self {
+        let unspecced = result.missing_in_spec.len() + result.probable_renames.len() + result.unresolved_annotations.len();
+        Self { items: result.code_items, specced: result.code_items.saturating_sub(unspecced) }
+    }
+
+    /// Of a source file without a spec
+    pub fn unspecced(items: usize) -> Self {
+        Self { items, specced: 0 }
+    }
+
+    /// The share of items specced, from 0 to 100; a tree without items is fully covered
+    pub fn percent(&self) -> f64 {
+        if self.items == 0 { 100.0 } else { self.specced as f64 * 100.0 / self.items as f64 }
+    }
+}
+
+impl AddAssign for Coverage {
+    fn add_assign(&mut self, other: Self) {
+        self.items += other.items;
+        self.specced += other.specced;
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.items == 0 {
+            return write!(f, "no items");
+        }
+        // Rounded down, so a tree just short of `--min-coverage` doesn't show as meeting it
+        write!(f, "{}/{} item(s) specced ({}%)", self.specced, self.items, self.specced * 100 / self.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rust_parser::{ItemKind, RustItem};
+
+    #[test]
+    fn test_coverage_of_a_comparison() {
+        let item = |name: &str| RustItem::new(name.to_string(), ItemKind::Struct, String::new(), Default::default(), Vec::new(), 1);
+        let result = ComparisonResult { code_items: 4, missing_in_spec: vec![item("Mesh")], missing_in_code: vec![item("Gone")], ..Default::default() };
+        let mut total = Coverage::of(&result);
+        assert_eq!(total, Coverage { items: 4, specced: 3 });
+
+        total += Coverage::unspecced(2);
+        assert_eq!(total.to_string(), "3/6 item(s) specced (50%)");
+        assert_eq!(total.percent(), 50.0);
+        assert_eq!(Coverage::default().percent(), 100.0);
+    }
+}
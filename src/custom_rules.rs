@@ -0,0 +1,8 @@
+//! Organization-specific rules compiled into this build of spec-check.
+//!
+//! Implement `spec_check::plugin::Rule` and register it here, e.g.
+//! `plugin::register(my_rules::ErrorSection);`. Registered rules run on every
+//! checked file and their violations are reported like the built-in ones.
+
+/// Called once at startup, before arguments are parsed
+pub fn register() {}
@@ -0,0 +1,157 @@
+//! Items that derive and attribute macros generate, from the crate's macro-expanded source,
+//! for `--expand`
+
+use crate::cache::{self, Cache};
+use crate::config::Visibility;
+use crate::rust_parser::{self, RustItem};
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The items of the library crate once its macros are expanded
+#[derive(Debug, Clone, Default)]
+pub struct Expanded {
+    items: Vec<RustItem>,
+    /// Of the expanded source, so results cached without the expansion aren't reused
+    pub hash: u64,
+}
+
+impl Expanded {
+    /// Expand the library crate in the current directory with `cargo expand`, or with rustc's
+    /// `-Zunpretty=expanded` where cargo-expand isn't installed. `features`, when given, are
+    /// the only ones enabled. The expansion is cached, keyed by the manifest, `Cargo.lock`,
+    /// the features and what's in `src/` and `build.rs`.
+    pub fn load(features: Option<&BTreeSet<String>>, visibility: Visibility, cache: &Cache) -> Result<Self> {
+        let source = cache.get_or_insert_with("expanded", input_hash(features), || expand(features))?;
+        Self::from_source(&source, visibility)
+    }
+
+    pub fn from_source(source: &str, visibility: Visibility) -> Result<Self> {
+        let items = rust_parser::parse_rust_file(source, visibility).context("Failed to parse the expanded crate")?;
+        Ok(Self { items, hash: cache::hash(&[source.as_bytes()]) })
+    }
+
+    /// A source file's `items` and the ones only the expansion has, in the file's module at
+    /// `module` (`["math"]` for `src/math.rs`) and the inline modules the file declares items
+    /// in. An item a macro generates is given the line of the source item before it in the
+    /// expansion, which is usually the one whose derive or attribute generated it, and no column.
+    pub fn with_generated(&self, module: &[String], mut items: Vec<RustItem>) -> Vec<RustItem> {
+        let inline: HashSet<&[String]> = items.iter().map(|item| item.module.as_slice()).chain([&[][..]]).collect();
+        let mut own: Vec<RustItem> = self.items.iter()
+            .filter_map(|item| {
+                let relative = item.module.strip_prefix(module)?;
+                inline.contains(relative).then(|| RustItem { module: relative.to_vec(), ..item.clone() })
+            })
+            .collect();
+        own.sort_by_key(|item| item.line_number);
+
+        let mut line = 1;
+        let mut generated = Vec::new();
+        for mut item in own {
+            match items.iter().find(|source| **source == item) {
+                Some(source) => line = source.line_number,
+                None => {
+                    item.line_number = line;
+                    item.column = 0;
+                    generated.push(item);
+                }
+            }
+        }
+        items.extend(generated);
+        items
+    }
+}
+
+/// The expanded source of the library crate, with the `features` given
+fn expand(features: Option<&BTreeSet<String>>) -> Result<String> {
+    let mut feature_args = Vec::new();
+    if let Some(features) = features {
+        feature_args.push("--no-default-features".to_string());
+        feature_args.push(format!("--features={}", features.iter().cloned().collect::<Vec<_>>().join(",")));
+    }
+    let output = Command::new("cargo")
+        .args(["expand", "--lib", "--ugly"])
+        .args(&feature_args)
+        .output()
+        .context("Failed to run cargo expand")?;
+    let output = if !output.status.success() && String::from_utf8_lossy(&output.stderr).contains("no such command") {
+        Command::new("cargo")
+            .args(["rustc", "--lib", "--profile=check", "--quiet"])
+            .args(&feature_args)
+            .args(["--", "-Zunpretty=expanded"])
+            // `-Z` flags are for nightly, which the stable toolchain accepts with this. It's set
+            // for this cargo run only, never for the user's own builds.
+            .env("RUSTC_BOOTSTRAP", "1")
+            .output()
+            .context("Failed to run cargo rustc")?
+    } else {
+        output
+    };
+    if !output.status.success() {
+        anyhow::bail!("Failed to expand the crate's macros: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A hash of what the expansion of the crate in the current directory depends on
+fn input_hash(features: Option<&BTreeSet<String>>) -> u64 {
+    let mut files: Vec<PathBuf> = ["Cargo.toml", "Cargo.lock", "build.rs"].into_iter().map(PathBuf::from).collect();
+    let mut sources: Vec<PathBuf> = walkdir::WalkDir::new("src").into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    sources.sort();
+    files.extend(sources);
+
+    let features = features.map(|features| features.iter().cloned().collect::<Vec<_>>().join(","));
+    let mut parts: Vec<Vec<u8>> = vec![features.map_or_else(|| b"default".to_vec(), String::into_bytes)];
+    for file in &files {
+        parts.push(file.to_string_lossy().into_owned().into_bytes());
+        parts.push(fs::read(file).unwrap_or_default());
+    }
+    cache::hash(&parts.iter().map(Vec::as_slice).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_items_join_their_file() {
+        let source = "pub struct Config;\n\npub mod net {\n    pub struct Socket;\n}\n";
+        let expanded = concat!(
+            "pub struct Config;\n",
+            "pub struct ConfigBuilder { name: Option<String> }\n",
+            "impl ConfigBuilder { pub fn name(self, name: String) -> Self { self } }\n",
+            "pub mod net {\n    pub struct Socket;\n    pub struct SocketBuilder;\n}\n",
+            "pub mod math {\n    pub struct VectorBuilder;\n}\n",
+        );
+        let expanded = Expanded::from_source(expanded, Visibility::Pub).unwrap();
+
+        let items = expanded.with_generated(&[], rust_parser::parse_rust_file(source, Visibility::Pub).unwrap());
+        let names: Vec<(&str, usize)> = items.iter().map(|item| (item.name.as_str(), item.line_number)).collect();
+        // `math` is a file of its own, so its items are left to it
+        assert_eq!(names, [("Config", 1), ("Socket", 4), ("ConfigBuilder", 1), ("name", 1), ("SocketBuilder", 4)]);
+
+        let math = expanded.with_generated(&["math".to_string()], Vec::new());
+        assert_eq!(math.iter().map(|item| item.module.clone()).collect::<Vec<_>>(), [Vec::<String>::new()]);
+    }
+    #[test]
+    fn test_expansions_are_cached_by_their_inputs() {
+        let features: BTreeSet<String> = ["serde".to_string()].into();
+        assert_eq!(input_hash(None), input_hash(None));
+        assert_ne!(input_hash(None), input_hash(Some(&features)));
+
+        // A stored expansion is used without running cargo
+        let dir = std::env::temp_dir().join(format!("spec-check-expand-{}", std::process::id()));
+        let cache = Cache::new(Some(dir.clone()));
+        let source: String = cache.get_or_insert_with("expanded", input_hash(Some(&features)), || Ok("pub struct Stored;\n".to_string())).unwrap();
+        let expanded = Expanded::load(Some(&features), Visibility::Pub, &cache).unwrap();
+        assert_eq!(expanded.items.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["Stored"]);
+        assert_eq!(expanded.hash, cache::hash(&[source.as_bytes()]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,37 +1,353 @@
-use anyhow::Result;
+use crate::config::{FileFilter, MappingRule, SpecLayout, SpecSettings};
+use crate::ignore_files::Ignores;
+use crate::{git, glob};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::thread;
 use walkdir::WalkDir;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct FileMapping {
     pub rust_file: PathBuf,
+    /// The spec file, or the first of them under a `[[mapping]]` rule
     pub spec_file: Option<PathBuf>,
+    /// Under a `[[mapping]]` rule, every spec file of the rule: the rule's source files are
+    /// checked together against all of them. `None` for the mirrored path.
+    pub mapped_specs: Option<Vec<PathBuf>>,
+    /// Whether the level-2 headings of `mapped_specs` name the modules their items are in
+    pub headings: bool,
+    /// From the front matter of `spec_file`
+    pub settings: SpecSettings,
 }
 
-pub fn find_file_mappings(src_dir: &Path, spec_dir: &Path) -> Result<Vec<FileMapping>> {
+pub fn find_file_mappings(src_dir: &Path, spec_dir: &Path, rules: &[MappingRule], filter: &FileFilter) -> Result<Vec<FileMapping>> {
+    // One walk of each tree instead of a stat per source file, which is slow on network filesystems
+    let (rust_files, mut spec_files) = walk_trees(src_dir, spec_dir, filter);
+    spec_files.extend(rules.iter().map(|rule| PathBuf::from(&rule.spec))
+        .filter(|path| !glob::is_pattern(&path.to_string_lossy()) && !path.starts_with(spec_dir) && path.is_file()));
+    let settings = spec_settings(&spec_files, read_front_matter)?;
+    map_files(rust_files, spec_files, src_dir, spec_dir, rules, filter, &settings)
+}
+
+/// The front matter settings of the spec files that have any, by spec file
+fn spec_settings(spec_files: &[PathBuf], read: impl Fn(&Path) -> Result<Option<String>>) -> Result<HashMap<PathBuf, SpecSettings>> {
+    let mut settings = HashMap::new();
+    for spec_file in spec_files {
+        let Some(markdown) = read(spec_file)? else { continue };
+        let front = SpecSettings::from_markdown(&markdown).with_context(|| format!("Invalid front matter in {}", spec_file.display()))?;
+        settings.insert(spec_file.clone(), front);
+    }
+    Ok(settings)
+}
+
+/// A spec file's content if it starts with front matter, reading no further than its first
+/// fence when it doesn't
+fn read_front_matter(path: &Path) -> Result<Option<String>> {
+    let mut file = fs::File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut fence = [0; 3];
+    if file.read_exact(&mut fence).is_err() || !matches!(&fence, b"+++" | b"---") {
+        return Ok(None);
+    }
+    let mut rest = String::new();
+    file.read_to_string(&mut rest).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Some(format!("{}{}", String::from_utf8_lossy(&fence), rest)))
+}
+
+/// Pair each source file with its spec: by the `rust` glob of a spec's front matter, then the
+/// first `[[mapping]]` rule its path matches, or at the mirrored path. A spec file a rule
+/// claims is only ever checked under that rule.
+fn map_files(
+    rust_files: Vec<PathBuf>,
+    spec_files: Vec<PathBuf>,
+    src_dir: &Path,
+    spec_dir: &Path,
+    rules: &[MappingRule],
+    filter: &FileFilter,
+    settings: &HashMap<PathBuf, SpecSettings>,
+) -> Result<Vec<FileMapping>> {
+    let mut front_rules: Vec<MappingRule> = spec_files.iter()
+        .filter_map(|spec_file| {
+            let rust = settings.get(spec_file)?.rust.as_ref()?;
+            let rust = src_dir.join(rust).to_string_lossy().replace('\\', "/");
+            Some(MappingRule { rust, spec: spec_file.to_string_lossy().replace('\\', "/"), headings: false })
+        })
+        .collect();
+    front_rules.extend(rules.iter().cloned());
+    let rules = front_rules;
+    let settings_of = |spec_file: Option<&PathBuf>| spec_file.and_then(|spec_file| settings.get(spec_file)).cloned().unwrap_or_default();
+
+    let mut claimed = HashSet::new();
+    let mut rule_specs = Vec::new();
+    for rule in &rules {
+        let specs: Vec<PathBuf> = spec_files.iter().filter(|path| rule.matches_spec(path)).cloned().collect();
+        for spec in &specs {
+            if !claimed.insert(spec.clone()) {
+                anyhow::bail!("{} is matched by more than one [[mapping]] rule", spec.display());
+            }
+        }
+        rule_specs.push(specs);
+    }
+    let spec_files: HashSet<PathBuf> = spec_files.into_iter().filter(|path| !claimed.contains(path)).collect();
+
     let mut mappings = Vec::new();
+    for rust_file in rust_files {
+        if let Some(rule) = rules.iter().position(|rule| rule.matches_rust(&rust_file)) {
+            let specs = rule_specs[rule].clone();
+            let settings = settings_of(specs.first());
+            mappings.push(FileMapping { rust_file, spec_file: specs.first().cloned(), mapped_specs: Some(specs), headings: rules[rule].headings, settings });
+            continue;
+        }
+        let spec_path = mirrored_spec_path(&rust_file, src_dir, spec_dir, filter)?;
+        let spec_file = spec_files.contains(&spec_path).then_some(spec_path);
+        let settings = settings_of(spec_file.as_ref());
+        mappings.push(FileMapping { rust_file, spec_file, mapped_specs: None, headings: false, settings });
+    }
+
+    Ok(mappings)
+}
+
+/// The spec files of each `[[mapping]]` rule that has any, and the indices in `mappings` of
+/// the source files checked together against them
+pub fn mapped_groups(mappings: &[FileMapping]) -> Vec<(&[PathBuf], Vec<usize>)> {
+    let mut groups: Vec<(&[PathBuf], Vec<usize>)> = Vec::new();
+    for (index, mapping) in mappings.iter().enumerate() {
+        let Some(specs) = mapping.mapped_specs.as_deref().filter(|specs| !specs.is_empty()) else { continue };
+        match groups.iter_mut().find(|(other, _)| *other == specs) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((specs, vec![index])),
+        }
+    }
+    groups
+}
 
-    for entry in WalkDir::new(src_dir)
+/// Every file under `dir` with the given extension that `filter` allows, in file name order
+fn walk_files(dir: &Path, extension: &str, filter: &FileFilter) -> Vec<PathBuf> {
+    let hidden = filter.walks_hidden();
+    let ignores = (!filter.no_ignore).then(Ignores::default);
+    WalkDir::new(dir)
+        .sort_by_file_name()
         .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0 || ((hidden || !e.file_name().to_string_lossy().starts_with('.'))
+                && !ignores.as_ref().is_some_and(|ignores| ignores.is_ignored(e.path(), e.file_type().is_dir())))
+        })
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "rs"))
-    {
-        let rust_file = entry.path().to_path_buf();
-        
-        // Calculate relative path from src_dir
-        let relative_path = rust_file.strip_prefix(src_dir)?;
-        
-        // Convert .rs to .md and prepend spec_dir
-        let spec_path = spec_dir.join(relative_path).with_extension("md");
-        
-        let spec_file = if spec_path.exists() {
-            Some(spec_path)
-        } else {
-            None
-        };
-
-        mappings.push(FileMapping { rust_file, spec_file });
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == extension) && filter.allows(e.path()))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Whether `filter` allows a file listed rather than walked, e.g. from the git index
+fn allows_listed(path: &Path, root: &Path, filter: &FileFilter) -> bool {
+    let hidden = path.strip_prefix(root).unwrap_or(path).components().any(|part| part.as_os_str().to_string_lossy().starts_with('.'));
+    (!hidden || filter.walks_hidden()) && filter.allows(path)
+}
+
+/// Every spec file under `spec_dir`, for matching that doesn't go by file
+pub fn find_spec_files(spec_dir: &Path, filter: &FileFilter) -> Vec<PathBuf> {
+    walk_files(spec_dir, filter.spec_format.extension(), filter)
+}
+
+/// Every source file under `src_dir` and the extra roots of `filter`
+pub fn find_rust_files(src_dir: &Path, filter: &FileFilter) -> Vec<PathBuf> {
+    let mut rust_files = walk_files(src_dir, "rs", filter);
+    for root in &filter.roots {
+        rust_files.extend(walk_files(root, "rs", filter));
     }
+    rust_files
+}
 
-    Ok(mappings)
+/// The source files under `src_dir` and the extra roots, and the spec files under `spec_dir`,
+/// with the two walked at once
+fn walk_trees(src_dir: &Path, spec_dir: &Path, filter: &FileFilter) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    thread::scope(|scope| {
+        let spec_files = scope.spawn(|| walk_files(spec_dir, filter.spec_format.extension(), filter));
+        (find_rust_files(src_dir, filter), spec_files.join().expect("directory walk panicked"))
+    })
+}
+
+/// `spec_path_for` with the extra roots of `filter` mirrored too, `tests/foo.rs` ->
+/// `spec/tests/foo.md`, the extension of its spec format, and its spec layout
+pub fn mirrored_spec_path(rust_file: &Path, src_dir: &Path, spec_dir: &Path, filter: &FileFilter) -> Result<PathBuf> {
+    let extension = filter.spec_format.extension();
+    let (root, spec_root) = filter.roots.iter()
+        .find(|root| !rust_file.starts_with(src_dir) && rust_file.starts_with(root))
+        .and_then(|root| Some((root.as_path(), spec_dir.join(root.file_name()?))))
+        .unwrap_or((src_dir, spec_dir.to_path_buf()));
+    let relative = rust_file.strip_prefix(root)?;
+    let module = match filter.spec_layout {
+        SpecLayout::Module if relative.file_name().is_some_and(|name| name == "mod.rs") => relative.parent().filter(|dir| !dir.as_os_str().is_empty()),
+        _ => None,
+    };
+    Ok(match module {
+        // Appending rather than `with_extension`, which would cut a directory name like `v1.2`
+        Some(dir) => {
+            let mut path = spec_root.join(dir).into_os_string();
+            path.push(format!(".{}", extension));
+            PathBuf::from(path)
+        }
+        None => spec_root.join(relative).with_extension(extension),
+    })
+}
+
+/// Like `find_file_mappings`, but for the files staged in the git index
+pub fn find_staged_mappings(src_dir: &Path, spec_dir: &Path, rules: &[MappingRule], filter: &FileFilter) -> Result<Vec<FileMapping>> {
+    let mut staged_specs: Vec<PathBuf> = git::list_staged_files(spec_dir)?.into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == filter.spec_format.extension()) && allows_listed(path, spec_dir, filter))
+        .collect();
+    for rule in rules.iter().filter(|rule| !glob::is_pattern(&rule.spec) && !Path::new(&rule.spec).starts_with(spec_dir)) {
+        staged_specs.extend(git::list_staged_files(Path::new(&rule.spec))?);
+    }
+    let mut staged_sources = Vec::new();
+    for root in std::iter::once(src_dir).chain(filter.roots.iter().map(PathBuf::as_path)) {
+        staged_sources.extend(git::list_staged_files(root)?.into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs") && allows_listed(path, root, filter)));
+    }
+    staged_sources.sort();
+    let settings = spec_settings(&staged_specs, |path| {
+        let markdown = git::show_staged_file(path)?;
+        Ok(markdown.starts_with(['+', '-']).then_some(markdown))
+    })?;
+    map_files(staged_sources, staged_specs, src_dir, spec_dir, rules, filter, &settings)
+}
+
+/// The conventional spec path for a source file: `src/foo.rs` -> `spec/foo.md`
+pub fn spec_path_for(rust_file: &Path, src_dir: &Path, spec_dir: &Path) -> Result<PathBuf> {
+    // Calculate relative path from src_dir
+    let relative_path = rust_file.strip_prefix(src_dir)?;
+
+    // Convert .rs to .md and prepend spec_dir
+    Ok(spec_dir.join(relative_path).with_extension("md"))
+}
+
+/// The source file a spec file is for by convention: `spec/foo.md` -> `src/foo.rs`, or
+/// `src/foo/mod.rs` if that exists under the module layout
+pub fn rust_path_for(spec_file: &Path, src_dir: &Path, spec_dir: &Path, layout: SpecLayout) -> Result<PathBuf> {
+    let rust_file = src_dir.join(spec_file.strip_prefix(spec_dir)?).with_extension("rs");
+    let mod_file = rust_file.with_extension("").join("mod.rs");
+    Ok(if layout == SpecLayout::Module && !rust_file.exists() && mod_file.exists() { mod_file } else { rust_file })
+}
+
+/// Spec files no source file maps to: `spec/foo.md` without `src/foo.rs`, or claimed by a
+/// `[[mapping]]` rule that matches no source file. Files the mapped specs include aren't.
+pub fn find_orphaned_specs(src_dir: &Path, spec_dir: &Path, rules: &[MappingRule], filter: &FileFilter) -> Result<Vec<PathBuf>> {
+    let (rust_files, spec_files) = walk_trees(src_dir, spec_dir, filter);
+    let settings = spec_settings(&spec_files, read_front_matter)?;
+    let mappings = map_files(rust_files, spec_files.clone(), src_dir, spec_dir, rules, filter, &settings)?;
+    let mapped: HashSet<&PathBuf> = mappings.iter()
+        .flat_map(|mapping| mapping.spec_file.iter().chain(mapping.mapped_specs.iter().flatten()))
+        .collect();
+    let included: HashSet<PathBuf> = mapped.iter()
+        .filter_map(|spec_file| Some(crate::spec_includes(&fs::read_to_string(spec_file).ok()?, spec_file)))
+        .flatten()
+        .filter_map(|include| include.canonicalize().ok())
+        .collect();
+    let orphaned = |spec_file: &PathBuf| !mapped.contains(spec_file) && !spec_file.canonicalize().is_ok_and(|path| included.contains(&path));
+    Ok(spec_files.iter().filter(|spec_file| orphaned(spec_file)).cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_rules_before_mirrored_paths() {
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let rules = [
+            MappingRule { rust: "src/animation/**/*.rs".to_string(), spec: "spec/animation.md".to_string(), headings: false },
+            MappingRule { rust: "src/render.rs".to_string(), spec: "spec/render/*.md".to_string(), headings: false },
+        ];
+        let mappings = map_files(
+            paths(&["src/animation/clip.rs", "src/animation/curves/bezier.rs", "src/animation.rs", "src/math.rs", "src/render.rs"]),
+            paths(&["spec/animation.md", "spec/math.md", "spec/render/passes.md", "spec/render/targets.md"]),
+            Path::new("src"),
+            Path::new("spec"),
+            &rules,
+            &FileFilter::default(),
+            &HashMap::new(),
+        ).unwrap();
+
+        let specs = |mapping: &FileMapping| (mapping.spec_file.clone(), mapping.mapped_specs.clone());
+        let animation = (Some(PathBuf::from("spec/animation.md")), Some(paths(&["spec/animation.md"])));
+        assert_eq!(specs(&mappings[0]), animation);
+        assert_eq!(specs(&mappings[1]), animation);
+        // The rule claims the mirrored spec of `src/animation.rs`
+        assert_eq!(specs(&mappings[2]), (None, None));
+        assert_eq!(specs(&mappings[3]), (Some(PathBuf::from("spec/math.md")), None));
+        assert_eq!(specs(&mappings[4]).1, Some(paths(&["spec/render/passes.md", "spec/render/targets.md"])));
+
+        let overlapping = [rules[0].clone(), MappingRule { rust: "src/*.rs".to_string(), spec: "spec/*.md".to_string(), headings: false }];
+        assert!(map_files(Vec::new(), paths(&["spec/animation.md"]), Path::new("src"), Path::new("spec"), &overlapping, &FileFilter::default(), &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_extra_roots_mirrored_in_spec_subdirectories() {
+        let filter = FileFilter { roots: vec![PathBuf::from("tests"), PathBuf::from("examples")], ..Default::default() };
+        let mappings = map_files(
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("tests/harness.rs"), PathBuf::from("examples/demo.rs")],
+            vec![PathBuf::from("spec/lib.md"), PathBuf::from("spec/tests/harness.md")],
+            Path::new("src"),
+            Path::new("spec"),
+            &[],
+            &filter,
+            &HashMap::new(),
+        ).unwrap();
+        let specs: Vec<Option<PathBuf>> = mappings.into_iter().map(|mapping| mapping.spec_file).collect();
+        assert_eq!(specs, [Some(PathBuf::from("spec/lib.md")), Some(PathBuf::from("spec/tests/harness.md")), None]);
+        assert_eq!(mirrored_spec_path(Path::new("examples/demo.rs"), Path::new("src"), Path::new("spec"), &filter).unwrap(), PathBuf::from("spec/examples/demo.md"));
+    }
+
+    #[test]
+    fn test_module_layout_specs_mod_rs_as_its_module() {
+        let filter = FileFilter { spec_layout: SpecLayout::Module, roots: vec![PathBuf::from("tests")], ..Default::default() };
+        let spec_of = |rust_file: &str, filter: &FileFilter| mirrored_spec_path(Path::new(rust_file), Path::new("src"), Path::new("spec"), filter).unwrap();
+        assert_eq!(spec_of("src/animation/mod.rs", &filter), PathBuf::from("spec/animation.md"));
+        assert_eq!(spec_of("src/animation.rs", &filter), PathBuf::from("spec/animation.md"));
+        assert_eq!(spec_of("src/animation/curves/mod.rs", &filter), PathBuf::from("spec/animation/curves.md"));
+        assert_eq!(spec_of("src/v1.2/mod.rs", &filter), PathBuf::from("spec/v1.2.md"));
+        assert_eq!(spec_of("src/mod.rs", &filter), PathBuf::from("spec/mod.md"));
+        assert_eq!(spec_of("tests/common/mod.rs", &filter), PathBuf::from("spec/tests/common.md"));
+        assert_eq!(spec_of("src/animation/mod.rs", &FileFilter::default()), PathBuf::from("spec/animation/mod.md"));
+    }
+
+    #[test]
+    fn test_walk_files_leaves_out_excluded_and_hidden() {
+        let dir = std::env::temp_dir().join(format!("spec-check-walk-{}", std::process::id()));
+        for file in ["src/lib.rs", "src/generated/wire.rs", "src/.cache/stale.rs", "src/net/mod.rs"] {
+            fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let relative = |files: Vec<PathBuf>| files.iter().map(|file| file.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/")).collect::<Vec<_>>();
+        let exclude = |patterns: &[&str]| FileFilter { exclude: patterns.iter().map(|pattern| format!("{}/{}", dir.display(), pattern)).collect(), ..Default::default() };
+
+        assert_eq!(relative(walk_files(&dir.join("src"), "rs", &FileFilter::default())), ["src/generated/wire.rs", "src/lib.rs", "src/net/mod.rs"]);
+        assert_eq!(relative(walk_files(&dir.join("src"), "rs", &exclude(&["**/generated/**", "**/mod.rs"]))), ["src/lib.rs"]);
+        let hidden = FileFilter { include: vec![format!("{}/src/.cache/*.rs", dir.display())], ..Default::default() };
+        assert_eq!(relative(walk_files(&dir.join("src"), "rs", &hidden)), ["src/.cache/stale.rs"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_orphaned_specs_leave_out_included_ones() {
+        let dir = std::env::temp_dir().join(format!("spec-check-orphans-{}", std::process::id()));
+        let files = [
+            ("src/lib.rs", ""),
+            ("spec/lib.md", "<!-- spec-check: include shared/errors.md -->\n"),
+            ("spec/shared/errors.md", "<!-- spec-check: include codes.md -->\n"),
+            ("spec/shared/codes.md", ""),
+            ("spec/colour.md", ""),
+        ];
+        for (file, content) in files {
+            fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            fs::write(dir.join(file), content).unwrap();
+        }
+        let orphans = find_orphaned_specs(&dir.join("src"), &dir.join("spec"), &[], &FileFilter::default()).unwrap();
+        assert_eq!(orphans, [dir.join("spec/colour.md")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
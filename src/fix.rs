@@ -0,0 +1,255 @@
+//! Rewriting the items that don't match as the other side declares them: the spec's items as
+//! the code declares them when the code is the source of truth, or the code's as the spec does
+//! when the spec is. Prose, function bodies and the rest of each file are left as they are.
+
+use crate::comparator::ComparisonResult;
+use crate::config::Visibility;
+use crate::spec_format::SpecFormat;
+use crate::rust_parser::RustItem;
+use crate::skeleton::{self, Declaration};
+use anyhow::Result;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A file's text with its mismatched items rewritten
+#[derive(Debug, Clone)]
+pub struct Fixed {
+    pub text: String,
+    /// Items now declared as on the other side
+    pub updated: Vec<RustItem>,
+    /// Mismatched items that couldn't be rewritten: in a spec block that is indented (e.g. in a
+    /// list) or doesn't parse as plain Rust, such as one with placeholders, or a trait of the
+    /// code with method bodies the spec's declaration would drop
+    pub left: Vec<RustItem>,
+}
+
+/// Rewrite the spec items of `result`'s signature, attribute and discriminant mismatches in
+/// `markdown`, a spec in `format`, as `source` declares them
+pub fn fix(markdown: &str, format: &dyn SpecFormat, source: &str, result: &ComparisonResult, visibility: Visibility, ignored_attributes: &[String]) -> Result<Fixed> {
+    let code = skeleton::declarations(source, visibility, ignored_attributes)?;
+    let spec = spec_declarations(markdown, format, visibility, ignored_attributes);
+    Ok(rewrite(markdown, &spec, &code, mismatched(result), false))
+}
+
+/// Rewrite the code items of `result`'s mismatches in `source` as `markdown` declares them,
+/// keeping function bodies
+pub fn fix_code(source: &str, markdown: &str, format: &dyn SpecFormat, result: &ComparisonResult, visibility: Visibility, ignored_attributes: &[String]) -> Result<Fixed> {
+    let code = skeleton::declarations(source, visibility, ignored_attributes)?;
+    let spec = spec_declarations(markdown, format, visibility, ignored_attributes);
+    Ok(rewrite(source, &code, &spec, mismatched(result), true))
+}
+
+fn mismatched(result: &ComparisonResult) -> impl Iterator<Item = &RustItem> {
+    result.signature_mismatches.iter().map(|mismatch| &mismatch.spec_item)
+        .chain(result.attribute_mismatches.iter().map(|mismatch| &mismatch.spec_item))
+        .chain(result.discriminant_mismatches.iter().map(|mismatch| &mismatch.spec_item))
+        .chain(result.value_mismatches.iter().map(|mismatch| &mismatch.spec_item))
+        .chain(result.abi_mismatches.iter().map(|mismatch| &mismatch.spec_item))
+}
+
+/// The declarations of a spec's blocks, with ranges in the markdown
+fn spec_declarations(markdown: &str, format: &dyn SpecFormat, visibility: Visibility, ignored_attributes: &[String]) -> Vec<Declaration> {
+    let mut spec = Vec::new();
+    for block in format.rust_blocks(markdown).filter(|block| block.unknown_tag.is_none()) {
+        // Only a block borrowed from the markdown has offsets that are the markdown's
+        let Cow::Borrowed(text) = block.code else { continue };
+        let Ok(declarations) = skeleton::declarations(text, visibility, ignored_attributes) else { continue };
+        let shift = |range: Range<usize>| range.start + block.range.start..range.end + block.range.start;
+        spec.extend(declarations.into_iter().map(|declaration| Declaration {
+            range: shift(declaration.range),
+            body: declaration.body.map(shift),
+            ..declaration
+        }));
+    }
+    spec
+}
+
+/// `text` with the declarations in `targets` of `items` replaced by those in `sources`, up to
+/// the body of functions with `keep_bodies`
+fn rewrite<'a, 'd>(text: &str, targets: &'d [Declaration], sources: &'d [Declaration], items: impl Iterator<Item = &'a RustItem>, keep_bodies: bool) -> Fixed {
+    let (mut updated, mut left) = (Vec::new(), Vec::new());
+    let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+    for item in items {
+        // An item can mismatch both in signature and in attributes
+        if updated.contains(item) || left.contains(item) {
+            continue;
+        }
+        let declared = |declarations: &'d [Declaration]| declarations.iter().find(|declaration| declaration.items.contains(item));
+        let (Some(target), Some(source)) = (declared(targets), declared(sources)) else {
+            left.push(item.clone());
+            continue;
+        };
+        let replacement = match &target.body {
+            Some(body) if keep_bodies => source.text.strip_suffix("{}").map(|signature| (target.range.start..body.start, signature.to_string())),
+            None if keep_bodies && has_default_bodies(&text[target.range.clone()]) => None,
+            _ => Some((target.range.clone(), with_docs(&source.text, &text[target.range.clone()]))),
+        };
+        let Some((range, replacement)) = replacement else {
+            left.push(item.clone());
+            continue;
+        };
+        // A trait and its methods are one declaration
+        if !replacements.iter().any(|(other, _)| *other == range) {
+            replacements.push((range, replacement));
+        }
+        updated.push(item.clone());
+    }
+
+    let mut fixed = text.to_string();
+    replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, replacement) in replacements {
+        // Lines after the first are indented as the line the item starts on
+        let line_start = text[..range.start].rfind('\n').map_or(0, |at| at + 1);
+        let indent = &text[line_start..range.start];
+        let indent = if indent.trim().is_empty() { indent } else { "" };
+        let replacement = replacement.lines().enumerate()
+            .map(|(index, line)| if index == 0 || line.is_empty() { line.to_string() } else { format!("{}{}", indent, line) })
+            .collect::<Vec<_>>()
+            .join("\n");
+        fixed.replace_range(range, &replacement);
+    }
+    Fixed { text: fixed, updated, left }
+}
+
+/// Whether `text` declares a trait with default method bodies
+fn has_default_bodies(text: &str) -> bool {
+    matches!(syn::parse_str::<syn::Item>(text), Ok(syn::Item::Trait(node))
+        if node.items.iter().any(|item| matches!(item, syn::TraitItem::Fn(method) if method.default.is_some())))
+}
+
+/// `new` with the doc comments `old` gives its fields, variants or trait members where `new`
+/// has none, so prose written on the side rewritten survives
+fn with_docs(new: &str, old: &str) -> String {
+    let old_members = skeleton::members(old);
+    let mut text = new.to_string();
+    for member in skeleton::members(new).iter().rev().filter(|member| member.docs.is_empty()) {
+        let Some(documented) = old_members.iter().find(|old_member| old_member.name == member.name) else { continue };
+        let line = &new[member.line_start..];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let docs: String = documented.docs.iter().map(|doc| format!("{}{}\n", indent, doc)).collect();
+        text.insert_str(member.line_start, &docs);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec_format::Markdown;
+
+    #[test]
+    fn test_fix_rewrites_mismatched_items_only() {
+        let source = "\
+pub struct Curve {
+    pub points: Vec<f32>,
+    pub closed: bool,
+}
+
+impl Curve {
+    pub fn sample(&self, n: usize, t: f32) -> Vec<f32> { Vec::new() }
+    pub fn len(&self) -> usize { 0 }
+}
+
+pub mod math {
+    /// Linear interpolation
+    #[inline]
+    pub fn lerp(a: f64, b: f64) -> f64 { a + b }
+}
+";
+        let markdown = "\
+# Curves
+
+A curve is a list of points.
+
+```rust
+/// Points along it
+pub struct Curve {
+    /// In order
+    pub points: Vec<f32>,
+}
+
+impl Curve {
+    /// Samples it
+    pub fn sample(&self, n: usize) -> Vec<f32> {}
+    pub fn len(&self) -> usize { unimplemented!() }
+}
+
+pub mod math {
+    pub fn lerp(a: f32, b: f32) -> f32 { unimplemented!() }
+}
+```
+
+- And in a list:
+
+  ```rust
+  pub fn listed() {}
+  ```
+";
+        let ignored = ["doc".to_string()];
+        let result = {
+            let mut result = crate::compare(crate::parse_rust_items(source, Visibility::Pub).unwrap(), crate::extract_spec_items(markdown, Visibility::Pub), &ignored);
+            crate::comparator::relax(&mut result, &crate::CompareOptions::default(), &ignored);
+            result
+        };
+        let fixed = fix(markdown, &Markdown, source, &result, Visibility::Pub, &ignored).unwrap();
+        assert_eq!(fixed.text, "\
+# Curves
+
+A curve is a list of points.
+
+```rust
+/// Points along it
+pub struct Curve {
+    /// In order
+    pub points: Vec<f32>,
+    pub closed: bool,
+}
+
+impl Curve {
+    /// Samples it
+    pub fn sample(&self, n: usize, t: f32) -> Vec<f32> {}
+    pub fn len(&self) -> usize { unimplemented!() }
+}
+
+pub mod math {
+    #[inline]
+    pub fn lerp(a: f64, b: f64) -> f64 {}
+}
+```
+
+- And in a list:
+
+  ```rust
+  pub fn listed() {}
+  ```
+");
+        let names: Vec<&str> = fixed.updated.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["Curve", "sample", "lerp"]);
+        assert!(fixed.left.is_empty());
+    }
+
+    #[test]
+    fn test_fix_code_keeps_bodies() {
+        let source = "\
+pub struct Mesh {
+    /// Flat xyz
+    pub vertices: Vec<f32>,
+}
+
+impl Mesh {
+    pub fn scale(&mut self, factor: f32) {
+        self.vertices.iter_mut().for_each(|v| *v *= factor);
+    }
+}
+";
+        let markdown = "```rust\npub struct Mesh {\n    pub vertices: Vec<f64>,\n}\n\nimpl Mesh {\n    pub fn scale(&mut self, factor: f64) {}\n}\n```\n";
+        let result = {
+            let mut result = crate::compare(crate::parse_rust_items(source, Visibility::Pub).unwrap(), crate::extract_spec_items(markdown, Visibility::Pub), &[]);
+            crate::comparator::relax(&mut result, &crate::CompareOptions::default(), &[]);
+            result
+        };
+        let fixed = fix_code(source, markdown, &Markdown, &result, Visibility::Pub, &["doc".to_string()]).unwrap();
+        assert_eq!(fixed.text, source.replace("f32", "f64"));
+        assert_eq!(fixed.updated.len(), 2);
+    }
+}
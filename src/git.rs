@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run git with the given arguments in the current directory and return stdout
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// List all files under `dir` at `rev`, relative to the current directory
+pub fn list_files(rev: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = dir.to_string_lossy();
+    let output = git(&["ls-tree", "-r", "--name-only", rev, "--", &dir])?;
+    Ok(output.lines().map(PathBuf::from).collect())
+}
+
+/// Read the content of `path` (relative to the current directory) at `rev`
+pub fn show_file(rev: &str, path: &Path) -> Result<String> {
+    let spec = format!("{}:./{}", rev, path.to_string_lossy());
+    git(&["show", &spec])
+}
+
+/// List the files under `dir` in the index, relative to the current directory
+pub fn list_staged_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = dir.to_string_lossy();
+    let output = git(&["ls-files", "--cached", "--", &dir])?;
+    Ok(output.lines().map(PathBuf::from).collect())
+}
+
+/// Read the staged content of `path` (relative to the current directory)
+pub fn show_staged_file(path: &Path) -> Result<String> {
+    let spec = format!(":./{}", path.to_string_lossy());
+    git(&["show", &spec])
+}
+
+/// Files whose staged content differs from HEAD, deleted ones included, relative to the current
+/// directory
+pub fn staged_changes() -> Result<Vec<PathBuf>> {
+    let output = git(&["diff", "--cached", "--name-only", "--relative", "--"])?;
+    Ok(output.lines().map(PathBuf::from).collect())
+}
+
+/// Where the repository's hooks go, honoring `core.hooksPath`
+pub fn hooks_dir() -> Result<PathBuf> {
+    Ok(PathBuf::from(git(&["rev-parse", "--git-path", "hooks"])?.trim()))
+}
+
+/// The current directory relative to the top of the working tree, `""` at the top
+pub fn prefix() -> Result<String> {
+    Ok(git(&["rev-parse", "--show-prefix"])?.trim().to_string())
+}
+
+/// `git blame --line-porcelain` of `path` (relative to the current directory) as it is in the
+/// working tree
+pub fn blame(path: &Path) -> Result<String> {
+    git(&["blame", "--line-porcelain", "--", &path.to_string_lossy()])
+}
+
+/// The commit checked out in the current directory
+pub fn head_commit() -> Result<String> {
+    Ok(git(&["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Files changed in the working tree since the commit where `base` and HEAD diverged, untracked
+/// ones included, relative to the current directory. Only files under it are listed.
+pub fn changed_files(base: &str) -> Result<Vec<PathBuf>> {
+    let fork = git(&["merge-base", base, "HEAD"])?;
+    let changed = git(&["diff", "--name-only", "--relative", fork.trim(), "--"])?;
+    let untracked = git(&["ls-files", "--others", "--exclude-standard"])?;
+    Ok(changed.lines().chain(untracked.lines()).map(PathBuf::from).collect())
+}
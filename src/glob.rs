@@ -0,0 +1,72 @@
+//! Shell-style wildcard matching: `*` matches any run of characters, `?` exactly one, and in
+//! paths `**` any number of directories
+
+/// Whether `text` matches `pattern` in full
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Iterative matching with backtracking to the most recent `*`
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether the `/`-separated `path` matches `pattern`, where `*` and `?` stay within one
+/// component and a `**` component matches any number of them (`src/**/*.rs`)
+pub fn matches_path(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|part| !part.is_empty() && *part != ".").collect();
+    let path: Vec<&str> = path.split('/').filter(|part| !part.is_empty() && *part != ".").collect();
+    matches_components(&pattern, &path)
+}
+
+fn matches_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_components(rest, &path[skip..])),
+        Some((first, rest)) => path.split_first().is_some_and(|(component, path)| matches(first, component) && matches_components(rest, path)),
+    }
+}
+
+/// Whether `pattern` uses any wildcard characters
+pub fn is_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*Geometry", "CurveGeometry"));
+        assert!(matches("Curve*::s?mple", "CurveGeometry::sample"));
+        assert!(matches("*", ""));
+        assert!(!matches("Curve*", "SurfaceGeometry"));
+        assert!(!matches("?", ""));
+    }
+
+    #[test]
+    fn test_matches_path() {
+        assert!(matches_path("src/animation/**/*.rs", "src/animation/clip.rs"));
+        assert!(matches_path("src/animation/**/*.rs", "./src/animation/curves/bezier.rs"));
+        assert!(matches_path("src/animation/**", "src/animation/clip.rs"));
+        assert!(!matches_path("src/*.rs", "src/animation/clip.rs"));
+        assert!(!matches_path("src/animation/**/*.rs", "src/render.rs"));
+    }
+}
@@ -0,0 +1,191 @@
+//! A standalone HTML report, for attaching to CI artifacts: an index of the checked files, and
+//! for each finding its code and spec locations and a word-level diff of the two signatures
+
+use crate::reporter::Finding;
+use crate::rules;
+use crate::word_diff::{self, Change};
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+const STYLE: &str = "\
+body { font: 15px/1.5 system-ui, sans-serif; margin: 2em auto; max-width: 70em; padding: 0 1em; color: #1f2328; }
+h1 { margin-bottom: 0; }
+.summary { color: #59636e; }
+.ok { color: #1a7f37; }
+.bad { color: #cf222e; }
+section { border-top: 1px solid #d1d9e0; margin-top: 2em; }
+details { margin: 1em 0; border: 1px solid #d1d9e0; border-radius: 6px; padding: 0.5em 1em; }
+summary { cursor: pointer; }
+.rule { font: 600 13px monospace; background: #ffebe9; color: #cf222e; border-radius: 4px; padding: 0 0.4em; }
+.where { color: #59636e; margin: 0.5em 0; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 0.75em; overflow-x: auto; white-space: pre-wrap; }
+del { background: #ffcecb; text-decoration: line-through; }
+ins { background: #aceebb; text-decoration: none; }
+a { color: #0969da; }
+";
+
+/// The report for a run that checked `files`, each with its spec file if it has one. Links to
+/// the files are relative to `dir`, the directory the report is written to.
+pub fn to_string(dir: &Path, files: &[(PathBuf, Option<PathBuf>)], findings: &[Finding]) -> String {
+    let mut cases: Vec<(&Path, Option<&Path>)> = files.iter().map(|(file, spec)| (file.as_path(), spec.as_deref())).collect();
+    for finding in findings {
+        if !cases.iter().any(|(file, _)| *file == finding.file) {
+            cases.push((&finding.file, None));
+        }
+    }
+    let failing = cases.iter().filter(|(file, _)| findings.iter().any(|finding| finding.file == *file)).count();
+
+    let mut out = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    writeln!(out, "<title>spec-check report</title>\n<style>\n{}</style>\n</head>\n<body>", STYLE).unwrap();
+    writeln!(out, "<h1>spec-check report</h1>").unwrap();
+    let summary = if findings.is_empty() {
+        format!("<span class=\"ok\">All {} file(s) match their specs</span>", cases.len())
+    } else {
+        format!("<span class=\"bad\">{} finding(s)</span> in {} of {} file(s)", findings.len(), failing, cases.len())
+    };
+    writeln!(out, "<p class=\"summary\">{} &middot; spec-check {}</p>", summary, env!("CARGO_PKG_VERSION")).unwrap();
+
+    out.push_str("<nav>\n<h2>Files</h2>\n<ul>\n");
+    for (index, (file, _)) in cases.iter().enumerate() {
+        let count = findings.iter().filter(|finding| finding.file == *file).count();
+        let name = escape(&display(file));
+        if count == 0 {
+            writeln!(out, "<li>{} <span class=\"ok\">&#10003; matches</span></li>", name).unwrap();
+        } else {
+            writeln!(out, "<li><a href=\"#file-{}\">{}</a> <span class=\"bad\">{} finding(s)</span></li>", index + 1, name, count).unwrap();
+        }
+    }
+    out.push_str("</ul>\n</nav>\n");
+
+    for (index, (file, spec)) in cases.iter().enumerate() {
+        let found: Vec<&Finding> = findings.iter().filter(|finding| finding.file == *file).collect();
+        if found.is_empty() {
+            continue;
+        }
+        writeln!(out, "<section id=\"file-{}\">", index + 1).unwrap();
+        let spec_link = spec.map(|spec| format!(" &harr; {}", link(dir, spec, None))).unwrap_or_default();
+        writeln!(out, "<h2>{}{}</h2>", link(dir, file, None), spec_link).unwrap();
+        for finding in found {
+            write_finding(&mut out, dir, *spec, finding);
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn write_finding(out: &mut String, dir: &Path, spec_file: Option<&Path>, finding: &Finding) {
+    let title = rules::find(finding.rule).map(|rule| format!(" title=\"{}: {}\"", rule.name, escape(rule.summary))).unwrap_or_default();
+    writeln!(out, "<details open>\n<summary><span class=\"rule\"{}>{}</span> {}</summary>", title, finding.rule, escape(&finding.message)).unwrap();
+
+    let mut locations = Vec::new();
+    if let Some(line) = finding.line {
+        locations.push(format!("code {}", link(dir, &finding.file, Some(line))));
+    }
+    if let (Some(spec), Some(line)) = (spec_file, finding.spec_line) {
+        locations.push(format!("spec {}", link(dir, spec, Some(line))));
+    }
+    if !locations.is_empty() {
+        writeln!(out, "<p class=\"where\">{}</p>", locations.join(" &harr; ")).unwrap();
+    }
+
+    match (&finding.code, &finding.spec) {
+        // Attributes are lists rather than one signature, so they're shown side by side
+        (Some(code), Some(spec)) if finding.rule == rules::ATTRIBUTE_MISMATCH => {
+            writeln!(out, "<p>Code:</p>\n<pre>{}</pre>\n<p>Spec:</p>\n<pre>{}</pre>", escape(code), escape(spec)).unwrap();
+        }
+        (Some(code), Some(spec)) => {
+            let diff: Vec<String> = word_diff::diff(spec, code).into_iter().map(|segment| {
+                let text = escape(&segment.text);
+                let text = match segment.change {
+                    Change::Same => text,
+                    Change::Removed => format!("<del title=\"spec\">{}</del>", text),
+                    Change::Added => format!("<ins title=\"code\">{}</ins>", text),
+                };
+                if segment.spaced { format!(" {}", text) } else { text }
+            }).collect();
+            writeln!(out, "<pre class=\"diff\">{}</pre>", diff.concat().trim_start()).unwrap();
+        }
+        (Some(code), None) => writeln!(out, "<pre>{}</pre>", escape(code)).unwrap(),
+        (None, Some(spec)) => writeln!(out, "<pre>{}</pre>", escape(spec)).unwrap(),
+        (None, None) => {}
+    }
+    out.push_str("</details>\n");
+}
+
+/// A link to `file`, shown with its line if given
+fn link(dir: &Path, file: &Path, line: Option<usize>) -> String {
+    let text = match line {
+        Some(line) => format!("{}:{}", display(file), line),
+        None => display(file),
+    };
+    format!("<a href=\"{}\">{}</a>", escape(&href(dir, file)), escape(&text))
+}
+
+/// `target` relative to `dir`, as a URL path
+fn href(dir: &Path, target: &Path) -> String {
+    let absolute = |path: &Path| -> Vec<String> {
+        let path = std::env::current_dir().map(|cwd| cwd.join(path)).unwrap_or_else(|_| path.to_path_buf());
+        let mut parts: Vec<String> = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::ParentDir => { parts.pop(); }
+                Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+                _ => {}
+            }
+        }
+        parts
+    };
+    let (from, to) = (absolute(dir), absolute(target));
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec!["..".to_string(); from.len() - common];
+    parts.extend(to[common..].iter().map(|part| encode(part)));
+    parts.join("/")
+}
+
+/// A path segment with the characters that would end or change a URL path percent-encoded
+fn encode(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for c in segment.chars() {
+        match c {
+            ' ' | '#' | '%' | '?' | '"' => write!(out, "%{:02X}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_report() {
+        let files = [
+            (PathBuf::from("src/lib.rs"), Some(PathBuf::from("spec/lib.md"))),
+            (PathBuf::from("src/ok.rs"), Some(PathBuf::from("spec/ok.md"))),
+        ];
+        let findings = [Finding {
+            code: Some("pub fn f (x : Vec < u8 >)".to_string()),
+            spec: Some("pub fn f (x : Vec < u16 >)".to_string()),
+            spec_line: Some(7),
+            ..Finding::new(rules::SIGNATURE_MISMATCH, Path::new("src/lib.rs"), Some(3), "fn f does not match the spec".to_string())
+        }];
+        let html = to_string(Path::new("target/spec-check"), &files, &findings);
+        assert!(html.contains("<span class=\"bad\">1 finding(s)</span> in 1 of 2 file(s)"));
+        assert!(html.contains("<li><a href=\"#file-1\">src/lib.rs</a> <span class=\"bad\">1 finding(s)</span></li>"));
+        assert!(html.contains("<li>src/ok.rs <span class=\"ok\">&#10003; matches</span></li>"));
+        assert!(html.contains("code <a href=\"../../src/lib.rs\">src/lib.rs:3</a> &harr; spec <a href=\"../../spec/lib.md\">spec/lib.md:7</a>"));
+        assert!(html.contains("<pre class=\"diff\">pub fn f (x : Vec &lt; <del title=\"spec\">u16</del> <ins title=\"code\">u8</ins> &gt;)</pre>"));
+        assert!(!html.contains("file-2"));
+        assert_eq!(href(Path::new(""), Path::new("my specs/a#b.md")), "my%20specs/a%23b.md");
+    }
+}
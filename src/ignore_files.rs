@@ -0,0 +1,111 @@
+//! `.gitignore` and `.ignore` files, read the way git reads `.gitignore`: one pattern per line,
+//! `#` comments, `!` to re-include, a trailing `/` for directories only, and patterns without
+//! a `/` matching a name at any depth below the file that lists them
+
+use crate::glob;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The files read in each directory, later lines winning over earlier ones
+pub const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern {
+    glob: String,
+    negated: bool,
+    directories_only: bool,
+    /// Matched against the path relative to the ignore file, not just the name
+    anchored: bool,
+}
+
+fn parse(content: &str) -> Vec<Pattern> {
+    let mut patterns = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (directories_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let glob = line.strip_prefix('/').unwrap_or(line).to_string();
+        if !glob.is_empty() {
+            patterns.push(Pattern { glob, negated, directories_only, anchored });
+        }
+    }
+    patterns
+}
+
+/// The ignore files of the directories a walk passes through and of the directories above it,
+/// up to the repository root or, for relative paths, the current directory, each read once
+#[derive(Default)]
+pub struct Ignores {
+    read: RefCell<HashMap<PathBuf, Vec<Pattern>>>,
+}
+
+impl Ignores {
+    /// Whether the ignore files of the directories above `path` ignore it. A walk that skips
+    /// ignored directories also skips everything under them, as git does.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut dirs = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            dirs.push(dir);
+            if dir.as_os_str().is_empty() || dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let mut ignored = false;
+        for dir in dirs.into_iter().rev() {
+            let Ok(relative) = path.strip_prefix(dir) else { continue };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            let mut read = self.read.borrow_mut();
+            let patterns = read.entry(dir.to_path_buf()).or_insert_with(|| {
+                IGNORE_FILES.iter().flat_map(|file| parse(&fs::read_to_string(dir.join(file)).unwrap_or_default())).collect()
+            });
+            for pattern in patterns.iter().filter(|pattern| is_dir || !pattern.directories_only) {
+                let matched = if pattern.anchored { glob::matches_path(&pattern.glob, &relative) } else { glob::matches(&pattern.glob, &name) };
+                if matched {
+                    ignored = !pattern.negated;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignores_by_nearest_files() {
+        let dir = std::env::temp_dir().join(format!("spec-check-ignore-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/vendor")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".gitignore"), "# build output\n/target\n*.bak\nbuild/\n").unwrap();
+        fs::write(dir.join("src/.ignore"), "vendor/**/*.rs\n!vendor/keep.rs\nbuild\n").unwrap();
+
+        let ignores = Ignores::default();
+        assert!(ignores.is_ignored(&dir.join("target"), true));
+        assert!(!ignores.is_ignored(&dir.join("src/target"), true));
+        assert!(ignores.is_ignored(&dir.join("src/lib.rs.bak"), false));
+        assert!(ignores.is_ignored(&dir.join("src/vendor/zlib.rs"), false));
+        assert!(!ignores.is_ignored(&dir.join("src/vendor/keep.rs"), false));
+        // `build/` is for directories only, but the deeper `build` is for any file
+        assert!(!ignores.is_ignored(&dir.join("build"), false));
+        assert!(ignores.is_ignored(&dir.join("src/build"), false));
+        assert!(!ignores.is_ignored(&dir.join("src/lib.rs"), false));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
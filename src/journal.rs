@@ -0,0 +1,184 @@
+//! Journal of the last verdict per source file, with the size, modification time and hash of
+//! its inputs, so unchanged pairs can be skipped without even reading them
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Files modified this recently may change again within the same timestamp tick,
+/// so their modification time isn't trusted on the next run (only their hash)
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Size and modification time of a file when it was looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    len: i64,
+    modified: i64,
+}
+
+impl Stamp {
+    /// The current stamp of `path`, or none if it can't be read
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self { len: metadata.len() as i64, modified: modified.as_nanos() as i64 })
+    }
+
+    fn is_racy(&self) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now.as_nanos() as i64 - self.modified < RACY_WINDOW.as_nanos() as i64
+    }
+}
+
+/// Stamps and content hashes of a source file and its spec, whose hash covers the files it includes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inputs {
+    pub source: Option<Stamp>,
+    pub source_hash: String,
+    pub spec: Option<Stamp>,
+    pub spec_hash: Option<String>,
+    /// The stamps of the files the spec includes; the spec's own stamp is only trusted with them
+    #[serde(default)]
+    pub includes: BTreeMap<PathBuf, Stamp>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry<V> {
+    inputs: Inputs,
+    verdict: V,
+}
+
+#[derive(Serialize, Deserialize)]
+struct File<V> {
+    settings: String,
+    files: BTreeMap<PathBuf, Entry<V>>,
+}
+
+pub struct Journal<V> {
+    path: Option<PathBuf>,
+    settings: String,
+    writable: bool,
+    previous: HashMap<PathBuf, Entry<V>>,
+    current: Mutex<BTreeMap<PathBuf, Entry<V>>>,
+}
+
+impl<V: Serialize + DeserializeOwned + Clone> Journal<V> {
+    /// Load the journal at `path` (none disables it). Entries recorded with different
+    /// `settings` are discarded; a read-only journal is never written back.
+    pub fn load(path: Option<PathBuf>, settings: u64, writable: bool) -> Self {
+        let settings = format!("{:016x}", settings);
+        let previous = path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| toml::from_str::<File<V>>(&content).ok())
+            .filter(|file| file.settings == settings)
+            .map(|file| file.files.into_iter().collect())
+            .unwrap_or_default();
+        Self { path, settings, writable, previous, current: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// The last verdict for `source` if neither it nor `spec` changed size or modification time
+    pub fn unchanged(&self, source: &Path, spec: Option<&Path>) -> Option<V> {
+        let entry = self.previous.get(source)?;
+        let trusted = |recorded: Option<Stamp>, path: Option<&Path>| match (recorded, path) {
+            (Some(recorded), Some(path)) => Stamp::of(path) == Some(recorded),
+            _ => false,
+        };
+        if !trusted(entry.inputs.source, Some(source)) {
+            return None;
+        }
+        if spec.is_some() != entry.inputs.spec_hash.is_some() || (spec.is_some() && !trusted(entry.inputs.spec, spec)) {
+            return None;
+        }
+        if !entry.inputs.includes.iter().all(|(path, &stamp)| trusted(Some(stamp), Some(path))) {
+            return None;
+        }
+        self.keep(source, entry.clone());
+        Some(entry.verdict.clone())
+    }
+
+    /// The last verdict for `source` if its inputs have the same content, even if touched since
+    pub fn same_content(&self, source: &Path, inputs: &Inputs) -> Option<V> {
+        let entry = self.previous.get(source)?;
+        if entry.inputs.source_hash != inputs.source_hash || entry.inputs.spec_hash != inputs.spec_hash {
+            return None;
+        }
+        let verdict = entry.verdict.clone();
+        self.record(source, inputs.clone(), verdict.clone());
+        Some(verdict)
+    }
+
+    /// Remember the verdict for `source`, given the stamps taken before its inputs were read
+    pub fn record(&self, source: &Path, mut inputs: Inputs, verdict: V) {
+        inputs.source = inputs.source.filter(|stamp| !stamp.is_racy());
+        inputs.spec = inputs.spec.filter(|stamp| !stamp.is_racy() && !inputs.includes.values().any(Stamp::is_racy));
+        self.keep(source, Entry { inputs, verdict });
+    }
+
+    fn keep(&self, source: &Path, entry: Entry<V>) {
+        let mut current = self.current.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        current.insert(source.to_path_buf(), entry);
+    }
+
+    /// Write the journal back, with the entries seen this run (and earlier ones for files
+    /// this run stopped before reaching). I/O problems are ignored; the journal is only a shortcut.
+    pub fn save(self, sources: &[&Path]) {
+        let (Some(path), true) = (&self.path, self.writable) else { return };
+        let mut files = self.current.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut previous = self.previous;
+        for source in sources {
+            if !files.contains_key(*source) && let Some(entry) = previous.remove(*source) {
+                files.insert(source.to_path_buf(), entry);
+            }
+        }
+
+        let Ok(serialized) = toml::to_string(&File { settings: self.settings, files }) else { return };
+        let temp = path.with_extension("tmp");
+        let written = path.parent().map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&temp, serialized))
+            .and_then(|_| fs::rename(&temp, path));
+        if written.is_err() {
+            fs::remove_file(&temp).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_skips_unchanged_and_touched_files() {
+        let dir = std::env::temp_dir().join(format!("spec-check-journal-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("lib.rs");
+        let journal_path = dir.join("journal.toml");
+        fs::write(&source, "pub struct A;").unwrap();
+
+        // Pretend the file was written long ago, so its modification time is trusted
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options().write(true).open(&source).unwrap().set_modified(old).unwrap();
+        let inputs = Inputs { source: Stamp::of(&source), source_hash: "a".to_string(), spec: None, spec_hash: None, includes: BTreeMap::new() };
+
+        let journal = Journal::load(Some(journal_path.clone()), 1, true);
+        assert_eq!(journal.unchanged(&source, None), None::<String>);
+        journal.record(&source, inputs.clone(), "ok".to_string());
+        journal.save(&[&source]);
+
+        let journal = Journal::<String>::load(Some(journal_path.clone()), 1, true);
+        assert_eq!(journal.unchanged(&source, None).as_deref(), Some("ok"));
+        assert_eq!(journal.unchanged(&source, Some(&dir.join("lib.md"))), None);
+
+        // Touching the file forces a hash comparison, which still matches
+        fs::File::options().write(true).open(&source).unwrap().set_modified(SystemTime::now()).unwrap();
+        assert_eq!(journal.unchanged(&source, None), None);
+        assert_eq!(journal.same_content(&source, &inputs).as_deref(), Some("ok"));
+
+        // Different settings invalidate everything
+        assert_eq!(Journal::<String>::load(Some(journal_path), 2, true).unchanged(&source, None), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+//! Minimal JSON output for machine-readable reports, built on `toml::Value`
+//! so report structures can be assembled with the same types as the config.
+//! Reports can be read back, too, for files spec-check writes and later reads.
+
+use anyhow::{Context, Result};
+use std::fmt::Write;
+
+/// Render a value as pretty-printed JSON (two-space indent, keys in table order)
+pub fn to_string_pretty(value: &toml::Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, 0);
+    out.push('\n');
+    out
+}
+
+fn write_value(out: &mut String, value: &toml::Value, indent: usize) {
+    match value {
+        toml::Value::String(s) => write_string(out, s),
+        toml::Value::Integer(i) => write!(out, "{}", i).unwrap(),
+        toml::Value::Float(f) if f.is_finite() => write!(out, "{}", f).unwrap(),
+        toml::Value::Float(_) => out.push_str("null"),
+        toml::Value::Boolean(b) => write!(out, "{}", b).unwrap(),
+        toml::Value::Datetime(d) => write_string(out, &d.to_string()),
+        toml::Value::Array(items) => {
+            if items.is_empty() {
+                out.push_str("[]");
+                return;
+            }
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                out.push_str(if index == 0 { "\n" } else { ",\n" });
+                push_indent(out, indent + 1);
+                write_value(out, item, indent + 1);
+            }
+            out.push('\n');
+            push_indent(out, indent);
+            out.push(']');
+        }
+        toml::Value::Table(table) => {
+            if table.is_empty() {
+                out.push_str("{}");
+                return;
+            }
+            out.push('{');
+            for (index, (key, item)) in table.iter().enumerate() {
+                out.push_str(if index == 0 { "\n" } else { ",\n" });
+                push_indent(out, indent + 1);
+                write_string(out, key);
+                out.push_str(": ");
+                write_value(out, item, indent + 1);
+            }
+            out.push('\n');
+            push_indent(out, indent);
+            out.push('}');
+        }
+    }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    out.push_str(&"  ".repeat(indent));
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Parse JSON into a `toml::Value`. TOML has no `null`, so nulls are left out of the tables
+/// and arrays they are in; a `null` document is an empty table.
+pub fn parse(text: &str) -> Result<toml::Value> {
+    let mut reader = Reader { text, at: 0 };
+    let value = reader.value()?.unwrap_or_else(|| toml::Value::Table(toml::Table::new()));
+    reader.skip_whitespace();
+    if reader.at < text.len() {
+        anyhow::bail!("Unexpected text after the JSON value at byte {}", reader.at);
+    }
+    Ok(value)
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.at..];
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.at..].chars().next()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            anyhow::bail!("Expected `{}` at byte {}", c, self.at);
+        }
+        self.at += 1;
+        Ok(())
+    }
+
+    /// The next value, `None` for `null`
+    fn value(&mut self) -> Result<Option<toml::Value>> {
+        self.skip_whitespace();
+        let rest = &self.text[self.at..];
+        for (word, value) in [("true", Some(toml::Value::Boolean(true))), ("false", Some(toml::Value::Boolean(false))), ("null", None)] {
+            if rest.starts_with(word) {
+                self.at += word.len();
+                return Ok(value);
+            }
+        }
+        match self.peek() {
+            Some('"') => Ok(Some(toml::Value::String(self.string()?))),
+            Some('[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                    return Ok(Some(toml::Value::Array(items)));
+                }
+                loop {
+                    items.extend(self.value()?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(']')?;
+                Ok(Some(toml::Value::Array(items)))
+            }
+            Some('{') => {
+                self.at += 1;
+                let mut table = toml::Table::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                    return Ok(Some(toml::Value::Table(table)));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    if let Some(value) = self.value()? {
+                        table.insert(key, value);
+                    }
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        _ => break,
+                    }
+                }
+                self.expect('}')?;
+                Ok(Some(toml::Value::Table(table)))
+            }
+            _ => {
+                let end = rest.find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c))).unwrap_or(rest.len());
+                let number = &rest[..end];
+                let value = match number.parse::<i64>() {
+                    Ok(integer) => toml::Value::Integer(integer),
+                    Err(_) => toml::Value::Float(number.parse().with_context(|| format!("Expected a JSON value at byte {}", self.at))?),
+                };
+                self.at += end;
+                Ok(Some(value))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        if self.peek() != Some('"') {
+            anyhow::bail!("Expected a string at byte {}", self.at);
+        }
+        let mut out = String::new();
+        let mut chars = self.text[self.at + 1..].char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.at += index + 2;
+                    return Ok(out);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        let code = u32::from_str_radix(&hex, 16).with_context(|| format!("Invalid escape `\\u{}`", hex))?;
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(other) => out.push(other),
+                    None => break,
+                },
+                c => out.push(c),
+            }
+        }
+        anyhow::bail!("Unterminated string at byte {}", self.at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_string_pretty_escapes_and_nests() {
+        let value: toml::Value = toml::from_str("name = \"a \\\"b\\\"\\n\"\nitems = [1, true]\nempty = []").unwrap();
+        assert_eq!(
+            to_string_pretty(&value),
+            "{\n  \"empty\": [],\n  \"items\": [\n    1,\n    true\n  ],\n  \"name\": \"a \\\"b\\\"\\n\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_reads_back_what_it_writes() {
+        let value: toml::Value = toml::from_str("name = \"a \\\"b\\\"\\n\"\nitems = [1, true, -2.5]\nempty = []\nnested = { key = \"é\" }").unwrap();
+        assert_eq!(parse(&to_string_pretty(&value)).unwrap(), value);
+        assert_eq!(parse(r#"{"a": null, "b": [null, "\u00e9"]}"#).unwrap(), toml::from_str::<toml::Value>("b = [\"é\"]").unwrap());
+        assert!(parse("{\"a\": 1").is_err());
+        assert!(parse("[1] 2").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! JUnit XML output, the format CI test dashboards aggregate: each checked file is a test
+//! case, and each of its findings a failure
+
+use crate::reporter::Finding;
+use crate::rules;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// The report for a run that checked `files`. Files that only appear in `findings` get a test
+/// case of their own, after the checked ones.
+pub fn to_string(files: &[PathBuf], findings: &[Finding]) -> String {
+    let mut cases: Vec<&Path> = files.iter().map(PathBuf::as_path).collect();
+    for finding in findings {
+        if !cases.contains(&finding.file.as_path()) {
+            cases.push(&finding.file);
+        }
+    }
+    // A file that couldn't be parsed wasn't checked at all, which JUnit calls an error
+    let is_error = |finding: &&Finding| finding.rule == rules::PARSE_FAILURE;
+    // Counted per test case, as JUnit readers expect
+    let errors = cases.iter().filter(|&&file| findings.iter().filter(is_error).any(|finding| finding.file == file)).count();
+    let failing = cases.iter().filter(|&&file| findings.iter().any(|finding| finding.file == file)).count();
+    let failures = failing - errors;
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let counts = format!("tests=\"{}\" failures=\"{}\" errors=\"{}\"", cases.len(), failures, errors);
+    writeln!(out, "<testsuites name=\"spec-check\" {}>", counts).unwrap();
+    writeln!(out, "  <testsuite name=\"spec-check\" {}>", counts).unwrap();
+    for file in cases {
+        let path = escape(&file.to_string_lossy().replace('\\', "/"));
+        let found: Vec<&Finding> = findings.iter().filter(|finding| finding.file == file).collect();
+        if found.is_empty() {
+            writeln!(out, "    <testcase classname=\"spec-check\" name=\"{}\" file=\"{}\"/>", path, path).unwrap();
+            continue;
+        }
+        writeln!(out, "    <testcase classname=\"spec-check\" name=\"{}\" file=\"{}\">", path, path).unwrap();
+        for finding in found {
+            let element = if is_error(&finding) { "error" } else { "failure" };
+            let location = finding.line.map_or_else(|| path.clone(), |line| format!("{}:{}", path, line));
+            writeln!(out, "      <{} type=\"{}\" message=\"{}\">{}: {}</{}>", element, finding.rule, escape(&finding.message),
+                location, escape(&finding.message), element).unwrap();
+        }
+        writeln!(out, "    </testcase>").unwrap();
+    }
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Not allowed in XML 1.0 at all
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_are_test_cases_and_findings_failures() {
+        let files = [PathBuf::from("src/lib.rs"), PathBuf::from("src/math.rs")];
+        let findings = [
+            Finding::new(rules::SIGNATURE_MISMATCH, Path::new("src/math.rs"), Some(3), "fn lerp<T> differs from the spec".to_string()),
+            Finding::new(rules::PARSE_FAILURE, Path::new("src/broken.rs"), None, "Failed to parse: expected `;`".to_string()),
+        ];
+        let xml = to_string(&files, &findings);
+        assert!(xml.contains("<testsuites name=\"spec-check\" tests=\"3\" failures=\"1\" errors=\"1\">"));
+        assert!(xml.contains("<testcase classname=\"spec-check\" name=\"src/lib.rs\" file=\"src/lib.rs\"/>"));
+        assert!(xml.contains("<failure type=\"SC003\" message=\"fn lerp&lt;T&gt; differs from the spec\">src/math.rs:3: fn lerp&lt;T&gt; differs from the spec</failure>"));
+        assert!(xml.contains("<error type=\"SC006\" message=\"Failed to parse: expected `;`\">src/broken.rs: "));
+    }
+}
@@ -0,0 +1,830 @@
+//! The checker behind the `spec-check` CLI, for tools that want results as data
+//! instead of scraping the log.
+//!
+//! ```
+//! let code = spec_check::parse_rust_items("pub struct Mesh { pub vertices: Vec<f32> }", Default::default())?;
+//! let spec = spec_check::extract_spec_items("```rust\npub struct Mesh;\n```\n", Default::default());
+//!
+//! let result = spec_check::compare(code, spec, &["doc".to_string()]);
+//! assert_eq!(result.signature_mismatches.len(), 1);
+//! assert_eq!(result.signature_mismatches[0].spec_item.line_number, 2);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! [`check`] runs the whole check over a source and a spec tree, as the CLI does, and
+//! [`check_with`] over a source tree whose spec items come from a [`SpecSource`].
+
+pub mod annotations;
+pub mod api_report;
+pub mod asciidoc_parser;
+pub mod baseline;
+pub mod blame;
+pub mod cfg;
+pub mod comparator;
+pub mod config;
+pub mod coverage;
+pub mod expand;
+pub mod file_walker;
+pub mod fix;
+pub mod html;
+pub mod ignore_files;
+pub mod junit;
+pub mod line_diff;
+pub mod markdown_parser;
+pub mod naming;
+pub mod placeholder;
+pub mod plugin;
+pub mod prometheus;
+pub mod reporter;
+pub mod requirements;
+pub mod rst_parser;
+pub mod rules;
+pub mod rust_parser;
+pub mod rustdoc_json;
+pub mod sarif;
+pub mod scaffold;
+pub mod semver;
+pub mod signature_diff;
+pub mod skeleton;
+pub mod snapshot;
+pub mod spec_format;
+pub mod spec_tests;
+pub mod testing;
+pub mod type_paths;
+pub mod wasm_rules;
+pub mod word_diff;
+
+// Support code for the CLI; not part of the stable API
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod git;
+#[doc(hidden)]
+pub mod glob;
+#[doc(hidden)]
+pub mod journal;
+#[doc(hidden)]
+pub mod json;
+#[doc(hidden)]
+pub mod parallel;
+#[doc(hidden)]
+pub mod project;
+#[doc(hidden)]
+pub mod terminal;
+#[doc(hidden)]
+pub mod webhook;
+#[doc(hidden)]
+pub mod zip;
+
+pub use comparator::{AttributeMismatch, CompareOptions, ComparisonResult, SignatureMismatch, SpecParseError};
+pub use reporter::Finding;
+pub use rust_parser::{ItemKind, RustItem};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Items of a Rust source file at least as visible as `visibility`
+pub fn parse_rust_items(source: &str, visibility: config::Visibility) -> Result<Vec<RustItem>> {
+    rust_parser::parse_rust_file(source, visibility)
+}
+
+/// Items declared in the ```rust blocks of a spec, with line numbers in the markdown.
+/// Blocks that don't parse are left out; [`parse_spec`] reports them.
+pub fn extract_spec_items(markdown: &str, visibility: config::Visibility) -> Vec<RustItem> {
+    parse_spec(markdown, visibility).0
+}
+
+/// The items of a spec's ```rust blocks, as `extract_spec_items` has them, and the blocks that
+/// don't parse
+pub fn parse_spec(markdown: &str, visibility: config::Visibility) -> (Vec<RustItem>, Vec<SpecParseError>) {
+    parse_spec_as(markdown, &spec_format::Markdown, visibility)
+}
+
+/// Like [`parse_spec`], for a spec in `format`. Blocks included from other files are left out,
+/// as there's no file to read them relative to.
+pub fn parse_spec_as(spec: &str, format: &dyn spec_format::SpecFormat, visibility: config::Visibility) -> (Vec<RustItem>, Vec<SpecParseError>) {
+    parse_blocks(spec, format, None, visibility, &mut Vec::new())
+}
+
+/// Like [`parse_spec_as`], for the contents of `spec_file` in its format, reading the files its
+/// blocks include relative to it
+pub fn parse_spec_file(spec: &str, spec_file: &Path, visibility: config::Visibility) -> (Vec<RustItem>, Vec<SpecParseError>) {
+    parse_blocks(spec, spec_format::of(spec_file), Some(spec_file), visibility, &mut vec![identity(spec_file)])
+}
+
+/// Every file `spec_file` includes, directly or through the specs it includes
+pub fn spec_includes(spec: &str, spec_file: &Path) -> Vec<PathBuf> {
+    let mut includes: Vec<PathBuf> = Vec::new();
+    let mut seen = vec![identity(spec_file)];
+    let mut pending = vec![(spec.to_string(), spec_file.to_path_buf())];
+    while let Some((spec, file)) = pending.pop() {
+        let dir = file.parent().unwrap_or(Path::new(""));
+        for include in spec_format::of(&file).rust_blocks(&spec).filter_map(|block| block.include.map(|include| dir.join(include))) {
+            let include_identity = identity(&include);
+            if seen.contains(&include_identity) {
+                continue;
+            }
+            seen.push(include_identity);
+            if spec_format::is_spec_file(&include) && let Ok(content) = fs::read_to_string(&include) {
+                pending.push((content, include.clone()));
+            }
+            includes.push(include);
+        }
+    }
+    includes
+}
+
+/// A file's path as its includes are told apart, so one reached by two paths is seen as one
+fn identity(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The items of `spec`, read from `file` if it's known. `including` are the specs whose includes
+/// led to it, which it can't include again.
+fn parse_blocks(spec: &str, format: &dyn spec_format::SpecFormat, file: Option<&Path>, visibility: config::Visibility, including: &mut Vec<PathBuf>) -> (Vec<RustItem>, Vec<SpecParseError>) {
+    let (mut items, mut errors) = (Vec::new(), Vec::new());
+    let sections = format.sections(spec);
+    for (index, block) in format.rust_blocks(spec).enumerate() {
+        if let Some(tag) = &block.unknown_tag {
+            let message = format!("unknown tag `{}` on its fence; tag it `ignore` if it isn't meant to be checked", tag);
+            errors.push(SpecParseError { block: index + 1, line: block.line.saturating_sub(1).max(1), message, skipped: None });
+            continue;
+        }
+        let mut block_items = match block.include {
+            Some(include) => {
+                let Some(file) = file else { continue };
+                match included_items(include, file, visibility, including) {
+                    Ok(included) => included,
+                    Err(message) => {
+                        errors.push(SpecParseError { block: index + 1, line: block.line, message, skipped: None });
+                        continue;
+                    }
+                }
+            }
+            None => match rust_parser::parse_spec_block_with_skipped(&block.code, block.line, visibility) {
+                Ok((block_items, skipped)) => {
+                    for (line, text) in skipped {
+                        errors.push(SpecParseError { block: index + 1, line, message: "not an item spec-check can compare".to_string(), skipped: Some(text) });
+                    }
+                    block_items
+                }
+                Err(err) => {
+                    // syn counts lines from 1 within the block
+                    let line = err.downcast_ref::<syn::Error>().map(|err| err.span().start().line).filter(|&line| line > 0);
+                    errors.push(SpecParseError { block: index + 1, line: line.map_or(block.line, |line| block.line + line - 1), message: err.to_string(), skipped: None });
+                    continue;
+                }
+            },
+        };
+        let features = cfg::feature_attribute(&block.features);
+        let requirements = block.prose.map(requirements::ids).unwrap_or_default();
+        // Prose describes the item of a block of one, not each item of a longer block
+        let prose = block.prose.filter(|_| block_items.len() == 1);
+        let versions = markdown_parser::block_versions(&block.tags, &sections, block.line);
+        for item in &mut block_items {
+            item.attributes.extend(features.clone());
+            // An included spec's items keep their own stage and prose unless the directive has them
+            if block.include.is_none() || block.stage != rust_parser::Stage::default() {
+                item.stage = block.stage.clone();
+            }
+            if block.include.is_none() || prose.is_some() {
+                item.requirements.clone_from(&requirements);
+                item.prose = prose.map(str::to_string);
+            }
+            if block.include.is_none() || versions != rust_parser::Versions::default() {
+                item.versions.clone_from(&versions);
+            }
+            // An included file's lines aren't the spec's; its items are at the directive
+            if block.include.is_some() {
+                item.line_number = block.line;
+            }
+        }
+        items.extend(block_items);
+    }
+    (items, errors)
+}
+
+/// The items of the file a block of `file` includes: a spec in its own format, or else Rust code
+fn included_items(include: &str, file: &Path, visibility: config::Visibility, including: &mut Vec<PathBuf>) -> Result<Vec<RustItem>, String> {
+    let path = file.parent().unwrap_or(Path::new("")).join(include);
+    let identity = identity(&path);
+    if including.contains(&identity) {
+        return Err(format!("{} includes itself", include));
+    }
+    let content = fs::read_to_string(&path).map_err(|err| format!("Failed to read included {}: {}", include, err))?;
+    let format: &dyn spec_format::SpecFormat = if spec_format::is_spec_file(&path) { spec_format::of(&path) } else { &spec_format::RustSource };
+    including.push(identity);
+    let (items, errors) = parse_blocks(&content, format, Some(&path), visibility, including);
+    including.pop();
+    match errors.first() {
+        Some(error) => Err(format!("{} line {}: {}", include, error.line, error.message)),
+        None => Ok(items),
+    }
+}
+
+/// Compare code items against spec items, ignoring the named attributes (e.g. `doc`)
+pub fn compare(code_items: Vec<RustItem>, spec_items: Vec<RustItem>, ignored_attributes: &[String]) -> ComparisonResult {
+    comparator::compare_items(code_items, spec_items, ignored_attributes)
+}
+
+/// Where `check` finds the source files and the specs they are checked against
+#[derive(Debug, Clone)]
+pub struct CheckPaths {
+    pub src: PathBuf,
+    pub spec: PathBuf,
+    /// `[[mapping]]` rules, tried before the mirrored path
+    pub mappings: Vec<config::MappingRule>,
+    /// Which files under `src` and `spec` are checked
+    pub filter: config::FileFilter,
+}
+
+impl Default for CheckPaths {
+    fn default() -> Self {
+        Self { src: PathBuf::from("src"), spec: PathBuf::from("spec"), mappings: Vec::new(), filter: config::FileFilter::default() }
+    }
+}
+
+/// How `check` compares items
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    pub visibility: config::Visibility,
+    /// Attributes left out of the comparison, e.g. `doc`
+    pub ignored_attributes: Vec<String>,
+    pub compare: CompareOptions,
+    /// The package's version, for spec items deprecated until a removal version
+    pub version: Option<String>,
+    /// The version of the API the code is checked against, if not the package's
+    pub spec_version: Option<String>,
+    /// Which side findings ask to change, from `source-of-truth`
+    pub source_of_truth: Option<config::SourceOfTruth>,
+}
+
+impl CheckOptions {
+    /// The settings of a crate's `[package.metadata.spec-check]`, as the CLI reads them
+    pub fn from_config(config: &config::Config) -> Self {
+        Self {
+            visibility: config.visibility(),
+            ignored_attributes: config.get_ignored_attributes(),
+            compare: config.compare_options(),
+            version: None,
+            spec_version: None,
+            source_of_truth: config.source_of_truth,
+        }
+    }
+
+    /// The version of the API the spec's blocks are checked for: `spec_version`, or else the
+    /// package's
+    pub fn checked_version(&self) -> Option<&str> {
+        self.spec_version.as_deref().or(self.version.as_deref())
+    }
+}
+
+/// The outcome of `check`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckReport {
+    /// Every source file checked, in file order
+    pub files: Vec<PathBuf>,
+    /// Findings in file order, as the log would list them
+    pub findings: Vec<Finding>,
+}
+
+impl CheckReport {
+    /// Whether every file matches its spec
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// The findings of one source file
+    pub fn findings_in<'a>(&'a self, file: &'a Path) -> impl Iterator<Item = &'a Finding> {
+        self.findings.iter().filter(move |finding| finding.file == file)
+    }
+}
+
+/// Check every source file under `paths.src` against its spec under `paths.spec`, without the
+/// CLI's log, cache or parallelism
+///
+/// ```no_run
+/// let options = spec_check::CheckOptions { ignored_attributes: vec!["doc".to_string()], ..Default::default() };
+/// let report = spec_check::check(&spec_check::CheckPaths::default(), &options)?;
+/// for finding in &report.findings {
+///     eprintln!("{}:{}: {} {}", finding.file.display(), finding.line.unwrap_or(0), finding.rule, finding.message);
+/// }
+/// assert!(report.is_clean(), "{} of {} files drifted from the spec", report.findings.len(), report.files.len());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn check(paths: &CheckPaths, options: &CheckOptions) -> Result<CheckReport> {
+    let mut report = CheckReport::default();
+    let mappings = file_walker::find_file_mappings(&paths.src, &paths.spec, &paths.mappings, &paths.filter)?;
+    let directories = config::DirectoryOverrides::load(&paths.src, &paths.spec)?;
+    let mut parsed = Vec::new();
+    for mapping in &mappings {
+        let content = fs::read_to_string(&mapping.rust_file)
+            .with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
+        parsed.push(parse_rust_items(&content, mapping.settings.visibility(options.visibility)));
+    }
+    let (mut pooled, spec_only) = compare_mapped(paths, &mappings, &parsed, &directories, options)?;
+
+    for (index, (mapping, code_items)) in mappings.iter().zip(parsed).enumerate() {
+        report.files.push(mapping.rust_file.clone());
+        let code_items = match code_items {
+            Ok(items) => items,
+            Err(err) => {
+                report.findings.push(reporter::parse_failure_finding(&mapping.rust_file, &err));
+                continue;
+            }
+        };
+        let Some(spec_file) = &mapping.spec_file else {
+            report.findings.push(reporter::missing_spec_finding(&mapping.rust_file));
+            continue;
+        };
+        let result = match pooled.remove(&index) {
+            Some(result) => result,
+            None => {
+                let markdown = fs::read_to_string(spec_file)
+                    .with_context(|| format!("Failed to read {}", spec_file.display()))?;
+                let (spec_items, spec_parse_errors) = parse_spec_file(&markdown, spec_file, mapping.settings.visibility(options.visibility));
+                ComparisonResult {
+                    spec_parse_errors,
+                    ..compare_checked(&mapping.rust_file, Some(spec_file), code_items, spec_items, &mapping.settings, &directories, options)
+                }
+            }
+        };
+        report.findings.extend(reporter::findings_for(&mapping.rust_file, Some(spec_file), &result, options.source_of_truth));
+    }
+    for (spec_file, result) in spec_only {
+        report.findings.extend(reporter::findings_for(&spec_file, Some(&spec_file), &result, options.source_of_truth));
+    }
+    Ok(report)
+}
+
+/// Spec items kept somewhere other than a spec tree: a database, items generated from an
+/// OpenAPI document, another repository. [`check_with`] compares each source file with the items
+/// its source gives it, as [`check`] does with its spec file's.
+pub trait SpecSource {
+    /// The items `rust_file` is checked against, with the lines findings give for them, or `None`
+    /// if it has no spec
+    fn spec_items(&self, rust_file: &Path) -> Result<Option<Vec<RustItem>>>;
+
+    /// What findings give as the spec of `rust_file`, if anything
+    fn spec_path(&self, _rust_file: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Check every source file under `src` that `filter` allows against the items `source` has for
+/// it, with no spec files read
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// struct Generated;
+///
+/// impl spec_check::SpecSource for Generated {
+///     fn spec_items(&self, rust_file: &Path) -> anyhow::Result<Option<Vec<spec_check::RustItem>>> {
+///         let spec = std::fs::read_to_string(Path::new("target/generated").join(rust_file.file_name().unwrap()))?;
+///         Ok(Some(spec_check::parse_rust_items(&spec, Default::default())?))
+///     }
+/// }
+///
+/// let report = spec_check::check_with(Path::new("src"), &Default::default(), &Generated, &Default::default())?;
+/// assert!(report.is_clean());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn check_with(src: &Path, filter: &config::FileFilter, source: &dyn SpecSource, options: &CheckOptions) -> Result<CheckReport> {
+    let mut report = CheckReport::default();
+    let directories = config::DirectoryOverrides::default();
+    for rust_file in file_walker::find_rust_files(src, filter) {
+        report.files.push(rust_file.clone());
+        let content = fs::read_to_string(&rust_file)
+            .with_context(|| format!("Failed to read {}", rust_file.display()))?;
+        let code_items = match parse_rust_items(&content, options.visibility) {
+            Ok(items) => items,
+            Err(err) => {
+                report.findings.push(reporter::parse_failure_finding(&rust_file, &err));
+                continue;
+            }
+        };
+        let Some(spec_items) = source.spec_items(&rust_file)? else {
+            report.findings.push(reporter::missing_spec_finding(&rust_file));
+            continue;
+        };
+        let spec_file = source.spec_path(&rust_file);
+        let result = compare_checked(&rust_file, spec_file.as_deref(), code_items, spec_items, &Default::default(), &directories, options);
+        report.findings.extend(reporter::findings_for(&rust_file, spec_file.as_deref(), &result, options.source_of_truth));
+    }
+    Ok(report)
+}
+
+/// How [`compare_file`] compares one source file's items with its spec's
+#[derive(Clone, Copy)]
+pub struct FileComparison<'a> {
+    pub rust_file: &'a Path,
+    /// The one spec file the items are from, if they are from one, for plugins and annotations
+    pub spec_file: Option<&'a Path>,
+    /// Attributes left out of the comparison, e.g. `doc`
+    pub ignored_attributes: &'a [String],
+    pub compare: &'a CompareOptions,
+    /// The version of the API spec items are kept for and deprecations are due by
+    pub version: Option<&'a str>,
+    /// With these, `#[spec_checked]` items are compared with the spec items they name instead
+    pub annotations: Option<&'a annotations::Annotations>,
+}
+
+/// Compare one source file's items with the spec items it is checked against, the way every
+/// check does: plugins' rules, the spec items of `version`, docs, order and naming, then the
+/// items themselves, relaxed as `compare` allows
+pub fn compare_file(comparison: &FileComparison, code_items: Vec<RustItem>, mut spec_items: Vec<RustItem>) -> ComparisonResult {
+    let custom_violations = plugin::run(&plugin::FileContext {
+        rust_file: comparison.rust_file,
+        spec_file: comparison.spec_file,
+        code_items: &code_items,
+        spec_items: &spec_items,
+    });
+    let (annotated, code_items) = match comparison.annotations {
+        Some(annotations) => {
+            if let Some(spec_file) = comparison.spec_file {
+                annotations.remove_claimed(spec_file, &mut spec_items);
+            }
+            annotations::split_annotated(code_items)
+        }
+        None => (Vec::new(), code_items),
+    };
+    let (ignored, compare) = (comparison.ignored_attributes, comparison.compare);
+    let spec_items = comparator::in_version(spec_items, comparison.version);
+    let doc_mismatches = if compare.docs { comparator::doc_mismatches(&code_items, &spec_items) } else { Vec::new() };
+    let ordering_violations = if compare.order { comparator::ordering_violations(&code_items, &spec_items) } else { Vec::new() };
+    let naming_violations = naming::violations(&code_items, &compare.naming);
+    let mut result = ComparisonResult {
+        custom_violations,
+        doc_mismatches,
+        ordering_violations,
+        naming_violations,
+        ..comparator::compare_items_at(code_items, spec_items, ignored, comparison.version)
+    };
+    if let Some(annotations) = comparison.annotations {
+        result.code_items += annotated.len();
+        annotations.check(annotated, ignored, &mut result);
+    }
+    comparator::relax(&mut result, compare, ignored);
+    result
+}
+
+/// The attributes left out of comparing a source file with its spec, and how the rest are
+/// compared: as configured, overridden by the `.spec-check.toml` files of the source file's
+/// subtree, with the spec's front matter on top
+pub fn file_settings(
+    rust_file: &Path,
+    settings: &config::SpecSettings,
+    directories: &config::DirectoryOverrides,
+    ignored_attributes: &[String],
+    compare: &CompareOptions,
+) -> (Vec<String>, CompareOptions) {
+    let overrides = directories.for_file(rust_file);
+    (settings.ignored_attributes(&overrides.ignored_attributes(ignored_attributes)), overrides.compare_options(compare))
+}
+
+/// [`compare_file`] as `check` calls it
+fn compare_checked(
+    rust_file: &Path,
+    spec_file: Option<&Path>,
+    code_items: Vec<RustItem>,
+    spec_items: Vec<RustItem>,
+    settings: &config::SpecSettings,
+    directories: &config::DirectoryOverrides,
+    options: &CheckOptions,
+) -> ComparisonResult {
+    let (ignored, compare) = file_settings(rust_file, settings, directories, &options.ignored_attributes, &options.compare);
+    let comparison = FileComparison {
+        rust_file,
+        spec_file,
+        ignored_attributes: &ignored,
+        compare: &compare,
+        version: options.checked_version(),
+        annotations: None,
+    };
+    compare_file(&comparison, code_items, spec_items)
+}
+
+/// Results for spec files with items no source file defines, by spec file
+pub type SpecOnly = Vec<(PathBuf, ComparisonResult)>;
+
+/// The results of the source files under `[[mapping]]` rules, by mapping index, each compared
+/// with the items of its rule's spec files it defines; and per spec file, the items no source
+/// file of the rule defines
+fn compare_mapped(
+    paths: &CheckPaths,
+    mappings: &[file_walker::FileMapping],
+    parsed: &[Result<Vec<RustItem>>],
+    directories: &config::DirectoryOverrides,
+    options: &CheckOptions,
+) -> Result<(HashMap<usize, ComparisonResult>, SpecOnly)> {
+    let cache = cache::Cache::new(None);
+    let read = |path: &Path| fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()));
+    let (mut results, mut spec_only) = (HashMap::new(), Vec::new());
+    for (specs, mut members) in file_walker::mapped_groups(mappings) {
+        let first = &mappings[members[0]];
+        // Files that don't parse are reported as such
+        members.retain(|&index| parsed[index].is_ok());
+        let code = members.iter()
+            .map(|&index| (mappings[index].rust_file.clone(), parsed[index].as_ref().cloned().unwrap_or_default()))
+            .collect();
+        let pool = Pool {
+            src: &paths.src,
+            spec: &paths.spec,
+            spec_files: specs,
+            headings: first.headings,
+            settings: &first.settings,
+            directories,
+            visibility: options.visibility,
+            ignored_attributes: &options.ignored_attributes,
+            compare: &options.compare,
+            version: options.checked_version(),
+            jobs: 1,
+        };
+        let (pooled, unmatched) = compare_pooled(&pool, code, &cache, &read)?;
+        results.extend(members.into_iter().zip(pooled));
+        spec_only.extend(unmatched);
+    }
+    Ok((results, spec_only))
+}
+
+/// Source files checked against the items of a set of spec files pooled, as for
+/// `--scope project` or a `[[mapping]]` rule
+#[doc(hidden)]
+pub struct Pool<'a> {
+    /// The trees module paths are taken relative to
+    pub src: &'a Path,
+    pub spec: &'a Path,
+    pub spec_files: &'a [PathBuf],
+    /// Whether the level-2 headings of the specs scope their items to modules
+    pub headings: bool,
+    /// From the front matter of the first spec
+    pub settings: &'a config::SpecSettings,
+    pub directories: &'a config::DirectoryOverrides,
+    pub visibility: config::Visibility,
+    pub ignored_attributes: &'a [String],
+    pub compare: &'a CompareOptions,
+    pub version: Option<&'a str>,
+    /// Threads the spec files are parsed on
+    pub jobs: usize,
+}
+
+/// The result of each source file of `code`, in order, compared with the items of the pool's
+/// spec files it defines; and an entry per spec file whose items none of them defines, reported
+/// as missing in code against the spec file itself. Spec files are read with `read` and parsed
+/// through `cache`.
+#[doc(hidden)]
+pub fn compare_pooled(
+    pool: &Pool,
+    code: Vec<(PathBuf, Vec<RustItem>)>,
+    cache: &cache::Cache,
+    read: &(dyn Fn(&Path) -> Result<String> + Sync),
+) -> Result<(Vec<ComparisonResult>, SpecOnly)> {
+    let visibility = pool.settings.visibility(pool.visibility);
+    let code: Vec<project::PoolFile> = code.into_iter().map(|(path, items)| {
+        let module = rustdoc_json::module_path(path.strip_prefix(pool.src).unwrap_or(&path));
+        project::PoolFile { path, module, items, scopes: Vec::new() }
+    }).collect();
+
+    let parsed = parallel::map(pool.spec_files, pool.jobs, |path| -> Result<(project::PoolFile, Vec<SpecParseError>)> {
+        let content = read(path)?;
+        let scopes = if pool.headings { project::heading_scopes(&content, spec_format::of(path)) } else { Vec::new() };
+        let (items, errors) = parse_spec_cached(&content, path, spec_hash(&content, path), visibility, cache)?;
+        let module = rustdoc_json::module_path(path.strip_prefix(pool.spec).unwrap_or(path));
+        Ok((project::PoolFile { path: path.clone(), module, items: comparator::in_version(items, pool.version), scopes }, errors))
+    });
+    let (mut spec, mut spec_parse_errors) = (Vec::new(), Vec::new());
+    for parsed in parsed {
+        let (file, errors) = parsed?;
+        spec.push(file);
+        spec_parse_errors.push(errors);
+    }
+
+    let assignment = project::assign(&code, spec);
+    // Plugins see a spec file only when the items are all from it
+    let only_spec = match pool.spec_files { [spec] => Some(spec.as_path()), _ => None };
+    let results = code.into_iter().zip(assignment.matched).map(|(file, spec_items)| {
+        let (ignored, compare) = file_settings(&file.path, pool.settings, pool.directories, pool.ignored_attributes, pool.compare);
+        let comparison = FileComparison {
+            rust_file: &file.path,
+            spec_file: only_spec,
+            ignored_attributes: &ignored,
+            compare: &compare,
+            version: pool.version,
+            annotations: None,
+        };
+        compare_file(&comparison, file.items, spec_items)
+    }).collect();
+
+    // A spec's unparseable blocks are reported once, against the spec itself
+    let spec_only = pool.spec_files.iter().zip(assignment.unmatched).zip(spec_parse_errors)
+        .filter(|((_, missing_in_code), errors)| !missing_in_code.is_empty() || !errors.is_empty())
+        .map(|((spec_file, missing_in_code), spec_parse_errors)| {
+            (spec_file.clone(), ComparisonResult { missing_in_code, spec_parse_errors, ..Default::default() })
+        })
+        .collect();
+    Ok((results, spec_only))
+}
+
+/// The hash of a spec's content and that of the files it includes, which its parsed items
+/// are cached by
+#[doc(hidden)]
+pub fn spec_hash(spec: &str, spec_file: &Path) -> u64 {
+    let included: Vec<Vec<u8>> = spec_includes(spec, spec_file).iter().map(|include| fs::read(include).unwrap_or_default()).collect();
+    let parts: Vec<&[u8]> = [spec.as_bytes()].into_iter().chain(included.iter().map(Vec::as_slice)).collect();
+    cache::hash(&parts)
+}
+
+/// [`parse_spec_file`] through `cache`, for a spec whose [`spec_hash`] is `hash`
+#[doc(hidden)]
+pub fn parse_spec_cached(
+    spec: &str,
+    spec_file: &Path,
+    hash: u64,
+    visibility: config::Visibility,
+    cache: &cache::Cache,
+) -> Result<(Vec<RustItem>, Vec<SpecParseError>)> {
+    let key = cache::hash(&[b"spec", &hash.to_le_bytes(), &[visibility as u8], spec_format::of(spec_file).extension().as_bytes()]);
+    cache.get_or_insert_with("spec-items", key, || Ok(parse_spec_file(spec, spec_file, visibility)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unparseable_spec_blocks_are_reported() {
+        let markdown = "# Math\n\n```rust\npub struct Vec3;\n```\n\n```rust\npub fn dot(a: Vec3, b: Vec3) -> f32\npub fn cross(a: Vec3, b: Vec3) -> Vec3;\n```\n";
+        let (items, errors) = parse_spec(markdown, Default::default());
+        assert_eq!(items.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["Vec3"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].block, errors[0].line), (2, 9));
+    }
+
+    #[test]
+    fn test_blocks_with_unknown_tags_are_reported() {
+        let markdown = "# Math\n\n```rust,planed\npub struct Vec3;\n```\n\n```rust,ignore\nlet v = Vec3;\n```\n";
+        let (items, errors) = parse_spec(markdown, Default::default());
+        assert!(items.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].block, errors[0].line), (1, 3));
+        assert!(errors[0].message.contains("`planed`"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn test_versioned_blocks_are_for_their_versions() {
+        let markdown = concat!(
+            "# Mesh\n\n## v1 API\n\n```rust\npub fn load(path: &str) {}\n```\n\n",
+            "## v2 API\n\n```rust\npub fn load(path: &Path) {}\n```\n\n```rust,since=2.1\npub fn save() {}\n```\n",
+        );
+        let (items, errors) = parse_spec(markdown, Default::default());
+        assert!(errors.is_empty());
+        let versions = |item: &RustItem| (item.versions.since.clone(), item.versions.until.clone());
+        assert_eq!(versions(&items[0]), (Some("1".to_string()), Some("2".to_string())));
+        assert_eq!(versions(&items[2]), (Some("2.1".to_string()), None));
+
+        let lines_at = |version| comparator::in_version(items.clone(), version).iter().map(|item| item.line_number).collect::<Vec<_>>();
+        assert_eq!(lines_at(Some("2.1.0")), [12, 16]);
+        assert_eq!(lines_at(Some("1.4")), [6]);
+        assert_eq!(lines_at(None), [6, 12, 16]);
+    }
+
+    #[test]
+    fn test_check_compares_the_checked_version() {
+        let dir = std::env::temp_dir().join(format!("spec-check-checked-version-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("spec")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn load(path: &Path) {}\n").unwrap();
+        fs::write(dir.join("spec/lib.md"), "## v1 API\n\n```rust\npub fn load(path: &str) {}\n```\n\n## v2 API\n\n```rust\npub fn load(path: &Path) {}\n```\n").unwrap();
+        let paths = CheckPaths { src: dir.join("src"), spec: dir.join("spec"), ..Default::default() };
+
+        let rules_at = |version: &str, spec_version: Option<&str>| {
+            let options = CheckOptions { version: Some(version.to_string()), spec_version: spec_version.map(str::to_string), ..Default::default() };
+            let report = check(&paths, &options).unwrap();
+            report.findings.iter().filter(|finding| finding.rule.starts_with("SC")).map(|finding| finding.rule).collect::<Vec<_>>()
+        };
+        assert!(rules_at("2.0.0", None).is_empty());
+        // The spec version wins over the package's, as on the command line
+        assert_eq!(rules_at("2.0.0", Some("1.3")), [rules::SIGNATURE_MISMATCH]);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_pooled_spec_items_go_to_the_files_that_define_them() {
+        let dir = std::env::temp_dir().join(format!("spec-check-pooled-{}", std::process::id()));
+        fs::create_dir_all(dir.join("spec")).unwrap();
+        fs::write(dir.join("spec/api.md"), "## geometry\n\n```rust\npub fn area() -> f64 {}\n```\n\n## math\n\n```rust\npub fn area() -> f32 {}\n```\n").unwrap();
+        fs::write(dir.join("spec/extra.md"), "```rust\npub fn gone() {}\n```\n").unwrap();
+        let spec_files = [dir.join("spec/api.md"), dir.join("spec/extra.md")];
+        let code = vec![
+            (dir.join("src/geometry.rs"), parse_rust_items("pub fn area() -> f32 { 0.0 }", Default::default()).unwrap()),
+            (dir.join("src/math.rs"), parse_rust_items("pub fn area() -> f32 { 0.0 }", Default::default()).unwrap()),
+        ];
+        let pool = Pool {
+            src: &dir.join("src"),
+            spec: &dir.join("spec"),
+            spec_files: &spec_files,
+            headings: true,
+            settings: &Default::default(),
+            directories: &Default::default(),
+            visibility: Default::default(),
+            ignored_attributes: &[],
+            compare: &Default::default(),
+            version: None,
+            jobs: 2,
+        };
+        let read = |path: &Path| Ok(fs::read_to_string(path)?);
+        let (results, spec_only) = compare_pooled(&pool, code, &cache::Cache::new(None), &read).unwrap();
+
+        // Each heading's item is compared with its own module's `area`
+        assert_eq!(results[0].signature_mismatches.len(), 1);
+        assert_eq!(results[0].signature_mismatches[0].spec_item.line_number, 4);
+        assert!(results[1].signature_mismatches.is_empty() && results[1].missing_in_code.is_empty());
+        assert_eq!(spec_only.len(), 1);
+        assert_eq!(spec_only[0].0, spec_files[1]);
+        assert_eq!(spec_only[0].1.missing_in_code.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["gone"]);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_spec_sources_replace_spec_files() {
+        struct Items(HashMap<&'static str, &'static str>);
+
+        impl SpecSource for Items {
+            fn spec_items(&self, rust_file: &Path) -> Result<Option<Vec<RustItem>>> {
+                let name = rust_file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+                self.0.get(name).map(|spec| parse_rust_items(spec, Default::default())).transpose()
+            }
+
+            fn spec_path(&self, rust_file: &Path) -> Option<PathBuf> {
+                Some(Path::new("db://specs").join(rust_file.file_name()?))
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("spec-check-source-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), "pub fn run(times: u32) {}\n").unwrap();
+        fs::write(dir.join("math.rs"), "pub struct Vec3;\n").unwrap();
+        fs::write(dir.join("extra.rs"), "pub struct Extra;\n").unwrap();
+        let source = Items(HashMap::from([("lib.rs", "pub fn run(times: u64) {}"), ("math.rs", "pub struct Vec3;")]));
+
+        let report = check_with(&dir, &Default::default(), &source, &CheckOptions::default()).unwrap();
+        assert_eq!(report.files.len(), 3);
+        // Less the custom rules other tests register
+        let findings: Vec<(&str, Option<&Path>)> = report.findings.iter().filter(|finding| finding.rule.starts_with("SC")).map(|finding| (finding.rule, finding.spec_path.as_deref())).collect();
+        assert_eq!(findings, [(rules::MISSING_SPEC_FILE, None), (rules::SIGNATURE_MISMATCH, Some(Path::new("db://specs/lib.rs")))]);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_included_specs_are_transcluded() {
+        let dir = std::env::temp_dir().join(format!("spec-check-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(dir.join("shared/errors.md"), "```rust
+pub enum Error { Io }
+```
+
+```rust,planned
+pub struct Retry;
+```
+").unwrap();
+        fs::write(dir.join("shared/loop.md"), "<!-- spec-check: include ../lib.md -->
+").unwrap();
+        let spec = concat!(
+            "# Lib
+
+```rust
+pub fn run() {}
+```
+
+<!-- spec-check: include shared/errors.md -->
+
+",
+            "<!-- spec-check: deprecated -->
+<!-- spec-check: include shared/missing.md -->
+
+<!-- spec-check: include shared/loop.md -->
+",
+        );
+        fs::write(dir.join("lib.md"), spec).unwrap();
+
+        let (items, errors) = parse_spec_file(spec, &dir.join("lib.md"), Default::default());
+        let summary: Vec<(&str, usize, rust_parser::Stage)> = items.iter().map(|item| (item.name.as_str(), item.line_number, item.stage.clone())).collect();
+        assert_eq!(summary, [
+            ("run", 4, rust_parser::Stage::Stable),
+            ("Error", 7, rust_parser::Stage::Stable),
+            ("Retry", 7, rust_parser::Stage::Planned),
+        ]);
+        let errors: Vec<(usize, &str)> = errors.iter().map(|error| (error.line, error.message.as_str())).collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].0 == 10 && errors[0].1.starts_with("Failed to read included shared/missing.md"), "{:?}", errors);
+        assert_eq!(errors[1], (12, "shared/loop.md line 1: ../lib.md includes itself"));
+        assert_eq!(spec_includes(spec, &dir.join("lib.md")).len(), 3);
+        // Without the spec's path there's nothing to include
+        assert_eq!(parse_spec(spec, Default::default()).0.len(), 1);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
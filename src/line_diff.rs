@@ -0,0 +1,103 @@
+//! Line-level unified diffs of whole files, for showing what a rewrite would change without
+//! making it
+
+use crate::word_diff::{self, Change};
+
+/// Unchanged lines shown around each change
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The lines turning `old` into `new`
+fn lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old_middle, new_middle) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut out: Vec<Line> = old[..prefix].iter().map(|line| Line::Same(line)).collect();
+    let (mut i, mut j) = (0, 0);
+    for change in word_diff::lcs(old_middle, new_middle) {
+        match change {
+            Change::Same => {
+                out.push(Line::Same(old_middle[i]));
+                (i, j) = (i + 1, j + 1);
+            }
+            Change::Removed => {
+                out.push(Line::Removed(old_middle[i]));
+                i += 1;
+            }
+            Change::Added => {
+                out.push(Line::Added(new_middle[j]));
+                j += 1;
+            }
+        }
+    }
+    out.extend(old[old.len() - suffix..].iter().map(|line| Line::Same(line)));
+    out
+}
+
+/// A unified diff from `old`, the content of the file at `path` (`None` for a file that
+/// doesn't exist yet), to `new`, as `git apply` takes it. Empty when nothing changes.
+pub fn unified(path: &str, old: Option<&str>, new: &str) -> String {
+    let (old_lines, new_lines): (Vec<&str>, Vec<&str>) = (old.unwrap_or_default().lines().collect(), new.lines().collect());
+    let diff = lines(&old_lines, &new_lines);
+    let changed: Vec<usize> = (0..diff.len()).filter(|&index| !matches!(diff[index], Line::Same(_))).collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    let mut out = match old {
+        Some(_) => format!("--- a/{path}\n+++ b/{path}\n"),
+        None => format!("--- /dev/null\n+++ b/{}\n", path),
+    };
+    // Changes closer than twice the context share a hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &index in &changed {
+        match hunks.last_mut() {
+            Some((_, end)) if index <= *end + 2 * CONTEXT => *end = index,
+            _ => hunks.push((index, index)),
+        }
+    }
+    for (first, last) in hunks {
+        let (start, end) = (first.saturating_sub(CONTEXT), (last + CONTEXT + 1).min(diff.len()));
+        let count = |keep: fn(&Line) -> bool, lines: &[Line]| lines.iter().filter(|line| keep(line)).count();
+        let is_old = |line: &Line| !matches!(line, Line::Added(_));
+        let is_new = |line: &Line| !matches!(line, Line::Removed(_));
+        let (old_start, new_start) = (count(is_old, &diff[..start]), count(is_new, &diff[..start]));
+        let (old_count, new_count) = (count(is_old, &diff[start..end]), count(is_new, &diff[start..end]));
+        // A range of no lines starts at the line before it
+        let position = |start: usize, count: usize| if count == 0 { start } else { start + 1 };
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", position(old_start, old_count), old_count, position(new_start, new_count), new_count));
+        for line in &diff[start..end] {
+            match line {
+                Line::Same(text) => out.push_str(&format!(" {}\n", text)),
+                Line::Removed(text) => out.push_str(&format!("-{}\n", text)),
+                Line::Added(text) => out.push_str(&format!("+{}\n", text)),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "# Math\n\n```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\n```\n\nMore.\n\n\n\n\n```rust\npub struct Mesh;\n```\n";
+        let new = old.replace("b: f32) -> f32", "b: f64) -> f64").replace("pub struct Mesh;", "pub struct Mesh {\n    pub vertices: Vec<f32>,\n}");
+        assert_eq!(unified("spec/math.md", Some(old), &new), concat!(
+            "--- a/spec/math.md\n+++ b/spec/math.md\n",
+            "@@ -1,7 +1,7 @@\n # Math\n \n ```rust\n-pub fn lerp(a: f32, b: f32) -> f32 {}\n+pub fn lerp(a: f32, b: f64) -> f64 {}\n ```\n \n More.\n",
+            "@@ -10,5 +10,7 @@\n \n \n ```rust\n-pub struct Mesh;\n+pub struct Mesh {\n+    pub vertices: Vec<f32>,\n+}\n ```\n",
+        ));
+        assert_eq!(unified("spec/new.md", None, "# New\n"), "--- /dev/null\n+++ b/spec/new.md\n@@ -0,0 +1,1 @@\n+# New\n");
+        assert_eq!(unified("spec/math.md", Some(old), old), "");
+    }
+}
@@ -1,131 +1,194 @@
-mod rust_parser;
-mod markdown_parser;
-mod comparator;
-mod file_walker;
-mod reporter;
-mod config;
-
-use anyhow::{Context, Result};
-use clap::Parser;
-use std::path::PathBuf;
-use std::fs;
+mod commands;
+mod custom_rules;
+mod scratch_dir;
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
+
+/// Exit codes, so wrapper scripts can branch on the kind of failure
+mod exit_code {
+    pub const SUCCESS: u8 = 0;
+    pub const VIOLATIONS: u8 = 1;
+    pub const USAGE: u8 = 2;
+    pub const PARSE_FAILURE: u8 = 3;
+    pub const MISSING_SPEC: u8 = 4;
+}
 
 #[derive(Parser)]
 #[command(name = "spec-check")]
 #[command(about = "Validate Rust code against specification markdown files", long_about = None)]
-struct Args {
-    /// Source directory
-    #[arg(short, long)]
-    src: Option<PathBuf>,
-    
-    /// Spec directory
-    #[arg(short = 'p', long)]
-    spec: Option<PathBuf>,
-
-    /// Check private items in addition to public items
-    #[arg(long)]
-    check_private: Option<bool>,
-
-    /// Output log file
-    #[arg(short, long)]
-    log: Option<PathBuf>,
-
-    /// Attributes to ignore (can be specified multiple times)
-    #[arg(short = 'i', long)]
-    ignore_attr: Vec<String>,
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    check: commands::check::CheckArgs,
+
+    #[command(flatten)]
+    output: commands::check::OutputArgs,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let config = config::Config::load_from_cargo_toml().unwrap_or_default();
-
-    // Determine final values (CLI overrides Cargo.toml metadata)
-    let src = args.src
-        .or_else(|| config.src_dir.as_ref().map(PathBuf::from))
-        .unwrap_or_else(|| PathBuf::from("src"));
-    
-    let spec = args.spec
-        .or_else(|| config.spec_dir.as_ref().map(PathBuf::from))
-        .unwrap_or_else(|| PathBuf::from("spec"));
-    
-    let log = args.log
-        .or_else(|| config.log_file.as_ref().map(PathBuf::from))
-        .unwrap_or_else(|| PathBuf::from("spec-check.log"));
-    
-    let check_private = args.check_private
-        .or(config.check_private)
-        .unwrap_or(false);
-
-    let mut ignored_attributes = config.get_ignored_attributes();
-    ignored_attributes.extend(args.ignore_attr);
-
-    // Validate directories exist
-    if !src.exists() {
-        anyhow::bail!("Source directory does not exist: {}", src.display());
-    }
-    if !spec.exists() {
-        anyhow::bail!("Spec directory does not exist: {}", spec.display());
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Explain a finding's rule code (lists all rules when no code is given)
+    Explain {
+        /// Rule code or name, e.g. SC003 or signature-mismatch
+        code: Option<String>,
+    },
 
-    // Initialize reporter
-    let mut reporter = reporter::Reporter::new(&log)
-        .context("Failed to create log file")?;
-
-    // Find all file mappings
-    let mappings = file_walker::find_file_mappings(&src, &spec)
-        .context("Failed to find file mappings")?;
-
-    let mut files_with_errors = 0;
-    let total_files = mappings.len();
-
-    // Process each file
-    for mapping in &mappings {
-        // Parse Rust file
-        let rust_content = fs::read_to_string(&mapping.rust_file)
-            .with_context(|| format!("Failed to read {}", mapping.rust_file.display()))?;
-        
-        let code_items = rust_parser::parse_rust_file(&rust_content, check_private)
-            .with_context(|| format!("Failed to parse {}", mapping.rust_file.display()))?;
-
-        // Check if spec file exists
-        let Some(spec_file) = &mapping.spec_file else {
-            reporter.report_missing_spec(&mapping.rust_file)?;
-            files_with_errors += 1;
-            continue;
-        };
-
-        // Parse spec file
-        let spec_content = fs::read_to_string(spec_file)
-            .with_context(|| format!("Failed to read {}", spec_file.display()))?;
-        
-        let rust_blocks = markdown_parser::extract_rust_blocks(&spec_content)
-            .with_context(|| format!("Failed to parse markdown {}", spec_file.display()))?;
-
-        // Parse all Rust blocks from spec
-        let mut spec_items = Vec::new();
-        for block in rust_blocks {
-            if let Ok(items) = rust_parser::parse_rust_file(&block, check_private) {
-                spec_items.extend(items);
-            }
-        }
+    /// Run against the bundled example project and walk through each finding type
+    Demo,
 
-        // Compare items
-        let result = comparator::compare_items(code_items, spec_items, &ignored_attributes);
-        
-        if result.has_errors() {
-            files_with_errors += 1;
-        }
+    /// Remove the cache directory and/or log file
+    Clean {
+        /// What to remove
+        #[arg(long, value_enum, default_value = "all")]
+        what: commands::clean::CleanTarget,
 
-        reporter.report_results(&mapping.rust_file, &result)?;
-    }
+        /// Log file to remove (defaults to the configured log file)
+        #[arg(short, long)]
+        log: Option<std::path::PathBuf>,
+    },
 
-    // Write summary
-    reporter.write_summary(total_files, files_with_errors)?;
+    /// Write a markdown changelog of API changes between two git revisions
+    Changelog(commands::changelog::ChangelogArgs),
 
-    // Exit with error code if there were any errors
-    if files_with_errors > 0 {
-        std::process::exit(1);
-    }
+    /// Draft release notes pairing spec prose with API changes between two git revisions
+    ReleaseNotes(commands::release_notes::ReleaseNotesArgs),
+
+    /// Record the crate's parsed items, for `spec-check diff` to compare a later version with
+    Snapshot(commands::snapshot::SnapshotArgs),
+
+    /// List the items added, removed and changed since a snapshot
+    Diff(commands::diff::DiffArgs),
+
+    /// Check the sources of a version published on crates.io against the current spec tree
+    VerifyPublished(commands::verify_published::VerifyPublishedArgs),
+
+    /// Diagnose common setup problems and suggest fixes
+    Doctor(commands::check::CheckArgs),
+
+    /// Write skeleton spec files with every public item's declaration, for source files without one
+    Init(commands::init::InitArgs),
+
+    /// Write a git pre-commit hook that runs `spec-check --staged`
+    InstallHook(commands::install_hook::InstallHookArgs),
+
+    /// Add stubs to the source files for the spec items they don't have yet, or write them as a patch
+    Scaffold(commands::scaffold::ScaffoldArgs),
+
+    /// Check one source file against one spec file, either read from stdin with `-`
+    File(commands::file::FileArgs),
+
+    /// Print a source file with a per-item spec status gutter
+    Annotate(commands::annotate::AnnotateArgs),
+
+    /// Open an item's spec definition in $EDITOR (or print its file:line)
+    Open(commands::open::OpenArgs),
+
+    /// Move spec files to follow moved or renamed source files
+    Migrate(commands::migrate::MigrateArgs),
 
-    Ok(())
+    /// Git merge driver that merges spec code blocks item by item: `spec-check merge-driver %O %A %B`
+    MergeDriver(commands::merge_driver::MergeDriverArgs),
+
+    /// Check with CI defaults: provider annotations and a SARIF report
+    Ci(commands::ci::CiArgs),
+
+    /// Browse findings interactively, grouped by file and rule
+    Tui(commands::check::CheckArgs),
+
+    /// Serve a live dashboard and JSON results on localhost, re-checking on file changes
+    Serve(commands::serve::ServeArgs),
+
+    /// Export the extracted items for other tools, e.g. in rustdoc's JSON layout
+    Export(commands::export::ExportArgs),
+
+    /// Write per-module spec status fragments for crate docs (`#[doc = include_str!(...)]`)
+    Fragments(commands::fragments::FragmentsArgs),
+
+    /// Write a zip of the config, tool version, specs, item model, findings and traceability matrix
+    AuditBundle(commands::audit_bundle::AuditBundleArgs),
+
+    /// Map the spec's requirement IDs to the items and files that describe and implement them
+    Requirements(commands::requirements::RequirementsArgs),
+
+    /// Run the spec's example blocks as tests against the crate, and check its items are where it puts them
+    Test(commands::test::TestArgs),
+
+    /// Check that `cargo package` would publish every spec file
+    Package(commands::package::PackageArgs),
+
+    /// Write every public item, grouped by module and kind, as markdown or JSON for API review
+    ApiReport(commands::api_report::ApiReportArgs),
+
+    /// Cross-check `cargo public-api` output (a listing or a diff) against the spec
+    PublicApi(commands::public_api::PublicApiArgs),
+
+    /// Check that localized spec trees have the same code blocks as the reference language
+    Translations(commands::translations::TranslationsArgs),
+
+    /// Check every crate of the workspace and summarize coverage across them
+    Workspace(commands::workspace::WorkspaceArgs),
+
+    /// Search parsed items in code and spec by name glob or partial signature
+    Grep(commands::grep::GrepArgs),
+
+    /// Keep parse results warm in memory and answer check requests over a local socket
+    Daemon(commands::daemon::DaemonArgs),
+
+    /// Serve diagnostics for open source and spec files over the Language Server Protocol on stdio
+    Lsp(commands::lsp::LspArgs),
+}
+
+fn main() -> ExitCode {
+    custom_rules::register();
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        None => commands::check::run(cli.check, cli.output),
+        Some(Command::Explain { code }) => commands::explain::run(code.as_deref()),
+        Some(Command::Demo) => commands::demo::run(),
+        Some(Command::Clean { what, log }) => commands::clean::run(what, log),
+        Some(Command::Changelog(args)) => commands::changelog::run(args),
+        Some(Command::ReleaseNotes(args)) => commands::release_notes::run(args),
+        Some(Command::Snapshot(args)) => commands::snapshot::run(args),
+        Some(Command::Diff(args)) => commands::diff::run(args),
+        Some(Command::VerifyPublished(args)) => commands::verify_published::run(args),
+        Some(Command::Doctor(args)) => commands::doctor::run(args),
+        Some(Command::Init(args)) => commands::init::run(args),
+        Some(Command::InstallHook(args)) => commands::install_hook::run(args),
+        Some(Command::Scaffold(args)) => commands::scaffold::run(args),
+        Some(Command::File(args)) => commands::file::run(args),
+        Some(Command::Annotate(args)) => commands::annotate::run(args),
+        Some(Command::Open(args)) => commands::open::run(args),
+        Some(Command::Migrate(args)) => commands::migrate::run(args),
+        Some(Command::MergeDriver(args)) => commands::merge_driver::run(args),
+        Some(Command::Ci(args)) => commands::ci::run(args),
+        Some(Command::Tui(args)) => commands::tui::run(args),
+        Some(Command::Serve(args)) => commands::serve::run(args),
+        Some(Command::Grep(args)) => commands::grep::run(args),
+        Some(Command::Export(args)) => commands::export::run(args),
+        Some(Command::Fragments(args)) => commands::fragments::run(args),
+        Some(Command::AuditBundle(args)) => commands::audit_bundle::run(args),
+        Some(Command::Requirements(args)) => commands::requirements::run(args),
+        Some(Command::Test(args)) => commands::test::run(args),
+        Some(Command::Package(args)) => commands::package::run(args),
+        Some(Command::ApiReport(args)) => commands::api_report::run(args),
+        Some(Command::PublicApi(args)) => commands::public_api::run(args),
+        Some(Command::Translations(args)) => commands::translations::run(args),
+        Some(Command::Workspace(args)) => commands::workspace::run(args),
+        Some(Command::Daemon(args)) => commands::daemon::run(args),
+        Some(Command::Lsp(args)) => commands::lsp::run(args),
+    };
+
+    // Anything that escapes a command is a configuration or usage problem
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitCode::from(exit_code::USAGE)
+        }
+    }
 }
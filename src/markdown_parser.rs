@@ -1,36 +1,425 @@
+use crate::rust_parser::{Stage, Versions};
 use anyhow::Result;
-use pulldown_cmark::{Parser, Event, Tag, CodeBlockKind};
+use pulldown_cmark::{Parser, Event, Tag, CodeBlockKind, OffsetIter};
+use std::borrow::Cow;
+use std::ops::Range;
 
-pub fn extract_rust_blocks(markdown: &str) -> Result<Vec<String>> {
-    let parser = Parser::new(markdown);
-    let mut rust_blocks = Vec::new();
-    let mut in_rust_block = false;
+/// A ```rust block's contents, borrowed from the markdown unless the block is indented
+/// (e.g. inside a list), where the indentation has to be stripped into a copy
+#[derive(Debug, Clone)]
+pub struct RustBlock<'a> {
+    pub code: Cow<'a, str>,
+    /// Byte range of the contents in the markdown, excluding the fences
+    pub range: Range<usize>,
+    /// 1-based line in the markdown of the block's first line of code
+    pub line: usize,
+    /// From a ```rust,planned style fence, or a `<!-- spec-check: planned -->` directive before the block
+    /// (or before a `<!-- spec-check: include ... -->`)
+    pub stage: Stage,
+    /// From `feature=vr` tags on the fence: features every item of the block requires
+    pub features: Vec<String>,
+    /// The paragraph right before the block, if only directives come between them
+    pub prose: Option<&'a str>,
+    /// The fence's tags after `rust`, e.g. `["example", "should_panic"]`
+    pub tags: Vec<String>,
+    /// For a block the spec includes from another file, its path as the spec writes it, relative
+    /// to the spec file; the code is empty until it's read
+    pub include: Option<&'a str>,
+    /// A fence tag spec-check doesn't know, such as a misspelled stage: the block's items are
+    /// left unchecked, and the block is reported instead of being taken as an example
+    pub unknown_tag: Option<String>,
+}
+
+pub fn extract_rust_blocks(markdown: &str) -> Vec<RustBlock<'_>> {
+    rust_blocks(markdown).collect()
+}
+
+/// A block of settings at the very top of a spec, between `+++` lines (TOML) or `---` lines (YAML)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    pub text: &'a str,
+    pub toml: bool,
+    /// Byte offset of the markdown after the closing fence
+    pub end: usize,
+}
+
+pub fn front_matter(markdown: &str) -> Option<FrontMatter<'_>> {
+    let fence = ["+++", "---"].into_iter().find(|fence| markdown.lines().next().is_some_and(|line| line.trim_end() == *fence))?;
+    let start = markdown.find('\n')? + 1;
+    let mut offset = start;
+    for line in markdown[start..].split_inclusive('\n') {
+        if line.trim_end() == fence {
+            return Some(FrontMatter { text: &markdown[start..offset], toml: fence == "+++", end: offset + line.len() });
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Where the markdown proper starts, after any front matter
+pub(crate) fn body_start(markdown: &str) -> usize {
+    front_matter(markdown).map_or(0, |front| front.end)
+}
+
+/// A line of a document after any front matter, without its line ending
+pub(crate) struct Line<'a> {
+    pub text: &'a str,
+    /// Byte offsets of the line in the document, and of the next
+    pub start: usize,
+    pub end: usize,
+    /// 1-based
+    pub number: usize,
+}
+
+/// The lines of a spec in a format other than markdown, which takes front matter the same way
+pub(crate) fn body_lines(spec: &str) -> impl Iterator<Item = Line<'_>> {
+    let body = body_start(spec);
+    let first = spec[..body].matches('\n').count() + 1;
+    spec[body..].split_inclusive('\n').scan(body, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, *offset, line.trim_end_matches(['\n', '\r'])))
+    })
+    .enumerate()
+    .map(move |(index, (start, end, text))| Line { text, start, end, number: first + index })
+}
+
+/// The Rust blocks of a markdown file, produced as the parser reaches them
+pub fn rust_blocks(markdown: &str) -> RustBlocks<'_> {
+    let body = body_start(markdown);
+    RustBlocks { markdown, parser: Parser::new(&markdown[body..]).into_offset_iter(), body, counted: 0, line: 1, directive: None, skip_next: false, off: false, prose: None, examples: false }
+}
+
+/// The ```rust,example blocks of a markdown file instead of the normative ones
+pub fn example_blocks(markdown: &str) -> RustBlocks<'_> {
+    RustBlocks { examples: true, ..rust_blocks(markdown) }
+}
+
+pub struct RustBlocks<'a> {
+    markdown: &'a str,
+    /// Over the markdown after any front matter, at offset `body`
+    parser: OffsetIter<'a, 'a>,
+    body: usize,
+    /// Lines are counted incrementally up to `counted`, since blocks come in order
+    counted: usize,
+    line: usize,
+    /// Stage from a directive, for the next Rust block
+    directive: Option<Stage>,
+    /// After `<!-- spec-check: ignore-next-block -->`
+    skip_next: bool,
+    /// Between `<!-- spec-check: off -->` and `<!-- spec-check: on -->`
+    off: bool,
+    /// Byte range of the last paragraph, if only directives came after it
+    prose: Option<Range<usize>>,
+    /// Whether to produce the example blocks
+    examples: bool,
+}
+
+impl<'a> RustBlocks<'a> {
+    fn line_of(&mut self, offset: usize) -> usize {
+        self.line += count_newlines(&self.markdown[self.counted..offset]);
+        self.counted = offset;
+        self.line
+    }
+
+    /// Collect the contents of a block whose opening fence spans `fence`
+    fn read_block(&mut self, fence: Range<usize>, stage: Stage) -> RustBlock<'a> {
+        let markdown = self.markdown;
+        // Contents start on the line after the opening fence
+        let start = markdown[fence.start..].find('\n').map_or(fence.end, |i| fence.start + i + 1);
+        let line = self.line_of(fence.start) + 1;
+        let mut end = start;
+        let mut owned: Option<String> = None;
+
+        for (event, range) in self.parser.by_ref() {
+            let range = range.start + self.body..range.end + self.body;
+            match event {
+                Event::Text(text) => {
+                    // Text that is exactly the next stretch of the source can stay borrowed
+                    if owned.is_none() && !(range.start == end && *text == markdown[range.clone()]) {
+                        owned = Some(markdown[start..end].to_string());
+                    }
+                    if let Some(owned) = owned.as_mut() {
+                        owned.push_str(&text);
+                    }
+                    end = range.end;
+                }
+                Event::End(Tag::CodeBlock(_)) => break,
+                _ => {}
+            }
+        }
+
+        let code = owned.map_or(Cow::Borrowed(&markdown[start..end]), Cow::Owned);
+        RustBlock { code, range: start..end, line, stage, features: Vec::new(), prose: None, tags: Vec::new(), include: None, unknown_tag: None }
+    }
+}
+
+fn count_newlines(text: &str) -> usize {
+    text.bytes().filter(|&b| b == b'\n').count()
+}
+
+impl<'a> Iterator for RustBlocks<'a> {
+    type Item = RustBlock<'a>;
+
+    fn next(&mut self) -> Option<RustBlock<'a>> {
+        loop {
+            let (event, range) = self.parser.next()?;
+            let range = range.start + self.body..range.end + self.body;
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                    let markdown = self.markdown;
+                    let prose = self.prose.take().map(|range| markdown[range].trim_end());
+                    let mut tags = info.split(',').map(str::trim);
+                    let stage = if self.examples {
+                        if tags.next() != Some("rust") || !tags.any(|tag| tag == "example") { continue }
+                        Stage::default()
+                    } else {
+                        match stage_tags(tags, "rust") {
+                            Some(stage) => stage.or(self.directive.take()).unwrap_or_default(),
+                            None if unknown_tag(&info).is_some() => Stage::default(),
+                            None => continue,
+                        }
+                    };
+                    let mut block = self.read_block(range, stage);
+                    block.unknown_tag = unknown_tag(&info).map(str::to_string);
+                    block.prose = prose;
+                    block.tags = info.split(',').skip(1).map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect();
+                    block.features = info.split(',').filter_map(|tag| tag.trim().strip_prefix("feature=")).map(|feature| feature.trim_matches('"').to_string()).collect();
+                    // Examples and pseudo-code the spec marks as not describing items
+                    if self.off || std::mem::take(&mut self.skip_next) {
+                        continue;
+                    }
+                    return Some(block);
+                }
+                Event::Start(Tag::Paragraph) => self.prose = Some(range),
+                // A list's or quote's last paragraph isn't about what comes after the list
+                Event::Start(Tag::Heading(..) | Tag::BlockQuote | Tag::CodeBlock(_) | Tag::List(_) | Tag::Table(_))
+                | Event::End(Tag::BlockQuote | Tag::List(_) | Tag::Table(_))
+                | Event::Rule => self.prose = None,
+                Event::Html(_) => match directive(&self.markdown[range.clone()]) {
+                    Some("ignore-next-block") => self.skip_next = true,
+                    Some(words) if words.starts_with("include ") => {
+                        if self.examples || self.off || std::mem::take(&mut self.skip_next) {
+                            continue;
+                        }
+                        let line = self.line_of(range.start);
+                        let stage = self.directive.take().unwrap_or_default();
+                        let include = words["include ".len()..].trim();
+                        let prose = self.prose.take().map(|range| self.markdown[range].trim_end());
+                        return Some(RustBlock { code: Cow::Borrowed(""), range: range.end..range.end, line, stage, features: Vec::new(), prose, tags: Vec::new(), include: Some(include), unknown_tag: None });
+                    }
+                    Some("off") => self.off = true,
+                    Some("on") => self.off = false,
+                    Some(words) => {
+                        if let Some(stage) = stage_tags(words.split_whitespace(), "").flatten() {
+                            self.directive = Some(stage);
+                        }
+                    }
+                    None => {}
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Markdown as the text it renders, with runs of whitespace as single spaces, so prose and doc
+/// comments compare by what they say
+pub fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(part) | Event::Code(part) => text.push_str(&part),
+            Event::End(Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..)) => {}
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The stage of a block with `tags` in another format than markdown, as for a fence with them:
+/// `None` if it isn't a normative block, or with `examples` an example block. A directive's
+/// stage is taken for a normative block without one of its own.
+pub(crate) fn block_stage(tags: &[&str], examples: bool, directive: &mut Option<Stage>) -> Option<Stage> {
+    if examples {
+        return tags.contains(&"example").then(Stage::default);
+    }
+    let stage = stage_tags(tags.iter().copied(), "")?;
+    Some(stage.or(directive.take()).unwrap_or_default())
+}
+
+/// The body of a `<!-- spec-check: ... -->` comment
+fn directive(html: &str) -> Option<&str> {
+    let body = html.trim().strip_prefix("<!--")?.strip_suffix("-->")?.trim();
+    Some(body.strip_prefix("spec-check:")?.trim())
+}
+
+/// Fence tags that say a block isn't normative: `example`, and rustdoc's own
+const OPT_OUT_TAGS: [&str; 5] = ["example", "ignore", "no_run", "should_panic", "compile_fail"];
+
+/// The first tag of a ```rust fence's `info` that is neither a stage, a setting nor an opt-out,
+/// so that `rust,planed` isn't silently taken for a block that isn't normative
+pub(crate) fn unknown_tag(info: &str) -> Option<&str> {
+    let mut tags = info.split(',').map(str::trim);
+    if tags.next() != Some("rust") {
+        return None;
+    }
+    tags.filter(|tag| !tag.is_empty()).find(|tag| {
+        !["planned", "stable", "deprecated", "forbidden", "spec"].contains(tag) && !OPT_OUT_TAGS.contains(tag)
+            && !["feature=", "since=", "until=", "removal=", "edition"].iter().any(|prefix| tag.starts_with(prefix))
+    })
+}
+
+/// The stage named by tags after `first` (`rust,deprecated,removal=0.5.0`): `None` if the tags
+/// include anything else, such as `example`, `Some(None)` if they name no stage. `spec` only
+/// says the block is normative, as untagged blocks are.
+pub(crate) fn stage_tags<'t>(mut tags: impl Iterator<Item = &'t str>, first: &str) -> Option<Option<Stage>> {
+    if !first.is_empty() && tags.next()? != first {
+        return None;
+    }
+    let mut stage = None;
+    let mut removal = None;
+    for tag in tags.filter(|tag| !tag.is_empty()) {
+        match tag {
+            "planned" => stage = Some(Stage::Planned),
+            "stable" => stage = Some(Stage::Stable),
+            "deprecated" => stage = Some(Stage::Deprecated { removal: None }),
+            "forbidden" => stage = Some(Stage::Forbidden),
+            "spec" => {}
+            "example" => return None,
+            _ if ["feature=", "since=", "until="].iter().any(|prefix| tag.starts_with(prefix)) => {}
+            _ => removal = Some(tag.strip_prefix("removal=")?.trim_matches('"').to_string()),
+        }
+    }
+    match stage {
+        Some(Stage::Deprecated { .. }) => Some(Some(Stage::Deprecated { removal })),
+        _ if removal.is_some() => None,
+        stage => Some(stage),
+    }
+}
+
+/// A heading and the lines it covers, up to the next heading of the same or a higher level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub heading: String,
+    pub level: usize,
+    /// 1-based lines, from the heading's own line
+    pub lines: Range<usize>,
+}
+
+/// The versions the block at `line` is about: those of its `since=` and `until=` tags, or else
+/// of the innermost heading around it that starts with a version, `## v2 API` being from 2 until 3
+pub fn block_versions(tags: &[String], sections: &[Section], line: usize) -> Versions {
+    let tag = |name: &str| tags.iter().find_map(|tag| tag.strip_prefix(name)).map(|version| version.trim_matches('"').to_string());
+    let tagged = Versions { since: tag("since="), until: tag("until=") };
+    if tagged != Versions::default() {
+        return tagged;
+    }
+    sections.iter()
+        .filter(|section| section.lines.contains(&line))
+        .filter_map(|section| Some((section.level, heading_versions(&section.heading)?)))
+        .max_by_key(|(level, _)| *level)
+        .map(|(_, versions)| versions)
+        .unwrap_or_default()
+}
+
+/// The versions of a heading starting with one, such as `v2 API` or `v1.4`: from that version
+/// up to the next at its precision
+fn heading_versions(heading: &str) -> Option<Versions> {
+    let version = heading.split_whitespace().next()?.strip_prefix('v')?;
+    let mut parts: Vec<u64> = version.split('.').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    *parts.last_mut()? += 1;
+    let until = parts.iter().map(u64::to_string).collect::<Vec<_>>().join(".");
+    Some(Versions { since: Some(version.to_string()), until: Some(until) })
+}
+
+pub fn sections(markdown: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut open: Option<Section> = None;
+    let body = body_start(markdown);
+    for (event, range) in Parser::new(&markdown[body..]).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(level, ..)) => {
+                let line = count_newlines(&markdown[..body + range.start]) + 1;
+                let level = level as usize;
+                // Close the sections this heading ends (all deeper or equal ones still open)
+                for section in sections.iter_mut().filter(|s| s.lines.end == usize::MAX && s.level >= level) {
+                    section.lines.end = line;
+                }
+                open = Some(Section { heading: String::new(), level, lines: line..usize::MAX });
+            }
+            Event::End(Tag::Heading(..)) => sections.extend(open.take()),
+            Event::Text(text) | Event::Code(text) if open.is_some() => {
+                open.as_mut().expect("heading is open").heading.push_str(&text);
+            }
+            _ => {}
+        }
+    }
+    sections
+}
+
+/// A Rust code block together with the prose that introduces it
+#[derive(Debug, Clone)]
+pub struct DocumentedBlock {
+    /// Text of the nearest heading above the block
+    pub heading: Option<String>,
+    /// Paragraphs between the previous heading or code block and this block
+    pub prose: String,
+    pub code: String,
+}
+
+pub fn extract_documented_blocks(markdown: &str) -> Result<Vec<DocumentedBlock>> {
+    let parser = Parser::new(&markdown[body_start(markdown)..]);
+    let mut blocks = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut prose = String::new();
     let mut current_block = String::new();
+    let mut in_rust_block = false;
+    let mut in_heading = false;
+    let mut in_paragraph = false;
 
     for event in parser {
         match event {
-            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
-                if lang.as_ref() == "rust" {
-                    in_rust_block = true;
-                    current_block.clear();
-                }
+            Event::Start(Tag::Heading(..)) => {
+                in_heading = true;
+                heading = Some(String::new());
+                prose.clear();
             }
-            Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
-                if lang.as_ref() == "rust" && in_rust_block {
-                    rust_blocks.push(current_block.clone());
-                    in_rust_block = false;
+            Event::End(Tag::Heading(..)) => in_heading = false,
+            Event::Start(Tag::Paragraph) => {
+                in_paragraph = true;
+                if !prose.is_empty() {
+                    prose.push_str("\n\n");
                 }
             }
-            Event::Text(text) => {
-                if in_rust_block {
-                    current_block.push_str(&text);
-                }
+            Event::End(Tag::Paragraph) => in_paragraph = false,
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) if lang.as_ref() == "rust" => {
+                in_rust_block = true;
+                current_block.clear();
+            }
+            Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) if lang.as_ref() == "rust" && in_rust_block => {
+                blocks.push(DocumentedBlock {
+                    heading: heading.clone(),
+                    prose: prose.clone(),
+                    code: current_block.clone(),
+                });
+                prose.clear();
+                in_rust_block = false;
+            }
+            Event::Text(text) if in_rust_block => current_block.push_str(&text),
+            Event::Text(text) if in_heading => heading.get_or_insert_with(String::new).push_str(&text),
+            Event::Text(text) if in_paragraph => prose.push_str(&text),
+            Event::Code(code) if in_heading => {
+                heading.get_or_insert_with(String::new).push_str(&format!("`{}`", code));
             }
+            Event::Code(code) if in_paragraph => prose.push_str(&format!("`{}`", code)),
+            Event::SoftBreak | Event::HardBreak if in_paragraph => prose.push(' '),
             _ => {}
         }
     }
 
-    Ok(rust_blocks)
+    Ok(blocks)
 }
 
 #[cfg(test)]
@@ -51,9 +440,12 @@ pub struct MyStruct {}
 More text
         "#;
 
-        let blocks = extract_rust_blocks(markdown).unwrap();
+        let blocks = extract_rust_blocks(markdown);
         assert_eq!(blocks.len(), 1);
-        assert!(blocks[0].contains("pub struct MyStruct"));
+        assert!(blocks[0].code.contains("pub struct MyStruct"));
+        assert!(matches!(blocks[0].code, Cow::Borrowed(_)));
+        assert_eq!(blocks[0].line, 7);
+        assert_eq!(&markdown[blocks[0].range.clone()], blocks[0].code);
     }
 
     #[test]
@@ -68,7 +460,7 @@ pub struct Second {}
 ```
         "#;
 
-        let blocks = extract_rust_blocks(markdown).unwrap();
+        let blocks = extract_rust_blocks(markdown);
         assert_eq!(blocks.len(), 2);
     }
 
@@ -85,8 +477,110 @@ pub struct MyStruct {}
 ```
         "#;
 
-        let blocks = extract_rust_blocks(markdown).unwrap();
+        let blocks = extract_rust_blocks(markdown);
         assert_eq!(blocks.len(), 1);
-        assert!(blocks[0].contains("MyStruct"));
+        assert!(blocks[0].code.contains("MyStruct"));
+    }
+
+    #[test]
+    fn test_indented_block_is_copied_without_indentation() {
+        let markdown = "- item\n\n  ```rust\n  pub struct Listed;\n  ```\n";
+
+        let blocks = extract_rust_blocks(markdown);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].code, "pub struct Listed;\n");
+        assert!(matches!(blocks[0].code, Cow::Owned(_)));
+        assert_eq!(blocks[0].line, 4);
+    }
+
+    #[test]
+    fn test_documented_blocks_capture_prose() {
+        let markdown = r#"
+### Error Handling
+
+Engine-wide errors use the `MathAnimationError` enum,
+providing a unified result type.
+
+```rust
+pub enum MathAnimationError {}
+```
+
+```rust
+pub struct Undocumented;
+```
+        "#;
+
+        let blocks = extract_documented_blocks(markdown).unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].heading.as_deref(), Some("Error Handling"));
+        assert_eq!(blocks[0].prose, "Engine-wide errors use the `MathAnimationError` enum, providing a unified result type.");
+        assert!(blocks[0].code.contains("MathAnimationError"));
+        assert!(blocks[1].prose.is_empty());
+    }
+
+    #[test]
+    fn test_sections_end_at_next_heading_of_same_level() {
+        let markdown = "# Math\n\n## Geometry\n\ntext\n\n### `Curve`\n\n## Color\n";
+        let sections = sections(markdown);
+        let lines: Vec<_> = sections.iter().map(|s| (s.heading.as_str(), s.lines.clone())).collect();
+        assert_eq!(lines, vec![("Math", 1..usize::MAX), ("Geometry", 3..9), ("Curve", 7..9), ("Color", 9..usize::MAX)]);
+    }
+
+    #[test]
+    fn test_block_stages() {
+        let markdown = "```rust,planned\npub fn render();\n```\n\n<!-- spec-check: deprecated removal=0.5.0 -->\n\n```rust\npub fn draw();\n```\n\n```rust\npub fn clear();\n```\n\n```rust,ignore\nnot checked\n```\n\n```rust,example\nlet curve = Curve::new();\n```\n\n```rust,spec,planned\npub fn fill();\n```\n";
+        let stages: Vec<Stage> = rust_blocks(markdown).map(|block| block.stage).collect();
+        let examples: Vec<Vec<String>> = example_blocks(markdown).map(|block| block.tags).collect();
+        assert_eq!(examples, [["example"]]);
+        assert_eq!(stages, vec![
+            Stage::Planned,
+            Stage::Deprecated { removal: Some("0.5.0".to_string()) },
+            Stage::Stable,
+            Stage::Planned,
+        ]);
+    }
+
+    #[test]
+    fn test_misspelled_stages_are_kept_as_unknown_tags() {
+        let markdown = "```rust,planed\npub fn render();\n```\n\n```rust,no_run\nrender();\n```\n\n```rust,stable,sepc\npub fn draw();\n```\n\n```rust,ignore\nnot checked\n```\n";
+        let blocks: Vec<(Option<String>, String)> = rust_blocks(markdown).map(|block| (block.unknown_tag, block.code.trim().to_string())).collect();
+        assert_eq!(blocks, [
+            (Some("planed".to_string()), "pub fn render();".to_string()),
+            (Some("sepc".to_string()), "pub fn draw();".to_string()),
+        ]);
+        assert_eq!(unknown_tag("rust,feature=serde,since=0.2.0,edition2021"), None);
+    }
+
+    #[test]
+    fn test_suppressed_blocks() {
+        let markdown = concat!(
+            "<!-- spec-check: ignore-next-block -->\n```rust\nfn example() {}\n```\n\n```rust\npub fn kept();\n```\n\n",
+            "<!-- spec-check: off -->\n\n```rust\nlet pseudo = code;\n```\n\n```rust\nfn sketch() {}\n```\n\n",
+            "<!-- spec-check: on -->\n\n```rust\npub fn also_kept();\n```\n",
+        );
+        let blocks: Vec<String> = rust_blocks(markdown).map(|block| block.code.trim().to_string()).collect();
+        assert_eq!(blocks, ["pub fn kept();", "pub fn also_kept();"]);
+    }
+
+    #[test]
+    fn test_block_prose_is_the_paragraph_before() {
+        let markdown = "Easing curves (REQ-ANIM-012, `REQ-ANIM-013`).\n\n<!-- spec-check: planned -->\n\n```rust\npub fn ease();\n```\n\nREQ-ANIM-014\n\n## Blending\n\n```rust\npub fn blend();\n```\n";
+        let prose: Vec<Option<&str>> = rust_blocks(markdown).map(|block| block.prose).collect();
+        assert_eq!(prose, [Some("Easing curves (REQ-ANIM-012, `REQ-ANIM-013`)."), None]);
+        assert_eq!(plain_text("Eases `t`,\n*smoothly*."), "Eases t, smoothly.");
+    }
+
+    #[test]
+    fn test_front_matter_is_not_markdown() {
+        let markdown = "---\ncheck-private: true\n---\n\n## Geometry\n\n```rust\npub struct Curve;\n```\n";
+        let front = front_matter(markdown).unwrap();
+        assert_eq!((front.text, front.toml), ("check-private: true\n", false));
+        // Not a setext heading
+        assert_eq!(sections(markdown).iter().map(|s| (s.heading.as_str(), s.lines.start)).collect::<Vec<_>>(), [("Geometry", 5)]);
+        let block = rust_blocks(markdown).next().unwrap();
+        assert_eq!((block.line, &markdown[block.range.clone()]), (8, "pub struct Curve;\n"));
+
+        assert!(front_matter("---\nno closing fence\n").is_none());
+        assert_eq!(front_matter("+++\nrust = \"geometry/*.rs\"\n+++\n").unwrap().text, "rust = \"geometry/*.rs\"\n");
     }
 }
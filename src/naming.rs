@@ -0,0 +1,132 @@
+//! Naming conventions for code items, from `[[naming]]` rules: a case for the names of a kind
+//! of item, and a glob they have to match, optionally only for items with a given attribute.
+
+use crate::glob;
+use crate::rust_parser::{ItemKind, RustItem};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The cases of rustc's naming lints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Case {
+    UpperCamelCase,
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+    #[serde(rename = "SCREAMING_SNAKE_CASE")]
+    ScreamingSnakeCase,
+}
+
+impl Case {
+    fn allows(self, name: &str) -> bool {
+        // Leading underscores mark an unused item rather than a word
+        let name = name.strip_prefix("r#").unwrap_or(name).trim_start_matches('_');
+        match self {
+            Case::UpperCamelCase => name.starts_with(|c: char| c.is_uppercase()) && !name.contains('_'),
+            Case::SnakeCase => !name.chars().any(char::is_uppercase) && !name.contains("__"),
+            Case::ScreamingSnakeCase => !name.chars().any(char::is_lowercase) && !name.contains("__"),
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Case::UpperCamelCase => "UpperCamelCase",
+            Case::SnakeCase => "snake_case",
+            Case::ScreamingSnakeCase => "SCREAMING_SNAKE_CASE",
+        })
+    }
+}
+
+/// One `[[naming]]` rule
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamingRule {
+    /// The kind of item it's for, as summaries name kinds (`struct`, `trait method`, ...);
+    /// every kind with a name of its own without it
+    pub kind: Option<String>,
+    /// Only items with an attribute this glob matches, e.g. `#[derive(*Error*)]`
+    pub attribute: Option<String>,
+    pub case: Option<Case>,
+    /// A glob the name has to match, e.g. `*Error`
+    pub pattern: Option<String>,
+}
+
+impl NamingRule {
+    fn applies_to(&self, item: &RustItem) -> bool {
+        let kind = match &self.kind {
+            Some(kind) => item.kind.label() == kind,
+            // Re-exports and trait impls are named by what they refer to
+            None => !matches!(item.kind, ItemKind::Use | ItemKind::TraitImpl),
+        };
+        kind && self.attribute.as_ref().is_none_or(|pattern| item.attributes.iter().any(|attr| glob::matches(pattern, &attr.replace(' ', ""))))
+    }
+
+    /// What the name of `item` fails of the rule, e.g. `UpperCamelCase`
+    fn broken_by(&self, item: &RustItem) -> Option<String> {
+        let case = self.case.filter(|case| !case.allows(&item.name)).map(|case| case.to_string());
+        let pattern = self.pattern.as_ref().filter(|pattern| !glob::matches(pattern, &item.name)).map(|pattern| format!("`{}`", pattern));
+        match (case, pattern) {
+            (Some(case), Some(pattern)) => Some(format!("{} and {}", case, pattern)),
+            (case, pattern) => case.or(pattern),
+        }
+    }
+}
+
+/// A code item whose name breaks a `[[naming]]` rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamingViolation {
+    pub code_item: RustItem,
+    /// What the name should have been, e.g. `UpperCamelCase` or `` `*Error` ``
+    pub expected: String,
+}
+
+/// The code items whose names break one of `rules`, once each, by the first rule they break
+pub fn violations(code_items: &[RustItem], rules: &[NamingRule]) -> Vec<NamingViolation> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    code_items.iter()
+        .filter_map(|item| {
+            let expected = rules.iter().filter(|rule| rule.applies_to(item)).find_map(|rule| rule.broken_by(item))?;
+            Some(NamingViolation { code_item: item.clone(), expected })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_held_to_their_kind_rules() {
+        let items = crate::parse_rust_items(concat!(
+            "pub struct http_client;\npub struct Client;\n",
+            "#[derive(Debug, thiserror::Error)] pub enum ParseFailure { Eof }\n",
+            "#[derive(Debug, thiserror::Error)] pub enum ParseError { Eof }\n",
+            "pub enum Mode { A }\n",
+            "pub fn Draw() {}\npub fn _unused() {}\npub const max_len: usize = 4;\npub use Client as Alias;\n",
+        ), Default::default()).unwrap();
+        let rule = |kind: Option<&str>, attribute: Option<&str>, case: Option<Case>, pattern: Option<&str>| NamingRule {
+            kind: kind.map(str::to_string),
+            attribute: attribute.map(str::to_string),
+            case,
+            pattern: pattern.map(str::to_string),
+        };
+        let rules = [
+            rule(Some("struct"), None, Some(Case::UpperCamelCase), None),
+            rule(Some("enum"), Some("#[derive(*Error)]"), None, Some("*Error")),
+            rule(Some("function"), None, Some(Case::SnakeCase), None),
+            rule(Some("const"), None, Some(Case::ScreamingSnakeCase), None),
+        ];
+        let found = |rules: &[NamingRule], items: &[RustItem]| -> Vec<(String, String)> {
+            violations(items, rules).into_iter().map(|v| (v.code_item.name, v.expected)).collect()
+        };
+        let owned = |pairs: &[(&str, &str)]| -> Vec<(String, String)> { pairs.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect() };
+        assert_eq!(found(&rules, &items), owned(&[("http_client", "UpperCamelCase"), ("ParseFailure", "`*Error`"), ("Draw", "snake_case"), ("max_len", "SCREAMING_SNAKE_CASE")]));
+
+        let every_kind = [rule(None, None, Some(Case::UpperCamelCase), Some("C*"))];
+        assert_eq!(found(&every_kind, &items[..2]), owned(&[("http_client", "UpperCamelCase and `C*`")]));
+        assert!(violations(&items, &[]).is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+//! Order-preserving parallel map on scoped threads
+
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+/// How many items per thread workers may run ahead of the consumer
+const WINDOW_PER_JOB: usize = 4;
+
+/// Number of worker threads to use when `--jobs` is not given
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Apply `f` to every item on up to `jobs` threads, returning results in input order
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Workers pull the next index from a shared counter, so slow items don't stall a whole chunk
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| scope.spawn(|| {
+                let mut done = Vec::new();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else { break };
+                    done.push((index, f(item)));
+                }
+                done
+            }))
+            .collect();
+        workers.into_iter().flat_map(|worker| worker.join().expect("worker thread panicked")).collect()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Apply `f` to every item on up to `jobs` threads, handing each result to `consume` in input
+/// order as soon as it and everything before it are done. Workers stay a bounded number of
+/// items ahead, so memory doesn't grow with `items`; `ControlFlow::Break` or an error from
+/// `consume` stops them early.
+pub fn for_each_ordered<T, R, E, F, C>(items: &[T], jobs: usize, f: F, mut consume: C) -> Result<(), E>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    C: FnMut(&T, R) -> Result<ControlFlow<()>, E>,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        for item in items {
+            if consume(item, f(item))?.is_break() {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    let window = jobs * WINDOW_PER_JOB;
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let consumed = (Mutex::new(0), Condvar::new());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next, stop, consumed, f) = (&next, &stop, &consumed, &f);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else { break };
+                {
+                    let (done, advanced) = consumed;
+                    let mut done = done.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    while index >= *done + window && !stop.load(Ordering::Relaxed) {
+                        done = advanced.wait(done).unwrap_or_else(|poisoned| poisoned.into_inner());
+                    }
+                }
+                if stop.load(Ordering::Relaxed) || sender.send((index, f(item))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        let mut deliver = || {
+            for (index, result) in receiver.iter() {
+                pending.insert(index, result);
+                while let Some(result) = pending.remove(&expected) {
+                    let flow = consume(&items[expected], result)?;
+                    expected += 1;
+                    let (done, advanced) = &consumed;
+                    *done.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = expected;
+                    advanced.notify_all();
+                    if flow.is_break() {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        };
+        let outcome = deliver();
+
+        // Wake any worker waiting for the window to move, and let it see we're done
+        let (done, advanced) = &consumed;
+        let _guard = done.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stop.store(true, Ordering::Relaxed);
+        advanced.notify_all();
+        outcome
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_preserves_order() {
+        let items: Vec<u64> = (0..100).collect();
+        let squares = map(&items, 4, |n| n * n);
+        assert_eq!(squares, items.iter().map(|n| n * n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_for_each_ordered_streams_in_order_and_stops_on_break() {
+        let items: Vec<u64> = (0..1000).collect();
+        let mut seen = Vec::new();
+        for_each_ordered(&items, 8, |n| n * 2, |_, doubled| {
+            seen.push(doubled);
+            Ok::<_, ()>(if doubled == 200 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) })
+        }).unwrap();
+        assert_eq!(seen, (0..=100).map(|n| n * 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,338 @@
+//! Placeholders in spec blocks: `/* ... */` stands for any number of fields, variants or
+//! parameters, `(..)` for any one type, and `// + private fields` or a final `..` in a struct
+//! for any other fields, anywhere. Spec blocks are rewritten so the placeholders survive
+//! tokenizing as marker names, which then match whatever the code has there.
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::borrow::Cow;
+
+/// Marker for `/* ... */`, as a variant or a tuple field
+const REST: &str = "__SpecCheckRest";
+/// Marker for `/* ... */` as a named field or parameter, typed `REST`
+const REST_FIELD: &str = "__spec_check_rest";
+/// `/* ... */` as a named field or parameter
+const REST_DECLARATION: &str = "__spec_check_rest: __SpecCheckRest";
+/// Marker for `(..)`, as a type
+const ANY: &str = "__SpecCheckAny";
+/// Marker for `// + private fields` and a struct's final `..`, typed `FIELDS`
+const FIELDS_FIELD: &str = "__spec_check_fields";
+const FIELDS: &str = "__SpecCheckFields";
+/// `// + private fields` as a named field
+const FIELDS_DECLARATION: &str = "__spec_check_fields: __SpecCheckFields";
+
+/// `code` with its placeholders replaced by markers that parse. A `/* ... */` outside a
+/// field, variant or parameter list stays a comment, as does `// + private fields` outside the
+/// fields of a struct.
+pub fn expand(code: &str) -> Cow<'_, str> {
+    if !code.contains("/*") && !code.contains("..") && !code.contains("// +") {
+        return Cow::Borrowed(code);
+    }
+    let mut out = String::with_capacity(code.len());
+    // Byte offsets of the unclosed `{` and `(` before the current position
+    let mut open: Vec<usize> = Vec::new();
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        let offset = code.len() - rest.len();
+        let in_fields = || open.last().is_some_and(|&at| code[at..].starts_with('{') && rest_marker(code, at) == Some(REST_DECLARATION));
+        if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let inner = rest[2..end].trim();
+            if inner.starts_with('+') && inner.ends_with("fields") && in_fields() {
+                push_fields_marker(&mut out, &rest[end..]);
+            } else {
+                out.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+        } else if rest.starts_with("..") && !rest.starts_with("...") && rest[2..].trim_start().starts_with('}') && in_fields() {
+            push_fields_marker(&mut out, &rest[2..]);
+            rest = &rest[2..];
+        } else if rest.starts_with("/*") {
+            let end = rest.find("*/").map_or(rest.len(), |end| end + 2);
+            let comment = &rest[..end];
+            rest = &rest[end..];
+            let inner = comment.trim_start_matches("/*").trim_end_matches("*/").trim();
+            match open.last().filter(|_| matches!(inner, "..." | ".." | "…")).and_then(|&at| rest_marker(code, at)) {
+                Some(marker) => {
+                    out.push_str(marker);
+                    if !rest.trim_start().starts_with([',', ')', '}']) {
+                        out.push(',');
+                    }
+                }
+                None => out.push_str(comment),
+            }
+        } else if c == '"' {
+            let end = string_end(rest);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if rest.starts_with("(..)") {
+            out.push_str(ANY);
+            rest = &rest[4..];
+        } else {
+            match c {
+                '{' | '(' => open.push(offset),
+                '}' | ')' => { open.pop(); }
+                _ => {}
+            }
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// The marker for the other fields of a struct, with the commas that separate it from the
+/// fields before and after it
+fn push_fields_marker(out: &mut String, rest: &str) {
+    if !out.trim_end().ends_with([',', '{']) {
+        out.push(',');
+    }
+    out.push_str(FIELDS_DECLARATION);
+    if !rest.trim_start().starts_with('}') {
+        out.push(',');
+    }
+}
+
+/// What `/* ... */` becomes in the list opened at `code[at]`: fields of a struct or union,
+/// variants of an enum, parameters of a function, or fields of a tuple struct or variant
+fn rest_marker(code: &str, at: usize) -> Option<&'static str> {
+    let before = &code[..at];
+    if code[at..].starts_with('{') {
+        let keyword = |word: &str| before.rfind(&format!("{} ", word));
+        let enum_at = keyword("enum");
+        let struct_at = keyword("struct").max(keyword("union"));
+        return match (enum_at, struct_at) {
+            (Some(e), s) if s.is_none_or(|s| e > s) => Some(REST),
+            (_, Some(_)) => Some(REST_DECLARATION),
+            _ => None,
+        };
+    }
+    // `fn name<T>(` opens parameters; anything else, a tuple
+    let is_params = before.rfind("fn ").is_some_and(|fn_at| {
+        before[fn_at + 3..].chars().all(|c| c.is_alphanumeric() || "_<>:, '".contains(c))
+    });
+    Some(if is_params { REST_DECLARATION } else { REST })
+}
+
+/// Length of the string literal at the start of `text`, quotes included
+fn string_end(text: &str) -> usize {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return index + 1,
+            _ => escaped = false,
+        }
+    }
+    text.len()
+}
+
+/// A rendered signature with its markers shown as the placeholders they stand for
+pub fn restore(signature: &str) -> String {
+    signature
+        .replace(&format!("{} : {}", FIELDS_FIELD, FIELDS), "..")
+        .replace(&format!("{} : {}", REST_FIELD, REST), "..")
+        .replace(REST, "..")
+        .replace(ANY, "(..)")
+}
+
+/// Whether a signature as `restore` shows it ends a list of fields or variants with `..`, which
+/// the code's can't be lined up with token by token
+pub fn leaves_open(signature: &str) -> bool {
+    signature.contains(", .. }") || signature.contains("{ .. }")
+}
+
+/// Whether the rendered tokens of a spec item use placeholders
+pub fn has_placeholders(tokens: &str) -> bool {
+    tokens.contains(REST) || tokens.contains(ANY) || is_partial(tokens)
+}
+
+/// Whether the rendered tokens of a spec struct list only some of its fields
+pub fn is_partial(tokens: &str) -> bool {
+    tokens.contains(FIELDS)
+}
+
+/// Whether `field` is the marker for the fields a partial struct leaves out
+pub fn is_fields_marker(field: &str) -> bool {
+    field == FIELDS_FIELD
+}
+
+/// Whether the rendered tokens of a code item fit those of a spec item with placeholders.
+/// Always false when the spec has none.
+pub fn matches(code: &str, spec: &str) -> bool {
+    if !has_placeholders(spec) {
+        return false;
+    }
+    if is_partial(spec) {
+        return matches_partial(code, spec);
+    }
+    let (Ok(code), Ok(spec)) = (code.parse::<TokenStream>(), spec.parse::<TokenStream>()) else { return false };
+    match_at(&trees(code), &patterns(spec))
+}
+
+/// A struct fits a partial spec struct if everything but the fields is the same, and each field
+/// the spec lists is one of the code's, whatever their order
+fn matches_partial(code: &str, spec: &str) -> bool {
+    let (Ok(mut code), Ok(mut spec)) = (syn::parse_str::<syn::ItemStruct>(code), syn::parse_str::<syn::ItemStruct>(spec)) else { return false };
+    let (syn::Fields::Named(code_fields), syn::Fields::Named(spec_fields)) = (&code.fields, &spec.fields) else { return false };
+    let render = |field: &syn::Field| quote::quote!(#field).to_string();
+    let code_fields: Vec<String> = code_fields.named.iter().map(render).collect();
+    let listed = spec_fields.named.iter()
+        .filter(|field| field.ident.as_ref().is_none_or(|ident| ident != FIELDS_FIELD))
+        .all(|field| code_fields.contains(&render(field)));
+    (code.fields, spec.fields) = (syn::Fields::Unit, syn::Fields::Unit);
+    listed && quote::quote!(#code).to_string() == quote::quote!(#spec).to_string()
+}
+
+enum Tree {
+    Token(String),
+    Group(Delimiter, Vec<Tree>),
+}
+
+enum Pattern {
+    /// Any tokens, none included
+    Rest,
+    /// One type: at least one token, up to a `,` or `;` outside angle brackets
+    Any,
+    /// A `,` next to `Rest` or at the end of a list, which the code may leave out
+    OptionalComma,
+    Token(String),
+    Group(Delimiter, Vec<Pattern>),
+}
+
+fn is_comma(tree: &Tree) -> bool {
+    matches!(tree, Tree::Token(token) if token == ",")
+}
+
+/// The code's token trees, without trailing commas
+fn trees(stream: TokenStream) -> Vec<Tree> {
+    let mut trees: Vec<Tree> = stream.into_iter().map(|tree| match tree {
+        TokenTree::Group(group) => Tree::Group(group.delimiter(), trees(group.stream())),
+        other => Tree::Token(other.to_string()),
+    }).collect();
+    if trees.last().is_some_and(is_comma) {
+        trees.pop();
+    }
+    trees
+}
+
+fn patterns(stream: TokenStream) -> Vec<Pattern> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut list = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        let is_ident = |at: usize, name: &str| matches!(tokens.get(at), Some(TokenTree::Ident(ident)) if ident == name);
+        let pattern = match &tokens[index] {
+            TokenTree::Ident(_) if is_ident(index, REST_FIELD) && is_ident(index + 2, REST) => {
+                index += 2;
+                Pattern::Rest
+            }
+            TokenTree::Ident(ident) if ident == REST => Pattern::Rest,
+            TokenTree::Ident(ident) if ident == ANY => Pattern::Any,
+            TokenTree::Group(group) => Pattern::Group(group.delimiter(), patterns(group.stream())),
+            other => Pattern::Token(other.to_string()),
+        };
+        list.push(pattern);
+        index += 1;
+    }
+    for index in 0..list.len() {
+        let is_rest = |at: Option<usize>| at.and_then(|at| list.get(at)).is_some_and(|p| matches!(p, Pattern::Rest));
+        let optional = is_rest(index.checked_sub(1)) || is_rest(Some(index + 1)) || index + 1 == list.len();
+        if optional && matches!(&list[index], Pattern::Token(token) if token == ",") {
+            list[index] = Pattern::OptionalComma;
+        }
+    }
+    list
+}
+
+fn match_at(code: &[Tree], spec: &[Pattern]) -> bool {
+    let Some((first, spec_rest)) = spec.split_first() else { return code.is_empty() };
+    match first {
+        Pattern::Rest => (0..=code.len()).any(|skip| match_at(&code[skip..], spec_rest)),
+        Pattern::Any => {
+            let mut depth = 0i32;
+            for (index, tree) in code.iter().enumerate() {
+                match tree {
+                    Tree::Token(token) if (token == "," || token == ";") && depth <= 0 => return false,
+                    Tree::Token(token) if token == "<" => depth += 1,
+                    Tree::Token(token) if token == ">" => depth -= 1,
+                    _ => {}
+                }
+                if depth <= 0 && match_at(&code[index + 1..], spec_rest) {
+                    return true;
+                }
+            }
+            false
+        }
+        Pattern::OptionalComma => {
+            (code.first().is_some_and(is_comma) && match_at(&code[1..], spec_rest)) || match_at(code, spec_rest)
+        }
+        Pattern::Token(token) => match code.split_first() {
+            Some((Tree::Token(code_token), code_rest)) => code_token == token && match_at(code_rest, spec_rest),
+            _ => false,
+        },
+        Pattern::Group(delimiter, inner) => match code.split_first() {
+            Some((Tree::Group(code_delimiter, code_inner), code_rest)) => {
+                code_delimiter == delimiter && match_at(code_inner, inner) && match_at(code_rest, spec_rest)
+            }
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `code` matches `spec` once both are parsed like their blocks would be
+    fn fits(code: &str, spec: &str) -> bool {
+        let render = |text: &str| text.parse::<TokenStream>().unwrap().to_string();
+        matches(&render(code), &render(&expand(spec)))
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            expand("pub struct Selectable { pub hover_color: Color, /* ... */ }"),
+            "pub struct Selectable { pub hover_color: Color, __spec_check_rest: __SpecCheckRest }",
+        );
+        assert_eq!(expand("pub enum Shape { Circle, /* ... */ }"), "pub enum Shape { Circle, __SpecCheckRest }");
+        assert_eq!(expand("fn f(/* ... */ x: u8) {}"), "fn f(__spec_check_rest: __SpecCheckRest, x: u8) {}");
+        assert_eq!(expand("impl Frame { /* ... */ }\n// (..)"), "impl Frame { /* ... */ }\n// (..)");
+        assert_eq!(restore("pub struct S { pub a : u8 , __spec_check_rest : __SpecCheckRest }"), "pub struct S { pub a : u8 , .. }");
+
+        let selectable = "pub struct Selectable { pub hover_color: Color, /* ... */ }";
+        assert!(!fits("pub struct Selectable { pub label: String, pub hover_color: Color, pub pressed: bool }", selectable));
+        assert!(fits("pub struct Selectable { pub hover_color: Color, pub pressed: bool, }", selectable));
+        assert!(fits("pub struct Selectable { pub hover_color: Color }", selectable));
+        assert!(!fits("pub struct Selectable { pub hover_color: u32, pub pressed: bool }", selectable));
+        assert!(fits("pub struct Selectable { pub label: String, pub hover_color: Color }", "pub struct Selectable { /* ... */ pub hover_color: Color, /* ... */ }"));
+
+        let frame = "pub fn frenet_frame(&self, t: f32) -> (..) {}";
+        assert!(fits("pub fn frenet_frame(&self, t: f32) -> (Vec3, Vec3, Vec3) {}", frame));
+        assert!(fits("pub fn frenet_frame(&self, t: f32) -> HashMap<String, Vec<f32>> {}", frame));
+        assert!(!fits("pub fn frenet_frame(&self, t: f64) -> Vec3 {}", frame));
+        let bodiless = |code: &str| crate::rust_parser::without_body(&code.parse::<TokenStream>().unwrap().to_string()).unwrap();
+        assert!(matches(&bodiless("pub fn frenet_frame(&self, t: f32) -> Vec3 { self.tangent(t) }"), &bodiless(&expand(frame))));
+        assert!(fits("pub fn tween(from: f32, to: f32, ease: Ease) {}", "pub fn tween(from: f32, /* ... */) {}"));
+        assert!(fits("pub enum Shape { Circle(f32), Square }", "pub enum Shape { Circle(f32), /* ... */ }"));
+        assert!(!fits("pub struct S { a: u8 }", "pub struct S { a: u16 }"));
+    }
+
+    #[test]
+    fn test_partial_structs() {
+        assert_eq!(
+            expand("pub struct Config {\n    pub name: String\n    // + private fields\n}"),
+            "pub struct Config {\n    pub name: String\n    ,__spec_check_fields: __SpecCheckFields\n}",
+        );
+        assert_eq!(expand("pub struct Config { pub name: String, .. }"), "pub struct Config { pub name: String, __spec_check_fields: __SpecCheckFields }");
+        assert_eq!(expand("pub enum Mode { A, .. }\nfn f() { // + more fields\n}"), "pub enum Mode { A, .. }\nfn f() { // + more fields\n}");
+        assert_eq!(restore("pub struct S { pub a : u8 , __spec_check_fields : __SpecCheckFields }"), "pub struct S { pub a : u8 , .. }");
+
+        let config = "pub struct Config<T> { pub name: String, pub scale: T, .. }";
+        assert!(fits("pub struct Config<T> { cache: Vec<u8>, pub scale: T, pub name: String, id: u64 }", config));
+        assert!(!fits("pub struct Config<T> { pub name: String, scale: T }", config));
+        assert!(!fits("pub struct Config<T> { pub name: String }", config));
+        assert!(!fits("pub struct Config { pub name: String, pub scale: f32 }", config));
+        assert!(fits("pub struct Config { pub name: String }", "pub struct Config {\n    // + private fields\n}"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Custom rules compiled into spec-check, run on every source file after the built-in comparison.
+//!
+//! ```
+//! use spec_check::plugin::{self, FileContext, Rule, Violation};
+//! use spec_check::ItemKind;
+//!
+//! /// Every public function must be documented in the spec
+//! struct DocumentedFunctions;
+//!
+//! impl Rule for DocumentedFunctions {
+//!     fn code(&self) -> &'static str { "ORG001" }
+//!     fn name(&self) -> &'static str { "undocumented-function" }
+//!     fn summary(&self) -> &'static str { "A spec function has no doc comment." }
+//!
+//!     fn check(&self, file: &FileContext) -> Vec<Violation> {
+//!         file.spec_items.iter()
+//!             .filter(|item| item.kind == ItemKind::Function && !item.attributes.iter().any(|a| a.contains("doc")))
+//!             .map(|item| Violation::new(format!("fn {} has no doc comment in the spec", item.name)).at(item))
+//!             .collect()
+//!     }
+//! }
+//!
+//! plugin::register(DocumentedFunctions);
+//! assert!(plugin::find("ORG001").is_some());
+//! ```
+
+use crate::rules;
+use crate::rust_parser::RustItem;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::RwLock;
+
+/// What a custom rule sees of one source file and its spec
+pub struct FileContext<'a> {
+    pub rust_file: &'a Path,
+    /// The spec's path, for rules that need more of it than its items (e.g. its prose)
+    pub spec_file: Option<&'a Path>,
+    pub code_items: &'a [RustItem],
+    pub spec_items: &'a [RustItem],
+}
+
+/// A problem found by a custom rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    /// Code of the rule that found it; filled in when the rule is run
+    pub rule: String,
+    pub line: Option<usize>,
+    pub message: String,
+    /// The item as `open` accepts it, e.g. `CurveGeometry::sample`
+    pub item: Option<String>,
+}
+
+impl Violation {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { rule: String::new(), line: None, message: message.into(), item: None }
+    }
+
+    /// Attach the violation to an item and its line
+    pub fn at(self, item: &RustItem) -> Self {
+        Self { line: Some(item.line_number), item: Some(crate::reporter::item_path(item)), ..self }
+    }
+}
+
+pub trait Rule: Send + Sync {
+    /// Code used in reports, e.g. `ORG001`; `SC` codes are reserved for built-in rules
+    fn code(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    fn summary(&self) -> &'static str;
+    fn check(&self, file: &FileContext) -> Vec<Violation>;
+}
+
+static REGISTRY: RwLock<Vec<&'static dyn Rule>> = RwLock::new(Vec::new());
+
+/// Add a rule to every check from now on.
+/// Panics if its code is reserved or already registered, since that is a build mistake.
+pub fn register(rule: impl Rule + 'static) {
+    let code = rule.code();
+    assert!(rules::find(code).is_none() && !code.to_ascii_uppercase().starts_with("SC"),
+        "custom rule code {} is reserved for built-in rules", code);
+    assert!(find(code).is_none(), "custom rule {} is registered twice", code);
+    REGISTRY.write().unwrap_or_else(|poisoned| poisoned.into_inner()).push(Box::leak(Box::new(rule)));
+}
+
+pub fn registered() -> Vec<&'static dyn Rule> {
+    REGISTRY.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Look up a registered rule by code (case-insensitive) or by name
+pub fn find(code_or_name: &str) -> Option<&'static dyn Rule> {
+    registered().into_iter().find(|rule| rule.code().eq_ignore_ascii_case(code_or_name) || rule.name() == code_or_name)
+}
+
+/// Run every registered rule on a file
+pub fn run(file: &FileContext) -> Vec<Violation> {
+    registered().into_iter()
+        .flat_map(|rule| rule.check(file).into_iter().map(|violation| Violation { rule: rule.code().to_string(), ..violation }))
+        .collect()
+}
+
+/// Identifies the registered rule set, so cached results from another set aren't reused
+pub fn fingerprint() -> String {
+    registered().iter().map(|rule| rule.code()).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Visibility;
+
+    struct NoFunctions;
+
+    impl Rule for NoFunctions {
+        fn code(&self) -> &'static str { "TEST001" }
+        fn name(&self) -> &'static str { "no-functions" }
+        fn summary(&self) -> &'static str { "Functions are not allowed." }
+
+        fn check(&self, file: &FileContext) -> Vec<Violation> {
+            file.code_items.iter()
+                .filter(|item| item.kind == crate::rust_parser::ItemKind::Function)
+                .map(|item| Violation::new("no functions").at(item))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_run_tags_violations_with_rule_code() {
+        register(NoFunctions);
+        let items = crate::rust_parser::parse_rust_file("pub fn a() {}", Visibility::Pub).unwrap();
+        let file = FileContext { rust_file: Path::new("src/lib.rs"), spec_file: None, code_items: &items, spec_items: &[] };
+
+        let violations = run(&file);
+        assert_eq!(violations, vec![Violation { rule: "TEST001".to_string(), line: Some(1), message: "no functions".to_string(), item: Some("a".to_string()) }]);
+        assert!(find("no-functions").is_some());
+        assert!(std::panic::catch_unwind(|| register(NoFunctions)).is_err());
+    }
+}
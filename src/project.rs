@@ -0,0 +1,154 @@
+//! Project-wide matching: items from every source file and every spec file are pooled and
+//! paired by name, whichever files they appear in
+
+use crate::spec_format::SpecFormat;
+use crate::rust_parser::{ItemKind, RustItem};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// One file of the pool, with the module path it stands for (`src/geometry/mod.rs` and
+/// `spec/geometry.md` are both `geometry`)
+pub struct PoolFile {
+    pub path: PathBuf,
+    pub module: Vec<String>,
+    pub items: Vec<RustItem>,
+    /// For a spec file whose headings name modules, each module path and the lines its heading
+    /// covers: the items there are only matched with that module's code
+    pub scopes: Vec<(Vec<String>, Range<usize>)>,
+}
+
+/// The level-2 headings of a spec as module paths (`## math`, `## vr::input`, `## crate` for
+/// the crate root), with the lines each one covers
+pub fn heading_scopes(spec: &str, format: &dyn SpecFormat) -> Vec<(Vec<String>, Range<usize>)> {
+    format.sections(spec).into_iter()
+        .filter(|section| section.level == 2)
+        .map(|section| {
+            let module = section.heading.split("::").map(str::trim).filter(|part| !part.is_empty() && *part != "crate").map(str::to_string).collect();
+            (module, section.lines)
+        })
+        .collect()
+}
+
+/// Where the spec items went: the ones each source file is compared against, and
+/// the ones no source file defines, by spec file
+#[derive(Debug, Default)]
+pub struct Assignment {
+    pub matched: Vec<Vec<RustItem>>,
+    pub unmatched: Vec<Vec<RustItem>>,
+}
+
+/// Pair each spec item with the source files defining an item of the same name, kind and inline module.
+/// When several modules define it, the one whose path matches the spec file's qualifies the
+/// name; if none does, every definition is compared against the spec item. An item under a
+/// heading scope is only paired with the item at the same path from the crate root, the source
+/// file's module and then its inline module.
+pub fn assign(code: &[PoolFile], spec: Vec<PoolFile>) -> Assignment {
+    let mut index: HashMap<(&str, &ItemKind, &[String]), Vec<usize>> = HashMap::new();
+    for (file, pool) in code.iter().enumerate() {
+        for item in &pool.items {
+            let files = index.entry((item.name.as_str(), &item.kind, item.module.as_slice())).or_default();
+            if files.last() != Some(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    let mut assignment = Assignment { matched: vec![Vec::new(); code.len()], unmatched: Vec::new() };
+    for pool in spec {
+        let mut unmatched = Vec::new();
+        for item in pool.items {
+            if let Some((scope, _)) = pool.scopes.iter().find(|(_, lines)| lines.contains(&item.line_number)) {
+                let path: Vec<String> = scope.iter().chain(&item.module).cloned().collect();
+                let path = &path;
+                let targets: Vec<(usize, usize)> = (0..=path.len())
+                    .flat_map(|split| index.get(&(item.name.as_str(), &item.kind, &path[split..])).into_iter().flatten()
+                        .filter(move |&&file| code[file].module == path[..split])
+                        .map(move |&file| (file, split)))
+                    .collect();
+                if targets.is_empty() {
+                    unmatched.push(item);
+                    continue;
+                }
+                // Compared as the code declares it, within the file's module
+                for (file, split) in targets {
+                    let mut item = item.clone();
+                    item.module = path[split..].to_vec();
+                    assignment.matched[file].push(item);
+                }
+                continue;
+            }
+            let Some(files) = index.get(&(item.name.as_str(), &item.kind, item.module.as_slice())) else {
+                unmatched.push(item);
+                continue;
+            };
+            let qualified: Vec<usize> = files.iter().copied().filter(|&file| code[file].module == pool.module).collect();
+            let targets = if qualified.is_empty() { files } else { &qualified };
+            for &file in targets {
+                assignment.matched[file].push(item.clone());
+            }
+        }
+        assignment.unmatched.push(unmatched);
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn item(name: &str) -> RustItem {
+        RustItem::new(name.to_string(), ItemKind::Struct, format!("struct {}", name), quote!(struct Foo;), vec![], 1)
+    }
+
+    fn pool(path: &str, module: &[&str], items: &[&str]) -> PoolFile {
+        PoolFile {
+            path: PathBuf::from(path),
+            module: module.iter().map(|s| s.to_string()).collect(),
+            items: items.iter().map(|name| item(name)).collect(),
+            scopes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_assign_across_files() {
+        let code = vec![
+            pool("src/geometry.rs", &["geometry"], &["Curve", "Error"]),
+            pool("src/render.rs", &["render"], &["Frame", "Error"]),
+        ];
+        let spec = vec![
+            pool("spec/types.md", &["types"], &["Curve", "Frame", "Shader"]),
+            pool("spec/render.md", &["render"], &["Error"]),
+        ];
+        let assignment = assign(&code, spec);
+
+        let names = |items: &[RustItem]| items.iter().map(|i| i.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&assignment.matched[0]), ["Curve"]);
+        assert_eq!(names(&assignment.matched[1]), ["Frame", "Error"]);
+        assert_eq!(names(&assignment.unmatched[0]), ["Shader"]);
+        assert!(assignment.unmatched[1].is_empty());
+    }
+
+    #[test]
+    fn test_assign_by_heading_scope() {
+        let mut lib = pool("src/lib.rs", &[], &[]);
+        lib.items.push(RustItem { module: vec!["vr".to_string()], ..item("Point") });
+        let code = vec![pool("src/math.rs", &["math"], &["Point", "Scalar"]), lib];
+        let markdown = "# Crate\n\n## math\n\nPoint (line 5)\n\n## `crate::vr`\n\nPoint (line 9), Scalar (line 10)\n";
+        let mut spec = pool("SPEC.md", &[], &[]);
+        spec.items = [("Point", 5), ("Point", 9), ("Scalar", 10)].iter()
+            .map(|&(name, line)| RustItem { line_number: line, ..item(name) })
+            .collect();
+        spec.scopes = heading_scopes(markdown, &crate::spec_format::Markdown);
+        assert_eq!(spec.scopes.iter().map(|(module, _)| module.join("::")).collect::<Vec<_>>(), ["math", "vr"]);
+
+        let assignment = assign(&code, vec![spec]);
+        let lines = |items: &[RustItem]| items.iter().map(|i| (i.name.clone(), i.line_number)).collect::<Vec<_>>();
+        assert_eq!(lines(&assignment.matched[0]), [("Point".to_string(), 5)]);
+        // Paired with the inline module, as the code declares it
+        assert_eq!(lines(&assignment.matched[1]), [("Point".to_string(), 9)]);
+        assert_eq!(assignment.matched[1][0].module, ["vr"]);
+        assert_eq!(lines(&assignment.unmatched[0]), [("Scalar".to_string(), 10)]);
+    }
+}
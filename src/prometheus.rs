@@ -0,0 +1,86 @@
+//! Metrics of a run in Prometheus' text exposition format, for node_exporter's textfile
+//! collector to pick up. Every value describes the last run, so all are gauges.
+
+use crate::coverage::Coverage;
+use crate::reporter::Finding;
+use crate::rules;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// What a run measured
+pub struct Metrics<'a> {
+    pub files_checked: usize,
+    pub files_with_errors: usize,
+    pub findings: &'a [Finding],
+    pub coverage: Option<Coverage>,
+    pub duration: Duration,
+}
+
+pub fn to_string(metrics: &Metrics) -> String {
+    let mut out = String::new();
+    gauge(&mut out, "spec_check_files_checked", "Source files checked", &[(None, metrics.files_checked as f64)]);
+    gauge(&mut out, "spec_check_files_with_errors", "Source files with failing findings", &[(None, metrics.files_with_errors as f64)]);
+
+    // Every built-in rule has a series, so a rule's count drops to 0 rather than disappearing
+    let mut by_rule: BTreeMap<&str, usize> = rules::RULES.iter().map(|rule| (rule.code, 0)).collect();
+    for finding in metrics.findings {
+        *by_rule.entry(finding.rule).or_default() += 1;
+    }
+    let by_rule: Vec<(Option<String>, f64)> = by_rule.into_iter().map(|(rule, count)| (Some(format!("rule=\"{}\"", escape(rule))), count as f64)).collect();
+    gauge(&mut out, "spec_check_findings", "Findings reported, by rule", &by_rule);
+
+    if let Some(coverage) = metrics.coverage {
+        gauge(&mut out, "spec_check_coverage_items", "Items in the checked source files", &[(None, coverage.items as f64)]);
+        gauge(&mut out, "spec_check_coverage_specced_items", "Items the spec describes", &[(None, coverage.specced as f64)]);
+        gauge(&mut out, "spec_check_coverage_percent", "Share of items the spec describes, in percent", &[(None, coverage.percent())]);
+    }
+    gauge(&mut out, "spec_check_duration_seconds", "How long the run took", &[(None, metrics.duration.as_secs_f64())]);
+    out
+}
+
+/// A gauge's `HELP` and `TYPE` lines, then a sample per set of labels
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(Option<String>, f64)]) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} gauge", name).unwrap();
+    for (labels, value) in samples {
+        match labels {
+            Some(labels) => writeln!(out, "{}{{{}}} {}", name, labels, value).unwrap(),
+            None => writeln!(out, "{} {}", name, value).unwrap(),
+        }
+    }
+}
+
+/// A label value with backslashes, quotes and line breaks escaped
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_metrics_have_a_series_per_rule() {
+        let findings = [
+            Finding::new(rules::MISSING_IN_SPEC, Path::new("src/math.rs"), Some(3), "struct Color is in the code but not in the spec".to_string()),
+            Finding::new(rules::MISSING_IN_SPEC, Path::new("src/math.rs"), Some(9), "fn mix is in the code but not in the spec".to_string()),
+            Finding::new("ACME001", Path::new("src/lib.rs"), None, "custom".to_string()),
+        ];
+        let text = to_string(&Metrics {
+            files_checked: 4,
+            files_with_errors: 1,
+            findings: &findings,
+            coverage: Some(Coverage { items: 8, specced: 6 }),
+            duration: Duration::from_millis(1500),
+        });
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[..3], ["# HELP spec_check_files_checked Source files checked", "# TYPE spec_check_files_checked gauge", "spec_check_files_checked 4"]);
+        assert!(lines.contains(&"spec_check_findings{rule=\"SC001\"} 2"));
+        assert!(lines.contains(&"spec_check_findings{rule=\"SC002\"} 0"));
+        assert!(lines.contains(&"spec_check_findings{rule=\"ACME001\"} 1"));
+        assert!(lines.contains(&"spec_check_coverage_percent 75"));
+        assert_eq!(lines.last(), Some(&"spec_check_duration_seconds 1.5"));
+    }
+}
@@ -1,37 +1,219 @@
-use crate::comparator::ComparisonResult;
-use crate::rust_parser::{RustItem, ItemKind};
+use crate::blame::Blame;
+use crate::comparator::{ComparisonResult, OrderingViolation, SignatureMismatch, VariantChange};
+use crate::config::SourceOfTruth;
+use crate::coverage::Coverage;
+use crate::plugin;
+use crate::rules;
+use crate::semver::{Bump, Impact};
+use crate::word_diff;
+use crate::rust_parser::{self, RustItem, ItemKind};
 use anyhow::Result;
-use std::fs::OpenOptions;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub struct Reporter {
+/// Where a check run's results go, file by file as they're done. Several can be active at once
+/// as a `Vec<Box<dyn Report>>`.
+pub trait Report {
+    /// Called once, before the first file, with how many files will be reported
+    fn report_start(&mut self, _files: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn report_missing_spec(&mut self, file: &Path) -> Result<()>;
+
+    fn report_parse_failure(&mut self, file: &Path, error: &anyhow::Error) -> Result<()>;
+
+    /// A spec file no source file maps to; called after the source files
+    fn report_orphaned_spec(&mut self, spec_file: &Path) -> Result<()>;
+
+    /// The result of comparing a file with its spec, also when it matches
+    fn report_results(&mut self, file: &Path, spec: Option<&Path>, result: &ComparisonResult) -> Result<()>;
+
+    fn report_stopped_early(&mut self, _reason: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once, after the last file
+    fn write_summary(&mut self, total_files: usize, files_with_errors: usize) -> Result<()>;
+
+    fn report_baseline(&mut self, _suppressed: usize, _unused: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// The spec coverage of each source file with items, the total, and the least total the
+    /// run passes with; called before the summary
+    fn report_coverage(&mut self, _files: &[(PathBuf, Coverage)], _total: Coverage, _min: Option<f64>) -> Result<()> {
+        Ok(())
+    }
+
+    /// The version bump the run's drift needs, the package's version, and the largest bump the
+    /// run passes with; called before the summary
+    fn report_semver(&mut self, _impact: Impact, _version: Option<&str>, _max: Option<Bump>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Report for Vec<Box<dyn Report>> {
+    fn report_start(&mut self, files: usize) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_start(files))
+    }
+
+    fn report_missing_spec(&mut self, file: &Path) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_missing_spec(file))
+    }
+
+    fn report_parse_failure(&mut self, file: &Path, error: &anyhow::Error) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_parse_failure(file, error))
+    }
+
+    fn report_orphaned_spec(&mut self, spec_file: &Path) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_orphaned_spec(spec_file))
+    }
+
+    fn report_results(&mut self, file: &Path, spec: Option<&Path>, result: &ComparisonResult) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_results(file, spec, result))
+    }
+
+    fn report_stopped_early(&mut self, reason: &str) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_stopped_early(reason))
+    }
+
+    fn write_summary(&mut self, total_files: usize, files_with_errors: usize) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.write_summary(total_files, files_with_errors))
+    }
+
+    fn report_baseline(&mut self, suppressed: usize, unused: usize) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_baseline(suppressed, unused))
+    }
+
+    fn report_coverage(&mut self, files: &[(PathBuf, Coverage)], total: Coverage, min: Option<f64>) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_coverage(files, total, min))
+    }
+
+    fn report_semver(&mut self, impact: Impact, version: Option<&str>, max: Option<Bump>) -> Result<()> {
+        self.iter_mut().try_for_each(|report| report.report_semver(impact, version, max))
+    }
+}
+
+/// The plain-text log, `spec-check.log` by default
+pub struct LogReport {
     log_file: std::fs::File,
+    /// The spec coverage lines, written with the summary
+    coverage: String,
+    /// The semver impact lines, written with the summary
+    semver: String,
+    tally: Tally,
 }
 
-impl Reporter {
+/// How the log is opened, from `log-append`, `log-max-size` and `log-keep`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogOptions {
+    /// Add each run to the log, under a header with its start time, instead of replacing it
+    pub append: bool,
+    /// Move a log this many bytes or larger to `<log>.1` before the run, and older ones up
+    pub max_size: Option<u64>,
+    /// How many rotated logs to keep
+    pub keep: usize,
+}
+
+impl LogReport {
     pub fn new(log_path: &Path) -> Result<Self> {
-        let log_file = OpenOptions::new()
+        Self::open(log_path, &LogOptions::default())
+    }
+
+    pub fn open(log_path: &Path, options: &LogOptions) -> Result<Self> {
+        if let Some(max_size) = options.max_size {
+            rotate(log_path, max_size, options.keep)?;
+        }
+        let mut log_file = OpenOptions::new()
             .create(true)
             .write(true)
-            .truncate(true)
+            .append(options.append)
+            .truncate(!options.append)
             .open(log_path)?;
-        
-        Ok(Self { log_file })
+        if options.append {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            // Runs after the first are set off by a blank line
+            let separator = if log_file.metadata()?.len() > 0 { "\n" } else { "" };
+            writeln!(log_file, "{}======== spec-check run at {} ========", separator, utc_timestamp(now))?;
+        }
+
+        Ok(Self { log_file, coverage: String::new(), semver: String::new(), tally: Tally::default() })
+    }
+}
+
+/// `<log>.1` and so on, the older the higher
+pub fn rotated(log_path: &Path, index: usize) -> PathBuf {
+    let mut name = log_path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Move the log out of the way if it reached `max_size`, keeping `keep` rotated logs
+fn rotate(log_path: &Path, max_size: u64, keep: usize) -> Result<()> {
+    if fs::metadata(log_path).map_or(true, |metadata| metadata.len() < max_size) {
+        return Ok(());
     }
+    if keep == 0 {
+        fs::remove_file(log_path)?;
+        return Ok(());
+    }
+    for index in (1..keep).rev() {
+        if rotated(log_path, index).exists() {
+            fs::rename(rotated(log_path, index), rotated(log_path, index + 1))?;
+        }
+    }
+    fs::rename(log_path, rotated(log_path, 1))?;
+    Ok(())
+}
 
-    pub fn report_missing_spec(&mut self, file: &Path) -> Result<()> {
+/// Seconds since the epoch as an ISO 8601 UTC time, e.g. `2024-03-09T14:05:00Z`
+pub fn utc_timestamp(secs: u64) -> String {
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01, after Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rest / 3_600, rest % 3_600 / 60, rest % 60)
+}
+
+impl Report for LogReport {
+    fn report_missing_spec(&mut self, file: &Path) -> Result<()> {
         writeln!(self.log_file, "WARNING: No spec file found for {}", file.display())?;
+        self.tally.add(file, [(rules::MISSING_SPEC_FILE, None)]);
+        Ok(())
+    }
+
+    fn report_parse_failure(&mut self, file: &Path, error: &anyhow::Error) -> Result<()> {
+        writeln!(self.log_file, "\nERROR: Failed to parse {}: {}", file.display(), error)?;
+        self.tally.add(file, [(rules::PARSE_FAILURE, None)]);
         Ok(())
     }
 
-    pub fn report_results(&mut self, file: &Path, result: &ComparisonResult) -> Result<()> {
+    fn report_orphaned_spec(&mut self, spec_file: &Path) -> Result<()> {
+        writeln!(self.log_file, "\nERROR: No source file found for spec {}", spec_file.display())?;
+        self.tally.add(spec_file, [(rules::ORPHANED_SPEC, None)]);
+        Ok(())
+    }
+
+    fn report_results(&mut self, file: &Path, _spec: Option<&Path>, result: &ComparisonResult) -> Result<()> {
         if !result.has_errors() {
             writeln!(self.log_file, "OK: {}", file.display())?;
             return Ok(());
         }
 
         writeln!(self.log_file, "\nERROR: {}", file.display())?;
+        self.tally.add(file, finding_kinds(result));
 
         // Report items in code but not in spec
         if !result.missing_in_spec.is_empty() {
@@ -45,7 +227,7 @@ impl Reporter {
         if !result.missing_in_code.is_empty() {
             writeln!(self.log_file, "  Items in spec but not in code:")?;
             for item in &result.missing_in_code {
-                writeln!(self.log_file, "    - {} (line {})", format_item(item), item.line_number)?;
+                writeln!(self.log_file, "    - {} (spec line {})", format_item(item), item.line_number)?;
             }
         }
 
@@ -53,11 +235,39 @@ impl Reporter {
         if !result.signature_mismatches.is_empty() {
             writeln!(self.log_file, "  Signature mismatches:")?;
             for mismatch in &result.signature_mismatches {
-                writeln!(self.log_file, "    - {}", format_item(&mismatch.code_item))?;
-                writeln!(self.log_file, "      Code (line {}): {}", mismatch.code_item.line_number, &mismatch.code_item.signature)?;
-                writeln!(self.log_file, "      Spec (line {}): {}", mismatch.spec_item.line_number, &mismatch.spec_item.signature)?;
-                if let Some(pos) = mismatch.first_diff_pos {
-                    writeln!(self.log_file, "      First difference at character {}", pos)?;
+                if mismatch.differences.is_empty() && (!mismatch.field_diffs.is_empty() || !mismatch.variant_diffs.is_empty()) {
+                    // Only fields or variants drifted: list them instead of both full signatures
+                    writeln!(self.log_file, "    - {} (code line {}, spec line {})", format_item(&mismatch.code_item),
+                        mismatch.code_item.line_number, mismatch.spec_item.line_number)?;
+                } else {
+                    writeln!(self.log_file, "    - {}", format_item(&mismatch.code_item))?;
+                    let (code, spec) = shown_signatures(mismatch);
+                    writeln!(self.log_file, "      Code (line {}): {}", mismatch.code_item.line_number, code)?;
+                    writeln!(self.log_file, "      Spec (line {}): {}", mismatch.spec_item.line_number, spec)?;
+                    let diff = word_diff::diff(&spec, &code);
+                    writeln!(self.log_file, "      Diff: {}", word_diff::render(&diff))?;
+                }
+                for difference in &mismatch.differences {
+                    writeln!(self.log_file, "      Difference: {}", difference)?;
+                }
+                if !mismatch.field_diffs.is_empty() {
+                    writeln!(self.log_file, "      Fields:")?;
+                    for diff in &mismatch.field_diffs {
+                        writeln!(self.log_file, "        - {}", diff)?;
+                    }
+                }
+                if !mismatch.variant_diffs.is_empty() {
+                    writeln!(self.log_file, "      Variants:")?;
+                    for diff in &mismatch.variant_diffs {
+                        let VariantChange::Fields(fields) = &diff.change else {
+                            writeln!(self.log_file, "        - {}", diff)?;
+                            continue;
+                        };
+                        writeln!(self.log_file, "        - variant `{}`:", diff.variant)?;
+                        for field in fields {
+                            writeln!(self.log_file, "          - {}", field)?;
+                        }
+                    }
                 }
             }
         }
@@ -75,26 +285,608 @@ impl Reporter {
             }
         }
 
+        // Report doc comments that drifted from the spec
+        if !result.doc_mismatches.is_empty() {
+            writeln!(self.log_file, "  Doc comment mismatches:")?;
+            for mismatch in &result.doc_mismatches {
+                writeln!(self.log_file, "    - {} (code line {}, spec line {})",
+                    format_item(&mismatch.code_item),
+                    mismatch.code_item.line_number,
+                    mismatch.spec_item.line_number)?;
+                writeln!(self.log_file, "      Code docs: {}", if mismatch.code.is_empty() { "none" } else { &mismatch.code })?;
+                writeln!(self.log_file, "      Spec docs: {}", mismatch.spec)?;
+                writeln!(self.log_file, "      Diff: {}", word_diff::render(&word_diff::diff(&mismatch.spec, &mismatch.code)))?;
+            }
+        }
+
+        // Report items the code deprecated and the spec didn't
+        if !result.deprecated_in_code.is_empty() {
+            writeln!(self.log_file, "  Deprecated in code:")?;
+            for deprecated in &result.deprecated_in_code {
+                writeln!(self.log_file, "    - {} (code line {}, spec line {})",
+                    format_item(&deprecated.code_item),
+                    deprecated.code_item.line_number,
+                    deprecated.spec_item.line_number)?;
+            }
+        }
+
+        // Report items out of the spec's order
+        if !result.ordering_violations.is_empty() {
+            writeln!(self.log_file, "  Ordering violations:")?;
+            for violation in &result.ordering_violations {
+                writeln!(self.log_file, "    - {} (code line {}, spec line {}): {}",
+                    format_item(&violation.code_item),
+                    violation.code_item.line_number,
+                    violation.spec_item.line_number,
+                    spec_position(violation))?;
+            }
+        }
+
+        // Report names breaking the naming conventions
+        if !result.naming_violations.is_empty() {
+            writeln!(self.log_file, "  Naming convention violations:")?;
+            for violation in &result.naming_violations {
+                writeln!(self.log_file, "    - {} (line {}): should be {}",
+                    format_item(&violation.code_item),
+                    violation.code_item.line_number,
+                    violation.expected)?;
+            }
+        }
+
+        // Report changed discriminants
+        if !result.discriminant_mismatches.is_empty() {
+            writeln!(self.log_file, "  Discriminant mismatches:")?;
+            for mismatch in &result.discriminant_mismatches {
+                writeln!(self.log_file, "    - {} (code line {}, spec line {})",
+                    format_item(&mismatch.code_item),
+                    mismatch.code_item.line_number,
+                    mismatch.spec_item.line_number)?;
+                for (variant, spec, code) in &mismatch.changes {
+                    writeln!(self.log_file, "      {}: {} in the spec, {} in the code", variant, spec, code)?;
+                }
+            }
+        }
+
+        // Report changed const and static values
+        if !result.value_mismatches.is_empty() {
+            writeln!(self.log_file, "  Value mismatches:")?;
+            for mismatch in &result.value_mismatches {
+                writeln!(self.log_file, "    - {} (code line {}, spec line {}): {} in the spec, {} in the code",
+                    format_item(&mismatch.code_item),
+                    mismatch.code_item.line_number,
+                    mismatch.spec_item.line_number,
+                    mismatch.spec,
+                    mismatch.code)?;
+            }
+        }
+
+        // Report changed layouts and calling conventions
+        if !result.abi_mismatches.is_empty() {
+            writeln!(self.log_file, "  ABI mismatches:")?;
+            for mismatch in &result.abi_mismatches {
+                writeln!(self.log_file, "    - {} (code line {}, spec line {})",
+                    format_item(&mismatch.code_item),
+                    mismatch.code_item.line_number,
+                    mismatch.spec_item.line_number)?;
+                for difference in &mismatch.differences {
+                    writeln!(self.log_file, "      {}", difference)?;
+                }
+            }
+        }
+
+        // Report custom rule violations
+        if !result.custom_violations.is_empty() {
+            writeln!(self.log_file, "  Custom rule violations:")?;
+            for violation in &result.custom_violations {
+                match violation.line {
+                    Some(line) => writeln!(self.log_file, "    - [{}] {} (line {})", violation.rule, violation.message, line)?,
+                    None => writeln!(self.log_file, "    - [{}] {}", violation.rule, violation.message)?,
+                }
+            }
+        }
+
+        // Report items the spec forbids
+        if !result.forbidden_present.is_empty() {
+            writeln!(self.log_file, "  Forbidden items present:")?;
+            for item in &result.forbidden_present {
+                writeln!(self.log_file, "    - {} (line {})", format_item(item), item.line_number)?;
+            }
+        }
+
+        // Report items that look renamed on one side
+        if !result.probable_renames.is_empty() {
+            writeln!(self.log_file, "  Probable renames:")?;
+            for rename in &result.probable_renames {
+                writeln!(self.log_file, "    - {} (line {}) looks like {} renamed (spec line {}, {:.0}% alike)",
+                    format_item(&rename.code_item), rename.code_item.line_number, format_item(&rename.spec_item),
+                    rename.spec_item.line_number, rename.similarity * 100.0)?;
+            }
+        }
+
+        // Report items defined twice on one side
+        if !result.duplicates.is_empty() {
+            writeln!(self.log_file, "  Duplicate definitions:")?;
+            for duplicate in &result.duplicates {
+                let line = if duplicate.in_spec { "spec line" } else { "line" };
+                writeln!(self.log_file, "    - {} ({} {}) differs from its definition at {} {}",
+                    format_item(&duplicate.duplicate), line, duplicate.duplicate.line_number, line, duplicate.first.line_number)?;
+            }
+        }
+
+        // Report spec blocks whose items went unchecked
+        if !result.spec_parse_errors.is_empty() {
+            writeln!(self.log_file, "  Unparseable spec blocks:")?;
+            for error in &result.spec_parse_errors {
+                match &error.skipped {
+                    Some(item) => writeln!(self.log_file, "    - block {} (spec line {}): `{}`: {}", error.block, error.line, item, error.message)?,
+                    None => writeln!(self.log_file, "    - block {} (spec line {}): {}", error.block, error.line, error.message)?,
+                }
+            }
+        }
+
+        // Report annotations that point nowhere
+        if !result.unresolved_annotations.is_empty() {
+            writeln!(self.log_file, "  Unresolved #[spec_checked] annotations:")?;
+            for item in &result.unresolved_annotations {
+                writeln!(self.log_file, "    - {} (line {})", format_item(item), item.line_number)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn report_stopped_early(&mut self, reason: &str) -> Result<()> {
+        writeln!(self.log_file, "\nSTOPPED: {}", reason)?;
         Ok(())
     }
 
-    pub fn write_summary(&mut self, total_files: usize, files_with_errors: usize) -> Result<()> {
+    fn write_summary(&mut self, total_files: usize, files_with_errors: usize) -> Result<()> {
         writeln!(self.log_file, "\n{}", "=".repeat(80))?;
         writeln!(self.log_file, "SUMMARY")?;
         writeln!(self.log_file, "Total files checked: {}", total_files)?;
         writeln!(self.log_file, "Files with errors: {}", files_with_errors)?;
         writeln!(self.log_file, "Files passing: {}", total_files - files_with_errors)?;
+        self.tally.write(&mut self.log_file)?;
+        write!(self.log_file, "{}", self.coverage)?;
+        write!(self.log_file, "{}", self.semver)?;
+        Ok(())
+    }
+
+    fn report_baseline(&mut self, suppressed: usize, unused: usize) -> Result<()> {
+        writeln!(self.log_file, "Findings accepted by the baseline: {}", suppressed)?;
+        if unused > 0 {
+            writeln!(self.log_file, "Accepted findings that no longer occur: {} (run --write-baseline to drop them)", unused)?;
+        }
+        Ok(())
+    }
+
+    fn report_coverage(&mut self, files: &[(PathBuf, Coverage)], total: Coverage, min: Option<f64>) -> Result<()> {
+        self.coverage = format!("Spec coverage: {}\n", total);
+        for (file, coverage) in files {
+            self.coverage += &format!("  {}: {}\n", file.display(), coverage);
+        }
+        if let Some(min) = min.filter(|&min| total.percent() < min) {
+            self.coverage += &format!("ERROR: Spec coverage is below the minimum of {}%\n", min);
+        }
+        Ok(())
+    }
+
+    fn report_semver(&mut self, impact: Impact, version: Option<&str>, max: Option<Bump>) -> Result<()> {
+        self.semver = format!("Semver impact: {}\n", impact.describe(version));
+        if let Some(max) = max.filter(|&max| impact.bump().is_some_and(|bump| bump > max)) {
+            self.semver += &format!("ERROR: The drift needs more than a {} bump (--max-bump)\n", max);
+        }
+        Ok(())
+    }
+}
+
+/// Findings counted by rule and item kind, and by source file, so the summary shows where
+/// drift is concentrated
+#[derive(Default)]
+struct Tally {
+    /// By rule code, then item kind; `None` for findings about a whole file or without an item
+    by_rule: BTreeMap<&'static str, BTreeMap<Option<&'static str>, usize>>,
+    by_file: Vec<(PathBuf, usize)>,
+}
+
+impl Tally {
+    fn add(&mut self, file: &Path, findings: impl IntoIterator<Item = (&'static str, Option<&'static str>)>) {
+        let mut count = 0;
+        for (rule, kind) in findings {
+            *self.by_rule.entry(rule).or_default().entry(kind).or_default() += 1;
+            count += 1;
+        }
+        if count > 0 {
+            self.by_file.push((file.to_path_buf(), count));
+        }
+    }
+
+    /// Lines like `SC001 missing-in-spec: 12 struct, 3 trait method`, then the files with the
+    /// most findings first
+    fn write(&self, out: &mut impl Write) -> std::io::Result<()> {
+        if self.by_rule.is_empty() {
+            return Ok(());
+        }
+        writeln!(out, "Findings by rule and item kind:")?;
+        for (rule, kinds) in &self.by_rule {
+            let mut kinds: Vec<(&Option<&str>, &usize)> = kinds.iter().collect();
+            kinds.sort_by_key(|&(kind, count)| (std::cmp::Reverse(*count), *kind));
+            let counts: Vec<String> = kinds.iter()
+                .map(|(kind, count)| kind.map_or(count.to_string(), |kind| format!("{} {}", count, kind)))
+                .collect();
+            let name = rules::find(rule).map(|known| format!(" {}", known.name)).unwrap_or_default();
+            writeln!(out, "  {}{}: {}", rule, name, counts.join(", "))?;
+        }
+        writeln!(out, "Findings by file:")?;
+        let mut by_file: Vec<&(PathBuf, usize)> = self.by_file.iter().collect();
+        by_file.sort_by_key(|(file, count)| (std::cmp::Reverse(*count), file));
+        for (file, count) in by_file {
+            writeln!(out, "  {}: {}", file.display(), count)?;
+        }
         Ok(())
     }
 }
 
-fn format_item(item: &RustItem) -> String {
+/// The rule and item kind of each finding a comparison result is reported as
+fn finding_kinds(result: &ComparisonResult) -> Vec<(&'static str, Option<&'static str>)> {
+    let kind = |item: &RustItem| Some(item.kind.label());
+    let mut kinds = Vec::new();
+    kinds.extend(result.missing_in_spec.iter().map(|item| (rules::MISSING_IN_SPEC, kind(item))));
+    kinds.extend(result.missing_in_code.iter().map(|item| (rules::MISSING_IN_CODE, kind(item))));
+    kinds.extend(result.signature_mismatches.iter().map(|mismatch| (rules::SIGNATURE_MISMATCH, kind(&mismatch.code_item))));
+    kinds.extend(result.attribute_mismatches.iter().map(|mismatch| (rules::ATTRIBUTE_MISMATCH, kind(&mismatch.code_item))));
+    kinds.extend(result.doc_mismatches.iter().map(|mismatch| (rules::DOC_MISMATCH, kind(&mismatch.code_item))));
+    kinds.extend(result.deprecated_in_code.iter().map(|deprecated| (rules::DEPRECATED_IN_CODE, kind(&deprecated.code_item))));
+    kinds.extend(result.ordering_violations.iter().map(|violation| (rules::ORDERING_VIOLATION, kind(&violation.code_item))));
+    kinds.extend(result.naming_violations.iter().map(|violation| (rules::NAMING_CONVENTION, kind(&violation.code_item))));
+    kinds.extend(result.discriminant_mismatches.iter().map(|mismatch| (rules::DISCRIMINANT_MISMATCH, kind(&mismatch.code_item))));
+    kinds.extend(result.value_mismatches.iter().map(|mismatch| (rules::VALUE_MISMATCH, kind(&mismatch.code_item))));
+    kinds.extend(result.abi_mismatches.iter().map(|mismatch| (rules::ABI_MISMATCH, kind(&mismatch.code_item))));
+    kinds.extend(result.custom_violations.iter().map(|violation| (plugin::find(&violation.rule).map_or("custom", |rule| rule.code()), None)));
+    kinds.extend(result.forbidden_present.iter().map(|item| (rules::FORBIDDEN_PRESENT, kind(item))));
+    kinds.extend(result.probable_renames.iter().map(|rename| (rules::PROBABLE_RENAME, kind(&rename.code_item))));
+    kinds.extend(result.unresolved_annotations.iter().map(|item| (rules::UNRESOLVED_ANNOTATION, kind(item))));
+    kinds.extend(result.duplicates.iter().map(|duplicate| (rules::DUPLICATE_DEFINITION, kind(&duplicate.duplicate))));
+    kinds.extend(result.spec_parse_errors.iter().map(|_| (rules::SPEC_PARSE_FAILURE, None)));
+    kinds
+}
+
+/// One reported problem, for machine-readable outputs
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    /// 1-based column on `line` of the item's name
+    pub column: Option<usize>,
+    pub message: String,
+    /// The item as `open` accepts it, e.g. `CurveGeometry::sample`
+    pub item: Option<String>,
+    /// The item's definition in the code and in the spec, when it has one there
+    pub code: Option<String>,
+    pub spec: Option<String>,
+    /// The line of the item in the spec file
+    pub spec_line: Option<usize>,
+    /// The spec file `spec_line` is in, when the source file was compared with one
+    pub spec_path: Option<PathBuf>,
+    /// The spec file to change instead of `file`, for items missing on either side when the code
+    /// is the source of truth. `file` stays the checked source file, which baselines go by.
+    pub spec_file: Option<PathBuf>,
+    /// The workspace member the file belongs to, in `workspace` runs
+    pub crate_name: Option<String>,
+    /// Who last changed `line` and `spec_line`, with `--blame`
+    pub blame: Option<Blame>,
+    pub spec_blame: Option<Blame>,
+}
+
+impl Finding {
+    pub fn new(rule: &'static str, file: &Path, line: Option<usize>, message: String) -> Self {
+        Self { rule, file: file.to_path_buf(), line, column: None, message, item: None, code: None, spec: None, spec_line: None, spec_path: None, spec_file: None, crate_name: None, blame: None, spec_blame: None }
+    }
+
+    /// Where reports point for the finding: the spec file, at the item if it's there, when the
+    /// finding is attributed to the spec
+    pub fn location(&self) -> (&Path, Option<usize>) {
+        match &self.spec_file {
+            Some(spec_file) => (spec_file, self.spec_line),
+            None => (&self.file, self.line),
+        }
+    }
+
+    /// The column of `location`'s line, when it's at an item in the source file
+    pub fn column(&self) -> Option<usize> {
+        self.column.filter(|_| self.spec_file.is_none())
+    }
+}
+
+/// A finding as JSON reports, webhooks and `serve` give it, with `/` separated paths
+pub fn finding_json(finding: &Finding) -> toml::Value {
+    let mut table = toml::Table::new();
+    table.insert("rule".to_string(), toml::Value::String(finding.rule.to_string()));
+    if let Some(rule) = rules::find(finding.rule) {
+        table.insert("name".to_string(), toml::Value::String(rule.name.to_string()));
+    }
+    if let Some(crate_name) = &finding.crate_name {
+        table.insert("crate".to_string(), toml::Value::String(crate_name.clone()));
+    }
+    table.insert("file".to_string(), toml::Value::String(finding.file.to_string_lossy().replace('\\', "/")));
+    if let Some(line) = finding.line {
+        table.insert("line".to_string(), toml::Value::Integer(line as i64));
+    }
+    table.insert("message".to_string(), toml::Value::String(finding.message.clone()));
+    if let Some(line) = finding.spec_line {
+        table.insert("spec_line".to_string(), toml::Value::Integer(line as i64));
+    }
+    if let Some(spec_path) = &finding.spec_path {
+        table.insert("spec_file".to_string(), toml::Value::String(spec_path.to_string_lossy().replace('\\', "/")));
+    }
+    for (key, value) in [("item", &finding.item), ("code", &finding.code), ("spec", &finding.spec)] {
+        if let Some(value) = value {
+            table.insert(key.to_string(), toml::Value::String(value.clone()));
+        }
+    }
+    for (key, blame) in [("blame", &finding.blame), ("spec_blame", &finding.spec_blame)] {
+        if let Some(blame) = blame {
+            let mut entry = toml::Table::new();
+            entry.insert("author".to_string(), toml::Value::String(blame.author.clone()));
+            entry.insert("email".to_string(), toml::Value::String(blame.email.clone()));
+            entry.insert("commit".to_string(), toml::Value::String(blame.commit.clone()));
+            table.insert(key.to_string(), toml::Value::Table(entry));
+        }
+    }
+    toml::Value::Table(table)
+}
+
+pub fn missing_spec_finding(file: &Path) -> Finding {
+    Finding::new(rules::MISSING_SPEC_FILE, file, None, "No spec file found".to_string())
+}
+
+pub fn orphaned_spec_finding(spec_file: &Path) -> Finding {
+    Finding::new(rules::ORPHANED_SPEC, spec_file, None, "No source file matches this spec".to_string())
+}
+
+pub fn parse_failure_finding(file: &Path, error: &anyhow::Error) -> Finding {
+    Finding::new(rules::PARSE_FAILURE, file, None, format!("Failed to parse: {}", error))
+}
+
+/// Flatten a comparison result into findings against the source file, worded for `truth`, the
+/// side that is right when they disagree. With the code as the source of truth, items missing on
+/// either side are attributed to `spec_file`.
+pub fn findings_for(file: &Path, spec_file: Option<&Path>, result: &ComparisonResult, truth: Option<SourceOfTruth>) -> Vec<Finding> {
+    let for_item = |rule, item: &RustItem, line: Option<usize>, message| Finding {
+        item: Some(item_path(item)),
+        column: line.and(Some(item.column)).filter(|&column| column > 0),
+        ..Finding::new(rule, file, line, message)
+    };
+    let attributed = spec_file.filter(|_| truth == Some(SourceOfTruth::Code)).map(Path::to_path_buf);
+    // With the code as the source of truth, a difference is the spec not keeping up
+    let differs = |item: &RustItem, what: &str, neutral: &str, detail: String| match truth {
+        Some(SourceOfTruth::Code) => format!("{} {} changed since the spec{}; update the spec", format_item(item), what, detail),
+        _ => format!("{} {}{}", format_item(item), neutral, detail),
+    };
+
+    let mut findings = Vec::new();
+    for item in &result.missing_in_spec {
+        let message = match truth {
+            None => "is in the code but not in the spec",
+            Some(SourceOfTruth::Code) => "is in the code but not in the spec yet; add it to the spec",
+            Some(SourceOfTruth::Spec) => "is in the code but not in the spec; remove it, or add it to the spec first",
+        };
+        findings.push(Finding {
+            code: Some(item.signature.clone()),
+            spec_file: attributed.clone(),
+            ..for_item(rules::MISSING_IN_SPEC, item, Some(item.line_number), format!("{} {}", format_item(item), message))
+        });
+    }
+    for item in &result.missing_in_code {
+        let message = match truth {
+            None => "is in the spec but not in the code",
+            Some(SourceOfTruth::Code) => "is in the spec but no longer in the code; remove it from the spec",
+            Some(SourceOfTruth::Spec) => "is in the spec but not implemented yet",
+        };
+        findings.push(Finding {
+            spec: Some(item.signature.clone()),
+            spec_line: Some(item.line_number),
+            spec_file: attributed.clone(),
+            ..for_item(rules::MISSING_IN_CODE, item, None, format!("{} {}", format_item(item), message))
+        });
+    }
+    for mismatch in &result.signature_mismatches {
+        let (code, spec) = shown_signatures(mismatch);
+        findings.push(Finding {
+            code: Some(code),
+            spec: Some(spec),
+            spec_line: Some(mismatch.spec_item.line_number),
+            ..for_item(rules::SIGNATURE_MISMATCH, &mismatch.code_item, Some(mismatch.code_item.line_number),
+                differs(&mismatch.code_item, "signature", "does not match the spec", format!(": {}", mismatch_detail(mismatch))))
+        });
+    }
+    for mismatch in &result.attribute_mismatches {
+        findings.push(Finding {
+            code: Some(mismatch.code_item.attributes.join("\n")),
+            spec: Some(mismatch.spec_item.attributes.join("\n")),
+            spec_line: Some(mismatch.spec_item.line_number),
+            ..for_item(rules::ATTRIBUTE_MISMATCH, &mismatch.code_item, Some(mismatch.code_item.line_number),
+                differs(&mismatch.code_item, "attributes", "attributes differ from the spec", format!(" (code: {}, spec: {})",
+                    format_attributes(&mismatch.code_item.attributes), format_attributes(&mismatch.spec_item.attributes))))
+        });
+    }
+    for mismatch in &result.doc_mismatches {
+        findings.push(Finding {
+            code: Some(mismatch.code.clone()),
+            spec: Some(mismatch.spec.clone()),
+            spec_line: Some(mismatch.spec_item.line_number),
+            ..for_item(rules::DOC_MISMATCH, &mismatch.code_item, Some(mismatch.code_item.line_number),
+                differs(&mismatch.code_item, "doc comments", "doc comments differ from the spec", String::new()))
+        });
+    }
+    for deprecated in &result.deprecated_in_code {
+        findings.push(Finding {
+            code: Some(deprecated.code_item.attributes.join("\n")),
+            spec_line: Some(deprecated.spec_item.line_number),
+            ..for_item(rules::DEPRECATED_IN_CODE, &deprecated.code_item, Some(deprecated.code_item.line_number),
+                format!("{} is deprecated in the code but not in the spec; mark its spec block deprecated", format_item(&deprecated.code_item)))
+        });
+    }
+    for violation in &result.ordering_violations {
+        findings.push(Finding {
+            spec_line: Some(violation.spec_item.line_number),
+            ..for_item(rules::ORDERING_VIOLATION, &violation.code_item, Some(violation.code_item.line_number),
+                format!("{} is out of the spec's order: {}", format_item(&violation.code_item), spec_position(violation)))
+        });
+    }
+    for violation in &result.naming_violations {
+        findings.push(for_item(rules::NAMING_CONVENTION, &violation.code_item, Some(violation.code_item.line_number),
+            format!("{} doesn't follow the naming convention: expected {}", format_item(&violation.code_item), violation.expected)));
+    }
+    for mismatch in &result.discriminant_mismatches {
+        let changes: Vec<String> = mismatch.changes.iter()
+            .map(|(variant, spec, code)| format!("{} = {} (spec: {})", variant, code, spec))
+            .collect();
+        findings.push(Finding {
+            code: Some(mismatch.code_item.signature.clone()),
+            spec: Some(mismatch.spec_item.signature.clone()),
+            spec_line: Some(mismatch.spec_item.line_number),
+            ..for_item(rules::DISCRIMINANT_MISMATCH, &mismatch.code_item, Some(mismatch.code_item.line_number),
+                differs(&mismatch.code_item, "discriminants", "discriminants differ from the spec", format!(": {}", changes.join(", "))))
+        });
+    }
+    for mismatch in &result.value_mismatches {
+        findings.push(Finding {
+            code: Some(mismatch.code_item.signature.clone()),
+            spec: Some(mismatch.spec_item.signature.clone()),
+            spec_line: Some(mismatch.spec_item.line_number),
+            ..for_item(rules::VALUE_MISMATCH, &mismatch.code_item, Some(mismatch.code_item.line_number),
+                differs(&mismatch.code_item, "value", "value differs from the spec", format!(": {} (spec: {})", mismatch.code, mismatch.spec)))
+        });
+    }
+    for mismatch in &result.abi_mismatches {
+        findings.push(Finding {
+            code: Some(mismatch.code_item.signature.clone()),
+            spec: Some(mismatch.spec_item.signature.clone()),
+            spec_line: Some(mismatch.spec_item.line_number),
+            ..for_item(rules::ABI_MISMATCH, &mismatch.code_item, Some(mismatch.code_item.line_number),
+                differs(&mismatch.code_item, "ABI", "ABI differs from the spec", format!(": {}", mismatch.differences.join("; "))))
+        });
+    }
+    for violation in &result.custom_violations {
+        // Cached results are keyed by the registered rule set, so the rule is normally found
+        let rule = plugin::find(&violation.rule).map_or("custom", |rule| rule.code());
+        findings.push(Finding {
+            item: violation.item.clone(),
+            ..Finding::new(rule, file, violation.line, violation.message.clone())
+        });
+    }
+    for item in &result.forbidden_present {
+        findings.push(Finding {
+            code: Some(item.signature.clone()),
+            ..for_item(rules::FORBIDDEN_PRESENT, item, Some(item.line_number),
+                format!("{} is forbidden by the spec but still exists in the code", format_item(item)))
+        });
+    }
+    for rename in &result.probable_renames {
+        findings.push(Finding {
+            code: Some(rename.code_item.signature.clone()),
+            spec: Some(rename.spec_item.signature.clone()),
+            spec_line: Some(rename.spec_item.line_number),
+            ..for_item(rules::PROBABLE_RENAME, &rename.code_item, Some(rename.code_item.line_number),
+                format!("{} is not in the spec, but looks like a rename of {} (spec line {}); rename one to match the other",
+                    format_item(&rename.code_item), format_item(&rename.spec_item), rename.spec_item.line_number))
+        });
+    }
+    for item in &result.unresolved_annotations {
+        findings.push(Finding {
+            code: Some(item.signature.clone()),
+            ..for_item(rules::UNRESOLVED_ANNOTATION, item, Some(item.line_number),
+                format!("{} is annotated #[spec_checked] but the spec it names doesn't describe it", format_item(item)))
+        });
+    }
+    for duplicate in &result.duplicates {
+        let (item, first) = (&duplicate.duplicate, &duplicate.first);
+        findings.push(if duplicate.in_spec {
+            Finding {
+                spec: Some(item.signature.clone()),
+                spec_line: Some(item.line_number),
+                ..for_item(rules::DUPLICATE_DEFINITION, item, None,
+                    format!("{} is defined again in the spec, differently from spec line {}; keep one definition", format_item(item), first.line_number))
+            }
+        } else {
+            Finding {
+                code: Some(item.signature.clone()),
+                ..for_item(rules::DUPLICATE_DEFINITION, item, Some(item.line_number),
+                    format!("{} is defined again in the code, differently from line {}", format_item(item), first.line_number))
+            }
+        });
+    }
+    for error in &result.spec_parse_errors {
+        let message = match &error.skipped {
+            Some(item) => format!("spec block {} has `{}`, which is left unchecked: {}", error.block, item, error.message),
+            None => format!("spec block {} doesn't parse, so its items are unchecked: {}", error.block, error.message),
+        };
+        // The spec is all there is to point at
+        findings.push(Finding {
+            spec_line: Some(error.line),
+            spec_file: spec_file.map(Path::to_path_buf),
+            ..Finding::new(rules::SPEC_PARSE_FAILURE, file, None, message)
+        });
+    }
+    for finding in findings.iter_mut().filter(|finding| finding.spec_line.is_some()) {
+        finding.spec_path = spec_file.map(Path::to_path_buf);
+    }
+    findings
+}
+
+/// What a signature finding says: the differing parts, fields and variants, or the spec's signature
+/// The code's and the spec's signature as a mismatch shows and diffs them: without the function
+/// bodies when those weren't compared, which would otherwise look like a difference
+pub fn shown_signatures(mismatch: &SignatureMismatch) -> (String, String) {
+    let shown = |item: &RustItem| match mismatch.bodies_ignored {
+        true => rust_parser::without_body(&item.signature).unwrap_or_else(|| item.signature.clone()),
+        false => item.signature.clone(),
+    };
+    (shown(&mismatch.code_item), shown(&mismatch.spec_item))
+}
+
+fn mismatch_detail(mismatch: &SignatureMismatch) -> String {
+    let parts: Vec<String> = mismatch.differences.iter().cloned()
+        .chain(mismatch.field_diffs.iter().map(|diff| diff.to_string()))
+        .chain(mismatch.variant_diffs.iter().map(|diff| diff.to_string()))
+        .collect();
+    if parts.is_empty() { mismatch.spec_item.signature.clone() } else { parts.join("; ") }
+}
+
+/// `Name`, or `Owner::name` for members of a trait or type, after the inline modules (`math::Point`)
+pub fn item_path(item: &RustItem) -> String {
+    let mut path = item.module.clone();
+    path.extend(item.kind.owner().map(str::to_string));
+    path.push(item.name.clone());
+    path.join("::")
+}
+
+pub fn format_item(item: &RustItem) -> String {
+    let path = item_path(item);
     match &item.kind {
-        ItemKind::Struct => format!("struct {}", item.name),
-        ItemKind::Enum => format!("enum {}", item.name),
-        ItemKind::Trait => format!("trait {}", item.name),
-        ItemKind::TraitMethod { trait_name } => format!("{}::{}", trait_name, item.name),
-        ItemKind::Function => format!("fn {}", item.name),
+        ItemKind::Struct => format!("struct {}", path),
+        ItemKind::Enum => format!("enum {}", path),
+        ItemKind::Union => format!("union {}", path),
+        ItemKind::Trait => format!("trait {}", path),
+        ItemKind::TraitMethod { .. } => path,
+        ItemKind::TraitAssocType { .. } => format!("type {}", path),
+        ItemKind::TraitConst { .. } => format!("const {}", path),
+        ItemKind::Method { .. } | ItemKind::Function => format!("fn {}", path),
+        ItemKind::Const => format!("const {}", path),
+        ItemKind::Static => format!("static {}", path),
+        ItemKind::Use => format!("use {}", path),
+        ItemKind::Macro => format!("macro {}!", path),
+        ItemKind::TraitImpl => format!("impl {}", path),
+    }
+}
+
+/// Where the spec puts an item the code has out of order
+fn spec_position(violation: &OrderingViolation) -> String {
+    match &violation.after {
+        Some(after) => format!("the spec puts it after {}", format_item(after)),
+        None => "the spec puts it first".to_string(),
     }
 }
 
@@ -106,3 +898,98 @@ fn format_attributes(attrs: &[String]) -> String {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_breakdown() {
+        let item = |name: &str, kind| RustItem::new(name.to_string(), kind, String::new(), Default::default(), Vec::new(), 1);
+        let method = || ItemKind::TraitMethod { trait_name: "Shape".to_string() };
+        let math = ComparisonResult {
+            missing_in_spec: vec![item("Mesh", ItemKind::Struct), item("area", method()), item("Curve", ItemKind::Struct)],
+            signature_mismatches: vec![SignatureMismatch {
+                code_item: item("volume", method()),
+                spec_item: item("volume", method()),
+                differences: Vec::new(),
+                field_diffs: Vec::new(),
+                variant_diffs: Vec::new(),
+                bodies_ignored: false,
+            }],
+            ..Default::default()
+        };
+        let lib = ComparisonResult { missing_in_spec: vec![item("Config", ItemKind::Struct)], ..Default::default() };
+
+        let mut tally = Tally::default();
+        tally.add(Path::new("src/lib.rs"), finding_kinds(&lib));
+        tally.add(Path::new("src/math.rs"), finding_kinds(&math));
+        tally.add(Path::new("src/net.rs"), [(rules::MISSING_SPEC_FILE, None)]);
+        let mut out = Vec::new();
+        tally.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            "Findings by rule and item kind:\n",
+            "  SC001 missing-in-spec: 3 struct, 1 trait method\n",
+            "  SC003 signature-mismatch: 1 trait method\n",
+            "  SC005 missing-spec-file: 1\n",
+            "Findings by file:\n",
+            "  src/math.rs: 4\n",
+            "  src/lib.rs: 1\n",
+            "  src/net.rs: 1\n",
+        ));
+    }
+
+    #[test]
+    fn test_spec_side_findings_point_at_the_spec_line() {
+        let code = crate::parse_rust_items("pub struct Mesh;\n\npub fn lerp(a: f32, b: f32, t: f32) -> f32 { a }\n", Default::default()).unwrap();
+        let spec = crate::extract_spec_items("# Mesh\n\n```rust\npub struct Mesh;\n```\n\n```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\npub fn ease(t: f32) -> f32 {}\n```\n", Default::default());
+        let result = crate::compare(code, spec, &[]);
+        let findings = findings_for(Path::new("src/mesh.rs"), Some(Path::new("spec/mesh.md")), &result, None);
+        let lines: Vec<(&str, Option<usize>, Option<usize>)> = findings.iter().map(|finding| (finding.rule, finding.line, finding.spec_line)).collect();
+        assert_eq!(lines, [(rules::MISSING_IN_CODE, None, Some(9)), (rules::SIGNATURE_MISMATCH, Some(3), Some(8))]);
+        assert!(findings.iter().all(|finding| finding.spec_path.as_deref() == Some(Path::new("spec/mesh.md"))));
+
+        let json = finding_json(&findings[1]);
+        assert_eq!(json["spec_file"].as_str(), Some("spec/mesh.md"));
+        assert_eq!(json["spec_line"].as_integer(), Some(8));
+    }
+
+    #[test]
+    fn test_uncompared_bodies_are_left_out_of_diffs() {
+        let code = crate::parse_rust_items("pub fn add(a: i64, b: i32) -> i64 { a + b as i64 }\n", Default::default()).unwrap();
+        let spec = crate::extract_spec_items("```rust\npub fn add(a: i64, b: i64) -> i64 {}\n```\n", Default::default());
+        let mut result = crate::compare(code, spec, &[]);
+        crate::comparator::ignore_function_bodies(&mut result);
+        let findings = findings_for(Path::new("src/math.rs"), Some(Path::new("spec/math.md")), &result, None);
+        let (code, spec) = (findings[0].code.as_deref().unwrap(), findings[0].spec.as_deref().unwrap());
+        assert_eq!(word_diff::render(&word_diff::diff(spec, code)), "pub fn add (a : i64 , b : [-i64-]{+i32+}) -> i64");
+        assert_eq!(shown_signatures(&result.signature_mismatches[0]), (code.to_string(), spec.to_string()));
+    }
+
+    #[test]
+    fn test_appended_logs_rotate_by_size() {
+        let dir = std::env::temp_dir().join(format!("spec-check-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("spec-check.log");
+        let options = LogOptions { append: true, max_size: Some(1000), keep: 2 };
+        for _ in 0..2 {
+            let mut report = LogReport::open(&log, &options).unwrap();
+            report.report_missing_spec(Path::new("src/lib.rs")).unwrap();
+        }
+        let content = fs::read_to_string(&log).unwrap();
+        assert_eq!(content.matches("======== spec-check run at ").count(), 2, "{}", content);
+
+        // Past the size, each run starts a new log and the oldest falls off
+        for _ in 0..3 {
+            fs::write(&log, "x".repeat(1000)).unwrap();
+            LogReport::open(&log, &options).unwrap();
+        }
+        assert!(rotated(&log, 1).exists() && rotated(&log, 2).exists() && !rotated(&log, 3).exists());
+        assert_eq!(rotated(&log, 1).file_name().unwrap(), "spec-check.log.1");
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_timestamp(1_709_993_100), "2024-03-09T14:05:00Z");
+        assert_eq!(utc_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+    }
+}
@@ -0,0 +1,151 @@
+//! Requirement IDs (`REQ-ANIM-012`) and the traceability matrix between them, the spec items
+//! that describe them and the code items that implement them.
+//!
+//! A spec tags items with the IDs in the paragraph right before their block. Code items refer
+//! to IDs in their doc comments (`#[doc = "REQ-ANIM-012"]`) or with
+//! `#[requirement("REQ-ANIM-012")]` (see the `spec-check-macros` crate), and implement the IDs
+//! of the spec items they match.
+
+use crate::reporter::format_item;
+use crate::rust_parser::{self, RustItem};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use syn::punctuated::Punctuated;
+
+/// Name of the requirement attribute, which is never compared as an attribute itself
+pub const ATTRIBUTE: &str = "requirement";
+
+const PREFIX: &str = "REQ-";
+
+/// The requirement IDs in some text, in order and without repeats: words of `REQ-` and
+/// uppercase letters, digits and dashes
+pub fn ids(text: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')) {
+        let word = word.trim_end_matches('-');
+        let Some(rest) = word.strip_prefix(PREFIX) else { continue };
+        if !rest.is_empty() && rest.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-') && !ids.iter().any(|id| id == word) {
+            ids.push(word.to_string());
+        }
+    }
+    ids
+}
+
+/// The IDs an item is tagged with or refers to, from its block's prose, its doc comments and
+/// its `#[requirement(...)]` attributes
+pub fn of_item(item: &RustItem) -> BTreeSet<String> {
+    let mut ids: BTreeSet<String> = item.requirements.iter().cloned().collect();
+    ids.extend(self::ids(&rust_parser::doc_text(&item.attributes)));
+    for attribute in item.attributes.iter().filter(|attribute| attribute.contains(ATTRIBUTE)) {
+        let Ok(parsed) = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attribute) else { continue };
+        for attr in parsed.iter().filter(|attr| attr.path().segments.last().is_some_and(|segment| segment.ident == ATTRIBUTE)) {
+            if let Ok(values) = attr.parse_args_with(Punctuated::<syn::LitStr, syn::Token![,]>::parse_terminated) {
+                ids.extend(values.iter().map(syn::LitStr::value));
+            }
+        }
+    }
+    ids
+}
+
+/// An item where a requirement is described or implemented
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Site {
+    pub file: PathBuf,
+    pub line: usize,
+    /// As reports name it, e.g. `fn ease`
+    pub item: String,
+}
+
+impl Site {
+    fn of(file: &Path, item: &RustItem) -> Self {
+        Self { file: file.to_path_buf(), line: item.line_number, item: format_item(item) }
+    }
+}
+
+/// The spec items that describe a requirement and the code items that implement it
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    pub spec: BTreeSet<Site>,
+    pub code: BTreeSet<Site>,
+}
+
+/// Every requirement of a tree, by ID
+#[derive(Debug, Clone, Default)]
+pub struct Matrix {
+    pub requirements: BTreeMap<String, Trace>,
+}
+
+impl Matrix {
+    /// The items of a spec file
+    pub fn add_spec(&mut self, file: &Path, items: &[RustItem]) {
+        for item in items {
+            for id in of_item(item) {
+                self.requirements.entry(id).or_default().spec.insert(Site::of(file, item));
+            }
+        }
+    }
+
+    /// The items of a source file, which implement what they refer to and the requirements of
+    /// the items of `spec_items`, its specs' items, that they match
+    pub fn add_code(&mut self, file: &Path, items: &[RustItem], spec_items: &[RustItem]) {
+        for item in items {
+            let described = spec_items.iter().filter(|spec_item| *spec_item == item).flat_map(of_item);
+            for id in of_item(item).into_iter().chain(described) {
+                self.requirements.entry(id).or_default().code.insert(Site::of(file, item));
+            }
+        }
+    }
+
+    /// Requirements the spec describes that nothing in the code implements
+    pub fn orphaned(&self) -> impl Iterator<Item = (&String, &Trace)> + '_ {
+        self.requirements.iter().filter(|(_, trace)| !trace.spec.is_empty() && trace.code.is_empty())
+    }
+
+    /// Requirements the code refers to that no spec describes
+    pub fn unknown(&self) -> impl Iterator<Item = (&String, &Trace)> + '_ {
+        self.requirements.iter().filter(|(_, trace)| trace.spec.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rust_parser::ItemKind;
+
+    #[test]
+    fn test_requirements_trace_from_spec_to_code() {
+        assert_eq!(ids("Covers REQ-ANIM-012 and (REQ-ANIM-013), not REQ- or req-1 or REQ-ANIM-012."), ["REQ-ANIM-012", "REQ-ANIM-013"]);
+
+        let item = |name: &str, attributes: &[&str]| {
+            RustItem::new(name.to_string(), ItemKind::Function, String::new(), Default::default(), attributes.iter().map(|a| a.to_string()).collect(), 1)
+        };
+        let spec = vec![
+            RustItem { requirements: vec!["REQ-ANIM-012".to_string()], ..item("ease", &[]) },
+            RustItem { requirements: vec!["REQ-ANIM-013".to_string()], ..item("blend", &[]) },
+        ];
+        let code = vec![
+            item("ease", &[]),
+            item("lerp", &[r#"# [doc = " Interpolates, for REQ-ANIM-013"]"#]),
+            item("clamp", &[r#"#[spec_check_macros::requirement("REQ-ANIM-099")]"#]),
+        ];
+        let mut matrix = Matrix::default();
+        matrix.add_spec(Path::new("spec/anim.md"), &spec);
+        matrix.add_code(Path::new("src/anim.rs"), &code, &spec);
+
+        let implementers = |id: &str| matrix.requirements[id].code.iter().map(|site| site.item.clone()).collect::<Vec<_>>();
+        assert_eq!(implementers("REQ-ANIM-012"), ["fn ease"]);
+        assert_eq!(implementers("REQ-ANIM-013"), ["fn lerp"]);
+        assert_eq!(matrix.orphaned().count(), 0);
+        assert_eq!(matrix.unknown().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), ["REQ-ANIM-099"]);
+    }
+    #[test]
+    fn test_readme_example_tags_its_item() {
+        let readme = include_str!("../README.md");
+        let section = &readme[readme.find("## Requirement Traceability").unwrap()..];
+        let example = section.split("````").nth(1).unwrap().strip_prefix("markdown\n").unwrap();
+        let (items, errors) = crate::parse_spec(example, Default::default());
+        assert!(errors.is_empty());
+        let tagged: Vec<(&str, &[String])> = items.iter().map(|item| (item.name.as_str(), item.requirements.as_slice())).collect();
+        assert_eq!(tagged, [("ease", &["REQ-ANIM-012".to_string()][..])]);
+    }
+}
@@ -0,0 +1,280 @@
+//! Specs written in reStructuredText, as Sphinx builds them. A Rust block is a `code-block`
+//! (or `code`, `sourcecode`) directive for `rust`, or a `literalinclude` of a whole `.rs` file,
+//! with the tags markdown fences take after `rust` as its `:class:`. Directives are
+//! `.. spec-check: ...` comments.
+
+use crate::markdown_parser::{self, Line, RustBlock, Section};
+use crate::rust_parser::Stage;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The lines after any front matter, with trailing whitespace dropped as well
+fn lines(rst: &str) -> Vec<Line<'_>> {
+    markdown_parser::body_lines(rst).map(|line| Line { text: line.text.trim_end(), ..line }).collect()
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Whether a line is a section adornment, a run of one punctuation character
+fn is_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+    let Some(first) = chars.next() else { return false };
+    line.len() >= 2 && first.is_ascii_punctuation() && chars.all(|c| c == first)
+}
+
+/// The heading starting at `lines[index]`: its title, the adornment style that sets its level,
+/// and how many lines it takes
+fn heading(lines: &[Line], index: usize) -> Option<(String, (char, bool), usize)> {
+    let text = |offset: usize| lines.get(index + offset).map_or("", |line| line.text);
+    let title_of = |title: &str| title.trim().replace('`', "");
+    let (title, underline, overline) = if is_adornment(text(0)) && !text(1).trim().is_empty() && text(2) == text(0) {
+        (text(1), text(2), true)
+    } else {
+        (text(0), text(1), false)
+    };
+    let starts_line = index == 0 || lines[index - 1].text.is_empty();
+    let plain = !overline && indent(title) == 0 && !is_adornment(title);
+    if !starts_line || !(overline || plain) || title.trim().is_empty() || !is_adornment(underline) || underline.len() < title.trim().len() {
+        return None;
+    }
+    let style = (underline.chars().next().expect("adornment is not empty"), overline);
+    Some((title_of(title), style, if overline { 3 } else { 2 }))
+}
+
+/// The name and argument of an explicit markup line, `("code-block", "rust")` for
+/// `.. code-block:: rust`
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let (name, argument) = line.trim_start().strip_prefix(".. ")?.split_once("::")?;
+    let name = name.trim();
+    (!name.is_empty() && !name.contains(' ')).then(|| (name, argument.trim()))
+}
+
+/// The body of a `.. spec-check: ...` comment
+fn spec_check_comment(line: &str) -> Option<&str> {
+    Some(line.trim_start().strip_prefix("..")?.trim().strip_prefix("spec-check:")?.trim())
+}
+
+/// The `:class:` values spec-check reads as tags; Sphinx passes others on to the HTML
+fn class_tags(classes: &str) -> Vec<&str> {
+    const TAGS: [&str; 6] = ["planned", "stable", "deprecated", "forbidden", "spec", "example"];
+    classes.split_whitespace()
+        .filter(|tag| TAGS.contains(tag) || ["removal=", "feature=", "since=", "until="].iter().any(|prefix| tag.starts_with(prefix)))
+        .collect()
+}
+
+/// The normative Rust blocks of a reStructuredText spec
+pub fn rust_blocks(rst: &str) -> Vec<RustBlock<'_>> {
+    blocks(rst, false)
+}
+
+/// The blocks with the `example` class instead of the normative ones
+pub fn example_blocks(rst: &str) -> Vec<RustBlock<'_>> {
+    blocks(rst, true)
+}
+
+fn blocks(rst: &str, examples: bool) -> Vec<RustBlock<'_>> {
+    let lines = lines(rst);
+    let mut blocks = Vec::new();
+    // The last paragraph, if only comments came after it
+    let mut prose: Option<Range<usize>> = None;
+    let mut in_paragraph = false;
+    let mut directive_stage: Option<Stage> = None;
+    let (mut skip_next, mut off) = (false, false);
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        let text = line.text;
+        if text.is_empty() {
+            in_paragraph = false;
+            index += 1;
+            continue;
+        }
+        if let Some((_, _, taken)) = heading(&lines, index) {
+            prose = None;
+            in_paragraph = false;
+            index += taken;
+            continue;
+        }
+        let depth = indent(text);
+        // The end of the indented lines after the markup line
+        let body_end = |from: usize| (from..lines.len()).find(|&i| !lines[i].text.is_empty() && indent(lines[i].text) <= depth).unwrap_or(lines.len());
+
+        if let Some(words) = spec_check_comment(text) {
+            match words {
+                "ignore-next-block" => skip_next = true,
+                "off" => off = true,
+                "on" => off = false,
+                words => {
+                    if let Some(stage) = markdown_parser::stage_tags(words.split_whitespace(), "").flatten() {
+                        directive_stage = Some(stage);
+                    }
+                }
+            }
+            index += 1;
+            continue;
+        }
+        let Some((name, argument)) = directive(text) else {
+            if text.trim_start().starts_with("..") {
+                // A comment, target or footnote, with whatever is indented under it
+                index = body_end(index + 1);
+            } else if depth > 0 || text.starts_with(['*', '-', '+']) || text.starts_with("#.") {
+                // Quotes, literal blocks and lists aren't about what comes after them
+                prose = None;
+                index += 1;
+            } else {
+                let end = line.start + text.len();
+                match prose.as_mut().filter(|_| in_paragraph) {
+                    Some(range) => range.end = end,
+                    None => prose = Some(line.start..end),
+                }
+                in_paragraph = true;
+                index += 1;
+            }
+            continue;
+        };
+
+        // The directive's options, then its content
+        let end = body_end(index + 1);
+        let options_end = (index + 1..end).find(|&i| !lines[i].text.trim_start().starts_with(':')).unwrap_or(end);
+        let options = &lines[index + 1..options_end];
+        let option = |option: &str| options.iter().find_map(|line| {
+            let value = line.text.trim_start().strip_prefix(':')?.strip_prefix(option)?.strip_prefix(':')?;
+            Some(value.trim())
+        });
+        let block_prose = prose.take().map(|range| rst[range].trim_end());
+        in_paragraph = false;
+        index = end;
+
+        let include = match name {
+            "code-block" | "code" | "sourcecode" if argument == "rust" => None,
+            "code-block" | "code" | "sourcecode" => continue,
+            "literalinclude" => {
+                let rust = option("language").map_or(argument.ends_with(".rs"), |language| language == "rust");
+                // Only a whole file is checked
+                let selects = ["lines", "start-after", "end-before", "start-at", "end-at", "pyobject", "diff"].iter().any(|name| option(name).is_some());
+                if !rust || selects || argument.is_empty() { continue }
+                Some(argument)
+            }
+            _ => {
+                // Other directives' content is more of the document
+                index = options_end;
+                continue;
+            }
+        };
+        let tags = class_tags(option("class").unwrap_or_default());
+        let Some(stage) = markdown_parser::block_stage(&tags, examples, &mut directive_stage) else { continue };
+        if off || std::mem::take(&mut skip_next) {
+            continue;
+        }
+
+        let mut content: Vec<&Line> = lines[options_end..end].iter().skip_while(|line| line.text.is_empty()).collect();
+        while content.last().is_some_and(|line| line.text.is_empty()) {
+            content.pop();
+        }
+        let (code, range, first_line) = match (include, content.first()) {
+            (Some(_), _) => (String::new(), line.start..line.end, line.number),
+            (None, Some(first)) => {
+                let strip = content.iter().filter(|line| !line.text.is_empty()).map(|line| indent(line.text)).min().unwrap_or(0);
+                let code = content.iter().map(|line| format!("{}\n", line.text.get(strip..).unwrap_or_default())).collect();
+                (code, first.start..content[content.len() - 1].end, first.number)
+            }
+            (None, None) => continue,
+        };
+        let features = tags.iter().filter_map(|tag| tag.strip_prefix("feature=")).map(str::to_string).collect();
+        blocks.push(RustBlock {
+            code: Cow::Owned(code),
+            range,
+            line: first_line,
+            stage,
+            features,
+            prose: block_prose,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            include,
+            unknown_tag: None,
+        });
+    }
+    blocks
+}
+
+/// The sections of a reStructuredText spec, as [`markdown_parser::sections`] has them for
+/// markdown. A heading's level goes by the order its adornment style is first used in.
+pub fn sections(rst: &str) -> Vec<Section> {
+    let lines = lines(rst);
+    let mut styles: Vec<(char, bool)> = Vec::new();
+    let mut sections: Vec<Section> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let Some((heading, style, taken)) = heading(&lines, index) else {
+            index += 1;
+            continue;
+        };
+        let level = match styles.iter().position(|&known| known == style) {
+            Some(position) => position + 1,
+            None => {
+                styles.push(style);
+                styles.len()
+            }
+        };
+        let number = lines[index].number;
+        for section in sections.iter_mut().filter(|s| s.lines.end == usize::MAX && s.level >= level) {
+            section.lines.end = number;
+        }
+        sections.push(Section { heading, level, lines: number..usize::MAX });
+        index += taken;
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_blocks_and_includes_are_rust_blocks() {
+        let rst = concat!(
+            "=========\nAnimation\n=========\n\nEasing\n======\n\nEases ``t`` (REQ-ANIM-012).\n\n",
+            ".. code-block:: rust\n   :linenos:\n\n   pub fn ease(t: f32) -> f32 {}\n\n   pub fn clamp() {}\n\n",
+            ".. code-block:: python\n\n   def ease(): pass\n\n",
+            ".. spec-check: deprecated removal=0.5.0\n.. code:: rust\n\n   pub fn draw() {}\n\n",
+            ".. note::\n\n   .. code-block:: rust\n      :class: planned wide\n\n      pub fn blend() {}\n\n",
+            ".. literalinclude:: ../src/mix.rs\n   :class: planned\n\n",
+            ".. literalinclude:: ../src/mix.rs\n   :lines: 1-3\n\n",
+            ".. code-block:: rust\n   :class: example\n\n   assert_eq!(ease(0.0), 0.0);\n\n",
+            ".. spec-check: ignore-next-block\n.. code-block:: rust\n\n   fn sketch() {}\n\n",
+            "..\n   .. code-block:: rust\n\n      fn commented() {}\n\n",
+            "Blending\n========\n\nModes\n-----\n",
+        );
+        let blocks = rust_blocks(rst);
+        let summary: Vec<(&str, usize, Stage, Option<&str>)> = blocks.iter().map(|block| (block.code.as_ref(), block.line, block.stage.clone(), block.include)).collect();
+        assert_eq!(summary, [
+            ("pub fn ease(t: f32) -> f32 {}\n\npub fn clamp() {}\n", 13, Stage::Stable, None),
+            ("pub fn draw() {}\n", 24, Stage::Deprecated { removal: Some("0.5.0".to_string()) }, None),
+            ("pub fn blend() {}\n", 31, Stage::Planned, None),
+            ("", 33, Stage::Planned, Some("../src/mix.rs")),
+        ]);
+        assert_eq!(blocks[0].prose, Some("Eases ``t`` (REQ-ANIM-012)."));
+        assert_eq!(&rst[blocks[0].range.clone()], "   pub fn ease(t: f32) -> f32 {}\n\n   pub fn clamp() {}\n");
+        assert_eq!(example_blocks(rst).iter().map(|block| block.line).collect::<Vec<_>>(), [42]);
+
+        let sections: Vec<(String, usize, Range<usize>)> = sections(rst).into_iter().map(|s| (s.heading, s.level, s.lines)).collect();
+        assert_eq!(sections, [
+            ("Animation".to_string(), 1, 1..usize::MAX),
+            ("Easing".to_string(), 2, 5..54),
+            ("Blending".to_string(), 2, 54..usize::MAX),
+            ("Modes".to_string(), 3, 57..usize::MAX),
+        ]);
+    }
+
+    #[test]
+    fn test_front_matter_is_not_part_of_the_document() {
+        // Without skipping it, the fenced line would read as a title between adornments
+        let rst = "+++\nvisibility = \"public\"\n+++\n\nEasing\n======\n\n.. code-block:: rust\n\n   pub fn ease(t: f32) -> f32 {}\n";
+        let blocks = rust_blocks(rst);
+        assert_eq!(blocks.iter().map(|block| (block.code.as_ref(), block.line)).collect::<Vec<_>>(), [("pub fn ease(t: f32) -> f32 {}\n", 10)]);
+        let sections: Vec<(String, usize, Range<usize>)> = sections(rst).into_iter().map(|s| (s.heading, s.level, s.lines)).collect();
+        assert_eq!(sections, [("Easing".to_string(), 1, 5..usize::MAX)]);
+    }
+}
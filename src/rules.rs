@@ -0,0 +1,452 @@
+/// Structured metadata for every kind of finding spec-check can report.
+/// This is the single source for rule codes, names, and `explain` output.
+#[derive(Debug)]
+pub struct Rule {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub rationale: &'static str,
+    pub violating: Example,
+    pub conforming: Example,
+    pub config_keys: &'static [&'static str],
+}
+
+/// A code/spec pair illustrating a rule
+#[derive(Debug)]
+pub struct Example {
+    pub code: &'static str,
+    pub spec: &'static str,
+}
+
+pub const MISSING_IN_SPEC: &str = "SC001";
+pub const MISSING_IN_CODE: &str = "SC002";
+pub const SIGNATURE_MISMATCH: &str = "SC003";
+pub const ATTRIBUTE_MISMATCH: &str = "SC004";
+pub const MISSING_SPEC_FILE: &str = "SC005";
+pub const PARSE_FAILURE: &str = "SC006";
+pub const UNRESOLVED_ANNOTATION: &str = "SC007";
+pub const WRONG_CRATE: &str = "SC008";
+pub const DISCRIMINANT_MISMATCH: &str = "SC009";
+pub const FORBIDDEN_PRESENT: &str = "SC010";
+pub const PROBABLE_RENAME: &str = "SC011";
+pub const DUPLICATE_DEFINITION: &str = "SC012";
+pub const SPEC_PARSE_FAILURE: &str = "SC013";
+pub const DOC_MISMATCH: &str = "SC014";
+pub const ORDERING_VIOLATION: &str = "SC015";
+pub const DEPRECATED_IN_CODE: &str = "SC016";
+pub const VALUE_MISMATCH: &str = "SC017";
+pub const ABI_MISMATCH: &str = "SC018";
+pub const ORPHANED_SPEC: &str = "SC019";
+pub const NAMING_CONVENTION: &str = "SC020";
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        code: MISSING_IN_SPEC,
+        name: "missing-in-spec",
+        summary: "An item exists in the code but is not described in the spec.",
+        rationale: "The spec is the documented contract of the module. A public item that \
+            the spec does not mention is undocumented API surface: reviewers cannot tell \
+            whether it was added on purpose, and consumers have nothing to rely on.",
+        violating: Example {
+            code: "pub struct Color;\npub struct Mesh;",
+            spec: "pub struct Color;",
+        },
+        conforming: Example {
+            code: "pub struct Color;\npub struct Mesh;",
+            spec: "pub struct Color;\npub struct Mesh;",
+        },
+        config_keys: &["check-private", "src-dir"],
+    },
+    Rule {
+        code: MISSING_IN_CODE,
+        name: "missing-in-code",
+        summary: "An item is described in the spec but does not exist in the code.",
+        rationale: "Spec items are promises. An item that only exists in the spec was either \
+            removed or renamed in the code without updating the contract, or has not been \
+            implemented yet.",
+        violating: Example {
+            code: "pub trait MathHash {\n    fn math_hash(&self) -> u64;\n}",
+            spec: "pub trait MathHash {\n    fn math_hash(&self) -> u64;\n}\n\npub trait EpsilonMatch {\n    fn epsilon_match(&self, other: &Self, epsilon: f32) -> bool;\n}",
+        },
+        conforming: Example {
+            code: "pub trait MathHash {\n    fn math_hash(&self) -> u64;\n}",
+            spec: "pub trait MathHash {\n    fn math_hash(&self) -> u64;\n}",
+        },
+        config_keys: &["check-private", "spec-dir"],
+    },
+    Rule {
+        code: SIGNATURE_MISMATCH,
+        name: "signature-mismatch",
+        summary: "An item exists on both sides but its signature differs.",
+        rationale: "Items are matched by name and kind, then compared token by token \
+            (whitespace and formatting are ignored). Any other difference means callers \
+            written against the spec would not compile against the code.",
+        violating: Example {
+            code: "pub fn sample(resolution: usize) -> Vec<Vec3>;",
+            spec: "pub fn sample(resolution: u32) -> Vec<Vec3>;",
+        },
+        conforming: Example {
+            code: "pub fn sample(resolution: usize) -> Vec<Vec3>;",
+            spec: "pub fn sample(resolution : usize) -> Vec < Vec3 >;",
+        },
+        config_keys: &["check-private"],
+    },
+    Rule {
+        code: ATTRIBUTE_MISMATCH,
+        name: "attribute-mismatch",
+        summary: "An item's attributes differ between code and spec.",
+        rationale: "Attributes such as derives change the API of a type (a missing \
+            `#[derive(Clone)]` removes `.clone()`). Attributes listed in \
+            `ignored-attributes` (doc comments by default) are not compared.",
+        violating: Example {
+            code: "#[derive(Debug)]\npub enum MathAnimationError {}",
+            spec: "#[derive(Debug, thiserror::Error)]\npub enum MathAnimationError {}",
+        },
+        conforming: Example {
+            code: "/// Engine errors\n#[derive(Debug)]\npub enum MathAnimationError {}",
+            spec: "#[derive(Debug)]\npub enum MathAnimationError {}",
+        },
+        config_keys: &["ignored-attributes"],
+    },
+    Rule {
+        code: MISSING_SPEC_FILE,
+        name: "missing-spec-file",
+        summary: "A source file has no corresponding spec file.",
+        rationale: "Every source file under the source directory is expected to have a \
+            spec at the same relative path with a `.md` extension \
+            (`src/math.rs` -> `spec/math.md`). Without one nothing in the file is checked.",
+        violating: Example {
+            code: "src/math.rs",
+            spec: "(no spec/math.md)",
+        },
+        conforming: Example {
+            code: "src/math.rs",
+            spec: "spec/math.md",
+        },
+        config_keys: &["src-dir", "spec-dir"],
+    },
+    Rule {
+        code: PARSE_FAILURE,
+        name: "parse-failure",
+        summary: "A source file is not valid Rust syntax and could not be checked.",
+        rationale: "Items are extracted with a full Rust parser. A file that does not parse \
+            cannot be compared, so the run exits with a distinct code rather than \
+            reporting it as passing.",
+        violating: Example {
+            code: "pub fn (",
+            spec: "pub fn broken();",
+        },
+        conforming: Example {
+            code: "pub fn fixed() {}",
+            spec: "pub fn fixed() {}",
+        },
+        config_keys: &[],
+    },
+    Rule {
+        code: UNRESOLVED_ANNOTATION,
+        name: "unresolved-annotation",
+        summary: "An item annotated with #[spec_checked] has no matching item in the spec it names.",
+        rationale: "The annotation moves an item's contract to another spec file or section. \
+            If nothing there describes the item, the annotation points at the wrong place or \
+            the spec was moved, and the item would otherwise go unchecked.",
+        violating: Example {
+            code: "#[spec_checked(file = \"spec/math.md\", section = \"Geometry\")]\npub struct Curve;",
+            spec: "## Color\n\n```rust\npub struct Curve;\n```",
+        },
+        conforming: Example {
+            code: "#[spec_checked(file = \"spec/math.md\", section = \"Geometry\")]\npub struct Curve;",
+            spec: "## Geometry\n\n```rust\npub struct Curve;\n```",
+        },
+        config_keys: &["spec-annotations"],
+    },
+    Rule {
+        code: WRONG_CRATE,
+        name: "wrong-crate",
+        summary: "A crate's spec describes an item that another crate of the workspace defines.",
+        rationale: "In a `workspace` run, an item missing from a crate's code but defined in a \
+            sibling crate was most likely moved between crates, or specced under the wrong one. \
+            The spec belongs with the crate that owns the item.",
+        violating: Example {
+            code: "// crates/render/src/lib.rs\npub struct Frame;",
+            spec: "// crates/geometry/spec/lib.md\npub struct Frame;",
+        },
+        conforming: Example {
+            code: "// crates/render/src/lib.rs\npub struct Frame;",
+            spec: "// crates/render/spec/lib.md\npub struct Frame;",
+        },
+        config_keys: &["spec-dir"],
+    },
+    Rule {
+        code: DISCRIMINANT_MISMATCH,
+        name: "discriminant-mismatch",
+        summary: "An enum's discriminant values differ between code and spec.",
+        rationale: "Explicit discriminants and `#[repr]` enums are wire and FFI contracts: \
+            a changed value silently breaks every peer that still sends or stores the old \
+            one, even if the code compiles. Such enums are compared by the value of every \
+            variant, implicit ones included, and reported here instead of as a signature mismatch.",
+        violating: Example {
+            code: "#[repr(u8)]\npub enum Status { Ok = 0, Retry, Err = 2 }",
+            spec: "#[repr(u8)]\npub enum Status { Ok = 0, Err = 1, Retry = 2 }",
+        },
+        conforming: Example {
+            code: "#[repr(u8)]\npub enum Status { Ok = 0, Err = 1, Retry = 2 }",
+            spec: "#[repr(u8)]\npub enum Status { Ok = 0, Err = 1, Retry }",
+        },
+        config_keys: &[],
+    },
+    Rule {
+        code: FORBIDDEN_PRESENT,
+        name: "forbidden-present",
+        summary: "An item the spec forbids still exists in the code.",
+        rationale: "A ```rust,forbidden block lists items that must not exist, such as an API \
+            being phased out or one that must never be public. The spec tracks the removal \
+            until the item is gone; items are matched by name and kind, whatever their signature.",
+        violating: Example {
+            code: "pub fn draw() {}
+pub fn unsafe_raw_access() {}",
+            spec: "pub fn draw() {}
+
+// ```rust,forbidden
+pub fn unsafe_raw_access() {}",
+        },
+        conforming: Example {
+            code: "pub fn draw() {}",
+            spec: "pub fn draw() {}
+
+// ```rust,forbidden
+pub fn unsafe_raw_access() {}",
+        },
+        config_keys: &[],
+    },
+    Rule {
+        code: PROBABLE_RENAME,
+        name: "probable-rename",
+        summary: "An item missing from the spec looks like a spec item missing from the code, renamed.",
+        rationale: "Renaming an item on one side only would otherwise show up as two unrelated \
+            findings. When a code item and a spec item of the same kind and module are nearly \
+            identical once their names are left out, they're reported as one rename instead.",
+        violating: Example {
+            code: "pub fn lerp_clamped(a: f32, b: f32, t: f32) -> f32 {}",
+            spec: "pub fn lerp(a: f32, b: f32, t: f32) -> f32 {}",
+        },
+        conforming: Example {
+            code: "pub fn lerp_clamped(a: f32, b: f32, t: f32) -> f32 {}",
+            spec: "pub fn lerp_clamped(a: f32, b: f32, t: f32) -> f32 {}",
+        },
+        config_keys: &[],
+    },
+    Rule {
+        code: DUPLICATE_DEFINITION,
+        name: "duplicate-definition",
+        summary: "An item is defined more than once, with different signatures, on one side.",
+        rationale: "When two spec blocks, or two spec files, describe the same item differently, \
+            only one of them can be compared and the other goes unchecked. The same holds for \
+            code that defines an item twice. Definitions behind different `#[cfg]`s are \
+            alternatives rather than duplicates and aren't reported.",
+        violating: Example {
+            code: "pub fn scale(v: Vec3, by: f32) -> Vec3 {}",
+            spec: "pub fn scale(v: Vec3, by: f32) -> Vec3 {}
+
+// ```rust
+pub fn scale(v: Vec3, by: f64) -> Vec3 {}",
+        },
+        conforming: Example {
+            code: "pub fn scale(v: Vec3, by: f32) -> Vec3 {}",
+            spec: "pub fn scale(v: Vec3, by: f32) -> Vec3 {}",
+        },
+        config_keys: &[],
+    },
+    Rule {
+        code: SPEC_PARSE_FAILURE,
+        name: "spec-parse-failure",
+        summary: "A ```rust block of the spec is not valid Rust.",
+        rationale: "A block that doesn't parse declares no items, so whatever it was meant to \
+            describe goes unchecked without this. Fix the block, tag it ```rust,ignore or \
+            ```rust,example if it's not meant to be checked, or make the rule a warning with \
+            `severity` where partial snippets are intentional.",
+        violating: Example {
+            code: "pub fn scale(v: Vec3, by: f32) -> Vec3 {}",
+            spec: "pub fn scale(v: Vec3, by: f32) -> Vec3",
+        },
+        conforming: Example {
+            code: "pub fn scale(v: Vec3, by: f32) -> Vec3 {}",
+            spec: "pub fn scale(v: Vec3, by: f32) -> Vec3;",
+        },
+        config_keys: &["severity"],
+    },
+    Rule {
+        code: DOC_MISMATCH,
+        name: "doc-mismatch",
+        summary: "An item's doc comments say something other than the spec.",
+        rationale: "Documented behavior is part of the contract too. With `compare-docs`, the \
+            doc comments of each item the spec documents are compared with the spec's doc \
+            comments for it, or with the paragraph right before a block that declares only that \
+            item. Both are compared as plain text, so markdown formatting and line breaks don't count.",
+        violating: Example {
+            code: "/// Eases `t` in and out.
+pub fn ease(t: f32) -> f32 {}",
+            spec: "/// Eases `t` in.
+pub fn ease(t: f32) -> f32 {}",
+        },
+        conforming: Example {
+            code: "/// Eases `t` in and out.
+pub fn ease(t: f32) -> f32 {}",
+            spec: "Eases `t` in and out.
+
+// ```rust
+pub fn ease(t: f32) -> f32 {}",
+        },
+        config_keys: &["compare-docs"],
+    },
+    Rule {
+        code: ORDERING_VIOLATION,
+        name: "ordering-violation",
+        summary: "The code has an item somewhere else among its neighbours than the spec.",
+        rationale: "A canonical order keeps large modules reviewable and their diffs small. With \
+            `compare-order`, the items both sides have are compared in order among the others of \
+            their module, and methods among the methods of their type; the fewest items that \
+            put the rest in the spec's order are reported, with where the spec puts them.",
+        violating: Example {
+            code: "pub fn blend() {}
+pub fn ease(t: f32) -> f32 {}",
+            spec: "pub fn ease(t: f32) -> f32 {}
+pub fn blend() {}",
+        },
+        conforming: Example {
+            code: "pub fn ease(t: f32) -> f32 {}
+pub fn blend() {}",
+            spec: "pub fn ease(t: f32) -> f32 {}
+pub fn blend() {}",
+        },
+        config_keys: &["compare-order"],
+    },
+    Rule {
+        code: DEPRECATED_IN_CODE,
+        name: "deprecated-in-code",
+        summary: "The code marks an item `#[deprecated]` that the spec still describes as stable.",
+        rationale: "Deprecating an item changes its contract: users are told to move off it, and \
+            it may be removed later. The spec should say so too, by giving the item's block the \
+            `deprecated` stage (with a `removal` version if there is one) or the same attribute. \
+            Deprecated code items the spec doesn't have at all are fine unless \
+            `allow-unspecced-deprecated` is off.",
+        violating: Example {
+            code: "#[deprecated]
+pub fn draw_legacy() {}",
+            spec: "pub fn draw_legacy() {}",
+        },
+        conforming: Example {
+            code: "#[deprecated]
+pub fn draw_legacy() {}",
+            spec: "// ```rust,deprecated,removal=0.5.0
+pub fn draw_legacy() {}",
+        },
+        config_keys: &["allow-unspecced-deprecated"],
+    },
+    Rule {
+        code: VALUE_MISMATCH,
+        name: "value-mismatch",
+        summary: "A const or static has another value in the code than in the spec.",
+        rationale: "Protocol versions, magic numbers and limits are contracts like discriminants \
+            are: peers and stored data depend on the exact value. A const or static that differs \
+            only in its value is reported here instead of as a signature mismatch. Integers are \
+            compared by value, so `0x10` matches `16`; turn the check off with \
+            `compare-const-values = false`.",
+        violating: Example {
+            code: "pub const PROTOCOL_VERSION: u16 = 4;",
+            spec: "pub const PROTOCOL_VERSION: u16 = 3;",
+        },
+        conforming: Example {
+            code: "pub const MAGIC: u32 = 0x5350_4543;",
+            spec: "pub const MAGIC: u32 = 1397769539;",
+        },
+        config_keys: &["compare-const-values"],
+    },
+    Rule {
+        code: ABI_MISMATCH,
+        name: "abi-mismatch",
+        summary: "An item's layout or calling convention differs from the spec's.",
+        rationale: "`#[repr]`, `#[no_mangle]` and a function's `extern` ABI are what C callers, \
+            FFI bindings and transmutes depend on, and changing them is breaking however \
+            compatible the Rust signature looks. The attributes of the `abi` class are compared \
+            exactly whatever their `attribute-policy`, and these findings are reported instead \
+            of signature or attribute mismatches. Change which attributes are in the class with \
+            `attribute-classes`.",
+        violating: Example {
+            code: "#[repr(u16)]
+pub enum Opcode { Nop, Halt }",
+            spec: "#[repr(u8)]
+pub enum Opcode { Nop, Halt }",
+        },
+        conforming: Example {
+            code: "#[repr(C)]
+pub struct Point { pub x: f32, pub y: f32 }",
+            spec: "#[repr(C)]
+pub struct Point { pub x: f32, pub y: f32 }",
+        },
+        config_keys: &["attribute-classes"],
+    },
+    Rule {
+        code: ORPHANED_SPEC,
+        name: "orphaned-spec",
+        summary: "A spec file describes no source file.",
+        rationale: "When a source file is deleted or renamed, its spec stays behind and is never \
+            checked again, so the contract it states silently stops holding. A spec file that \
+            neither mirrors a source file, nor is matched by a `[[mapping]]` rule that matches \
+            one, nor is included by such a spec is reported, with `check-orphaned-specs = true`. \
+            `spec-check migrate` moves orphaned specs to the source files they describe.",
+        violating: Example {
+            code: "// src/color.rs, after src/colour.rs was renamed\npub struct Color;",
+            spec: "// spec/colour.md\npub struct Color;",
+        },
+        conforming: Example {
+            code: "// src/color.rs\npub struct Color;",
+            spec: "// spec/color.md\npub struct Color;",
+        },
+        config_keys: &["check-orphaned-specs", "spec-dir"],
+    },
+    Rule {
+        code: NAMING_CONVENTION,
+        name: "naming-convention",
+        summary: "An item's name breaks a naming convention of its kind.",
+        rationale: "Conventions the compiler doesn't enforce, such as error types ending in \
+            `Error`, are only kept if something checks them. Each `[[naming]]` rule gives the \
+            case (`UpperCamelCase`, `snake_case` or `SCREAMING_SNAKE_CASE`) and a glob names of \
+            a kind of item must match, optionally only for items with a given attribute; code \
+            items are reported with the first rule they break.",
+        violating: Example {
+            code: "#[derive(Debug, thiserror::Error)]\npub enum ParseFailure { Eof }",
+            spec: "#[derive(Debug, thiserror::Error)]\npub enum ParseFailure { Eof }",
+        },
+        conforming: Example {
+            code: "#[derive(Debug, thiserror::Error)]\npub enum ParseError { Eof }",
+            spec: "#[derive(Debug, thiserror::Error)]\npub enum ParseError { Eof }",
+        },
+        config_keys: &["naming"],
+    },
+];
+
+/// Look up a rule by code (case-insensitive) or by name
+pub fn find(code_or_name: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| {
+        rule.code.eq_ignore_ascii_case(code_or_name) || rule.name == code_or_name
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_codes_are_unique() {
+        for (i, rule) in RULES.iter().enumerate() {
+            assert!(RULES[i + 1..].iter().all(|other| other.code != rule.code && other.name != rule.name));
+        }
+    }
+
+    #[test]
+    fn test_find_by_code_or_name() {
+        assert_eq!(find("sc003").unwrap().name, "signature-mismatch");
+        assert_eq!(find("attribute-mismatch").unwrap().code, ATTRIBUTE_MISMATCH);
+        assert!(find("SC999").is_none());
+    }
+}
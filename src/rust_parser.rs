@@ -1,21 +1,42 @@
+use crate::config;
+use crate::placeholder;
 use anyhow::Result;
-use syn::{visit::Visit, File, ItemStruct, ItemTrait, ItemFn, ItemEnum, TraitItem, TraitItemFn, Visibility};
-use proc_macro2::TokenStream;
+use syn::{visit::Visit, File, ForeignItem, ImplItem, ItemConst, ItemForeignMod, ItemStruct, ItemTrait, ItemFn, ItemEnum, ItemImpl, ItemMacro, ItemStatic, ItemUnion, ItemUse, TraitItem, UseTree, Visibility};
+use proc_macro2::{Span, TokenStream};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RustItem {
     pub name: String,
     pub kind: ItemKind,
     pub signature: String,  // Original for display
-    pub tokens: TokenStream,  // For comparison
+    pub tokens: String,  // Rendered token stream, for comparison
     pub attributes: Vec<String>,
     pub line_number: usize,  // Line number in source file
+    /// 1-based column of the item's name on that line; 0 where it isn't known
+    #[serde(default)]
+    pub column: usize,
+    /// Inline modules the item is declared in, outermost first (`mod math { ... }` gives `["math"]`)
+    #[serde(default)]
+    pub module: Vec<String>,
+    /// Lifecycle stage the spec gives the item; always stable for code items
+    #[serde(default)]
+    pub stage: Stage,
+    /// Requirement IDs from the spec prose right before the item's block; none for code items
+    #[serde(default)]
+    pub requirements: Vec<String>,
+    /// The spec prose right before the item's block, when the block declares only this item
+    #[serde(default)]
+    pub prose: Option<String>,
+    /// The versions of the API the spec describes the item in; every version for code items
+    #[serde(default)]
+    pub versions: Versions,
 }
 
-// Manual PartialEq and Eq that only compare name and kind for HashSet
+// Manual PartialEq and Eq that only compare name, kind and module for HashSet
 impl PartialEq for RustItem {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.kind == other.kind
+        self.name == other.name && self.kind == other.kind && self.module == other.module
     }
 }
 
@@ -24,61 +45,313 @@ impl Eq for RustItem {}
 impl std::hash::Hash for RustItem {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.name.hash(state);
-        format!("{:?}", self.kind).hash(state);
+        self.kind.hash(state);
+        self.module.hash(state);
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ItemKind {
     Struct,
     Enum,
+    Union,
     Trait,
     TraitMethod { trait_name: String },
+    /// `type Output;` in a trait
+    TraitAssocType { trait_name: String },
+    /// `const EPSILON: f32;` in a trait
+    TraitConst { trait_name: String },
+    /// A method of an inherent `impl` block
+    Method { type_name: String },
     Function,
+    Const,
+    Static,
+    /// A `macro_rules!` macro, exported with `#[macro_export]`, or a proc-macro entry point
+    Macro,
+    /// A `pub use` re-export, named by the name it is exported under (`path::*` for globs)
+    Use,
+    /// An `impl Trait for Type` block, named `Trait for Type`; only its header is compared
+    TraitImpl,
+}
+
+/// Where a spec item is in its lifecycle, from the block's fence tag or a directive before it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stage {
+    /// Not implemented yet, so it may be missing from the code
+    Planned,
+    /// Checked strictly
+    #[default]
+    Stable,
+    /// Must still exist until the package reaches the removal version, if one is given
+    Deprecated { removal: Option<String> },
+    /// Must not exist in the code, e.g. an item whose removal is being tracked
+    Forbidden,
+}
+
+impl Stage {
+    /// Whether the item may be missing from the code of a package at `version`
+    pub fn allows_missing(&self, version: Option<&str>) -> bool {
+        match self {
+            Stage::Planned | Stage::Forbidden => true,
+            Stage::Stable => false,
+            Stage::Deprecated { removal } => match (removal, version) {
+                (Some(removal), Some(version)) => version_at_least(version, removal),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The versions a spec block is about, from its `since=2.0` and `until=3.0` tags or a heading
+/// such as `## v2 API`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Versions {
+    /// The first version with the item
+    pub since: Option<String>,
+    /// The first version without it
+    pub until: Option<String>,
+}
+
+impl Versions {
+    /// Whether an API at `version` has the items; every version does without one
+    pub fn contains(&self, version: Option<&str>) -> bool {
+        let Some(version) = version else { return true };
+        self.since.as_deref().is_none_or(|since| version_at_least(version, since))
+            && self.until.as_deref().is_none_or(|until| !version_at_least(version, until))
+    }
+}
+
+/// Compare dotted versions numerically (`0.10.0` >= `0.9`); pre-release and build suffixes are ignored
+fn version_at_least(version: &str, minimum: &str) -> bool {
+    let parts = |v: &str| -> Vec<u64> {
+        v.split(['-', '+']).next().unwrap_or_default().split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    let (mut version, mut minimum) = (parts(version), parts(minimum));
+    let len = version.len().max(minimum.len());
+    version.resize(len, 0);
+    minimum.resize(len, 0);
+    version >= minimum
+}
+
+impl ItemKind {
+    /// What the kind is called in summaries and exports, e.g. `trait method`
+    pub fn label(&self) -> &'static str {
+        match self {
+            ItemKind::Struct => "struct",
+            ItemKind::Enum => "enum",
+            ItemKind::Union => "union",
+            ItemKind::Trait => "trait",
+            ItemKind::TraitMethod { .. } => "trait method",
+            ItemKind::TraitAssocType { .. } => "trait type",
+            ItemKind::TraitConst { .. } => "trait const",
+            ItemKind::Method { .. } => "method",
+            ItemKind::Function => "function",
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+            ItemKind::Use => "re-export",
+            ItemKind::Macro => "macro",
+            ItemKind::TraitImpl => "trait impl",
+        }
+    }
+
+    /// The trait or type a member item belongs to
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            ItemKind::TraitMethod { trait_name } | ItemKind::TraitAssocType { trait_name } | ItemKind::TraitConst { trait_name } => Some(trait_name),
+            ItemKind::Method { type_name } => Some(type_name),
+            _ => None,
+        }
+    }
 }
 
 impl RustItem {
     pub fn new(name: String, kind: ItemKind, signature: String, tokens: TokenStream, attributes: Vec<String>, line_number: usize) -> Self {
-        Self { name, kind, signature, tokens, attributes, line_number }
+        Self { name, kind, signature, tokens: tokens.to_string(), attributes, line_number, column: 0, module: Vec::new(), stage: Stage::Stable, requirements: Vec::new(), prose: None, versions: Versions::default() }
     }
 }
 
-struct ItemCollector {
+struct ItemCollector<'a> {
     items: Vec<RustItem>,
     current_trait: Option<String>,
-    check_private: bool,
-    source_text: String,  // Store source text for line number calculation
+    module: Vec<String>,
+    visibility: config::Visibility,
+    lines: Vec<&'a str>,
+    first_line: usize,  // Line of the source text's first line in the file it came from
+    /// Items syn could only keep as tokens, by line: spec authors' shorthands it doesn't know
+    skipped: Skipped,
 }
 
-impl ItemCollector {
-    fn new(check_private: bool, source_text: String) -> Self {
+impl<'a> ItemCollector<'a> {
+    fn new(visibility: config::Visibility, source_text: &'a str, first_line: usize) -> Self {
         Self {
             items: Vec::new(),
             current_trait: None,
-            check_private,
-            source_text,
+            module: Vec::new(),
+            visibility,
+            lines: source_text.lines().collect(),
+            first_line,
+            skipped: Vec::new(),
         }
     }
 
+    /// Note an item syn kept as tokens, unless it's a function declared without a body
+    /// (`pub fn ease(t: f32) -> f32;`), which is returned as one with an empty body
+    fn bodiless_fn(&mut self, tokens: &TokenStream) -> Option<ItemFn> {
+        match syn::parse2::<syn::ForeignItemFn>(tokens.clone()) {
+            Ok(syn::ForeignItemFn { attrs, vis, sig, .. }) => Some(ItemFn { attrs, vis, sig, block: Box::new(syn::parse_quote!({})) }),
+            Err(_) => {
+                let span = tokens.clone().into_iter().next().map_or_else(Span::call_site, |tree| tree.span());
+                let text = tokens.to_string();
+                if !self.is_ignored(&[], Some(self.position(span).0)) {
+                    self.skipped.push((self.position(span).0, text));
+                }
+                None
+            }
+        }
+    }
+
+    fn push(&mut self, item: RustItem) {
+        if self.is_ignored(&item.attributes, Some(item.line_number)) {
+            return;
+        }
+        self.items.push(RustItem { module: self.module.clone(), ..item });
+    }
+
+    /// Whether an item is marked to be skipped, by an ignore attribute or by a
+    /// `// spec-check: ignore` comment among the lines above it or at the end of its first line
+    fn is_ignored(&self, attributes: &[String], line_number: Option<usize>) -> bool {
+        if attributes.iter().any(|attr| is_ignore_attribute(attr)) {
+            return true;
+        }
+        let Some(index) = line_number.and_then(|line| line.checked_sub(self.first_line)) else { return false };
+        if self.lines.get(index).is_some_and(|line| line.find("//").is_some_and(|at| is_ignore_comment(&line[at..]))) {
+            return true;
+        }
+        // Attributes and comments run up to the end of the previous item or a blank line
+        for line in self.lines[..index.min(self.lines.len())].iter().rev().map(|line| line.trim()) {
+            if line.starts_with("//") {
+                if is_ignore_comment(line) {
+                    return true;
+                }
+            } else if line.is_empty() || line.ends_with('}') || line.ends_with(';') {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// One item per name a `use` tree exports, each with tokens spelling its whole path,
+    /// so `pub use a::{b, c};` compares equal to `pub use a::b; pub use a::c;`
+    fn collect_uses(&mut self, node: &ItemUse, tree: &UseTree, prefix: &mut Vec<syn::Ident>, attributes: &[String]) {
+        let (vis, colon) = (&node.vis, &node.leading_colon);
+        let (name, span, tokens) = match tree {
+            UseTree::Path(path) => {
+                prefix.push(path.ident.clone());
+                self.collect_uses(node, &path.tree, prefix, attributes);
+                prefix.pop();
+                return;
+            }
+            UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.collect_uses(node, tree, prefix, attributes);
+                }
+                return;
+            }
+            // `use a::b::{self}` exports `b`
+            UseTree::Name(name) if name.ident == "self" => {
+                let Some(last) = prefix.last() else { return };
+                (last.to_string(), name.ident.span(), quote::quote!(#vis use #colon #(#prefix)::*;))
+            }
+            UseTree::Name(name) => {
+                let ident = &name.ident;
+                (ident.to_string(), ident.span(), quote::quote!(#vis use #colon #(#prefix::)* #ident;))
+            }
+            // `use Trait as _` only brings the trait's methods into scope
+            UseTree::Rename(rename) if rename.rename == "_" => return,
+            UseTree::Rename(rename) => {
+                let (ident, alias) = (&rename.ident, &rename.rename);
+                (alias.to_string(), alias.span(), quote::quote!(#vis use #colon #(#prefix::)* #ident as #alias;))
+            }
+            UseTree::Glob(glob) => {
+                let path: Vec<String> = prefix.iter().map(|ident| ident.to_string()).chain(["*".to_string()]).collect();
+                (path.join("::"), glob.star_token.span, quote::quote!(#vis use #colon #(#prefix::)* *;))
+            }
+        };
+        let (line_number, column) = self.position(span);
+        self.push(RustItem { column, ..RustItem::new(name, ItemKind::Use, tokens.to_string(), tokens, attributes.to_vec(), line_number) });
+    }
+
     fn should_include(&self, vis: &Visibility) -> bool {
-        self.check_private || matches!(vis, Visibility::Public(_))
-    }
-
-    /// Calculate line number by finding the identifier in source text
-    /// This is a heuristic approach since proc_macro2 spans don't provide location info
-    fn calculate_line_number(&self, ident_name: &str, search_start: usize) -> usize {
-        // Find the identifier in the source text starting from search_start
-        if let Some(pos) = self.source_text[search_start..].find(ident_name) {
-            let actual_pos = search_start + pos;
-            // Count newlines up to this position
-            self.source_text[..actual_pos]
-                .chars()
-                .filter(|&c| c == '\n')
-                .count() + 1
-        } else {
-            1  // Fallback to line 1 if not found
+        is_included(vis, self.visibility)
+    }
+
+    /// The items of a module less visible than the configured visibility: only the impls and
+    /// `#[macro_export]` macros, which are part of the API wherever they are
+    fn visit_hidden_items<'ast>(&mut self, items: &'ast [syn::Item]) where Self: Visit<'ast> {
+        for item in items {
+            match item {
+                syn::Item::Impl(item) => self.visit_item_impl(item),
+                syn::Item::Macro(item) => self.visit_item_macro(item),
+                syn::Item::Mod(item) => {
+                    self.module.push(item.ident.to_string());
+                    if let Some((_, items)) = &item.content {
+                        self.visit_hidden_items(items);
+                    }
+                    self.module.pop();
+                }
+                _ => {}
+            }
         }
     }
+
+    /// The line in the file and the 1-based column where a token starts
+    fn position(&self, span: Span) -> (usize, usize) {
+        let start = span.start();
+        (start.line + self.first_line - 1, start.column + 1)
+    }
+
+    /// An `impl Trait for Type` block as an item named `Trait for Type`, with the last segments
+    /// of both paths so `fmt::Display` matches `Display`
+    fn push_trait_impl(&mut self, node: &ItemImpl, negative: bool, path: &syn::Path) {
+        let Some(segment) = path.segments.last() else { return };
+        let self_ty = match &*node.self_ty {
+            syn::Type::Path(ty) if ty.qself.is_none() => match ty.path.segments.last() {
+                Some(last) => quote::quote!(#last),
+                None => return,
+            },
+            ty => quote::quote!(#ty),
+        };
+        let bang = negative.then(|| quote::quote!(!));
+        let name = format!("{}{} for {}", if negative { "!" } else { "" }, compact(&quote::quote!(#segment).to_string()), compact(&self_ty.to_string()));
+        let (line_number, column) = self.position(segment.ident.span());
+        let attributes: Vec<String> = node.attrs.iter().map(|attr| quote::quote!(#attr).to_string()).collect();
+
+        // The items are the trait's, so the header is what the impl declares
+        let (unsafety, generics, where_clause) = (&node.unsafety, &node.generics, &node.generics.where_clause);
+        let tokens: TokenStream = quote::quote!(#unsafety impl #generics #bang #segment for #self_ty #where_clause {});
+        self.push(RustItem { column, ..RustItem::new(name, ItemKind::TraitImpl, tokens.to_string(), tokens, attributes, line_number) });
+    }
+}
+
+/// Tokens as they'd be written: `Vec < T >` as `Vec<T>`
+fn compact(tokens: &str) -> String {
+    [(" < ", "<"), ("< ", "<"), (" >", ">"), (" :: ", "::"), (" ,", ","), ("& ", "&")].iter()
+        .fold(tokens.to_string(), |text, (from, to)| text.replace(from, to))
+}
+
+/// Paths of the attribute that skips an item: the `spec-check-macros` attribute under its own
+/// crate name or a `spec_check` rename
+const IGNORE_ATTRIBUTES: &[&str] = &["spec_check :: ignore", "spec_check_macros :: ignore"];
+
+fn is_ignore_attribute(attr: &str) -> bool {
+    let Ok(attrs) = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attr) else { return false };
+    attrs.first().is_some_and(|attr| IGNORE_ATTRIBUTES.contains(&quote::ToTokens::to_token_stream(attr.path()).to_string().as_str()))
+}
+
+/// `// spec-check: ignore`, spaced any way, optionally followed by a reason
+fn is_ignore_comment(comment: &str) -> bool {
+    let text: String = comment.trim_start_matches('/').split_whitespace().collect();
+    text.starts_with("spec-check:ignore")
 }
 
 /// Recursively strip attributes from a syn node
@@ -109,13 +382,7 @@ impl StripAttrs for ItemTrait {
     fn strip_attrs(&mut self) {
         self.attrs.clear();
         for item in &mut self.items {
-            match item {
-                syn::TraitItem::Const(c) => c.attrs.clear(),
-                syn::TraitItem::Fn(f) => f.attrs.clear(),
-                syn::TraitItem::Type(t) => t.attrs.clear(),
-                syn::TraitItem::Macro(m) => m.attrs.clear(),
-                _ => {}
-            }
+            item.strip_attrs();
         }
     }
 }
@@ -126,6 +393,39 @@ impl StripAttrs for ItemFn {
     }
 }
 
+impl StripAttrs for ItemUnion {
+    fn strip_attrs(&mut self) {
+        self.attrs.clear();
+        for field in &mut self.fields.named {
+            field.attrs.clear();
+        }
+    }
+}
+
+impl StripAttrs for ForeignItem {
+    fn strip_attrs(&mut self) {
+        match self {
+            ForeignItem::Fn(f) => f.attrs.clear(),
+            ForeignItem::Static(s) => s.attrs.clear(),
+            ForeignItem::Type(t) => t.attrs.clear(),
+            ForeignItem::Macro(m) => m.attrs.clear(),
+            _ => {}
+        }
+    }
+}
+
+impl StripAttrs for ItemConst {
+    fn strip_attrs(&mut self) {
+        self.attrs.clear();
+    }
+}
+
+impl StripAttrs for ItemStatic {
+    fn strip_attrs(&mut self) {
+        self.attrs.clear();
+    }
+}
+
 impl StripAttrs for ItemEnum {
     fn strip_attrs(&mut self) {
         self.attrs.clear();
@@ -154,17 +454,48 @@ impl StripAttrs for syn::Variant {
     }
 }
 
-impl StripAttrs for TraitItemFn {
+impl StripAttrs for TraitItem {
     fn strip_attrs(&mut self) {
-        self.attrs.clear();
+        match self {
+            TraitItem::Const(c) => c.attrs.clear(),
+            TraitItem::Fn(f) => f.attrs.clear(),
+            TraitItem::Type(t) => t.attrs.clear(),
+            TraitItem::Macro(m) => m.attrs.clear(),
+            _ => {}
+        }
     }
 }
 
-impl<'ast> Visit<'ast> for ItemCollector {
+impl<'ast> Visit<'ast> for ItemCollector<'_> {
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        match node {
+            syn::Item::Verbatim(tokens) => {
+                if let Some(function) = self.bodiless_fn(tokens) {
+                    self.visit_item_fn(&function);
+                }
+            }
+            _ => syn::visit::visit_item(self, node),
+        }
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let attributes: Vec<String> = node.attrs.iter().map(|attr| quote::quote!(#attr).to_string()).collect();
+        if self.is_ignored(&attributes, Some(self.position(node.mod_token.span).0)) {
+            return;
+        }
+        self.module.push(node.ident.to_string());
+        if self.should_include(&node.vis) {
+            syn::visit::visit_item_mod(self, node);
+        } else if let Some((_, items)) = &node.content {
+            self.visit_hidden_items(items);
+        }
+        self.module.pop();
+    }
+
     fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
         if self.should_include(&node.vis) {
             let name = node.ident.to_string();
-            let line_number = self.calculate_line_number(&name, 0);
+            let (line_number, column) = self.position(node.ident.span());
             
             // Extract attributes
             let attributes: Vec<String> = node.attrs.iter()
@@ -177,21 +508,21 @@ impl<'ast> Visit<'ast> for ItemCollector {
             let signature = quote::quote!(#item_without_attrs).to_string();
             let tokens: TokenStream = quote::quote!(#item_without_attrs);
             
-            self.items.push(RustItem::new(
+            self.push(RustItem { column, ..RustItem::new(
                 name,
                 ItemKind::Struct,
                 signature,
                 tokens,
                 attributes,
                 line_number,
-            ));
+            ) });
         }
     }
 
     fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
         if self.should_include(&node.vis) {
             let name = node.ident.to_string();
-            let line_number = self.calculate_line_number(&name, 0);
+            let (line_number, column) = self.position(node.ident.span());
             
             // Extract attributes
             let attributes: Vec<String> = node.attrs.iter()
@@ -204,26 +535,30 @@ impl<'ast> Visit<'ast> for ItemCollector {
             let signature = quote::quote!(#item_without_attrs).to_string();
             let tokens: TokenStream = quote::quote!(#item_without_attrs);
             
-            self.items.push(RustItem::new(
+            self.push(RustItem { column, ..RustItem::new(
                 name,
                 ItemKind::Enum,
                 signature,
                 tokens,
                 attributes,
                 line_number,
-            ));
+            ) });
         }
     }
 
     fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
         if self.should_include(&node.vis) {
             let trait_name = node.ident.to_string();
-            let line_number = self.calculate_line_number(&trait_name, 0);
+            let (line_number, column) = self.position(node.ident.span());
             
             // Extract attributes
             let attributes: Vec<String> = node.attrs.iter()
                 .map(|attr| quote::quote!(#attr).to_string())
                 .collect();
+            // Its members go with it
+            if self.is_ignored(&attributes, Some(line_number)) {
+                return;
+            }
             
             // Build signature and tokens without attributes
             let mut item_without_attrs = node.clone();
@@ -231,52 +566,217 @@ impl<'ast> Visit<'ast> for ItemCollector {
             let signature = quote::quote!(#item_without_attrs).to_string();
             let tokens: TokenStream = quote::quote!(#item_without_attrs);
             
-            self.items.push(RustItem::new(
+            self.push(RustItem { column, ..RustItem::new(
                 trait_name.clone(),
                 ItemKind::Trait,
                 signature,
                 tokens,
                 attributes,
                 line_number,
-            ));
+            ) });
 
-            // Visit trait methods
+            // Visit trait methods, associated types and associated consts
             let old_trait = self.current_trait.replace(trait_name.clone());
             for item in &node.items {
-                if let TraitItem::Fn(method) = item {
-                    let method_name = method.sig.ident.to_string();
-                    let line_number = self.calculate_line_number(&method_name, 0);
-                    
-                    // Extract attributes
-                    let attributes: Vec<String> = method.attrs.iter()
-                        .map(|attr| quote::quote!(#attr).to_string())
-                        .collect();
-                    
-                    // Build signature and tokens without attributes
-                    let mut method_without_attrs = method.clone();
-                    method_without_attrs.strip_attrs();
-                    let method_sig = quote::quote!(#method_without_attrs).to_string();
-                    let method_tokens: TokenStream = quote::quote!(#method_without_attrs);
-                    
-                    self.items.push(RustItem::new(
-                        method_name,
-                        ItemKind::TraitMethod { trait_name: trait_name.clone() },
-                        method_sig,
-                        method_tokens,
-                        attributes,
-                        line_number,
-                    ));
-                }
+                let (name, kind, attrs) = match item {
+                    TraitItem::Fn(method) => (&method.sig.ident, ItemKind::TraitMethod { trait_name: trait_name.clone() }, &method.attrs),
+                    TraitItem::Type(ty) => (&ty.ident, ItemKind::TraitAssocType { trait_name: trait_name.clone() }, &ty.attrs),
+                    TraitItem::Const(constant) => (&constant.ident, ItemKind::TraitConst { trait_name: trait_name.clone() }, &constant.attrs),
+                    _ => continue,
+                };
+                let (line_number, column) = self.position(name.span());
+                let name = name.to_string();
+
+                // Extract attributes
+                let attributes: Vec<String> = attrs.iter()
+                    .map(|attr| quote::quote!(#attr).to_string())
+                    .collect();
+
+                // Build signature and tokens without attributes
+                let mut item_without_attrs = item.clone();
+                item_without_attrs.strip_attrs();
+                let tokens: TokenStream = quote::quote!(#item_without_attrs);
+
+                self.push(RustItem { column, ..RustItem::new(
+                    name,
+                    kind,
+                    tokens.to_string(),
+                    tokens,
+                    attributes,
+                    line_number,
+                ) });
             }
             self.current_trait = old_trait;
         }
     }
 
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        // Trait impls only restate the trait's methods, so the impl itself is the item
+        if let Some((bang, path, _)) = &node.trait_ {
+            self.push_trait_impl(node, bang.is_some(), path);
+            return;
+        }
+        let type_name = match &*node.self_ty {
+            syn::Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        let Some(type_name) = type_name else { return };
+        let attributes: Vec<String> = node.attrs.iter().map(|attr| quote::quote!(#attr).to_string()).collect();
+        if self.is_ignored(&attributes, None) {
+            return;
+        }
+
+        for item in &node.items {
+            let bodiless;
+            let method = match item {
+                ImplItem::Fn(method) => method,
+                ImplItem::Verbatim(tokens) => {
+                    let Some(ItemFn { attrs, vis, sig, block }) = self.bodiless_fn(tokens) else { continue };
+                    bodiless = syn::ImplItemFn { attrs, vis, defaultness: None, sig, block: *block };
+                    &bodiless
+                }
+                _ => continue,
+            };
+            if !self.should_include(&method.vis) {
+                continue;
+            }
+            let method_name = method.sig.ident.to_string();
+            let (line_number, column) = self.position(method.sig.ident.span());
+
+            let attributes: Vec<String> = method.attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+
+            // The body is the implementation, not the API, so only the signature is compared
+            let (vis, sig) = (&method.vis, &method.sig);
+            let tokens: TokenStream = quote::quote!(#vis #sig);
+
+            self.push(RustItem { column, ..RustItem::new(
+                method_name,
+                ItemKind::Method { type_name: type_name.clone() },
+                tokens.to_string(),
+                tokens,
+                attributes,
+                line_number,
+            ) });
+        }
+    }
+
+    fn visit_item_union(&mut self, node: &'ast ItemUnion) {
+        if self.should_include(&node.vis) {
+            let name = node.ident.to_string();
+            let (line_number, column) = self.position(node.ident.span());
+            let attributes: Vec<String> = node.attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+
+            let mut item_without_attrs = node.clone();
+            item_without_attrs.strip_attrs();
+            let tokens: TokenStream = quote::quote!(#item_without_attrs);
+            self.push(RustItem { column, ..RustItem::new(name, ItemKind::Union, tokens.to_string(), tokens, attributes, line_number) });
+        }
+    }
+
+    /// Functions and statics of `extern` blocks, each wrapped in its block's ABI so a changed
+    /// ABI is a signature mismatch (and a foreign fn never equals a Rust one)
+    fn visit_item_foreign_mod(&mut self, node: &'ast ItemForeignMod) {
+        let abi = &node.abi;
+        for item in &node.items {
+            let (name, kind, vis, attrs) = match item {
+                ForeignItem::Fn(f) => (&f.sig.ident, ItemKind::Function, &f.vis, &f.attrs),
+                ForeignItem::Static(s) => (&s.ident, ItemKind::Static, &s.vis, &s.attrs),
+                _ => continue,
+            };
+            if !self.should_include(vis) {
+                continue;
+            }
+            let (line_number, column) = self.position(name.span());
+            let name = name.to_string();
+            let attributes: Vec<String> = attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+
+            let mut item_without_attrs = item.clone();
+            item_without_attrs.strip_attrs();
+            let tokens: TokenStream = quote::quote!(#abi { #item_without_attrs });
+            self.push(RustItem { column, ..RustItem::new(name, kind, tokens.to_string(), tokens, attributes, line_number) });
+        }
+    }
+
+    fn visit_item_const(&mut self, node: &'ast ItemConst) {
+        // `const _: () = ...;` only asserts something at compile time
+        if self.should_include(&node.vis) && node.ident != "_" {
+            let name = node.ident.to_string();
+            let (line_number, column) = self.position(node.ident.span());
+            let attributes: Vec<String> = node.attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+
+            let mut item_without_attrs = node.clone();
+            item_without_attrs.strip_attrs();
+            let tokens: TokenStream = quote::quote!(#item_without_attrs);
+            self.push(RustItem { column, ..RustItem::new(name, ItemKind::Const, tokens.to_string(), tokens, attributes, line_number) });
+        }
+    }
+
+    fn visit_item_static(&mut self, node: &'ast ItemStatic) {
+        if self.should_include(&node.vis) {
+            let name = node.ident.to_string();
+            let (line_number, column) = self.position(node.ident.span());
+            let attributes: Vec<String> = node.attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+
+            let mut item_without_attrs = node.clone();
+            item_without_attrs.strip_attrs();
+            let tokens: TokenStream = quote::quote!(#item_without_attrs);
+            self.push(RustItem { column, ..RustItem::new(name, ItemKind::Static, tokens.to_string(), tokens, attributes, line_number) });
+        }
+    }
+
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        // A plain `use` imports rather than exports, so it isn't an item even with `visibility = "all"`
+        let exported = !matches!(node.vis, Visibility::Inherited) && self.should_include(&node.vis);
+        if exported {
+            let attributes: Vec<String> = node.attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+            self.collect_uses(node, &node.tree, &mut Vec::new(), &attributes);
+        }
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro) {
+        let Some(ident) = node.ident.as_ref().filter(|_| node.mac.path.is_ident("macro_rules")) else { return };
+        let exported = node.attrs.iter().any(|attr| attr.path().is_ident("macro_export"));
+        if exported || self.visibility == config::Visibility::All {
+            let name = ident.to_string();
+            let (line_number, column) = self.position(ident.span());
+            let attributes: Vec<String> = node.attrs.iter()
+                .map(|attr| quote::quote!(#attr).to_string())
+                .collect();
+            // Rules aren't compared, only that the macro exists; `#[macro_export]` is an attribute
+            let tokens: TokenStream = quote::quote!(macro_rules! #ident);
+            self.push(RustItem { column, ..RustItem::new(name, ItemKind::Macro, tokens.to_string(), tokens, attributes, line_number) });
+        }
+    }
+
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
         // Only collect top-level functions (not trait methods or impl methods)
         if self.current_trait.is_none() && self.should_include(&node.vis) {
+            if let Some(name) = proc_macro_name(node) {
+                let (line_number, column) = self.position(node.sig.ident.span());
+                let attributes: Vec<String> = node.attrs.iter()
+                    .map(|attr| quote::quote!(#attr).to_string())
+                    .collect();
+                // The expansion is the implementation; the entry point's signature is all there is to compare
+                let (vis, sig) = (&node.vis, &node.sig);
+                let tokens: TokenStream = quote::quote!(#vis #sig);
+                self.push(RustItem { column, ..RustItem::new(name, ItemKind::Macro, tokens.to_string(), tokens, attributes, line_number) });
+                return;
+            }
+
             let name = node.sig.ident.to_string();
-            let line_number = self.calculate_line_number(&name, 0);
+            let (line_number, column) = self.position(node.sig.ident.span());
             
             // Extract attributes
             let attributes: Vec<String> = node.attrs.iter()
@@ -289,27 +789,130 @@ impl<'ast> Visit<'ast> for ItemCollector {
             let signature = quote::quote!(#item_without_attrs).to_string();
             let tokens: TokenStream = quote::quote!(#item_without_attrs);
             
-            self.items.push(RustItem::new(
+            self.push(RustItem { column, ..RustItem::new(
                 name,
                 ItemKind::Function,
                 signature,
                 tokens,
                 attributes,
                 line_number,
-            ));
+            ) });
         }
     }
 }
 
-pub fn parse_rust_file(content: &str, check_private: bool) -> Result<Vec<RustItem>> {
+/// The name a proc-macro entry point is invoked by: the function's for `#[proc_macro]` and
+/// `#[proc_macro_attribute]`, the derive's for `#[proc_macro_derive(Name)]`
+fn proc_macro_name(node: &ItemFn) -> Option<String> {
+    node.attrs.iter().find_map(|attr| {
+        if attr.path().is_ident("proc_macro") || attr.path().is_ident("proc_macro_attribute") {
+            Some(node.sig.ident.to_string())
+        } else if attr.path().is_ident("proc_macro_derive") {
+            let mut name = None;
+            attr.parse_nested_meta(|meta| {
+                if name.is_none() {
+                    name = meta.path.get_ident().map(|ident| ident.to_string());
+                }
+                // Skip `attributes(...)` and whatever else follows the name
+                if meta.input.peek(syn::token::Paren) {
+                    meta.input.parse::<proc_macro2::Group>()?;
+                }
+                Ok(())
+            }).ok()?;
+            name
+        } else {
+            None
+        }
+    })
+}
+
+/// The tokens of a const or static with its value left out, so that only its name and type
+/// are compared. `None` for other items.
+pub fn without_value(tokens: &str) -> Option<String> {
+    let item: syn::Item = syn::parse_str(tokens).ok()?;
+    let stripped = match item {
+        syn::Item::Const(ItemConst { vis, ident, ty, .. }) => quote::quote!(#vis const #ident: #ty;),
+        syn::Item::Static(ItemStatic { vis, mutability, ident, ty, .. }) => quote::quote!(#vis static #mutability #ident: #ty;),
+        _ => return None,
+    };
+    Some(stripped.to_string())
+}
+
+/// The tokens of a function without its body, leaving the visibility and signature. `None`
+/// for other items.
+pub fn without_body(tokens: &str) -> Option<String> {
+    let ItemFn { vis, sig, .. } = syn::parse_str(tokens).ok()?;
+    Some(quote::quote!(#vis #sig).to_string())
+}
+
+/// Extract the text of `#[doc = "..."]` attributes (i.e. `///` comments), one line per attribute
+pub fn doc_text(attributes: &[String]) -> String {
+    attributes.iter()
+        .filter_map(|attr| {
+            let attrs = syn::parse::Parser::parse_str(syn::Attribute::parse_outer, attr).ok()?;
+            let attr = attrs.into_iter().next()?;
+            let syn::Meta::NameValue(meta) = attr.meta else { return None };
+            if !meta.path.is_ident("doc") {
+                return None;
+            }
+            match meta.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(text), .. }) => Some(text.value().trim().to_string()),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether an item declared `vis` is checked at the `visibility` threshold. `pub(crate)` counts
+/// as crate-visible; `pub(super)` and `pub(in path)` below the crate root only with `all`.
+pub fn is_included(vis: &Visibility, visibility: config::Visibility) -> bool {
+    match vis {
+        Visibility::Public(_) => true,
+        Visibility::Restricted(restricted) if restricted.path.is_ident("crate") => visibility >= config::Visibility::PubCrate,
+        _ => visibility == config::Visibility::All,
+    }
+}
+
+pub fn parse_rust_file(content: &str, visibility: config::Visibility) -> Result<Vec<RustItem>> {
+    parse_rust_at(content, 1, visibility)
+}
+
+/// Parse code embedded in a larger file, e.g. a spec block, numbering lines from `first_line`
+pub fn parse_rust_at(content: &str, first_line: usize, visibility: config::Visibility) -> Result<Vec<RustItem>> {
     let syntax_tree: File = syn::parse_file(content)?;
     
-    let mut collector = ItemCollector::new(check_private, content.to_string());
+    let mut collector = ItemCollector::new(visibility, content, first_line);
     collector.visit_file(&syntax_tree);
     
     Ok(collector.items)
 }
 
+/// Parse a spec block, whose `/* ... */` and `(..)` placeholders stand for whatever the code has there
+pub fn parse_spec_block(code: &str, first_line: usize, visibility: config::Visibility) -> Result<Vec<RustItem>> {
+    Ok(parse_spec_block_with_skipped(code, first_line, visibility)?.0)
+}
+
+/// Items left out of a spec block, by line and as written
+pub type Skipped = Vec<(usize, String)>;
+
+/// [`parse_spec_block`], with the items it had to skip, by line and as written: items syn
+/// parses but can't make anything of, such as a const without a value
+pub fn parse_spec_block_with_skipped(code: &str, first_line: usize, visibility: config::Visibility) -> Result<(Vec<RustItem>, Skipped)> {
+    let expanded = placeholder::expand(code);
+    let syntax_tree: File = syn::parse_file(&expanded)?;
+    let mut collector = ItemCollector::new(visibility, &expanded, first_line);
+    collector.visit_file(&syntax_tree);
+    let skipped = collector.skipped.into_iter().map(|(line, text)| (line, placeholder::restore(&text))).collect();
+    let mut items = collector.items;
+    for item in &mut items {
+        if placeholder::has_placeholders(&item.tokens) {
+            item.signature = placeholder::restore(&item.signature);
+        }
+    }
+    Ok((items, skipped))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,7 +925,7 @@ mod tests {
             }
         "#;
         
-        let items = parse_rust_file(code, false).unwrap();
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].name, "MyStruct");
         assert!(matches!(items[0].kind, ItemKind::Struct));
@@ -336,7 +939,7 @@ mod tests {
             }
         "#;
         
-        let items = parse_rust_file(code, false).unwrap();
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
         assert_eq!(items.len(), 2); // trait + method
         
         let trait_item = items.iter().find(|i| matches!(i.kind, ItemKind::Trait)).unwrap();
@@ -346,6 +949,27 @@ mod tests {
         assert_eq!(method_item.name, "method");
     }
 
+    #[test]
+    fn test_parse_trait_associated_items() {
+        let code = r#"
+            pub trait Approx {
+                /// Tolerance of comparisons
+                const EPSILON: f32;
+                type Output: Clone;
+                fn approx(&self) -> Self::Output;
+            }
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[1].kind, ItemKind::TraitConst { trait_name: "Approx".to_string() });
+        assert_eq!((items[1].name.as_str(), items[1].signature.as_str(), items[1].line_number), ("EPSILON", "const EPSILON : f32 ;", 4));
+        assert_eq!(items[1].attributes.len(), 1);
+        assert_eq!(items[2].kind, ItemKind::TraitAssocType { trait_name: "Approx".to_string() });
+        assert_eq!(items[2].kind.owner(), Some("Approx"));
+        assert_eq!(items[2].signature, "type Output : Clone ;");
+    }
+
     #[test]
     fn test_ignores_private_items() {
         let code = r#"
@@ -353,7 +977,7 @@ mod tests {
             pub struct PublicStruct {}
         "#;
         
-        let items = parse_rust_file(code, false).unwrap();
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].name, "PublicStruct");
     }
@@ -368,9 +992,283 @@ mod tests {
             }
         "#;
         
-        let items = parse_rust_file(code, false).unwrap();
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].name, "MyEnum");
         assert!(matches!(items[0].kind, ItemKind::Enum));
     }
+
+    #[test]
+    fn test_parse_inherent_methods() {
+        let code = r#"
+            pub struct Frame;
+
+            impl Frame {
+                /// An empty frame
+                pub fn new() -> Self { Frame }
+                fn reset(&mut self) {}
+            }
+
+            impl Default for Frame {
+                fn default() -> Self { Frame::new() }
+            }
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].name, "new");
+        assert_eq!(items[1].kind, ItemKind::Method { type_name: "Frame".to_string() });
+        assert_eq!(items[1].signature, "pub fn new () -> Self");
+        assert_eq!(items[1].line_number, 6);
+
+        // A spec describes the method with an empty body, which isn't compared
+        let spec = parse_rust_file("impl Frame { pub fn new() -> Self {} }", config::Visibility::Pub).unwrap();
+        assert_eq!(spec[0].tokens, items[1].tokens);
+        assert_eq!(parse_rust_file(code, config::Visibility::All).unwrap().len(), 4);
+
+        // The trait impl is an item of its own, compared by its header
+        assert_eq!((items[2].name.as_str(), &items[2].kind, items[2].line_number), ("Default for Frame", &ItemKind::TraitImpl, 10));
+        let spec = parse_rust_file("impl std::default::Default for Frame {}", config::Visibility::Pub).unwrap();
+        assert_eq!(spec[0].tokens.to_string(), items[2].tokens.to_string());
+        let generic = parse_rust_file("unsafe impl<T: Send> Send for Slot<T> {}\nimpl !Sync for Frame {}", config::Visibility::Pub).unwrap();
+        assert_eq!(generic.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["Send for Slot<T>", "!Sync for Frame"]);
+    }
+
+    #[test]
+    fn test_parse_consts_and_statics() {
+        let code = r#"
+            pub const MAX_DEPTH: usize = 64;
+            pub static mut COUNTER: u32 = 0;
+            const _: () = assert!(MAX_DEPTH > 0);
+            const PRIVATE: u8 = 1;
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].name.as_str(), &items[0].kind), ("MAX_DEPTH", &ItemKind::Const));
+        assert_eq!(items[0].signature, "pub const MAX_DEPTH : usize = 64 ;");
+        assert_eq!(items[1].kind, ItemKind::Static);
+
+        assert_eq!(without_value(&items[0].tokens).as_deref(), Some("pub const MAX_DEPTH : usize ;"));
+        assert_eq!(without_value(&items[1].tokens).as_deref(), Some("pub static mut COUNTER : u32 ;"));
+        assert_eq!(without_value("pub fn f () {}"), None);
+    }
+
+    #[test]
+    fn test_items_of_inline_modules() {
+        let code = r#"
+            pub mod math {
+                pub struct Point { pub x: f32 }
+                pub mod nested {
+                    pub fn lerp() {}
+                }
+            }
+            pub mod vr {
+                pub struct Point { pub x: f64 }
+            }
+            pub fn top() {}
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        let modules: Vec<(&str, Vec<String>)> = items.iter().map(|item| (item.name.as_str(), item.module.clone())).collect();
+        assert_eq!(modules, [
+            ("Point", vec!["math".to_string()]),
+            ("lerp", vec!["math".to_string(), "nested".to_string()]),
+            ("Point", vec!["vr".to_string()]),
+            ("top", vec![]),
+        ]);
+        assert_ne!(items[0], items[2]);
+        assert_eq!(items[2].line_number, 9);
+    }
+
+    #[test]
+    fn test_private_inline_modules_are_left_out() {
+        let code = r#"
+            pub struct Point;
+            mod imp {
+                pub fn helper() {}
+                pub mod deeper {
+                    pub struct Hidden;
+                    #[macro_export]
+                    macro_rules! point { () => {} }
+                }
+                impl super::Point {
+                    pub fn origin() -> Self { super::Point }
+                }
+            }
+        "#;
+
+        let names = |visibility| parse_rust_file(code, visibility).unwrap().into_iter().map(|item| item.name).collect::<Vec<_>>();
+        // Exported macros and methods of public types are reachable from anywhere
+        assert_eq!(names(config::Visibility::Pub), ["Point", "point", "origin"]);
+        assert_eq!(names(config::Visibility::All), ["Point", "helper", "Hidden", "point", "origin"]);
+    }
+
+    #[test]
+    fn test_parse_reexports() {
+        let code = r#"
+            pub use crate::math::{Point, Vec3 as V3, geometry::{self, *}};
+            pub use std::fmt::Display as _;
+            use crate::internal::Helper;
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        let uses: Vec<(&str, &str)> = items.iter().map(|item| (item.name.as_str(), item.signature.as_str())).collect();
+        assert_eq!(uses, [
+            ("Point", "pub use crate :: math :: Point ;"),
+            ("V3", "pub use crate :: math :: Vec3 as V3 ;"),
+            ("geometry", "pub use crate :: math :: geometry ;"),
+            ("crate::math::geometry::*", "pub use crate :: math :: geometry :: * ;"),
+        ]);
+        assert!(items.iter().all(|item| item.kind == ItemKind::Use));
+
+        assert_eq!(parse_rust_file(code, config::Visibility::All).unwrap().len(), 4);
+
+        let split = parse_rust_file("pub use crate::math::Point;", config::Visibility::Pub).unwrap();
+        assert_eq!(split[0].tokens, items[0].tokens);
+    }
+
+    #[test]
+    fn test_parse_unions_and_extern_blocks() {
+        let code = r#"
+            #[repr(C)]
+            pub union Word { pub int: u32, pub float: f32 }
+
+            extern "C" {
+                pub fn frame_count(device: *const Device) -> u32;
+                pub static mut LAST_ERROR: i32;
+                fn private_helper();
+            }
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!((items[0].kind.clone(), items[0].attributes.len()), (ItemKind::Union, 1));
+        assert_eq!((items[1].name.as_str(), &items[1].kind), ("frame_count", &ItemKind::Function));
+        assert_eq!(items[1].signature, r#"extern "C" { pub fn frame_count (device : * const Device) -> u32 ; }"#);
+        assert_eq!(items[2].kind, ItemKind::Static);
+
+        let system = parse_rust_file(r#"extern "system" { pub fn frame_count(device: *const Device) -> u32; }"#, config::Visibility::Pub).unwrap();
+        assert_ne!(system[0].tokens, items[1].tokens);
+    }
+
+    #[test]
+    fn test_parse_macros() {
+        let code = r#"
+            #[macro_export]
+            macro_rules! animate {
+                ($e:expr) => { $e };
+            }
+
+            macro_rules! internal {
+                () => {};
+            }
+
+            #[proc_macro_derive(Animate, attributes(animate))]
+            pub fn derive_animate(input: TokenStream) -> TokenStream { input }
+
+            #[proc_macro_attribute]
+            pub fn keyframe(args: TokenStream, input: TokenStream) -> TokenStream { input }
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        let macros: Vec<(&str, &ItemKind)> = items.iter().map(|item| (item.name.as_str(), &item.kind)).collect();
+        assert_eq!(macros, [("animate", &ItemKind::Macro), ("Animate", &ItemKind::Macro), ("keyframe", &ItemKind::Macro)]);
+        assert_eq!(items[0].signature, "macro_rules ! animate");
+        assert_eq!(items[0].attributes, ["# [macro_export]"]);
+        assert_eq!(items[1].line_number, 12);
+
+        // Only exported macros are public
+        assert_eq!(parse_rust_file(code, config::Visibility::All).unwrap()[1].name, "internal");
+    }
+
+    #[test]
+    fn test_visibility_threshold() {
+        let code = r#"
+            pub struct Mesh;
+            pub(crate) fn rebuild() {}
+            pub(super) fn helper() {}
+            fn private() {}
+            pub(crate) use crate::math::Point;
+            use crate::math::Vector;
+        "#;
+
+        let names = |visibility| parse_rust_file(code, visibility).unwrap().into_iter().map(|item| item.name).collect::<Vec<_>>();
+        assert_eq!(names(config::Visibility::Pub), ["Mesh"]);
+        assert_eq!(names(config::Visibility::PubCrate), ["Mesh", "rebuild", "Point"]);
+        assert_eq!(names(config::Visibility::All), ["Mesh", "rebuild", "helper", "private", "Point"]);
+    }
+
+    #[test]
+    fn test_positions_come_from_spans() {
+        let code = concat!(
+            "/// Builds a Mesh from its vertices\n",
+            "pub fn build() -> Mesh { todo!() }\n",
+            "pub struct Mesh;\n",
+            "impl Mesh { pub fn build(&self) {} }\n",
+            "pub use crate::math::{Vector as Mesh2, Point};\n",
+        );
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        let positions: Vec<(&str, usize, usize)> = items.iter().map(|item| (item.name.as_str(), item.line_number, item.column)).collect();
+        // Neither the doc comment nor `build`'s return type is where `Mesh` is declared
+        assert_eq!(positions, [("build", 2, 8), ("Mesh", 3, 12), ("build", 4, 20), ("Mesh2", 5, 33), ("Point", 5, 40)]);
+    }
+
+    #[test]
+    fn test_ignored_items() {
+        let code = r#"
+            pub struct Kept;
+
+            #[spec_check::ignore]
+            pub struct Generated;
+
+            /// Still settling
+            // spec-check: ignore - experimental
+            #[derive(Debug)]
+            pub fn experiment() {}
+
+            pub const SEED: u64 = 7; // spec-check: ignore
+
+            #[spec_check_macros::ignore]
+            impl Kept {
+                pub fn new() -> Self { Kept }
+            }
+
+            // spec-check: ignore
+            pub mod bindings {
+                pub fn raw() {}
+            }
+
+            // An ordinary comment
+            pub fn kept() {}
+        "#;
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["Kept", "kept"]);
+    }
+
+    #[test]
+    fn test_doc_text() {
+        let code = r#"
+            /// Computes the frame.
+            /// Returns (T, N, B).
+            #[inline]
+            pub fn frame() {}
+        "#;
+
+        let items = parse_rust_file(code, config::Visibility::Pub).unwrap();
+        assert_eq!(doc_text(&items[0].attributes), "Computes the frame.\nReturns (T, N, B).");
+    }
+
+    #[test]
+    fn test_spec_blocks_declare_functions_without_bodies() {
+        let block = "/// Eases `t`\npub fn ease(t: f32) -> f32;\n\nimpl Curve {\n    pub fn frame(&self, t: f32) -> (..);\n}\n\npub const MAX: usize;\n";
+        let (items, skipped) = parse_spec_block_with_skipped(block, 10, config::Visibility::Pub).unwrap();
+        let summary: Vec<(&str, &str, usize)> = items.iter().map(|item| (item.name.as_str(), item.signature.as_str(), item.line_number)).collect();
+        assert_eq!(summary, [("ease", "pub fn ease (t : f32) -> f32 { }", 11), ("frame", "pub fn frame (& self , t : f32) -> (..)", 14)]);
+        assert_eq!(doc_text(&items[0].attributes), "Eases `t`");
+        // The same as an empty body
+        assert_eq!(items[0].tokens, parse_spec_block("pub fn ease(t: f32) -> f32 {}", 1, config::Visibility::Pub).unwrap()[0].tokens);
+        assert_eq!(skipped, [(17, "pub const MAX : usize ;".to_string())]);
+    }
 }
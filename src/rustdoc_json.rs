@@ -0,0 +1,270 @@
+//! The item model in the shape of rustdoc's JSON output (`root`, `index`, `paths`), for
+//! documentation generators and API diff tools that already read it.
+//!
+//! Only what spec-check extracts is there: items carry their signature as a string rather than
+//! rustdoc's typed `inner` structures, and ids are assigned per export rather than by rustdoc.
+
+use crate::json;
+use crate::rust_parser::{self, ItemKind, RustItem};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use toml::{Table, Value};
+
+/// Not one of rustdoc's format versions, so readers can tell this export apart
+pub const FORMAT_VERSION: i64 = 0;
+
+/// The items of one source (or spec) file
+pub struct Module {
+    pub file: PathBuf,
+    /// Module path below the crate root, e.g. `["geometry", "curve"]`
+    pub path: Vec<String>,
+    pub items: Vec<RustItem>,
+}
+
+/// The module path of a file relative to its tree: `lib.rs` is the root,
+/// `geometry/mod.rs` and `geometry.rs` are both `geometry`
+pub fn module_path(relative: &Path) -> Vec<String> {
+    let mut path: Vec<String> = relative.with_extension("").components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    if path.len() == 1 && matches!(path[0].as_str(), "lib" | "main") || path.last().is_some_and(|last| last == "mod") {
+        path.pop();
+    }
+    path
+}
+
+struct Export<'a> {
+    crate_name: &'a str,
+    index: Table,
+    paths: Table,
+    next_id: usize,
+}
+
+impl Export<'_> {
+    fn id(&mut self) -> String {
+        let id = format!("0:{}", self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn add(&mut self, id: &str, path: &[String], kind: &str, item: Table) {
+        let mut full_path = vec![Value::String(self.crate_name.to_string())];
+        full_path.extend(path.iter().cloned().map(Value::String));
+        self.paths.insert(id.to_string(), table([
+            ("crate_id", Value::Integer(0)),
+            ("path", Value::Array(full_path)),
+            ("kind", kind.into()),
+        ]));
+        self.index.insert(id.to_string(), Value::Table(item));
+    }
+
+    fn item(&mut self, id: &str, file: &Path, item: &RustItem, inner: Value) -> Table {
+        let filename = file.to_string_lossy().replace('\\', "/");
+        let position = || Value::Array(vec![Value::Integer(item.line_number as i64), Value::Integer(0)]);
+        let visibility = if item.signature.starts_with("pub") { "public" } else { "default" };
+        let attrs = item.attributes.iter()
+            .filter(|attr| rust_parser::doc_text(std::slice::from_ref(attr)).is_empty())
+            .cloned()
+            .map(Value::String)
+            .collect();
+
+        let mut entry = Table::new();
+        entry.insert("id".to_string(), id.into());
+        entry.insert("crate_id".to_string(), Value::Integer(0));
+        entry.insert("name".to_string(), item.name.clone().into());
+        entry.insert("span".to_string(), table([("filename", filename.into()), ("begin", position()), ("end", position())]));
+        entry.insert("visibility".to_string(), visibility.into());
+        let docs = rust_parser::doc_text(&item.attributes);
+        if !docs.is_empty() {
+            entry.insert("docs".to_string(), docs.into());
+        }
+        entry.insert("attrs".to_string(), Value::Array(attrs));
+        entry.insert("inner".to_string(), inner);
+        entry
+    }
+}
+
+/// Build the export for a crate from its modules
+pub fn to_string(crate_name: &str, crate_version: Option<&str>, modules: &[Module], includes_private: bool) -> String {
+    let mut export = Export { crate_name, index: Table::new(), paths: Table::new(), next_id: 0 };
+    let root = export.id();
+
+    // Module items by path, created on first use so parents always come first
+    let mut module_items: ModuleItems = BTreeMap::new();
+    module_items.insert(Vec::new(), (root.clone(), Vec::new()));
+    for module in modules {
+
+        module_entry(&mut export, &mut module_items, &module.path);
+
+        // Trait members follow their trait in the item list; inherent methods are grouped
+        // into one `impl` item per type. Both are keyed by their path, inline modules included.
+        let mut traits: BTreeMap<Vec<String>, (String, String, Vec<Value>)> = BTreeMap::new();
+        let mut impls: BTreeMap<Vec<String>, (String, Vec<Value>)> = BTreeMap::new();
+        for item in &module.items {
+            // rustdoc lists trait impls under their types, which the export doesn't describe
+            if item.kind == ItemKind::TraitImpl {
+                continue;
+            }
+            let id = export.id();
+            let mut path = module.path.clone();
+            path.extend(item.module.iter().cloned());
+            let owner = |name: &str| [path.as_slice(), &[name.to_string()]].concat();
+            let (kind, inner) = match &item.kind {
+                ItemKind::Struct => ("struct", table([("struct", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::Enum => ("enum", table([("enum", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::Union => ("union", table([("union", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::Trait => {
+                    traits.insert(owner(&item.name), (id.clone(), item.signature.clone(), Vec::new()));
+                    ("trait", Value::Boolean(false))
+                }
+                ItemKind::Function | ItemKind::TraitMethod { .. } | ItemKind::Method { .. } => {
+                    ("function", table([("function", table([("signature", item.signature.clone().into())]))]))
+                }
+                ItemKind::Const => ("constant", table([("constant", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::Static => ("static", table([("static", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::Macro => ("macro", table([("macro", item.signature.clone().into())])),
+                ItemKind::Use => ("use", table([("use", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::TraitAssocType { .. } => ("assoc_type", table([("assoc_type", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::TraitConst { .. } => ("assoc_const", table([("assoc_const", table([("signature", item.signature.clone().into())]))])),
+                ItemKind::TraitImpl => unreachable!("trait impls are skipped"),
+            };
+            if let ItemKind::TraitMethod { trait_name } | ItemKind::TraitAssocType { trait_name } | ItemKind::TraitConst { trait_name } = &item.kind {
+                if let Some((_, _, methods)) = traits.get_mut(&owner(trait_name)) {
+                    methods.push(id.clone().into());
+                }
+                path.push(trait_name.clone());
+            } else if let ItemKind::Method { type_name } = &item.kind {
+                let type_path = owner(type_name);
+                if !impls.contains_key(&type_path) {
+                    let impl_id = export.id();
+                    module_entry(&mut export, &mut module_items, &path).push(impl_id.clone().into());
+                    impls.insert(type_path.clone(), (impl_id, Vec::new()));
+                }
+                impls.get_mut(&type_path).expect("impl exists").1.push(id.clone().into());
+                path.push(type_name.clone());
+            } else {
+                module_entry(&mut export, &mut module_items, &path).push(id.clone().into());
+            }
+            path.push(item.name.clone());
+            let entry = export.item(&id, &module.file, item, inner);
+            export.add(&id, &path, kind, entry);
+        }
+
+        // Now that its members are known, fill in each trait's `inner`
+        for (id, signature, methods) in traits.into_values() {
+            if let Some(Value::Table(entry)) = export.index.get_mut(&id) {
+                entry.insert("inner".to_string(), table([("trait", table([
+                    ("signature", signature.into()),
+                    ("items", Value::Array(methods)),
+                ]))]));
+            }
+        }
+
+        // Impls have no path of their own, so they only go in the index
+        for (type_path, (id, methods)) in impls {
+            let mut entry = Table::new();
+            entry.insert("id".to_string(), id.clone().into());
+            entry.insert("crate_id".to_string(), Value::Integer(0));
+            entry.insert("visibility".to_string(), "default".into());
+            entry.insert("attrs".to_string(), Value::Array(Vec::new()));
+            entry.insert("inner".to_string(), table([("impl", table([
+                ("for", type_path.last().cloned().unwrap_or_default().into()),
+                ("items", Value::Array(methods)),
+            ]))]));
+            export.index.insert(id, Value::Table(entry));
+        }
+    }
+
+    for (path, (id, items)) in module_items {
+        let name = path.last().map_or(crate_name.to_string(), |name| name.clone());
+        let mut entry = Table::new();
+        entry.insert("id".to_string(), id.clone().into());
+        entry.insert("crate_id".to_string(), Value::Integer(0));
+        entry.insert("name".to_string(), name.into());
+        entry.insert("visibility".to_string(), "public".into());
+        entry.insert("attrs".to_string(), Value::Array(Vec::new()));
+        entry.insert("inner".to_string(), table([("module", table([
+            ("is_crate", Value::Boolean(path.is_empty())),
+            ("items", Value::Array(items)),
+        ]))]));
+        export.add(&id, &path, "module", entry);
+    }
+
+    let mut document = Table::new();
+    document.insert("root".to_string(), root.into());
+    if let Some(version) = crate_version {
+        document.insert("crate_version".to_string(), version.into());
+    }
+    document.insert("includes_private".to_string(), Value::Boolean(includes_private));
+    document.insert("index".to_string(), Value::Table(export.index));
+    document.insert("paths".to_string(), Value::Table(export.paths));
+    document.insert("external_crates".to_string(), Value::Table(Table::new()));
+    document.insert("format_version".to_string(), Value::Integer(FORMAT_VERSION));
+    json::to_string_pretty(&Value::Table(document))
+}
+
+/// The id and item list of each module, by path below the crate root
+type ModuleItems = BTreeMap<Vec<String>, (String, Vec<Value>)>;
+
+/// The item list of the module at `path`, creating it and its parents on first use
+fn module_entry<'a>(export: &mut Export, module_items: &'a mut ModuleItems, path: &[String]) -> &'a mut Vec<Value> {
+    for depth in 1..=path.len() {
+        if !module_items.contains_key(&path[..depth]) {
+            let id = export.id();
+            module_items.get_mut(&path[..depth - 1]).expect("parent module exists").1.push(id.clone().into());
+            module_items.insert(path[..depth].to_vec(), (id, Vec::new()));
+        }
+    }
+    &mut module_items.get_mut(path).expect("module exists").1
+}
+
+fn table<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Table(entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Visibility;
+
+    #[test]
+    fn test_export_nests_modules_and_trait_methods() {
+        assert_eq!(module_path(Path::new("lib.rs")), Vec::<String>::new());
+        assert_eq!(module_path(Path::new("geometry/mod.rs")), vec!["geometry"]);
+        assert_eq!(module_path(Path::new("geometry/curve.md")), vec!["geometry", "curve"]);
+
+        let code = "/// A shape\npub trait Shape {\n    fn area(&self) -> f32;\n}\npub struct Mesh;\nimpl Mesh {\n    pub fn faces(&self) -> usize { 0 }\n}\npub mod round {\n    pub struct Circle;\n}\n";
+        let modules = [Module {
+            file: PathBuf::from("src/geometry.rs"),
+            path: vec!["geometry".to_string()],
+            items: crate::parse_rust_items(code, Visibility::Pub).unwrap(),
+        }];
+        let json = to_string("demo", Some("0.1.0"), &modules, false);
+
+        assert!(json.contains(r#""root": "0:0""#));
+        assert!(json.contains(r#""path": [
+        "demo",
+        "geometry",
+        "Shape",
+        "area"
+      ]"#));
+        assert!(json.contains(r#""path": [
+        "demo",
+        "geometry",
+        "Mesh",
+        "faces"
+      ]"#));
+        assert!(json.contains(r#""for": "Mesh""#));
+        assert!(json.contains(r#""path": [
+        "demo",
+        "geometry",
+        "round",
+        "Circle"
+      ]"#));
+        assert!(json.contains(r#""docs": "A shape""#));
+        assert!(json.contains(r#""is_crate": true"#));
+    }
+}
@@ -0,0 +1,95 @@
+//! SARIF 2.1.0 output, the format code-scanning dashboards ingest
+
+use crate::json;
+use crate::plugin;
+use crate::reporter::Finding;
+use crate::rules;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF level for a rule; missing spec files are warnings, everything else errors
+pub fn level(rule: &str) -> &'static str {
+    if rule == rules::MISSING_SPEC_FILE { "warning" } else { "error" }
+}
+
+pub fn write(path: &Path, findings: &[Finding]) -> Result<()> {
+    fs::write(path, to_string(findings))
+        .with_context(|| format!("Failed to write SARIF report {}", path.display()))
+}
+
+pub fn to_string(findings: &[Finding]) -> String {
+    let mut rules: Vec<Value> = rules::RULES.iter()
+        .map(|rule| table([
+            ("id", rule.code.into()),
+            ("name", rule.name.into()),
+            ("shortDescription", table([("text", rule.summary.into())])),
+            ("fullDescription", table([("text", rule.rationale.into())])),
+        ]))
+        .collect();
+    rules.extend(plugin::registered().into_iter().map(|rule| table([
+        ("id", rule.code().into()),
+        ("name", rule.name().into()),
+        ("shortDescription", table([("text", rule.summary().into())])),
+    ])));
+
+    let driver = table([
+        ("name", "spec-check".into()),
+        ("version", env!("CARGO_PKG_VERSION").into()),
+        ("rules", Value::Array(rules)),
+    ]);
+
+    let run = table([
+        ("tool", table([("driver", driver)])),
+        ("results", Value::Array(findings.iter().map(result).collect())),
+    ]);
+
+    json::to_string_pretty(&table([
+        ("$schema", SCHEMA.into()),
+        ("version", "2.1.0".into()),
+        ("runs", Value::Array(vec![run])),
+    ]))
+}
+
+fn result(finding: &Finding) -> Value {
+    let (file, line) = finding.location();
+    let mut result = Table::new();
+    result.insert("ruleId".to_string(), finding.rule.into());
+    result.insert("level".to_string(), level(finding.rule).into());
+    result.insert("message".to_string(), table([("text", finding.message.clone().into())]));
+    result.insert("locations".to_string(), Value::Array(vec![table([("physicalLocation", physical_location(file, line, finding.column()))])]));
+    // The spec's side of a finding reported at the source file
+    if let (None, Some(spec_path), Some(spec_line)) = (&finding.spec_file, &finding.spec_path, finding.spec_line) {
+        result.insert("relatedLocations".to_string(), Value::Array(vec![table([
+            ("id", Value::Integer(1)),
+            ("message", table([("text", "spec".into())])),
+            ("physicalLocation", physical_location(spec_path, Some(spec_line), None)),
+        ])]));
+    }
+    if let Some(crate_name) = &finding.crate_name {
+        result.insert("properties".to_string(), table([("crate", crate_name.clone().into())]));
+    }
+    Value::Table(result)
+}
+
+fn physical_location(file: &Path, line: Option<usize>, column: Option<usize>) -> Value {
+    let uri = file.to_string_lossy().replace('\\', "/");
+    let mut location = Table::new();
+    location.insert("artifactLocation".to_string(), table([("uri", uri.into())]));
+    if let Some(line) = line {
+        let mut region = Table::new();
+        region.insert("startLine".to_string(), Value::Integer(line as i64));
+        if let Some(column) = column {
+            region.insert("startColumn".to_string(), Value::Integer(column as i64));
+        }
+        location.insert("region".to_string(), Value::Table(region));
+    }
+    Value::Table(location)
+}
+
+fn table<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Table(entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+}
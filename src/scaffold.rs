@@ -0,0 +1,198 @@
+//! Code stubs for the spec items the code doesn't have yet, for writing the spec first: each
+//! item as the spec declares it, with `todo!()` for function bodies
+
+use crate::config::Visibility;
+use crate::markdown_parser;
+use crate::rust_parser::{self, ItemKind, RustItem, Stage};
+use crate::skeleton;
+
+/// Rust to add to a source file for the items of its spec it's missing
+#[derive(Debug, Clone, Default)]
+pub struct Stubs {
+    /// To append to the source file; empty if there's nothing to add
+    pub code: String,
+    /// Spec items stubbed
+    pub added: Vec<RustItem>,
+    /// Missing items that can't be added as a separate item without clashing with the code,
+    /// such as members of a trait or items of an inline module it already has, or that are in
+    /// a block that isn't plain Rust
+    pub left: Vec<RustItem>,
+}
+
+/// Stubs for the items of `markdown` that aren't among `code_items`. Items a block tags
+/// `forbidden` are never stubbed.
+pub fn stubs(markdown: &str, code_items: &[RustItem], visibility: Visibility) -> Stubs {
+    let mut stubs = Stubs::default();
+    // Pieces by inline module, and methods by module and `impl` header, in spec order
+    let mut modules: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+    let mut impls: Vec<(Vec<String>, String, Vec<String>)> = Vec::new();
+    let module_exists = |path: &[String]| code_items.iter().any(|item| item.module.first() == path.first());
+
+    for block in markdown_parser::rust_blocks(markdown) {
+        if block.stage == Stage::Forbidden || block.unknown_tag.is_some() {
+            continue;
+        }
+        let Ok(declarations) = skeleton::declarations(&block.code, visibility, &[]) else {
+            let items = rust_parser::parse_spec_block(&block.code, block.line, visibility).unwrap_or_default();
+            stubs.left.extend(items.into_iter().filter(|item| !code_items.contains(item)));
+            continue;
+        };
+        for declaration in declarations {
+            let missing: Vec<RustItem> = declaration.items.iter().filter(|item| !code_items.contains(item)).cloned().collect();
+            let Some(first) = declaration.items.first() else { continue };
+            if missing.is_empty() {
+                continue;
+            }
+            // Adding the rest of a trait or a `use` again would declare what the code has twice
+            if missing.len() < declaration.items.len() || (!first.module.is_empty() && module_exists(&first.module)) {
+                stubs.left.extend(missing);
+                continue;
+            }
+            let text = with_todo(&declaration.text, &first.kind);
+            match declaration.context {
+                Some(header) => match impls.iter_mut().find(|(module, other, _)| *module == first.module && *other == header) {
+                    Some((_, _, methods)) => methods.push(text),
+                    None => impls.push((first.module.clone(), header, vec![text])),
+                },
+                None => match modules.iter_mut().find(|(module, _)| *module == first.module) {
+                    Some((_, pieces)) => pieces.push(text),
+                    None => modules.push((first.module.clone(), vec![text])),
+                },
+            }
+            stubs.added.extend(missing);
+        }
+    }
+
+    for (module, header, methods) in impls {
+        let text = format!("{} {{\n{}\n}}", header, indent(&methods.join("\n\n")));
+        match modules.iter_mut().find(|(other, _)| *other == module) {
+            Some((_, pieces)) => pieces.push(text),
+            None => modules.push((module, vec![text])),
+        }
+    }
+    let mut pieces = Vec::new();
+    for (module, items) in modules {
+        let mut text = items.join("\n\n");
+        for name in module.iter().rev() {
+            text = format!("pub mod {} {{\n{}\n}}", name, indent(&text));
+        }
+        pieces.push(text);
+    }
+    if !pieces.is_empty() {
+        stubs.code = format!("{}\n", pieces.join("\n\n"));
+    }
+    stubs
+}
+
+/// A function's declaration with `todo!()` for the body the skeleton left empty
+fn with_todo(text: &str, kind: &ItemKind) -> String {
+    let is_fn = matches!(kind, ItemKind::Function | ItemKind::Method { .. }) || (*kind == ItemKind::Macro && !text.contains("macro_rules!"));
+    match text.strip_suffix("{}") {
+        Some(signature) if is_fn => format!("{}{{\n    todo!()\n}}", signature),
+        _ => text.to_string(),
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines().map(|line| if line.is_empty() { String::new() } else { format!("    {}", line) }).collect::<Vec<_>>().join("\n")
+}
+
+/// A unified diff that appends `added` to the file at `path` whose content is `old` (`None` for a
+/// file that doesn't exist yet), as `git apply` and `patch -p1` take it
+pub fn append_patch(path: &str, old: Option<&str>, added: &str) -> String {
+    let added_lines: Vec<&str> = added.lines().collect();
+    let Some(old) = old.filter(|old| !old.is_empty()) else {
+        let mut patch = format!("--- /dev/null\n+++ b/{}\n@@ -0,0 +1,{} @@\n", path, added_lines.len());
+        added_lines.iter().for_each(|line| patch.push_str(&format!("+{}\n", line)));
+        return patch;
+    };
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let context = old_lines.len().min(3);
+    let first = old_lines.len() - context + 1;
+    // A blank line between the file's last item and the stubs
+    let separator = !old.ends_with("\n\n") as usize;
+    let mut patch = format!(
+        "--- a/{path}\n+++ b/{path}\n@@ -{first},{context} +{first},{} @@\n",
+        context + separator + added_lines.len(),
+    );
+    let (&last, before) = old_lines[first - 1..].split_last().expect("a non-empty file has a line");
+    before.iter().for_each(|line| patch.push_str(&format!(" {}\n", line)));
+    if old.ends_with('\n') {
+        patch.push_str(&format!(" {}\n", last));
+    } else {
+        // The old last line changes by getting a newline
+        patch.push_str(&format!("-{}\n\\ No newline at end of file\n+{}\n", last, last));
+    }
+    if separator == 1 {
+        patch.push_str("+\n");
+    }
+    added_lines.iter().for_each(|line| patch.push_str(&format!("+{}\n", line)));
+    patch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stubs_for_missing_items() {
+        let code = crate::parse_rust_items("pub trait Shape { fn area(&self) -> f32; }\n\npub struct Circle;\n", Visibility::Pub).unwrap();
+        let markdown = "\
+```rust
+pub struct Circle;
+
+impl Circle {
+    pub fn new(radius: f32) -> Self {}
+}
+
+pub trait Shape {
+    fn area(&self) -> f32;
+    fn perimeter(&self) -> f32;
+}
+
+#[derive(Debug)]
+pub enum Fill { Solid, Hatched }
+
+pub mod units {
+    pub fn to_mm(inches: f32) -> f32 {}
+}
+```
+
+```rust,forbidden
+pub fn unsafe_area() {}
+```
+";
+        let stubs = stubs(markdown, &code, Visibility::Pub);
+        assert_eq!(stubs.code, "\
+#[derive(Debug)]
+pub enum Fill { Solid, Hatched }
+
+impl Circle {
+    pub fn new(radius: f32) -> Self {
+        todo!()
+    }
+}
+
+pub mod units {
+    pub fn to_mm(inches: f32) -> f32 {
+        todo!()
+    }
+}
+");
+        let left: Vec<&str> = stubs.left.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(left, ["perimeter"]);
+
+        let patch = append_patch("src/shapes.rs", Some("pub struct Circle;"), "pub fn new() {}\n");
+        assert_eq!(patch, "\
+--- a/src/shapes.rs
++++ b/src/shapes.rs
+@@ -1,1 +1,3 @@
+-pub struct Circle;
+\\ No newline at end of file
++pub struct Circle;
++
++pub fn new() {}
+");
+    }
+}
@@ -0,0 +1,63 @@
+//! Private working directories under the system temp directory
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How many taken names to skip before giving up
+const ATTEMPTS: usize = 100;
+
+/// A directory created for this process alone, removed with its contents when dropped.
+/// Names end in the pid and a counter, and a name that is already taken is skipped rather
+/// than reused, so a directory someone else put there is never written to or deleted.
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    pub fn new(prefix: &str) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        for _ in 0..ATTEMPTS {
+            let name = format!("{}-{}-{}", prefix, std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+            let path = std::env::temp_dir().join(name);
+            let mut builder = fs::DirBuilder::new();
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            match builder.create(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err).with_context(|| format!("Failed to create {}", path.display())),
+            }
+        }
+        anyhow::bail!("Failed to create a {} directory in {}: every name tried is taken", prefix, std::env::temp_dir().display())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_scratch_dir_is_new_and_removed_on_drop() {
+        let first = ScratchDir::new("spec-check-scratch-test").unwrap();
+        let second = ScratchDir::new("spec-check-scratch-test").unwrap();
+        assert_ne!(first.path(), second.path());
+        fs::write(first.path().join("file"), "").unwrap();
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().is_dir());
+    }
+}
@@ -0,0 +1,196 @@
+//! The semver impact of drift, reading the spec as the released API and the code as the next
+//! version: a removed or changed item needs a major bump, an added one a minor bump, and a
+//! change to docs alone a patch.
+
+use crate::comparator::{self, ComparisonResult, FieldChange, SignatureMismatch, VariantChange};
+use crate::rust_parser::{ItemKind, RustItem};
+use crate::signature_diff;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::AddAssign;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        })
+    }
+}
+
+impl Bump {
+    /// The version after `version` with this bump, where below 1.0 a breaking change bumps the
+    /// minor version and anything else the patch version, as Cargo reads `0.y.z`
+    pub fn next(self, version: &str) -> Option<String> {
+        let core = version.split(['-', '+']).next()?;
+        let parts: Vec<u64> = core.split('.').map(str::parse).collect::<Result<_, _>>().ok()?;
+        let &[major, minor, patch] = &parts[..] else { return None };
+        let next = match (self, major) {
+            (Bump::Major, 0) => [0, minor + 1, 0],
+            (Bump::Major, _) => [major + 1, 0, 0],
+            (Bump::Minor, 0) | (Bump::Patch, _) => [major, minor, patch + 1],
+            (Bump::Minor, _) => [major, minor + 1, 0],
+        };
+        Some(format!("{}.{}.{}", next[0], next[1], next[2]))
+    }
+}
+
+/// How many changes need each bump
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Impact {
+    pub major: usize,
+    pub minor: usize,
+    pub patch: usize,
+}
+
+impl Impact {
+    /// Of a source file compared with its spec
+    pub fn of(result: &ComparisonResult, ignored_attributes: &[String]) -> Self {
+        let mut impact = Self::default();
+        for item in &result.missing_in_spec {
+            impact.add(added(item, &result.missing_in_spec));
+        }
+        impact.major += result.missing_in_code.len() + result.probable_renames.len() + result.discriminant_mismatches.len()
+            + result.value_mismatches.len() + result.abi_mismatches.len();
+        for mismatch in &result.signature_mismatches {
+            // A trait that only gained members is judged by them, which are missing from the spec
+            let members_added = mismatch.code_item.kind == ItemKind::Trait && mismatch.differences.iter().all(|difference| difference.starts_with("extra "));
+            if !members_added {
+                impact.add(changed(mismatch));
+            }
+        }
+        for mismatch in &result.attribute_mismatches {
+            // Gaining whole attributes is additive, except for `#[non_exhaustive]`
+            let old = comparator::normalize_attributes(&mismatch.spec_item.attributes, ignored_attributes);
+            let new = comparator::normalize_attributes(&mismatch.code_item.attributes, ignored_attributes);
+            let kept = old.iter().all(|attr| new.contains(attr));
+            impact.add(if kept && !is_non_exhaustive(&mismatch.code_item) { Bump::Minor } else { Bump::Major });
+        }
+        impact.minor += result.forbidden_present.len();
+        // Deprecating an item is additive, as it still works
+        impact.minor += result.deprecated_in_code.len();
+        impact.patch += result.doc_mismatches.len();
+        impact
+    }
+
+    /// Of a source file without a spec, whose `items` are all new
+    pub fn unspecced(items: usize) -> Self {
+        Self { minor: items, ..Self::default() }
+    }
+
+    /// The least bump that covers every change, if there are any
+    pub fn bump(&self) -> Option<Bump> {
+        [(self.major, Bump::Major), (self.minor, Bump::Minor), (self.patch, Bump::Patch)].into_iter()
+            .find(|&(count, _)| count > 0)
+            .map(|(_, bump)| bump)
+    }
+
+    /// As the summary shows it, with the version the bump leads to from the package's `version`
+    pub fn describe(&self, version: Option<&str>) -> String {
+        match (self.bump(), version) {
+            (Some(bump), Some(version)) => match bump.next(version) {
+                Some(next) => format!("{}, {} -> {}", self, version, next),
+                None => self.to_string(),
+            },
+            _ => self.to_string(),
+        }
+    }
+
+    fn add(&mut self, bump: Bump) {
+        match bump {
+            Bump::Major => self.major += 1,
+            Bump::Minor => self.minor += 1,
+            Bump::Patch => self.patch += 1,
+        }
+    }
+}
+
+impl AddAssign for Impact {
+    fn add_assign(&mut self, other: Self) {
+        self.major += other.major;
+        self.minor += other.minor;
+        self.patch += other.patch;
+    }
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bump() {
+            None => write!(f, "no API changes"),
+            Some(bump) => write!(f, "{} bump ({} breaking, {} additive, {} patch-level change(s))", bump, self.major, self.minor, self.patch),
+        }
+    }
+}
+
+/// An item the code has and the spec doesn't. A required item of a trait the spec has breaks
+/// its implementations; one of a new trait doesn't.
+fn added(item: &RustItem, added: &[RustItem]) -> Bump {
+    let Some(trait_name) = item.kind.owner().filter(|_| !matches!(item.kind, ItemKind::Method { .. })) else { return Bump::Minor };
+    let new_trait = added.iter().any(|other| other.kind == ItemKind::Trait && other.name == trait_name && other.module == item.module);
+    let signature = item.signature.trim_end();
+    let required = signature.ends_with(';') && (matches!(item.kind, ItemKind::TraitMethod { .. }) || !signature.contains('='));
+    if required && !new_trait { Bump::Major } else { Bump::Minor }
+}
+
+/// A changed item, which breaks its users unless a `#[non_exhaustive]` struct or enum only
+/// gained fields or variants. Renamed parameters only show in the docs.
+fn changed(mismatch: &SignatureMismatch) -> Bump {
+    if !mismatch.differences.is_empty() && mismatch.differences.iter().all(|difference| signature_diff::is_param_name(difference)) {
+        return Bump::Patch;
+    }
+    let only_fields_added = !mismatch.field_diffs.is_empty() && mismatch.field_diffs.iter().all(|diff| matches!(diff.change, FieldChange::Extra { .. }));
+    let only_variants_added = !mismatch.variant_diffs.is_empty() && mismatch.variant_diffs.iter().all(|diff| matches!(diff.change, VariantChange::Extra { .. }));
+    let extended = mismatch.differences.is_empty() && (only_fields_added || only_variants_added);
+    if extended && is_non_exhaustive(&mismatch.spec_item) { Bump::Minor } else { Bump::Major }
+}
+
+fn is_non_exhaustive(item: &RustItem) -> bool {
+    item.attributes.iter().any(|attr| attr.replace(' ', "") == "#[non_exhaustive]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impact_of_drift() {
+        let code = crate::parse_rust_items(concat!(
+            "pub trait Shape { fn area(&self) -> f32; fn name(&self) -> &str { \"\" } fn sides(&self) -> u32; }\n",
+            "pub trait Fresh { fn go(&self); }\n",
+            "#[non_exhaustive] pub enum Mode { A, B }\n",
+            "pub fn ease(t: f64) -> f64 { t }\n",
+        ), Default::default()).unwrap();
+        let spec = crate::extract_spec_items(concat!(
+            "```rust\npub trait Shape { fn area(&self) -> f32; }\n#[non_exhaustive]\npub enum Mode { A }\n",
+            "pub fn ease(t: f32) -> f32 {}\npub fn blend() {}\n```\n",
+        ), Default::default());
+        let result = crate::compare(code, spec, &[]);
+
+        // The required `sides`, the removed `blend` and the changed `ease`; `name`, `Fresh`, `go`
+        // and `Mode::B`
+        assert_eq!(Impact::of(&result, &[]), Impact { major: 3, minor: 4, patch: 0 });
+        assert_eq!(Impact::of(&result, &[]).bump(), Some(Bump::Major));
+        assert_eq!(Impact::default().bump(), None);
+        assert_eq!(Impact { minor: 1, ..Impact::default() }.describe(Some("0.4.2")), "minor bump (0 breaking, 1 additive, 0 patch-level change(s)), 0.4.2 -> 0.4.3");
+
+        // Renaming a parameter breaks no caller, unlike retyping one
+        let code = crate::parse_rust_items("pub fn ease(time: f32) -> f32 { 0.0 }\npub fn lerp(a: f32, b: f32, amount: f64) -> f32 { 0.0 }\n", Default::default()).unwrap();
+        let spec = crate::extract_spec_items("```rust\npub fn ease(t: f32) -> f32 { 0.0 }\npub fn lerp(a: f32, b: f32, t: f32) -> f32 { 0.0 }\n```\n", Default::default());
+        let renamed = crate::compare(code, spec, &[]);
+        assert_eq!(Impact::of(&renamed, &[]), Impact { major: 1, minor: 0, patch: 1 });
+
+        assert_eq!(Bump::Major.next("1.4.2").as_deref(), Some("2.0.0"));
+        assert_eq!(Bump::Minor.next("1.4.2").as_deref(), Some("1.5.0"));
+        assert_eq!(Bump::Major.next("0.4.2-alpha.1").as_deref(), Some("0.5.0"));
+        assert_eq!(Bump::Minor.next("0.4.2").as_deref(), Some("0.4.3"));
+    }
+}
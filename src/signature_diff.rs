@@ -0,0 +1,503 @@
+//! Which part of a signature differs: both sides are parsed back into `syn` trees and
+//! walked component by component, e.g. "parameter 2 type: `f32` (spec: `f64`)"
+
+use crate::comparator::{FieldChange, FieldDiff, VariantChange, VariantDiff};
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{Fields, ForeignItem, GenericParam, Generics, Item, Signature, TraitItem, Visibility, WherePredicate};
+
+/// The difference of two functions whose bodies differ
+pub const BODY_DIFFERS: &str = "body differs";
+
+/// What differs between two rendered token streams of the same item, code first. Empty when
+/// either doesn't parse or the difference is in a part that isn't walked.
+pub fn differences(code: &str, spec: &str) -> Vec<String> {
+    let (Some(code), Some(spec)) = (Parsed::parse(code), Parsed::parse(spec)) else { return Vec::new() };
+    let mut diff = Diff::default();
+    match (&code, &spec) {
+        (Parsed::Item(code), Parsed::Item(spec)) => diff.items(code, spec),
+        (Parsed::TraitItem(code), Parsed::TraitItem(spec)) => match (&**code, &**spec) {
+            (TraitItem::Fn(code), TraitItem::Fn(spec)) => diff.signatures(&code.sig, &spec.sig),
+            (TraitItem::Type(code), TraitItem::Type(spec)) => {
+                diff.tokens("bounds", &code.bounds, &spec.bounds);
+                diff.tokens("default", &code.default.as_ref().map(|(_, ty)| ty), &spec.default.as_ref().map(|(_, ty)| ty));
+            }
+            (TraitItem::Const(code), TraitItem::Const(spec)) => {
+                diff.tokens("type", &code.ty, &spec.ty);
+                diff.tokens("default", &code.default.as_ref().map(|(_, expr)| expr), &spec.default.as_ref().map(|(_, expr)| expr));
+            }
+            _ => {}
+        },
+        (Parsed::Method(code), Parsed::Method(spec)) => {
+            diff.visibility(&code.vis, &spec.vis);
+            diff.signatures(&code.sig, &spec.sig);
+        }
+        _ => {}
+    }
+    diff.0
+}
+
+/// Items are rendered whole, except methods (`pub fn new () -> Self`, no body) and trait members
+enum Parsed {
+    Item(Item),
+    TraitItem(Box<TraitItem>),
+    Method(syn::ForeignItemFn),
+}
+
+impl Parsed {
+    fn parse(tokens: &str) -> Option<Self> {
+        // syn accepts a body-less `fn` as verbatim tokens; those are trait members or methods
+        if let Ok(item) = syn::parse_str::<Item>(tokens) && !matches!(item, Item::Verbatim(_)) {
+            return Some(Parsed::Item(item));
+        }
+        if let Ok(ForeignItem::Fn(method)) = syn::parse_str::<ForeignItem>(&format!("{};", tokens)) {
+            return Some(Parsed::Method(method));
+        }
+        syn::parse_str::<TraitItem>(tokens).ok().map(|item| Parsed::TraitItem(Box::new(item)))
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Parsed::Item(item) => render(item),
+            Parsed::TraitItem(item) => render(item),
+            Parsed::Method(method) => render(method),
+        }
+    }
+
+    fn generics_mut(&mut self) -> Option<&mut Generics> {
+        match self {
+            Parsed::Item(Item::Fn(item)) => Some(&mut item.sig.generics),
+            Parsed::Item(Item::Struct(item)) => Some(&mut item.generics),
+            Parsed::Item(Item::Enum(item)) => Some(&mut item.generics),
+            Parsed::Item(Item::Union(item)) => Some(&mut item.generics),
+            Parsed::Item(Item::Trait(item)) => Some(&mut item.generics),
+            Parsed::Item(Item::Impl(item)) => Some(&mut item.generics),
+            Parsed::Item(Item::Type(item)) => Some(&mut item.generics),
+            Parsed::TraitItem(item) => match &mut **item {
+                TraitItem::Fn(item) => Some(&mut item.sig.generics),
+                TraitItem::Type(item) => Some(&mut item.generics),
+                _ => None,
+            },
+            Parsed::Method(method) => Some(&mut method.sig.generics),
+            Parsed::Item(_) => None,
+        }
+    }
+}
+
+/// The tokens of a function, method or trait method with every parameter's pattern replaced
+/// by `_`, so that only the parameters' types are compared. `None` for other items.
+pub fn without_param_names(tokens: &str) -> Option<String> {
+    let mut parsed = Parsed::parse(tokens)?;
+    let mut signatures: Vec<&mut Signature> = match &mut parsed {
+        Parsed::Item(Item::Fn(item)) => vec![&mut item.sig],
+        Parsed::Item(Item::ForeignMod(block)) => block.items.iter_mut()
+            .filter_map(|item| match item { ForeignItem::Fn(item) => Some(&mut item.sig), _ => None })
+            .collect(),
+        Parsed::TraitItem(item) => match &mut **item {
+            TraitItem::Fn(item) => vec![&mut item.sig],
+            _ => Vec::new(),
+        },
+        Parsed::Method(method) => vec![&mut method.sig],
+        Parsed::Item(_) => Vec::new(),
+    };
+    if signatures.is_empty() {
+        return None;
+    }
+    for sig in &mut signatures {
+        for input in sig.inputs.iter_mut() {
+            if let syn::FnArg::Typed(arg) = input {
+                *arg.pat = syn::parse_quote!(_);
+            }
+        }
+    }
+    Some(parsed.render())
+}
+
+/// The tokens of an item with generics, with every bound moved from the parameters into the
+/// where clause, bounds of the same type merged and everything sorted, so that
+/// `fn f<T: Clone>(x: T)` and `fn f<T>(x: T) where T: Clone` read the same. `None` for items
+/// without generics.
+pub fn with_canonical_generics(tokens: &str) -> Option<String> {
+    let mut parsed = Parsed::parse(tokens)?;
+    canonicalize_generics(parsed.generics_mut()?);
+    Some(parsed.render())
+}
+
+fn canonicalize_generics(generics: &mut Generics) {
+    let mut predicates: Vec<WherePredicate> = Vec::new();
+    for param in generics.params.iter_mut() {
+        match param {
+            GenericParam::Type(param) if !param.bounds.is_empty() => {
+                let (ident, bounds) = (&param.ident, std::mem::take(&mut param.bounds));
+                predicates.push(syn::parse_quote!(#ident: #bounds));
+                param.colon_token = None;
+            }
+            GenericParam::Lifetime(param) if !param.bounds.is_empty() => {
+                let (lifetime, bounds) = (&param.lifetime, std::mem::take(&mut param.bounds));
+                predicates.push(syn::parse_quote!(#lifetime: #bounds));
+                param.colon_token = None;
+            }
+            _ => {}
+        }
+    }
+    predicates.extend(generics.where_clause.take().into_iter().flat_map(|clause| clause.predicates));
+
+    // What is bounded (`for<'a> F`, `'a`), and the bounds on it, each once
+    let mut bounded: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for predicate in &predicates {
+        let (key, bounds) = match predicate {
+            WherePredicate::Type(predicate) => {
+                let (lifetimes, ty) = (&predicate.lifetimes, &predicate.bounded_ty);
+                (render(&quote::quote!(#lifetimes #ty)), predicate.bounds.iter().map(render).collect::<Vec<_>>())
+            }
+            WherePredicate::Lifetime(predicate) => (render(&predicate.lifetime), predicate.bounds.iter().map(render).collect()),
+            other => (render(other), Vec::new()),
+        };
+        bounded.entry(key).or_default().extend(bounds);
+    }
+    let predicates: Vec<WherePredicate> = bounded.into_iter()
+        .filter_map(|(key, bounds)| {
+            let predicate = if bounds.is_empty() { key } else { format!("{}: {}", key, bounds.into_iter().collect::<Vec<_>>().join(" + ")) };
+            syn::parse_str(&predicate).ok()
+        })
+        .collect();
+    if !predicates.is_empty() {
+        generics.where_clause = Some(syn::parse_quote!(where #(#predicates),*));
+    }
+}
+
+/// Whether `difference` is only about a parameter's name
+pub fn is_param_name(difference: &str) -> bool {
+    difference.strip_prefix("parameter ")
+        .and_then(|rest| rest.split_once(' '))
+        .is_some_and(|(position, rest)| position.parse::<usize>().is_ok() && rest.starts_with("name:"))
+}
+
+#[derive(Default)]
+struct Diff(Vec<String>);
+
+impl Diff {
+    /// Record `what` if the two render differently
+    fn tokens(&mut self, what: &str, code: &impl ToTokens, spec: &impl ToTokens) {
+        let (code, spec) = (render(code), render(spec));
+        if code != spec {
+            self.0.push(format!("{}: {} (spec: {})", what, or_none(&code), or_none(&spec)));
+        }
+    }
+
+    fn visibility(&mut self, code: &Visibility, spec: &Visibility) {
+        self.tokens("visibility", code, spec);
+    }
+
+    fn items(&mut self, code: &Item, spec: &Item) {
+        match (code, spec) {
+            (Item::Fn(code), Item::Fn(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.signatures(&code.sig, &spec.sig);
+                if render(&code.block) != render(&spec.block) {
+                    self.0.push(BODY_DIFFERS.to_string());
+                }
+            }
+            (Item::Struct(code), Item::Struct(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("generics", &code.generics, &spec.generics);
+                self.tokens("where clause", &code.generics.where_clause, &spec.generics.where_clause);
+                self.shape("", &code.fields, &spec.fields);
+            }
+            (Item::Union(code), Item::Union(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("generics", &code.generics, &spec.generics);
+            }
+            (Item::Enum(code), Item::Enum(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("generics", &code.generics, &spec.generics);
+                let names = |e: &syn::ItemEnum| e.variants.iter().map(|v| v.ident.to_string()).collect::<Vec<_>>();
+                let (code_names, spec_names) = (names(code), names(spec));
+                let common = |names: &[String], other: &[String]| names.iter().filter(|n| other.contains(n)).cloned().collect::<Vec<_>>();
+                if self.0.is_empty() && variant_diffs(code, spec).is_empty()
+                    && common(&code_names, &spec_names) != common(&spec_names, &code_names) {
+                    self.0.push("variants are in a different order".to_string());
+                }
+            }
+            (Item::Trait(code), Item::Trait(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("unsafety", &code.unsafety, &spec.unsafety);
+                self.tokens("generics", &code.generics, &spec.generics);
+                self.tokens("supertraits", &code.supertraits, &spec.supertraits);
+                let member = |item: &TraitItem| match item {
+                    TraitItem::Fn(f) => Some(format!("method `{}`", f.sig.ident)),
+                    TraitItem::Type(t) => Some(format!("associated type `{}`", t.ident)),
+                    TraitItem::Const(c) => Some(format!("associated const `{}`", c.ident)),
+                    _ => None,
+                };
+                let (code_members, spec_members): (Vec<_>, Vec<_>) =
+                    (code.items.iter().filter_map(member).collect(), spec.items.iter().filter_map(member).collect());
+                for name in spec_members.iter().filter(|m| !code_members.contains(m)) {
+                    self.0.push(format!("missing {}", name));
+                }
+                for name in code_members.iter().filter(|m| !spec_members.contains(m)) {
+                    self.0.push(format!("extra {}", name));
+                }
+                // Members in both are items of their own, compared on their own
+            }
+            (Item::Const(code), Item::Const(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("type", &code.ty, &spec.ty);
+                self.tokens("value", &code.expr, &spec.expr);
+            }
+            (Item::Static(code), Item::Static(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("mutability", &code.mutability, &spec.mutability);
+                self.tokens("type", &code.ty, &spec.ty);
+                self.tokens("value", &code.expr, &spec.expr);
+            }
+            (Item::Use(code), Item::Use(spec)) => {
+                self.visibility(&code.vis, &spec.vis);
+                self.tokens("path", &code.tree, &spec.tree);
+            }
+            (Item::ForeignMod(code), Item::ForeignMod(spec)) => {
+                self.tokens("ABI", &code.abi, &spec.abi);
+                match (code.items.first(), spec.items.first()) {
+                    (Some(ForeignItem::Fn(code)), Some(ForeignItem::Fn(spec))) => {
+                        self.visibility(&code.vis, &spec.vis);
+                        self.signatures(&code.sig, &spec.sig);
+                    }
+                    (Some(ForeignItem::Static(code)), Some(ForeignItem::Static(spec))) => {
+                        self.visibility(&code.vis, &spec.vis);
+                        self.tokens("mutability", &code.mutability, &spec.mutability);
+                        self.tokens("type", &code.ty, &spec.ty);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn signatures(&mut self, code: &Signature, spec: &Signature) {
+        self.tokens("constness", &code.constness, &spec.constness);
+        self.tokens("asyncness", &code.asyncness, &spec.asyncness);
+        self.tokens("unsafety", &code.unsafety, &spec.unsafety);
+        self.tokens("ABI", &code.abi, &spec.abi);
+        self.tokens("generics", &code.generics, &spec.generics);
+        self.tokens("where clause", &code.generics.where_clause, &spec.generics.where_clause);
+
+        if code.inputs.len() != spec.inputs.len() {
+            self.0.push(format!("{} parameter(s) (spec: {})", code.inputs.len(), spec.inputs.len()));
+        }
+        for (index, (code_arg, spec_arg)) in code.inputs.iter().zip(&spec.inputs).enumerate() {
+            let position = index + 1;
+            match (code_arg, spec_arg) {
+                (syn::FnArg::Typed(code_arg), syn::FnArg::Typed(spec_arg)) => {
+                    self.tokens(&format!("parameter {} name", position), &code_arg.pat, &spec_arg.pat);
+                    self.tokens(&format!("parameter {} type", position), &code_arg.ty, &spec_arg.ty);
+                }
+                _ => self.tokens(&format!("parameter {}", position), code_arg, spec_arg),
+            }
+        }
+        self.tokens("variadic", &code.variadic, &spec.variadic);
+        if render(&code.output) != render(&spec.output) {
+            self.0.push(format!("return type: {} (spec: {})", return_type(&code.output), return_type(&spec.output)));
+        }
+    }
+
+    /// Whether both have the same kind of fields, recording it if not
+    fn shape(&mut self, prefix: &str, code: &Fields, spec: &Fields) -> bool {
+        let same = shape(code) == shape(spec);
+        if !same {
+            self.0.push(format!("{}fields are {} (spec: {})", prefix, shape(code), shape(spec)));
+        }
+        same
+    }
+}
+
+/// Variant by variant differences of two enums, from their rendered token streams
+pub fn enum_variant_diffs(code: &str, spec: &str) -> Vec<VariantDiff> {
+    match (syn::parse_str::<Item>(code), syn::parse_str::<Item>(spec)) {
+        (Ok(Item::Enum(code)), Ok(Item::Enum(spec))) => variant_diffs(&code, &spec),
+        _ => Vec::new(),
+    }
+}
+
+/// Variants missing from the code, extra in it, or with a different payload or discriminant,
+/// in spec order then the extra ones
+fn variant_diffs(code: &syn::ItemEnum, spec: &syn::ItemEnum) -> Vec<VariantDiff> {
+    let mut diffs = Vec::new();
+    let diff = |variant: &syn::Variant, change| VariantDiff { variant: variant.ident.to_string(), change };
+    for variant in &spec.variants {
+        let Some(code_variant) = code.variants.iter().find(|v| v.ident == variant.ident) else {
+            diffs.push(diff(variant, VariantChange::Missing { spec: render(variant) }));
+            continue;
+        };
+        if payload(&code_variant.fields) != payload(&variant.fields) {
+            diffs.push(diff(variant, VariantChange::Payload {
+                code: payload(&code_variant.fields).to_string(),
+                spec: payload(&variant.fields).to_string(),
+            }));
+        } else {
+            let fields = field_diffs(&code_variant.fields, &variant.fields);
+            if !fields.is_empty() {
+                diffs.push(diff(variant, VariantChange::Fields(fields)));
+            }
+        }
+        let discriminant = |v: &syn::Variant| v.discriminant.as_ref().map(|(_, expr)| render(expr)).unwrap_or_default();
+        if discriminant(code_variant) != discriminant(variant) {
+            diffs.push(diff(variant, VariantChange::Discriminant { code: discriminant(code_variant), spec: discriminant(variant) }));
+        }
+    }
+    for variant in code.variants.iter().filter(|v| !spec.variants.iter().any(|s| s.ident == v.ident)) {
+        diffs.push(diff(variant, VariantChange::Extra { code: render(variant) }));
+    }
+    diffs
+}
+
+/// Field by field differences of two structs or unions, from their rendered token streams.
+/// Empty unless both are structs (or unions) with the same kind of fields.
+pub fn struct_field_diffs(code: &str, spec: &str) -> Vec<FieldDiff> {
+    match (syn::parse_str::<Item>(code), syn::parse_str::<Item>(spec)) {
+        (Ok(Item::Struct(code)), Ok(Item::Struct(spec))) => field_diffs(&code.fields, &spec.fields),
+        (Ok(Item::Union(code)), Ok(Item::Union(spec))) => field_diffs(&Fields::Named(code.fields), &Fields::Named(spec.fields)),
+        _ => Vec::new(),
+    }
+}
+
+/// Fields missing from the code, extra in it, or declared differently, in spec order then
+/// the extra ones. Tuple fields are matched by position.
+pub fn field_diffs(code: &Fields, spec: &Fields) -> Vec<FieldDiff> {
+    let fields = |fields: &Fields| -> Vec<(String, syn::Field)> {
+        fields.iter().enumerate()
+            .map(|(index, field)| (field.ident.as_ref().map_or_else(|| index.to_string(), |ident| ident.to_string()), field.clone()))
+            .collect()
+    };
+    if shape(code) != shape(spec) {
+        return Vec::new();
+    }
+    let (code, spec) = (fields(code), fields(spec));
+    let mut diffs = Vec::new();
+    for (name, field) in &spec {
+        let Some((_, code_field)) = code.iter().find(|(code_name, _)| code_name == name) else {
+            diffs.push(FieldDiff { field: name.clone(), change: FieldChange::Missing { spec: render(field) } });
+            continue;
+        };
+        if render(&code_field.ty) != render(&field.ty) {
+            diffs.push(FieldDiff { field: name.clone(), change: FieldChange::Type { code: render(&code_field.ty), spec: render(&field.ty) } });
+        }
+        if render(&code_field.vis) != render(&field.vis) {
+            diffs.push(FieldDiff { field: name.clone(), change: FieldChange::Visibility { code: render(&code_field.vis), spec: render(&field.vis) } });
+        }
+    }
+    for (name, field) in code.iter().filter(|(name, _)| !spec.iter().any(|(spec_name, _)| spec_name == name)) {
+        diffs.push(FieldDiff { field: name.clone(), change: FieldChange::Extra { code: render(field) } });
+    }
+    diffs
+}
+
+fn render(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string()
+}
+
+pub(crate) fn or_none(rendered: &str) -> String {
+    if rendered.is_empty() { "none".to_string() } else { format!("`{}`", rendered) }
+}
+
+fn return_type(output: &syn::ReturnType) -> String {
+    match output {
+        syn::ReturnType::Default => "`()`".to_string(),
+        syn::ReturnType::Type(_, ty) => format!("`{}`", render(ty)),
+    }
+}
+
+/// The kind of variant `fields` make
+fn payload(fields: &Fields) -> &'static str {
+    match fields {
+        Fields::Named(_) => "struct",
+        Fields::Unnamed(_) => "tuple",
+        Fields::Unit => "unit",
+    }
+}
+
+fn shape(fields: &Fields) -> &'static str {
+    match fields {
+        Fields::Named(_) => "named",
+        Fields::Unnamed(_) => "a tuple",
+        Fields::Unit => "absent",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_differences_name_the_component() {
+        assert_eq!(
+            differences("pub fn lerp (a : f32 , t : f32) -> f32 { }", "pub fn lerp (a : f32 , t : f64) { }"),
+            ["parameter 2 type: `f32` (spec: `f64`)", "return type: `f32` (spec: `()`)"],
+        );
+        assert!(differences("pub struct Button { color : u32 }", "pub struct Button { pub color : u32 }").is_empty());
+        assert_eq!(differences("pub struct Id (u32) ;", "pub struct Id { pub value : u32 }"), ["fields are a tuple (spec: named)"]);
+        assert_eq!(differences("pub fn new (x : u8) -> Self", "pub fn new () -> Self"), ["1 parameter(s) (spec: 0)"]);
+        assert_eq!(differences("fn area (& self) -> f32 ;", "fn area (& mut self) -> f32 ;"), ["parameter 1: `& self` (spec: `& mut self`)"]);
+        assert!(differences("pub enum E { A , B (u8) , C }", "pub enum E { A , B (u16) }").is_empty());
+        assert!(differences("macro_rules ! animate", "macro_rules ! animate").is_empty());
+    }
+
+    #[test]
+    fn test_with_canonical_generics() {
+        let canonical = |tokens: &str| with_canonical_generics(&tokens.parse::<proc_macro2::TokenStream>().unwrap().to_string()).unwrap();
+        assert_eq!(canonical("fn f<T: Clone>(x: T) {}"), canonical("fn f<T>(x: T) where T: Clone {}"));
+        assert_eq!(canonical("fn f<T: Clone + Debug, U>(x: T, y: U) where U: Send {}"), canonical("fn f<T, U>(x: T, y: U) where U: Send, T: Debug, T: Clone {}"));
+        assert_eq!(canonical("pub struct Pool<'a, T: 'a> { items: &'a [T] }"), canonical("pub struct Pool<'a, T> where T: 'a { items: &'a [T] }"));
+        assert_ne!(canonical("fn f<T: Clone>(x: T) {}"), canonical("fn f<T: Copy>(x: T) {}"));
+        assert!(with_canonical_generics("pub const N : usize = 1 ;").is_none());
+    }
+
+    #[test]
+    fn test_without_param_names() {
+        let erased = |tokens: &str| without_param_names(tokens).unwrap();
+        assert_eq!(erased("pub fn sample (& self , resolution : usize)"), erased("pub fn sample (& self , n : usize)"));
+        assert_ne!(erased("pub fn sample (& self , n : usize)"), erased("pub fn sample (& self , n : u32)"));
+        assert_eq!(erased("fn area (& self , (w , h) : (f32 , f32)) -> f32 ;"), erased("fn area (& self , size : (f32 , f32)) -> f32 ;"));
+        assert_eq!(erased("extern \"C\" { pub fn mix (a : u8) ; }"), erased("extern \"C\" { pub fn mix (b : u8) ; }"));
+        assert!(without_param_names("pub struct Frame ;").is_none());
+
+        assert!(is_param_name("parameter 2 name: `resolution` (spec: `n`)"));
+        assert!(!is_param_name("parameter 2 type: `usize` (spec: `u32`)"));
+    }
+
+    #[test]
+    fn test_struct_field_diffs() {
+        let diffs = struct_field_diffs(
+            "pub struct Button { pub label : String , color : u32 , pub pressed : bool }",
+            "pub struct Button { pub label : Cow < 'static , str > , pub color : u32 , pub hover_color : u32 }",
+        );
+        let rendered: Vec<String> = diffs.iter().map(|diff| diff.to_string()).collect();
+        assert_eq!(rendered, [
+            "field `label` type: `String` (spec: `Cow < 'static , str >`)",
+            "field `color` visibility: none (spec: `pub`)",
+            "missing field `hover_color` (spec: `pub hover_color : u32`)",
+            "extra field `pressed` (`pub pressed : bool`)",
+        ]);
+        assert_eq!(diffs[2].change, FieldChange::Missing { spec: "pub hover_color : u32".to_string() });
+
+        assert_eq!(struct_field_diffs("pub struct Id (pub u32 , u8) ;", "pub struct Id (pub u64) ;").len(), 2);
+    }
+
+    #[test]
+    fn test_enum_variant_diffs() {
+        let diffs = enum_variant_diffs(
+            "pub enum MathAnimationError { Parse (String) , Timeout { ms : u64 } , Io = 3 , Overflow }",
+            "pub enum MathAnimationError { Parse { line : usize } , Timeout { ms : u32 } , Io = 4 , Empty }",
+        );
+        let rendered: Vec<String> = diffs.iter().map(|diff| diff.to_string()).collect();
+        assert_eq!(rendered, [
+            "variant `Parse` is a tuple variant (spec: struct)",
+            "variant `Timeout`: field `ms` type: `u64` (spec: `u32`)",
+            "variant `Io` discriminant: `3` (spec: `4`)",
+            "missing variant `Empty`",
+            "extra variant `Overflow`",
+        ]);
+        assert!(enum_variant_diffs("pub enum E { A , B }", "pub enum E { A , B }").is_empty());
+        assert_eq!(differences("pub enum E { B , A }", "pub enum E { A , B }"), ["variants are in a different order"]);
+    }
+}
@@ -0,0 +1,385 @@
+//! Skeleton specs for adopting spec-check on existing code: every public item of a source file
+//! as the code declares it, with function bodies emptied, under a heading per inline module
+
+use crate::config;
+use crate::rust_parser::{self, RustItem};
+use anyhow::Result;
+use proc_macro2::{LineColumn, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::ops::Range;
+use syn::{Attribute, ForeignItem, ImplItem, Item, Visibility};
+
+/// The skeleton spec of `source`, headed by `title` (e.g. the source file's path). Attributes
+/// named in `ignored_attributes` and doc comments are left out.
+pub fn to_markdown(source: &str, title: &str, visibility: config::Visibility, ignored_attributes: &[String]) -> Result<String> {
+    let file = syn::parse_file(source)?;
+    let writer = Writer { text: source, line_starts: line_starts(source), visibility, ignored_attributes };
+    let mut out = format!("# `{}`\n", title);
+    writer.section(&mut out, &file.items, &mut Vec::new());
+    Ok(out)
+}
+
+/// An item of `source` as its skeleton declares it, and where it is in `source`
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    /// What spec-check makes of it, e.g. a trait and its methods
+    pub items: Vec<RustItem>,
+    pub text: String,
+    /// The header of the `impl` block, for a method
+    pub context: Option<String>,
+    /// Where a function's body is, braces included
+    pub body: Option<Range<usize>>,
+    /// From its first attribute the skeleton keeps, so doc comments before that are left out
+    pub range: Range<usize>,
+}
+
+/// The declarations of `source`'s items, those of inline modules and inherent methods included.
+/// `source` may be a spec block, with placeholders.
+pub fn declarations(source: &str, visibility: config::Visibility, ignored_attributes: &[String]) -> Result<Vec<Declaration>> {
+    let file = syn::parse_file(source)?;
+    let writer = Writer { text: source, line_starts: line_starts(source), visibility, ignored_attributes };
+    let mut out = Vec::new();
+    writer.declarations(&mut out, &file.items, &mut Vec::new());
+    Ok(out)
+}
+
+/// A field, variant or trait member of a declaration's text
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// Its name, or its index for a tuple field
+    pub name: String,
+    /// Its doc comments as written, one per line
+    pub docs: Vec<String>,
+    /// Where the line it starts on starts, doc comments left out
+    pub line_start: usize,
+}
+
+/// The members of the item declared in `text`, if it's a struct, enum, union or trait
+pub fn members(text: &str) -> Vec<Member> {
+    let Ok(item) = syn::parse_str::<Item>(text) else { return Vec::new() };
+    let writer = Writer { text, line_starts: line_starts(text), visibility: config::Visibility::All, ignored_attributes: &[] };
+    let fields = |fields: &syn::Fields| -> Vec<(String, Vec<Attribute>, TokenStream)> {
+        fields.iter().enumerate()
+            .map(|(index, field)| (field.ident.as_ref().map_or_else(|| index.to_string(), |ident| ident.to_string()), field.attrs.clone(), field.to_token_stream()))
+            .collect()
+    };
+    let members = match &item {
+        Item::Struct(node) => fields(&node.fields),
+        Item::Union(node) => fields(&syn::Fields::Named(node.fields.clone())),
+        Item::Enum(node) => node.variants.iter().map(|variant| (variant.ident.to_string(), variant.attrs.clone(), variant.to_token_stream())).collect(),
+        Item::Trait(node) => node.items.iter().filter_map(|member| {
+            let (ident, attrs) = match member {
+                syn::TraitItem::Fn(method) => (&method.sig.ident, &method.attrs),
+                syn::TraitItem::Type(ty) => (&ty.ident, &ty.attrs),
+                syn::TraitItem::Const(constant) => (&constant.ident, &constant.attrs),
+                _ => return None,
+            };
+            Some((ident.to_string(), attrs.clone(), member.to_token_stream()))
+        }).collect(),
+        _ => Vec::new(),
+    };
+    members.into_iter().filter_map(|(name, attrs, tokens)| {
+        let start = writer.bounds(&tokens)?.start;
+        let docs = attrs.iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .filter_map(|attr| writer.bounds(&attr.to_token_stream()))
+            .flat_map(|bounds| text[bounds].lines().map(|line| line.trim().to_string()).collect::<Vec<_>>())
+            .collect();
+        Some(Member { name, docs, line_start: text[..start].rfind('\n').map_or(0, |at| at + 1) })
+    }).collect()
+}
+
+struct Writer<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    visibility: config::Visibility,
+    ignored_attributes: &'a [String],
+}
+
+impl Writer<'_> {
+    /// A block of the items of one module, then a section per inline module in it. `modules`
+    /// holds the headers of the enclosing inline modules, outermost first, with their names.
+    fn section(&self, out: &mut String, items: &[Item], modules: &mut Vec<(String, String)>) {
+        let pieces: Vec<String> = items.iter().filter_map(|item| self.piece(item)).collect();
+        if !pieces.is_empty() {
+            if !modules.is_empty() {
+                let path: Vec<&str> = modules.iter().map(|(_, name)| name.as_str()).collect();
+                out.push_str(&format!("\n## `{}`\n", path.join("::")));
+            }
+            let mut block = pieces.join("\n\n");
+            // Items of inline modules are only theirs inside a `mod` of the same name
+            for (header, _) in modules.iter().rev() {
+                block = format!("{} {{\n{}\n}}", header, indent(&block));
+            }
+            out.push_str(&format!("\n```rust\n{}\n```\n", block));
+        }
+        for item in items {
+            let Item::Mod(module) = item else { continue };
+            let Some((brace, items)) = &module.content else { continue };
+            let header = self.slice(&module.to_token_stream(), Some(brace.span.open().start()), &[]);
+            modules.push((header.trim_end().to_string(), module.ident.to_string()));
+            self.section(out, items, modules);
+            modules.pop();
+        }
+    }
+
+    fn declarations(&self, out: &mut Vec<Declaration>, items: &[Item], module: &mut Vec<String>) {
+        for item in items {
+            match item {
+                Item::Mod(node) => {
+                    let Some((_, items)) = &node.content else { continue };
+                    module.push(node.ident.to_string());
+                    self.declarations(out, items, module);
+                    module.pop();
+                }
+                Item::Impl(node) if node.trait_.is_none() => {
+                    let header = self.slice(&item.to_token_stream(), Some(node.brace_token.span.open().start()), &[]);
+                    for method in &node.items {
+                        let ImplItem::Fn(method) = method else { continue };
+                        if !self.includes(&method.vis) {
+                            continue;
+                        }
+                        let tokens = method.to_token_stream();
+                        let text = self.slice(&tokens, None, &[braces(&method.block.brace_token)]);
+                        let declaration = self.declaration(module, &method.attrs, &tokens, self.with_attributes(&method.attrs, text), Some(header.trim_end()));
+                        out.extend(declaration.map(|declaration| Declaration { body: Some(self.braces(&method.block)), ..declaration }));
+                    }
+                }
+                _ => {
+                    let Some((attrs, text)) = self.declare(item) else { continue };
+                    let declaration = self.declaration(module, attrs, &item.to_token_stream(), self.with_attributes(attrs, text), None);
+                    let body = match item {
+                        Item::Fn(node) => Some(self.braces(&node.block)),
+                        _ => None,
+                    };
+                    out.extend(declaration.map(|declaration| Declaration { body, ..declaration }));
+                }
+            }
+        }
+    }
+
+    /// The declaration of the item in `tokens`, inside the `impl` block of `context` for methods
+    fn declaration(&self, module: &[String], attrs: &[Attribute], tokens: &TokenStream, text: String, context: Option<&str>) -> Option<Declaration> {
+        let keyed = context.map_or_else(|| text.clone(), |header| format!("{} {{\n{}\n}}", header, text));
+        let mut items = rust_parser::parse_spec_block(&keyed, 1, self.visibility).ok()?;
+        items.iter_mut().for_each(|item| item.module = module.to_vec());
+        let end = self.bounds(tokens)?.end;
+        let start = attrs.iter().filter(|attr| self.keeps(attr))
+            .filter_map(|attr| self.bounds(&attr.to_token_stream()))
+            .chain(self.bounds(tokens))
+            .map(|bounds| bounds.start)
+            .min()?;
+        Some(Declaration { items, text, context: context.map(str::to_string), body: None, range: start..end })
+    }
+
+    /// How the spec declares an item, if it's one spec-check compares
+    fn piece(&self, item: &Item) -> Option<String> {
+        let (attrs, text) = self.declare(item)?;
+        Some(self.with_attributes(attrs, text))
+    }
+
+    /// An item's attributes and its text after them, if it's one spec-check compares
+    fn declare<'i>(&self, item: &'i Item) -> Option<(&'i [Attribute], String)> {
+        let tokens = item.to_token_stream();
+        let (attrs, text) = match item {
+            Item::Fn(node) if self.includes(&node.vis) => (&node.attrs, self.slice(&tokens, None, &[braces(&node.block.brace_token)])),
+            Item::Struct(node) if self.includes(&node.vis) => (&node.attrs, self.slice(&tokens, None, &[])),
+            Item::Enum(node) if self.includes(&node.vis) => (&node.attrs, self.slice(&tokens, None, &[])),
+            Item::Union(node) if self.includes(&node.vis) => (&node.attrs, self.slice(&tokens, None, &[])),
+            Item::Const(node) if self.includes(&node.vis) && node.ident != "_" => (&node.attrs, self.slice(&tokens, None, &[])),
+            Item::Static(node) if self.includes(&node.vis) => (&node.attrs, self.slice(&tokens, None, &[])),
+            Item::Use(node) if !matches!(node.vis, Visibility::Inherited) && self.includes(&node.vis) => {
+                (&node.attrs, self.slice(&tokens, None, &[]))
+            }
+            // Whole, default method bodies included, as they're part of what's compared
+            Item::Trait(node) if self.includes(&node.vis) => (&node.attrs, self.slice(&tokens, None, &[])),
+            Item::Macro(node) if node.ident.is_some() && node.mac.path.is_ident("macro_rules") => {
+                let exported = node.attrs.iter().any(|attr| attr.path().is_ident("macro_export"));
+                if !exported && self.visibility != config::Visibility::All {
+                    return None;
+                }
+                (&node.attrs, self.slice(&tokens, None, &[]))
+            }
+            Item::ForeignMod(node) => {
+                let public = node.items.iter().any(|item| match item {
+                    ForeignItem::Fn(f) => self.includes(&f.vis),
+                    ForeignItem::Static(s) => self.includes(&s.vis),
+                    _ => false,
+                });
+                if !public {
+                    return None;
+                }
+                (&node.attrs, self.slice(&tokens, None, &[]))
+            }
+            Item::Impl(node) if node.trait_.is_none() => {
+                let methods: Vec<String> = node.items.iter().filter_map(|item| match item {
+                    ImplItem::Fn(method) if self.includes(&method.vis) => {
+                        let text = self.slice(&method.to_token_stream(), None, &[braces(&method.block.brace_token)]);
+                        Some(self.with_attributes(&method.attrs, text))
+                    }
+                    _ => None,
+                }).collect();
+                if methods.is_empty() {
+                    return None;
+                }
+                let header = self.slice(&tokens, Some(node.brace_token.span.open().start()), &[]);
+                let text = format!("{} {{\n{}\n}}", header.trim_end(), indent(&methods.join("\n\n")));
+                (&node.attrs, text)
+            }
+            _ => return None,
+        };
+        Some((attrs, text))
+    }
+
+    fn includes(&self, vis: &Visibility) -> bool {
+        rust_parser::is_included(vis, self.visibility)
+    }
+
+    /// An item's text after the attributes the spec should repeat
+    fn with_attributes(&self, attrs: &[Attribute], text: String) -> String {
+        let mut lines: Vec<String> = attrs.iter()
+            .filter(|attr| self.keeps(attr))
+            .map(|attr| self.slice(&attr.to_token_stream(), None, &[]))
+            .collect();
+        lines.push(text);
+        lines.join("\n")
+    }
+
+    fn braces(&self, block: &syn::Block) -> Range<usize> {
+        let (open, close) = braces(&block.brace_token);
+        self.offset(open)..self.offset(close)
+    }
+
+    fn keeps(&self, attr: &Attribute) -> bool {
+        !attr.path().is_ident("doc") && !self.ignored_attributes.iter().any(|name| attr.path().is_ident(name))
+    }
+
+    /// Where the text of `tokens` is, leading attributes left out
+    fn bounds(&self, tokens: &TokenStream) -> Option<Range<usize>> {
+        let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+        let mut first = 0;
+        // `#` and `[...]` of each outer attribute, doc comments included
+        while first + 2 < trees.len()
+            && matches!(&trees[first], TokenTree::Punct(punct) if punct.as_char() == '#')
+            && matches!(trees[first + 1], TokenTree::Group(_))
+        {
+            first += 2;
+        }
+        let (start, last) = (trees.get(first)?, trees.last()?);
+        Some(self.offset(start.span().start())..self.offset(last.span().end()))
+    }
+
+    /// The source text of `tokens`, leading attributes left out, up to `end` if given, with
+    /// each of `holes` replaced by `{}` and the common indentation removed
+    fn slice(&self, tokens: &TokenStream, end: Option<LineColumn>, holes: &[(LineColumn, LineColumn)]) -> String {
+        let Some(bounds) = self.bounds(tokens) else { return String::new() };
+        let mut from = bounds.start;
+        let to = end.map_or(bounds.end, |end| self.offset(end));
+        // From the start of the line when only indentation comes before, so that it's removed
+        // like that of the lines after
+        let line_start = self.text[..from].rfind('\n').map_or(0, |at| at + 1);
+        if self.text[line_start..from].trim().is_empty() {
+            from = line_start;
+        }
+
+        let mut text = String::new();
+        let mut at = from;
+        for &(open, close) in holes {
+            let (open, close) = (self.offset(open), self.offset(close));
+            if open < at || close > to {
+                continue;
+            }
+            text.push_str(&self.text[at..open]);
+            text.push_str("{}");
+            at = close;
+        }
+        text.push_str(&self.text[at..to]);
+        dedent(&text)
+    }
+
+    /// The byte offset of a line and column, as spans give them (1-based lines, columns in chars)
+    fn offset(&self, at: LineColumn) -> usize {
+        let Some(&line_start) = self.line_starts.get(at.line.saturating_sub(1)) else { return self.text.len() };
+        self.text[line_start..].char_indices().nth(at.column).map_or(self.text.len(), |(index, _)| line_start + index)
+    }
+}
+
+fn braces(brace: &syn::token::Brace) -> (LineColumn, LineColumn) {
+    (brace.span.open().start(), brace.span.close().end())
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0).chain(text.match_indices('\n').map(|(at, _)| at + 1)).collect()
+}
+
+fn dedent(text: &str) -> String {
+    let width = text.lines().filter(|line| !line.trim().is_empty()).map(|line| line.len() - line.trim_start().len()).min().unwrap_or(0);
+    text.lines().map(|line| line.get(width..).unwrap_or(line.trim_start())).collect::<Vec<_>>().join("\n")
+}
+
+fn indent(text: &str) -> String {
+    text.lines().map(|line| if line.is_empty() { String::new() } else { format!("    {}", line) }).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skeleton_keeps_declarations_and_empties_bodies() {
+        let source = "\
+/// A curve
+#[derive(Debug, Clone)]
+pub struct Curve {
+    pub points: Vec<f32>,
+}
+
+impl Curve {
+    /// Samples it
+    pub fn sample(&self, n: usize) -> Vec<f32> {
+        self.points.iter().take(n).copied().collect()
+    }
+
+    fn private(&self) {}
+}
+
+fn helper() {}
+
+pub mod math {
+    pub fn lerp(a: f32,
+                b: f32) -> f32 { a + b }
+}
+";
+        let markdown = to_markdown(source, "src/curve.rs", config::Visibility::Pub, &["doc".to_string()]).unwrap();
+        assert_eq!(markdown, "\
+# `src/curve.rs`
+
+```rust
+#[derive(Debug, Clone)]
+pub struct Curve {
+    pub points: Vec<f32>,
+}
+
+impl Curve {
+    pub fn sample(&self, n: usize) -> Vec<f32> {}
+}
+```
+
+## `math`
+
+```rust
+pub mod math {
+    pub fn lerp(a: f32,
+                b: f32) -> f32 {}
+}
+```
+");
+
+        // The skeleton describes the code exactly
+        let code = crate::parse_rust_items(source, config::Visibility::Pub).unwrap();
+        let spec = crate::extract_spec_items(&markdown, config::Visibility::Pub);
+        let mut result = crate::compare(code, spec, &["doc".to_string()]);
+        crate::comparator::relax(&mut result, &crate::CompareOptions::default(), &[]);
+        assert!(!result.has_errors());
+    }
+}
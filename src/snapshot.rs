@@ -0,0 +1,79 @@
+//! Snapshots of a crate's parsed items, written by `spec-check snapshot` and compared with the
+//! current code by `spec-check diff`, so releases can be diffed without a spec
+
+use crate::config::PackageInfo;
+use crate::json;
+use crate::rust_parser::RustItem;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Bumped when items are recorded differently
+const VERSION: i64 = 1;
+
+/// The items of each source file
+pub type Items = BTreeMap<PathBuf, Vec<RustItem>>;
+
+/// The snapshot of `items`, with the package they're from, as JSON
+pub fn to_json(items: &Items, package: Option<&PackageInfo>) -> Result<String> {
+    let mut files = Table::new();
+    for (file, file_items) in items {
+        let file_items = file_items.iter().map(Value::try_from).collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Failed to record the items of {}", file.display()))?;
+        files.insert(file.to_string_lossy().replace('\\', "/"), Value::Array(file_items));
+    }
+    let mut root = Table::new();
+    root.insert("version".to_string(), Value::Integer(VERSION));
+    if let Some(package) = package {
+        root.insert("package".to_string(), package.name.clone().into());
+        root.insert("package_version".to_string(), package.version.clone().into());
+    }
+    root.insert("files".to_string(), Value::Table(files));
+    Ok(json::to_string_pretty(&Value::Table(root)))
+}
+
+pub fn from_json(content: &str) -> Result<Items> {
+    let root = json::parse(content)?;
+    let version = root.get("version").and_then(Value::as_integer);
+    if version != Some(VERSION) {
+        anyhow::bail!("Unsupported snapshot version {:?}; write it again with `spec-check snapshot`", version);
+    }
+    let mut items = Items::new();
+    for (file, file_items) in root.get("files").and_then(Value::as_table).into_iter().flatten() {
+        let file_items = file_items.as_array().with_context(|| format!("Snapshot entry for {} isn't a list of items", file))?;
+        let file_items = file_items.iter().cloned().map(Value::try_into).collect::<Result<Vec<RustItem>, _>>()
+            .with_context(|| format!("Failed to read the items of {}", file))?;
+        items.insert(PathBuf::from(file), file_items);
+    }
+    Ok(items)
+}
+
+pub fn load(path: &Path) -> Result<Items> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+    from_json(&content).with_context(|| format!("Failed to parse snapshot {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let code = "/// Eases\npub fn ease(t: f32) -> f32 { t }\npub mod shapes { pub trait Shape { fn area(&self) -> f32; } }\n#[derive(Debug)]\npub enum Mode { A = 1 }\n";
+        let items = Items::from([(PathBuf::from("src/lib.rs"), crate::parse_rust_items(code, Default::default()).unwrap())]);
+        let package = PackageInfo { name: "anim".to_string(), version: "0.3.1".to_string() };
+        let json = to_json(&items, Some(&package)).unwrap();
+        assert!(json.contains("\"package_version\": \"0.3.1\""));
+
+        let read = from_json(&json).unwrap();
+        assert_eq!(read.keys().collect::<Vec<_>>(), [Path::new("src/lib.rs")]);
+        let (before, after) = (&items[Path::new("src/lib.rs")], &read[Path::new("src/lib.rs")]);
+        assert_eq!(before.len(), after.len());
+        for (before, after) in before.iter().zip(after) {
+            assert_eq!((before, &before.signature, &before.attributes, before.line_number), (after, &after.signature, &after.attributes, after.line_number));
+        }
+        assert!(from_json("{\"version\": 0}").is_err());
+    }
+}
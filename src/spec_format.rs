@@ -0,0 +1,186 @@
+//! The formats specs are written in. Each finds the Rust blocks and sections of a spec its own
+//! way; which one reads a spec goes by the file's extension.
+
+use crate::markdown_parser::{self, RustBlock, Section};
+use crate::rust_parser::Stage;
+use crate::{asciidoc_parser, rst_parser};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::path::Path;
+
+pub trait SpecFormat: Sync {
+    /// The extension of its spec files, without the dot
+    fn extension(&self) -> &'static str;
+
+    /// The normative Rust blocks, in order
+    fn rust_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a>;
+
+    /// The blocks tagged `example` instead
+    fn example_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a>;
+
+    /// The headings and the lines each covers
+    fn sections(&self, spec: &str) -> Vec<Section>;
+}
+
+/// ```rust fences, the default
+pub struct Markdown;
+
+impl SpecFormat for Markdown {
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn rust_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(markdown_parser::rust_blocks(spec))
+    }
+
+    fn example_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(markdown_parser::example_blocks(spec))
+    }
+
+    fn sections(&self, spec: &str) -> Vec<Section> {
+        markdown_parser::sections(spec)
+    }
+}
+
+/// `[source,rust]` listings
+pub struct AsciiDoc;
+
+impl SpecFormat for AsciiDoc {
+    fn extension(&self) -> &'static str {
+        "adoc"
+    }
+
+    fn rust_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(asciidoc_parser::rust_blocks(spec).into_iter())
+    }
+
+    fn example_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(asciidoc_parser::example_blocks(spec).into_iter())
+    }
+
+    fn sections(&self, spec: &str) -> Vec<Section> {
+        asciidoc_parser::sections(spec)
+    }
+}
+
+/// `code-block` directives and `literalinclude`s, as Sphinx reads them
+pub struct Rst;
+
+impl SpecFormat for Rst {
+    fn extension(&self) -> &'static str {
+        "rst"
+    }
+
+    fn rust_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(rst_parser::rust_blocks(spec).into_iter())
+    }
+
+    fn example_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(rst_parser::example_blocks(spec).into_iter())
+    }
+
+    fn sections(&self, spec: &str) -> Vec<Section> {
+        rst_parser::sections(spec)
+    }
+}
+
+/// Rust files of declarations, the whole file being one block
+pub struct RustSource;
+
+impl SpecFormat for RustSource {
+    fn extension(&self) -> &'static str {
+        "rs"
+    }
+
+    fn rust_blocks<'a>(&self, spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(std::iter::once(RustBlock {
+            code: Cow::Borrowed(spec),
+            range: 0..spec.len(),
+            line: 1,
+            stage: Stage::default(),
+            features: Vec::new(),
+            prose: None,
+            tags: Vec::new(),
+            include: None,
+            unknown_tag: None,
+        }))
+    }
+
+    fn example_blocks<'a>(&self, _spec: &'a str) -> Box<dyn Iterator<Item = RustBlock<'a>> + 'a> {
+        Box::new(std::iter::empty())
+    }
+
+    fn sections(&self, _spec: &str) -> Vec<Section> {
+        Vec::new()
+    }
+}
+
+/// The format of new and mirrored spec files (`spec-format`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Markdown,
+    Asciidoc,
+    Rst,
+    Rust,
+}
+
+impl Format {
+    pub fn spec_format(self) -> &'static dyn SpecFormat {
+        match self {
+            Format::Markdown => &Markdown,
+            Format::Asciidoc => &AsciiDoc,
+            Format::Rst => &Rst,
+            Format::Rust => &RustSource,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        self.spec_format().extension()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Markdown => "markdown",
+            Format::Asciidoc => "asciidoc",
+            Format::Rst => "rst",
+            Format::Rust => "rust",
+        }
+    }
+}
+
+/// The format a spec file is read as: AsciiDoc for `.adoc`, `.asciidoc` and `.asc`,
+/// reStructuredText for `.rst`, Rust for `.rs`, markdown otherwise
+pub fn of(path: &Path) -> &'static dyn SpecFormat {
+    match path.extension().and_then(|ext| ext.to_str()).unwrap_or_default() {
+        "adoc" | "asciidoc" | "asc" => &AsciiDoc,
+        "rst" => &Rst,
+        "rs" => &RustSource,
+        _ => &Markdown,
+    }
+}
+
+/// Whether `path` is a spec file of some format
+pub fn is_spec_file(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| matches!(ext, "md" | "adoc" | "asciidoc" | "asc" | "rst" | "rs"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_files_are_one_block() {
+        let spec = "//! Easing\n\n/// Eases `t`\npub fn ease(t: f32) -> f32 {}\n\npub mod curves {\n    pub struct Curve;\n}\n";
+        let format = of(Path::new("spec/anim.rs"));
+        assert_eq!(format.extension(), "rs");
+        let (items, errors) = crate::parse_spec_as(spec, format, Default::default());
+        assert!(errors.is_empty());
+        let summary: Vec<(&str, usize)> = items.iter().map(|item| (item.name.as_str(), item.line_number)).collect();
+        assert_eq!(summary, [("ease", 4), ("Curve", 7)]);
+        assert!(format.example_blocks(spec).next().is_none());
+        assert_eq!(of(Path::new("spec/anim.md")).extension(), "md");
+    }
+}
@@ -0,0 +1,428 @@
+//! Spec blocks as a test crate, so a spec is checked by what its code does and not only by how
+//! it reads: each ```rust,example block becomes a test run against the checked crate, and each
+//! stable `pub` item of the normative blocks is imported from the module its spec file
+//! describes (`spec/math.md` from `my_crate::math`). A `// assert: Send + Sync` comment before
+//! a type or trait is a bound the test crate requires it to meet. With `--compile-specs`, the
+//! normative blocks themselves are compiled too, against the module they describe.
+
+use crate::rust_parser::{ItemKind, RustItem, Stage};
+use crate::{markdown_parser, placeholder, rustdoc_json, spec_format};
+use proc_macro2::{Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The manifest of the test crate for the package `name` at `package_dir`
+pub fn manifest(name: &str, package_dir: &Path) -> String {
+    let path = toml::Value::String(package_dir.to_string_lossy().replace('\\', "/"));
+    format!(
+        "# Written by `spec-check test`\n[package]\nname = \"{name}-spec-tests\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [lib]\npath = \"spec.rs\"\n\n[dependencies]\n{name} = {{ path = {path} }}\n\n\
+         # Not a member of the package's workspace\n[workspace]\n"
+    )
+}
+
+/// The source of the test crate, with a module for each spec file, given by its path relative
+/// to the spec directory and its markdown. `crate_name` is the package's library as it's
+/// imported, with underscores. With `compile_specs`, each module has the spec's normative blocks
+/// as well. Also returns what of the specs the crate leaves unchecked, as `path:line: why`.
+pub fn source(crate_name: &str, specs: &[(&Path, &str)], compile_specs: bool) -> (String, Vec<String>) {
+    let mut left_out = Vec::new();
+    let mut out = String::from("// Written by `spec-check test`\n#![allow(unused, redundant_semicolons, unreachable_code)]\n");
+    for (relative, markdown) in specs {
+        let format = spec_format::of(relative);
+        let items = crate::parse_spec_as(markdown, format, Default::default()).0;
+        let mut imports = imports(crate_name, relative, &items);
+        imports.extend(assertions(crate_name, relative, format.rust_blocks(markdown).filter(|block| block.unknown_tag.is_none()), &items, &mut left_out));
+        let examples: Vec<markdown_parser::RustBlock> = format.example_blocks(markdown)
+            .filter(|block| !block.tags.iter().any(|tag| tag == "ignore" || tag == "compile_fail"))
+            .collect();
+        let compiled = match compile_specs {
+            true => compiled_blocks(crate_name, relative, format.rust_blocks(markdown).filter(|block| block.unknown_tag.is_none()), &mut left_out),
+            false => Vec::new(),
+        };
+        if imports.is_empty() && examples.is_empty() && compiled.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(out, "\n// {}\nmod {} {{", relative.display(), module_name(relative));
+        out.extend(imports);
+        if !compiled.is_empty() {
+            // In a module of their own, so the spec's declarations don't clash with the imports
+            let module: String = rustdoc_json::module_path(relative).iter().map(|name| format!("::{}", name)).collect();
+            let _ = writeln!(out, "\n    mod spec {{\n    use ::{}{}::*;", crate_name, module);
+            out.extend(compiled);
+            out.push_str("    }\n");
+        }
+        for block in examples {
+            let tagged = |tag: &str| block.tags.iter().any(|t| t == tag);
+            let _ = writeln!(out, "\n    // {}:{}\n    #[test]", relative.display(), block.line);
+            if tagged("no_run") {
+                out.push_str("    #[ignore = \"no_run\"]\n");
+            }
+            // `?` needs a `Result`, which a test that should panic can't return
+            let (returns, ok) = if tagged("should_panic") {
+                out.push_str("    #[should_panic]\n");
+                ("", "")
+            } else {
+                (" -> Result<(), Box<dyn std::error::Error>>", "    Ok(())\n")
+            };
+            let _ = writeln!(out, "    fn line_{}(){} {{\n    use super::*;\n    use ::{}::*;", block.line, returns, crate_name);
+            // Not indented, which would change multi-line string literals
+            out.push_str(block.code.trim_end_matches('\n'));
+            let _ = write!(out, "\n    ;\n{}    }}\n", ok);
+        }
+        out.push_str("}\n");
+    }
+    (out, left_out)
+}
+
+/// `use` lines for the stable `pub` items of a spec, which fail to compile if the crate doesn't
+/// have them where the spec file puts them. A name imported twice, from two inline modules, is imported as `_`.
+fn imports(crate_name: &str, relative: &Path, items: &[RustItem]) -> Vec<String> {
+    let module = rustdoc_json::module_path(relative);
+    let mut names = HashSet::new();
+    let mut paths = HashSet::new();
+    let mut imports = Vec::new();
+    for item in items {
+        let nameable = matches!(item.kind, ItemKind::Struct | ItemKind::Enum | ItemKind::Union | ItemKind::Trait
+            | ItemKind::Function | ItemKind::Const | ItemKind::Static | ItemKind::Macro | ItemKind::Use);
+        if !nameable || !is_available(item) {
+            continue;
+        }
+        let path = item_path(crate_name, &module, item);
+        if paths.insert(path.clone()) {
+            let alias = if names.insert(item.name.clone()) { String::new() } else { " as _".to_string() };
+            imports.push(format!("    // {}:{}\n    use ::{}{};\n", relative.display(), item.line_number, path, alias));
+        }
+    }
+    imports
+}
+
+/// Whether the crate has `item` for others to use, by the spec
+fn is_available(item: &RustItem) -> bool {
+    matches!(item.stage, Stage::Stable | Stage::Deprecated { .. }) && item.signature.starts_with("pub ") && !item.name.contains('*')
+}
+
+/// The path of `item` from outside the crate, `anim::geometry::Curve`
+fn item_path(crate_name: &str, module: &[String], item: &RustItem) -> String {
+    // Exported macros are at the crate root
+    let path = if item.kind == ItemKind::Macro { Vec::new() } else { [module, &item.module[..]].concat() };
+    std::iter::once(crate_name).chain(path.iter().map(String::as_str)).chain([item.name.as_str()])
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Checks of the `// assert: <bounds>` comments in `blocks`, each for the item declared next
+/// in its block, which fail to compile if a type (or a trait's objects) doesn't meet the bounds.
+/// A generic item is checked for any parameters that meet both its declared bounds and the
+/// asserted ones, as `Wrap<T>` is `Send` when `T` is. Comments that
+/// can't be checked are noted in `left_out`.
+fn assertions<'a>(crate_name: &str, relative: &Path, blocks: impl Iterator<Item = markdown_parser::RustBlock<'a>>, items: &[RustItem], left_out: &mut Vec<String>) -> Vec<String> {
+    let module = rustdoc_json::module_path(relative);
+    let mut checks = Vec::new();
+    for block in blocks {
+        let block_end = block.line + block.code.lines().count();
+        for (offset, line) in block.code.lines().enumerate() {
+            let Some(bounds) = line.trim().strip_prefix("// assert:").map(str::trim).filter(|bounds| !bounds.is_empty()) else { continue };
+            let line = block.line + offset;
+            let mut skip = |why: String| left_out.push(format!("{}:{}: the assertion isn't checked, as {}", relative.display(), line, why));
+            let next = items.iter().filter(|item| item.line_number > line && item.line_number < block_end).min_by_key(|item| item.line_number);
+            let Some(item) = next else {
+                skip("no checked item follows it in its block".to_string());
+                continue;
+            };
+            if !is_available(item) {
+                skip(format!("{} {} isn't a stable `pub` item", item.kind.label(), item.name));
+                continue;
+            }
+            let path = item_path(crate_name, &module, item);
+            let mut generics = match syn::parse_str::<syn::Item>(&item.tokens) {
+                Ok(syn::Item::Struct(item)) => item.generics,
+                Ok(syn::Item::Enum(item)) => item.generics,
+                Ok(syn::Item::Union(item)) => item.generics,
+                Ok(syn::Item::Trait(item)) => item.generics,
+                _ => {
+                    skip(format!("{} {} isn't a type or trait", item.kind.label(), item.name));
+                    continue;
+                }
+            };
+            // `Wrap<T>` is asked to be `Send` when `T` is, as derives and auto traits work
+            if let Ok(asserted) = syn::parse_str::<syn::TypeParam>(&format!("T: {}", bounds)) {
+                generics.type_params_mut().for_each(|param| param.bounds.extend(asserted.bounds.iter().cloned()));
+            }
+            let (params, arguments, where_clause) = generics.split_for_impl();
+            let checked = match item.kind {
+                ItemKind::Trait => format!("dyn ::{}{}", path, arguments.to_token_stream()),
+                _ => format!("::{}{}", path, arguments.to_token_stream()),
+            };
+            let assert = match generics.params.is_empty() {
+                true => format!("assert::<{}>();", checked),
+                false => {
+                    let where_clause = where_clause.map_or_else(String::new, |clause| format!(" {}", clause.to_token_stream()));
+                    format!("fn check{}(){} {{ assert::<{}>(); }}", params.to_token_stream(), where_clause, checked)
+                }
+            };
+            checks.push(format!(
+                "    // {}:{}\n    const _: fn() = || {{\n        fn assert<T: ?Sized + {}>() {{}}\n        {}\n    }};\n",
+                relative.display(), line, bounds, assert,
+            ));
+        }
+    }
+    checks
+}
+
+/// The items of the stable and deprecated blocks, as code that compiles if the types and traits
+/// they use are still in the crate. Function bodies become `loop {}`, attributes are dropped as
+/// the crate's derives aren't in scope, and `crate::` paths go to the checked crate. Blocks with
+/// placeholders or that don't parse, `impl`s of items the spec file doesn't declare, which the
+/// crate couldn't have, functions returning `impl Trait`, which a `loop {}` doesn't implement,
+/// inline modules and macro calls can't be compiled, and are noted in `left_out`.
+fn compiled_blocks<'a>(crate_name: &str, relative: &Path, blocks: impl Iterator<Item = markdown_parser::RustBlock<'a>>, left_out: &mut Vec<String>) -> Vec<String> {
+    let mut parsed: Vec<(usize, syn::File)> = Vec::new();
+    let mut notes: Vec<(usize, String)> = Vec::new();
+    for block in blocks.filter(|block| matches!(block.stage, Stage::Stable | Stage::Deprecated { .. }) && block.include.is_none()) {
+        if matches!(placeholder::expand(&block.code), std::borrow::Cow::Owned(_)) {
+            notes.push((block.line, "the block has placeholders, so it isn't compiled".to_string()));
+            continue;
+        }
+        match syn::parse_file(&block.code) {
+            Ok(file) => parsed.push((block.line, file)),
+            Err(err) => notes.push((block.line + err.span().start().line - 1, format!("the block isn't compiled, as it doesn't parse: {}", err))),
+        }
+    }
+    let declared: HashSet<String> = parsed.iter().flat_map(|(_, file)| &file.items).filter_map(|item| match item {
+        syn::Item::Struct(item) => Some(item.ident.to_string()),
+        syn::Item::Enum(item) => Some(item.ident.to_string()),
+        syn::Item::Union(item) => Some(item.ident.to_string()),
+        syn::Item::Trait(item) => Some(item.ident.to_string()),
+        syn::Item::Type(item) => Some(item.ident.to_string()),
+        _ => None,
+    }).collect();
+    let mut out = Vec::new();
+    for (first_line, file) in parsed {
+        let line = |span: Span| first_line + span.start().line - 1;
+        for mut item in file.items {
+            // syn keeps a function declared without a body as tokens
+            if let syn::Item::Verbatim(tokens) = &item && let Ok(syn::ForeignItemFn { attrs, vis, sig, .. }) = syn::parse2(tokens.clone()) {
+                item = syn::Item::Fn(syn::ItemFn { attrs, vis, sig, block: Box::new(syn::parse_quote!({})) });
+            }
+            let start = line(item.span());
+            let mut reasons = Vec::new();
+            let compiles = compilable(&mut item, &declared, &mut reasons);
+            notes.extend(reasons.into_iter().map(|(span, why)| (line(span), why)));
+            if compiles {
+                let tokens = to_crate(item.into_token_stream(), crate_name);
+                out.push(format!("    // {}:{}\n    {}\n", relative.display(), start, tokens));
+            }
+        }
+    }
+    notes.sort_by_key(|(line, _)| *line);
+    left_out.extend(notes.into_iter().map(|(line, why)| format!("{}:{}: {}", relative.display(), line, why)));
+    out
+}
+
+/// Whether `item` can be compiled on its own, once it has no attributes or bodies. Why it or a
+/// member left out can't be is added to `reasons`.
+fn compilable(item: &mut syn::Item, declared: &HashSet<String>, reasons: &mut Vec<(Span, String)>) -> bool {
+    let names = |path: &syn::Path| path.segments.last().is_some_and(|segment| declared.contains(&segment.ident.to_string()));
+    let span = item.span();
+    match item {
+        syn::Item::Fn(item) => {
+            item.attrs.clear();
+            return bodiless(&item.sig, &mut item.block, reasons);
+        }
+        syn::Item::Struct(item) => {
+            item.attrs.clear();
+            item.fields.iter_mut().for_each(|field| field.attrs.clear());
+        }
+        syn::Item::Enum(item) => {
+            item.attrs.clear();
+            for variant in &mut item.variants {
+                variant.attrs.clear();
+                variant.fields.iter_mut().for_each(|field| field.attrs.clear());
+            }
+        }
+        syn::Item::Union(item) => {
+            item.attrs.clear();
+            item.fields.named.iter_mut().for_each(|field| field.attrs.clear());
+        }
+        syn::Item::Trait(item) => {
+            item.attrs.clear();
+            item.items.retain_mut(|trait_item| match trait_item {
+                syn::TraitItem::Fn(method) => {
+                    method.attrs.clear();
+                    match &mut method.default {
+                        Some(block) => bodiless(&method.sig, block, reasons),
+                        None => true,
+                    }
+                }
+                syn::TraitItem::Macro(call) => {
+                    reasons.push((call.span(), "macro calls aren't compiled".to_string()));
+                    false
+                }
+                _ => true,
+            });
+        }
+        syn::Item::Impl(item) => {
+            let self_declared = matches!(&*item.self_ty, syn::Type::Path(ty) if names(&ty.path));
+            let trait_declared = item.trait_.as_ref().is_some_and(|(_, path, _)| names(path));
+            if !self_declared && !trait_declared {
+                reasons.push((span, "the impl isn't compiled, as the spec file doesn't declare its type or trait".to_string()));
+                return false;
+            }
+            item.attrs.clear();
+            for impl_item in &mut item.items {
+                let declared_fn = match impl_item {
+                    syn::ImplItem::Verbatim(tokens) => syn::parse2::<syn::ForeignItemFn>(tokens.clone()).ok(),
+                    _ => None,
+                };
+                if let Some(syn::ForeignItemFn { attrs, vis, sig, .. }) = declared_fn {
+                    *impl_item = syn::ImplItem::Fn(syn::ImplItemFn { attrs, vis, defaultness: None, sig, block: syn::parse_quote!({}) });
+                }
+            }
+            item.items.retain_mut(|impl_item| match impl_item {
+                syn::ImplItem::Fn(method) => {
+                    method.attrs.clear();
+                    bodiless(&method.sig, &mut method.block, reasons)
+                }
+                syn::ImplItem::Macro(call) => {
+                    reasons.push((call.span(), "macro calls aren't compiled".to_string()));
+                    false
+                }
+                syn::ImplItem::Verbatim(tokens) => {
+                    reasons.push((tokens.span(), format!("`{}` isn't an item that can be compiled", tokens)));
+                    false
+                }
+                _ => true,
+            });
+        }
+        syn::Item::Const(item) => item.attrs.clear(),
+        syn::Item::Static(item) => item.attrs.clear(),
+        syn::Item::Type(item) => item.attrs.clear(),
+        syn::Item::Use(item) => item.attrs.clear(),
+        syn::Item::ForeignMod(item) => item.attrs.clear(),
+        syn::Item::Macro(item) if item.mac.path.is_ident("macro_rules") => {}
+        syn::Item::Macro(_) => {
+            reasons.push((span, "macro calls aren't compiled".to_string()));
+            return false;
+        }
+        syn::Item::Mod(item) => {
+            reasons.push((span, format!("inline module {} isn't compiled", item.ident)));
+            return false;
+        }
+        item => {
+            reasons.push((span, format!("`{}` isn't an item that can be compiled", item.to_token_stream())));
+            return false;
+        }
+    }
+    true
+}
+
+/// Replaces a function's body with `loop {}`, unless it returns `impl Trait`
+fn bodiless(sig: &syn::Signature, block: &mut syn::Block, reasons: &mut Vec<(Span, String)>) -> bool {
+    if matches!(&sig.output, syn::ReturnType::Type(_, ty) if matches!(**ty, syn::Type::ImplTrait(_))) {
+        reasons.push((sig.ident.span(), format!("fn {} isn't compiled, as it returns `impl Trait`", sig.ident)));
+        return false;
+    }
+    *block = syn::parse_quote!({ loop {} });
+    true
+}
+
+/// `tokens` with `crate::` paths starting from the crate `crate_name` instead
+fn to_crate(tokens: TokenStream, crate_name: &str) -> TokenStream {
+    let mut out = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "crate" && matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':') => {
+                out.push(TokenTree::Punct(Punct::new(':', Spacing::Joint)));
+                out.push(TokenTree::Punct(Punct::new(':', Spacing::Alone)));
+                out.push(TokenTree::Ident(Ident::new(crate_name, Span::call_site())));
+            }
+            TokenTree::Group(group) => {
+                let mut inner = proc_macro2::Group::new(group.delimiter(), to_crate(group.stream(), crate_name));
+                inner.set_span(group.span());
+                out.push(TokenTree::Group(inner));
+            }
+            token => out.push(token),
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// A module name for a spec file: `geometry/curve.md` is `geometry_curve_md`
+fn module_name(relative: &Path) -> String {
+    let name: String = relative.to_string_lossy().chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) { format!("_{}", name) } else { name }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_blocks_become_tests() {
+        let markdown = concat!(
+            "```rust\npub struct Curve;\npub fn ease(t: f32) -> f32 {}\nfn helper() {}\n```\n\n",
+            "```rust,planned\npub fn blend();\n```\n\n",
+            "```rust\n// assert: Send + Sync\npub trait Geometry {}\n// assert: Send\nfn private() {}\n```\n\n",
+            "```rust,example\nassert_eq!(ease(0.0), 0.0);\n```\n\n",
+            "```rust,example,should_panic\nease(f32::NAN);\n```\n\n",
+            "```rust,example,ignore\nnot code\n```\n\n",
+            "```rust\n// assert: Send\npub struct Wrap<T: Clone = u8>(pub T);\n// assert: Sync\npub fn wrap() {}\n// assert: Send\n```\n",
+        );
+        let (source, left_out) = source("anim", &[(Path::new("geometry/curve.md"), markdown)], false);
+
+        assert!(source.contains("mod geometry_curve_md {\n"));
+        assert!(source.contains("    // geometry/curve.md:2\n    use ::anim::geometry::curve::Curve;\n"));
+        assert!(source.contains("use ::anim::geometry::curve::ease;\n"));
+        assert!(!source.contains("helper") && !source.contains("blend") && !source.contains("not code"));
+        assert!(source.contains("    // geometry/curve.md:19\n    #[test]\n    fn line_19() -> Result<(), Box<dyn std::error::Error>> {\n    use super::*;\n    use ::anim::*;\nassert_eq!(ease(0.0), 0.0);\n    ;\n    Ok(())\n    }\n"));
+        assert!(source.contains("    #[should_panic]\n    fn line_23() {\n"));
+        assert!(source.contains("    // geometry/curve.md:12\n    const _: fn() = || {\n        fn assert<T: ?Sized + Send + Sync>() {}\n        assert::<dyn ::anim::geometry::curve::Geometry>();\n    };\n"));
+        // Generic types are checked for the parameters that meet the bounds too
+        assert!(source.contains("    // geometry/curve.md:31\n    const _: fn() = || {\n        fn assert<T: ?Sized + Send>() {}\n        fn check< T : Clone + Send >() { assert::<::anim::geometry::curve::Wrap< T >>(); }\n    };\n"), "{}", source);
+        assert_eq!(source.matches("fn assert<").count(), 2);
+        assert_eq!(left_out, [
+            "geometry/curve.md:14: the assertion isn't checked, as no checked item follows it in its block",
+            "geometry/curve.md:33: the assertion isn't checked, as function wrap isn't a type or trait",
+            "geometry/curve.md:35: the assertion isn't checked, as no checked item follows it in its block",
+        ]);
+        assert!(manifest("anim", Path::new("/work/anim")).contains("anim = { path = \"/work/anim\" }"));
+    }
+
+    #[test]
+    fn test_normative_blocks_compile_against_the_crate() {
+        let markdown = concat!(
+            "```rust\n#[derive(Debug, Serialize)]\npub struct Curve {\n    #[serde(skip)]\n    pub points: Vec<crate::math::Vec3>,\n}\n",
+            "impl Curve {\n    pub fn len(&self) -> f32 {}\n    pub fn iter(&self) -> impl Iterator<Item = f32> {}\n}\n",
+            "impl Clone for Mesh {}\nbitflags! {}\n```\n\n",
+            "```rust,planned\npub fn blend();\n```\n\n",
+            "```rust\npub struct Spline(/* ... */);\n```\n\n",
+            "```rust\npub fn ease(t: Gone) -> f32;\nimpl Curve {\n    pub fn reset(&mut self);\n}\npub const MAX: usize;\n```\n",
+        );
+        let (source, left_out) = source("anim", &[(Path::new("geometry/curve.md"), markdown)], true);
+
+        assert!(source.contains("    mod spec {\n    use ::anim::geometry::curve::*;\n"));
+        assert!(source.contains("    // geometry/curve.md:2\n    pub struct Curve { pub points : Vec < :: anim :: math :: Vec3 > , }\n"));
+        assert!(source.contains("    // geometry/curve.md:7\n    impl Curve { pub fn len (& self) -> f32 { loop { } } }\n"));
+        let compiled = &source[source.find("mod spec").unwrap()..];
+        assert!(!compiled.contains("Mesh") && !compiled.contains("bitflags") && !compiled.contains("iter") && !compiled.contains("blend") && !compiled.contains("Spline"));
+        // Functions declared without a body are compiled all the same
+        assert!(source.contains("    // geometry/curve.md:24\n    pub fn ease (t : Gone) -> f32 { loop { } }\n"), "{}", source);
+        assert!(source.contains("    // geometry/curve.md:25\n    impl Curve { pub fn reset (& mut self) { loop { } } }\n"));
+        assert_eq!(left_out, [
+            "geometry/curve.md:9: fn iter isn't compiled, as it returns `impl Trait`",
+            "geometry/curve.md:11: the impl isn't compiled, as the spec file doesn't declare its type or trait",
+            "geometry/curve.md:12: macro calls aren't compiled",
+            "geometry/curve.md:20: the block has placeholders, so it isn't compiled",
+            "geometry/curve.md:28: `pub const MAX : usize ;` isn't an item that can be compiled",
+        ]);
+        assert!(!super::source("anim", &[(Path::new("geometry/curve.md"), markdown)], false).0.contains("mod spec"));
+    }
+}
@@ -0,0 +1,521 @@
+//! The report `check` prints to the terminal: findings grouped by file, each with the line of
+//! code it's about and, when an item differs from the spec, a caret under the first difference
+
+use crate::blame::{self, Blamer};
+use crate::config::{DirectoryOverrides, Severity, SourceOfTruth};
+use crate::coverage::Coverage;
+use crate::semver::{Bump, Impact};
+use crate::placeholder;
+use crate::comparator::ComparisonResult;
+use crate::reporter::{self, Finding, Report};
+use crate::rules;
+use crate::word_diff;
+use anyhow::Result;
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[1;32m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+/// Whether to color stdout: it's a terminal, `--no-color` wasn't given and `NO_COLOR` isn't set
+pub fn use_color(no_color: bool) -> bool {
+    !no_color && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stdout().is_terminal()
+}
+
+pub struct Printer<W> {
+    out: W,
+    color: bool,
+    /// Severities by rule code; unlisted rules are errors
+    severities: BTreeMap<String, Severity>,
+    /// What `.spec-check.toml` files override of `severities` for their subtrees
+    directories: DirectoryOverrides,
+    /// The file the last finding was shown in, and its source if it could be read
+    file: Option<(PathBuf, Option<String>)>,
+    /// The source file of the last finding, which may be shown in its spec file
+    counted: Option<PathBuf>,
+    /// The log written alongside, for the summary to point at
+    log: Option<PathBuf>,
+    /// A file's source that isn't read from disk, e.g. one read from stdin
+    given: Option<(PathBuf, String)>,
+    /// How findings are worded and which file they're shown in
+    truth: Option<SourceOfTruth>,
+    /// With `--quiet`, findings are counted but only the summary is printed
+    quiet: bool,
+    findings: usize,
+    files_with_findings: usize,
+    /// The run's spec coverage and `--min-coverage`, for the summary
+    coverage: Option<(Coverage, Option<f64>)>,
+    /// The run's semver impact, the package's version and `--max-bump`, for the summary
+    semver: Option<(Impact, Option<String>, Option<Bump>)>,
+    /// With `--blame`, who last changed the lines of each finding
+    blamer: Option<Arc<Blamer>>,
+    /// With `--group-by author`, how many findings each author owns
+    authors: Option<BTreeMap<String, usize>>,
+}
+
+impl<W: Write> Printer<W> {
+    pub fn new(out: W, color: bool, severities: BTreeMap<String, Severity>) -> Self {
+        Self { out, color, severities, directories: DirectoryOverrides::default(), file: None, counted: None, log: None, given: None, truth: None, quiet: false, findings: 0, files_with_findings: 0, coverage: None, semver: None, blamer: None, authors: None }
+    }
+
+    /// Take findings' severities from the `.spec-check.toml` files of their subtrees
+    pub fn with_directories(mut self, directories: DirectoryOverrides) -> Self {
+        self.directories = directories;
+        self
+    }
+
+    /// Point the summary at the log written alongside
+    pub fn with_log(mut self, log: Option<PathBuf>) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Show snippets of `file` from `source` rather than from the file on disk
+    pub fn with_source(mut self, file: &Path, source: String) -> Self {
+        self.given = Some((file.to_path_buf(), source));
+        self
+    }
+
+    /// Print only the summary
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Show who last changed the lines of each finding
+    pub fn with_blame(mut self, blamer: Option<Arc<Blamer>>) -> Self {
+        self.blamer = blamer;
+        self
+    }
+
+    /// Count findings by the author who owns them, for the summary
+    pub fn with_author_summary(mut self, by_author: bool) -> Self {
+        self.authors = by_author.then(BTreeMap::new);
+        self
+    }
+
+    /// Word findings for the side that is right when code and spec disagree
+    pub fn with_source_of_truth(mut self, truth: Option<SourceOfTruth>) -> Self {
+        self.truth = truth;
+        self
+    }
+
+    /// Print a finding, under a heading for its file if it's the first one there
+    pub fn finding(&mut self, finding: &Finding) -> io::Result<()> {
+        if self.counted.as_ref() != Some(&finding.file) {
+            self.counted = Some(finding.file.clone());
+            self.files_with_findings += 1;
+        }
+        self.findings += 1;
+        if let Some(authors) = &mut self.authors {
+            let owner = blame::owner(finding).map_or("unattributed".to_string(), |blame| blame.author_with_email());
+            *authors.entry(owner).or_default() += 1;
+        }
+        if self.quiet {
+            return Ok(());
+        }
+
+        let (location, _) = finding.location();
+        if self.file.as_ref().is_none_or(|(file, _)| file != location) {
+            if self.file.is_some() {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "{}", self.paint(BOLD, &location.display().to_string()))?;
+            let source = match &self.given {
+                Some((file, source)) if file == location => Some(source.clone()),
+                _ => fs::read_to_string(location).ok(),
+            };
+            self.file = Some((location.to_path_buf(), source));
+        }
+
+        let (label, style) = match self.directories.severity(finding.rule, &finding.file, &self.severities) {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+            Severity::Info | Severity::Off => ("info", CYAN),
+        };
+        writeln!(self.out, "  {}{}", self.paint(style, &format!("{}[{}]", label, finding.rule)), self.paint(BOLD, &format!(": {}", finding.message)))?;
+        self.snippet(finding)?;
+        if let (rules::SIGNATURE_MISMATCH | rules::DOC_MISMATCH, Some(code), Some(spec)) = (finding.rule, &finding.code, &finding.spec)
+            && !placeholder::leaves_open(spec) {
+            let diff = word_diff::diff(spec, code);
+            let diff = if self.color {
+                word_diff::render_with(&diff, ("\x1b[31m", RESET), ("\x1b[32m", RESET))
+            } else {
+                word_diff::render(&diff)
+            };
+            writeln!(self.out, "    {} diff: {}", self.paint(BLUE, "="), diff)?;
+        }
+        for (label, blame) in [("blame", &finding.blame), ("spec blame", &finding.spec_blame)] {
+            if let Some(blame) = blame {
+                writeln!(self.out, "    {} {}: {}", self.paint(BLUE, "="), label, blame)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a finding of a file just checked, with its blame when asked for
+    fn report(&mut self, mut finding: Finding) -> Result<()> {
+        if let Some(blamer) = &self.blamer {
+            blamer.attribute(&mut finding);
+        }
+        unless_closed(self.finding(&finding))
+    }
+
+    /// The line of code a finding is about, or the spec's item when the code has none
+    fn snippet(&mut self, finding: &Finding) -> io::Result<()> {
+        let source = self.file.as_ref().and_then(|(_, source)| source.as_deref());
+        let (file, line) = finding.location();
+        let Some(line) = line else {
+            if let Some(spec) = &finding.spec {
+                writeln!(self.out, "    {} spec: {}", self.paint(BLUE, "="), placeholder::restore(spec))?;
+            }
+            return Ok(());
+        };
+        let Some(source) = source else {
+            return writeln!(self.out, "    {} {}:{}", self.paint(BLUE, "-->"), file.display(), line);
+        };
+        // Attributes are listed above the item, so only signatures get a caret
+        let caret = match (&finding.code, &finding.spec) {
+            (Some(code), Some(spec)) if finding.rule != rules::ATTRIBUTE_MISMATCH && !placeholder::leaves_open(spec) => caret(source, line, code, spec),
+            _ => None,
+        };
+        let shown = caret.as_ref().map_or(line, |caret| caret.line);
+        writeln!(self.out, "    {} {}:{}", self.paint(BLUE, "-->"), file.display(), shown)?;
+        let Some(text) = source.lines().nth(shown.wrapping_sub(1)) else { return Ok(()) };
+        let gutter = " ".repeat(shown.to_string().len());
+        writeln!(self.out, "    {}", self.paint(BLUE, &format!("{} |", gutter)))?;
+        writeln!(self.out, "    {} {}", self.paint(BLUE, &format!("{} |", shown)), text)?;
+        if let Some(caret) = caret {
+            // Tabs stay tabs so the caret lines up however wide the terminal draws them
+            let indent: String = text[..caret.column].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+            let marks = format!("{} {}", "^".repeat(caret.width.max(1)), caret.label);
+            writeln!(self.out, "    {} {}{}", self.paint(BLUE, &format!("{} |", gutter)), indent, self.paint(RED, &marks))?;
+        }
+        Ok(())
+    }
+
+    /// Print the totals after the last finding
+    pub fn finish(&mut self, files_checked: usize) -> io::Result<()> {
+        if self.findings == 0 {
+            let summary = format!("All {} file(s) match their specs", files_checked);
+            writeln!(self.out, "{}", self.paint(GREEN, &summary))?;
+        } else {
+            let summary = format!("{} finding(s) in {} of {} file(s)", self.findings, self.files_with_findings, files_checked);
+            let gap = if self.quiet { "" } else { "\n" };
+            match &self.log {
+                Some(log) => writeln!(self.out, "{}{} (details in {})", gap, self.paint(RED, &summary), log.display())?,
+                None => writeln!(self.out, "{}{}", gap, self.paint(RED, &summary))?,
+            }
+        }
+        if let Some(authors) = self.authors.take().filter(|authors| !authors.is_empty()) {
+            let mut authors: Vec<(String, usize)> = authors.into_iter().collect();
+            authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            writeln!(self.out, "Findings by author:")?;
+            for (author, count) in authors {
+                writeln!(self.out, "  {:>4}  {}", count, author)?;
+            }
+        }
+        match self.coverage.take() {
+            Some((total, Some(min))) if total.percent() < min => {
+                let line = format!("Spec coverage: {}, below the minimum of {}%", total, min);
+                writeln!(self.out, "{}", self.paint(RED, &line))?;
+            }
+            Some((total, _)) if total.items > 0 => writeln!(self.out, "Spec coverage: {}", total)?,
+            _ => {}
+        }
+        match self.semver.take() {
+            Some((impact, version, Some(max))) if impact.bump().is_some_and(|bump| bump > max) => {
+                let line = format!("Semver impact: {}, more than --max-bump {}", impact.describe(version.as_deref()), max);
+                writeln!(self.out, "{}", self.paint(RED, &line))
+            }
+            Some((impact, version, _)) if impact.bump().is_some() => writeln!(self.out, "Semver impact: {}", impact.describe(version.as_deref())),
+            _ => Ok(()),
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color { format!("{}{}{}", style, text, RESET) } else { text.to_string() }
+    }
+}
+
+impl<W: Write> Report for Printer<W> {
+    fn report_missing_spec(&mut self, file: &Path) -> Result<()> {
+        self.report(reporter::missing_spec_finding(file))
+    }
+
+    fn report_parse_failure(&mut self, file: &Path, error: &anyhow::Error) -> Result<()> {
+        self.report(reporter::parse_failure_finding(file, error))
+    }
+
+    fn report_orphaned_spec(&mut self, spec_file: &Path) -> Result<()> {
+        self.report(reporter::orphaned_spec_finding(spec_file))
+    }
+
+    fn report_results(&mut self, file: &Path, spec: Option<&Path>, result: &ComparisonResult) -> Result<()> {
+        reporter::findings_for(file, spec, result, self.truth).into_iter().try_for_each(|finding| self.report(finding))
+    }
+
+    fn report_stopped_early(&mut self, reason: &str) -> Result<()> {
+        let line = format!("\nStopped early: {}", reason);
+        unless_closed(writeln!(self.out, "{}", self.paint(YELLOW, &line)))
+    }
+
+    fn write_summary(&mut self, total_files: usize, _files_with_errors: usize) -> Result<()> {
+        unless_closed(self.finish(total_files))
+    }
+
+    fn report_coverage(&mut self, _files: &[(PathBuf, Coverage)], total: Coverage, min: Option<f64>) -> Result<()> {
+        self.coverage = Some((total, min));
+        Ok(())
+    }
+
+    fn report_semver(&mut self, impact: Impact, version: Option<&str>, max: Option<Bump>) -> Result<()> {
+        self.semver = Some((impact, version.map(str::to_string), max));
+        Ok(())
+    }
+}
+
+/// Wraps the reports of a run to show on stderr how far it got: a `[done/total]` line redrawn
+/// after each file when stderr is a terminal, or with `verbosity` a line per file (and at 2 one
+/// per finding too), so a long run isn't silent until the end
+pub struct Progress<W, R> {
+    out: W,
+    inner: R,
+    verbosity: u8,
+    /// Whether to draw the `[done/total]` line
+    bar: bool,
+    truth: Option<SourceOfTruth>,
+    total: usize,
+    done: usize,
+}
+
+impl<W: Write, R: Report> Progress<W, R> {
+    pub fn new(out: W, inner: R, verbosity: u8, bar: bool, truth: Option<SourceOfTruth>) -> Self {
+        Self { out, inner, verbosity, bar: bar && verbosity == 0, truth, total: 0, done: 0 }
+    }
+
+    /// Around each report of a file: clear the progress line so the report prints on a line of
+    /// its own, then echo the file's outcome and draw the line again
+    fn file(&mut self, file: &Path, findings: &[Finding], report: impl FnOnce(&mut R) -> Result<()>) -> Result<()> {
+        self.clear()?;
+        report(&mut self.inner)?;
+        self.done += 1;
+        if self.verbosity > 0 {
+            let outcome = if findings.is_empty() { "ok".to_string() } else { format!("{} finding(s)", findings.len()) };
+            unless_closed(writeln!(self.out, "[{}/{}] {}: {}", self.done, self.total, file.display(), outcome))?;
+        }
+        if self.verbosity > 1 {
+            for finding in findings {
+                unless_closed(writeln!(self.out, "    {} {}", finding.rule, finding.message))?;
+            }
+        }
+        if self.bar {
+            unless_closed(write!(self.out, "[{}/{}] {}", self.done, self.total, file.display()).and_then(|_| self.out.flush()))?;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        if self.bar && self.done > 0 {
+            unless_closed(write!(self.out, "\r\x1b[K").and_then(|_| self.out.flush()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write, R: Report> Report for Progress<W, R> {
+    fn report_start(&mut self, files: usize) -> Result<()> {
+        self.total = files;
+        self.inner.report_start(files)
+    }
+
+    fn report_missing_spec(&mut self, file: &Path) -> Result<()> {
+        self.file(file, &[reporter::missing_spec_finding(file)], |inner| inner.report_missing_spec(file))
+    }
+
+    fn report_parse_failure(&mut self, file: &Path, error: &anyhow::Error) -> Result<()> {
+        self.file(file, &[reporter::parse_failure_finding(file, error)], |inner| inner.report_parse_failure(file, error))
+    }
+
+    /// Not one of the files counted, so only echoed
+    fn report_orphaned_spec(&mut self, spec_file: &Path) -> Result<()> {
+        self.clear()?;
+        self.bar = false;
+        self.inner.report_orphaned_spec(spec_file)?;
+        if self.verbosity > 0 {
+            unless_closed(writeln!(self.out, "{}: orphaned spec", spec_file.display()))?;
+        }
+        Ok(())
+    }
+
+    fn report_results(&mut self, file: &Path, spec: Option<&Path>, result: &ComparisonResult) -> Result<()> {
+        let findings = if self.verbosity > 0 { reporter::findings_for(file, spec, result, self.truth) } else { Vec::new() };
+        self.file(file, &findings, |inner| inner.report_results(file, spec, result))
+    }
+
+    fn report_stopped_early(&mut self, reason: &str) -> Result<()> {
+        self.clear()?;
+        self.bar = false;
+        self.inner.report_stopped_early(reason)
+    }
+
+    fn write_summary(&mut self, total_files: usize, files_with_errors: usize) -> Result<()> {
+        self.clear()?;
+        self.bar = false;
+        self.inner.write_summary(total_files, files_with_errors)
+    }
+
+    fn report_baseline(&mut self, suppressed: usize, unused: usize) -> Result<()> {
+        self.inner.report_baseline(suppressed, unused)
+    }
+
+    fn report_coverage(&mut self, files: &[(PathBuf, Coverage)], total: Coverage, min: Option<f64>) -> Result<()> {
+        self.inner.report_coverage(files, total, min)
+    }
+
+    fn report_semver(&mut self, impact: Impact, version: Option<&str>, max: Option<Bump>) -> Result<()> {
+        self.inner.report_semver(impact, version, max)
+    }
+}
+
+/// A closed stdout (e.g. piped to `head`) shouldn't stop the check
+fn unless_closed(result: io::Result<()>) -> Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => Ok(other?),
+    }
+}
+
+/// Where the first token of a code signature that differs from the spec's is in the source
+#[derive(Debug, PartialEq)]
+struct Caret {
+    line: usize,
+    /// Byte offset into the line
+    column: usize,
+    width: usize,
+    label: String,
+}
+
+/// The caret for the first token `code` and `spec` differ in, looking for `code`'s tokens in
+/// order from the start of `line` in `source`
+fn caret(source: &str, line: usize, code: &str, spec: &str) -> Option<Caret> {
+    let (code, spec) = (leaves(code.parse().ok()?), leaves(spec.parse().ok()?));
+    let index = code.iter().zip(&spec).position(|(a, b)| a != b).unwrap_or(code.len().min(spec.len()));
+    let label = match spec.get(index) {
+        _ if index == code.len() && index == spec.len() => return None,
+        Some(token) if index < code.len() => format!("spec has `{}`", placeholder::restore(token)),
+        Some(token) => format!("spec continues with `{}`", placeholder::restore(token)),
+        None => "not in the spec".to_string(),
+    };
+    // Past the end of the code, point just after its last token
+    let (target, past_end) = if index < code.len() { (index, false) } else { (index - 1, true) };
+
+    let mut offset = source.split_inclusive('\n').take(line.checked_sub(1)?).map(str::len).sum::<usize>();
+    for token in &code[..target] {
+        offset += source.get(offset..)?.find(token.as_str())? + token.len();
+    }
+    let start = offset + source.get(offset..)?.find(code[target].as_str())?;
+    let line_start = source[..start].rfind('\n').map_or(0, |at| at + 1);
+    let (column, width) = if past_end { (start - line_start + code[target].len(), 1) } else { (start - line_start, code[target].chars().count()) };
+    Some(Caret { line: source[..start].matches('\n').count() + 1, column, width, label })
+}
+
+/// The tokens of a stream as written, with delimiters as tokens of their own and joined
+/// punctuation (`->`, `::`) as one
+fn leaves(stream: TokenStream) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut joint = false;
+    for tree in stream {
+        if let (true, TokenTree::Punct(punct), Some(last)) = (joint, &tree, tokens.last_mut()) {
+            last.push(punct.as_char());
+            joint = punct.spacing() == Spacing::Joint;
+            continue;
+        }
+        joint = matches!(&tree, TokenTree::Punct(punct) if punct.spacing() == Spacing::Joint);
+        match tree {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                if !open.is_empty() {
+                    tokens.push(open.to_string());
+                }
+                tokens.extend(leaves(group.stream()));
+                if !close.is_empty() {
+                    tokens.push(close.to_string());
+                }
+            }
+            other => tokens.push(other.to_string()),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caret_points_at_first_difference() {
+        let source = "use std::fmt;\n\npub struct Mesh {\n    pub vertices: Vec<f32>,\n    pub indices: Vec<u16>,\n}\n";
+        let code = "pub struct Mesh { pub vertices : Vec < f32 > , pub indices : Vec < u16 > , }";
+        let spec = "pub struct Mesh { pub vertices : Vec < f32 > , pub indices : Vec < u32 > , }";
+        let caret = caret(source, 3, code, spec).unwrap();
+        assert_eq!(caret, Caret { line: 5, column: 21, width: 3, label: "spec has `u32`".to_string() });
+        assert_eq!(&source.lines().nth(4).unwrap()[caret.column..caret.column + caret.width], "u16");
+
+        let caret = super::caret("pub fn f() {}\n", 1, "pub fn f ()", "pub fn f () -> u8").unwrap();
+        assert_eq!((caret.column, caret.label.as_str()), (10, "spec continues with `->`"));
+        assert_eq!(super::caret("pub fn f() {}\n", 1, "pub fn f ()", "pub fn f ()"), None);
+
+        let finding = Finding {
+            code: Some("pub fn f (x : u8)".to_string()),
+            spec: Some("pub fn f (x : u16)".to_string()),
+            ..Finding::new(rules::SIGNATURE_MISMATCH, Path::new("missing.rs"), Some(1), "fn f does not match the spec".to_string())
+        };
+        let mut printer = Printer::new(Vec::new(), false, BTreeMap::new()).with_log(Some(PathBuf::from("spec-check.log")));
+        printer.finding(&finding).unwrap();
+        printer.finish(2).unwrap();
+        assert_eq!(String::from_utf8(printer.out).unwrap(), concat!(
+            "missing.rs\n",
+            "  error[SC003]: fn f does not match the spec\n",
+            "    --> missing.rs:1\n",
+            "    = diff: pub fn f (x : [-u16-]{+u8+})\n",
+            "\n1 finding(s) in 1 of 2 file(s) (details in spec-check.log)\n",
+        ));
+    }
+
+    #[test]
+    fn test_progress_echoes_files_and_quiet_prints_the_summary() {
+        let printer = Printer::new(Vec::new(), false, BTreeMap::new()).with_quiet(true);
+        let mut progress = Progress::new(Vec::new(), printer, 2, false, None);
+        progress.report_start(2).unwrap();
+        progress.report_missing_spec(Path::new("src/a.rs")).unwrap();
+        progress.report_results(Path::new("src/b.rs"), Some(Path::new("spec/b.md")), &ComparisonResult::default()).unwrap();
+        progress.write_summary(2, 1).unwrap();
+        assert_eq!(String::from_utf8(progress.out).unwrap(), concat!(
+            "[1/2] src/a.rs: 1 finding(s)\n",
+            "    SC005 No spec file found\n",
+            "[2/2] src/b.rs: ok\n",
+        ));
+        assert_eq!(String::from_utf8(progress.inner.out).unwrap(), "1 finding(s) in 1 of 2 file(s)\n");
+
+        let mut bar = Progress::new(Vec::new(), Vec::new(), 0, true, None);
+        bar.report_start(1).unwrap();
+        bar.report_results(Path::new("src/b.rs"), None, &ComparisonResult::default()).unwrap();
+        bar.write_summary(1, 0).unwrap();
+        assert_eq!(String::from_utf8(bar.out).unwrap(), "[1/1] src/b.rs\r\x1b[K");
+    }
+}
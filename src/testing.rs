@@ -0,0 +1,89 @@
+//! Spec compliance as a test, so `cargo test` alone enforces the spec:
+//!
+//! ```no_run
+//! #[test]
+//! fn code_matches_spec() {
+//!     spec_check::assert_spec_compliance!("spec");
+//! }
+//! ```
+
+use crate::config::Config;
+use crate::reporter::Finding;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Check `src` against `spec` in process and panic with a report of every finding.
+/// Relative paths are resolved against the crate's manifest directory, whose
+/// `[package.metadata.spec-check]` supplies the other settings.
+#[macro_export]
+macro_rules! assert_spec_compliance {
+    () => {
+        $crate::assert_spec_compliance!("src", "spec")
+    };
+    ($spec:expr) => {
+        $crate::assert_spec_compliance!("src", $spec)
+    };
+    ($src:expr, $spec:expr) => {
+        $crate::testing::assert_compliance(::std::path::Path::new(env!("CARGO_MANIFEST_DIR")), $src.as_ref(), $spec.as_ref())
+    };
+}
+
+#[track_caller]
+pub fn assert_compliance(manifest_dir: &Path, src: &Path, spec: &Path) {
+    let config = Config::load_from_manifest(&manifest_dir.join("Cargo.toml"))
+        .unwrap_or_else(|err| panic!("Failed to load [package.metadata.spec-check]: {:#}", err));
+    let version = crate::config::load_package_info_at(&manifest_dir.join("Cargo.toml")).ok().flatten().map(|p| p.version);
+    let paths = crate::CheckPaths {
+        src: manifest_dir.join(src),
+        spec: manifest_dir.join(spec),
+        mappings: config.mapping.iter().flatten().map(|rule| rule.within(manifest_dir)).collect(),
+        filter: config.file_filter().within(manifest_dir),
+    };
+    let options = crate::CheckOptions { version, ..crate::CheckOptions::from_config(&config) };
+    let checked = crate::check(&paths, &options).unwrap_or_else(|err| panic!("spec-check failed: {:#}", err));
+    if !checked.is_clean() {
+        panic!("{}", report(manifest_dir, &checked.findings));
+    }
+}
+
+/// One paragraph per finding with the code and spec side by side, paths relative to the crate
+fn report(manifest_dir: &Path, findings: &[Finding]) -> String {
+    let mut report = format!("{} spec finding(s):\n", findings.len());
+    for finding in findings {
+        let file = finding.file.strip_prefix(manifest_dir).unwrap_or(&finding.file);
+        let location = finding.line.map_or(file.display().to_string(), |line| format!("{}:{}", file.display(), line));
+        writeln!(report, "\n  {}: {} {}", location, finding.rule, finding.message).unwrap();
+        for (label, text) in [("code", &finding.code), ("spec", &finding.spec)] {
+            if let Some(text) = text.as_ref().filter(|text| !text.is_empty()) {
+                writeln!(report, "    {}: {}", label, text).unwrap();
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_drift_panics_with_a_report() {
+        let dir = std::env::temp_dir().join(format!("spec-check-testing-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("spec")).unwrap();
+        // Structs only, since the plugin tests register a rule against functions in this process
+        fs::write(dir.join("src/lib.rs"), "pub struct Scale { pub factor: f32 }\n").unwrap();
+        fs::write(dir.join("spec/lib.md"), "```rust\npub struct Scale { pub factor: f32 }\n```\n").unwrap();
+        assert_compliance(&dir, Path::new("src"), Path::new("spec"));
+
+        fs::write(dir.join("src/lib.rs"), "pub struct Scale { pub factor: f64 }\n").unwrap();
+        let panic = std::panic::catch_unwind(|| assert_compliance(&dir, Path::new("src"), Path::new("spec"))).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("1 spec finding(s):"), "{}", message);
+        assert!(message.contains("src/lib.rs:1: SC003"), "{}", message);
+        assert!(message.contains("    spec: pub struct Scale { pub factor : f32 }"), "{}", message);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Type paths written out in full, like `std::vec::Vec<Vec3>`, shortened to how code usually
+//! names them, so that a spec spelling a type differently from the code still matches it
+
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use std::collections::BTreeMap;
+
+/// Standard library paths that the prelude or a customary `use` shortens to their last
+/// segment. `core::` and `alloc::` spellings count as `std::`.
+const WELL_KNOWN: &[&str] = &[
+    "std::vec::Vec",
+    "std::string::String",
+    "std::string::ToString",
+    "std::boxed::Box",
+    "std::option::Option",
+    "std::option::Option::Some",
+    "std::option::Option::None",
+    "std::result::Result",
+    "std::result::Result::Ok",
+    "std::result::Result::Err",
+    "std::borrow::Cow",
+    "std::borrow::ToOwned",
+    "std::rc::Rc",
+    "std::rc::Weak",
+    "std::sync::Arc",
+    "std::sync::Mutex",
+    "std::sync::RwLock",
+    "std::cell::Cell",
+    "std::cell::RefCell",
+    "std::collections::HashMap",
+    "std::collections::HashSet",
+    "std::collections::BTreeMap",
+    "std::collections::BTreeSet",
+    "std::collections::VecDeque",
+    "std::collections::BinaryHeap",
+    "std::marker::PhantomData",
+    "std::marker::Send",
+    "std::marker::Sync",
+    "std::marker::Sized",
+    "std::marker::Copy",
+    "std::clone::Clone",
+    "std::default::Default",
+    "std::convert::From",
+    "std::convert::Into",
+    "std::convert::AsRef",
+    "std::convert::TryFrom",
+    "std::convert::TryInto",
+    "std::iter::Iterator",
+    "std::iter::IntoIterator",
+    "std::ops::Fn",
+    "std::ops::FnMut",
+    "std::ops::FnOnce",
+    "std::ops::Drop",
+    "std::path::Path",
+    "std::path::PathBuf",
+    "std::time::Duration",
+];
+
+/// `tokens` with every path that starts with a well-known one or an alias shortened. Aliases
+/// map a path (`crate::math::Vec3`) to what it is written as instead (`Vec3`); a path matches
+/// the longest one it starts with, `crate::math::Vec3::ZERO` becoming `Vec3::ZERO`.
+pub fn normalize(tokens: &str, aliases: &BTreeMap<String, String>) -> String {
+    let Ok(stream) = tokens.parse::<TokenStream>() else { return tokens.to_string() };
+    let mut table: Vec<(Vec<String>, TokenStream)> = WELL_KNOWN.iter()
+        .filter_map(|path| {
+            let segments = segments(path);
+            let short = segments.last()?.parse().ok()?;
+            Some((segments, short))
+        })
+        .collect();
+    table.extend(aliases.iter().filter_map(|(path, short)| Some((segments(path), short.parse().ok()?))));
+    // Longest first, so the most specific one wins
+    table.sort_by_key(|(segments, _)| std::cmp::Reverse(segments.len()));
+    rewrite(stream, &table).to_string()
+}
+
+/// The segments of a `::`-separated path, with a leading `::` dropped
+fn segments(path: &str) -> Vec<String> {
+    path.split("::").map(str::trim).filter(|segment| !segment.is_empty()).map(String::from).collect()
+}
+
+/// Whether `segments` start with `prefix`, `core` and `alloc` standing in for `std`
+fn starts_with(segments: &[String], prefix: &[String]) -> bool {
+    segments.len() >= prefix.len() && segments.iter().zip(prefix).enumerate().all(|(index, (segment, expected))| {
+        segment == expected || (index == 0 && expected == "std" && (segment == "core" || segment == "alloc"))
+    })
+}
+
+fn rewrite(stream: TokenStream, table: &[(Vec<String>, TokenStream)]) -> TokenStream {
+    let trees: Vec<TokenTree> = stream.into_iter().collect();
+    let mut out = Vec::with_capacity(trees.len());
+    let mut index = 0;
+    while index < trees.len() {
+        // A path is `::`-separated identifiers, optionally after a leading `::`. One that
+        // continues another (`mycrate::std::vec::Vec`, `<T as Trait>::Output`) isn't rewritten.
+        let continues = |at: usize| at >= 2 && is_path_sep(&trees, at - 2);
+        let leading = is_path_sep(&trees, index) && !ends_path(&trees[..index]);
+        let start = if leading { index + 2 } else { index };
+        if matches!(trees.get(start), Some(TokenTree::Ident(_))) && (leading || !continues(start)) {
+            let mut path = Vec::new();
+            let mut end = start;
+            while let Some(TokenTree::Ident(ident)) = trees.get(end) {
+                path.push(ident.to_string());
+                end += 1;
+                if !is_path_sep(&trees, end) || !matches!(trees.get(end + 2), Some(TokenTree::Ident(_))) {
+                    break;
+                }
+                end += 2;
+            }
+            if let Some((prefix, short)) = table.iter().find(|(prefix, _)| starts_with(&path, prefix)) {
+                out.extend(short.clone());
+                // The rest of the path follows the shortened prefix, separators included
+                out.extend(trees[start..end].iter().skip(prefix.len() * 3 - 2).cloned());
+                index = end;
+                continue;
+            }
+        }
+        out.push(match &trees[index] {
+            TokenTree::Group(group) => {
+                let mut rewritten = proc_macro2::Group::new(group.delimiter(), rewrite(group.stream(), table));
+                rewritten.set_span(group.span());
+                TokenTree::Group(rewritten)
+            }
+            other => other.clone(),
+        });
+        index += 1;
+    }
+    out.into_iter().collect()
+}
+
+/// Whether a `::` after `trees` would continue a path: they end in a name or in the `>` of
+/// generic arguments or a qualified path, rather than of `->`
+fn ends_path(trees: &[TokenTree]) -> bool {
+    match trees {
+        [.., TokenTree::Ident(_)] => true,
+        [.., TokenTree::Punct(arrow), TokenTree::Punct(close)] if close.as_char() == '>' && arrow.as_char() == '-' => {
+            arrow.spacing() != Spacing::Joint
+        }
+        [.., TokenTree::Punct(close)] => close.as_char() == '>',
+        _ => false,
+    }
+}
+
+/// Whether a `::` starts at `trees[at]`
+fn is_path_sep(trees: &[TokenTree], at: usize) -> bool {
+    match (trees.get(at), trees.get(at + 1)) {
+        (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second))) => {
+            first.as_char() == ':' && first.spacing() == Spacing::Joint && second.as_char() == ':'
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(tokens: &str, aliases: &[(&str, &str)]) -> String {
+        let aliases = aliases.iter().map(|(path, short)| (path.to_string(), short.to_string())).collect();
+        normalize(&tokens.parse::<TokenStream>().unwrap().to_string(), &aliases)
+    }
+
+    fn rendered(tokens: &str) -> String {
+        tokens.parse::<TokenStream>().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_normalize_type_paths() {
+        assert_eq!(normalized("pub points: std::vec::Vec<Vec3>", &[]), rendered("pub points: Vec<Vec3>"));
+        assert_eq!(normalized("fn f() -> ::core::option::Option<alloc::boxed::Box<str>>", &[]), rendered("fn f() -> Option<Box<str>>"));
+        assert_eq!(normalized("let v = std::vec::Vec::new();", &[]), rendered("let v = Vec::new();"));
+        assert_eq!(normalized("x: std::collections::HashMap<std::string::String, u8>", &[]), rendered("x: HashMap<String, u8>"));
+        // Not the standard library's, and not well-known
+        assert_eq!(normalized("x: mycrate::std::vec::Vec<u8>", &[]), rendered("x: mycrate::std::vec::Vec<u8>"));
+        assert_eq!(normalized("x: std::io::Result<()>", &[]), rendered("x: std::io::Result<()>"));
+        assert_eq!(normalized("x: <T as Tr>::std::vec::Vec", &[]), rendered("x: <T as Tr>::std::vec::Vec"));
+        assert_eq!(normalized("x: ::std::vec::Vec<u8>", &[]), rendered("x: Vec<u8>"));
+
+        let aliases = [("crate::math::Vec3", "Vec3"), ("Real", "f32")];
+        assert_eq!(normalized("fn at(t: Real) -> crate::math::Vec3", &aliases), rendered("fn at(t: f32) -> Vec3"));
+        assert_eq!(normalized("const O: crate::math::Vec3 = crate::math::Vec3::ZERO;", &aliases), rendered("const O: Vec3 = Vec3::ZERO;"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Custom rules shipped as WASI modules, run without rebuilding spec-check.
+//!
+//! Each module is run once per checked file by a WASI runtime (`wasmtime run` by default).
+//! The guest API is plain TOML over stdio: the module reads one document from stdin
+//!
+//! ```toml
+//! api-version = 1
+//! rust-file = "src/lib.rs"
+//! spec-file = "spec/lib.md"
+//! [[code-items]]   # and [[spec-items]]: name, kind, signature, tokens, attributes, line_number
+//! ```
+//!
+//! and writes its violations to stdout, as zero or more `[[violations]]` tables with a
+//! `message` and optional `line` and `item`.
+
+use crate::config::WasmRuleConfig;
+use crate::plugin::{self, FileContext, Rule, Violation};
+use crate::rust_parser::RustItem;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Bumped only for incompatible changes to the documents exchanged with modules
+pub const API_VERSION: i64 = 1;
+
+const DEFAULT_RUNNER: &[&str] = &["wasmtime", "run"];
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Input<'a> {
+    api_version: i64,
+    rust_file: &'a Path,
+    spec_file: Option<&'a Path>,
+    code_items: &'a [RustItem],
+    spec_items: &'a [RustItem],
+}
+
+#[derive(Deserialize)]
+struct Output {
+    #[serde(default)]
+    violations: Vec<GuestViolation>,
+}
+
+#[derive(Deserialize)]
+struct GuestViolation {
+    message: String,
+    line: Option<usize>,
+    item: Option<String>,
+}
+
+pub struct WasmRule {
+    code: &'static str,
+    name: &'static str,
+    summary: &'static str,
+    module: PathBuf,
+    runner: Vec<String>,
+}
+
+impl WasmRule {
+    pub fn new(config: &WasmRuleConfig) -> Self {
+        // Rule metadata is static so findings can refer to it; rules are loaded once per process
+        let leak = |s: &str| -> &'static str { Box::leak(s.to_string().into_boxed_str()) };
+        Self {
+            code: leak(&config.code),
+            name: leak(config.name.as_deref().unwrap_or(&config.code)),
+            summary: leak(config.summary.as_deref().unwrap_or("Custom rule loaded from a WASM module.")),
+            module: PathBuf::from(&config.module),
+            runner: config.runner.clone().unwrap_or_else(|| DEFAULT_RUNNER.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    fn run_module(&self, file: &FileContext) -> Result<Vec<Violation>> {
+        let input = toml::to_string(&Input {
+            api_version: API_VERSION,
+            rust_file: file.rust_file,
+            spec_file: file.spec_file,
+            code_items: file.code_items,
+            spec_items: file.spec_items,
+        })?;
+
+        let (program, args) = self.runner.split_first().context("wasm rule runner is empty")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .arg(&self.module)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {}", program))?;
+        // Write on another thread, so a module that answers before reading everything can't deadlock us
+        let mut stdin = child.stdin.take().context("no stdin for wasm module")?;
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output()?;
+        writer.join().ok();
+
+        if !output.status.success() {
+            anyhow::bail!("{} exited with {}: {}", self.module.display(), output.status, String::from_utf8_lossy(&output.stderr).trim());
+        }
+        let output: Output = toml::from_str(&String::from_utf8_lossy(&output.stdout))
+            .with_context(|| format!("{} wrote invalid output", self.module.display()))?;
+        Ok(output.violations.into_iter()
+            .map(|v| Violation { line: v.line, item: v.item, ..Violation::new(v.message) })
+            .collect())
+    }
+}
+
+impl Rule for WasmRule {
+    fn code(&self) -> &'static str {
+        self.code
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn summary(&self) -> &'static str {
+        self.summary
+    }
+
+    fn check(&self, file: &FileContext) -> Vec<Violation> {
+        // A broken module is reported rather than silently passing every file
+        self.run_module(file).unwrap_or_else(|err| vec![Violation::new(format!("rule module failed: {:#}", err))])
+    }
+}
+
+/// Register the configured modules that aren't registered yet
+pub fn register(configs: &[WasmRuleConfig]) {
+    for config in configs {
+        if plugin::find(&config.code).is_none() {
+            plugin::register(WasmRule::new(config));
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config::Visibility;
+
+    #[test]
+    fn test_module_violations_are_read_from_stdout() {
+        // Stand in for a WASI runtime with a shell script that checks its input
+        let script = r#"input=$(cat); echo "$input" | grep -q 'api-version = 1' && echo "$input" | grep -q 'name = "Mesh"' && printf '[[violations]]\nmessage = "meshes need a spec"\nline = 3\n'"#;
+        let rule = WasmRule::new(&WasmRuleConfig {
+            code: "WASM001".to_string(),
+            name: None,
+            summary: None,
+            module: "checks.wasm".to_string(),
+            runner: Some(vec!["sh".to_string(), "-c".to_string(), script.to_string(), "sh".to_string()]),
+        });
+
+        let items = crate::rust_parser::parse_rust_file("pub struct Mesh;", Visibility::Pub).unwrap();
+        let file = FileContext { rust_file: Path::new("src/lib.rs"), spec_file: None, code_items: &items, spec_items: &[] };
+        let violations = rule.check(&file);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "meshes need a spec");
+        assert_eq!(violations[0].line, Some(3));
+
+        let failing = WasmRule { runner: vec!["false".to_string()], ..rule };
+        assert!(failing.check(&file)[0].message.starts_with("rule module failed"));
+    }
+}
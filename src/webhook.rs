@@ -0,0 +1,113 @@
+//! Delivery of results to a webhook, through curl like the crates.io downloads
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Give up on a webhook that takes longer than this
+const TIMEOUT_SECS: &str = "30";
+
+/// POST a JSON body to `url` with extra `Name: value` headers
+pub fn post(url: &str, headers: &[String], body: &str) -> Result<()> {
+    let config = HeaderConfig::write(headers)?;
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", TIMEOUT_SECS])
+        .args(["--user-agent", concat!("spec-check/", env!("CARGO_PKG_VERSION"))])
+        .args(["--header", "Content-Type: application/json"])
+        .arg("--config").arg(&config.path)
+        .args(["--data-binary", "@-", "--output", "/dev/null"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+    child.stdin.take().context("no stdin for curl")?.write_all(body.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("POST to {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// A curl config file with the extra headers, readable only by the user and removed when
+/// dropped. Headers often carry tokens, which any user could read from curl's arguments.
+struct HeaderConfig {
+    path: PathBuf,
+}
+
+impl HeaderConfig {
+    fn write(headers: &[String]) -> Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!("spec-check-webhook-{}-{}.curlrc", std::process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let config = Self { path };
+        file.write_all(config_text(headers).as_bytes()).with_context(|| format!("Failed to write {}", config.path.display()))?;
+        Ok(config)
+    }
+}
+
+impl Drop for HeaderConfig {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A `header = "..."` line per header, with `${NAME}`s expanded. Line breaks are dropped, so a
+/// variable can't add headers of its own.
+fn config_text(headers: &[String]) -> String {
+    headers.iter()
+        .map(|header| {
+            let value: String = expand_env(header).chars().filter(|c| !matches!(c, '\r' | '\n')).collect();
+            format!("header = \"{}\"\n", value.replace('\\', "\\\\").replace('"', "\\\""))
+        })
+        .collect()
+}
+
+/// Replace `${NAME}` with the environment variable, so tokens can stay out of Cargo.toml
+pub fn expand_env(text: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else { break };
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&std::env::var(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_env_replaces_variables() {
+        let path = std::env::var("PATH").unwrap_or_default();
+        assert_eq!(expand_env("Authorization: Bearer ${PATH}"), format!("Authorization: Bearer {}", path));
+        assert_eq!(expand_env("X-Token: ${SPEC_CHECK_SURELY_UNSET_VARIABLE}!"), "X-Token: !");
+        assert_eq!(expand_env("unterminated ${NAME"), "unterminated ${NAME");
+    }
+
+    #[test]
+    fn test_headers_go_in_a_private_config_file() {
+        let headers = ["Authorization: Bearer ${SPEC_CHECK_SURELY_UNSET_VARIABLE}abc".to_string(), "X-Note: say \"hi\"\r\nX-Extra: 1".to_string()];
+        assert_eq!(config_text(&headers), "header = \"Authorization: Bearer abc\"\nheader = \"X-Note: say \\\"hi\\\"X-Extra: 1\"\n");
+
+        let config = HeaderConfig::write(&headers).unwrap();
+        assert_eq!(fs::read_to_string(&config.path).unwrap(), config_text(&headers));
+        #[cfg(unix)]
+        assert_eq!(std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&config.path).unwrap().permissions()) & 0o777, 0o600);
+        let path = config.path.clone();
+        drop(config);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,179 @@
+//! Word-level diffs of signatures, like `git diff --word-diff`: the spec's signature is the old
+//! side and the code's the new one, so `[-u32-]{+u16+}` reads as "the code changed u32 to u16"
+
+/// Past this many pairs of elements the sides are shown as wholly replaced rather than diffed
+const MAX_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+/// A run of tokens both sides have, or only one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub change: Change,
+    pub text: String,
+    /// Whether whitespace comes before the run
+    pub spaced: bool,
+}
+
+/// A token and whether whitespace comes before it
+type Word<'a> = (bool, &'a str);
+
+/// The words of `text`: runs without whitespace, with each bracket a word of its own so that
+/// `(x` and `(y` share the `(`
+fn words(text: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut spaced = false;
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        let bracket = "()[]{}".contains(c);
+        if let Some(from) = start.filter(|_| c.is_whitespace() || bracket) {
+            words.push((spaced, &text[from..index]));
+            (spaced, start) = (false, None);
+        }
+        if c.is_whitespace() {
+            spaced = true;
+        } else if bracket {
+            words.push((spaced, &text[index..index + 1]));
+            spaced = false;
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(from) = start {
+        words.push((spaced, &text[from..]));
+    }
+    words
+}
+
+fn push(segments: &mut Vec<Segment>, change: Change, (spaced, word): Word) {
+    match segments.last_mut() {
+        Some(last) if last.change == change => {
+            if spaced {
+                last.text.push(' ');
+            }
+            last.text.push_str(word);
+        }
+        _ => segments.push(Segment { change, text: word.to_string(), spaced }),
+    }
+}
+
+/// The segments turning `old` into `new`, word by word, with each run of one kind merged into
+/// one segment
+pub fn diff(old: &str, new: &str) -> Vec<Segment> {
+    let (old, new) = (words(old), words(new));
+    // Spacing is shown, not compared
+    let (old_texts, new_texts): (Vec<&str>, Vec<&str>) = (old.iter().map(|word| word.1).collect(), new.iter().map(|word| word.1).collect());
+    let mut segments = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for change in lcs(&old_texts, &new_texts) {
+        match change {
+            // The code's spacing, as that's the side shown
+            Change::Same => {
+                push(&mut segments, Change::Same, new[j]);
+                (i, j) = (i + 1, j + 1);
+            }
+            Change::Removed => {
+                push(&mut segments, Change::Removed, old[i]);
+                i += 1;
+            }
+            Change::Added => {
+                push(&mut segments, Change::Added, new[j]);
+                j += 1;
+            }
+        }
+    }
+    segments
+}
+
+/// The steps turning `old` into `new` while keeping a longest common subsequence of the two:
+/// `Same` takes the next element of both, `Removed` the next of `old` and `Added` the next of
+/// `new`. Past `MAX_CELLS` pairs, all of `old` is removed and all of `new` added instead.
+pub fn lcs<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Change> {
+    if old.len().saturating_mul(new.len()) > MAX_CELLS {
+        return std::iter::repeat_n(Change::Removed, old.len()).chain(std::iter::repeat_n(Change::Added, new.len())).collect();
+    }
+
+    // Length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+        }
+    }
+    let mut changes = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push(Change::Same);
+            (i, j) = (i + 1, j + 1);
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(Change::Removed);
+            i += 1;
+        } else {
+            changes.push(Change::Added);
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// The diff as plain text, with removed runs in `[-...-]` and added ones in `{+...+}`
+pub fn render(segments: &[Segment]) -> String {
+    render_with(segments, ("[-", "-]"), ("{+", "+}"))
+}
+
+/// The diff with each removed and added run wrapped in the given open and close markers. A
+/// replacement is written without a space between its removed and added runs.
+pub fn render_with(segments: &[Segment], removed: (&str, &str), added: (&str, &str)) -> String {
+    let mut out = String::new();
+    for (index, segment) in segments.iter().enumerate() {
+        let replaces = index > 0 && segments[index - 1].change == Change::Removed && segment.change == Change::Added;
+        if index > 0 && segment.spaced && !replaces {
+            out.push(' ');
+        }
+        let (open, close) = match segment.change {
+            Change::Same => ("", ""),
+            Change::Removed => removed,
+            Change::Added => added,
+        };
+        out.push_str(open);
+        out.push_str(&segment.text);
+        out.push_str(close);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lcs_keeps_the_longest_common_run() {
+        use Change::*;
+        assert_eq!(lcs(&[1, 2, 3, 4], &[1, 3, 4, 5]), [Same, Removed, Same, Same, Added]);
+        assert_eq!(lcs::<u8>(&[], &[7]), [Added]);
+        assert!(lcs::<u8>(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let spec = "pub fn lerp (a : f32 , b : f32 , t : f32) -> f32";
+        let code = "pub fn lerp (a : f64 , b : f64 , t : f32) -> f64";
+        assert_eq!(render(&diff(spec, code)), "pub fn lerp (a : [-f32-]{+f64+} , b : [-f32-]{+f64+} , t : f32) -> [-f32-]{+f64+}");
+        let changes: Vec<(Change, String)> = diff("pub struct S ;", "pub struct S { pub x : u8 }").into_iter()
+            .map(|segment| (segment.change, segment.text))
+            .collect();
+        assert_eq!(changes, [
+            (Change::Same, "pub struct S".to_string()),
+            (Change::Removed, ";".to_string()),
+            (Change::Added, "{ pub x : u8 }".to_string()),
+        ]);
+        assert_eq!(render(&diff("pub fn f (x : u16)", "pub fn f (x : u8)")), "pub fn f (x : [-u16-]{+u8+})");
+        assert_eq!(render(&diff("a b", "a b")), "a b");
+    }
+}
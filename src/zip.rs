@@ -0,0 +1,112 @@
+//! Minimal zip archives: entries are stored uncompressed with a fixed timestamp,
+//! so the same inputs always produce the same bytes
+
+/// 1980-01-01 00:00, the earliest date a zip entry can hold
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Entries whose names and contents are collected in memory, then written at once
+#[derive(Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    pub fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.data.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+        let name = name.replace('\\', "/");
+
+        let mut header = Vec::new();
+        push_u32(&mut header, 0x0403_4b50);
+        push_common(&mut header, crc, size, name.len());
+        self.data.extend(header);
+        self.data.extend(name.as_bytes());
+        self.data.extend(contents);
+
+        push_u32(&mut self.central, 0x0201_4b50);
+        push_u16(&mut self.central, 20); // made by: version 2.0
+        push_common(&mut self.central, crc, size, name.len());
+        push_u16(&mut self.central, 0); // comment length
+        push_u16(&mut self.central, 0); // disk number
+        push_u16(&mut self.central, 0); // internal attributes
+        push_u32(&mut self.central, 0o100644 << 16); // external attributes: a regular, readable file
+        push_u32(&mut self.central, offset);
+        self.central.extend(name.as_bytes());
+        self.entries += 1;
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.data.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.data.extend(self.central);
+        push_u32(&mut self.data, 0x0605_4b50);
+        push_u16(&mut self.data, 0); // this disk
+        push_u16(&mut self.data, 0); // disk with the central directory
+        push_u16(&mut self.data, self.entries);
+        push_u16(&mut self.data, self.entries);
+        push_u32(&mut self.data, central_size);
+        push_u32(&mut self.data, central_offset);
+        push_u16(&mut self.data, 0); // comment length
+        self.data
+    }
+}
+
+/// Fields shared by local and central headers, from "version needed" to "extra field length"
+fn push_common(out: &mut Vec<u8>, crc: u32, size: u32, name_len: usize) {
+    push_u16(out, 20); // version needed
+    push_u16(out, 1 << 11); // flags: names are UTF-8
+    push_u16(out, 0); // method: stored
+    push_u16(out, DOS_TIME);
+    push_u16(out, DOS_DATE);
+    push_u32(out, crc);
+    push_u32(out, size); // compressed
+    push_u32(out, size); // uncompressed
+    push_u16(out, name_len as u16);
+    push_u16(out, 0); // extra field length
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend(value.to_le_bytes());
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend(value.to_le_bytes());
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_layout() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        let mut zip = ZipWriter::default();
+        zip.add("a.txt", b"hello");
+        zip.add("dir\\b.txt", b"");
+        let bytes = zip.finish();
+
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+        assert_eq!(&bytes[30..35], b"a.txt");
+        assert_eq!(&bytes[35..40], b"hello");
+        let eocd = &bytes[bytes.len() - 22..];
+        assert_eq!(&eocd[..4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([eocd[10], eocd[11]]), 2);
+        assert!(bytes.windows(9).any(|w| w == b"dir/b.txt"));
+    }
+}
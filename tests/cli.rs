@@ -0,0 +1,249 @@
+//! The `spec-check` binary run on small projects in temporary directories
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A package with a source and a spec tree, removed when dropped
+struct Project {
+    dir: PathBuf,
+}
+
+impl Project {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("spec-check-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let project = Self { dir };
+        project.write("Cargo.toml", &format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name));
+        for (path, content) in files {
+            project.write(path, content);
+        }
+        project
+    }
+
+    fn write(&self, path: &str, content: &str) {
+        let path = self.dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.dir.join(path)).unwrap()
+    }
+
+    /// `spec-check` with `args`, uncached and logging to the project's `spec-check.log`
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_spec-check"))
+            .args(args)
+            .args(["--no-cache", "--log", "spec-check.log"])
+            .current_dir(&self.dir)
+            .output()
+            .unwrap()
+    }
+
+    /// `spec-check` with `args` exactly, given `input` on stdin
+    fn run_with_stdin(&self, args: &[&str], input: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_spec-check"))
+            .args(args)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+const LERP: &str = "pub fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }\n";
+
+#[test]
+fn fix_dry_run_leaves_the_spec_alone() {
+    let spec = "# Lerp\n\n```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\n```\n";
+    let project = Project::new("fix_dry_run", &[("src/lib.rs", LERP), ("spec/lib.md", spec)]);
+    let output = project.run(&["--fix", "--dry-run"]);
+    assert_eq!(project.read("spec/lib.md"), spec);
+    let printed = stdout(&output);
+    assert!(printed.contains("--- a/spec/lib.md\n+++ b/spec/lib.md\n"), "{}", printed);
+    assert!(printed.contains("-pub fn lerp(a: f32, b: f32) -> f32 {}\n+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {}\n"), "{}", printed);
+    assert!(printed.contains("Would update spec/lib.md: fn lerp"), "{}", printed);
+    // The check that follows still sees the old spec
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn init_dry_run_writes_no_spec() {
+    let project = Project::new("init_dry_run", &[("src/lib.rs", LERP)]);
+    let output = project.run(&["init", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(!project.dir.join("spec").exists());
+    let printed = stdout(&output);
+    assert!(printed.contains("--- /dev/null\n+++ b/spec/lib.md\n"), "{}", printed);
+    assert!(printed.contains("+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {}\n"), "{}", printed);
+}
+
+#[test]
+fn scaffold_dry_run_leaves_the_code_alone() {
+    let source = "pub fn ease(t: f32) -> f32 { t }\n";
+    let spec = "```rust\npub fn ease(t: f32) -> f32 {}\npub fn lerp(a: f32, b: f32, t: f32) -> f32 {}\n```\n";
+    let project = Project::new("scaffold_dry_run", &[("src/lib.rs", source), ("spec/lib.md", spec)]);
+    let output = project.run(&["scaffold", "--dry-run"]);
+    assert!(output.status.success());
+    assert_eq!(project.read("src/lib.rs"), source);
+    let printed = stdout(&output);
+    assert!(printed.contains("+++ b/src/lib.rs\n"), "{}", printed);
+    assert!(printed.contains("+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {\n+    todo!()\n+}\n"), "{}", printed);
+}
+
+#[test]
+fn parse_failures_count_towards_max_errors() {
+    let project = Project::new("parse_failure_budget", &[
+        ("src/lib.rs", "pub mod a;\npub mod b;\n"),
+        ("src/a.rs", "pub fn broken( {}\n"),
+        ("src/b.rs", "pub fn broken( {}\n"),
+        ("spec/a.md", "```rust\npub fn broken() {}\n```\n"),
+        ("spec/b.md", "```rust\npub fn broken() {}\n```\n"),
+    ]);
+    let output = project.run(&["--max-errors", "1"]);
+    let printed = stdout(&output);
+    assert!(printed.contains("1 findings reported (--max-errors 1)"), "{}", printed);
+    assert!(!printed.contains("src/b.rs"), "{}", printed);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+const LERP_SPEC: &str = "# Lerp\n\n```rust\npub fn lerp(a: f32, b: f32, t: f32) -> f32 {}\n```\n";
+
+#[test]
+fn exit_code_is_0_when_the_code_matches() {
+    let project = Project::new("exit_clean", &[("src/lib.rs", LERP), ("spec/lib.md", LERP_SPEC)]);
+    let output = project.run(&[]);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout(&output));
+}
+
+#[test]
+fn exit_code_is_1_for_violations() {
+    let project = Project::new("exit_violations", &[("src/lib.rs", LERP), ("spec/lib.md", "```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\n```\n")]);
+    assert_eq!(project.run(&[]).status.code(), Some(1));
+}
+
+#[test]
+fn exit_code_is_2_for_usage_and_config_errors() {
+    let project = Project::new("exit_usage", &[("src/lib.rs", LERP), ("spec/lib.md", LERP_SPEC)]);
+    assert_eq!(project.run(&["--src", "missing"]).status.code(), Some(2));
+    assert_eq!(project.run(&["--fail-on", "sometimes"]).status.code(), Some(2));
+    project.write("Cargo.toml", "[package]\nname = \"exit_usage\"\nversion = \"0.1.0\"\n\n[package.metadata.spec-check]\nseverity = { missing-in-spec = \"sometimes\" }\n");
+    assert_eq!(project.run(&[]).status.code(), Some(2));
+}
+
+#[test]
+fn exit_code_is_3_for_parse_failures_whatever_else_fails() {
+    let project = Project::new("exit_parse_failure", &[
+        ("src/lib.rs", "pub mod a;\npub mod b;\n"),
+        ("src/a.rs", "pub fn broken( {}\n"),
+        ("src/b.rs", LERP),
+        ("spec/a.md", "```rust\npub fn broken() {}\n```\n"),
+        ("spec/b.md", "```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\n```\n"),
+    ]);
+    assert_eq!(project.run(&[]).status.code(), Some(3));
+}
+
+#[test]
+fn exit_code_is_4_when_only_specs_are_missing() {
+    let project = Project::new("exit_missing_spec", &[("src/lib.rs", LERP), ("spec/.keep", "")]);
+    let output = project.run(&[]);
+    assert_eq!(output.status.code(), Some(4), "{}", stdout(&output));
+}
+
+#[test]
+fn fail_on_decides_which_severities_fail_the_run() {
+    let project = Project::new("exit_fail_on", &[("src/lib.rs", LERP), ("spec/lib.md", "# Lerp\n")]);
+    project.write("Cargo.toml", "[package]\nname = \"exit_fail_on\"\nversion = \"0.1.0\"\n\n[package.metadata.spec-check]\nseverity = { missing-in-spec = \"warning\" }\n");
+    assert_eq!(project.run(&[]).status.code(), Some(0));
+    assert_eq!(project.run(&["--fail-on", "warning"]).status.code(), Some(1));
+    assert_eq!(project.run(&["--fail-on", "error"]).status.code(), Some(0));
+}
+
+#[test]
+fn error_budget_tolerates_that_many_violations() {
+    let source = "pub fn ease(t: f32) -> f32 { t }\npub fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }\n";
+    let project = Project::new("exit_error_budget", &[("src/lib.rs", source), ("spec/lib.md", "# Lerp\n")]);
+    assert_eq!(project.run(&["--error-budget", "2"]).status.code(), Some(0));
+    let output = project.run(&["--error-budget", "1"]);
+    assert_eq!(output.status.code(), Some(1));
+    // Every finding is still reported
+    let printed = stdout(&output);
+    assert!(printed.contains("ease") && printed.contains("lerp"), "{}", printed);
+}
+
+#[test]
+fn file_checks_a_source_read_from_stdin() {
+    let project = Project::new("file_stdin", &[("spec/lib.md", "```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\n```\n")]);
+    let output = project.run_with_stdin(&["file", "--rust", "-", "--spec", "spec/lib.md", "--json"], LERP);
+    assert_eq!(output.status.code(), Some(1));
+    let printed = stdout(&output);
+    assert!(printed.contains("\"exit_code\": 1"), "{}", printed);
+    assert!(printed.contains("\"file\": \"<stdin>\""), "{}", printed);
+    assert!(printed.contains("\"spec_file\": \"spec/lib.md\""), "{}", printed);
+
+    let output = project.run_with_stdin(&["file", "--rust", "-", "--spec", "-"], LERP);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Only one of --rust and --spec can be read from stdin"));
+}
+
+#[test]
+fn since_checks_only_the_files_changed_in_git() {
+    let stale = "```rust\npub fn ease(t: f32) -> f32 {}\n```\n";
+    let project = Project::new("since_changed", &[
+        ("src/lib.rs", "pub mod a;\npub mod b;\n"),
+        ("src/a.rs", "pub fn ease(t: f64) -> f64 { t }\n"),
+        ("src/b.rs", LERP),
+        ("spec/a.md", stale),
+        ("spec/b.md", "```rust\npub fn lerp(a: f32, b: f32) -> f32 {}\n```\n"),
+        ("spec/lib.md", "```rust\npub mod a;\npub mod b;\n```\n"),
+        (".gitignore", "spec-check.log\n"),
+    ]);
+    let git = |args: &[&str]| {
+        let status = Command::new("git").args(["-c", "user.name=Test", "-c", "user.email=test@example.com"]).args(args).current_dir(&project.dir).status().unwrap();
+        assert!(status.success(), "git {:?}", args);
+    };
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "Add curves"]);
+    assert_eq!(project.run(&[]).status.code(), Some(1));
+
+    // Fixing b.rs's spec leaves only a.rs failing, which --since and --changed skip
+    project.write("spec/b.md", LERP_SPEC);
+    assert_eq!(project.run(&[]).status.code(), Some(1));
+    assert_eq!(project.run(&["--since", "HEAD"]).status.code(), Some(0));
+    assert_eq!(project.run(&["--changed"]).status.code(), Some(0));
+    assert!(!project.read("spec-check.log").contains("a.rs"));
+    assert_eq!(project.run(&["--changed", "--scope", "project"]).status.code(), Some(2));
+}
+
+#[test]
+fn error_budget_counts_only_failing_findings() {
+    let source = "pub mod a;\npub fn ease(t: f32) -> f32 { t }\npub fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }\n";
+    let project = Project::new("error_budget_severity", &[
+        ("src/lib.rs", source),
+        ("src/a.rs", "pub fn clamp(t: f32) -> f32 { t }\n"),
+        ("spec/lib.md", "```rust\npub mod a;\npub fn ease(t: f64) -> f64 {}\n```\n"),
+    ]);
+    project.write("Cargo.toml", "[package]\nname = \"error_budget_severity\"\nversion = \"0.1.0\"\n\n[package.metadata.spec-check]\nseverity = { missing-in-spec = \"warning\" }\n");
+    // The missing lerp is a warning, so only ease's mismatch spends the budget, and a.rs
+    // without a spec keeps its own code
+    assert_eq!(project.run(&["--error-budget", "1"]).status.code(), Some(4));
+    assert_eq!(project.run(&["--error-budget", "0"]).status.code(), Some(1));
+    assert_eq!(project.run(&["--error-budget", "1", "--fail-on", "warning"]).status.code(), Some(1));
+}